@@ -13,8 +13,18 @@ pub struct APIConfig {
     pub bind_private: SocketAddr,
     /// bind for the public API
     pub bind_public: SocketAddr,
+    /// whether the WebSocket subscription API is enabled
+    pub enable_ws: bool,
+    /// bind for the WebSocket subscription API
+    pub bind_ws: SocketAddr,
+    /// max number of simultaneous subscriptions of a connection to the WebSocket API
+    pub max_subscriptions_per_session: usize,
     /// max argument count
     pub max_arguments: u64,
+    /// max number of items in a page of a list-returning endpoint
+    pub max_page_size: usize,
+    /// max number of events in a page of `get_filtered_sc_output_event`
+    pub max_events_page_size: usize,
     /// max datastore value length
     pub max_datastore_value_length: u64,
     /// max op datastore entry
@@ -27,4 +37,6 @@ pub struct APIConfig {
     pub max_function_name_length: u16,
     /// max parameter size
     pub max_parameter_size: u32,
+    /// max gas per block, which is the max gas available to gas estimations
+    pub max_gas_per_block: u64,
 }
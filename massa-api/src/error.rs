@@ -46,6 +46,10 @@ pub enum ApiError {
     MissingConfig(String),
     /// the wrong API (either Public or Private) was called
     WrongAPI,
+    /// too many subscriptions on this connection: max {0}
+    TooManySubscriptions(usize),
+    /// unknown subscription: {0}
+    UnknownSubscription(String),
 }
 
 impl From<ApiError> for jsonrpc_core::Error {
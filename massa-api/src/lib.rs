@@ -5,27 +5,37 @@
 #![warn(unused_crate_dependencies)]
 use crate::error::ApiError::WrongAPI;
 use error::ApiError;
-use jsonrpc_core::{BoxFuture, IoHandler, Value};
+use jsonrpc_core::futures::channel::mpsc::UnboundedSender;
+use jsonrpc_core::{BoxFuture, IoHandler, MetaIoHandler, Value};
 use jsonrpc_derive::rpc;
 use jsonrpc_http_server::{CloseHandle, ServerBuilder};
-use massa_consensus_exports::{ConsensusCommandSender, ConsensusConfig};
+use jsonrpc_pubsub::{typed, PubSubHandler, SubscriptionId};
+use jsonrpc_ws_server::RequestContext;
+use massa_consensus_exports::{BlockStoreController, ConsensusCommandSender, ConsensusConfig};
 use massa_execution_exports::ExecutionController;
 use massa_models::api::{
     AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, OperationInput,
+    EndorsementInfo, EventFilter, GasEstimationInput, NodeStatus, OperationInfo, OperationInput,
+    PageRequest, PagedResponse, PoolOperationFilter, PoolOperationStatus,
     ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
-use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::execution::{
+    ExecuteReadOnlyResponse, ExecutionTrace, GasEstimation, OperationSimulation,
+};
+use massa_models::ip_range::{IpAccessLists, IpRange};
+use massa_models::ledger_models::LedgerSubEntry;
+use massa_models::ledger_proof::LedgerEntryProof;
 use massa_models::node::NodeId;
 use massa_models::operation::OperationId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
 use massa_models::{
     address::Address,
-    block::{Block, BlockId},
+    block::{Block, BlockId, WrappedBlock},
     endorsement::EndorsementId,
+    operation::WrappedOperation,
     slot::Slot,
     version::Version,
 };
@@ -41,14 +51,19 @@ use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::thread;
 use std::thread::JoinHandle;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tracing::{info, warn};
 
 mod config;
 mod error;
 mod private;
 mod public;
+mod pubsub;
+#[cfg(test)]
+mod tests;
 pub use config::APIConfig;
+pub use pubsub::SubscriptionSession;
+use pubsub::Subscriptions;
 
 /// Public API component
 pub struct Public {
@@ -78,6 +93,8 @@ pub struct Public {
     pub compensation_millis: i64,
     /// our node id
     pub node_id: NodeId,
+    /// optional disk store of final blocks, used for blocks pruned from storage
+    pub final_block_store: Option<Box<dyn BlockStoreController>>,
 }
 
 /// Private API content
@@ -96,6 +113,26 @@ pub struct Private {
     pub stop_node_channel: mpsc::Sender<()>,
     /// User wallet
     pub node_wallet: Arc<RwLock<Wallet>>,
+    /// IP ranges allowed and denied to bootstrap from us, if the bootstrap server is running
+    pub bootstrap_access_lists: Option<Arc<RwLock<IpAccessLists>>>,
+}
+
+/// Subscription (WebSocket) API content
+pub struct PubSub {
+    /// broadcast channel of blocks newly integrated in the graph
+    pub block_sender: broadcast::Sender<WrappedBlock>,
+    /// broadcast channel of newly finalized blocks
+    pub finalized_block_sender: broadcast::Sender<WrappedBlock>,
+    /// broadcast channel of operations newly added to the pool
+    pub operation_sender: broadcast::Sender<WrappedOperation>,
+    /// broadcast channel of smart contract output events
+    pub sc_output_event_sender: broadcast::Sender<SCOutputEvent>,
+    /// API settings
+    pub api_settings: APIConfig,
+    /// forwarding tasks of the active subscriptions, by session
+    subscriptions: Arc<Subscriptions>,
+    /// runtime on which the forwarding tasks are spawned
+    runtime: tokio::runtime::Handle,
 }
 
 /// The API wrapper
@@ -124,21 +161,65 @@ fn serve(api: impl Endpoints, url: &SocketAddr) -> StopHandle {
         .expect("failed to spawn thread : rpc-server");
 
     StopHandle {
-        close_handle,
+        close_handle: ServerCloseHandle::Http(close_handle),
         join_handle,
     }
 }
 
+fn serve_pubsub(
+    api: impl PubSubEndpoints<Metadata = SubscriptionSession>,
+    url: &SocketAddr,
+    open_session: impl Fn(UnboundedSender<String>) -> SubscriptionSession + Send + Sync + 'static,
+) -> StopHandle {
+    let mut io = PubSubHandler::new(MetaIoHandler::default());
+    io.extend_with(api.to_delegate());
+
+    // the session metadata is extracted once per connection
+    let server = jsonrpc_ws_server::ServerBuilder::with_meta_extractor(
+        io,
+        move |context: &RequestContext| open_session(context.sender()),
+    )
+    .event_loop_executor(tokio::runtime::Handle::current())
+    .start(url)
+    .expect("Unable to start WebSocket server");
+
+    let close_handle = server.close_handle();
+    let thread_builder = thread::Builder::new().name("ws-server".into());
+    let join_handle = thread_builder
+        .spawn(|| {
+            if let Err(err) = server.wait() {
+                warn!("WebSocket server error: {}", err);
+            }
+        })
+        .expect("failed to spawn thread : ws-server");
+
+    StopHandle {
+        close_handle: ServerCloseHandle::Ws(close_handle),
+        join_handle,
+    }
+}
+
+/// Close handle of the underlying server
+enum ServerCloseHandle {
+    /// HTTP server (public and private APIs)
+    Http(CloseHandle),
+    /// WebSocket server (subscription API)
+    Ws(jsonrpc_ws_server::CloseHandle),
+}
+
 /// Used to be able to stop the API
 pub struct StopHandle {
-    close_handle: CloseHandle,
+    close_handle: ServerCloseHandle,
     join_handle: JoinHandle<()>,
 }
 
 impl StopHandle {
     /// stop the API gracefully
     pub fn stop(self) {
-        self.close_handle.close();
+        match self.close_handle {
+            ServerCloseHandle::Http(close_handle) => close_handle.close(),
+            ServerCloseHandle::Ws(close_handle) => close_handle.close(),
+        }
         if let Err(err) = self.join_handle.join() {
             warn!("API thread panicked: {:?}", err);
         } else {
@@ -178,6 +259,15 @@ pub trait Endpoints {
         _: Vec<ReadOnlyCall>,
     ) -> BoxFuture<Result<Vec<ExecuteReadOnlyResponse>, ApiError>>;
 
+    /// Estimate the gas spent by unsigned `ExecuteSC` or `CallSC` operations,
+    /// by executing them in read-only mode on behalf of their sender.
+    /// Returns the gas cost along with the events and ledger changes the operations would cause.
+    #[rpc(name = "estimate_gas")]
+    fn estimate_gas(
+        &self,
+        _: Vec<GasEstimationInput>,
+    ) -> BoxFuture<Result<Vec<GasEstimation>, ApiError>>;
+
     /// Remove a vector of addresses used to stake.
     /// No confirmation to expect.
     #[rpc(name = "remove_staking_addresses")]
@@ -187,6 +277,14 @@ pub trait Endpoints {
     #[rpc(name = "get_staking_addresses")]
     fn get_staking_addresses(&self) -> BoxFuture<Result<PreHashSet<Address>, ApiError>>;
 
+    /// Get the call tree recorded during the latest execution of each operation,
+    /// or `null` if it is unknown. Execution tracing needs to be enabled in the node settings.
+    #[rpc(name = "get_execution_traces")]
+    fn get_execution_traces(
+        &self,
+        _: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<Option<ExecutionTrace>>, ApiError>>;
+
     /// Bans given IP address(es).
     /// No confirmation to expect.
     #[rpc(name = "node_ban_by_ip")]
@@ -209,6 +307,33 @@ pub trait Endpoints {
     #[rpc(name = "node_remove_from_whitelist")]
     fn node_remove_from_whitelist(&self, _: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>>;
 
+    /// Get the IP ranges allowed and denied to bootstrap from the node.
+    #[rpc(name = "get_bootstrap_access_lists")]
+    fn get_bootstrap_access_lists(&self) -> BoxFuture<Result<IpAccessLists, ApiError>>;
+
+    /// Allow given IP ranges to bootstrap from the node.
+    /// If the allow list is not empty, only the IPs in its ranges can bootstrap from the node.
+    #[rpc(name = "node_add_to_bootstrap_allow_list")]
+    fn node_add_to_bootstrap_allow_list(&self, _: Vec<IpRange>) -> BoxFuture<Result<(), ApiError>>;
+
+    /// Remove given IP ranges from the bootstrap allow list.
+    #[rpc(name = "node_remove_from_bootstrap_allow_list")]
+    fn node_remove_from_bootstrap_allow_list(
+        &self,
+        _: Vec<IpRange>,
+    ) -> BoxFuture<Result<(), ApiError>>;
+
+    /// Deny given IP ranges to bootstrap from the node, even if they are in the allow list.
+    #[rpc(name = "node_add_to_bootstrap_deny_list")]
+    fn node_add_to_bootstrap_deny_list(&self, _: Vec<IpRange>) -> BoxFuture<Result<(), ApiError>>;
+
+    /// Remove given IP ranges from the bootstrap deny list.
+    #[rpc(name = "node_remove_from_bootstrap_deny_list")]
+    fn node_remove_from_bootstrap_deny_list(
+        &self,
+        _: Vec<IpRange>,
+    ) -> BoxFuture<Result<(), ApiError>>;
+
     /// Unban given IP address(es).
     /// No confirmation to expect.
     #[rpc(name = "node_unban_by_ip")]
@@ -232,18 +357,22 @@ pub trait Endpoints {
     fn get_stakers(&self) -> BoxFuture<Result<Vec<(Address, u64)>, ApiError>>;
 
     /// Returns operations information associated to a given list of operations' IDs.
+    /// Optionally paged by offset over the operations found.
     #[rpc(name = "get_operations")]
     fn get_operations(
         &self,
         _: Vec<OperationId>,
-    ) -> BoxFuture<Result<Vec<OperationInfo>, ApiError>>;
+        _: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<OperationInfo>, ApiError>>;
 
-    /// Get endorsements (not yet implemented).
+    /// Get endorsements.
+    /// Optionally paged by offset over the endorsements found.
     #[rpc(name = "get_endorsements")]
     fn get_endorsements(
         &self,
         _: Vec<EndorsementId>,
-    ) -> BoxFuture<Result<Vec<EndorsementInfo>, ApiError>>;
+        _: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<EndorsementInfo>, ApiError>>;
 
     /// Get information on a block given its hash.
     #[rpc(name = "get_block")]
@@ -261,15 +390,36 @@ pub trait Endpoints {
         -> BoxFuture<Result<Vec<BlockSummary>, ApiError>>;
 
     /// Get multiple datastore entries.
+    /// Optionally paged by offset over the requested entries.
+    /// If a past final slot is given, both final and candidate values are the ones at the output of that slot.
     #[rpc(name = "get_datastore_entries")]
     fn get_datastore_entries(
         &self,
         _: Vec<DatastoreEntryInput>,
-    ) -> BoxFuture<Result<Vec<DatastoreEntryOutput>, ApiError>>;
+        _: Option<PageRequest>,
+        _: Option<Slot>,
+    ) -> BoxFuture<Result<PagedResponse<DatastoreEntryOutput>, ApiError>>;
 
     /// Get addresses.
+    /// Optionally paged by offset over the requested addresses.
+    /// If a past final slot is given, both final and candidate balances, roll counts and datastore keys
+    /// are the ones at the output of that slot.
     #[rpc(name = "get_addresses")]
-    fn get_addresses(&self, _: Vec<Address>) -> BoxFuture<Result<Vec<AddressInfo>, ApiError>>;
+    fn get_addresses(
+        &self,
+        _: Vec<Address>,
+        _: Option<PageRequest>,
+        _: Option<Slot>,
+    ) -> BoxFuture<Result<PagedResponse<AddressInfo>, ApiError>>;
+
+    /// Get the final value of a ledger sub-entry of an address,
+    /// along with its inclusion or exclusion proof against the final ledger hash.
+    #[rpc(name = "get_ledger_proof")]
+    fn get_ledger_proof(
+        &self,
+        _: Address,
+        _: LedgerSubEntry,
+    ) -> BoxFuture<Result<LedgerEntryProof, ApiError>>;
 
     /// Adds operations to pool. Returns operations that were ok and sent to pool.
     #[rpc(name = "send_operations")]
@@ -278,17 +428,147 @@ pub trait Endpoints {
         _: Vec<OperationInput>,
     ) -> BoxFuture<Result<Vec<OperationId>, ApiError>>;
 
+    /// Simulates operations, in order, on top of the candidate state,
+    /// without broadcasting them nor adding them to the pool.
+    /// Returns the outcome, fees, balance changes and events of each operation.
+    #[rpc(name = "simulate_operations")]
+    fn simulate_operations(
+        &self,
+        _: Vec<OperationInput>,
+    ) -> BoxFuture<Result<Vec<OperationSimulation>, ApiError>>;
+
     /// Get events optionally filtered by:
     /// * start slot
     /// * end slot
     /// * emitter address
     /// * original caller address
     /// * operation id
+    ///
+    /// Events are returned in slot order, final events first.
+    /// Optionally paged by cursor: the offset of the page request is ignored.
     #[rpc(name = "get_filtered_sc_output_event")]
     fn get_filtered_sc_output_event(
         &self,
         _: EventFilter,
-    ) -> BoxFuture<Result<Vec<SCOutputEvent>, ApiError>>;
+        _: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<SCOutputEvent>, ApiError>>;
+
+    /// Returns the status of operations in the pool: their rank by fee quality in their thread,
+    /// their validity periods, and why the pool rejected or evicted them if they are not in it.
+    #[rpc(name = "get_pool_operation_status")]
+    fn get_pool_operation_status(
+        &self,
+        _: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<PoolOperationStatus>, ApiError>>;
+
+    /// Lists the operations of the pool, optionally filtered by creator address and thread,
+    /// by thread and by decreasing fee quality.
+    /// Optionally paged by cursor: the next page starts after the last operation of the previous one.
+    #[rpc(name = "list_pool_operations")]
+    fn list_pool_operations(
+        &self,
+        _: PoolOperationFilter,
+        _: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<OperationId>, ApiError>>;
+}
+
+/// Exposed subscription endpoints, served over WebSocket
+#[rpc(server)]
+pub trait PubSubEndpoints {
+    /// Subscription session metadata
+    type Metadata;
+
+    /// Subscribe to blocks as they are integrated in the block graph.
+    #[pubsub(subscription = "new_blocks", subscribe, name = "subscribe_new_blocks")]
+    fn subscribe_new_blocks(&self, _: Self::Metadata, _: typed::Subscriber<WrappedBlock>);
+
+    /// Cancel a `subscribe_new_blocks` subscription.
+    #[pubsub(
+        subscription = "new_blocks",
+        unsubscribe,
+        name = "unsubscribe_new_blocks"
+    )]
+    fn unsubscribe_new_blocks(
+        &self,
+        _: Option<Self::Metadata>,
+        _: SubscriptionId,
+    ) -> jsonrpc_core::Result<bool>;
+
+    /// Subscribe to blocks as they become final.
+    #[pubsub(
+        subscription = "finalized_blocks",
+        subscribe,
+        name = "subscribe_finalized_blocks"
+    )]
+    fn subscribe_finalized_blocks(&self, _: Self::Metadata, _: typed::Subscriber<WrappedBlock>);
+
+    /// Cancel a `subscribe_finalized_blocks` subscription.
+    #[pubsub(
+        subscription = "finalized_blocks",
+        unsubscribe,
+        name = "unsubscribe_finalized_blocks"
+    )]
+    fn unsubscribe_finalized_blocks(
+        &self,
+        _: Option<Self::Metadata>,
+        _: SubscriptionId,
+    ) -> jsonrpc_core::Result<bool>;
+
+    /// Subscribe to smart contract output events matching the given filter.
+    /// Events are sent both when they are first produced (`is_final` false)
+    /// and when the slot that produced them becomes final (`is_final` true).
+    #[pubsub(
+        subscription = "filtered_sc_output_event",
+        subscribe,
+        name = "subscribe_filtered_sc_output_event"
+    )]
+    fn subscribe_filtered_sc_output_event(
+        &self,
+        _: Self::Metadata,
+        _: typed::Subscriber<SCOutputEvent>,
+        _: EventFilter,
+    );
+
+    /// Cancel a `subscribe_filtered_sc_output_event` subscription.
+    #[pubsub(
+        subscription = "filtered_sc_output_event",
+        unsubscribe,
+        name = "unsubscribe_filtered_sc_output_event"
+    )]
+    fn unsubscribe_filtered_sc_output_event(
+        &self,
+        _: Option<Self::Metadata>,
+        _: SubscriptionId,
+    ) -> jsonrpc_core::Result<bool>;
+
+    /// Subscribe to operations as they enter the operation pool.
+    #[pubsub(
+        subscription = "new_operations",
+        subscribe,
+        name = "subscribe_new_operations"
+    )]
+    fn subscribe_new_operations(&self, _: Self::Metadata, _: typed::Subscriber<WrappedOperation>);
+
+    /// Cancel a `subscribe_new_operations` subscription.
+    #[pubsub(
+        subscription = "new_operations",
+        unsubscribe,
+        name = "unsubscribe_new_operations"
+    )]
+    fn unsubscribe_new_operations(
+        &self,
+        _: Option<Self::Metadata>,
+        _: SubscriptionId,
+    ) -> jsonrpc_core::Result<bool>;
+}
+
+/// Returns the offset and limit of the requested page.
+/// The limit is capped to `max_page_size`, which is also the default page size.
+fn page_bounds(page: &Option<PageRequest>, max_page_size: usize) -> (usize, usize) {
+    match page {
+        Some(page) => (page.offset, page.limit.min(max_page_size)),
+        None => (0, max_page_size),
+    }
 }
 
 fn wrong_api<T>() -> BoxFuture<Result<T, ApiError>> {
@@ -11,12 +11,18 @@ use massa_consensus_exports::{ConsensusCommandSender, ConsensusConfig};
 use massa_execution_exports::ExecutionController;
 use massa_models::api::{
     AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, OperationInput,
+    EndorsementInfo, EventFilter, GasEstimationInput, NodeStatus, OperationInfo, OperationInput,
+    PageRequest, PagedResponse, PoolOperationFilter, PoolOperationStatus,
     ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
-use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::execution::{
+    ExecuteReadOnlyResponse, ExecutionTrace, GasEstimation, OperationSimulation,
+};
+use massa_models::ip_range::{IpAccessLists, IpRange};
+use massa_models::ledger_models::LedgerSubEntry;
+use massa_models::ledger_proof::LedgerEntryProof;
 use massa_models::node::NodeId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
@@ -44,6 +50,7 @@ impl API<Private> {
         api_settings: APIConfig,
         consensus_settings: ConsensusConfig,
         node_wallet: Arc<RwLock<Wallet>>,
+        bootstrap_access_lists: Option<Arc<RwLock<IpAccessLists>>>,
     ) -> (Self, mpsc::Receiver<()>) {
         let (stop_node_channel, rx) = mpsc::channel(1);
         (
@@ -55,6 +62,7 @@ impl API<Private> {
                 api_settings,
                 stop_node_channel,
                 node_wallet,
+                bootstrap_access_lists,
             }),
             rx,
         )
@@ -110,6 +118,13 @@ impl Endpoints for API<Private> {
         crate::wrong_api::<_>()
     }
 
+    fn estimate_gas(
+        &self,
+        _: Vec<GasEstimationInput>,
+    ) -> BoxFuture<Result<Vec<GasEstimation>, ApiError>> {
+        crate::wrong_api::<_>()
+    }
+
     fn remove_staking_addresses(&self, addresses: Vec<Address>) -> BoxFuture<Result<(), ApiError>> {
         let node_wallet = self.0.node_wallet.clone();
         let closure = async move || {
@@ -126,6 +141,20 @@ impl Endpoints for API<Private> {
         Box::pin(closure())
     }
 
+    fn get_execution_traces(
+        &self,
+        ids: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<Option<ExecutionTrace>>, ApiError>> {
+        if ids.len() as u64 > self.0.api_settings.max_arguments {
+            let closure =
+                async move || Err(ApiError::TooManyArguments("too many arguments".into()));
+            return Box::pin(closure());
+        }
+        let execution_controller = self.0.execution_controller.clone();
+        let closure = async move || Ok(execution_controller.get_execution_traces(&ids));
+        Box::pin(closure())
+    }
+
     fn node_ban_by_ip(&self, ips: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
         let network_command_sender = self.0.network_command_sender.clone();
         let closure = async move || Ok(network_command_sender.node_ban_by_ips(ips).await?);
@@ -165,15 +194,17 @@ impl Endpoints for API<Private> {
     fn get_operations(
         &self,
         _: Vec<OperationId>,
-    ) -> BoxFuture<Result<Vec<OperationInfo>, ApiError>> {
-        crate::wrong_api::<Vec<OperationInfo>>()
+        _: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<OperationInfo>, ApiError>> {
+        crate::wrong_api::<PagedResponse<OperationInfo>>()
     }
 
     fn get_endorsements(
         &self,
         _: Vec<EndorsementId>,
-    ) -> BoxFuture<Result<Vec<EndorsementInfo>, ApiError>> {
-        crate::wrong_api::<Vec<EndorsementInfo>>()
+        _: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<EndorsementInfo>, ApiError>> {
+        crate::wrong_api::<PagedResponse<EndorsementInfo>>()
     }
 
     fn get_block(&self, _: BlockId) -> BoxFuture<Result<BlockInfo, ApiError>> {
@@ -194,12 +225,27 @@ impl Endpoints for API<Private> {
     fn get_datastore_entries(
         &self,
         _: Vec<DatastoreEntryInput>,
-    ) -> BoxFuture<Result<Vec<DatastoreEntryOutput>, ApiError>> {
+        _: Option<PageRequest>,
+        _: Option<Slot>,
+    ) -> BoxFuture<Result<PagedResponse<DatastoreEntryOutput>, ApiError>> {
         crate::wrong_api()
     }
 
-    fn get_addresses(&self, _: Vec<Address>) -> BoxFuture<Result<Vec<AddressInfo>, ApiError>> {
-        crate::wrong_api::<Vec<AddressInfo>>()
+    fn get_addresses(
+        &self,
+        _: Vec<Address>,
+        _: Option<PageRequest>,
+        _: Option<Slot>,
+    ) -> BoxFuture<Result<PagedResponse<AddressInfo>, ApiError>> {
+        crate::wrong_api::<PagedResponse<AddressInfo>>()
+    }
+
+    fn get_ledger_proof(
+        &self,
+        _: Address,
+        _: LedgerSubEntry,
+    ) -> BoxFuture<Result<LedgerEntryProof, ApiError>> {
+        crate::wrong_api::<LedgerEntryProof>()
     }
 
     fn send_operations(
@@ -209,11 +255,34 @@ impl Endpoints for API<Private> {
         crate::wrong_api::<Vec<OperationId>>()
     }
 
+    fn simulate_operations(
+        &self,
+        _: Vec<OperationInput>,
+    ) -> BoxFuture<Result<Vec<OperationSimulation>, ApiError>> {
+        crate::wrong_api::<Vec<OperationSimulation>>()
+    }
+
     fn get_filtered_sc_output_event(
         &self,
         _: EventFilter,
-    ) -> BoxFuture<Result<Vec<SCOutputEvent>, ApiError>> {
-        crate::wrong_api::<Vec<SCOutputEvent>>()
+        _: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<SCOutputEvent>, ApiError>> {
+        crate::wrong_api::<PagedResponse<SCOutputEvent>>()
+    }
+
+    fn get_pool_operation_status(
+        &self,
+        _: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<PoolOperationStatus>, ApiError>> {
+        crate::wrong_api::<Vec<PoolOperationStatus>>()
+    }
+
+    fn list_pool_operations(
+        &self,
+        _: PoolOperationFilter,
+        _: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<OperationId>, ApiError>> {
+        crate::wrong_api::<PagedResponse<OperationId>>()
     }
 
     fn node_whitelist(&self, ips: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
@@ -227,4 +296,75 @@ impl Endpoints for API<Private> {
         let closure = async move || Ok(network_command_sender.remove_from_whitelist(ips).await?);
         Box::pin(closure())
     }
+
+    fn get_bootstrap_access_lists(&self) -> BoxFuture<Result<IpAccessLists, ApiError>> {
+        let access_lists = self.0.bootstrap_access_lists.clone();
+        let closure = async move || Ok(get_bootstrap_access_lists(access_lists)?.read().clone());
+        Box::pin(closure())
+    }
+
+    fn node_add_to_bootstrap_allow_list(
+        &self,
+        ranges: Vec<IpRange>,
+    ) -> BoxFuture<Result<(), ApiError>> {
+        let access_lists = self.0.bootstrap_access_lists.clone();
+        let closure = async move || {
+            get_bootstrap_access_lists(access_lists)?
+                .write()
+                .allow(ranges);
+            Ok(())
+        };
+        Box::pin(closure())
+    }
+
+    fn node_remove_from_bootstrap_allow_list(
+        &self,
+        ranges: Vec<IpRange>,
+    ) -> BoxFuture<Result<(), ApiError>> {
+        let access_lists = self.0.bootstrap_access_lists.clone();
+        let closure = async move || {
+            get_bootstrap_access_lists(access_lists)?
+                .write()
+                .remove_from_allow_list(&ranges);
+            Ok(())
+        };
+        Box::pin(closure())
+    }
+
+    fn node_add_to_bootstrap_deny_list(
+        &self,
+        ranges: Vec<IpRange>,
+    ) -> BoxFuture<Result<(), ApiError>> {
+        let access_lists = self.0.bootstrap_access_lists.clone();
+        let closure = async move || {
+            get_bootstrap_access_lists(access_lists)?
+                .write()
+                .deny(ranges);
+            Ok(())
+        };
+        Box::pin(closure())
+    }
+
+    fn node_remove_from_bootstrap_deny_list(
+        &self,
+        ranges: Vec<IpRange>,
+    ) -> BoxFuture<Result<(), ApiError>> {
+        let access_lists = self.0.bootstrap_access_lists.clone();
+        let closure = async move || {
+            get_bootstrap_access_lists(access_lists)?
+                .write()
+                .remove_from_deny_list(&ranges);
+            Ok(())
+        };
+        Box::pin(closure())
+    }
+}
+
+/// The bootstrap access lists only exist while the bootstrap server is running
+fn get_bootstrap_access_lists(
+    access_lists: Option<Arc<RwLock<IpAccessLists>>>,
+) -> Result<Arc<RwLock<IpAccessLists>>, ApiError> {
+    access_lists.ok_or_else(|| {
+        ApiError::MissingConfig("bootstrap bind: the bootstrap server is not running".to_string())
+    })
 }
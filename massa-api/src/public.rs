@@ -5,17 +5,21 @@ use crate::config::APIConfig;
 use crate::error::ApiError;
 use crate::{Endpoints, Public, RpcServer, StopHandle, API};
 use jsonrpc_core::BoxFuture;
-use massa_consensus_exports::{ConsensusCommandSender, ConsensusConfig};
+use massa_consensus_exports::{BlockStoreController, ConsensusCommandSender, ConsensusConfig};
 use massa_execution_exports::{
     ExecutionController, ExecutionStackElement, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
 };
 use massa_graph::DiscardReason;
 use massa_models::api::{
-    BlockGraphStatus, DatastoreEntryInput, DatastoreEntryOutput, OperationInput,
+    BlockGraphStatus, DatastoreEntryInput, DatastoreEntryOutput, GasEstimationInput,
+    OperationInput, PageRequest, PagedResponse, PoolOperationFilter, PoolOperationStatus,
     ReadOnlyBytecodeExecution, ReadOnlyCall, SlotAmount,
 };
-use massa_models::execution::ReadOnlyResult;
-use massa_models::operation::OperationDeserializer;
+use massa_models::execution::{ExecutionTrace, GasEstimation, OperationSimulation, ReadOnlyResult};
+use massa_models::ip_range::{IpAccessLists, IpRange};
+use massa_models::ledger_models::LedgerSubEntry;
+use massa_models::ledger_proof::LedgerEntryProof;
+use massa_models::operation::{OperationDeserializer, OperationType};
 use massa_models::wrapped::WrappedDeserializer;
 use massa_models::{
     block::Block, endorsement::WrappedEndorsement, error::ModelsError, operation::WrappedOperation,
@@ -41,7 +45,7 @@ use massa_models::{
     execution::ExecuteReadOnlyResponse,
     node::NodeId,
     operation::OperationId,
-    output_event::SCOutputEvent,
+    output_event::{EventCursor, SCOutputEvent},
     prehash::{PreHashMap, PreHashSet},
     slot::Slot,
     timeslots::{get_latest_block_slot_at_timestamp, time_range_to_slot_range},
@@ -70,6 +74,7 @@ impl API<Public> {
         compensation_millis: i64,
         node_id: NodeId,
         storage: Storage,
+        final_block_store: Option<Box<dyn BlockStoreController>>,
     ) -> Self {
         API(Public {
             consensus_command_sender,
@@ -85,6 +90,7 @@ impl API<Public> {
             execution_controller,
             selector_controller,
             storage,
+            final_block_store,
         })
     }
 }
@@ -175,12 +181,15 @@ impl Endpoints for API<Public> {
 
             // map result
             let result = ExecuteReadOnlyResponse {
-                executed_at: result.as_ref().map_or_else(|_| Slot::new(0, 0), |v| v.slot),
+                executed_at: result
+                    .as_ref()
+                    .map_or_else(|_| Slot::new(0, 0), |v| v.out.slot),
                 result: result.as_ref().map_or_else(
                     |err| ReadOnlyResult::Error(format!("readonly call failed: {}", err)),
                     |_| ReadOnlyResult::Ok,
                 ),
-                output_events: result.map_or_else(|_| Default::default(), |mut v| v.events.take()),
+                output_events: result
+                    .map_or_else(|_| Default::default(), |mut v| v.out.events.take()),
             };
 
             res.push(result);
@@ -251,12 +260,119 @@ impl Endpoints for API<Public> {
 
             // map result
             let result = ExecuteReadOnlyResponse {
-                executed_at: result.as_ref().map_or_else(|_| Slot::new(0, 0), |v| v.slot),
+                executed_at: result
+                    .as_ref()
+                    .map_or_else(|_| Slot::new(0, 0), |v| v.out.slot),
                 result: result.as_ref().map_or_else(
                     |err| ReadOnlyResult::Error(format!("readonly call failed: {}", err)),
                     |_| ReadOnlyResult::Ok,
                 ),
-                output_events: result.map_or_else(|_| Default::default(), |mut v| v.events.take()),
+                output_events: result
+                    .map_or_else(|_| Default::default(), |mut v| v.out.events.take()),
+            };
+
+            res.push(result);
+        }
+
+        // return result
+        let closure = async move || Ok(res);
+        Box::pin(closure())
+    }
+
+    fn estimate_gas(
+        &self,
+        reqs: Vec<GasEstimationInput>,
+    ) -> BoxFuture<Result<Vec<GasEstimation>, ApiError>> {
+        if reqs.len() as u64 > self.0.api_settings.max_arguments {
+            let closure =
+                async move || Err(ApiError::TooManyArguments("too many arguments".into()));
+            return Box::pin(closure());
+        }
+
+        let mut res: Vec<GasEstimation> = Vec::with_capacity(reqs.len());
+        for GasEstimationInput { op, sender_address } in reqs {
+            // translate the operation into a request executed on behalf of its sender,
+            // allowing it to spend as much gas as a block can hold
+            let (target, simulated_gas_price, call_stack) = match op {
+                OperationType::ExecuteSC {
+                    data,
+                    gas_price,
+                    datastore,
+                    ..
+                } => (
+                    ReadOnlyExecutionTarget::BytecodeExecution(data),
+                    gas_price,
+                    vec![ExecutionStackElement {
+                        address: sender_address,
+                        coins: Default::default(),
+                        owned_addresses: vec![sender_address],
+                        operation_datastore: Some(datastore),
+                    }],
+                ),
+                OperationType::CallSC {
+                    target_addr,
+                    target_func,
+                    param,
+                    coins,
+                    gas_price,
+                    ..
+                } => (
+                    ReadOnlyExecutionTarget::FunctionCall {
+                        target_addr,
+                        target_func,
+                        parameter: param,
+                    },
+                    gas_price,
+                    vec![
+                        ExecutionStackElement {
+                            address: sender_address,
+                            coins: Default::default(),
+                            owned_addresses: vec![sender_address],
+                            operation_datastore: None,
+                        },
+                        ExecutionStackElement {
+                            address: target_addr,
+                            coins,
+                            owned_addresses: vec![target_addr],
+                            operation_datastore: None,
+                        },
+                    ],
+                ),
+                _ => {
+                    let closure = async move || {
+                        Err(ApiError::InconsistencyError(
+                            "only ExecuteSC and CallSC operations spend gas".into(),
+                        ))
+                    };
+                    return Box::pin(closure());
+                }
+            };
+            let req = ReadOnlyExecutionRequest {
+                max_gas: self.0.api_settings.max_gas_per_block,
+                simulated_gas_price,
+                call_stack,
+                target,
+            };
+
+            // run
+            let result = self.0.execution_controller.execute_readonly_request(req);
+
+            // map result
+            let result = match result {
+                Ok(mut output) => GasEstimation {
+                    executed_at: output.out.slot,
+                    result: ReadOnlyResult::Ok,
+                    gas_cost: output.gas_cost,
+                    output_events: output.out.events.take(),
+                    ledger_changes: output.out.state_changes.ledger_changes.get_summary(),
+                },
+                Err(err) => GasEstimation {
+                    executed_at: Slot::new(0, 0),
+                    result: ReadOnlyResult::Error(format!("gas estimation failed: {}", err)),
+                    gas_cost: 0,
+                    output_events: Default::default(),
+                    ledger_changes: Vec::new(),
+                },
             };
 
             res.push(result);
@@ -275,6 +391,13 @@ impl Endpoints for API<Public> {
         crate::wrong_api::<PreHashSet<Address>>()
     }
 
+    fn get_execution_traces(
+        &self,
+        _: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<Option<ExecutionTrace>>, ApiError>> {
+        crate::wrong_api::<Vec<Option<ExecutionTrace>>>()
+    }
+
     fn node_ban_by_ip(&self, _: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
         crate::wrong_api::<()>()
     }
@@ -324,12 +447,14 @@ impl Endpoints for API<Public> {
                 pool_command_sender.get_endorsement_count(),
             );
 
+            let peers = peers?;
+
             Ok(NodeStatus {
                 node_id,
                 node_ip: network_config.routable_ip,
                 version,
                 current_time: now,
-                connected_nodes: peers?
+                connected_nodes: peers
                     .peers
                     .iter()
                     .flat_map(|(ip, peer)| {
@@ -338,6 +463,12 @@ impl Endpoints for API<Public> {
                             .map(move |(id, is_outgoing)| (*id, (*ip, *is_outgoing)))
                     })
                     .collect(),
+                peer_scores: peers
+                    .peers
+                    .iter()
+                    .filter(|(_, peer)| !peer.active_nodes.is_empty())
+                    .map(|(ip, peer)| (*ip, peer.peer_info.score))
+                    .collect(),
                 last_slot,
                 next_slot: last_slot
                     .unwrap_or_else(|| Slot::new(0, 0))
@@ -388,39 +519,54 @@ impl Endpoints for API<Public> {
     fn get_operations(
         &self,
         ops: Vec<OperationId>,
-    ) -> BoxFuture<Result<Vec<OperationInfo>, ApiError>> {
-        // get the operations and the list of blocks that contain them from storage
+        page: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<OperationInfo>, ApiError>> {
+        if ops.len() as u64 > self.0.api_settings.max_arguments {
+            let closure =
+                async move || Err(ApiError::TooManyArguments("too many arguments".into()));
+            return Box::pin(closure());
+        }
+        let (offset, limit) = crate::page_bounds(&page, self.0.api_settings.max_page_size);
+
+        // get the operations and the list of blocks that contain them from storage,
+        // or from the final block store for the operations pruned from storage
+        let mut archived_blocks: PreHashSet<BlockId> = PreHashSet::default();
         let storage_info: Vec<(WrappedOperation, PreHashSet<BlockId>)> = {
             let read_blocks = self.0.storage.read_blocks();
             let read_ops = self.0.storage.read_operations();
             ops.iter()
-                .filter_map(|id| {
-                    read_ops.get(id).cloned().map(|op| {
-                        (
-                            op,
-                            read_blocks
-                                .get_blocks_by_operation(id)
-                                .cloned()
-                                .unwrap_or_default(),
-                        )
-                    })
+                .filter_map(|id| match read_ops.get(id) {
+                    Some(op) => Some((
+                        op.clone(),
+                        read_blocks
+                            .get_blocks_by_operation(id)
+                            .cloned()
+                            .unwrap_or_default(),
+                    )),
+                    None => self
+                        .0
+                        .final_block_store
+                        .as_ref()
+                        .and_then(|store| store.get_operation(id))
+                        .map(|(op, block_id)| {
+                            archived_blocks.insert(block_id);
+                            (op, PreHashSet::from_iter([block_id]))
+                        }),
                 })
                 .collect()
         };
 
-        // keep only the ops found in storage
+        // keep only the requested page of the ops found in storage
+        let total_count = storage_info.len();
+        let storage_info: Vec<(WrappedOperation, PreHashSet<BlockId>)> =
+            storage_info.into_iter().skip(offset).take(limit).collect();
         let ops: Vec<OperationId> = storage_info.iter().map(|(op, _)| op.id).collect();
 
         // ask pool whether it carries the operations
         let in_pool = self.0.pool_command_sender.contains_operations(&ops);
 
-        let api_cfg = self.0.api_settings;
         let consensus_command_sender = self.0.consensus_command_sender.clone();
         let closure = async move || {
-            if ops.len() as u64 > api_cfg.max_arguments {
-                return Err(ApiError::TooManyArguments("too many arguments".into()));
-            }
-
             // check finality by cross-referencing Consensus and looking for final blocks that contain the op
             let is_final: Vec<bool> = {
                 let involved_blocks: Vec<BlockId> = storage_info
@@ -439,8 +585,10 @@ impl Endpoints for API<Public> {
                 storage_info
                     .iter()
                     .map(|(_op, bs)| {
-                        bs.iter()
-                            .any(|b| block_statuses.get(b) == Some(&BlockGraphStatus::Final))
+                        bs.iter().any(|b| {
+                            archived_blocks.contains(b)
+                                || block_statuses.get(b) == Some(&BlockGraphStatus::Final)
+                        })
                     })
                     .collect()
             };
@@ -464,7 +612,11 @@ impl Endpoints for API<Public> {
             }
 
             // return values in the right order
-            Ok(res)
+            Ok(PagedResponse {
+                content: res,
+                total_count: Some(total_count),
+                next_cursor: None,
+            })
         };
         Box::pin(closure())
     }
@@ -472,39 +624,54 @@ impl Endpoints for API<Public> {
     fn get_endorsements(
         &self,
         eds: Vec<EndorsementId>,
-    ) -> BoxFuture<Result<Vec<EndorsementInfo>, ApiError>> {
-        // get the endorsements and the list of blocks that contain them from storage
+        page: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<EndorsementInfo>, ApiError>> {
+        if eds.len() as u64 > self.0.api_settings.max_arguments {
+            let closure =
+                async move || Err(ApiError::TooManyArguments("too many arguments".into()));
+            return Box::pin(closure());
+        }
+        let (offset, limit) = crate::page_bounds(&page, self.0.api_settings.max_page_size);
+
+        // get the endorsements and the list of blocks that contain them from storage,
+        // or from the final block store for the endorsements pruned from storage
+        let mut archived_blocks: PreHashSet<BlockId> = PreHashSet::default();
         let storage_info: Vec<(WrappedEndorsement, PreHashSet<BlockId>)> = {
             let read_blocks = self.0.storage.read_blocks();
             let read_endos = self.0.storage.read_endorsements();
             eds.iter()
-                .filter_map(|id| {
-                    read_endos.get(id).cloned().map(|ed| {
-                        (
-                            ed,
-                            read_blocks
-                                .get_blocks_by_endorsement(id)
-                                .cloned()
-                                .unwrap_or_default(),
-                        )
-                    })
+                .filter_map(|id| match read_endos.get(id) {
+                    Some(ed) => Some((
+                        ed.clone(),
+                        read_blocks
+                            .get_blocks_by_endorsement(id)
+                            .cloned()
+                            .unwrap_or_default(),
+                    )),
+                    None => self
+                        .0
+                        .final_block_store
+                        .as_ref()
+                        .and_then(|store| store.get_endorsement(id))
+                        .map(|(ed, block_id)| {
+                            archived_blocks.insert(block_id);
+                            (ed, PreHashSet::from_iter([block_id]))
+                        }),
                 })
                 .collect()
         };
 
-        // keep only the ops found in storage
+        // keep only the requested page of the endorsements found in storage
+        let total_count = storage_info.len();
+        let storage_info: Vec<(WrappedEndorsement, PreHashSet<BlockId>)> =
+            storage_info.into_iter().skip(offset).take(limit).collect();
         let eds: Vec<EndorsementId> = storage_info.iter().map(|(ed, _)| ed.id).collect();
 
         // ask pool whether it carries the operations
         let in_pool = self.0.pool_command_sender.contains_endorsements(&eds);
 
         let consensus_command_sender = self.0.consensus_command_sender.clone();
-        let api_cfg = self.0.api_settings;
         let closure = async move || {
-            if eds.len() as u64 > api_cfg.max_arguments {
-                return Err(ApiError::TooManyArguments("too many arguments".into()));
-            }
-
             // check finality by cross-referencing Consensus and looking for final blocks that contain the endorsement
             let is_final: Vec<bool> = {
                 let involved_blocks: Vec<BlockId> = storage_info
@@ -523,8 +690,10 @@ impl Endpoints for API<Public> {
                 storage_info
                     .iter()
                     .map(|(_ed, bs)| {
-                        bs.iter()
-                            .any(|b| block_statuses.get(b) == Some(&BlockGraphStatus::Final))
+                        bs.iter().any(|b| {
+                            archived_blocks.contains(b)
+                                || block_statuses.get(b) == Some(&BlockGraphStatus::Final)
+                        })
                     })
                     .collect()
             };
@@ -548,7 +717,11 @@ impl Endpoints for API<Public> {
             }
 
             // return values in the right order
-            Ok(res)
+            Ok(PagedResponse {
+                content: res,
+                total_count: Some(total_count),
+                next_cursor: None,
+            })
         };
         Box::pin(closure())
     }
@@ -558,11 +731,22 @@ impl Endpoints for API<Public> {
     fn get_block(&self, id: BlockId) -> BoxFuture<Result<BlockInfo, ApiError>> {
         let consensus_command_sender = self.0.consensus_command_sender.clone();
         let storage = self.0.storage.clone_without_refs();
+        let final_block_store = self.0.final_block_store.clone();
         let closure = async move || {
             let block = match storage.read_blocks().get(&id).cloned() {
                 Some(b) => b.content,
                 None => {
-                    return Ok(BlockInfo { id, content: None });
+                    // blocks pruned from storage can only be found in the final block store
+                    let content = final_block_store
+                        .and_then(|store| store.get_block(&id))
+                        .map(|b| BlockInfoContent {
+                            is_final: true,
+                            is_in_blockclique: false,
+                            is_candidate: false,
+                            is_discarded: false,
+                            block: b.content,
+                        });
+                    return Ok(BlockInfo { id, content });
                 }
             };
 
@@ -671,22 +855,43 @@ impl Endpoints for API<Public> {
     fn get_datastore_entries(
         &self,
         entries: Vec<DatastoreEntryInput>,
-    ) -> BoxFuture<Result<Vec<DatastoreEntryOutput>, ApiError>> {
+        page: Option<PageRequest>,
+        at_slot: Option<Slot>,
+    ) -> BoxFuture<Result<PagedResponse<DatastoreEntryOutput>, ApiError>> {
+        let (offset, limit) = crate::page_bounds(&page, self.0.api_settings.max_page_size);
         let execution_controller = self.0.execution_controller.clone();
         let closure = async move || {
-            Ok(execution_controller
-                .get_final_and_active_data_entry(
-                    entries
-                        .into_iter()
-                        .map(|input| (input.address, input.key))
-                        .collect::<Vec<_>>(),
-                )
+            let total_count = entries.len();
+            let input = entries
                 .into_iter()
-                .map(|output| DatastoreEntryOutput {
-                    final_value: output.0,
-                    candidate_value: output.1,
-                })
-                .collect())
+                .skip(offset)
+                .take(limit)
+                .map(|input| (input.address, input.key))
+                .collect::<Vec<_>>();
+            let content = match at_slot {
+                // values at the output of a past final slot
+                Some(slot) => execution_controller
+                    .get_final_data_entries_at_slot(input, slot)?
+                    .into_iter()
+                    .map(|value| DatastoreEntryOutput {
+                        final_value: value.clone(),
+                        candidate_value: value,
+                    })
+                    .collect(),
+                None => execution_controller
+                    .get_final_and_active_data_entry(input)
+                    .into_iter()
+                    .map(|output| DatastoreEntryOutput {
+                        final_value: output.0,
+                        candidate_value: output.1,
+                    })
+                    .collect(),
+            };
+            Ok(PagedResponse {
+                content,
+                total_count: Some(total_count),
+                next_cursor: None,
+            })
         };
         Box::pin(closure())
     }
@@ -694,7 +899,14 @@ impl Endpoints for API<Public> {
     fn get_addresses(
         &self,
         addresses: Vec<Address>,
-    ) -> BoxFuture<Result<Vec<AddressInfo>, ApiError>> {
+        page: Option<PageRequest>,
+        at_slot: Option<Slot>,
+    ) -> BoxFuture<Result<PagedResponse<AddressInfo>, ApiError>> {
+        // keep only the requested page of addresses
+        let (offset, limit) = crate::page_bounds(&page, self.0.api_settings.max_page_size);
+        let total_count = addresses.len();
+        let addresses: Vec<Address> = addresses.into_iter().skip(offset).take(limit).collect();
+
         // get info from storage about which blocks the addresses have created
         let created_blocks: Vec<PreHashSet<BlockId>> = {
             let lck = self.0.storage.read_blocks();
@@ -734,8 +946,21 @@ impl Endpoints for API<Public> {
                 .collect()
         };
 
-        // get execution info
-        let execution_infos = self.0.execution_controller.get_addresses_infos(&addresses);
+        // get execution info, at the output of a past final slot if requested
+        let execution_infos = match at_slot {
+            Some(slot) => match self
+                .0
+                .execution_controller
+                .get_addresses_infos_at_slot(&addresses, slot)
+            {
+                Ok(execution_infos) => execution_infos,
+                Err(err) => {
+                    let closure = async move || Err(ApiError::from(err));
+                    return Box::pin(closure());
+                }
+            },
+            None => self.0.execution_controller.get_addresses_infos(&addresses),
+        };
 
         // get future draws from selector
         let selection_draws = {
@@ -825,7 +1050,24 @@ impl Endpoints for API<Public> {
             });
         }
 
-        let closure = async move || Ok(res);
+        let closure = async move || {
+            Ok(PagedResponse {
+                content: res,
+                total_count: Some(total_count),
+                next_cursor: None,
+            })
+        };
+        Box::pin(closure())
+    }
+
+    fn get_ledger_proof(
+        &self,
+        address: Address,
+        sub_entry: LedgerSubEntry,
+    ) -> BoxFuture<Result<LedgerEntryProof, ApiError>> {
+        let execution_controller = self.0.execution_controller.clone();
+        let closure =
+            async move || Ok(execution_controller.get_final_ledger_proof(&address, sub_entry));
         Box::pin(closure())
     }
 
@@ -841,42 +1083,7 @@ impl Endpoints for API<Public> {
             if ops.len() as u64 > api_cfg.max_arguments {
                 return Err(ApiError::TooManyArguments("too many arguments".into()));
             }
-            let operation_deserializer = WrappedDeserializer::new(OperationDeserializer::new(
-                api_cfg.max_datastore_value_length,
-                api_cfg.max_function_name_length,
-                api_cfg.max_parameter_size,
-                api_cfg.max_op_datastore_entry_count,
-                api_cfg.max_op_datastore_key_length,
-                api_cfg.max_op_datastore_value_length,
-            ));
-            let verified_ops = ops
-                .into_iter()
-                .map(|op_input| {
-                    let mut op_serialized = Vec::new();
-                    op_serialized.extend(op_input.signature.to_bytes());
-                    op_serialized.extend(op_input.creator_public_key.to_bytes());
-                    op_serialized.extend(op_input.serialized_content);
-                    let (rest, op): (&[u8], WrappedOperation) = operation_deserializer
-                        .deserialize::<DeserializeError>(&op_serialized)
-                        .map_err(|err| {
-                            ApiError::ModelsError(ModelsError::DeserializeError(err.to_string()))
-                        })?;
-                    if rest.is_empty() {
-                        Ok(op)
-                    } else {
-                        Err(ApiError::ModelsError(ModelsError::DeserializeError(
-                            "There is data left after operation deserialization".to_owned(),
-                        )))
-                    }
-                })
-                .map(|op| match op {
-                    Ok(operation) => {
-                        operation.verify_signature()?;
-                        Ok(operation)
-                    }
-                    Err(e) => Err(e),
-                })
-                .collect::<Result<Vec<WrappedOperation>, ApiError>>()?;
+            let verified_ops = verify_operations(ops, &api_cfg)?;
             to_send.store_operations(verified_ops.clone());
             let ids: Vec<OperationId> = verified_ops.iter().map(|op| op.id).collect();
             cmd_sender.add_operations(to_send.clone());
@@ -886,23 +1093,116 @@ impl Endpoints for API<Public> {
         Box::pin(closure())
     }
 
+    fn simulate_operations(
+        &self,
+        ops: Vec<OperationInput>,
+    ) -> BoxFuture<Result<Vec<OperationSimulation>, ApiError>> {
+        let api_cfg = self.0.api_settings;
+        let execution_controller = self.0.execution_controller.clone();
+        let closure = async move || {
+            if ops.len() as u64 > api_cfg.max_arguments {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            let verified_ops = verify_operations(ops, &api_cfg)?;
+            Ok(execution_controller.simulate_operations(verified_ops)?)
+        };
+        Box::pin(closure())
+    }
+
     /// Get events optionally filtered by:
     /// * start slot
     /// * end slot
     /// * emitter address
     /// * original caller address
     /// * operation id
+    ///
+    /// Final events are paged first, then candidate events, all in slot order.
     fn get_filtered_sc_output_event(
         &self,
         filter: EventFilter,
-    ) -> BoxFuture<Result<Vec<SCOutputEvent>, ApiError>> {
-        let events = self
-            .0
-            .execution_controller
-            .get_filtered_sc_output_event(filter);
-
-        // TODO: get rid of the async part
-        let closure = async move || Ok(events);
+        page: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<SCOutputEvent>, ApiError>> {
+        let (_, limit) = crate::page_bounds(&page, self.0.api_settings.max_events_page_size);
+        let execution_controller = self.0.execution_controller.clone();
+        let closure = async move || {
+            let cursor = match page.and_then(|page| page.cursor) {
+                Some(cursor) => Some(cursor.parse::<EventCursor>()?),
+                None => None,
+            };
+
+            // final events come first as their slots precede the ones of candidate events
+            let mut content = Vec::new();
+            let mut next_cursor = None;
+            if filter.is_final != Some(false) {
+                (content, next_cursor) = execution_controller.get_final_sc_output_events_page(
+                    EventFilter {
+                        is_final: Some(true),
+                        ..filter.clone()
+                    },
+                    cursor,
+                    limit,
+                );
+            }
+
+            // complete the page with candidate events
+            if next_cursor.is_none() && filter.is_final != Some(true) {
+                let mut candidate_events = execution_controller
+                    .get_filtered_sc_output_event(EventFilter {
+                        is_final: Some(false),
+                        ..filter
+                    })
+                    .into_iter()
+                    .filter(|event| cursor.map_or(true, |cursor| event.cursor() > cursor));
+                content.extend(candidate_events.by_ref().take(limit - content.len()));
+                if candidate_events.next().is_some() {
+                    next_cursor = content.last().map(SCOutputEvent::cursor);
+                }
+            }
+
+            Ok(PagedResponse {
+                content,
+                total_count: None,
+                next_cursor: next_cursor.map(|cursor| cursor.to_string()),
+            })
+        };
+        Box::pin(closure())
+    }
+
+    fn get_pool_operation_status(
+        &self,
+        ops: Vec<OperationId>,
+    ) -> BoxFuture<Result<Vec<PoolOperationStatus>, ApiError>> {
+        let api_cfg = self.0.api_settings;
+        let pool_command_sender = self.0.pool_command_sender.clone();
+        let closure = async move || {
+            if ops.len() as u64 > api_cfg.max_arguments {
+                return Err(ApiError::TooManyArguments("too many arguments".into()));
+            }
+            Ok(ops
+                .iter()
+                .map(|id| pool_command_sender.get_operation_status(id))
+                .collect())
+        };
+        Box::pin(closure())
+    }
+
+    fn list_pool_operations(
+        &self,
+        filter: PoolOperationFilter,
+        page: Option<PageRequest>,
+    ) -> BoxFuture<Result<PagedResponse<OperationId>, ApiError>> {
+        let (_, limit) = crate::page_bounds(&page, self.0.api_settings.max_page_size);
+        let pool_command_sender = self.0.pool_command_sender.clone();
+        let closure = async move || {
+            let cursor = match page.and_then(|page| page.cursor) {
+                Some(cursor) => Some(cursor.parse::<OperationId>()?),
+                None => None,
+            };
+            // the listing can't continue once the operation of the cursor left the pool
+            pool_command_sender
+                .list_operations(&filter, cursor, limit)
+                .ok_or(ApiError::NotFound)
+        };
         Box::pin(closure())
     }
 
@@ -913,4 +1213,72 @@ impl Endpoints for API<Public> {
     fn node_remove_from_whitelist(&self, _: Vec<IpAddr>) -> BoxFuture<Result<(), ApiError>> {
         crate::wrong_api::<()>()
     }
+
+    fn get_bootstrap_access_lists(&self) -> BoxFuture<Result<IpAccessLists, ApiError>> {
+        crate::wrong_api::<IpAccessLists>()
+    }
+
+    fn node_add_to_bootstrap_allow_list(&self, _: Vec<IpRange>) -> BoxFuture<Result<(), ApiError>> {
+        crate::wrong_api::<()>()
+    }
+
+    fn node_remove_from_bootstrap_allow_list(
+        &self,
+        _: Vec<IpRange>,
+    ) -> BoxFuture<Result<(), ApiError>> {
+        crate::wrong_api::<()>()
+    }
+
+    fn node_add_to_bootstrap_deny_list(&self, _: Vec<IpRange>) -> BoxFuture<Result<(), ApiError>> {
+        crate::wrong_api::<()>()
+    }
+
+    fn node_remove_from_bootstrap_deny_list(
+        &self,
+        _: Vec<IpRange>,
+    ) -> BoxFuture<Result<(), ApiError>> {
+        crate::wrong_api::<()>()
+    }
+}
+
+/// Deserializes operations and verifies their signature
+fn verify_operations(
+    ops: Vec<OperationInput>,
+    api_cfg: &APIConfig,
+) -> Result<Vec<WrappedOperation>, ApiError> {
+    let operation_deserializer = WrappedDeserializer::new(OperationDeserializer::new(
+        api_cfg.max_datastore_value_length,
+        api_cfg.max_function_name_length,
+        api_cfg.max_parameter_size,
+        api_cfg.max_op_datastore_entry_count,
+        api_cfg.max_op_datastore_key_length,
+        api_cfg.max_op_datastore_value_length,
+    ));
+    ops.into_iter()
+        .map(|op_input| {
+            let mut op_serialized = Vec::new();
+            op_serialized.extend(op_input.signature.to_bytes());
+            op_serialized.extend(op_input.creator_public_key.to_bytes());
+            op_serialized.extend(op_input.serialized_content);
+            let (rest, op): (&[u8], WrappedOperation) = operation_deserializer
+                .deserialize::<DeserializeError>(&op_serialized)
+                .map_err(|err| {
+                    ApiError::ModelsError(ModelsError::DeserializeError(err.to_string()))
+                })?;
+            if rest.is_empty() {
+                Ok(op)
+            } else {
+                Err(ApiError::ModelsError(ModelsError::DeserializeError(
+                    "There is data left after operation deserialization".to_owned(),
+                )))
+            }
+        })
+        .map(|op| match op {
+            Ok(operation) => {
+                operation.verify_signature()?;
+                Ok(operation)
+            }
+            Err(e) => Err(e),
+        })
+        .collect()
 }
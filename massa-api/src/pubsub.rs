@@ -0,0 +1,447 @@
+//! Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::config::APIConfig;
+use crate::error::ApiError;
+use crate::{PubSub, PubSubEndpoints, StopHandle, API};
+
+use jsonrpc_core::futures::channel::mpsc::UnboundedSender;
+use jsonrpc_pubsub::{typed, PubSubMetadata, Session, SubscriptionId};
+use massa_models::api::EventFilter;
+use massa_models::block::WrappedBlock;
+use massa_models::operation::WrappedOperation;
+use massa_models::output_event::SCOutputEvent;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// Kind of a subscription, each kind is cancelled by its own unsubscribe method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SubscriptionKind {
+    NewBlocks,
+    FinalizedBlocks,
+    FilteredSCOutputEvent,
+    NewOperations,
+}
+
+/// Metadata of a connection to the subscription API
+#[derive(Clone)]
+pub struct SubscriptionSession {
+    /// identifier of the session, unique while the server runs
+    id: u64,
+    /// pubsub session of the connection
+    session: Arc<Session>,
+}
+
+impl jsonrpc_core::Metadata for SubscriptionSession {}
+
+impl PubSubMetadata for SubscriptionSession {
+    fn session(&self) -> Option<Arc<Session>> {
+        Some(self.session.clone())
+    }
+}
+
+/// Forwarding tasks of a session, by subscription kind and identifier
+type SessionTasks = HashMap<(SubscriptionKind, SubscriptionId), JoinHandle<()>>;
+
+/// Forwarding tasks of the active subscriptions, by session
+#[derive(Default)]
+pub(crate) struct Subscriptions {
+    /// next session identifier
+    next_session_id: AtomicU64,
+    /// next subscription identifier
+    next_subscription_id: AtomicU64,
+    /// forwarding tasks of the open sessions
+    sessions: Mutex<HashMap<u64, SessionTasks>>,
+}
+
+impl Subscriptions {
+    /// Opens the session of a new connection.
+    /// The forwarding tasks of its subscriptions are stopped when the connection closes.
+    pub(crate) fn open_session(
+        self: &Arc<Self>,
+        sender: UnboundedSender<String>,
+    ) -> SubscriptionSession {
+        let id = self.next_session_id.fetch_add(1, Ordering::Relaxed);
+        self.sessions.lock().insert(id, SessionTasks::new());
+        let session = Arc::new(Session::new(sender));
+        let subscriptions = Arc::downgrade(self);
+        session.on_drop(move || {
+            if let Some(subscriptions) = subscriptions.upgrade() {
+                subscriptions.close_session(id);
+            }
+        });
+        SubscriptionSession { id, session }
+    }
+
+    /// Stops the forwarding tasks of a closed session
+    fn close_session(&self, session_id: u64) {
+        if let Some(tasks) = self.sessions.lock().remove(&session_id) {
+            for handle in tasks.into_values() {
+                handle.abort();
+            }
+        }
+    }
+}
+
+impl API<PubSub> {
+    /// generate a new subscription API
+    pub fn new(
+        block_sender: broadcast::Sender<WrappedBlock>,
+        finalized_block_sender: broadcast::Sender<WrappedBlock>,
+        operation_sender: broadcast::Sender<WrappedOperation>,
+        sc_output_event_sender: broadcast::Sender<SCOutputEvent>,
+        api_settings: APIConfig,
+    ) -> Self {
+        API(PubSub {
+            block_sender,
+            finalized_block_sender,
+            operation_sender,
+            sc_output_event_sender,
+            api_settings,
+            subscriptions: Default::default(),
+            runtime: tokio::runtime::Handle::current(),
+        })
+    }
+
+    /// Start the subscription API
+    pub fn serve(self, url: &SocketAddr) -> StopHandle {
+        let subscriptions = self.0.subscriptions.clone();
+        crate::serve_pubsub(self, url, move |sender| subscriptions.open_session(sender))
+    }
+}
+
+impl PubSub {
+    /// Register a subscription of a session and spawn the task forwarding
+    /// the items of `receiver` accepted by `filter` to the subscriber.
+    fn subscribe<T, F>(
+        &self,
+        meta: &SubscriptionSession,
+        kind: SubscriptionKind,
+        subscriber: typed::Subscriber<T>,
+        mut receiver: broadcast::Receiver<T>,
+        filter: F,
+    ) where
+        T: Serialize + Clone + Send + 'static,
+        F: Fn(&T) -> bool + Send + 'static,
+    {
+        // the lock is held until the task is registered so that it cannot unregister itself before
+        let mut sessions = self.subscriptions.sessions.lock();
+        let tasks = match sessions.get_mut(&meta.id) {
+            Some(tasks) => tasks,
+            // the connection was closed in the meantime
+            None => return,
+        };
+        let max_subscriptions = self.api_settings.max_subscriptions_per_session;
+        if tasks.len() >= max_subscriptions {
+            let _ = subscriber.reject(ApiError::TooManySubscriptions(max_subscriptions).into());
+            return;
+        }
+        let id = SubscriptionId::Number(
+            self.subscriptions
+                .next_subscription_id
+                .fetch_add(1, Ordering::Relaxed),
+        );
+        let sink = match subscriber.assign_id(id.clone()) {
+            Ok(sink) => sink,
+            // the client went away in the meantime
+            Err(()) => return,
+        };
+
+        let subscriptions = self.subscriptions.clone();
+        let session_id = meta.id;
+        let task_id = id.clone();
+        let handle = self.runtime.spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(item) => {
+                        if filter(&item) && sink.notify(Ok(item)).is_err() {
+                            // the client is gone
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(
+                            "subscription {:?} is lagging behind, {} notifications skipped",
+                            task_id, skipped
+                        );
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            if let Some(tasks) = subscriptions.sessions.lock().get_mut(&session_id) {
+                tasks.remove(&(kind, task_id));
+            }
+        });
+        tasks.insert((kind, id), handle);
+    }
+
+    /// Cancel a subscription of a session and stop its forwarding task.
+    /// Only the session that made the subscription can cancel it, with the unsubscribe method of its kind.
+    fn unsubscribe(
+        &self,
+        meta: Option<SubscriptionSession>,
+        kind: SubscriptionKind,
+        id: SubscriptionId,
+    ) -> jsonrpc_core::Result<bool> {
+        let handle = meta.and_then(|meta| {
+            self.subscriptions
+                .sessions
+                .lock()
+                .get_mut(&meta.id)
+                .and_then(|tasks| tasks.remove(&(kind, id.clone())))
+        });
+        match handle {
+            Some(handle) => {
+                handle.abort();
+                Ok(true)
+            }
+            None => Err(ApiError::UnknownSubscription(format!("{:?}", id)).into()),
+        }
+    }
+}
+
+#[doc(hidden)]
+impl PubSubEndpoints for API<PubSub> {
+    type Metadata = SubscriptionSession;
+
+    fn subscribe_new_blocks(
+        &self,
+        meta: Self::Metadata,
+        subscriber: typed::Subscriber<WrappedBlock>,
+    ) {
+        self.0.subscribe(
+            &meta,
+            SubscriptionKind::NewBlocks,
+            subscriber,
+            self.0.block_sender.subscribe(),
+            |_| true,
+        );
+    }
+
+    fn unsubscribe_new_blocks(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> jsonrpc_core::Result<bool> {
+        self.0.unsubscribe(meta, SubscriptionKind::NewBlocks, id)
+    }
+
+    fn subscribe_finalized_blocks(
+        &self,
+        meta: Self::Metadata,
+        subscriber: typed::Subscriber<WrappedBlock>,
+    ) {
+        self.0.subscribe(
+            &meta,
+            SubscriptionKind::FinalizedBlocks,
+            subscriber,
+            self.0.finalized_block_sender.subscribe(),
+            |_| true,
+        );
+    }
+
+    fn unsubscribe_finalized_blocks(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> jsonrpc_core::Result<bool> {
+        self.0
+            .unsubscribe(meta, SubscriptionKind::FinalizedBlocks, id)
+    }
+
+    fn subscribe_filtered_sc_output_event(
+        &self,
+        meta: Self::Metadata,
+        subscriber: typed::Subscriber<SCOutputEvent>,
+        filter: EventFilter,
+    ) {
+        self.0.subscribe(
+            &meta,
+            SubscriptionKind::FilteredSCOutputEvent,
+            subscriber,
+            self.0.sc_output_event_sender.subscribe(),
+            move |event| filter.matches(event),
+        );
+    }
+
+    fn unsubscribe_filtered_sc_output_event(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> jsonrpc_core::Result<bool> {
+        self.0
+            .unsubscribe(meta, SubscriptionKind::FilteredSCOutputEvent, id)
+    }
+
+    fn subscribe_new_operations(
+        &self,
+        meta: Self::Metadata,
+        subscriber: typed::Subscriber<WrappedOperation>,
+    ) {
+        self.0.subscribe(
+            &meta,
+            SubscriptionKind::NewOperations,
+            subscriber,
+            self.0.operation_sender.subscribe(),
+            |_| true,
+        );
+    }
+
+    fn unsubscribe_new_operations(
+        &self,
+        meta: Option<Self::Metadata>,
+        id: SubscriptionId,
+    ) -> jsonrpc_core::Result<bool> {
+        self.0
+            .unsubscribe(meta, SubscriptionKind::NewOperations, id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpc_core::futures::{channel::mpsc, StreamExt};
+    use jsonrpc_core::Value;
+    use std::time::Duration;
+
+    fn get_pubsub_api(max_subscriptions_per_session: usize) -> API<PubSub> {
+        let api_settings = APIConfig {
+            draw_lookahead_period_count: 10,
+            bind_private: "127.0.0.1:0".parse().unwrap(),
+            bind_public: "127.0.0.1:0".parse().unwrap(),
+            enable_ws: true,
+            bind_ws: "127.0.0.1:0".parse().unwrap(),
+            max_subscriptions_per_session,
+            max_arguments: 128,
+            max_page_size: 128,
+            max_events_page_size: 1000,
+            max_datastore_value_length: 1_000_000,
+            max_op_datastore_entry_count: 128,
+            max_op_datastore_key_length: 255,
+            max_op_datastore_value_length: 1_000_000,
+            max_function_name_length: 256,
+            max_parameter_size: 1_000_000,
+            max_gas_per_block: 1_000_000_000,
+        };
+        API::<PubSub>::new(
+            broadcast::channel(16).0,
+            broadcast::channel(16).0,
+            broadcast::channel(16).0,
+            broadcast::channel(16).0,
+            api_settings,
+        )
+    }
+
+    fn open_session(api: &API<PubSub>) -> SubscriptionSession {
+        api.0.subscriptions.open_session(mpsc::unbounded().0)
+    }
+
+    #[tokio::test]
+    async fn test_subscription_notifications() {
+        let api = get_pubsub_api(10);
+        let session = open_session(&api);
+        let (item_sender, _) = broadcast::channel::<u64>(16);
+        let (subscriber, id_receiver, mut notifications) = typed::Subscriber::new_test("numbers");
+        api.0.subscribe(
+            &session,
+            SubscriptionKind::NewBlocks,
+            subscriber,
+            item_sender.subscribe(),
+            |n| n % 2 == 0,
+        );
+        let id = id_receiver.await.unwrap().unwrap();
+
+        for n in 1..=4 {
+            item_sender.send(n).unwrap();
+        }
+        // only the items accepted by the filter are forwarded, in order
+        for expected in [2u64, 4] {
+            let notification: Value =
+                jsonrpc_core::serde_from_str(&notifications.next().await.unwrap()).unwrap();
+            assert_eq!(notification["method"], "numbers");
+            assert_eq!(
+                notification["params"]["subscription"],
+                Value::from(id.clone())
+            );
+            assert_eq!(notification["params"]["result"], expected);
+        }
+
+        // the forwarding task stops when the broadcast channel closes
+        drop(item_sender);
+        let end = tokio::time::timeout(Duration::from_secs(5), notifications.next())
+            .await
+            .expect("the forwarding task did not stop");
+        assert!(end.is_none());
+        assert!(api.0.subscriptions.sessions.lock()[&session.id].is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe_ownership() {
+        let api = get_pubsub_api(10);
+        let session = open_session(&api);
+        let other_session = open_session(&api);
+        let (subscriber, id_receiver, _notifications) = typed::Subscriber::new_test("new_blocks");
+        api.subscribe_new_blocks(session.clone(), subscriber);
+        let id = id_receiver.await.unwrap().unwrap();
+
+        // another session can't cancel the subscription
+        assert!(api
+            .unsubscribe_new_blocks(Some(other_session), id.clone())
+            .is_err());
+        assert!(api.unsubscribe_new_blocks(None, id.clone()).is_err());
+        // nor can the unsubscribe method of another kind
+        assert!(api
+            .unsubscribe_filtered_sc_output_event(Some(session.clone()), id.clone())
+            .is_err());
+
+        assert_eq!(
+            api.unsubscribe_new_blocks(Some(session.clone()), id.clone()),
+            Ok(true)
+        );
+        assert!(api.unsubscribe_new_blocks(Some(session), id).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_per_session() {
+        let api = get_pubsub_api(2);
+        let session = open_session(&api);
+        let session_id = session.id;
+        let mut notifications = Vec::new();
+        for _ in 0..2 {
+            let (subscriber, id_receiver, receiver) = typed::Subscriber::new_test("new_operations");
+            api.subscribe_new_operations(session.clone(), subscriber);
+            assert!(id_receiver.await.unwrap().is_ok());
+            notifications.push(receiver);
+        }
+        let (subscriber, id_receiver, _) = typed::Subscriber::new_test("new_operations");
+        api.subscribe_new_operations(session.clone(), subscriber);
+        assert!(id_receiver.await.unwrap().is_err());
+
+        // the limit applies per session
+        let other_session = open_session(&api);
+        let (subscriber, id_receiver, _) = typed::Subscriber::new_test("new_operations");
+        api.subscribe_new_operations(other_session, subscriber);
+        assert!(id_receiver.await.unwrap().is_ok());
+
+        // closing the connection stops the forwarding tasks of the session
+        drop(session);
+        assert!(!api
+            .0
+            .subscriptions
+            .sessions
+            .lock()
+            .contains_key(&session_id));
+        for mut receiver in notifications {
+            let end = tokio::time::timeout(Duration::from_secs(5), receiver.next())
+                .await
+                .expect("the forwarding task did not stop");
+            assert!(end.is_none());
+        }
+    }
+}
@@ -0,0 +1,364 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use super::tools::{get_public_api, get_test_api_config};
+use crate::{page_bounds, Endpoints};
+use massa_execution_exports::test_exports::MockExecutionControllerMessage;
+use massa_execution_exports::{
+    ExecutionOutput, ReadOnlyExecutionOutput, ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+};
+use massa_hash::Hash;
+use massa_models::{
+    address::Address,
+    amount::Amount,
+    api::{EventFilter, GasEstimationInput, PageRequest, PagedResponse, PoolOperationFilter},
+    endorsement::EndorsementId,
+    execution::ReadOnlyResult,
+    operation::{OperationId, OperationType},
+    output_event::{EventExecutionContext, SCOutputEvent},
+    slot::Slot,
+    wrapped::Id,
+};
+use massa_pool_exports::test_exports::MockPoolControllerMessage;
+use massa_signature::KeyPair;
+use std::str::FromStr;
+use std::sync::mpsc::Receiver;
+use std::thread;
+
+/// Answers every read-only execution request received by the mocked execution controller
+/// with a successful output spending `gas_cost`, after checking the request with `check`
+fn answer_readonly_requests<F>(
+    execution_receiver: Receiver<MockExecutionControllerMessage>,
+    gas_cost: u64,
+    check: F,
+) -> thread::JoinHandle<()>
+where
+    F: Fn(&ReadOnlyExecutionRequest) + Send + 'static,
+{
+    thread::spawn(move || {
+        while let Ok(msg) = execution_receiver.recv() {
+            if let MockExecutionControllerMessage::ExecuteReadonlyRequest { req, response_tx } = msg
+            {
+                check(&req);
+                response_tx
+                    .send(Ok(ReadOnlyExecutionOutput {
+                        out: ExecutionOutput {
+                            slot: Slot::new(1, 0),
+                            block_id: None,
+                            state_changes: Default::default(),
+                            events: Default::default(),
+                        },
+                        gas_cost,
+                    }))
+                    .unwrap();
+            }
+        }
+    })
+}
+
+fn get_event(period: u64, thread: u8, index_in_slot: u64) -> SCOutputEvent {
+    SCOutputEvent {
+        context: EventExecutionContext {
+            slot: Slot::new(period, thread),
+            block: None,
+            read_only: false,
+            index_in_slot,
+            call_stack: Default::default(),
+            origin_operation_id: None,
+            is_final: false,
+        },
+        data: format!("event {}_{}_{}", period, thread, index_in_slot),
+    }
+}
+
+#[test]
+fn test_page_bounds() {
+    // no page requested: first page of the max size
+    assert_eq!(page_bounds(&None, 3), (0, 3));
+
+    let page = |offset, limit| {
+        Some(PageRequest {
+            offset,
+            cursor: None,
+            limit,
+        })
+    };
+    assert_eq!(page_bounds(&page(5, 2), 3), (5, 2));
+    // the limit is capped to the max page size
+    assert_eq!(page_bounds(&page(1, 10), 3), (1, 3));
+    assert_eq!(page_bounds(&page(0, 0), 3), (0, 0));
+}
+
+#[tokio::test]
+async fn test_max_page_size() {
+    let api_settings = get_test_api_config();
+    let (api, _execution_receiver, pool_receiver) = get_public_api(api_settings);
+    let responder = thread::spawn(move || {
+        let mut requests = Vec::new();
+        while let Ok(msg) = pool_receiver.0.recv() {
+            if let MockPoolControllerMessage::ListOperations {
+                cursor,
+                limit,
+                response_tx,
+                ..
+            } = msg
+            {
+                requests.push((cursor, limit));
+                response_tx
+                    .send(Some(PagedResponse {
+                        content: Vec::new(),
+                        total_count: Some(0),
+                        next_cursor: None,
+                    }))
+                    .unwrap();
+            }
+        }
+        requests
+    });
+
+    let cursor = OperationId::new(Hash::compute_from(b"cursor"));
+    for page in [
+        None,
+        Some(PageRequest {
+            offset: 2,
+            cursor: None,
+            limit: 1,
+        }),
+        Some(PageRequest {
+            offset: 0,
+            cursor: Some(cursor.to_string()),
+            limit: 1000,
+        }),
+    ] {
+        api.list_pool_operations(PoolOperationFilter::default(), page)
+            .await
+            .unwrap();
+    }
+
+    // malformed cursors are rejected
+    assert!(api
+        .list_pool_operations(
+            PoolOperationFilter::default(),
+            Some(PageRequest {
+                offset: 0,
+                cursor: Some("cursor".into()),
+                limit: 1,
+            }),
+        )
+        .await
+        .is_err());
+
+    drop(api);
+    assert_eq!(
+        responder.join().unwrap(),
+        vec![
+            (None, api_settings.max_page_size),
+            (None, 1),
+            (Some(cursor), api_settings.max_page_size)
+        ]
+    );
+}
+
+/// Requests for more ids than the max argument count are rejected whatever the requested page
+#[tokio::test]
+async fn test_get_by_ids_max_arguments() {
+    let api_settings = get_test_api_config();
+    let (api, _execution_receiver, pool_receiver) = get_public_api(api_settings);
+    let page = Some(PageRequest {
+        offset: 0,
+        cursor: None,
+        limit: 1,
+    });
+
+    let op_ids: Vec<OperationId> = (0..=api_settings.max_arguments)
+        .map(|i| OperationId::new(Hash::compute_from(&i.to_be_bytes())))
+        .collect();
+    assert!(api.get_operations(op_ids, page.clone()).await.is_err());
+    let endorsement_ids: Vec<EndorsementId> = (0..=api_settings.max_arguments)
+        .map(|i| EndorsementId::new(Hash::compute_from(&i.to_be_bytes())))
+        .collect();
+    assert!(api.get_endorsements(endorsement_ids, page).await.is_err());
+
+    // the requests were rejected before asking the pool about the ids
+    assert!(pool_receiver.0.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_events_cursor_continuation() {
+    let (api, execution_receiver, _pool_receiver) = get_public_api(get_test_api_config());
+    let events = vec![
+        get_event(1, 0, 0),
+        get_event(1, 0, 1),
+        get_event(1, 1, 0),
+        get_event(2, 0, 0),
+        get_event(2, 0, 1),
+    ];
+    let candidate_events = events.clone();
+    let responder = thread::spawn(move || {
+        while let Ok(msg) = execution_receiver.recv() {
+            if let MockExecutionControllerMessage::GetFilteredScOutputEvent {
+                response_tx, ..
+            } = msg
+            {
+                response_tx.send(candidate_events.clone()).unwrap();
+            }
+        }
+    });
+
+    // page through the events, asking for more than the max events page size
+    let mut received = Vec::new();
+    let mut cursors = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = api
+            .get_filtered_sc_output_event(
+                EventFilter::default(),
+                Some(PageRequest {
+                    offset: 0,
+                    cursor: cursor.clone(),
+                    limit: 10,
+                }),
+            )
+            .await
+            .unwrap();
+        assert!(page.content.len() <= get_test_api_config().max_events_page_size);
+        received.extend(page.content.into_iter().map(|event| event.cursor()));
+        match page.next_cursor {
+            Some(next_cursor) => {
+                cursors.push(next_cursor.clone());
+                cursor = Some(next_cursor);
+            }
+            None => break,
+        }
+    }
+
+    // every event is returned once, in order
+    assert_eq!(
+        received,
+        events.iter().map(SCOutputEvent::cursor).collect::<Vec<_>>()
+    );
+    assert_eq!(cursors, vec!["1_0_1".to_string(), "2_0_0".to_string()]);
+
+    // malformed cursors are rejected
+    assert!(api
+        .get_filtered_sc_output_event(
+            EventFilter::default(),
+            Some(PageRequest {
+                offset: 0,
+                cursor: Some("1_0".into()),
+                limit: 10,
+            }),
+        )
+        .await
+        .is_err());
+
+    drop(api);
+    responder.join().unwrap();
+}
+
+#[tokio::test]
+async fn test_estimate_gas_execute_sc() {
+    let api_settings = get_test_api_config();
+    let (api, execution_receiver, _pool_receiver) = get_public_api(api_settings);
+    let sender_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let responder = answer_readonly_requests(execution_receiver, 1234, move |req| {
+        // the estimation can use the whole gas of a block, whatever the max gas of the operation
+        assert_eq!(req.max_gas, api_settings.max_gas_per_block);
+        assert!(
+            matches!(&req.target, ReadOnlyExecutionTarget::BytecodeExecution(data) if data == &vec![1, 2, 3])
+        );
+        assert_eq!(req.call_stack.len(), 1);
+        assert_eq!(req.call_stack[0].address, sender_address);
+        assert!(req.call_stack[0].operation_datastore.is_some());
+    });
+
+    let estimations = api
+        .estimate_gas(vec![GasEstimationInput {
+            op: OperationType::ExecuteSC {
+                data: vec![1, 2, 3],
+                max_gas: 10,
+                gas_price: Amount::from_str("0.01").unwrap(),
+                datastore: Default::default(),
+            },
+            sender_address,
+        }])
+        .await
+        .unwrap();
+    assert_eq!(estimations.len(), 1);
+    assert!(matches!(estimations[0].result, ReadOnlyResult::Ok));
+    assert_eq!(estimations[0].gas_cost, 1234);
+    assert_eq!(estimations[0].executed_at, Slot::new(1, 0));
+
+    drop(api);
+    responder.join().unwrap();
+}
+
+#[tokio::test]
+async fn test_estimate_gas_call_sc_with_coins() {
+    let api_settings = get_test_api_config();
+    let (api, execution_receiver, _pool_receiver) = get_public_api(api_settings);
+    let sender_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let target_addr = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let coins = Amount::from_str("100").unwrap();
+    // no function is called when the target function is empty, so no gas is spent
+    let responder = answer_readonly_requests(execution_receiver, 0, move |req| {
+        assert_eq!(req.max_gas, api_settings.max_gas_per_block);
+        assert!(matches!(
+            &req.target,
+            ReadOnlyExecutionTarget::FunctionCall { target_addr: addr, target_func, .. }
+                if addr == &target_addr && target_func.is_empty()
+        ));
+        // the coins are sent by the sender to the target
+        assert_eq!(req.call_stack.len(), 2);
+        assert_eq!(req.call_stack[0].address, sender_address);
+        assert_eq!(req.call_stack[0].coins, Amount::zero());
+        assert_eq!(req.call_stack[1].address, target_addr);
+        assert_eq!(req.call_stack[1].coins, coins);
+    });
+
+    let estimations = api
+        .estimate_gas(vec![GasEstimationInput {
+            op: OperationType::CallSC {
+                target_addr,
+                target_func: String::new(),
+                param: String::new(),
+                max_gas: 10,
+                coins,
+                gas_price: Amount::from_str("0.01").unwrap(),
+            },
+            sender_address,
+        }])
+        .await
+        .unwrap();
+    assert_eq!(estimations.len(), 1);
+    assert!(matches!(estimations[0].result, ReadOnlyResult::Ok));
+    assert_eq!(estimations[0].gas_cost, 0);
+
+    // operations that don't spend gas can't be estimated
+    assert!(api
+        .estimate_gas(vec![GasEstimationInput {
+            op: OperationType::Transaction {
+                recipient_address: target_addr,
+                amount: coins,
+            },
+            sender_address,
+        }])
+        .await
+        .is_err());
+
+    // the number of estimations is capped by the max argument count
+    let too_many = (0..=api_settings.max_arguments)
+        .map(|_| GasEstimationInput {
+            op: OperationType::ExecuteSC {
+                data: Vec::new(),
+                max_gas: 10,
+                gas_price: Amount::zero(),
+                datastore: Default::default(),
+            },
+            sender_address,
+        })
+        .collect();
+    assert!(api.estimate_gas(too_many).await.is_err());
+
+    drop(api);
+    responder.join().unwrap();
+}
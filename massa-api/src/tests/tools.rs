@@ -0,0 +1,70 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::{APIConfig, Public, API};
+use massa_consensus_exports::{ConsensusCommandSender, ConsensusConfig};
+use massa_execution_exports::test_exports::{
+    MockExecutionController, MockExecutionControllerMessage,
+};
+use massa_models::{node::NodeId, version::Version};
+use massa_network_exports::{NetworkCommandSender, NetworkConfig};
+use massa_pool_exports::test_exports::{MockPoolController, PoolEventReceiver};
+use massa_pos_exports::test_exports::MockSelectorController;
+use massa_protocol_exports::ProtocolCommandSender;
+use massa_signature::KeyPair;
+use massa_storage::Storage;
+use std::str::FromStr;
+use std::sync::mpsc::Receiver;
+use tokio::sync::mpsc;
+
+/// API settings with small page sizes, so that paging is easy to exercise
+pub fn get_test_api_config() -> APIConfig {
+    APIConfig {
+        draw_lookahead_period_count: 10,
+        bind_private: "127.0.0.1:0".parse().unwrap(),
+        bind_public: "127.0.0.1:0".parse().unwrap(),
+        enable_ws: false,
+        bind_ws: "127.0.0.1:0".parse().unwrap(),
+        max_subscriptions_per_session: 10,
+        max_arguments: 4,
+        max_page_size: 3,
+        max_events_page_size: 2,
+        max_datastore_value_length: 1_000_000,
+        max_op_datastore_entry_count: 128,
+        max_op_datastore_key_length: 255,
+        max_op_datastore_value_length: 1_000_000,
+        max_function_name_length: 256,
+        max_parameter_size: 1_000_000,
+        max_gas_per_block: 1_000_000_000,
+    }
+}
+
+/// Creates a public API on top of mocked execution and pool controllers,
+/// and returns it with the receivers of the messages of the mocks
+pub fn get_public_api(
+    api_settings: APIConfig,
+) -> (
+    API<Public>,
+    Receiver<MockExecutionControllerMessage>,
+    PoolEventReceiver,
+) {
+    let (execution_controller, execution_receiver) = MockExecutionController::new_with_receiver();
+    let (pool_controller, pool_receiver) = MockPoolController::new_with_receiver();
+    let (selector_controller, _) = MockSelectorController::new_with_receiver();
+    let api = API::<Public>::new(
+        ConsensusCommandSender(mpsc::channel(1).0),
+        execution_controller,
+        api_settings,
+        selector_controller,
+        ConsensusConfig::default(),
+        pool_controller,
+        ProtocolCommandSender(mpsc::channel(1).0),
+        NetworkConfig::default(),
+        Version::from_str("TEST.1.10").unwrap(),
+        NetworkCommandSender(mpsc::channel(1).0),
+        0,
+        NodeId(KeyPair::generate().get_public_key()),
+        Storage::create_root(),
+        None,
+    );
+    (api, execution_receiver, pool_receiver)
+}
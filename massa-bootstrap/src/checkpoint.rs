@@ -0,0 +1,126 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! On-disk checkpoint of a final state bootstrap in progress.
+//!
+//! The partially received final state is saved as a final state snapshot,
+//! along with the `AskFinalStatePart` message to send to resume the streaming from any bootstrap server.
+//! The cursor file starts with the hash of the snapshot it was saved with,
+//! so that a snapshot and a cursor written by different checkpoints are never used together.
+
+use crate::{
+    error::BootstrapError,
+    messages::{
+        BootstrapClientMessage, BootstrapClientMessageDeserializer,
+        BootstrapClientMessageSerializer,
+    },
+};
+use massa_final_state::{FinalState, FinalStateSnapshot};
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use parking_lot::RwLock;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Name of the file containing the partially received final state
+const STATE_FILE: &str = "final_state.snapshot";
+/// Name of the file containing the snapshot hash and the streaming cursors
+const CURSOR_FILE: &str = "cursor";
+
+/// Saves the partially received final state and the message resuming its streaming
+///
+/// # Arguments
+/// * `dir`: checkpoint directory
+/// * `snapshot`: snapshot of the partially received final state
+/// * `next_message`: `AskFinalStatePart` message to send to resume the streaming
+pub(crate) fn save_checkpoint(
+    dir: &Path,
+    snapshot: &FinalStateSnapshot,
+    next_message: &BootstrapClientMessage,
+) -> Result<(), BootstrapError> {
+    std::fs::create_dir_all(dir)?;
+
+    // write both files aside first, so that an interrupted save never leaves a truncated file
+    let state_tmp_path = dir.join(format!("{}.tmp", STATE_FILE));
+    let snapshot_hash = snapshot.write(&state_tmp_path)?;
+    let mut cursor = snapshot_hash.to_bytes().to_vec();
+    BootstrapClientMessageSerializer::new().serialize(next_message, &mut cursor)?;
+    let cursor_tmp_path = dir.join(format!("{}.tmp", CURSOR_FILE));
+    std::fs::write(&cursor_tmp_path, cursor)?;
+
+    std::fs::rename(state_tmp_path, dir.join(STATE_FILE))?;
+    std::fs::rename(cursor_tmp_path, dir.join(CURSOR_FILE))?;
+    Ok(())
+}
+
+/// Saves a checkpoint from a blocking task, without blocking the bootstrap runtime.
+/// The final state is only read-locked while its snapshot is taken, not while the files are written.
+pub(crate) async fn spawn_save_checkpoint(
+    dir: PathBuf,
+    final_state: Arc<RwLock<FinalState>>,
+    next_message: BootstrapClientMessage,
+) -> Result<(), BootstrapError> {
+    tokio::task::spawn_blocking(move || {
+        let snapshot = final_state.read().take_snapshot()?;
+        save_checkpoint(&dir, &snapshot, &next_message)
+    })
+    .await?
+}
+
+/// Loads a checkpoint into an empty final state
+///
+/// # Returns
+/// The `AskFinalStatePart` message resuming the streaming, or `None` if there is no checkpoint
+pub(crate) fn load_checkpoint(
+    dir: &Path,
+    final_state: &mut FinalState,
+    thread_count: u8,
+    max_datastore_key_length: u8,
+) -> Result<Option<BootstrapClientMessage>, BootstrapError> {
+    let state_path = dir.join(STATE_FILE);
+    let cursor_path = dir.join(CURSOR_FILE);
+    if !state_path.exists() || !cursor_path.exists() {
+        return Ok(None);
+    }
+
+    let cursor = std::fs::read(&cursor_path)?;
+    if cursor.len() < HASH_SIZE_BYTES {
+        return Err(BootstrapError::GeneralError(
+            "truncated bootstrap checkpoint cursor".into(),
+        ));
+    }
+    let snapshot_hash = Hash::from_bytes(cursor[..HASH_SIZE_BYTES].try_into().unwrap());
+    let (rest, message) =
+        BootstrapClientMessageDeserializer::new(thread_count, max_datastore_key_length)
+            .deserialize::<DeserializeError>(&cursor[HASH_SIZE_BYTES..])
+            .map_err(|err| {
+                BootstrapError::GeneralError(format!(
+                    "invalid bootstrap checkpoint cursor: {}",
+                    err
+                ))
+            })?;
+    if !rest.is_empty()
+        || !matches!(
+            message,
+            BootstrapClientMessage::AskFinalStatePart {
+                last_slot: Some(_),
+                ..
+            }
+        )
+    {
+        return Err(BootstrapError::GeneralError(
+            "invalid bootstrap checkpoint cursor".into(),
+        ));
+    }
+
+    // the snapshot hash is checked before modifying the final state
+    final_state.import_snapshot(&state_path, Some(snapshot_hash))?;
+    Ok(Some(message))
+}
+
+/// Removes the checkpoint, once the final state is fully received or cannot be resumed anymore
+pub(crate) fn remove_checkpoint(dir: &Path) -> Result<(), BootstrapError> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir)?;
+    }
+    Ok(())
+}
@@ -1,9 +1,9 @@
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use std::{net::SocketAddr, path::Path, sync::Arc, time::Duration};
 
-use massa_final_state::{ExecutedOpsStreamingStep, FinalState};
+use massa_final_state::{ExecutedOpsStreamingStep, FinalState, FinalStateCursor, StateChanges};
 use massa_ledger_exports::get_address_from_key;
 use massa_logging::massa_trace;
-use massa_models::version::Version;
+use massa_models::{slot::Slot, version::Version};
 use massa_pos_exports::PoSCycleStreamingStep;
 use massa_signature::PublicKey;
 use massa_time::MassaTime;
@@ -17,15 +17,44 @@ use tokio::time::sleep;
 use tracing::{debug, info, warn};
 
 use crate::{
+    checkpoint::{load_checkpoint, remove_checkpoint, spawn_save_checkpoint},
     client_binder::BootstrapClientBinder,
     error::BootstrapError,
     messages::{BootstrapClientMessage, BootstrapServerMessage},
+    parallel::parallel_get_state,
     BootstrapConfig, Establisher, GlobalBootstrapState,
 };
 
+/// Applies the final state changes of a slot received during the bootstrap
+pub(crate) fn apply_final_state_changes(
+    final_state: &mut FinalState,
+    slot: Slot,
+    changes: &StateChanges,
+) -> Result<(), BootstrapError> {
+    final_state
+        .ledger
+        .apply_changes(changes.ledger_changes.clone(), slot);
+    final_state
+        .async_pool
+        .apply_changes_unchecked(&changes.async_pool_changes);
+    if !changes.pos_changes.is_empty() {
+        final_state
+            .pos_state
+            .apply_changes(changes.pos_changes.clone(), slot, false)?;
+    }
+    if !changes.executed_ops.is_empty() {
+        final_state
+            .executed_ops
+            .extend(changes.executed_ops.clone());
+    }
+    Ok(())
+}
+
 /// This function will send the starting point to receive a stream of the ledger and will receive and process each part until receive a `BootstrapServerMessage::FinalStateFinished` message from the server.
 /// `next_bootstrap_message` passed as parameter must be `BootstrapClientMessage::AskFinalStatePart` enum variant.
 /// `next_bootstrap_message` will be updated after receiving each part so that in case of connection lost we can restart from the last message we processed.
+/// If checkpoints are enabled, the received state and `next_bootstrap_message` are also regularly saved to disk
+/// so that a later bootstrap can resume from them, even after a restart of the node.
 async fn stream_final_state(
     cfg: &BootstrapConfig,
     client: &mut BootstrapClientBinder,
@@ -47,6 +76,7 @@ async fn stream_final_state(
             Ok(Err(e)) => Err(e),
             Ok(Ok(_)) => Ok(()),
         }?;
+        let mut last_checkpoint = MassaTime::now(0)?;
         loop {
             let msg = match tokio::time::timeout(cfg.read_timeout.into(), client.next()).await {
                 Err(_) => {
@@ -69,58 +99,61 @@ async fn stream_final_state(
                     slot,
                     final_state_changes,
                 } => {
-                    let mut write_final_state = global_bootstrap_state.final_state.write();
-                    let last_key = write_final_state.ledger.set_ledger_part(ledger_data)?;
-                    let last_last_async_id = write_final_state
-                        .async_pool
-                        .set_pool_part(async_pool_part.as_bytes())?;
-                    let last_cycle_step = write_final_state
-                        .pos_state
-                        .set_cycle_history_part(pos_cycle_part.as_bytes())?;
-                    let last_credits_slot = write_final_state
-                        .pos_state
-                        .set_deferred_credits_part(pos_credits_part.as_bytes())?;
-                    let last_exec_ops_step = write_final_state
-                        .executed_ops
-                        .set_executed_ops_part(exec_ops_part.as_bytes(), cfg.thread_count)?;
-                    for (changes_slot, changes) in final_state_changes.iter() {
-                        write_final_state
-                            .ledger
-                            .apply_changes(changes.ledger_changes.clone(), *changes_slot);
-                        write_final_state
+                    // the final state is only locked while the part is applied
+                    {
+                        let mut write_final_state = global_bootstrap_state.final_state.write();
+                        let last_key = write_final_state.ledger.set_ledger_part(ledger_data)?;
+                        let last_last_async_id = write_final_state
                             .async_pool
-                            .apply_changes_unchecked(&changes.async_pool_changes);
-                        if !changes.pos_changes.is_empty() {
-                            write_final_state.pos_state.apply_changes(
-                                changes.pos_changes.clone(),
+                            .set_pool_part(async_pool_part.as_bytes())?;
+                        let last_cycle_step = write_final_state
+                            .pos_state
+                            .set_cycle_history_part(pos_cycle_part.as_bytes())?;
+                        let last_credits_slot = write_final_state
+                            .pos_state
+                            .set_deferred_credits_part(pos_credits_part.as_bytes())?;
+                        let last_exec_ops_step = write_final_state
+                            .executed_ops
+                            .set_executed_ops_part(exec_ops_part.as_bytes(), cfg.thread_count)?;
+                        for (changes_slot, changes) in final_state_changes.iter() {
+                            apply_final_state_changes(
+                                &mut write_final_state,
                                 *changes_slot,
-                                false,
+                                changes,
                             )?;
                         }
-                        if !changes.executed_ops.is_empty() {
-                            write_final_state
-                                .executed_ops
-                                .extend(changes.executed_ops.clone());
+                        write_final_state.slot = slot;
+                        if let BootstrapClientMessage::AskFinalStatePart {
+                            last_key: old_key,
+                            last_async_message_id: old_message_id,
+                            ..
+                        } = &next_bootstrap_message
+                        {
+                            debug!("Received ledger batch from {:#?} to {:#?}, an async pool batch from {:#?} to {:#?} a batch of ledger changes of size {:#?} and a batch of async pool changes of size {:#?}. for slot: {:#?}", old_key.clone().map(|key| get_address_from_key(&key)), last_key.clone().map(|key| get_address_from_key(&key)), old_message_id, last_last_async_id, final_state_changes.iter().map(|(_, elem)| elem.ledger_changes.0.len()).sum::<usize>(), final_state_changes.iter().map(|(_, elem)| elem.async_pool_changes.0.len()).sum::<usize>(), slot);
                         }
+                        // Set new message in case of disconnection
+                        *next_bootstrap_message = BootstrapClientMessage::AskFinalStatePart {
+                            last_slot: Some(slot),
+                            last_key,
+                            last_async_message_id: last_last_async_id,
+                            last_cycle_step,
+                            last_credits_slot,
+                            last_exec_ops_step,
+                        };
                     }
-                    write_final_state.slot = slot;
-                    if let BootstrapClientMessage::AskFinalStatePart {
-                        last_key: old_key,
-                        last_async_message_id: old_message_id,
-                        ..
-                    } = &next_bootstrap_message
-                    {
-                        debug!("Received ledger batch from {:#?} to {:#?}, an async pool batch from {:#?} to {:#?} a batch of ledger changes of size {:#?} and a batch of async pool changes of size {:#?}. for slot: {:#?}", old_key.clone().map(|key| get_address_from_key(&key)), last_key.clone().map(|key| get_address_from_key(&key)), old_message_id, last_last_async_id, final_state_changes.iter().map(|(_, elem)| elem.ledger_changes.0.len()).sum::<usize>(), final_state_changes.iter().map(|(_, elem)| elem.async_pool_changes.0.len()).sum::<usize>(), slot);
+                    // Save a checkpoint from time to time
+                    if let Some(checkpoint_path) = &cfg.checkpoint_path {
+                        let now = MassaTime::now(0)?;
+                        if now.saturating_sub(last_checkpoint) >= cfg.checkpoint_interval {
+                            spawn_save_checkpoint(
+                                checkpoint_path.clone(),
+                                global_bootstrap_state.final_state.clone(),
+                                next_bootstrap_message.clone(),
+                            )
+                            .await?;
+                            last_checkpoint = now;
+                        }
                     }
-                    // Set new message in case of disconnection
-                    *next_bootstrap_message = BootstrapClientMessage::AskFinalStatePart {
-                        last_slot: Some(slot),
-                        last_key,
-                        last_async_message_id: last_last_async_id,
-                        last_cycle_step,
-                        last_credits_slot,
-                        last_exec_ops_step,
-                    };
                 }
                 BootstrapServerMessage::FinalStateFinished => {
                     info!("State bootstrap complete");
@@ -128,12 +161,20 @@ async fn stream_final_state(
                     let mut write_final_state = global_bootstrap_state.final_state.write();
                     let slot = write_final_state.slot;
                     write_final_state.executed_ops.prune(slot);
+                    // The checkpoint is not needed anymore
+                    if let Some(checkpoint_path) = &cfg.checkpoint_path {
+                        remove_checkpoint(checkpoint_path)?;
+                    }
                     // Set next bootstrap message
                     *next_bootstrap_message = BootstrapClientMessage::AskBootstrapPeers;
                     return Ok(());
                 }
                 BootstrapServerMessage::SlotTooOld => {
                     info!("Slot is too old retry bootstrap from scratch");
+                    // The checkpoint cannot be resumed anymore
+                    if let Some(checkpoint_path) = &cfg.checkpoint_path {
+                        remove_checkpoint(checkpoint_path)?;
+                    }
                     *next_bootstrap_message = BootstrapClientMessage::AskFinalStatePart {
                         last_slot: None,
                         last_key: None,
@@ -169,7 +210,20 @@ async fn bootstrap_from_server(
     our_version: Version,
 ) -> Result<(), BootstrapError> {
     massa_trace!("bootstrap.lib.bootstrap_from_server", {});
+    global_bootstrap_state.compensation_millis = sync_with_server(cfg, client, our_version).await?;
+    ask_bootstrap_data(cfg, client, next_bootstrap_message, global_bootstrap_state).await
+}
 
+/// Performs the handshake with a bootstrap server and synchronizes the clocks
+/// needs to be CANCELLABLE
+///
+/// # Returns
+/// The clock compensation with the server in milliseconds
+pub(crate) async fn sync_with_server(
+    cfg: &BootstrapConfig,
+    client: &mut BootstrapClientBinder,
+    our_version: Version,
+) -> Result<i64, BootstrapError> {
     // read error (if sent by the server)
     // client.next() is not cancel-safe but we drop the whole client object if cancelled => it's OK
     match tokio::time::timeout(cfg.read_error_timeout.into(), client.next()).await {
@@ -269,8 +323,33 @@ async fn bootstrap_from_server(
         0
     };
 
-    global_bootstrap_state.compensation_millis = compensation_millis;
+    // prove our identity, servers that do not require client authentication ignore it
+    if let Some(keypair) = &cfg.client_keypair {
+        // client.authenticate() is not cancel-safe but we drop the whole client object if cancelled => it's OK
+        match tokio::time::timeout(cfg.write_timeout.into(), client.authenticate(keypair)).await {
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "bootstrap authentication send timed out",
+                )
+                .into())
+            }
+            Ok(Err(e)) => return Err(e),
+            Ok(Ok(_)) => {}
+        }
+    }
 
+    Ok(compensation_millis)
+}
+
+/// Asks the bootstrap data to a server, starting from `next_bootstrap_message`, until the bootstrap succeeds
+/// needs to be CANCELLABLE
+pub(crate) async fn ask_bootstrap_data(
+    cfg: &BootstrapConfig,
+    client: &mut BootstrapClientBinder,
+    next_bootstrap_message: &mut BootstrapClientMessage,
+    global_bootstrap_state: &mut GlobalBootstrapState,
+) -> Result<(), BootstrapError> {
     let write_timeout: std::time::Duration = cfg.write_timeout.into();
     // Loop to ask data to the server depending on the last message we sent
     loop {
@@ -341,13 +420,21 @@ async fn bootstrap_from_server(
             BootstrapClientMessage::BootstrapError { error: _ } => {
                 panic!("The next message to send shouldn't be BootstrapError");
             }
+            BootstrapClientMessage::Authenticate { .. } => {
+                panic!("The next message to send shouldn't be Authenticate");
+            }
+            BootstrapClientMessage::AskLedgerRangePart { .. }
+            | BootstrapClientMessage::AskStateWithoutLedgerPart { .. }
+            | BootstrapClientMessage::AskFinalStateChanges { .. } => {
+                panic!("The parallel bootstrap messages are not sent in sequence");
+            }
         };
     }
     info!("Successful bootstrap");
     Ok(())
 }
 
-async fn send_client_message(
+pub(crate) async fn send_client_message(
     message_to_send: &BootstrapClientMessage,
     client: &mut BootstrapClientBinder,
     write_timeout: Duration,
@@ -366,7 +453,7 @@ async fn send_client_message(
     }
 }
 
-async fn connect_to_server(
+pub(crate) async fn connect_to_server(
     establisher: &mut Establisher,
     bootstrap_config: &BootstrapConfig,
     addr: &SocketAddr,
@@ -405,8 +492,68 @@ async fn connect_to_server(
     ))
 }
 
+/// Loads the bootstrap checkpoint saved by a previous attempt, if any.
+/// A checkpoint that cannot be loaded is discarded and the bootstrap starts over.
+fn resume_checkpoint(
+    bootstrap_config: &BootstrapConfig,
+    checkpoint_path: &Path,
+    final_state: &Arc<RwLock<FinalState>>,
+) -> Result<Option<BootstrapClientMessage>, BootstrapError> {
+    match load_checkpoint(
+        checkpoint_path,
+        &mut final_state.write(),
+        bootstrap_config.thread_count,
+        bootstrap_config.max_datastore_key_length,
+    ) {
+        Ok(Some(message)) => {
+            info!(
+                "Resuming bootstrap from the checkpoint at slot {}",
+                final_state.read().slot
+            );
+            Ok(Some(message))
+        }
+        Ok(None) => Ok(None),
+        Err(err) => {
+            warn!("Discarding the bootstrap checkpoint: {}", err);
+            remove_checkpoint(checkpoint_path)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Saves a checkpoint after a failed attempt if the final state streaming was started,
+/// so that it is not lost if the node is stopped before the next attempt.
+async fn checkpoint_after_failure(
+    bootstrap_config: &BootstrapConfig,
+    next_bootstrap_message: &BootstrapClientMessage,
+    global_bootstrap_state: &GlobalBootstrapState,
+) {
+    if let (
+        Some(checkpoint_path),
+        BootstrapClientMessage::AskFinalStatePart {
+            last_slot: Some(_), ..
+        },
+    ) = (&bootstrap_config.checkpoint_path, next_bootstrap_message)
+    {
+        if let Err(err) = spawn_save_checkpoint(
+            checkpoint_path.clone(),
+            global_bootstrap_state.final_state.clone(),
+            next_bootstrap_message.clone(),
+        )
+        .await
+        {
+            warn!("Could not save the bootstrap checkpoint: {}", err);
+        }
+    }
+}
+
 /// Gets the state from a bootstrap server
 /// needs to be CANCELLABLE
+///
+/// If the final state was imported from a snapshot, `snapshot_cursor` is the cursor reached by the import
+/// and only the remaining parts and the changes since the snapshot slot are streamed.
+/// Otherwise, if a bootstrap checkpoint was saved by a previous attempt, the bootstrap resumes from it.
+/// Otherwise, if parallel bootstrap is enabled, the final state is streamed from several servers at once.
 pub async fn get_state(
     bootstrap_config: &BootstrapConfig,
     final_state: Arc<RwLock<FinalState>>,
@@ -414,14 +561,15 @@ pub async fn get_state(
     version: Version,
     genesis_timestamp: MassaTime,
     end_timestamp: Option<MassaTime>,
+    snapshot_cursor: Option<FinalStateCursor>,
 ) -> Result<GlobalBootstrapState, BootstrapError> {
     massa_trace!("bootstrap.lib.get_state", {});
     let now = MassaTime::now(0)?;
     // if we are before genesis, do not bootstrap
     if now < genesis_timestamp {
         massa_trace!("bootstrap.lib.get_state.init_from_scratch", {});
-        // init final state
-        {
+        // init final state, unless it was imported from a snapshot
+        if snapshot_cursor.is_none() {
             let mut final_state_guard = final_state.write();
             // load ledger from initial ledger file
             final_state_guard
@@ -444,16 +592,51 @@ pub async fn get_state(
     }
     let mut shuffled_list = bootstrap_config.bootstrap_list.clone();
     shuffled_list.shuffle(&mut StdRng::from_entropy());
+    // resume from the imported snapshot if any
+    let last_slot = snapshot_cursor.as_ref().map(|_| final_state.read().slot);
+    // otherwise resume from the checkpoint if any
+    let checkpoint_message = match (&snapshot_cursor, &bootstrap_config.checkpoint_path) {
+        (None, Some(checkpoint_path)) => {
+            resume_checkpoint(bootstrap_config, checkpoint_path, &final_state)?
+        }
+        _ => None,
+    };
+    let cursor = snapshot_cursor.unwrap_or_default();
     let mut next_bootstrap_message: BootstrapClientMessage =
-        BootstrapClientMessage::AskFinalStatePart {
-            last_slot: None,
-            last_key: None,
-            last_async_message_id: None,
-            last_cycle_step: PoSCycleStreamingStep::Started,
-            last_credits_slot: None,
-            last_exec_ops_step: ExecutedOpsStreamingStep::Started,
-        };
+        checkpoint_message.unwrap_or(BootstrapClientMessage::AskFinalStatePart {
+            last_slot,
+            last_key: cursor.last_key,
+            last_async_message_id: cursor.last_async_message_id,
+            last_cycle_step: cursor.last_cycle_step,
+            last_credits_slot: cursor.last_credits_slot,
+            last_exec_ops_step: cursor.last_exec_ops_step,
+        });
     let mut global_bootstrap_state = GlobalBootstrapState::new(final_state.clone());
+    // stream the final state from several servers at once, unless resuming a partially received one
+    let parallel_count = bootstrap_config
+        .parallel_bootstrap_servers
+        .min(shuffled_list.len());
+    if parallel_count > 1
+        && matches!(
+            next_bootstrap_message,
+            BootstrapClientMessage::AskFinalStatePart {
+                last_slot: None,
+                ..
+            }
+        )
+    {
+        info!("Bootstrapping from {} servers in parallel", parallel_count);
+        return parallel_get_state(
+            bootstrap_config,
+            &mut establisher,
+            &shuffled_list,
+            parallel_count,
+            global_bootstrap_state,
+            version,
+            end_timestamp,
+        )
+        .await;
+    }
     loop {
         for (addr, pub_key) in shuffled_list.iter() {
             if let Some(end) = end_timestamp {
@@ -467,9 +650,13 @@ pub async fn get_state(
                     match bootstrap_from_server(bootstrap_config, &mut client, &mut next_bootstrap_message, &mut global_bootstrap_state,version)
                     .await  // cancellable
                     {
-                        Err(BootstrapError::ReceivedError(error)) => warn!("Error received from bootstrap server: {}", error),
+                        Err(BootstrapError::ReceivedError(error)) => {
+                            warn!("Error received from bootstrap server: {}", error);
+                            checkpoint_after_failure(bootstrap_config, &next_bootstrap_message, &global_bootstrap_state).await;
+                        }
                         Err(e) => {
                             warn!("Error while bootstrapping: {}", e);
+                            checkpoint_after_failure(bootstrap_config, &next_bootstrap_message, &global_bootstrap_state).await;
                             // We allow unused result because we don't care if an error is thrown when sending the error message to the server we will close the socket anyway.
                             let _ = tokio::time::timeout(bootstrap_config.write_error_timeout.into(), client.send(&BootstrapClientMessage::BootstrapError { error: e.to_string() })).await;
                         }
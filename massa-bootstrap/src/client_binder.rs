@@ -12,7 +12,7 @@ use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_models::serialization::{DeserializeMinBEInt, SerializeMinBEInt};
 use massa_models::version::{Version, VersionSerializer};
 use massa_serialization::{DeserializeError, Deserializer, Serializer};
-use massa_signature::{PublicKey, Signature, SIGNATURE_SIZE_BYTES};
+use massa_signature::{KeyPair, PublicKey, Signature, SIGNATURE_SIZE_BYTES};
 use rand::{rngs::StdRng, RngCore, SeedableRng};
 use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWriteExt;
@@ -134,6 +134,21 @@ impl BootstrapClientBinder {
         Ok(())
     }
 
+    /// Proves that we own `keypair` by signing the hash of the session so far.
+    /// That hash covers the random bytes of our handshake so the signature cannot be replayed in another session.
+    /// Should be called right after receiving `BootstrapServerMessage::BootstrapTime`
+    /// NOT cancel-safe
+    pub async fn authenticate(&mut self, keypair: &KeyPair) -> Result<(), BootstrapError> {
+        let session_hash = self.prev_message.ok_or_else(|| {
+            BootstrapError::GeneralError("cannot authenticate before the handshake".to_string())
+        })?;
+        let message = BootstrapClientMessage::Authenticate {
+            public_key: keypair.get_public_key(),
+            signature: keypair.sign(&session_hash)?,
+        };
+        self.send(&message).await
+    }
+
     /// Reads the next message. NOT cancel-safe
     pub async fn next(&mut self) -> Result<BootstrapServerMessage, BootstrapError> {
         // read signature
@@ -9,6 +9,7 @@ use massa_network_exports::NetworkError;
 use massa_pos_exports::PosError;
 use massa_serialization::SerializeError;
 use massa_time::TimeError;
+use std::net::SocketAddr;
 use thiserror::Error;
 
 #[non_exhaustive]
@@ -50,4 +51,8 @@ pub enum BootstrapError {
     IncompatibleVersionError(String),
     /// Received error: {0}
     ReceivedError(String),
+    /// client authentication failed: {0}
+    ClientAuthenticationError(String),
+    /// the received final state does not match the one of bootstrap server {0}
+    FinalStateHashMismatch(SocketAddr),
 }
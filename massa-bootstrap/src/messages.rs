@@ -9,10 +9,15 @@ use massa_final_state::{
 use massa_graph::{
     BootstrapableGraph, BootstrapableGraphDeserializer, BootstrapableGraphSerializer,
 };
-use massa_ledger_exports::{KeyDeserializer, KeySerializer};
+use massa_hash::{Hash, HashDeserializer, HashSerializer};
+use massa_ledger_exports::{
+    KeyDeserializer, KeySerializer, LedgerChanges, LedgerChangesDeserializer,
+    LedgerChangesSerializer,
+};
 use massa_models::serialization::{VecU8Deserializer, VecU8Serializer};
 use massa_models::slot::SlotDeserializer;
 use massa_models::{
+    address::Address,
     slot::Slot,
     slot::SlotSerializer,
     version::{Version, VersionDeserializer, VersionSerializer},
@@ -25,6 +30,7 @@ use massa_serialization::{
     Deserializer, OptionDeserializer, OptionSerializer, SerializeError, Serializer,
     U32VarIntDeserializer, U32VarIntSerializer,
 };
+use massa_signature::{PublicKey, PublicKeyDeserializer, Signature, SignatureDeserializer};
 use massa_time::{MassaTime, MassaTimeDeserializer, MassaTimeSerializer};
 use nom::error::context;
 use nom::multi::{length_count, length_data};
@@ -85,6 +91,24 @@ pub enum BootstrapServerMessage {
         /// Error message
         error: String,
     },
+    /// Part of a range of the ledger, used by the parallel bootstrap
+    LedgerRangePart {
+        /// Part of the range of the execution ledger sent in a serialized way
+        ledger_data: Vec<u8>,
+        /// Slot the ledger changes are attached to
+        slot: Slot,
+        /// Ledger changes of the addresses of the range already streamed, from the slot of the client message until the actual slot
+        ledger_changes: Vec<(Slot, LedgerChanges)>,
+    },
+    /// Final state changes and final state hash, used by the parallel bootstrap
+    FinalStateChanges {
+        /// Slot the final state is attached to
+        slot: Slot,
+        /// Hash of the whole final state at `slot`
+        state_hash: Hash,
+        /// Final state changes from the slot of the client message until `slot`
+        changes: Vec<(Slot, StateChanges)>,
+    },
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -97,6 +121,8 @@ enum MessageServerTypeId {
     FinalStateFinished = 4u32,
     SlotTooOld = 5u32,
     BootstrapError = 6u32,
+    LedgerRangePart = 7u32,
+    FinalStateChanges = 8u32,
 }
 
 /// Serializer for `BootstrapServerMessage`
@@ -106,9 +132,11 @@ pub struct BootstrapServerMessageSerializer {
     version_serializer: VersionSerializer,
     peers_serializer: BootstrapPeersSerializer,
     state_changes_serializer: StateChangesSerializer,
+    ledger_changes_serializer: LedgerChangesSerializer,
     bootstrapable_graph_serializer: BootstrapableGraphSerializer,
     vec_u8_serializer: VecU8Serializer,
     slot_serializer: SlotSerializer,
+    hash_serializer: HashSerializer,
 }
 
 impl Default for BootstrapServerMessageSerializer {
@@ -126,9 +154,11 @@ impl BootstrapServerMessageSerializer {
             version_serializer: VersionSerializer::new(),
             peers_serializer: BootstrapPeersSerializer::new(),
             state_changes_serializer: StateChangesSerializer::new(),
+            ledger_changes_serializer: LedgerChangesSerializer::new(),
             bootstrapable_graph_serializer: BootstrapableGraphSerializer::new(),
             vec_u8_serializer: VecU8Serializer::new(),
             slot_serializer: SlotSerializer::new(),
+            hash_serializer: HashSerializer::new(),
         }
     }
 }
@@ -220,6 +250,39 @@ impl Serializer<BootstrapServerMessage> for BootstrapServerMessageSerializer {
                 )?;
                 buffer.extend(error.as_bytes())
             }
+            BootstrapServerMessage::LedgerRangePart {
+                ledger_data,
+                slot,
+                ledger_changes,
+            } => {
+                self.u32_serializer
+                    .serialize(&u32::from(MessageServerTypeId::LedgerRangePart), buffer)?;
+                self.vec_u8_serializer.serialize(ledger_data, buffer)?;
+                self.slot_serializer.serialize(slot, buffer)?;
+                self.u32_serializer
+                    .serialize(&(ledger_changes.len() as u32), buffer)?;
+                for (slot, changes) in ledger_changes {
+                    self.slot_serializer.serialize(slot, buffer)?;
+                    self.ledger_changes_serializer.serialize(changes, buffer)?;
+                }
+            }
+            BootstrapServerMessage::FinalStateChanges {
+                slot,
+                state_hash,
+                changes,
+            } => {
+                self.u32_serializer
+                    .serialize(&u32::from(MessageServerTypeId::FinalStateChanges), buffer)?;
+                self.slot_serializer.serialize(slot, buffer)?;
+                self.hash_serializer.serialize(state_hash, buffer)?;
+                self.u32_serializer
+                    .serialize(&(changes.len() as u32), buffer)?;
+                for (slot, state_changes) in changes {
+                    self.slot_serializer.serialize(slot, buffer)?;
+                    self.state_changes_serializer
+                        .serialize(state_changes, buffer)?;
+                }
+            }
         }
         Ok(())
     }
@@ -233,6 +296,8 @@ pub struct BootstrapServerMessageDeserializer {
     peers_deserializer: BootstrapPeersDeserializer,
     length_state_changes: U32VarIntDeserializer,
     state_changes_deserializer: StateChangesDeserializer,
+    ledger_changes_deserializer: LedgerChangesDeserializer,
+    hash_deserializer: HashDeserializer,
     bootstrapable_graph_deserializer: BootstrapableGraphDeserializer,
     final_state_parts_deserializer: VecU8Deserializer,
     length_bootstrap_error: U32VarIntDeserializer,
@@ -284,6 +349,13 @@ impl BootstrapServerMessageDeserializer {
                 Included(0),
                 Included(max_changes_slot_count),
             ),
+            ledger_changes_deserializer: LedgerChangesDeserializer::new(
+                max_ledger_changes_count,
+                max_datastore_key_length,
+                max_datastore_value_length,
+                max_datastore_entry_count,
+            ),
+            hash_deserializer: HashDeserializer::new(),
             bootstrapable_graph_deserializer: BootstrapableGraphDeserializer::new(
                 thread_count,
                 endorsement_count,
@@ -455,6 +527,62 @@ impl Deserializer<BootstrapServerMessage> for BootstrapServerMessageDeserializer
                     error: String::from_utf8_lossy(error).into_owned(),
                 })
                 .parse(input),
+                MessageServerTypeId::LedgerRangePart => tuple((
+                    context("Failed ledger_data deserialization", |input| {
+                        self.final_state_parts_deserializer.deserialize(input)
+                    }),
+                    context("Failed slot deserialization", |input| {
+                        self.slot_deserializer.deserialize(input)
+                    }),
+                    context(
+                        "Failed ledger_changes deserialization",
+                        length_count(
+                            context("Failed length deserialization", |input| {
+                                self.length_state_changes.deserialize(input)
+                            }),
+                            tuple((
+                                |input| self.slot_deserializer.deserialize(input),
+                                |input| self.ledger_changes_deserializer.deserialize(input),
+                            )),
+                        ),
+                    ),
+                ))
+                .map(|(ledger_data, slot, ledger_changes)| {
+                    BootstrapServerMessage::LedgerRangePart {
+                        ledger_data,
+                        slot,
+                        ledger_changes,
+                    }
+                })
+                .parse(input),
+                MessageServerTypeId::FinalStateChanges => tuple((
+                    context("Failed slot deserialization", |input| {
+                        self.slot_deserializer.deserialize(input)
+                    }),
+                    context("Failed state_hash deserialization", |input| {
+                        self.hash_deserializer.deserialize(input)
+                    }),
+                    context(
+                        "Failed changes deserialization",
+                        length_count(
+                            context("Failed length deserialization", |input| {
+                                self.length_state_changes.deserialize(input)
+                            }),
+                            tuple((
+                                |input| self.slot_deserializer.deserialize(input),
+                                |input| self.state_changes_deserializer.deserialize(input),
+                            )),
+                        ),
+                    ),
+                ))
+                .map(
+                    |(slot, state_hash, changes)| BootstrapServerMessage::FinalStateChanges {
+                        slot,
+                        state_hash,
+                        changes,
+                    },
+                )
+                .parse(input),
             }
         })
         .parse(buffer)
@@ -490,6 +618,42 @@ pub enum BootstrapClientMessage {
     },
     /// Bootstrap succeed
     BootstrapSuccess,
+    /// Ask for a part of a range of the ledger, used by the parallel bootstrap
+    AskLedgerRangePart {
+        /// Slot we are attached to for the changes of the range
+        last_slot: Option<Slot>,
+        /// First address of the range
+        first_address: Address,
+        /// Address following the range, or `None` if the range goes until the end of the ledger
+        end_address: Option<Address>,
+        /// Last key of the range we received from the server
+        last_key: Option<Vec<u8>>,
+    },
+    /// Ask for a part of the final state without the ledger, used by the parallel bootstrap
+    AskStateWithoutLedgerPart {
+        /// Slot we are attached to for changes
+        last_slot: Option<Slot>,
+        /// Last async message id  of the async message pool we received from the server
+        last_async_message_id: Option<AsyncMessageId>,
+        /// Last received Proof of Stake cycle
+        last_cycle_step: PoSCycleStreamingStep,
+        /// Last receive Proof of Stake credits slot
+        last_credits_slot: Option<Slot>,
+        /// Last executed operations streaming step
+        last_exec_ops_step: ExecutedOpsStreamingStep,
+    },
+    /// Ask for the final state changes since a slot and for the final state hash, used by the parallel bootstrap
+    AskFinalStateChanges {
+        /// Slot we are attached to for changes
+        last_slot: Slot,
+    },
+    /// Proves the identity of the client to a server requiring client authentication
+    Authenticate {
+        /// Public key of the client
+        public_key: PublicKey,
+        /// Signature of the hash of the session so far, see `BootstrapClientBinder::authenticate`
+        signature: Signature,
+    },
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -500,6 +664,10 @@ enum MessageClientTypeId {
     AskFinalStatePart = 2u32,
     BootstrapError = 3u32,
     BootstrapSuccess = 4u32,
+    AskLedgerRangePart = 5u32,
+    AskStateWithoutLedgerPart = 6u32,
+    AskFinalStateChanges = 7u32,
+    Authenticate = 8u32,
 }
 
 /// Serializer for `BootstrapClientMessage`
@@ -511,6 +679,10 @@ pub struct BootstrapClientMessageSerializer {
     cycle_step_serializer: PoSCycleStreamingStepSerializer,
     opt_slot_serializer: OptionSerializer<Slot, SlotSerializer>,
     exec_ops_step_serializer: ExecutedOpsStreamingStepSerializer,
+    hash_serializer: HashSerializer,
+    opt_hash_serializer: OptionSerializer<Hash, HashSerializer>,
+    opt_key_serializer: OptionSerializer<Vec<u8>, KeySerializer>,
+    opt_async_message_id_serializer: OptionSerializer<AsyncMessageId, AsyncMessageIdSerializer>,
 }
 
 impl BootstrapClientMessageSerializer {
@@ -524,6 +696,10 @@ impl BootstrapClientMessageSerializer {
             cycle_step_serializer: PoSCycleStreamingStepSerializer::new(),
             opt_slot_serializer: OptionSerializer::new(SlotSerializer::new()),
             exec_ops_step_serializer: ExecutedOpsStreamingStepSerializer::new(),
+            hash_serializer: HashSerializer::new(),
+            opt_hash_serializer: OptionSerializer::new(HashSerializer::new()),
+            opt_key_serializer: OptionSerializer::new(KeySerializer::new()),
+            opt_async_message_id_serializer: OptionSerializer::new(AsyncMessageIdSerializer::new()),
         }
     }
 }
@@ -597,6 +773,57 @@ impl Serializer<BootstrapClientMessage> for BootstrapClientMessageSerializer {
                 self.u32_serializer
                     .serialize(&u32::from(MessageClientTypeId::BootstrapSuccess), buffer)?;
             }
+            BootstrapClientMessage::AskLedgerRangePart {
+                last_slot,
+                first_address,
+                end_address,
+                last_key,
+            } => {
+                self.u32_serializer
+                    .serialize(&u32::from(MessageClientTypeId::AskLedgerRangePart), buffer)?;
+                self.opt_slot_serializer.serialize(last_slot, buffer)?;
+                self.hash_serializer.serialize(&first_address.0, buffer)?;
+                self.opt_hash_serializer
+                    .serialize(&end_address.map(|address| address.0), buffer)?;
+                self.opt_key_serializer.serialize(last_key, buffer)?;
+            }
+            BootstrapClientMessage::AskStateWithoutLedgerPart {
+                last_slot,
+                last_async_message_id,
+                last_cycle_step,
+                last_credits_slot,
+                last_exec_ops_step,
+            } => {
+                self.u32_serializer.serialize(
+                    &u32::from(MessageClientTypeId::AskStateWithoutLedgerPart),
+                    buffer,
+                )?;
+                self.opt_slot_serializer.serialize(last_slot, buffer)?;
+                self.opt_async_message_id_serializer
+                    .serialize(last_async_message_id, buffer)?;
+                self.cycle_step_serializer
+                    .serialize(last_cycle_step, buffer)?;
+                self.opt_slot_serializer
+                    .serialize(last_credits_slot, buffer)?;
+                self.exec_ops_step_serializer
+                    .serialize(last_exec_ops_step, buffer)?;
+            }
+            BootstrapClientMessage::AskFinalStateChanges { last_slot } => {
+                self.u32_serializer.serialize(
+                    &u32::from(MessageClientTypeId::AskFinalStateChanges),
+                    buffer,
+                )?;
+                self.slot_serializer.serialize(last_slot, buffer)?;
+            }
+            BootstrapClientMessage::Authenticate {
+                public_key,
+                signature,
+            } => {
+                self.u32_serializer
+                    .serialize(&u32::from(MessageClientTypeId::Authenticate), buffer)?;
+                buffer.extend(public_key.to_bytes());
+                buffer.extend(signature.to_bytes());
+            }
         }
         Ok(())
     }
@@ -612,6 +839,13 @@ pub struct BootstrapClientMessageDeserializer {
     cycle_step_deserializer: PoSCycleStreamingStepDeserializer,
     opt_slot_deserializer: OptionDeserializer<Slot, SlotDeserializer>,
     exec_ops_step_serializer: ExecutedOpsStreamingStepDeserializer,
+    hash_deserializer: HashDeserializer,
+    opt_hash_deserializer: OptionDeserializer<Hash, HashDeserializer>,
+    opt_key_deserializer: OptionDeserializer<Vec<u8>, KeyDeserializer>,
+    opt_async_message_id_deserializer:
+        OptionDeserializer<AsyncMessageId, AsyncMessageIdDeserializer>,
+    public_key_deserializer: PublicKeyDeserializer,
+    signature_deserializer: SignatureDeserializer,
 }
 
 impl BootstrapClientMessageDeserializer {
@@ -632,6 +866,16 @@ impl BootstrapClientMessageDeserializer {
                 (Included(0), Excluded(thread_count)),
             )),
             exec_ops_step_serializer: ExecutedOpsStreamingStepDeserializer::new(),
+            hash_deserializer: HashDeserializer::new(),
+            opt_hash_deserializer: OptionDeserializer::new(HashDeserializer::new()),
+            opt_key_deserializer: OptionDeserializer::new(KeyDeserializer::new(
+                max_datastore_key_length,
+            )),
+            opt_async_message_id_deserializer: OptionDeserializer::new(
+                AsyncMessageIdDeserializer::new(thread_count),
+            ),
+            public_key_deserializer: PublicKeyDeserializer::new(),
+            signature_deserializer: SignatureDeserializer::new(),
         }
     }
 }
@@ -750,6 +994,86 @@ impl Deserializer<BootstrapClientMessage> for BootstrapClientMessageDeserializer
                 MessageClientTypeId::BootstrapSuccess => {
                     Ok((input, BootstrapClientMessage::BootstrapSuccess))
                 }
+                MessageClientTypeId::AskLedgerRangePart => tuple((
+                    context("Failed last_slot deserialization", |input| {
+                        self.opt_slot_deserializer.deserialize(input)
+                    }),
+                    context("Failed first_address deserialization", |input| {
+                        self.hash_deserializer.deserialize(input)
+                    }),
+                    context("Failed end_address deserialization", |input| {
+                        self.opt_hash_deserializer.deserialize(input)
+                    }),
+                    context("Failed last_key deserialization", |input| {
+                        self.opt_key_deserializer.deserialize(input)
+                    }),
+                ))
+                .map(|(last_slot, first_address, end_address, last_key)| {
+                    BootstrapClientMessage::AskLedgerRangePart {
+                        last_slot,
+                        first_address: Address(first_address),
+                        end_address: end_address.map(Address),
+                        last_key,
+                    }
+                })
+                .parse(input),
+                MessageClientTypeId::AskStateWithoutLedgerPart => tuple((
+                    context("Failed last_slot deserialization", |input| {
+                        self.opt_slot_deserializer.deserialize(input)
+                    }),
+                    context("Failed async_message_id deserialization", |input| {
+                        self.opt_async_message_id_deserializer.deserialize(input)
+                    }),
+                    context("Failed cycle_step deserialization", |input| {
+                        self.cycle_step_deserializer.deserialize(input)
+                    }),
+                    context("Failed credits_slot deserialization", |input| {
+                        self.opt_slot_deserializer.deserialize(input)
+                    }),
+                    context("Failed exec_ops_step deserialization", |input| {
+                        self.exec_ops_step_serializer.deserialize(input)
+                    }),
+                ))
+                .map(
+                    |(
+                        last_slot,
+                        last_async_message_id,
+                        last_cycle_step,
+                        last_credits_slot,
+                        last_exec_ops_step,
+                    )| {
+                        BootstrapClientMessage::AskStateWithoutLedgerPart {
+                            last_slot,
+                            last_async_message_id,
+                            last_cycle_step,
+                            last_credits_slot,
+                            last_exec_ops_step,
+                        }
+                    },
+                )
+                .parse(input),
+                MessageClientTypeId::AskFinalStateChanges => {
+                    context("Failed last_slot deserialization", |input| {
+                        self.slot_deserializer.deserialize(input)
+                    })
+                    .map(|last_slot| BootstrapClientMessage::AskFinalStateChanges { last_slot })
+                    .parse(input)
+                }
+                MessageClientTypeId::Authenticate => tuple((
+                    context("Failed public_key deserialization", |input| {
+                        self.public_key_deserializer.deserialize(input)
+                    }),
+                    context("Failed signature deserialization", |input| {
+                        self.signature_deserializer.deserialize(input)
+                    }),
+                ))
+                .map(
+                    |(public_key, signature)| BootstrapClientMessage::Authenticate {
+                        public_key,
+                        signature,
+                    },
+                )
+                .parse(input),
             }
         })
         .parse(buffer)
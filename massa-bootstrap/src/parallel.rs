@@ -0,0 +1,674 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Parallel bootstrap of the final state from several trusted bootstrap servers.
+//!
+//! The address space is split into one range of the ledger per server and the ranges are streamed in parallel.
+//! Each received range is consistent with the slot of its last part.
+//! After its range, the first server (the primary) also streams the rest of the final state:
+//! the async pool, the Proof of Stake cycle history and deferred credits and the executed operations.
+//!
+//! The primary then sends the final state changes bringing every range and the rest of the state to a common slot.
+//! Finally, every server sends its final state hash along with the changes since the slot reached by the client,
+//! and the received final state must match each of them. The primary is checked last, so that its consensus state
+//! is never older than the received final state.
+//!
+//! A server whose hash does not match is not used anymore: every part of the final state it streamed or changed
+//! is dropped, and the next attempt streams these parts again from the other servers.
+
+use std::{collections::HashSet, net::SocketAddr, sync::Arc, time::Duration};
+
+use futures::future::join_all;
+use massa_final_state::{ExecutedOpsStreamingStep, FinalState, StateChanges};
+use massa_hash::Hash;
+use massa_ledger_exports::LedgerChanges;
+use massa_models::{
+    address::{Address, ADDRESS_SIZE_BYTES},
+    slot::Slot,
+    version::Version,
+};
+use massa_pos_exports::PoSCycleStreamingStep;
+use massa_signature::PublicKey;
+use massa_time::MassaTime;
+use nom::AsBytes;
+use parking_lot::RwLock;
+use tokio::time::{sleep, Instant};
+use tracing::{debug, info, warn};
+
+use crate::{
+    client::{
+        apply_final_state_changes, ask_bootstrap_data, connect_to_server, send_client_message,
+        sync_with_server,
+    },
+    client_binder::BootstrapClientBinder,
+    error::BootstrapError,
+    messages::{BootstrapClientMessage, BootstrapServerMessage},
+    BootstrapConfig, Establisher, GlobalBootstrapState,
+};
+
+/// Interval between two requests of final state changes to a server lagging behind the slot reached by the client
+const STATE_CHANGES_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Range of the ledger streamed by a single server
+struct LedgerRange {
+    /// first address of the range
+    first_address: Address,
+    /// address following the range, `None` if the range goes until the end of the ledger
+    end_address: Option<Address>,
+    /// slot the received part of the range is attached to
+    last_slot: Option<Slot>,
+    /// last key of the range received from the server
+    last_key: Option<Vec<u8>>,
+    /// true once the whole range is received
+    finished: bool,
+    /// servers the received part of the range comes from, or was changed by
+    servers: HashSet<SocketAddr>,
+}
+
+impl LedgerRange {
+    /// Message resuming the streaming of the range from any server
+    fn next_message(&self) -> BootstrapClientMessage {
+        BootstrapClientMessage::AskLedgerRangePart {
+            last_slot: self.last_slot,
+            first_address: self.first_address,
+            end_address: self.end_address,
+            last_key: self.last_key.clone(),
+        }
+    }
+
+    /// Checks whether an address belongs to the range
+    fn contains(&self, address: &Address) -> bool {
+        *address >= self.first_address
+            && self
+                .end_address
+                .map_or(true, |end_address| *address < end_address)
+    }
+
+    /// Drops the received part of the range
+    fn reset(&mut self, final_state: &mut FinalState) {
+        final_state.ledger.delete_ledger_range(
+            self.first_address.to_bytes(),
+            &self
+                .end_address
+                .map(|end_address| end_address.to_bytes().to_vec()),
+        );
+        self.last_slot = None;
+        self.last_key = None;
+        self.finished = false;
+        self.servers.clear();
+    }
+}
+
+/// Splits the address space into `count` ranges according to the first byte of the addresses
+fn split_ledger(count: usize) -> Vec<LedgerRange> {
+    let count = count.clamp(1, 256);
+    let boundaries: Vec<Address> = (0..count)
+        .map(|index| {
+            let mut bytes = [0u8; ADDRESS_SIZE_BYTES];
+            bytes[0] = (index * 256 / count) as u8;
+            Address::from_bytes(&bytes)
+        })
+        .collect();
+    boundaries
+        .iter()
+        .enumerate()
+        .map(|(index, first_address)| LedgerRange {
+            first_address: *first_address,
+            end_address: boundaries.get(index + 1).copied(),
+            last_slot: None,
+            last_key: None,
+            finished: false,
+            servers: HashSet::new(),
+        })
+        .collect()
+}
+
+/// Message starting the streaming of the final state without the ledger
+fn initial_state_message() -> BootstrapClientMessage {
+    BootstrapClientMessage::AskStateWithoutLedgerPart {
+        last_slot: None,
+        last_async_message_id: None,
+        last_cycle_step: PoSCycleStreamingStep::Started,
+        last_credits_slot: None,
+        last_exec_ops_step: ExecutedOpsStreamingStep::Started,
+    }
+}
+
+/// Progress of the parallel bootstrap, kept between attempts
+struct ParallelBootstrapProgress {
+    /// ranges of the ledger
+    ranges: Vec<LedgerRange>,
+    /// message resuming the streaming of the final state without the ledger, `None` once it is fully received
+    state_message: Option<BootstrapClientMessage>,
+    /// servers the received part of the final state without the ledger comes from, or was changed by
+    state_servers: HashSet<SocketAddr>,
+}
+
+impl ParallelBootstrapProgress {
+    /// Records that final state changes sent by a server were applied to the whole received final state,
+    /// and brings every range to the slot of the final state
+    fn changed_by(&mut self, addr: &SocketAddr, slot: Slot) {
+        for range in self.ranges.iter_mut() {
+            range.servers.insert(*addr);
+            range.last_slot = Some(slot);
+        }
+        self.state_servers.insert(*addr);
+    }
+
+    /// Drops every part of the final state that comes from a server or was changed by it
+    fn reset(&mut self, addr: &SocketAddr, final_state: &mut FinalState) {
+        for range in self
+            .ranges
+            .iter_mut()
+            .filter(|range| range.servers.contains(addr))
+        {
+            range.reset(final_state);
+        }
+        if self.state_servers.contains(addr) {
+            final_state.reset_state_without_ledger();
+            self.state_message = Some(initial_state_message());
+            self.state_servers.clear();
+        }
+    }
+}
+
+/// Streams a range of the ledger from a server until it is fully received
+async fn stream_ledger_range(
+    cfg: &BootstrapConfig,
+    client: &mut BootstrapClientBinder,
+    addr: &SocketAddr,
+    range: &mut LedgerRange,
+    final_state: &Arc<RwLock<FinalState>>,
+) -> Result<(), BootstrapError> {
+    match tokio::time::timeout(cfg.write_timeout.into(), client.send(&range.next_message())).await {
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "bootstrap ask ledger range part send timed out",
+        )
+        .into()),
+        Ok(Err(e)) => Err(e),
+        Ok(Ok(_)) => Ok(()),
+    }?;
+    loop {
+        let msg = match tokio::time::timeout(cfg.read_timeout.into(), client.next()).await {
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "ledger range bootstrap read timed out",
+                )
+                .into());
+            }
+            Ok(Err(e)) => return Err(e),
+            Ok(Ok(msg)) => msg,
+        };
+        match msg {
+            BootstrapServerMessage::LedgerRangePart {
+                ledger_data,
+                slot,
+                ledger_changes,
+            } => {
+                let mut write_final_state = final_state.write();
+                range.servers.insert(*addr);
+                let last_key = write_final_state.ledger.set_ledger_part(ledger_data)?;
+                for (changes_slot, changes) in ledger_changes {
+                    write_final_state
+                        .ledger
+                        .apply_changes(changes, changes_slot);
+                }
+                if last_key.is_some() {
+                    range.last_key = last_key;
+                }
+                range.last_slot = Some(slot);
+            }
+            BootstrapServerMessage::FinalStateFinished => {
+                debug!(
+                    "Ledger range starting at {} received at slot {:?}",
+                    range.first_address, range.last_slot
+                );
+                range.finished = true;
+                return Ok(());
+            }
+            BootstrapServerMessage::BootstrapError { error } => {
+                return Err(BootstrapError::ReceivedError(error))
+            }
+            other => return Err(BootstrapError::UnexpectedServerMessage(other)),
+        }
+    }
+}
+
+/// Streams the final state without the ledger from a server until it is fully received
+async fn stream_state_without_ledger(
+    cfg: &BootstrapConfig,
+    client: &mut BootstrapClientBinder,
+    addr: &SocketAddr,
+    state_message: &mut Option<BootstrapClientMessage>,
+    state_servers: &mut HashSet<SocketAddr>,
+    final_state: &Arc<RwLock<FinalState>>,
+) -> Result<(), BootstrapError> {
+    let Some(message) = state_message.as_ref() else {
+        return Ok(());
+    };
+    match tokio::time::timeout(cfg.write_timeout.into(), client.send(message)).await {
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "bootstrap ask state part send timed out",
+        )
+        .into()),
+        Ok(Err(e)) => Err(e),
+        Ok(Ok(_)) => Ok(()),
+    }?;
+    loop {
+        let msg = match tokio::time::timeout(cfg.read_timeout.into(), client.next()).await {
+            Err(_) => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "final state bootstrap read timed out",
+                )
+                .into());
+            }
+            Ok(Err(e)) => return Err(e),
+            Ok(Ok(msg)) => msg,
+        };
+        match msg {
+            BootstrapServerMessage::FinalStatePart {
+                async_pool_part,
+                pos_cycle_part,
+                pos_credits_part,
+                exec_ops_part,
+                slot,
+                final_state_changes,
+                ..
+            } => {
+                let mut write_final_state = final_state.write();
+                state_servers.insert(*addr);
+                let last_async_message_id = write_final_state
+                    .async_pool
+                    .set_pool_part(async_pool_part.as_bytes())?;
+                let last_cycle_step = write_final_state
+                    .pos_state
+                    .set_cycle_history_part(pos_cycle_part.as_bytes())?;
+                let last_credits_slot = write_final_state
+                    .pos_state
+                    .set_deferred_credits_part(pos_credits_part.as_bytes())?;
+                let last_exec_ops_step = write_final_state
+                    .executed_ops
+                    .set_executed_ops_part(exec_ops_part.as_bytes(), cfg.thread_count)?;
+                for (changes_slot, changes) in final_state_changes.iter() {
+                    apply_final_state_changes(&mut write_final_state, *changes_slot, changes)?;
+                }
+                write_final_state.slot = slot;
+                // Set new message in case of disconnection
+                *state_message = Some(BootstrapClientMessage::AskStateWithoutLedgerPart {
+                    last_slot: Some(slot),
+                    last_async_message_id,
+                    last_cycle_step,
+                    last_credits_slot,
+                    last_exec_ops_step,
+                });
+            }
+            BootstrapServerMessage::FinalStateFinished => {
+                debug!(
+                    "Final state without the ledger received at slot {}",
+                    final_state.read().slot
+                );
+                *state_message = None;
+                return Ok(());
+            }
+            BootstrapServerMessage::BootstrapError { error } => {
+                return Err(BootstrapError::ReceivedError(error))
+            }
+            other => return Err(BootstrapError::UnexpectedServerMessage(other)),
+        }
+    }
+}
+
+/// Asks a server for the final state changes after `last_slot`, waiting for its final state to reach `min_slot`
+///
+/// # Returns
+/// The slot of the final state of the server, its final state hash and the changes
+async fn ask_final_state_changes(
+    cfg: &BootstrapConfig,
+    client: &mut BootstrapClientBinder,
+    last_slot: Slot,
+    min_slot: Slot,
+) -> Result<(Slot, Hash, Vec<(Slot, StateChanges)>), BootstrapError> {
+    let deadline = Instant::now() + cfg.read_timeout.to_duration();
+    loop {
+        match send_client_message(
+            &BootstrapClientMessage::AskFinalStateChanges { last_slot },
+            client,
+            cfg.write_timeout.into(),
+            cfg.read_timeout.into(),
+            "ask final state changes timed out",
+        )
+        .await?
+        {
+            BootstrapServerMessage::FinalStateChanges {
+                slot,
+                state_hash,
+                changes,
+            } => {
+                if slot >= min_slot {
+                    return Ok((slot, state_hash, changes));
+                }
+                if Instant::now() > deadline {
+                    return Err(BootstrapError::GeneralError(format!(
+                        "bootstrap server final state is stuck at slot {} while waiting for slot {}",
+                        slot, min_slot
+                    )));
+                }
+                sleep(STATE_CHANGES_POLL_INTERVAL).await;
+            }
+            BootstrapServerMessage::BootstrapError { error } => {
+                return Err(BootstrapError::ReceivedError(error))
+            }
+            other => return Err(BootstrapError::UnexpectedServerMessage(other)),
+        }
+    }
+}
+
+/// Checks the received final state against the final state of a server,
+/// after applying the changes the server made since the slot reached by the client
+///
+/// If the hashes do not match, every part of the final state coming from the server is dropped.
+async fn check_final_state_hash(
+    cfg: &BootstrapConfig,
+    client: &mut BootstrapClientBinder,
+    addr: &SocketAddr,
+    progress: &mut ParallelBootstrapProgress,
+    final_state: &Arc<RwLock<FinalState>>,
+) -> Result<(), BootstrapError> {
+    let local_slot = final_state.read().slot;
+    let (slot, state_hash, changes) =
+        ask_final_state_changes(cfg, client, local_slot, local_slot).await?;
+    let mut write_final_state = final_state.write();
+    for (changes_slot, changes) in changes.iter() {
+        if *changes_slot > write_final_state.slot {
+            apply_final_state_changes(&mut write_final_state, *changes_slot, changes)?;
+        }
+    }
+    write_final_state.slot = slot;
+    write_final_state.executed_ops.prune(slot);
+    if slot != local_slot {
+        progress.changed_by(addr, slot);
+    }
+    if write_final_state.get_state_hash()? != state_hash {
+        progress.reset(addr, &mut write_final_state);
+        return Err(BootstrapError::FinalStateHashMismatch(*addr));
+    }
+    debug!(
+        "Final state hash checked with bootstrap server {} at slot {}",
+        addr, slot
+    );
+    Ok(())
+}
+
+/// Brings every range of the ledger and the rest of the final state to a common slot,
+/// using the final state changes of the primary server
+async fn catch_up_final_state(
+    cfg: &BootstrapConfig,
+    client: &mut BootstrapClientBinder,
+    addr: &SocketAddr,
+    progress: &mut ParallelBootstrapProgress,
+    final_state: &Arc<RwLock<FinalState>>,
+) -> Result<(), BootstrapError> {
+    let state_slot = final_state.read().slot;
+    let range_slots = progress.ranges.iter().filter_map(|range| range.last_slot);
+    let first_slot = range_slots.clone().fold(state_slot, Slot::min);
+    let last_slot = range_slots.fold(state_slot, Slot::max);
+
+    let (slot, _, changes) = ask_final_state_changes(cfg, client, first_slot, last_slot).await?;
+    let mut write_final_state = final_state.write();
+    for (changes_slot, changes) in changes.iter() {
+        for range in progress.ranges.iter().filter(|range| {
+            range
+                .last_slot
+                .map_or(true, |range_slot| *changes_slot > range_slot)
+        }) {
+            write_final_state.ledger.apply_changes(
+                LedgerChanges(
+                    changes
+                        .ledger_changes
+                        .0
+                        .iter()
+                        .filter(|(address, _)| range.contains(address))
+                        .map(|(address, change)| (*address, change.clone()))
+                        .collect(),
+                ),
+                *changes_slot,
+            );
+        }
+        if *changes_slot > state_slot {
+            let mut state_changes = changes.clone();
+            state_changes.ledger_changes = LedgerChanges::default();
+            apply_final_state_changes(&mut write_final_state, *changes_slot, &state_changes)?;
+        }
+    }
+    write_final_state.slot = slot;
+    write_final_state.executed_ops.prune(slot);
+    progress.changed_by(addr, slot);
+    info!(
+        "Final state received from several servers caught up to slot {}",
+        slot
+    );
+    Ok(())
+}
+
+/// Runs an attempt of parallel bootstrap with connected servers, the first one being the primary
+async fn parallel_bootstrap_attempt(
+    cfg: &BootstrapConfig,
+    clients: &mut [(SocketAddr, BootstrapClientBinder)],
+    progress: &mut ParallelBootstrapProgress,
+    global_bootstrap_state: &mut GlobalBootstrapState,
+) -> Result<(), BootstrapError> {
+    let final_state = global_bootstrap_state.final_state.clone();
+
+    // stream the unfinished ranges in parallel, the primary also streams the rest of the final state
+    let mut assigned_ranges: Vec<Vec<&mut LedgerRange>> =
+        clients.iter().map(|_| Vec::new()).collect();
+    for (index, range) in progress
+        .ranges
+        .iter_mut()
+        .filter(|range| !range.finished)
+        .enumerate()
+    {
+        assigned_ranges[index % clients.len()].push(range);
+    }
+    let mut state_progress = Some((&mut progress.state_message, &mut progress.state_servers));
+    let streams = clients
+        .iter_mut()
+        .zip(assigned_ranges)
+        .map(|((addr, client), ranges)| {
+            let state_progress = state_progress.take();
+            let final_state = &final_state;
+            async move {
+                for range in ranges {
+                    stream_ledger_range(cfg, client, addr, range, final_state).await?;
+                }
+                if let Some((state_message, state_servers)) = state_progress {
+                    stream_state_without_ledger(
+                        cfg,
+                        client,
+                        addr,
+                        state_message,
+                        state_servers,
+                        final_state,
+                    )
+                    .await?;
+                }
+                info!("Final state parts received from {}", addr);
+                Ok::<(), BootstrapError>(())
+            }
+        });
+    for result in join_all(streams).await {
+        result?;
+    }
+
+    // bring the whole final state to a common slot and check it against each server
+    let (primary, others) = clients
+        .split_first_mut()
+        .expect("parallel bootstrap without server");
+    catch_up_final_state(cfg, &mut primary.1, &primary.0, progress, &final_state).await?;
+    for (addr, client) in others.iter_mut() {
+        check_final_state_hash(cfg, client, addr, progress, &final_state).await?;
+    }
+    check_final_state_hash(cfg, &mut primary.1, &primary.0, progress, &final_state).await?;
+    info!("State bootstrap complete");
+
+    // get the rest of the bootstrap data from the primary
+    ask_bootstrap_data(
+        cfg,
+        &mut primary.1,
+        &mut BootstrapClientMessage::AskBootstrapPeers,
+        global_bootstrap_state,
+    )
+    .await?;
+    for (_, client) in others.iter_mut() {
+        // We allow unused result because the bootstrap is complete: the server will close the session anyway.
+        let _ = tokio::time::timeout(
+            cfg.write_timeout.into(),
+            client.send(&BootstrapClientMessage::BootstrapSuccess),
+        )
+        .await;
+    }
+    Ok(())
+}
+
+/// Gets the state by streaming the final state from `count` bootstrap servers of `servers` at once.
+/// needs to be CANCELLABLE
+///
+/// The final state must be empty. Each attempt connects to the next servers of the list,
+/// and resumes the ranges that are not received yet.
+/// The servers whose final state hash did not match are skipped, until every server of the list is.
+pub(crate) async fn parallel_get_state(
+    cfg: &BootstrapConfig,
+    establisher: &mut Establisher,
+    servers: &[(SocketAddr, PublicKey)],
+    count: usize,
+    mut global_bootstrap_state: GlobalBootstrapState,
+    version: Version,
+    end_timestamp: Option<MassaTime>,
+) -> Result<GlobalBootstrapState, BootstrapError> {
+    let mut progress = ParallelBootstrapProgress {
+        ranges: split_ledger(count),
+        state_message: Some(initial_state_message()),
+        state_servers: HashSet::new(),
+    };
+    let mut excluded_servers: HashSet<SocketAddr> = HashSet::new();
+    let mut server_iter = servers.iter().cycle();
+    loop {
+        if let Some(end) = end_timestamp {
+            if MassaTime::now(0).expect("could not get now time") > end {
+                panic!("This episode has come to an end, please get the latest testnet node version to continue");
+            }
+        }
+        if servers
+            .iter()
+            .all(|(addr, _)| excluded_servers.contains(addr))
+        {
+            warn!("The final state hash of every bootstrap server did not match, trying all of them again");
+            excluded_servers.clear();
+        }
+
+        // connect to the next servers of the list
+        let mut clients = Vec::with_capacity(count);
+        for (addr, pub_key) in server_iter.by_ref().take(servers.len()) {
+            if excluded_servers.contains(addr) {
+                continue;
+            }
+            info!("Start bootstrapping from {}", addr);
+            let mut client = match connect_to_server(establisher, cfg, addr, pub_key).await {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("Error while connecting to bootstrap server: {}", e);
+                    continue;
+                }
+            };
+            match sync_with_server(cfg, &mut client, version).await {
+                Ok(compensation_millis) => {
+                    if clients.is_empty() {
+                        global_bootstrap_state.compensation_millis = compensation_millis;
+                    }
+                    clients.push((*addr, client));
+                    if clients.len() == count {
+                        break;
+                    }
+                }
+                Err(e) => warn!("Error while bootstrapping from {}: {}", addr, e),
+            }
+        }
+
+        if !clients.is_empty() {
+            match parallel_bootstrap_attempt(
+                cfg,
+                &mut clients,
+                &mut progress,
+                &mut global_bootstrap_state,
+            )
+            .await
+            {
+                Ok(()) => return Ok(global_bootstrap_state),
+                Err(BootstrapError::ReceivedError(error)) => {
+                    warn!("Error received from bootstrap server: {}", error);
+                }
+                Err(e) => {
+                    warn!("Error while bootstrapping: {}", e);
+                    if let BootstrapError::FinalStateHashMismatch(addr) = &e {
+                        excluded_servers.insert(*addr);
+                    }
+                    for (_, client) in clients.iter_mut() {
+                        // We allow unused result because we don't care if an error is thrown when sending the error message to the server we will close the socket anyway.
+                        let _ = tokio::time::timeout(
+                            cfg.write_error_timeout.into(),
+                            client.send(&BootstrapClientMessage::BootstrapError {
+                                error: e.to_string(),
+                            }),
+                        )
+                        .await;
+                    }
+                }
+            }
+        }
+
+        info!(
+            "Parallel bootstrap failed. Your node will try to bootstrap from other servers in {:#?}.",
+            cfg.retry_delay.to_duration()
+        );
+        sleep(cfg.retry_delay.into()).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_ledger;
+    use massa_models::address::{Address, ADDRESS_SIZE_BYTES};
+
+    #[test]
+    fn test_split_ledger() {
+        let ranges = split_ledger(3);
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(
+            ranges[0].first_address,
+            Address::from_bytes(&[0u8; ADDRESS_SIZE_BYTES])
+        );
+        assert!(ranges[2].end_address.is_none());
+
+        // the ranges cover the whole address space without overlapping
+        for bytes in [
+            [0u8; ADDRESS_SIZE_BYTES],
+            [85u8; ADDRESS_SIZE_BYTES],
+            [255u8; ADDRESS_SIZE_BYTES],
+        ] {
+            let address = Address::from_bytes(&bytes);
+            assert_eq!(
+                ranges
+                    .iter()
+                    .filter(|range| range.contains(&address))
+                    .count(),
+                1
+            );
+        }
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[0].end_address, Some(pair[1].first_address));
+        }
+    }
+}
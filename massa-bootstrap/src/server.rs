@@ -10,9 +10,9 @@ use futures::StreamExt;
 use massa_async_pool::AsyncMessageId;
 use massa_consensus_exports::ConsensusCommandSender;
 use massa_final_state::{ExecutedOpsStreamingStep, FinalState};
-use massa_ledger_exports::get_address_from_key;
+use massa_ledger_exports::{get_address_from_key, LedgerChanges};
 use massa_logging::massa_trace;
-use massa_models::{slot::Slot, version::Version};
+use massa_models::{address::Address, ip_range::IpAccessLists, slot::Slot, version::Version};
 use massa_network_exports::NetworkCommandSender;
 use massa_pos_exports::PoSCycleStreamingStep;
 use massa_signature::KeyPair;
@@ -32,9 +32,15 @@ use crate::{
 pub struct BootstrapManager {
     join_handle: JoinHandle<Result<(), BootstrapError>>,
     manager_tx: mpsc::Sender<()>,
+    access_lists: Arc<RwLock<IpAccessLists>>,
 }
 
 impl BootstrapManager {
+    /// get the IP ranges allowed and denied to bootstrap from us, they can be updated while the server is running
+    pub fn get_access_lists(&self) -> Arc<RwLock<IpAccessLists>> {
+        self.access_lists.clone()
+    }
+
     /// stop the bootstrap server
     pub async fn stop(self) -> Result<(), BootstrapError> {
         massa_trace!("bootstrap.lib.stop", {});
@@ -64,6 +70,11 @@ pub async fn start_bootstrap_server(
     massa_trace!("bootstrap.lib.start_bootstrap_server", {});
     if let Some(bind) = bootstrap_config.bind {
         let (manager_tx, manager_rx) = mpsc::channel::<()>(1);
+        let access_lists = Arc::new(RwLock::new(IpAccessLists {
+            allow_list: bootstrap_config.ip_allow_list.clone(),
+            deny_list: bootstrap_config.ip_deny_list.clone(),
+        }));
+        let server_access_lists = access_lists.clone();
         let join_handle = tokio::spawn(async move {
             BootstrapServer {
                 consensus_command_sender,
@@ -71,6 +82,7 @@ pub async fn start_bootstrap_server(
                 final_state,
                 establisher,
                 manager_rx,
+                access_lists: server_access_lists,
                 bind,
                 keypair,
                 compensation_millis,
@@ -84,6 +96,7 @@ pub async fn start_bootstrap_server(
         Ok(Some(BootstrapManager {
             join_handle,
             manager_tx,
+            access_lists,
         }))
     } else {
         Ok(None)
@@ -96,6 +109,7 @@ struct BootstrapServer {
     final_state: Arc<RwLock<FinalState>>,
     establisher: Establisher,
     manager_rx: mpsc::Receiver<()>,
+    access_lists: Arc<RwLock<IpAccessLists>>,
     bind: SocketAddr,
     keypair: KeyPair,
     bootstrap_config: BootstrapConfig,
@@ -149,7 +163,14 @@ impl BootstrapServer {
                 }
 
                 // listener
-                Ok((dplx, remote_addr)) = listener.accept() => if bootstrap_sessions.len() < self.bootstrap_config.max_simultaneous_bootstraps.try_into().map_err(|_| BootstrapError::GeneralError("Fail to convert u32 to usize".to_string()))? {
+                Ok((dplx, remote_addr)) = listener.accept() => if !self.access_lists.read().is_allowed(&remote_addr.ip()) {
+                    let mut server = BootstrapServerBinder::new(dplx, self.keypair.clone(), self.bootstrap_config.max_bytes_read_write, self.bootstrap_config.max_bootstrap_message_size, self.bootstrap_config.thread_count, self.bootstrap_config.max_datastore_key_length, self.bootstrap_config.randomness_size_bytes);
+                    let _ = tokio::time::timeout(self.bootstrap_config.write_error_timeout.into(), server.send(BootstrapServerMessage::BootstrapError {
+                        error: "Your IP address is not allowed to bootstrap from this server.".to_string()
+                    })).await;
+                    massa_trace!("bootstrap.lib.run.select.accept.refuse_ip", {"remote_addr": remote_addr});
+                    debug!("did not bootstrap {}: IP address not allowed", remote_addr);
+                } else if bootstrap_sessions.len() < self.bootstrap_config.max_simultaneous_bootstraps.try_into().map_err(|_| BootstrapError::GeneralError("Fail to convert u32 to usize".to_string()))? {
 
                     massa_trace!("bootstrap.lib.run.select.accept", {"remote_addr": remote_addr});
                     let now = Instant::now();
@@ -260,6 +281,7 @@ pub async fn send_final_state_stream(
     mut last_cycle_step: PoSCycleStreamingStep,
     mut last_credits_slot: Option<Slot>,
     mut last_exec_ops_step: ExecutedOpsStreamingStep,
+    stream_ledger: bool,
     write_timeout: Duration,
 ) -> Result<(), BootstrapError> {
     loop {
@@ -281,7 +303,7 @@ pub async fn send_final_state_stream(
         {
             // Get all the next message data
             let final_state_read = final_state.read();
-            let (data, new_last_key) =
+            let (data, new_last_key) = if stream_ledger {
                 final_state_read
                     .ledger
                     .get_ledger_part(&last_key)
@@ -289,7 +311,10 @@ pub async fn send_final_state_stream(
                         BootstrapError::GeneralError(
                             "Error on fetching ledger part of execution".to_string(),
                         )
-                    })?;
+                    })?
+            } else {
+                (Vec::new(), None)
+            };
             ledger_data = data;
 
             let (pool_data, new_last_async_pool_id) = final_state_read
@@ -409,6 +434,129 @@ pub async fn send_final_state_stream(
     Ok(())
 }
 
+/// Streams a range of the ledger for the parallel bootstrap.
+/// Each part comes with the changes of the addresses of the range that were already streamed,
+/// so that the range received by the client is always consistent with the slot of the last part.
+#[allow(clippy::too_many_arguments)]
+pub async fn send_ledger_range_stream(
+    server: &mut BootstrapServerBinder,
+    final_state: Arc<RwLock<FinalState>>,
+    mut last_slot: Option<Slot>,
+    first_address: Address,
+    end_address: Option<Address>,
+    mut last_key: Option<Vec<u8>>,
+    write_timeout: Duration,
+) -> Result<(), BootstrapError> {
+    let end_key = end_address.map(|address| address.to_bytes().to_vec());
+    loop {
+        // The first part is always sent, even if the range is empty, to give its slot to the client
+        let first_part = last_slot.is_none();
+        let current_slot;
+        let ledger_data;
+        let ledger_changes: Vec<(Slot, LedgerChanges)>;
+
+        // Scope of the final state read
+        {
+            let final_state_read = final_state.read();
+            let (data, new_last_key) = final_state_read
+                .ledger
+                .get_ledger_range_part(
+                    last_key.as_deref().unwrap_or(first_address.to_bytes()),
+                    &end_key,
+                )
+                .map_err(|_| {
+                    BootstrapError::GeneralError(
+                        "Error on fetching ledger range part of execution".to_string(),
+                    )
+                })?;
+            ledger_data = data;
+
+            if let Some(slot) = last_slot && let Some(key) = &last_key && slot != final_state_read.slot {
+                if slot > final_state_read.slot {
+                    return Err(BootstrapError::GeneralError(
+                        "Bootstrap cursor set to future slot".to_string(),
+                    ));
+                }
+                let last_address = get_address_from_key(key).ok_or_else(|| {
+                    BootstrapError::GeneralError("Malformed key in slot changes".to_string())
+                })?;
+                ledger_changes = final_state_read
+                    .get_state_changes_since(slot)?
+                    .into_iter()
+                    .map(|(changes_slot, changes)| {
+                        (
+                            changes_slot,
+                            LedgerChanges(
+                                changes
+                                    .ledger_changes
+                                    .0
+                                    .into_iter()
+                                    .filter(|(address, _)| {
+                                        *address >= first_address && *address <= last_address
+                                    })
+                                    .collect(),
+                            ),
+                        )
+                    })
+                    .collect();
+            } else {
+                ledger_changes = Vec::new();
+            }
+
+            // Assign value for next turn
+            if new_last_key.is_some() {
+                last_key = new_last_key;
+            }
+            last_slot = Some(final_state_read.slot);
+            current_slot = final_state_read.slot;
+        }
+
+        if first_part
+            || !ledger_data.is_empty()
+            || ledger_changes
+                .iter()
+                .any(|(_, changes)| !changes.0.is_empty())
+        {
+            match tokio::time::timeout(
+                write_timeout,
+                server.send(BootstrapServerMessage::LedgerRangePart {
+                    ledger_data,
+                    slot: current_slot,
+                    ledger_changes,
+                }),
+            )
+            .await
+            {
+                Err(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "bootstrap ledger range part send timed out",
+                )
+                .into()),
+                Ok(Err(e)) => Err(e),
+                Ok(Ok(_)) => Ok(()),
+            }?;
+        } else {
+            // The range is fully streamed and the client is up to date.
+            match tokio::time::timeout(
+                write_timeout,
+                server.send(BootstrapServerMessage::FinalStateFinished),
+            )
+            .await
+            {
+                Err(_) => Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "bootstrap ledger range part send timed out",
+                )
+                .into()),
+                Ok(Err(e)) => Err(e),
+                Ok(Ok(_)) => Ok(()),
+            }?;
+            break;
+        }
+    }
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn manage_bootstrap(
     bootstrap_config: &BootstrapConfig,
@@ -471,6 +619,30 @@ async fn manage_bootstrap(
         Ok(Ok(_)) => Ok(()),
     }?;
 
+    // Check the identity of the client if required.
+    if !bootstrap_config.authorized_client_keys.is_empty() {
+        match tokio::time::timeout(
+            bootstrap_config.read_timeout.into(),
+            server.authenticate_client(&bootstrap_config.authorized_client_keys),
+        )
+        .await
+        {
+            Err(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "bootstrap client authentication read timed out",
+            )
+            .into()),
+            Ok(Err(e)) => Err(e),
+            Ok(Ok(public_key)) => {
+                debug!(
+                    "bootstrap client authenticated with public key {}",
+                    public_key
+                );
+                Ok(())
+            }
+        }?;
+    }
+
     loop {
         match tokio::time::timeout(bootstrap_config.read_timeout.into(), server.next()).await {
             Err(_) => break Ok(()),
@@ -511,10 +683,68 @@ async fn manage_bootstrap(
                         last_cycle_step,
                         last_credits_slot,
                         last_exec_ops_step,
+                        true,
                         write_timeout,
                     )
                     .await?;
                 }
+                BootstrapClientMessage::AskLedgerRangePart {
+                    last_slot,
+                    first_address,
+                    end_address,
+                    last_key,
+                } => {
+                    send_ledger_range_stream(
+                        server,
+                        final_state.clone(),
+                        last_slot,
+                        first_address,
+                        end_address,
+                        last_key,
+                        write_timeout,
+                    )
+                    .await?;
+                }
+                BootstrapClientMessage::AskStateWithoutLedgerPart {
+                    last_slot,
+                    last_async_message_id,
+                    last_cycle_step,
+                    last_credits_slot,
+                    last_exec_ops_step,
+                } => {
+                    send_final_state_stream(
+                        server,
+                        final_state.clone(),
+                        last_slot,
+                        None,
+                        last_async_message_id,
+                        last_cycle_step,
+                        last_credits_slot,
+                        last_exec_ops_step,
+                        false,
+                        write_timeout,
+                    )
+                    .await?;
+                }
+                BootstrapClientMessage::AskFinalStateChanges { last_slot } => {
+                    let message = {
+                        let final_state_read = final_state.read();
+                        BootstrapServerMessage::FinalStateChanges {
+                            slot: final_state_read.slot,
+                            state_hash: final_state_read.get_state_hash()?,
+                            changes: final_state_read.get_state_changes_since(last_slot)?,
+                        }
+                    };
+                    match tokio::time::timeout(write_timeout, server.send(message)).await {
+                        Err(_) => Err(std::io::Error::new(
+                            std::io::ErrorKind::TimedOut,
+                            "bootstrap final state changes send timed out",
+                        )
+                        .into()),
+                        Ok(Err(e)) => Err(e),
+                        Ok(Ok(_)) => Ok(()),
+                    }?;
+                }
                 BootstrapClientMessage::AskConsensusState => {
                     match tokio::time::timeout(
                         write_timeout,
@@ -533,6 +763,8 @@ async fn manage_bootstrap(
                         Ok(Ok(_)) => Ok(()),
                     }?;
                 }
+                // authentication is not required, or was already checked
+                BootstrapClientMessage::Authenticate { .. } => {}
                 BootstrapClientMessage::BootstrapSuccess => break Ok(()),
                 BootstrapClientMessage::BootstrapError { error } => {
                     break Err(BootstrapError::ReceivedError(error));
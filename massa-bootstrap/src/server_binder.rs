@@ -13,7 +13,7 @@ use massa_hash::HASH_SIZE_BYTES;
 use massa_models::serialization::{DeserializeMinBEInt, SerializeMinBEInt};
 use massa_models::version::{Version, VersionDeserializer, VersionSerializer};
 use massa_serialization::{DeserializeError, Deserializer, Serializer};
-use massa_signature::KeyPair;
+use massa_signature::{KeyPair, PublicKey};
 use std::convert::TryInto;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
@@ -91,6 +91,44 @@ impl BootstrapServerBinder {
         Ok(())
     }
 
+    /// Reads the authentication of the client and checks that it owns one of the `authorized_keys`.
+    /// See `BootstrapClientBinder::authenticate`
+    /// NOT cancel-safe
+    /// MUST be called right after the send of the `BootstrapMessage::BootstrapTime`
+    pub async fn authenticate_client(
+        &mut self,
+        authorized_keys: &[PublicKey],
+    ) -> Result<PublicKey, BootstrapError> {
+        let session_hash = self.prev_message.ok_or_else(|| {
+            BootstrapError::GeneralError(
+                "cannot authenticate the client before the handshake".to_string(),
+            )
+        })?;
+        match self.next().await? {
+            BootstrapClientMessage::Authenticate {
+                public_key,
+                signature,
+            } => {
+                if !authorized_keys.contains(&public_key) {
+                    return Err(BootstrapError::ClientAuthenticationError(format!(
+                        "public key {} is not authorized",
+                        public_key
+                    )));
+                }
+                public_key
+                    .verify_signature(&session_hash, &signature)
+                    .map_err(|err| BootstrapError::ClientAuthenticationError(err.to_string()))?;
+                Ok(public_key)
+            }
+            BootstrapClientMessage::BootstrapError { error } => {
+                Err(BootstrapError::ReceivedError(error))
+            }
+            _ => Err(BootstrapError::ClientAuthenticationError(
+                "this server requires client authentication".to_string(),
+            )),
+        }
+    }
+
     /// Writes the next message. NOT cancel-safe
     pub async fn send(&mut self, msg: BootstrapServerMessage) -> Result<(), BootstrapError> {
         // serialize message
@@ -1,9 +1,10 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use massa_signature::PublicKey;
+use massa_models::ip_range::IpRange;
+use massa_signature::{KeyPair, PublicKey};
 use massa_time::MassaTime;
 use serde::Deserialize;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, path::PathBuf};
 
 /// Bootstrap configuration.
 #[derive(Debug, Deserialize, Clone)]
@@ -82,4 +83,18 @@ pub struct BootstrapConfig {
     pub max_ledger_changes_count: u64,
     /// maximum of slot in state changes
     pub max_changes_slot_count: u32,
+    /// directory where the final state received so far is saved to resume an interrupted bootstrap, if enabled
+    pub checkpoint_path: Option<PathBuf>,
+    /// minimal interval between two bootstrap checkpoints
+    pub checkpoint_interval: MassaTime,
+    /// number of bootstrap servers the ledger is streamed from in parallel, 1 or less to bootstrap from a single server
+    pub parallel_bootstrap_servers: usize,
+    /// if not empty, only the IPs in one of these ranges are allowed to bootstrap from us
+    pub ip_allow_list: Vec<IpRange>,
+    /// IP ranges that are not allowed to bootstrap from us, even if they are in the allow list
+    pub ip_deny_list: Vec<IpRange>,
+    /// if not empty, clients have to prove they own one of these public keys to bootstrap from us
+    pub authorized_client_keys: Vec<PublicKey>,
+    /// keypair authenticating us to the bootstrap servers that require client authentication
+    pub client_keypair: Option<KeyPair>,
 }
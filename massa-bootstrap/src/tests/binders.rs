@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use crate::error::BootstrapError;
 use crate::messages::{BootstrapClientMessage, BootstrapServerMessage};
 use crate::BootstrapConfig;
 use crate::{
@@ -360,3 +361,85 @@ async fn test_binders_try_double_send_client_works() {
     server_thread.await.unwrap();
     client_thread.await.unwrap();
 }
+
+/// The client will authenticate twice, first with an authorized key then with an unknown one
+#[tokio::test]
+#[serial]
+async fn test_binders_client_authentication() {
+    let (bootstrap_config, server_keypair): &(BootstrapConfig, KeyPair) = &BOOTSTRAP_CONFIG_KEYPAIR;
+    let client_keypair = KeyPair::generate();
+    let authorized_keys = vec![client_keypair.get_public_key()];
+
+    let (client, server) = duplex(1000000);
+    let mut server = BootstrapServerBinder::new(
+        server,
+        server_keypair.clone(),
+        f64::INFINITY,
+        MAX_BOOTSTRAP_MESSAGE_SIZE,
+        THREAD_COUNT,
+        MAX_DATASTORE_KEY_LENGTH,
+        BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
+    );
+    let mut client = BootstrapClientBinder::new(
+        client,
+        bootstrap_config.bootstrap_list[0].1,
+        f64::INFINITY,
+        MAX_BOOTSTRAP_MESSAGE_SIZE,
+        ENDORSEMENT_COUNT,
+        MAX_ADVERTISE_LENGTH,
+        MAX_BOOTSTRAP_BLOCKS,
+        MAX_OPERATIONS_PER_BLOCK,
+        THREAD_COUNT,
+        BOOTSTRAP_RANDOMNESS_SIZE_BYTES,
+        MAX_BOOTSTRAP_ASYNC_POOL_CHANGES,
+        MAX_BOOTSTRAP_ERROR_LENGTH,
+        MAX_BOOTSTRAP_FINAL_STATE_PARTS_SIZE,
+        MAX_DATASTORE_ENTRY_COUNT,
+        MAX_DATASTORE_KEY_LENGTH,
+        MAX_DATASTORE_VALUE_LENGTH,
+        MAX_DATA_ASYNC_MESSAGE,
+        MAX_FUNCTION_NAME_LENGTH,
+        MAX_PARAMETERS_SIZE,
+        MAX_LEDGER_CHANGES_COUNT,
+        MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        1000,
+    );
+
+    let server_thread = tokio::spawn(async move {
+        let vector_peers = vec![bootstrap_config.bootstrap_list[0].0.ip()];
+        let test_peers_message = BootstrapServerMessage::BootstrapPeers {
+            peers: BootstrapPeers(vector_peers.clone()),
+        };
+        let version: Version = Version::from_str("TEST.1.10").unwrap();
+
+        server.handshake(version).await.unwrap();
+        server.send(test_peers_message).await.unwrap();
+
+        let public_key = server.authenticate_client(&authorized_keys).await.unwrap();
+        assert_eq!(public_key, authorized_keys[0]);
+
+        match server.authenticate_client(&authorized_keys).await {
+            Err(BootstrapError::ClientAuthenticationError(_)) => (),
+            other => panic!("Expected an authentication error, got {:?}", other),
+        }
+    });
+
+    let client_thread = tokio::spawn(async move {
+        let version: Version = Version::from_str("TEST.1.10").unwrap();
+
+        client.handshake(version).await.unwrap();
+        let message = client.next().await.unwrap();
+        match message {
+            BootstrapServerMessage::BootstrapPeers { .. } => (),
+            _ => panic!("Bad message receive: Expected a peers list message"),
+        }
+
+        client.authenticate(&client_keypair).await.unwrap();
+        client.authenticate(&KeyPair::generate()).await.unwrap();
+    });
+
+    server_thread.await.unwrap();
+    client_thread.await.unwrap();
+}
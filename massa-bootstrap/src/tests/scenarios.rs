@@ -4,28 +4,45 @@ use super::{
     mock_establisher,
     tools::{
         bridge_mock_streams, get_boot_state, get_peers, get_random_final_state_bootstrap,
-        get_random_ledger_changes, wait_consensus_command, wait_network_command,
+        get_random_ledger_changes, wait_consensus_command, wait_network_command, BASE_BOOTSTRAP_IP,
     },
 };
+use crate::checkpoint::{load_checkpoint, remove_checkpoint, save_checkpoint};
+use crate::parallel::parallel_get_state;
 use crate::tests::tools::{
     get_random_async_pool_changes, get_random_executed_ops, get_random_pos_changes,
 };
+use crate::BootstrapClientMessage;
 use crate::BootstrapConfig;
 use crate::{
     get_state, start_bootstrap_server,
     tests::tools::{assert_eq_bootstrap_graph, get_bootstrap_config},
+    GlobalBootstrapState,
 };
 use massa_consensus_exports::{commands::ConsensusCommand, ConsensusCommandSender};
-use massa_final_state::{test_exports::assert_eq_final_state, FinalState, StateChanges};
-use massa_models::{address::Address, slot::Slot, version::Version};
+use massa_final_state::{
+    test_exports::assert_eq_final_state, ExecutedOpsStreamingStep, FinalState, StateChanges,
+};
+use massa_hash::Hash;
+use massa_models::{
+    address::Address, config::MAX_DATASTORE_KEY_LENGTH, slot::Slot, version::Version,
+};
 use massa_network_exports::{NetworkCommand, NetworkCommandSender};
-use massa_pos_exports::{test_exports::assert_eq_pos_selection, PoSFinalState, SelectorConfig};
+use massa_pos_exports::{
+    test_exports::assert_eq_pos_selection, PoSCycleStreamingStep, PoSFinalState, SelectorConfig,
+};
 use massa_pos_worker::start_selector_worker;
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use parking_lot::RwLock;
 use serial_test::serial;
-use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
 use tokio::sync::mpsc;
 
 lazy_static::lazy_static! {
@@ -111,6 +128,7 @@ async fn test_bootstrap_server() {
             Version::from_str("TEST.1.10").unwrap(),
             MassaTime::now(0).unwrap().saturating_sub(1000.into()),
             None,
+            None,
         )
         .await
         .unwrap()
@@ -271,3 +289,308 @@ async fn test_bootstrap_server() {
     server_selector_manager.stop();
     client_selector_manager.stop();
 }
+
+#[test]
+#[serial]
+fn test_final_state_snapshot() {
+    let rolls_path = PathBuf::from_str("../massa-node/base_config/initial_rolls.json").unwrap();
+    let genesis_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let selector_config = SelectorConfig {
+        thread_count: 2,
+        periods_per_cycle: 2,
+        genesis_address,
+        ..Default::default()
+    };
+    let (mut exported_selector_manager, exported_selector_controller) =
+        start_selector_worker(selector_config.clone())
+            .expect("could not start exported selector controller");
+    let (mut imported_selector_manager, imported_selector_controller) =
+        start_selector_worker(selector_config)
+            .expect("could not start imported selector controller");
+
+    let exported_state = get_random_final_state_bootstrap(
+        PoSFinalState::new(
+            &"".to_string(),
+            &rolls_path,
+            2,
+            2,
+            exported_selector_controller,
+        )
+        .unwrap(),
+    );
+    let snapshot_path = std::env::temp_dir().join("massa_test_final_state.snapshot");
+    let snapshot_hash = exported_state.export_snapshot(&snapshot_path).unwrap();
+
+    // a wrong trusted hash is rejected before modifying the state
+    let new_imported_state = || {
+        FinalState::default_with_pos(
+            PoSFinalState::new(
+                &"".to_string(),
+                &rolls_path,
+                2,
+                2,
+                imported_selector_controller.clone(),
+            )
+            .unwrap(),
+        )
+    };
+    let mut imported_state = new_imported_state();
+    assert!(imported_state
+        .import_snapshot(&snapshot_path, Some(Hash::compute_from(b"wrong")))
+        .is_err());
+
+    // a trusted hash matching the export is accepted and the state is fully restored
+    let mut imported_state = new_imported_state();
+    let cursor = imported_state
+        .import_snapshot(&snapshot_path, Some(snapshot_hash))
+        .unwrap();
+    assert_eq_final_state(&exported_state, &imported_state);
+    assert!(cursor.last_key.is_some());
+    assert_eq!(
+        cursor.last_exec_ops_step,
+        ExecutedOpsStreamingStep::Finished
+    );
+
+    std::fs::remove_file(&snapshot_path).unwrap();
+    exported_selector_manager.stop();
+    imported_selector_manager.stop();
+}
+
+#[test]
+#[serial]
+fn test_bootstrap_checkpoint() {
+    let rolls_path = PathBuf::from_str("../massa-node/base_config/initial_rolls.json").unwrap();
+    let genesis_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let selector_config = SelectorConfig {
+        thread_count: 2,
+        periods_per_cycle: 2,
+        genesis_address,
+        ..Default::default()
+    };
+    let (mut saved_selector_manager, saved_selector_controller) =
+        start_selector_worker(selector_config.clone())
+            .expect("could not start saved selector controller");
+    let (mut loaded_selector_manager, loaded_selector_controller) =
+        start_selector_worker(selector_config).expect("could not start loaded selector controller");
+
+    let saved_state = get_random_final_state_bootstrap(
+        PoSFinalState::new(
+            &"".to_string(),
+            &rolls_path,
+            2,
+            2,
+            saved_selector_controller,
+        )
+        .unwrap(),
+    );
+    let checkpoint_path = std::env::temp_dir().join("massa_test_bootstrap_checkpoint");
+    remove_checkpoint(&checkpoint_path).unwrap();
+    let mut loaded_state = FinalState::default_with_pos(
+        PoSFinalState::new(
+            &"".to_string(),
+            &rolls_path,
+            2,
+            2,
+            loaded_selector_controller,
+        )
+        .unwrap(),
+    );
+
+    // no checkpoint was saved yet
+    assert!(load_checkpoint(
+        &checkpoint_path,
+        &mut loaded_state,
+        2,
+        MAX_DATASTORE_KEY_LENGTH
+    )
+    .unwrap()
+    .is_none());
+
+    // the partial state and the streaming cursors are restored
+    let last_key = saved_state.ledger.get_ledger_part(&None).unwrap().1;
+    let next_message = BootstrapClientMessage::AskFinalStatePart {
+        last_slot: Some(saved_state.slot),
+        last_key: last_key.clone(),
+        last_async_message_id: None,
+        last_cycle_step: PoSCycleStreamingStep::Started,
+        last_credits_slot: None,
+        last_exec_ops_step: ExecutedOpsStreamingStep::Started,
+    };
+    save_checkpoint(
+        &checkpoint_path,
+        &saved_state.take_snapshot().unwrap(),
+        &next_message,
+    )
+    .unwrap();
+    match load_checkpoint(
+        &checkpoint_path,
+        &mut loaded_state,
+        2,
+        MAX_DATASTORE_KEY_LENGTH,
+    )
+    .unwrap()
+    {
+        Some(BootstrapClientMessage::AskFinalStatePart {
+            last_slot,
+            last_key: loaded_last_key,
+            last_async_message_id: None,
+            last_cycle_step: PoSCycleStreamingStep::Started,
+            last_credits_slot: None,
+            last_exec_ops_step: ExecutedOpsStreamingStep::Started,
+        }) => {
+            assert_eq!(last_slot, Some(saved_state.slot));
+            assert_eq!(loaded_last_key, last_key);
+        }
+        other => panic!("unexpected checkpoint message: {:?}", other),
+    }
+    assert_eq_final_state(&saved_state, &loaded_state);
+
+    remove_checkpoint(&checkpoint_path).unwrap();
+    assert!(!checkpoint_path.exists());
+    saved_selector_manager.stop();
+    loaded_selector_manager.stop();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_parallel_bootstrap() {
+    let rolls_path = PathBuf::from_str("../massa-node/base_config/initial_rolls.json").unwrap();
+    let genesis_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+    let selector_config = SelectorConfig {
+        thread_count: 2,
+        periods_per_cycle: 2,
+        genesis_address,
+        ..Default::default()
+    };
+
+    // start two bootstrap servers: the second one has another final state
+    let mut selector_managers = Vec::new();
+    let mut server_states = Vec::new();
+    let mut bootstrap_managers = Vec::new();
+    let mut bootstrap_interfaces = Vec::new();
+    let mut servers = Vec::new();
+    for index in 0..2u16 {
+        let (selector_manager, selector_controller) =
+            start_selector_worker(selector_config.clone())
+                .expect("could not start server selector controller");
+        selector_managers.push(selector_manager);
+        let final_state = Arc::new(RwLock::new(get_random_final_state_bootstrap(
+            PoSFinalState::new(&"".to_string(), &rolls_path, 2, 2, selector_controller).unwrap(),
+        )));
+
+        // answer the consensus state and the peers asked by the primary
+        let (consensus_cmd_tx, mut consensus_cmd_rx) = mpsc::channel::<ConsensusCommand>(5);
+        let (network_cmd_tx, mut network_cmd_rx) = mpsc::channel::<NetworkCommand>(5);
+        tokio::spawn(async move {
+            while let Some(cmd) = consensus_cmd_rx.recv().await {
+                if let ConsensusCommand::GetBootstrapState(response) = cmd {
+                    response.send(Box::new(get_boot_state())).await.unwrap();
+                }
+            }
+        });
+        tokio::spawn(async move {
+            while let Some(cmd) = network_cmd_rx.recv().await {
+                if let NetworkCommand::GetBootstrapPeers(response) = cmd {
+                    response.send(get_peers()).unwrap();
+                }
+            }
+        });
+
+        let keypair = KeyPair::generate();
+        let (bootstrap_establisher, bootstrap_interface) = mock_establisher::new();
+        bootstrap_managers.push(
+            start_bootstrap_server(
+                ConsensusCommandSender(consensus_cmd_tx),
+                NetworkCommandSender(network_cmd_tx),
+                final_state.clone(),
+                get_bootstrap_config(keypair.get_public_key()),
+                bootstrap_establisher,
+                keypair.clone(),
+                0,
+                Version::from_str("TEST.1.10").unwrap(),
+            )
+            .await
+            .unwrap()
+            .unwrap(),
+        );
+        bootstrap_interfaces.push(bootstrap_interface);
+        server_states.push(final_state);
+        servers.push((
+            SocketAddr::new(BASE_BOOTSTRAP_IP, 16 + index),
+            keypair.get_public_key(),
+        ));
+    }
+
+    // route the connections of the client to the servers, each one from another ip
+    let (mut remote_establisher, mut remote_interface) = mock_establisher::new();
+    let server_addrs: Vec<SocketAddr> = servers.iter().map(|(addr, _)| *addr).collect();
+    let router = tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((remote_rw, conn_addr, resp)) = remote_interface
+            .wait_connection_attempt_from_controller()
+            .await
+        {
+            resp.send(true)
+                .expect("could not send connection accept to remote");
+            let index = server_addrs
+                .iter()
+                .position(|addr| *addr == conn_addr)
+                .expect("client connected to an unknown bootstrap ip");
+            let remote_addr = SocketAddr::new(
+                IpAddr::V4(Ipv4Addr::new(82, 245, 72, connections.len() as u8)),
+                10000,
+            );
+            let bootstrap_rw = bootstrap_interfaces[index]
+                .connect_to_controller(&remote_addr)
+                .await
+                .expect("could not connect to bootstrap");
+            tokio::spawn(bridge_mock_streams(remote_rw, bootstrap_rw));
+            connections.push(conn_addr);
+        }
+        (connections, bootstrap_interfaces)
+    });
+
+    let (mut client_selector_manager, client_selector_controller) =
+        start_selector_worker(selector_config).expect("could not start client selector controller");
+    let final_state_client = Arc::new(RwLock::new(FinalState::default_with_pos(
+        PoSFinalState::new(
+            &"".to_string(),
+            &rolls_path,
+            2,
+            2,
+            client_selector_controller,
+        )
+        .unwrap(),
+    )));
+    let bootstrap_res = parallel_get_state(
+        &get_bootstrap_config(servers[0].1),
+        &mut remote_establisher,
+        &servers,
+        2,
+        GlobalBootstrapState::new(final_state_client.clone()),
+        Version::from_str("TEST.1.10").unwrap(),
+        None,
+    )
+    .await
+    .expect("parallel bootstrap failed");
+    drop(remote_establisher);
+
+    // the hash of the second server does not match: its range is dropped and resumed from the first server,
+    // which keeps the range it already streamed
+    let (connections, _bootstrap_interfaces) = router.await.unwrap();
+    assert_eq!(connections, vec![servers[0].0, servers[1].0, servers[0].0]);
+    assert_eq_final_state(&server_states[0].read(), &final_state_client.read());
+    assert_eq!(get_peers().0, bootstrap_res.peers.unwrap().0);
+    assert_eq_bootstrap_graph(&get_boot_state(), &bootstrap_res.graph.unwrap());
+
+    for bootstrap_manager in bootstrap_managers {
+        bootstrap_manager
+            .stop()
+            .await
+            .expect("could not stop bootstrap server");
+    }
+    for mut selector_manager in selector_managers {
+        selector_manager.stop();
+    }
+    client_selector_manager.stop();
+}
@@ -291,6 +291,13 @@ pub fn get_bootstrap_config(bootstrap_public_key: PublicKey) -> BootstrapConfig
         max_ledger_changes_count: MAX_LEDGER_CHANGES_COUNT,
         max_parameters_size: MAX_PARAMETERS_SIZE,
         max_changes_slot_count: 1000,
+        checkpoint_path: None,
+        checkpoint_interval: 10000.into(),
+        parallel_bootstrap_servers: 1,
+        ip_allow_list: Vec::new(),
+        ip_deny_list: Vec::new(),
+        authorized_client_keys: Vec::new(),
+        client_keypair: None,
     }
 }
 
@@ -1,12 +1,19 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use crate::repl::Output;
+use crate::settings::SETTINGS;
 use anyhow::{anyhow, bail, Result};
 use console::style;
+use dialoguer::Confirm;
 use massa_models::api::{
-    AddressInfo, CompactAddressInfo, DatastoreEntryInput, EventFilter, OperationInput,
+    AddressInfo, CompactAddressInfo, DatastoreEntryInput, EventFilter, GasEstimationInput,
+    OperationInput, PageRequest, PoolOperationFilter,
 };
 use massa_models::api::{ReadOnlyBytecodeExecution, ReadOnlyCall};
+use massa_models::config::MAX_GAS_PER_BLOCK;
+use massa_models::execution::ReadOnlyResult;
+use massa_models::ip_range::IpRange;
+use massa_models::ledger_models::LedgerSubEntry;
 use massa_models::node::NodeId;
 use massa_models::prehash::PreHashMap;
 use massa_models::timeslots::get_current_latest_block_slot;
@@ -18,7 +25,7 @@ use massa_models::{
     operation::{Operation, OperationId, OperationType},
     slot::Slot,
 };
-use massa_sdk::Client;
+use massa_sdk::{Client, RpcResult};
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use massa_wallet::Wallet;
@@ -113,6 +120,47 @@ pub enum Command {
     )]
     node_remove_from_whitelist,
 
+    #[strum(
+        ascii_case_insensitive,
+        message = "show the IP ranges allowed and denied to bootstrap from the node"
+    )]
+    node_get_bootstrap_access_lists,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[IpRange]"),
+        message = "allow given IP addresses or CIDR ranges to bootstrap from the node"
+    )]
+    node_add_to_bootstrap_allow_list,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[IpRange]"),
+        message = "remove given IP addresses or CIDR ranges from the bootstrap allow list"
+    )]
+    node_remove_from_bootstrap_allow_list,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[IpRange]"),
+        message = "deny given IP addresses or CIDR ranges to bootstrap from the node"
+    )]
+    node_add_to_bootstrap_deny_list,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "[IpRange]"),
+        message = "remove given IP addresses or CIDR ranges from the bootstrap deny list"
+    )]
+    node_remove_from_bootstrap_deny_list,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "OperationId1 OperationId2 ..."),
+        message = "get the call trees recorded during the latest execution of given operations"
+    )]
+    node_get_execution_traces,
+
     #[strum(
         ascii_case_insensitive,
         message = "show the status of the node (reachable? number of peers connected, consensus, version, config parameter summary...)"
@@ -133,6 +181,13 @@ pub enum Command {
     )]
     get_datastore_entry,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "Address [Key]"),
+        message = "get the final balance, or the final datastore entry of a key (must be UTF-8), of an address with its proof against the final ledger hash"
+    )]
+    get_ledger_proof,
+
     #[strum(
         ascii_case_insensitive,
         props(args = "BlockId"),
@@ -157,12 +212,26 @@ pub enum Command {
     #[strum(
         ascii_case_insensitive,
         props(
-            args = "start=Slot end=Slot emitter_address=Address caller_address=Address operation_id=OperationId is_final=bool"
+            args = "start=Slot end=Slot emitter_address=Address caller_address=Address operation_id=OperationId is_final=bool cursor=EventCursor"
         ),
         message = "show events emitted by smart contracts with various filters"
     )]
     get_filtered_sc_output_event,
 
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "OperationId1 OperationId2 ..."),
+        message = "show the status of a list of operations in the pool (rank, validity, reason of removal ...)"
+    )]
+    get_pool_operation_status,
+
+    #[strum(
+        ascii_case_insensitive,
+        props(args = "creator_address=Address thread=u8 cursor=OperationId"),
+        message = "list the operations of the pool, by thread and by decreasing fee quality"
+    )]
+    list_pool_operations,
+
     #[strum(
         ascii_case_insensitive,
         message = "show wallet info (keys, addresses, balances ...)"
@@ -220,7 +289,7 @@ pub enum Command {
     #[strum(
         ascii_case_insensitive,
         props(args = "SenderAddress PathToBytecode MaxGas GasPrice Fee",),
-        message = "create and send an operation containing byte code"
+        message = "create and send an operation containing byte code, MaxGas can be auto to estimate it"
     )]
     send_smart_contract,
 
@@ -229,7 +298,7 @@ pub enum Command {
         props(
             args = "SenderAddress TargetAddress FunctionName Parameter MaxGas GasPrice Coins Fee",
         ),
-        message = "create and send an operation to call a function of a smart contract"
+        message = "create and send an operation to call a function of a smart contract, MaxGas can be auto to estimate it"
     )]
     call_smart_contract,
 
@@ -531,7 +600,7 @@ impl Command {
 
             Command::get_addresses => {
                 let addresses = parse_vec::<Address>(parameters)?;
-                match client.public.get_addresses(addresses).await {
+                match get_addresses_info(client, addresses).await {
                     Ok(addresses_info) => Ok(Box::new(addresses_info)),
                     Err(e) => rpc_error!(e),
                 }
@@ -545,10 +614,23 @@ impl Command {
                 let key = parameters[1].as_bytes().to_vec();
                 match client
                     .public
-                    .get_datastore_entries(vec![DatastoreEntryInput { address, key }])
+                    .get_datastore_entries(vec![DatastoreEntryInput { address, key }], None, None)
                     .await
                 {
-                    Ok(result) => Ok(Box::new(result)),
+                    Ok(result) => Ok(Box::new(result.content)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::get_ledger_proof => {
+                let sub_entry = match parameters.len() {
+                    1 => LedgerSubEntry::Balance,
+                    2 => LedgerSubEntry::Datastore(parameters[1].as_bytes().to_vec()),
+                    _ => bail!("invalid number of parameters"),
+                };
+                let address = parameters[0].parse::<Address>()?;
+                match client.public.get_ledger_proof(address, sub_entry).await {
+                    Ok(proof) => Ok(Box::new(proof)),
                     Err(e) => rpc_error!(e),
                 }
             }
@@ -566,28 +648,73 @@ impl Command {
 
             Command::get_endorsements => {
                 let endorsements = parse_vec::<EndorsementId>(parameters)?;
-                match client.public.get_endorsements(endorsements).await {
-                    Ok(endorsements_info) => Ok(Box::new(endorsements_info)),
+                match client.public.get_endorsements(endorsements, None).await {
+                    Ok(endorsements_info) => Ok(Box::new(endorsements_info.content)),
                     Err(e) => rpc_error!(e),
                 }
             }
 
             Command::get_operations => {
                 let operations = parse_vec::<OperationId>(parameters)?;
-                match client.public.get_operations(operations).await {
-                    Ok(operations_info) => Ok(Box::new(operations_info)),
+                match client.public.get_operations(operations, None).await {
+                    Ok(operations_info) => Ok(Box::new(operations_info.content)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::get_pool_operation_status => {
+                let operations = parse_vec::<OperationId>(parameters)?;
+                match client.public.get_pool_operation_status(operations).await {
+                    Ok(statuses) => Ok(Box::new(statuses)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+
+            Command::list_pool_operations => {
+                let p_list: [&str; 3] = ["creator_address", "thread", "cursor"];
+                let mut p: HashMap<&str, &str> = HashMap::new();
+                for v in parameters {
+                    let s: Vec<&str> = v.split('=').collect();
+                    if s.len() == 2 && p_list.contains(&s[0]) {
+                        p.insert(s[0], s[1]);
+                    } else {
+                        bail!("invalid parameter");
+                    }
+                }
+                let filter = PoolOperationFilter {
+                    creator_address: parse_key_value(&p, p_list[0]),
+                    thread: parse_key_value(&p, p_list[1]),
+                };
+                let page = p.get(p_list[2]).map(|cursor| PageRequest {
+                    cursor: Some(cursor.to_string()),
+                    limit: usize::MAX,
+                    ..Default::default()
+                });
+                match client.public.list_pool_operations(filter, page).await {
+                    Ok(operations) => {
+                        if let Some(next_cursor) = operations.next_cursor {
+                            if !json {
+                                client_warning!(format!(
+                                    "more operations are available, get them with cursor={}",
+                                    next_cursor
+                                ));
+                            }
+                        }
+                        Ok(Box::new(operations.content))
+                    }
                     Err(e) => rpc_error!(e),
                 }
             }
 
             Command::get_filtered_sc_output_event => {
-                let p_list: [&str; 6] = [
+                let p_list: [&str; 7] = [
                     "start",
                     "end",
                     "emitter_address",
                     "caller_address",
                     "operation_id",
                     "is_final",
+                    "cursor",
                 ];
                 let mut p: HashMap<&str, &str> = HashMap::new();
                 for v in parameters {
@@ -606,8 +733,27 @@ impl Command {
                     original_operation_id: parse_key_value(&p, p_list[4]),
                     is_final: parse_key_value(&p, p_list[5]),
                 };
-                match client.public.get_filtered_sc_output_event(filter).await {
-                    Ok(events) => Ok(Box::new(events)),
+                let page = p.get(p_list[6]).map(|cursor| PageRequest {
+                    cursor: Some(cursor.to_string()),
+                    limit: usize::MAX,
+                    ..Default::default()
+                });
+                match client
+                    .public
+                    .get_filtered_sc_output_event(filter, page)
+                    .await
+                {
+                    Ok(events) => {
+                        if let Some(next_cursor) = events.next_cursor {
+                            if !json {
+                                client_warning!(format!(
+                                    "more events are available, get them with cursor={}",
+                                    next_cursor
+                                ));
+                            }
+                        }
+                        Ok(Box::new(events.content))
+                    }
                     Err(e) => rpc_error!(e),
                 }
             }
@@ -616,9 +762,7 @@ impl Command {
                 if !json {
                     client_warning!("do not share your key");
                 }
-                match client
-                    .public
-                    .get_addresses(wallet.get_full_wallet().keys().copied().collect())
+                match get_addresses_info(client, wallet.get_full_wallet().keys().copied().collect())
                     .await
                 {
                     Ok(addresses_info) => {
@@ -690,9 +834,9 @@ impl Command {
                     {
                         Some(total) => {
                             if let Ok(addresses_info) =
-                                client.public.get_addresses(vec![addr]).await
+                                client.public.get_addresses(vec![addr], None, None).await
                             {
-                                match addresses_info.get(0) {
+                                match addresses_info.content.get(0) {
                                     Some(info) => {
                                         if info.candidate_balance < total {
                                             client_warning!("this operation may be rejected due to insufficient balance");
@@ -734,8 +878,10 @@ impl Command {
                 let fee = parameters[2].parse::<Amount>()?;
 
                 if !json {
-                    if let Ok(addresses_info) = client.public.get_addresses(vec![addr]).await {
-                        match addresses_info.get(0) {
+                    if let Ok(addresses_info) =
+                        client.public.get_addresses(vec![addr], None, None).await
+                    {
+                        match addresses_info.content.get(0) {
                             Some(info) => {
                                 if info.candidate_balance < fee
                                     || roll_count > info.candidate_roll_count
@@ -769,8 +915,10 @@ impl Command {
                 let fee = parameters[3].parse::<Amount>()?;
 
                 if !json {
-                    if let Ok(addresses_info) = client.public.get_addresses(vec![addr]).await {
-                        match addresses_info.get(0) {
+                    if let Ok(addresses_info) =
+                        client.public.get_addresses(vec![addr], None, None).await
+                    {
+                        match addresses_info.content.get(0) {
                             Some(info) => {
                                 if info.candidate_balance < fee {
                                     client_warning!("this operation may be rejected due to insufficient balance");
@@ -829,9 +977,28 @@ impl Command {
                 }
                 let addr = parameters[0].parse::<Address>()?;
                 let path = parameters[1].parse::<PathBuf>()?;
-                let max_gas = parameters[2].parse::<u64>()?;
+                let max_gas = parse_max_gas(&parameters[2])?;
                 let gas_price = parameters[3].parse::<Amount>()?;
                 let fee = parameters[4].parse::<Amount>()?;
+                let data = get_file_as_byte_vec(&path).await?;
+                let datastore = BTreeMap::new();
+                let max_gas = match max_gas {
+                    Some(max_gas) => max_gas,
+                    None => {
+                        estimate_max_gas(
+                            client,
+                            OperationType::ExecuteSC {
+                                data: data.clone(),
+                                max_gas: 0,
+                                gas_price,
+                                datastore: datastore.clone(),
+                            },
+                            addr,
+                            json,
+                        )
+                        .await?
+                    }
+                };
 
                 if !json {
                     match gas_price
@@ -840,9 +1007,9 @@ impl Command {
                     {
                         Some(total) => {
                             if let Ok(addresses_info) =
-                                client.public.get_addresses(vec![addr]).await
+                                client.public.get_addresses(vec![addr], None, None).await
                             {
-                                match addresses_info.get(0) {
+                                match addresses_info.content.get(0) {
                                     Some(info) => {
                                         if info.candidate_balance < total {
                                             client_warning!("this operation may be rejected due to insufficient balance");
@@ -859,7 +1026,6 @@ impl Command {
                         }
                     }
                 };
-                let data = get_file_as_byte_vec(&path).await?;
                 if !json {
                     let max_block_size = match client.public.get_status().await {
                         Ok(node_status) => node_status.config.max_block_size,
@@ -869,7 +1035,6 @@ impl Command {
                         client_warning!("bytecode size exceeded half of the maximum size of a block, operation will certainly be rejected");
                     }
                 }
-                let datastore = BTreeMap::new();
 
                 send_operation(
                     client,
@@ -894,20 +1059,41 @@ impl Command {
                 let target_addr = parameters[1].parse::<Address>()?;
                 let target_func = parameters[2].clone();
                 let param = parameters[3].clone();
-                let max_gas = parameters[4].parse::<u64>()?;
+                let max_gas = parse_max_gas(&parameters[4])?;
                 let gas_price = parameters[5].parse::<Amount>()?;
                 let coins = parameters[6].parse::<Amount>()?;
                 let fee = parameters[7].parse::<Amount>()?;
+                let max_gas = match max_gas {
+                    Some(max_gas) => max_gas,
+                    None => {
+                        estimate_max_gas(
+                            client,
+                            OperationType::CallSC {
+                                target_addr,
+                                target_func: target_func.clone(),
+                                param: param.clone(),
+                                max_gas: 0,
+                                coins,
+                                gas_price,
+                            },
+                            addr,
+                            json,
+                        )
+                        .await?
+                    }
+                };
                 if !json {
                     match gas_price
                         .checked_mul_u64(max_gas)
                         .and_then(|x| x.checked_add(fee))
                     {
                         Some(total) => {
-                            if let Ok(addresses_info) =
-                                client.public.get_addresses(vec![target_addr]).await
+                            if let Ok(addresses_info) = client
+                                .public
+                                .get_addresses(vec![target_addr], None, None)
+                                .await
                             {
-                                match addresses_info.get(0) {
+                                match addresses_info.content.get(0) {
                                     Some(info) => {
                                         if info.candidate_balance < total {
                                             client_warning!("this operation may be rejected due to insufficient balance");
@@ -1040,6 +1226,81 @@ impl Command {
                 }
                 Ok(Box::new(()))
             }
+            Command::node_get_bootstrap_access_lists => {
+                match client.private.get_bootstrap_access_lists().await {
+                    Ok(access_lists) => Ok(Box::new(access_lists)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
+            Command::node_add_to_bootstrap_allow_list => {
+                let ranges = parse_vec::<IpRange>(parameters)?;
+                match client
+                    .private
+                    .node_add_to_bootstrap_allow_list(ranges)
+                    .await
+                {
+                    Ok(()) => {
+                        if !json {
+                            println!("IP ranges successfully added to the bootstrap allow list!")
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+                Ok(Box::new(()))
+            }
+            Command::node_remove_from_bootstrap_allow_list => {
+                let ranges = parse_vec::<IpRange>(parameters)?;
+                match client
+                    .private
+                    .node_remove_from_bootstrap_allow_list(ranges)
+                    .await
+                {
+                    Ok(()) => {
+                        if !json {
+                            println!(
+                                "IP ranges successfully removed from the bootstrap allow list!"
+                            )
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+                Ok(Box::new(()))
+            }
+            Command::node_add_to_bootstrap_deny_list => {
+                let ranges = parse_vec::<IpRange>(parameters)?;
+                match client.private.node_add_to_bootstrap_deny_list(ranges).await {
+                    Ok(()) => {
+                        if !json {
+                            println!("IP ranges successfully added to the bootstrap deny list!")
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+                Ok(Box::new(()))
+            }
+            Command::node_remove_from_bootstrap_deny_list => {
+                let ranges = parse_vec::<IpRange>(parameters)?;
+                match client
+                    .private
+                    .node_remove_from_bootstrap_deny_list(ranges)
+                    .await
+                {
+                    Ok(()) => {
+                        if !json {
+                            println!("IP ranges successfully removed from the bootstrap deny list!")
+                        }
+                    }
+                    Err(e) => rpc_error!(e),
+                }
+                Ok(Box::new(()))
+            }
+            Command::node_get_execution_traces => {
+                let operation_ids = parse_vec::<OperationId>(parameters)?;
+                match client.private.get_execution_traces(operation_ids).await {
+                    Ok(traces) => Ok(Box::new(traces)),
+                    Err(e) => rpc_error!(e),
+                }
+            }
         }
     }
 }
@@ -1094,6 +1355,75 @@ async fn send_operation(
     }
 }
 
+/// Parses a max gas parameter, `None` meaning that it should be estimated
+fn parse_max_gas(parameter: &str) -> Result<Option<u64>> {
+    if parameter.eq_ignore_ascii_case("auto") {
+        Ok(None)
+    } else {
+        Ok(Some(parameter.parse::<u64>()?))
+    }
+}
+
+/// Estimates the max gas of an `ExecuteSC` or `CallSC` operation with the configured margin,
+/// and asks the user whether to use it unless the output is json
+async fn estimate_max_gas(
+    client: &Client,
+    op: OperationType,
+    sender_address: Address,
+    json: bool,
+) -> Result<u64> {
+    let estimation = match client
+        .public
+        .estimate_gas(GasEstimationInput { op, sender_address })
+        .await
+    {
+        Ok(estimation) => estimation,
+        Err(e) => rpc_error!(e),
+    };
+    if let ReadOnlyResult::Error(e) = &estimation.result {
+        bail!("cannot estimate max gas: {}", e);
+    }
+    let max_gas = add_gas_margin(estimation.gas_cost, SETTINGS.gas_estimation_margin);
+    if !json {
+        println!("{}", estimation);
+        if !Confirm::new()
+            .with_prompt(format!("Use {} as max gas?", max_gas))
+            .default(true)
+            .interact()?
+        {
+            bail!("no operation was sent");
+        }
+    }
+    Ok(max_gas)
+}
+
+/// Adds a margin of `margin_percent` percents to an estimated gas cost,
+/// without going over the max gas of a block
+fn add_gas_margin(gas_cost: u64, margin_percent: u64) -> u64 {
+    gas_cost
+        .saturating_add(gas_cost.saturating_mul(margin_percent) / 100)
+        .min(MAX_GAS_PER_BLOCK)
+}
+
+/// Gets the info of every given address, requesting as many pages as needed
+async fn get_addresses_info(
+    client: &Client,
+    addresses: Vec<Address>,
+) -> RpcResult<Vec<AddressInfo>> {
+    let mut addresses_info = Vec::with_capacity(addresses.len());
+    while addresses_info.len() < addresses.len() {
+        let page = client
+            .public
+            .get_addresses(addresses[addresses_info.len()..].to_vec(), None, None)
+            .await?;
+        if page.content.is_empty() {
+            break;
+        }
+        addresses_info.extend(page.content);
+    }
+    Ok(addresses_info)
+}
+
 /// TODO: ugly utilities functions
 /// takes a slice of string and makes it into a `Vec<T>`
 pub fn parse_vec<T: std::str::FromStr>(args: &[String]) -> anyhow::Result<Vec<T>, T::Err> {
@@ -9,9 +9,12 @@ use erased_serde::{Serialize, Serializer};
 use glob::glob;
 use massa_models::api::{
     AddressInfo, BlockInfo, DatastoreEntryOutput, EndorsementInfo, NodeStatus, OperationInfo,
+    PoolOperationStatus,
 };
 use massa_models::composite::PubkeySig;
-use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::execution::{ExecuteReadOnlyResponse, ExecutionTrace};
+use massa_models::ip_range::IpAccessLists;
+use massa_models::ledger_proof::LedgerEntryProof;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::PreHashSet;
 use massa_models::{address::Address, operation::OperationId};
@@ -290,6 +293,14 @@ impl Output for Vec<OperationInfo> {
     }
 }
 
+impl Output for Vec<PoolOperationStatus> {
+    fn pretty_print(&self) {
+        for status in self {
+            println!("{}", status);
+        }
+    }
+}
+
 impl Output for Vec<OperationId> {
     fn pretty_print(&self) {
         for operation_id in self {
@@ -325,3 +336,26 @@ impl Output for ExecuteReadOnlyResponse {
         println!("{}", self);
     }
 }
+
+impl Output for LedgerEntryProof {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for IpAccessLists {
+    fn pretty_print(&self) {
+        println!("{}", self);
+    }
+}
+
+impl Output for Vec<Option<ExecutionTrace>> {
+    fn pretty_print(&self) {
+        for trace in self {
+            match trace {
+                Some(trace) => println!("{}", trace),
+                None => println!("No execution trace available\n"),
+            }
+        }
+    }
+}
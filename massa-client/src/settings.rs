@@ -16,6 +16,7 @@ pub struct Settings {
     pub history: usize,
     pub history_file_path: PathBuf,
     pub timeout: MassaTime,
+    pub gas_estimation_margin: u64,
 }
 
 #[derive(Debug, Deserialize, Clone)]
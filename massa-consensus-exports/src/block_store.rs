@@ -0,0 +1,39 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This file defines the interface of the persistent store of final blocks
+
+use massa_models::block::{BlockId, WrappedBlock};
+use massa_models::endorsement::{EndorsementId, WrappedEndorsement};
+use massa_models::operation::{OperationId, WrappedOperation};
+
+/// Interface of a persistent store of final blocks, along with their operations and endorsements.
+/// It keeps blocks available after they are pruned from the block graph and the shared storage.
+pub trait BlockStoreController: Send + Sync {
+    /// Persists final blocks along with the operations they contain.
+    /// Storing an already stored block overwrites it.
+    fn store_final_blocks(&self, blocks: Vec<(WrappedBlock, Vec<WrappedOperation>)>);
+
+    /// Gets a stored block
+    fn get_block(&self, id: &BlockId) -> Option<WrappedBlock>;
+
+    /// Gets a stored operation along with the final block containing it
+    fn get_operation(&self, id: &OperationId) -> Option<(WrappedOperation, BlockId)>;
+
+    /// Gets a stored endorsement along with the final block containing it
+    fn get_endorsement(&self, id: &EndorsementId) -> Option<(WrappedEndorsement, BlockId)>;
+
+    /// Removes the blocks whose period is strictly below `min_period`, along with their operations and endorsements
+    fn prune(&self, min_period: u64);
+
+    /// Returns a boxed clone of self.
+    /// Useful to allow cloning `Box<dyn BlockStoreController>`.
+    fn clone_box(&self) -> Box<dyn BlockStoreController>;
+}
+
+/// Allow cloning `Box<dyn BlockStoreController>`
+/// Uses `BlockStoreController::clone_box` internally
+impl Clone for Box<dyn BlockStoreController> {
+    fn clone(&self) -> Box<dyn BlockStoreController> {
+        self.clone_box()
+    }
+}
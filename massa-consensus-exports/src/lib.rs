@@ -6,10 +6,12 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 
+pub use block_store::BlockStoreController;
 pub use consensus_controller::{ConsensusCommandSender, ConsensusEventReceiver, ConsensusManager};
 pub use error::ConsensusError;
 pub use settings::ConsensusConfig;
 
+mod block_store;
 mod consensus_controller;
 
 /// consensus errors
@@ -46,16 +46,18 @@
 //! `massa-models` crate sources.
 use massa_execution_exports::ExecutionController;
 use massa_graph::settings::GraphConfig;
+use massa_models::block::WrappedBlock;
 use massa_pool_exports::PoolController;
 use massa_pos_exports::SelectorController;
 use massa_protocol_exports::{ProtocolCommandSender, ProtocolEventReceiver};
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 
 use crate::{
     commands::{ConsensusCommand, ConsensusManagementCommand},
     events::ConsensusEvent,
+    BlockStoreController,
 };
 
 /// Consensus full configuration (static + user defined)
@@ -101,6 +103,12 @@ pub struct ConsensusConfig {
     pub max_gas_per_block: u64,
     /// channel size
     pub channel_size: usize,
+    /// whether new and finalized blocks are broadcast to external subscribers
+    pub broadcast_enabled: bool,
+    /// whether the final block store is an archive that is never pruned
+    pub block_store_archive: bool,
+    /// number of final periods kept in the final block store when it is not an archive
+    pub block_store_retention_periods: u64,
 }
 
 impl From<&ConsensusConfig> for GraphConfig {
@@ -143,6 +151,12 @@ pub struct ConsensusWorkerChannels {
     pub controller_event_tx: mpsc::Sender<ConsensusEvent>,
     /// Channel receiving consensus management commands.
     pub controller_manager_rx: mpsc::Receiver<ConsensusManagementCommand>,
+    /// Broadcast channel for blocks newly integrated in the graph.
+    pub block_sender: broadcast::Sender<WrappedBlock>,
+    /// Broadcast channel for newly finalized blocks.
+    pub finalized_block_sender: broadcast::Sender<WrappedBlock>,
+    /// Optional persistent store of final blocks.
+    pub final_block_store: Option<Box<dyn BlockStoreController>>,
 }
 
 /// Public channels associated to the consensus module.
@@ -158,6 +172,12 @@ pub struct ConsensusChannels {
     pub pool_command_sender: Box<dyn PoolController>,
     /// selector controller
     pub selector_controller: Box<dyn SelectorController>,
+    /// broadcast channel for blocks newly integrated in the graph
+    pub block_sender: broadcast::Sender<WrappedBlock>,
+    /// broadcast channel for newly finalized blocks
+    pub finalized_block_sender: broadcast::Sender<WrappedBlock>,
+    /// optional persistent store of final blocks
+    pub final_block_store: Option<Box<dyn BlockStoreController>>,
 }
 
 #[cfg(feature = "testing")]
@@ -213,6 +233,9 @@ impl Default for ConsensusConfig {
             max_item_return_count: 100,
             max_gas_per_block: MAX_GAS_PER_BLOCK,
             channel_size: CHANNEL_SIZE,
+            broadcast_enabled: false,
+            block_store_archive: false,
+            block_store_retention_periods: 1000,
         }
     }
 }
@@ -0,0 +1,340 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Module to persist final blocks on disk
+
+use massa_consensus_exports::BlockStoreController;
+use massa_models::{
+    block::{BlockDeserializer, BlockId, WrappedBlock},
+    endorsement::{EndorsementId, WrappedEndorsement},
+    operation::{OperationDeserializer, OperationId, WrappedOperation},
+    slot::{Slot, SLOT_KEY_SIZE},
+    wrapped::{WrappedDeserializer, WrappedSerializer},
+};
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use rocksdb::{ColumnFamilyDescriptor, IteratorMode, Options, ReadOptions, WriteBatch, DB};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const BLOCKS_CF: &str = "blocks";
+const SLOT_INDEX_CF: &str = "slot_index";
+const OPERATIONS_CF: &str = "operations";
+const ENDORSEMENTS_CF: &str = "endorsements";
+const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
+const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
+const CF_ERROR: &str = "critical: rocksdb column family operation failed";
+const SER_ERROR: &str = "critical: block store serialization failed";
+const DESER_ERROR: &str = "critical: stored block data is corrupted";
+const BLOCK_ID_SIZE_BYTES: usize = massa_hash::HASH_SIZE_BYTES;
+
+/// Reads the block ID at the start of a stored value or key
+fn block_id_from_bytes(bytes: &[u8]) -> BlockId {
+    BlockId::from_bytes(
+        bytes
+            .get(..BLOCK_ID_SIZE_BYTES)
+            .and_then(|id| id.try_into().ok())
+            .expect(DESER_ERROR),
+    )
+}
+
+/// Disk block store module
+///
+/// Contains a `RocksDB` DB instance holding the serialized final blocks keyed by block ID,
+/// their operations keyed by operation ID and prefixed with the ID of their block,
+/// the IDs of the blocks containing their endorsements keyed by endorsement ID,
+/// and an index of the blocks by slot used for pruning.
+/// Slot index keys are the slot key followed by the block ID.
+#[derive(Clone)]
+pub struct FinalBlockStore {
+    db: Arc<DB>,
+    thread_count: u8,
+    endorsement_count: u32,
+}
+
+impl FinalBlockStore {
+    /// Create and initialize a new `FinalBlockStore`.
+    ///
+    /// # Arguments
+    /// * path: path to the desired disk block store db directory
+    /// * `thread_count`: number of threads
+    /// * `endorsement_count`: number of endorsements per block
+    pub fn new(path: PathBuf, thread_count: u8, endorsement_count: u32) -> Self {
+        let mut db_opts = Options::default();
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+
+        let db = DB::open_cf_descriptors(
+            &db_opts,
+            path,
+            vec![
+                ColumnFamilyDescriptor::new(BLOCKS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(SLOT_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(OPERATIONS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(ENDORSEMENTS_CF, Options::default()),
+            ],
+        )
+        .expect(OPEN_ERROR);
+
+        FinalBlockStore {
+            db: Arc::new(db),
+            thread_count,
+            endorsement_count,
+        }
+    }
+
+    /// Deserializes a stored block.
+    /// Stored blocks were checked before becoming final so only structural limits are enforced.
+    fn deserialize_block(&self, bytes: &[u8]) -> WrappedBlock {
+        let deserializer = WrappedDeserializer::new(BlockDeserializer::new(
+            self.thread_count,
+            u32::MAX,
+            self.endorsement_count,
+        ));
+        let (_, block) = deserializer
+            .deserialize::<DeserializeError>(bytes)
+            .expect(DESER_ERROR);
+        block
+    }
+
+    /// Deserializes a stored operation, see `deserialize_block`
+    fn deserialize_operation(&self, bytes: &[u8]) -> WrappedOperation {
+        let deserializer = WrappedDeserializer::new(OperationDeserializer::new(
+            u64::MAX,
+            u16::MAX,
+            u32::MAX,
+            u64::MAX,
+            u8::MAX,
+            u64::MAX,
+        ));
+        let (_, operation) = deserializer
+            .deserialize::<DeserializeError>(bytes)
+            .expect(DESER_ERROR);
+        operation
+    }
+}
+
+impl BlockStoreController for FinalBlockStore {
+    /// Allows persisting final blocks
+    fn store_final_blocks(&self, blocks: Vec<(WrappedBlock, Vec<WrappedOperation>)>) {
+        let blocks_handle = self.db.cf_handle(BLOCKS_CF).expect(CF_ERROR);
+        let slot_index_handle = self.db.cf_handle(SLOT_INDEX_CF).expect(CF_ERROR);
+        let operations_handle = self.db.cf_handle(OPERATIONS_CF).expect(CF_ERROR);
+        let endorsements_handle = self.db.cf_handle(ENDORSEMENTS_CF).expect(CF_ERROR);
+
+        let serializer = WrappedSerializer::new();
+        let mut batch = WriteBatch::default();
+        for (block, operations) in blocks {
+            for operation in operations {
+                let mut value = block.id.to_bytes().to_vec();
+                serializer
+                    .serialize(&operation, &mut value)
+                    .expect(SER_ERROR);
+                batch.put_cf(operations_handle, operation.id.to_bytes(), value);
+            }
+            for endorsement in block.content.header.content.endorsements.iter() {
+                batch.put_cf(
+                    endorsements_handle,
+                    endorsement.id.to_bytes(),
+                    block.id.to_bytes(),
+                );
+            }
+            batch.put_cf(
+                slot_index_handle,
+                [
+                    &block.content.header.content.slot.to_bytes_key()[..],
+                    block.id.to_bytes(),
+                ]
+                .concat(),
+                b"",
+            );
+            let mut value = Vec::new();
+            serializer.serialize(&block, &mut value).expect(SER_ERROR);
+            batch.put_cf(blocks_handle, block.id.to_bytes(), value);
+        }
+        self.db.write(batch).expect(CRUD_ERROR);
+    }
+
+    fn get_block(&self, id: &BlockId) -> Option<WrappedBlock> {
+        let handle = self.db.cf_handle(BLOCKS_CF).expect(CF_ERROR);
+        self.db
+            .get_cf(handle, id.to_bytes())
+            .expect(CRUD_ERROR)
+            .map(|bytes| self.deserialize_block(&bytes))
+    }
+
+    fn get_operation(&self, id: &OperationId) -> Option<(WrappedOperation, BlockId)> {
+        let handle = self.db.cf_handle(OPERATIONS_CF).expect(CF_ERROR);
+        self.db
+            .get_cf(handle, id.to_bytes())
+            .expect(CRUD_ERROR)
+            .map(|bytes| {
+                let block_id = block_id_from_bytes(&bytes);
+                (
+                    self.deserialize_operation(&bytes[BLOCK_ID_SIZE_BYTES..]),
+                    block_id,
+                )
+            })
+    }
+
+    /// Endorsements are read from the header of their block
+    fn get_endorsement(&self, id: &EndorsementId) -> Option<(WrappedEndorsement, BlockId)> {
+        let handle = self.db.cf_handle(ENDORSEMENTS_CF).expect(CF_ERROR);
+        let block_id =
+            block_id_from_bytes(&self.db.get_cf(handle, id.to_bytes()).expect(CRUD_ERROR)?);
+        let block = self.get_block(&block_id)?;
+        block
+            .content
+            .header
+            .content
+            .endorsements
+            .into_iter()
+            .find(|endorsement| &endorsement.id == id)
+            .map(|endorsement| (endorsement, block_id))
+    }
+
+    /// Iterates the slot index up to `min_period` and removes every block found along with its content
+    fn prune(&self, min_period: u64) {
+        let blocks_handle = self.db.cf_handle(BLOCKS_CF).expect(CF_ERROR);
+        let slot_index_handle = self.db.cf_handle(SLOT_INDEX_CF).expect(CF_ERROR);
+        let operations_handle = self.db.cf_handle(OPERATIONS_CF).expect(CF_ERROR);
+        let endorsements_handle = self.db.cf_handle(ENDORSEMENTS_CF).expect(CF_ERROR);
+
+        let mut opt = ReadOptions::default();
+        opt.set_iterate_upper_bound(Slot::new(min_period, 0).to_bytes_key());
+
+        let mut batch = WriteBatch::default();
+        for (key, _) in self
+            .db
+            .iterator_cf_opt(slot_index_handle, opt, IteratorMode::Start)
+            .flatten()
+        {
+            let block_id = block_id_from_bytes(&key[SLOT_KEY_SIZE..]);
+            if let Some(block) = self.get_block(&block_id) {
+                for op_id in block.content.operations.iter() {
+                    batch.delete_cf(operations_handle, op_id.to_bytes());
+                }
+                for endorsement in block.content.header.content.endorsements.iter() {
+                    batch.delete_cf(endorsements_handle, endorsement.id.to_bytes());
+                }
+            }
+            batch.delete_cf(blocks_handle, block_id.to_bytes());
+            batch.delete_cf(slot_index_handle, key);
+        }
+        self.db.write(batch).expect(CRUD_ERROR);
+    }
+
+    fn clone_box(&self) -> Box<dyn BlockStoreController> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FinalBlockStore;
+    use massa_consensus_exports::BlockStoreController;
+    use massa_hash::Hash;
+    use massa_models::{
+        address::Address,
+        amount::Amount,
+        block::{
+            Block, BlockHeader, BlockHeaderSerializer, BlockId, BlockSerializer, WrappedBlock,
+        },
+        config::{ENDORSEMENT_COUNT, THREAD_COUNT},
+        endorsement::{Endorsement, EndorsementSerializer},
+        operation::{Operation, OperationSerializer, OperationType, WrappedOperation},
+        slot::Slot,
+        wrapped::WrappedContent,
+    };
+    use massa_signature::KeyPair;
+    use std::str::FromStr;
+    use tempfile::TempDir;
+
+    fn block_with_operation(slot: Slot, keypair: &KeyPair) -> (WrappedBlock, WrappedOperation) {
+        let operation = Operation::new_wrapped(
+            Operation {
+                fee: Amount::from_str("1").unwrap(),
+                expire_period: slot.period + 10,
+                op: OperationType::Transaction {
+                    recipient_address: Address::from_public_key(&keypair.get_public_key()),
+                    amount: Amount::from_str("10").unwrap(),
+                },
+            },
+            OperationSerializer::new(),
+            keypair,
+        )
+        .unwrap();
+        let endorsement = Endorsement::new_wrapped(
+            Endorsement {
+                slot,
+                index: 0,
+                endorsed_block: BlockId(Hash::compute_from(&slot.to_bytes_key())),
+            },
+            EndorsementSerializer::new(),
+            keypair,
+        )
+        .unwrap();
+        let header = BlockHeader::new_wrapped(
+            BlockHeader {
+                slot,
+                parents: (0..THREAD_COUNT)
+                    .map(|i| BlockId(Hash::compute_from(&[i])))
+                    .collect(),
+                operation_merkle_root: Hash::compute_from(operation.id.to_bytes()),
+                endorsements: vec![endorsement],
+            },
+            BlockHeaderSerializer::new(),
+            keypair,
+        )
+        .unwrap();
+        let block = Block::new_wrapped(
+            Block {
+                header,
+                operations: vec![operation.id],
+            },
+            BlockSerializer::new(),
+            keypair,
+        )
+        .unwrap();
+        (block, operation)
+    }
+
+    #[test]
+    fn test_block_store_prune() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FinalBlockStore::new(
+            temp_dir.path().to_path_buf(),
+            THREAD_COUNT,
+            ENDORSEMENT_COUNT,
+        );
+        let keypair = KeyPair::generate();
+        let (old_block, old_operation) = block_with_operation(Slot::new(1, 0), &keypair);
+        let (new_block, new_operation) = block_with_operation(Slot::new(5, 1), &keypair);
+        store.store_final_blocks(vec![
+            (old_block.clone(), vec![old_operation.clone()]),
+            (new_block.clone(), vec![new_operation.clone()]),
+        ]);
+
+        // stored items are read back with their block
+        let stored_block = store.get_block(&old_block.id).unwrap();
+        assert_eq!(stored_block.serialized_data, old_block.serialized_data);
+        let (stored_operation, block_id) = store.get_operation(&old_operation.id).unwrap();
+        assert_eq!(stored_operation.id, old_operation.id);
+        assert_eq!(
+            stored_operation.serialized_data,
+            old_operation.serialized_data
+        );
+        assert_eq!(block_id, old_block.id);
+        let endorsement = &new_block.content.header.content.endorsements[0];
+        let (stored_endorsement, block_id) = store.get_endorsement(&endorsement.id).unwrap();
+        assert_eq!(&stored_endorsement, endorsement);
+        assert_eq!(block_id, new_block.id);
+
+        // pruning removes the blocks strictly below the period along with their content
+        store.prune(5);
+        assert!(store.get_block(&old_block.id).is_none());
+        assert!(store.get_operation(&old_operation.id).is_none());
+        let old_endorsement_id = old_block.content.header.content.endorsements[0].id;
+        assert!(store.get_endorsement(&old_endorsement_id).is_none());
+        assert!(store.get_block(&new_block.id).is_some());
+        assert!(store.get_operation(&new_operation.id).is_some());
+    }
+}
@@ -238,6 +238,8 @@ impl ConsensusWorker {
                     massa_trace!("consensus.consensus_worker.run_loop.prune_timer", {});
                     // prune block db
                     let _discarded_final_blocks = self.block_db.prune()?;
+                    // prune final block store
+                    self.prune_final_block_store();
 
                     // reset timer
                     prune_timer.set(sleep( self.cfg.block_db_prune_interval.to_duration()))
@@ -557,6 +559,19 @@ impl ConsensusWorker {
         Ok(())
     }
 
+    /// Remove the blocks that are older than the retention period from the final block store,
+    /// unless it is an archive
+    fn prune_final_block_store(&self) {
+        if self.cfg.block_store_archive {
+            return;
+        }
+        if let Some(final_block_store) = &self.channels.final_block_store {
+            let min_final_period = self.latest_final_periods.iter().min().copied().unwrap_or(0);
+            final_block_store
+                .prune(min_final_period.saturating_sub(self.cfg.block_store_retention_periods));
+        }
+    }
+
     /// Notify execution about blockclique changes and finalized blocks.
     fn notify_execution(&mut self, finalized_blocks: HashMap<Slot, BlockId>) {
         // List new block storage instances that Execution doesn't know about.
@@ -652,6 +667,12 @@ impl ConsensusWorker {
             massa_trace!("consensus.consensus_worker.block_db_changed.integrated", {
                 "block_id": block_id
             });
+            if self.cfg.broadcast_enabled {
+                if let Some(wrapped_block) = storage.read_blocks().get(&block_id) {
+                    // an error only means that there are no subscribers
+                    let _ = self.channels.block_sender.send(wrapped_block.clone());
+                }
+            }
             self.channels
                 .protocol_command_sender
                 .integrated_block(block_id, storage)
@@ -673,11 +694,36 @@ impl ConsensusWorker {
         let timestamp = MassaTime::now(self.clock_compensation)?;
         let finalized_blocks = self.block_db.get_new_final_blocks();
         let mut final_block_slots = HashMap::with_capacity(finalized_blocks.len());
+        let mut stored_final_blocks = Vec::new();
         for b_id in finalized_blocks {
-            if let Some((a_block, _block_store)) = self.block_db.get_active_block(&b_id) {
+            if let Some((a_block, block_store)) = self.block_db.get_active_block(&b_id) {
                 // add to final blocks to notify execution
                 final_block_slots.insert(a_block.slot, b_id);
 
+                // gather the block and its operations for the final block store
+                if self.channels.final_block_store.is_some() {
+                    if let Some(wrapped_block) = block_store.read_blocks().get(&b_id) {
+                        let read_ops = block_store.read_operations();
+                        let operations = wrapped_block
+                            .content
+                            .operations
+                            .iter()
+                            .filter_map(|op_id| read_ops.get(op_id).cloned())
+                            .collect();
+                        stored_final_blocks.push((wrapped_block.clone(), operations));
+                    }
+                }
+
+                // notify subscribers of the newly finalized block
+                if self.cfg.broadcast_enabled {
+                    if let Some(wrapped_block) = block_store.read_blocks().get(&b_id) {
+                        let _ = self
+                            .channels
+                            .finalized_block_sender
+                            .send(wrapped_block.clone());
+                    }
+                }
+
                 // add to stats
                 let block_is_from_protocol = self
                     .protocol_blocks
@@ -691,6 +737,13 @@ impl ConsensusWorker {
             }
         }
 
+        // persist final blocks
+        if let Some(final_block_store) = &self.channels.final_block_store {
+            if !stored_final_blocks.is_empty() {
+                final_block_store.store_final_blocks(stored_final_blocks);
+            }
+        }
+
         // notify execution
         self.notify_execution(final_block_slots);
 
@@ -8,8 +8,10 @@
 #[macro_use]
 extern crate massa_logging;
 
+mod block_store_db;
 mod consensus_worker;
 mod tools;
+pub use block_store_db::FinalBlockStore;
 pub use tools::start_consensus_controller;
 
 #[cfg(test)]
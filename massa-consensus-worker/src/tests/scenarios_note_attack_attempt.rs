@@ -15,6 +15,7 @@ use massa_protocol_exports::test_exports::MockProtocolController;
 use massa_signature::KeyPair;
 use massa_storage::Storage;
 use serial_test::serial;
+use tokio::sync::broadcast;
 
 #[tokio::test]
 #[serial]
@@ -53,6 +54,9 @@ async fn test_invalid_block_notified_as_attack_attempt() {
                 protocol_event_receiver,
                 pool_command_sender: pool_controller,
                 selector_controller,
+                block_sender: broadcast::channel(cfg.channel_size).0,
+                finalized_block_sender: broadcast::channel(cfg.channel_size).0,
+                final_block_store: None,
             },
             None,
             storage.clone(),
@@ -123,6 +127,9 @@ async fn test_invalid_header_notified_as_attack_attempt() {
                 protocol_event_receiver,
                 pool_command_sender: pool_controller,
                 selector_controller,
+                block_sender: broadcast::channel(cfg.channel_size).0,
+                finalized_block_sender: broadcast::channel(cfg.channel_size).0,
+                final_block_store: None,
             },
             None,
             storage,
@@ -35,6 +35,7 @@ use massa_time::MassaTime;
 use parking_lot::Mutex;
 use std::{collections::BTreeMap, collections::HashSet, future::Future, path::Path};
 use std::{str::FromStr, sync::Arc, time::Duration};
+use tokio::sync::broadcast;
 
 use tracing::info;
 
@@ -733,6 +734,9 @@ pub async fn _consensus_pool_test<F, V>(
                 protocol_event_receiver,
                 pool_command_sender: pool_controller.clone(),
                 selector_controller,
+                block_sender: broadcast::channel(cfg.channel_size).0,
+                finalized_block_sender: broadcast::channel(cfg.channel_size).0,
+                final_block_store: None,
             },
             boot_graph,
             storage.clone(),
@@ -823,6 +827,9 @@ pub async fn consensus_pool_test_with_storage<F, V>(
                 protocol_event_receiver,
                 pool_command_sender: pool_controller.clone(),
                 selector_controller: selector_controller,
+                block_sender: broadcast::channel(cfg.channel_size).0,
+                finalized_block_sender: broadcast::channel(cfg.channel_size).0,
+                final_block_store: None,
             },
             boot_graph,
             storage.clone(),
@@ -917,6 +924,9 @@ where
                 protocol_event_receiver,
                 pool_command_sender: pool_controller,
                 selector_controller: selector_controller.clone(),
+                block_sender: broadcast::channel(cfg.channel_size).0,
+                finalized_block_sender: broadcast::channel(cfg.channel_size).0,
+                final_block_store: None,
             },
             None,
             storage.clone(),
@@ -1009,6 +1019,9 @@ where
                 protocol_event_receiver,
                 pool_command_sender: pool_controller,
                 selector_controller: selector_controller.clone(),
+                block_sender: broadcast::channel(cfg.channel_size).0,
+                finalized_block_sender: broadcast::channel(cfg.channel_size).0,
+                final_block_store: None,
             },
             None,
             storage.clone(),
@@ -78,6 +78,9 @@ pub async fn start_consensus_controller(
                 controller_command_rx: command_rx,
                 controller_event_tx: event_tx,
                 controller_manager_rx: manager_rx,
+                block_sender: channels.block_sender,
+                finalized_block_sender: channels.finalized_block_sender,
+                final_block_store: channels.final_block_store,
             },
             block_db,
             clock_compensation,
@@ -0,0 +1,14 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module exports the channels through which the execution worker broadcasts its activity
+
+use massa_models::output_event::SCOutputEvent;
+use tokio::sync::broadcast;
+
+/// Channels used by the execution worker to broadcast its outputs to external subscribers
+#[derive(Clone)]
+pub struct ExecutionChannels {
+    /// Broadcast channel for smart contract events, emitted once when the slot generating them
+    /// is executed as candidate and once again when it becomes final
+    pub sc_output_event_sender: broadcast::Sender<SCOutputEvent>,
+}
@@ -2,7 +2,7 @@
 
 //! This module exports generic traits representing interfaces for interacting with the Execution worker
 
-use crate::types::ExecutionOutput;
+use crate::types::ReadOnlyExecutionOutput;
 use crate::types::ReadOnlyExecutionRequest;
 use crate::ExecutionAddressInfo;
 use crate::ExecutionError;
@@ -10,8 +10,11 @@ use massa_models::address::Address;
 use massa_models::amount::Amount;
 use massa_models::api::EventFilter;
 use massa_models::block::BlockId;
-use massa_models::operation::OperationId;
-use massa_models::output_event::SCOutputEvent;
+use massa_models::execution::{ExecutionTrace, OperationSimulation};
+use massa_models::ledger_models::LedgerSubEntry;
+use massa_models::ledger_proof::LedgerEntryProof;
+use massa_models::operation::{OperationId, WrappedOperation};
+use massa_models::output_event::{EventCursor, SCOutputEvent};
 use massa_models::prehash::PreHashMap;
 use massa_models::prehash::PreHashSet;
 use massa_models::slot::Slot;
@@ -43,6 +46,24 @@ pub trait ExecutionController: Send + Sync {
     /// * operation id
     fn get_filtered_sc_output_event(&self, filter: EventFilter) -> Vec<SCOutputEvent>;
 
+    /// Get a page of final execution events matching the filter, in slot order.
+    /// Events are read from the persistent event store if it is enabled,
+    /// otherwise from the final events kept in RAM.
+    ///
+    /// # Arguments
+    /// * `filter`: criteria the returned events must match
+    /// * `cursor`: position of the last event of the previous page, if any
+    /// * `limit`: maximum number of returned events
+    ///
+    /// # Return value
+    /// * `(events, next_cursor)`: `next_cursor` is `None` if there are no more matching events
+    fn get_final_sc_output_events_page(
+        &self,
+        filter: EventFilter,
+        cursor: Option<EventCursor>,
+        limit: usize,
+    ) -> (Vec<SCOutputEvent>, Option<EventCursor>);
+
     /// Get the final and active values of balance.
     ///
     /// # Return value
@@ -62,6 +83,21 @@ pub trait ExecutionController: Send + Sync {
         input: Vec<(Address, Vec<u8>)>,
     ) -> Vec<(Option<Vec<u8>>, Option<Vec<u8>>)>;
 
+    /// Get copies of datastore entries as they were at the output of a past final slot
+    ///
+    /// # Return value
+    /// The value of each entry, or an error if the slot is not final or too old for the kept history
+    fn get_final_data_entries_at_slot(
+        &self,
+        input: Vec<(Address, Vec<u8>)>,
+        slot: Slot,
+    ) -> Result<Vec<Option<Vec<u8>>>, ExecutionError>;
+
+    /// Get the final value of a ledger sub-entry along with its inclusion or exclusion proof
+    /// against the ledger hash of the latest final slot
+    fn get_final_ledger_proof(&self, addr: &Address, sub_entry: LedgerSubEntry)
+        -> LedgerEntryProof;
+
     /// Returns for a given cycle the stakers taken into account
     /// by the selector. That correspond to the `roll_counts` in `cycle - 3`.
     ///
@@ -74,12 +110,32 @@ pub trait ExecutionController: Send + Sync {
     /// * `req`: an instance of `ReadOnlyCallRequest` describing the parameters of the execution
     ///
     /// # returns
-    /// An instance of `ExecutionOutput` containing a summary of the effects of the execution,
-    /// or an error if the execution failed.
+    /// An instance of `ReadOnlyExecutionOutput` containing a summary of the effects of the execution
+    /// and the gas it spent, or an error if the execution failed.
     fn execute_readonly_request(
         &self,
         req: ReadOnlyExecutionRequest,
-    ) -> Result<ExecutionOutput, ExecutionError>;
+    ) -> Result<ReadOnlyExecutionOutput, ExecutionError>;
+
+    /// Simulate the execution of operations, in order, on top of the candidate state,
+    /// without causing modifications to the consensus state nor to the operation pool
+    ///
+    /// # arguments
+    /// * `operations`: the operations to simulate
+    ///
+    /// # returns
+    /// The outcome of the simulation of each operation, or an error if the simulation could not happen.
+    fn simulate_operations(
+        &self,
+        operations: Vec<WrappedOperation>,
+    ) -> Result<Vec<OperationSimulation>, ExecutionError>;
+
+    /// Get the call tree recorded during the latest execution of each operation.
+    /// Traces are only recorded when execution tracing is enabled, and only the latest ones are kept.
+    ///
+    /// # Return value
+    /// The trace of each operation, or `None` if it is not available
+    fn get_execution_traces(&self, operation_ids: &[OperationId]) -> Vec<Option<ExecutionTrace>>;
 
     /// List which operations inside the provided list were not executed
     fn unexecuted_ops_among(
@@ -91,6 +147,17 @@ pub trait ExecutionController: Send + Sync {
     /// Gets information about a batch of addresses
     fn get_addresses_infos(&self, addresses: &[Address]) -> Vec<ExecutionAddressInfo>;
 
+    /// Gets information about a batch of addresses, with the balances, roll counts and datastore keys
+    /// they had at the output of a past final slot, both as final and candidate values.
+    /// Deferred credits and cycle information are the current ones.
+    ///
+    /// Returns an error if the slot is not final or too old for the kept history
+    fn get_addresses_infos_at_slot(
+        &self,
+        addresses: &[Address],
+        slot: Slot,
+    ) -> Result<Vec<ExecutionAddressInfo>, ExecutionError>;
+
     /// Get execution statistics
     fn get_stats(&self) -> ExecutionStats;
 
@@ -41,4 +41,7 @@ pub enum ExecutionError {
 
     /// Include operation error: {0}
     IncludeOperationError(String),
+
+    /// Final state history error: {0}
+    HistoryError(String),
 }
@@ -60,4 +60,10 @@ pub struct ExecutionConfig {
     pub max_datastore_value_size: u64,
     /// Storage cost constants
     pub storage_costs_constants: StorageCostsConstants,
+    /// whether execution outputs are broadcast to external subscribers
+    pub broadcast_enabled: bool,
+    /// whether the call trees of operation executions are recorded
+    pub trace_executions: bool,
+    /// maximum number of operation execution traces kept in cache
+    pub max_execution_traces: usize,
 }
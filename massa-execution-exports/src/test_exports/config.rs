@@ -2,9 +2,10 @@
 
 //! This file defines testing tools related to the configuration
 
-use crate::{ExecutionConfig, StorageCostsConstants};
+use crate::{ExecutionChannels, ExecutionConfig, StorageCostsConstants};
 use massa_models::config::*;
 use massa_time::MassaTime;
+use tokio::sync::broadcast;
 
 impl Default for ExecutionConfig {
     /// default configuration used for testing
@@ -42,6 +43,18 @@ impl Default for ExecutionConfig {
             max_bytecode_size: MAX_BYTECODE_LENGTH,
             max_datastore_value_size: MAX_DATASTORE_VALUE_LENGTH,
             storage_costs_constants,
+            broadcast_enabled: false,
+            trace_executions: false,
+            max_execution_traces: 1000,
+        }
+    }
+}
+
+impl Default for ExecutionChannels {
+    /// default channels used for testing
+    fn default() -> Self {
+        Self {
+            sc_output_event_sender: broadcast::channel(CHANNEL_SIZE).0,
         }
     }
 }
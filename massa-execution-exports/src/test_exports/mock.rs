@@ -3,17 +3,21 @@
 //! This file defines utilities to mock the crate for testing purposes
 
 use crate::{
-    ExecutionAddressInfo, ExecutionController, ExecutionError, ExecutionOutput,
+    ExecutionAddressInfo, ExecutionController, ExecutionError, ReadOnlyExecutionOutput,
     ReadOnlyExecutionRequest,
 };
+use massa_hash::Hash;
 use massa_ledger_exports::LedgerEntry;
 use massa_models::{
     address::Address,
     amount::Amount,
     api::EventFilter,
     block::BlockId,
-    operation::OperationId,
-    output_event::SCOutputEvent,
+    execution::{ExecutionTrace, OperationSimulation},
+    ledger_models::LedgerSubEntry,
+    ledger_proof::{LedgerEntryProof, LedgerProof, MERKLE_EMPTY_HASH_BYTES},
+    operation::{OperationId, WrappedOperation},
+    output_event::{EventCursor, SCOutputEvent},
     prehash::{PreHashMap, PreHashSet},
     slot::Slot,
     stats::ExecutionStats,
@@ -65,7 +69,14 @@ pub enum MockExecutionControllerMessage {
         /// read only execution request
         req: ReadOnlyExecutionRequest,
         /// response channel
-        response_tx: mpsc::Sender<Result<ExecutionOutput, ExecutionError>>,
+        response_tx: mpsc::Sender<Result<ReadOnlyExecutionOutput, ExecutionError>>,
+    },
+    /// operation simulation request
+    SimulateOperations {
+        /// operations to simulate
+        operations: Vec<WrappedOperation>,
+        /// response channel
+        response_tx: mpsc::Sender<Result<Vec<OperationSimulation>, ExecutionError>>,
     },
     /// Not executed operation among call
     UnexecutedOpsAmong {
@@ -152,6 +163,15 @@ impl ExecutionController for MockExecutionController {
         response_rx.recv().unwrap()
     }
 
+    fn get_final_sc_output_events_page(
+        &self,
+        _filter: EventFilter,
+        _cursor: Option<EventCursor>,
+        _limit: usize,
+    ) -> (Vec<SCOutputEvent>, Option<EventCursor>) {
+        (Vec::new(), None)
+    }
+
     fn get_final_and_candidate_balance(
         &self,
         addresses: &[Address],
@@ -177,10 +197,46 @@ impl ExecutionController for MockExecutionController {
         Vec::default()
     }
 
+    fn get_final_data_entries_at_slot(
+        &self,
+        _: Vec<(Address, Vec<u8>)>,
+        _: Slot,
+    ) -> Result<Vec<Option<Vec<u8>>>, ExecutionError> {
+        Ok(Vec::default())
+    }
+
+    fn get_final_ledger_proof(
+        &self,
+        addr: &Address,
+        sub_entry: LedgerSubEntry,
+    ) -> LedgerEntryProof {
+        // proof of absence in an empty ledger
+        LedgerEntryProof {
+            address: *addr,
+            sub_entry,
+            ledger_key: Vec::new(),
+            value: None,
+            slot: Slot::new(0, 0),
+            ledger_hash: Hash::from_bytes(&MERKLE_EMPTY_HASH_BYTES),
+            proof: LedgerProof {
+                siblings: Vec::new(),
+                other_leaf: None,
+            },
+        }
+    }
+
     fn get_addresses_infos(&self, _addresses: &[Address]) -> Vec<ExecutionAddressInfo> {
         Vec::default()
     }
 
+    fn get_addresses_infos_at_slot(
+        &self,
+        _addresses: &[Address],
+        _slot: Slot,
+    ) -> Result<Vec<ExecutionAddressInfo>, ExecutionError> {
+        Ok(Vec::default())
+    }
+
     fn get_cycle_active_rolls(&self, _cycle: u64) -> BTreeMap<Address, u64> {
         BTreeMap::default()
     }
@@ -188,7 +244,7 @@ impl ExecutionController for MockExecutionController {
     fn execute_readonly_request(
         &self,
         req: ReadOnlyExecutionRequest,
-    ) -> Result<ExecutionOutput, ExecutionError> {
+    ) -> Result<ReadOnlyExecutionOutput, ExecutionError> {
         let (response_tx, response_rx) = mpsc::channel();
         self.0
             .lock()
@@ -197,6 +253,25 @@ impl ExecutionController for MockExecutionController {
         response_rx.recv().unwrap()
     }
 
+    fn simulate_operations(
+        &self,
+        operations: Vec<WrappedOperation>,
+    ) -> Result<Vec<OperationSimulation>, ExecutionError> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .send(MockExecutionControllerMessage::SimulateOperations {
+                operations,
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
+    fn get_execution_traces(&self, operation_ids: &[OperationId]) -> Vec<Option<ExecutionTrace>> {
+        vec![None; operation_ids.len()]
+    }
+
     fn unexecuted_ops_among(
         &self,
         ops: &PreHashSet<OperationId>,
@@ -7,7 +7,7 @@
 //! # Architecture
 //!
 //! ## `config.rs`
-//! Provides a default execution configuration for testing.
+//! Provides a default execution configuration and channels for testing.
 //!
 //! ## `mock.rs`
 //! Provides a mock of `ExecutionController` to simulate interactions
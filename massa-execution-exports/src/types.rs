@@ -49,6 +49,15 @@ pub struct ExecutionOutput {
     pub events: EventStore,
 }
 
+/// structure describing the output of a read-only execution
+#[derive(Debug, Clone)]
+pub struct ReadOnlyExecutionOutput {
+    /// output of the execution
+    pub out: ExecutionOutput,
+    /// gas spent by the execution
+    pub gas_cost: u64,
+}
+
 /// structure describing different types of read-only execution request
 #[derive(Debug, Clone)]
 pub struct ReadOnlyExecutionRequest {
@@ -10,6 +10,7 @@
 use crate::speculative_async_pool::SpeculativeAsyncPool;
 use crate::speculative_executed_ops::SpeculativeExecutedOps;
 use crate::speculative_ledger::SpeculativeLedger;
+use crate::trace::ExecutionTracer;
 use crate::{active_history::ActiveHistory, speculative_roll_state::SpeculativeRollState};
 use massa_async_pool::{AsyncMessage, AsyncMessageId};
 use massa_execution_exports::{
@@ -18,6 +19,7 @@ use massa_execution_exports::{
 use massa_final_state::{ExecutedOps, FinalState, StateChanges};
 use massa_ledger_exports::LedgerChanges;
 use massa_models::address::ExecutionAddressCycleInfo;
+use massa_models::execution::TraceAction;
 use massa_models::{
     address::Address,
     amount::Amount,
@@ -125,6 +127,10 @@ pub(crate) struct ExecutionContext {
 
     /// operation id that originally caused this execution (if any)
     pub origin_operation_id: Option<OperationId>,
+
+    /// call tree recorder, present only while tracing the execution of an operation.
+    /// It is not part of the snapshots so that the actions reverted by an error remain in the trace.
+    pub tracer: Option<ExecutionTracer>,
 }
 
 impl ExecutionContext {
@@ -174,6 +180,7 @@ impl ExecutionContext {
             unsafe_rng: Xoshiro256PlusPlus::from_seed([0u8; 32]),
             creator_address: Default::default(),
             origin_operation_id: Default::default(),
+            tracer: None,
             config,
         }
     }
@@ -782,6 +789,13 @@ impl ExecutionContext {
         self.events.push(event);
     }
 
+    /// Records an action in the execution trace, if the execution is traced
+    pub fn trace(&mut self, action: TraceAction) {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.record(action);
+        }
+    }
+
     /// Check if an operation was previously executed (to prevent reuse)
     pub fn is_op_executed(&self, op_id: &OperationId) -> bool {
         self.speculative_executed_ops.is_op_executed(op_id)
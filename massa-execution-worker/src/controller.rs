@@ -7,13 +7,20 @@ use crate::execution::ExecutionState;
 use crate::request_queue::{RequestQueue, RequestWithResponseSender};
 use massa_execution_exports::{
     ExecutionAddressInfo, ExecutionConfig, ExecutionController, ExecutionError, ExecutionManager,
-    ExecutionOutput, ReadOnlyExecutionRequest,
+    ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
 };
 use massa_models::api::EventFilter;
-use massa_models::output_event::SCOutputEvent;
+use massa_models::execution::{ExecutionTrace, OperationSimulation};
+use massa_models::ledger_models::LedgerSubEntry;
+use massa_models::ledger_proof::LedgerEntryProof;
+use massa_models::output_event::{EventCursor, SCOutputEvent};
 use massa_models::prehash::{PreHashMap, PreHashSet};
 use massa_models::stats::ExecutionStats;
-use massa_models::{address::Address, amount::Amount, operation::OperationId};
+use massa_models::{
+    address::Address,
+    amount::Amount,
+    operation::{OperationId, WrappedOperation},
+};
 use massa_models::{block::BlockId, slot::Slot};
 use massa_storage::Storage;
 use parking_lot::{Condvar, Mutex, RwLock};
@@ -33,14 +40,16 @@ pub(crate) struct ExecutionInputData {
     /// storage instances for previously unprocessed blocks
     pub block_storage: PreHashMap<BlockId, Storage>,
     /// queue for read-only execution requests and response MPSCs to send back their outputs
-    pub readonly_requests: RequestQueue<ReadOnlyExecutionRequest, ExecutionOutput>,
+    pub readonly_requests: RequestQueue<ReadOnlyExecutionRequest, ReadOnlyExecutionOutput>,
+    /// queue for operation simulation requests and response MPSCs to send back their outputs
+    pub simulation_requests: RequestQueue<Vec<WrappedOperation>, Vec<OperationSimulation>>,
 }
 
 impl Display for ExecutionInputData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "stop={:?}, finalized={:?}, blockclique={:?}, readonly={:?}",
+            "stop={:?}, finalized={:?}, blockclique={:?}, readonly={:?}, simulation={:?}",
             self.stop,
             self.finalized_blocks
                 .iter()
@@ -50,7 +59,8 @@ impl Display for ExecutionInputData {
                 .iter()
                 .map(|(slot, id)| (*slot, *id))
                 .collect::<BTreeMap<Slot, BlockId>>()),
-            self.readonly_requests
+            self.readonly_requests,
+            self.simulation_requests
         )
     }
 }
@@ -64,6 +74,7 @@ impl ExecutionInputData {
             new_blockclique: Default::default(),
             block_storage: Default::default(),
             readonly_requests: RequestQueue::new(config.max_final_events),
+            simulation_requests: RequestQueue::new(config.readonly_queue_length),
         }
     }
 
@@ -71,6 +82,7 @@ impl ExecutionInputData {
     /// and resets self.
     pub fn take(&mut self) -> Self {
         let max_final_events = self.readonly_requests.capacity();
+        let max_simulations = self.simulation_requests.capacity();
         ExecutionInputData {
             stop: std::mem::take(&mut self.stop),
             finalized_blocks: std::mem::take(&mut self.finalized_blocks),
@@ -80,6 +92,10 @@ impl ExecutionInputData {
                 &mut self.readonly_requests,
                 RequestQueue::new(max_final_events),
             ),
+            simulation_requests: std::mem::replace(
+                &mut self.simulation_requests,
+                RequestQueue::new(max_simulations),
+            ),
         }
     }
 }
@@ -137,6 +153,18 @@ impl ExecutionController for ExecutionControllerImpl {
             .get_filtered_sc_output_event(filter)
     }
 
+    /// Get a page of final execution events matching the filter, starting right after `cursor`
+    fn get_final_sc_output_events_page(
+        &self,
+        filter: EventFilter,
+        cursor: Option<EventCursor>,
+        limit: usize,
+    ) -> (Vec<SCOutputEvent>, Option<EventCursor>) {
+        self.execution_state
+            .read()
+            .get_final_sc_output_events_page(&filter, cursor, limit)
+    }
+
     /// Get a copy of a single datastore entry with its final and active values
     ///
     /// # Return value
@@ -169,6 +197,30 @@ impl ExecutionController for ExecutionControllerImpl {
         result
     }
 
+    /// Get copies of datastore entries as they were at the output of a past final slot
+    fn get_final_data_entries_at_slot(
+        &self,
+        input: Vec<(Address, Vec<u8>)>,
+        slot: Slot,
+    ) -> Result<Vec<Option<Vec<u8>>>, ExecutionError> {
+        let lock = self.execution_state.read();
+        input
+            .into_iter()
+            .map(|(addr, key)| lock.get_final_data_entry_at(&addr, &key, slot))
+            .collect()
+    }
+
+    /// Get the final value of a ledger sub-entry along with its proof against the final ledger hash
+    fn get_final_ledger_proof(
+        &self,
+        addr: &Address,
+        sub_entry: LedgerSubEntry,
+    ) -> LedgerEntryProof {
+        self.execution_state
+            .read()
+            .get_final_ledger_proof(addr, sub_entry)
+    }
+
     /// Return the active rolls distribution for the given `cycle`
     fn get_cycle_active_rolls(&self, cycle: u64) -> BTreeMap<Address, u64> {
         self.execution_state.read().get_cycle_active_rolls(cycle)
@@ -179,7 +231,7 @@ impl ExecutionController for ExecutionControllerImpl {
     fn execute_readonly_request(
         &self,
         req: ReadOnlyExecutionRequest,
-    ) -> Result<ExecutionOutput, ExecutionError> {
+    ) -> Result<ReadOnlyExecutionOutput, ExecutionError> {
         let resp_rx = {
             let mut input_data = self.input_data.1.lock();
 
@@ -192,7 +244,7 @@ impl ExecutionController for ExecutionControllerImpl {
 
             // prepare the channel to send back the result of the read-only execution
             let (resp_tx, resp_rx) =
-                std::sync::mpsc::channel::<Result<ExecutionOutput, ExecutionError>>();
+                std::sync::mpsc::channel::<Result<ReadOnlyExecutionOutput, ExecutionError>>();
 
             // append the request to the queue of input read-only requests
             input_data
@@ -215,6 +267,54 @@ impl ExecutionController for ExecutionControllerImpl {
         }
     }
 
+    /// Simulates operations on top of the candidate state
+    /// Simulations do not modify consensus state nor touch the operation pool
+    fn simulate_operations(
+        &self,
+        operations: Vec<WrappedOperation>,
+    ) -> Result<Vec<OperationSimulation>, ExecutionError> {
+        let resp_rx = {
+            let mut input_data = self.input_data.1.lock();
+
+            // if the simulation queue is already full, return an error
+            if input_data.simulation_requests.is_full() {
+                return Err(ExecutionError::ChannelError(
+                    "too many queued operation simulation requests".into(),
+                ));
+            }
+
+            // prepare the channel to send back the result of the simulation
+            let (resp_tx, resp_rx) =
+                std::sync::mpsc::channel::<Result<Vec<OperationSimulation>, ExecutionError>>();
+
+            // append the request to the queue of input simulation requests
+            input_data
+                .simulation_requests
+                .push(RequestWithResponseSender::new(operations, resp_tx));
+
+            // wake up the execution main loop
+            self.input_data.0.notify_one();
+
+            resp_rx
+        };
+
+        // Wait for the result of the simulation
+        match resp_rx.recv() {
+            Ok(result) => result,
+            Err(err) => Err(ExecutionError::ChannelError(format!(
+                "operation simulation response channel readout failed: {}",
+                err
+            ))),
+        }
+    }
+
+    /// Get the call tree recorded during the latest execution of each operation
+    fn get_execution_traces(&self, operation_ids: &[OperationId]) -> Vec<Option<ExecutionTrace>> {
+        self.execution_state
+            .read()
+            .get_execution_traces(operation_ids)
+    }
+
     /// List which operations inside the provided list were not executed
     fn unexecuted_ops_among(
         &self,
@@ -251,6 +351,31 @@ impl ExecutionController for ExecutionControllerImpl {
         res
     }
 
+    /// Gets information about a batch of addresses at the output of a past final slot
+    fn get_addresses_infos_at_slot(
+        &self,
+        addresses: &[Address],
+        slot: Slot,
+    ) -> Result<Vec<ExecutionAddressInfo>, ExecutionError> {
+        let mut res = Vec::with_capacity(addresses.len());
+        let exec_state = self.execution_state.read();
+        for addr in addresses {
+            let (balance, roll_count, datastore_keys) =
+                exec_state.get_final_address_state_at(addr, slot)?;
+            res.push(ExecutionAddressInfo {
+                final_datastore_keys: datastore_keys.clone(),
+                candidate_datastore_keys: datastore_keys,
+                final_balance: balance.unwrap_or_default(),
+                candidate_balance: balance.unwrap_or_default(),
+                final_roll_count: roll_count,
+                candidate_roll_count: roll_count,
+                future_deferred_credits: exec_state.get_address_future_deferred_credits(addr),
+                cycle_infos: exec_state.get_address_cycle_infos(addr),
+            });
+        }
+        Ok(res)
+    }
+
     /// Get execution statistics
     fn get_stats(&self) -> ExecutionStats {
         self.execution_state.read().get_stats()
@@ -12,16 +12,21 @@ use crate::active_history::{ActiveHistory, HistorySearchResult};
 use crate::context::ExecutionContext;
 use crate::interface_impl::InterfaceImpl;
 use crate::stats::ExecutionStatsCounter;
+use crate::trace::ExecutionTracer;
 use massa_async_pool::AsyncMessage;
 use massa_execution_exports::{
-    EventStore, ExecutionConfig, ExecutionError, ExecutionOutput, ExecutionStackElement,
-    ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
+    EventStore, ExecutionChannels, ExecutionConfig, ExecutionError, ExecutionOutput,
+    ExecutionStackElement, ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
+    ReadOnlyExecutionTarget,
 };
-use massa_final_state::FinalState;
-use massa_ledger_exports::{SetOrDelete, SetUpdateOrDelete};
+use massa_final_state::{FinalState, FinalStateError};
+use massa_ledger_exports::{EventStoreController, SetOrDelete, SetUpdateOrDelete};
 use massa_models::address::ExecutionAddressCycleInfo;
 use massa_models::api::EventFilter;
-use massa_models::output_event::SCOutputEvent;
+use massa_models::execution::{BalanceChange, ExecutionTrace, OperationSimulation, ReadOnlyResult};
+use massa_models::ledger_models::LedgerSubEntry;
+use massa_models::ledger_proof::LedgerEntryProof;
+use massa_models::output_event::{EventCursor, SCOutputEvent};
 use massa_models::prehash::PreHashSet;
 use massa_models::stats::ExecutionStats;
 use massa_models::{
@@ -34,7 +39,7 @@ use massa_pos_exports::SelectorController;
 use massa_sc_runtime::Interface;
 use massa_storage::Storage;
 use parking_lot::{Mutex, RwLock};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
@@ -70,6 +75,12 @@ pub(crate) struct ExecutionState {
     execution_interface: Box<dyn Interface>,
     // execution statistics
     stats_counter: ExecutionStatsCounter,
+    // channels used to broadcast execution outputs
+    channels: ExecutionChannels,
+    // optional persistent store of final events
+    final_event_store: Option<Box<dyn EventStoreController>>,
+    // traces of the latest traced operation executions, oldest at the front
+    execution_traces: Mutex<VecDeque<ExecutionTrace>>,
 }
 
 impl ExecutionState {
@@ -78,10 +89,17 @@ impl ExecutionState {
     /// # Arguments
     /// * `config`: execution configuration
     /// * `final_state`: atomic access to the final state
+    /// * `channels`: channels used to broadcast execution outputs
+    /// * `final_event_store`: optional persistent store of final events
     ///
     /// # returns
     /// A new `ExecutionState`
-    pub fn new(config: ExecutionConfig, final_state: Arc<RwLock<FinalState>>) -> ExecutionState {
+    pub fn new(
+        config: ExecutionConfig,
+        final_state: Arc<RwLock<FinalState>>,
+        channels: ExecutionChannels,
+        final_event_store: Option<Box<dyn EventStoreController>>,
+    ) -> ExecutionState {
         // Get the slot at the output of which the final state is attached.
         // This should be among the latest final slots.
         let last_final_slot = final_state.read().slot;
@@ -118,6 +136,9 @@ impl ExecutionState {
                 config.stats_time_window_duration,
                 config.clock_compensation,
             ),
+            channels,
+            final_event_store,
+            execution_traces: Default::default(),
             config,
         }
     }
@@ -160,6 +181,15 @@ impl ExecutionState {
 
         // append generated events to the final event store
         exec_out.events.finalize();
+        self.broadcast_events(&exec_out.events);
+        if let Some(final_event_store) = self.final_event_store.as_mut() {
+            final_event_store.append_events(
+                exec_out
+                    .events
+                    .get_filtered_sc_output_events(&Default::default())
+                    .into(),
+            );
+        }
         self.final_events.extend(exec_out.events);
         self.final_events.prune(self.config.max_final_events);
     }
@@ -180,10 +210,24 @@ impl ExecutionState {
         // update active cursor to reflect the new latest active slot
         self.active_cursor = exec_out.slot;
 
+        // broadcast the generated candidate events
+        self.broadcast_events(&exec_out.events);
+
         // add the execution output at the end of the output history
         self.active_history.write().0.push_back(exec_out);
     }
 
+    /// Broadcasts events to external subscribers, if broadcasting is enabled
+    fn broadcast_events(&self, events: &EventStore) {
+        if !self.config.broadcast_enabled {
+            return;
+        }
+        for event in events.get_filtered_sc_output_events(&Default::default()) {
+            // an error only means that there is currently no subscriber
+            let _ = self.channels.sc_output_event_sender.send(event);
+        }
+    }
+
     /// Execute an operation in the context of a block.
     /// Assumes the execution context was initialized at the beginning of the slot.
     ///
@@ -192,13 +236,17 @@ impl ExecutionState {
     /// * `block_slot`: slot of the block in which the op is included
     /// * `remaining_block_gas`: mutable reference towards the remaining gas in the block
     /// * `block_credits`: mutable reference towards the total block reward/fee credits
+    ///
+    /// # Returns
+    /// An error if the operation could not be included,
+    /// otherwise the error that made its execution fail after its fees were spent, if any
     pub fn execute_operation(
         &self,
         operation: &WrappedOperation,
         block_slot: Slot,
         remaining_block_gas: &mut u64,
         block_credits: &mut Amount,
-    ) -> Result<(), ExecutionError> {
+    ) -> Result<Option<ExecutionError>, ExecutionError> {
         // check validity period
         if !(operation
             .get_validity_range(self.config.operation_validity_period)
@@ -313,7 +361,7 @@ impl ExecutionState {
 
             // check execution results
             match execution_result {
-                Ok(_) => {}
+                Ok(_) => Ok(None),
                 Err(err) => {
                     // an error occurred: emit error event and reset context to snapshot
                     let err = ExecutionError::RuntimeError(format!(
@@ -321,12 +369,11 @@ impl ExecutionState {
                         operation_id, &err
                     ));
                     debug!("{}", &err);
-                    context.reset_to_snapshot(context_snapshot, Some(err));
+                    context.reset_to_snapshot(context_snapshot, Some(err.clone()));
+                    Ok(Some(err))
                 }
             }
         }
-
-        Ok(())
     }
 
     /// Execute an operation of type `RollSell`
@@ -504,11 +551,18 @@ impl ExecutionState {
                 owned_addresses: vec![sender_addr],
                 operation_datastore: Some(datastore.clone()),
             }];
+            if let Some(tracer) = context.tracer.as_mut() {
+                tracer.enter_call(sender_addr, Amount::zero());
+            }
         };
 
         // run the VM on the bytecode contained in the operation
         match massa_sc_runtime::run_main(bytecode, *max_gas, &*self.execution_interface) {
-            Ok(_reamining_gas) => {}
+            Ok(remaining_gas) => {
+                if let Some(tracer) = context_guard!(self).tracer.as_mut() {
+                    tracer.set_gas_cost(max_gas.saturating_sub(remaining_gas));
+                }
+            }
             Err(err) => {
                 // there was an error during bytecode execution
                 return Err(ExecutionError::RuntimeError(format!(
@@ -586,6 +640,11 @@ impl ExecutionState {
                 )));
             }
 
+            if let Some(tracer) = context.tracer.as_mut() {
+                tracer.enter_call(sender_addr, Amount::zero());
+                tracer.enter_call(target_addr, coins);
+            }
+
             // quit if there is no function to be called
             if target_func.is_empty() {
                 return Ok(());
@@ -603,7 +662,11 @@ impl ExecutionState {
             param,
             &*self.execution_interface,
         ) {
-            Ok(_reamining_gas) => {}
+            Ok(remaining_gas) => {
+                if let Some(tracer) = context_guard!(self).tracer.as_mut() {
+                    tracer.set_gas_cost(max_gas.saturating_sub(remaining_gas));
+                }
+            }
             Err(err) => {
                 // there was an error during bytecode execution
                 return Err(ExecutionError::RuntimeError(format!(
@@ -804,16 +867,34 @@ impl ExecutionState {
             // Try executing the operations of this block in the order in which they appear in the block.
             // Errors are logged but do not interrupt the execution of the slot.
             for operation in operations.into_iter() {
-                if let Err(err) = self.execute_operation(
+                if self.config.trace_executions {
+                    context_guard!(self).tracer = Some(ExecutionTracer::default());
+                }
+                let result = self.execute_operation(
                     &operation,
                     stored_block.content.header.content.slot,
                     &mut remaining_block_gas,
                     &mut block_credits,
-                ) {
-                    debug!(
-                        "failed executing operation {} in block {}: {}",
-                        operation.id, block_id, err
-                    );
+                );
+                let tracer = context_guard!(self).tracer.take();
+                match result {
+                    Ok(opt_err) => {
+                        if let Some(root) = tracer.and_then(ExecutionTracer::finish) {
+                            self.store_execution_trace(ExecutionTrace {
+                                operation_id: operation.id,
+                                executed_at: *slot,
+                                block_id: Some(*block_id),
+                                error: opt_err.map(|err| err.to_string()),
+                                root,
+                            });
+                        }
+                    }
+                    Err(err) => {
+                        debug!(
+                            "failed executing operation {} in block {}: {}",
+                            operation.id, block_id, err
+                        );
+                    }
                 }
             }
 
@@ -1001,15 +1082,20 @@ impl ExecutionState {
     /// but all accumulated changes are simply returned as an `ExecutionOutput` object,
     /// and not actually applied to the consensus state.
     ///
+    /// For function calls, the coins of the last element of the call stack
+    /// are transferred from the first element of the call stack to the target,
+    /// and no function is called if the target function name is empty,
+    /// as for a `CallSC` operation.
+    ///
     /// # Arguments
     /// * `req`: a read-only execution request
     ///
     /// # Returns
-    ///  `ExecutionOutput` describing the output of the execution, or an error
+    ///  `ReadOnlyExecutionOutput` describing the output of the execution and the gas it spent, or an error
     pub(crate) fn execute_readonly_request(
         &self,
         req: ReadOnlyExecutionRequest,
-    ) -> Result<ExecutionOutput, ExecutionError> {
+    ) -> Result<ReadOnlyExecutionOutput, ExecutionError> {
         // TODO ensure that speculative things are reset after every execution ends (incl. on error and readonly)
         // otherwise, on prod stats accumulation etc... from the API we might be counting the remainder of this speculative execution
 
@@ -1019,8 +1105,16 @@ impl ExecutionState {
             .get_next_slot(self.config.thread_count)
             .expect("slot overflow in readonly execution");
 
+        // get the caller and the coins of a function call before the call stack is moved into the context
+        let call_coins = match (req.call_stack.first(), req.call_stack.last()) {
+            (Some(caller), Some(callee)) if !callee.coins.is_zero() => {
+                Some((caller.address, callee.coins))
+            }
+            _ => None,
+        };
+
         // create a readonly execution context
-        let execution_context = ExecutionContext::readonly(
+        let mut execution_context = ExecutionContext::readonly(
             self.config.clone(),
             slot,
             req.max_gas,
@@ -1031,20 +1125,32 @@ impl ExecutionState {
         );
 
         // run the intepreter according to the target type
-        match req.target {
+        let remaining_gas = match req.target {
             ReadOnlyExecutionTarget::BytecodeExecution(bytecode) => {
                 // set the execution context for execution
                 *context_guard!(self) = execution_context;
 
                 // run the bytecode's main function
                 massa_sc_runtime::run_main(&bytecode, req.max_gas, &*self.execution_interface)
-                    .map_err(|err| ExecutionError::RuntimeError(err.to_string()))?;
+                    .map_err(|err| ExecutionError::RuntimeError(err.to_string()))?
             }
             ReadOnlyExecutionTarget::FunctionCall {
                 target_addr,
                 target_func,
                 parameter,
             } => {
+                // transfer the coins of the call from the caller to the target
+                if let Some((caller_addr, coins)) = call_coins {
+                    execution_context
+                        .transfer_coins(Some(caller_addr), Some(target_addr), coins, false)
+                        .map_err(|err| {
+                            ExecutionError::RuntimeError(format!(
+                                "failed to transfer {} call coins from {} to {}: {}",
+                                coins, caller_addr, target_addr, err
+                            ))
+                        })?;
+                }
+
                 // get the bytecode, default to an empty vector
                 let bytecode = execution_context
                     .get_bytecode(&target_addr)
@@ -1053,20 +1159,156 @@ impl ExecutionState {
                 // set the execution context for execution
                 *context_guard!(self) = execution_context;
 
-                // run the target function in the bytecode
-                massa_sc_runtime::run_function(
-                    &bytecode,
-                    req.max_gas,
-                    &target_func,
-                    &parameter,
-                    &*self.execution_interface,
-                )
-                .map_err(|err| ExecutionError::RuntimeError(err.to_string()))?;
+                // run the target function in the bytecode, if any
+                if target_func.is_empty() {
+                    req.max_gas
+                } else {
+                    massa_sc_runtime::run_function(
+                        &bytecode,
+                        req.max_gas,
+                        &target_func,
+                        &parameter,
+                        &*self.execution_interface,
+                    )
+                    .map_err(|err| ExecutionError::RuntimeError(err.to_string()))?
+                }
             }
-        }
+        };
 
         // return the execution output
-        Ok(context_guard!(self).settle_slot())
+        Ok(ReadOnlyExecutionOutput {
+            out: context_guard!(self).settle_slot(),
+            gas_cost: req.max_gas.saturating_sub(remaining_gas),
+        })
+    }
+
+    /// Simulates the execution of operations, in order, on top of the latest candidate state.
+    /// Each operation is executed as if it was included in a block
+    /// at the first slot of its thread following the latest executed candidate slot.
+    /// All accumulated changes are discarded.
+    ///
+    /// # Arguments
+    /// * `operations`: the operations to simulate
+    ///
+    /// # Returns
+    /// The outcome of the simulation of each operation
+    pub(crate) fn simulate_operations(
+        &self,
+        operations: Vec<WrappedOperation>,
+    ) -> Vec<OperationSimulation> {
+        // set the execution slot to be the one after the latest executed active slot
+        let slot = self
+            .active_cursor
+            .get_next_slot(self.config.thread_count)
+            .expect("slot overflow in operation simulation");
+
+        // set up an execution context for the simulation, with no block at that slot
+        *context_guard!(self) = ExecutionContext::active_slot(
+            self.config.clone(),
+            slot,
+            None,
+            self.final_state.clone(),
+            self.active_history.clone(),
+        );
+
+        let mut block_credits = Amount::zero();
+        let mut res = Vec::with_capacity(operations.len());
+        for operation in operations {
+            // the operation is simulated in a block of its own thread
+            let op_thread = operation
+                .creator_address
+                .get_thread(self.config.thread_count);
+            let block_slot = if op_thread >= slot.thread {
+                Slot::new(slot.period, op_thread)
+            } else {
+                Slot::new(slot.period.saturating_add(1), op_thread)
+            };
+
+            let snapshot_before = context_guard!(self).get_snapshot();
+            let mut remaining_block_gas = self.config.max_gas_per_block;
+            let result = self.execute_operation(
+                &operation,
+                block_slot,
+                &mut remaining_block_gas,
+                &mut block_credits,
+            );
+
+            // find the addresses whose ledger entry changed, and get their balance after the operation
+            let mut context = context_guard!(self);
+            let snapshot_after = context.get_snapshot();
+            let mut balance_changes: Vec<BalanceChange> = snapshot_after
+                .ledger_changes
+                .0
+                .iter()
+                .filter(|(addr, change)| snapshot_before.ledger_changes.get(addr) != Some(*change))
+                .map(|(addr, _)| BalanceChange {
+                    address: *addr,
+                    balance_before: None,
+                    balance_after: context.get_balance(addr),
+                })
+                .collect();
+            balance_changes.sort_unstable_by_key(|change| change.address);
+
+            // go back to the state before the operation to get the previous balances
+            context.reset_to_snapshot(snapshot_before, None);
+            for change in balance_changes.iter_mut() {
+                change.balance_before = context.get_balance(&change.address);
+            }
+            context.reset_to_snapshot(snapshot_after, None);
+            balance_changes.retain(|change| change.balance_before != change.balance_after);
+
+            let (result, fee) = match result {
+                Ok(None) => (ReadOnlyResult::Ok, operation.get_total_fee()),
+                Ok(Some(err)) => (
+                    ReadOnlyResult::Error(err.to_string()),
+                    operation.get_total_fee(),
+                ),
+                Err(err) => (
+                    ReadOnlyResult::Error(format!("operation cannot be included: {}", err)),
+                    Amount::zero(),
+                ),
+            };
+            res.push(OperationSimulation {
+                operation_id: operation.id,
+                executed_at: block_slot,
+                result,
+                fee,
+                balance_changes,
+                output_events: context.events.get_filtered_sc_output_events(&EventFilter {
+                    original_operation_id: Some(operation.id),
+                    ..Default::default()
+                }),
+            });
+        }
+        res
+    }
+
+    /// Stores the trace of an operation execution,
+    /// dropping the oldest traces when more than `max_execution_traces` are kept
+    fn store_execution_trace(&self, trace: ExecutionTrace) {
+        let mut traces = self.execution_traces.lock();
+        traces.push_back(trace);
+        while traces.len() > self.config.max_execution_traces {
+            traces.pop_front();
+        }
+    }
+
+    /// Gets the trace of the latest execution of each operation, if it was traced and is still kept
+    pub fn get_execution_traces(
+        &self,
+        operation_ids: &[OperationId],
+    ) -> Vec<Option<ExecutionTrace>> {
+        let traces = self.execution_traces.lock();
+        operation_ids
+            .iter()
+            .map(|op_id| {
+                traces
+                    .iter()
+                    .rev()
+                    .find(|trace| &trace.operation_id == op_id)
+                    .cloned()
+            })
+            .collect()
     }
 
     /// Gets a balance both at the latest final and candidate executed slots
@@ -1118,6 +1360,52 @@ impl ExecutionState {
         )
     }
 
+    /// Gets the balance, roll count and datastore keys of an address at the output of a past final slot
+    pub fn get_final_address_state_at(
+        &self,
+        address: &Address,
+        slot: Slot,
+    ) -> Result<(Option<Amount>, u64, BTreeSet<Vec<u8>>), ExecutionError> {
+        let final_state = self.final_state.read();
+        let to_execution_error =
+            |err: FinalStateError| ExecutionError::HistoryError(err.to_string());
+        Ok((
+            final_state
+                .get_balance_at(address, slot)
+                .map_err(to_execution_error)?,
+            final_state
+                .get_rolls_at(address, slot)
+                .map_err(to_execution_error)?,
+            final_state
+                .get_datastore_keys_at(address, slot)
+                .map_err(to_execution_error)?,
+        ))
+    }
+
+    /// Gets a data entry at the output of a past final slot
+    pub fn get_final_data_entry_at(
+        &self,
+        address: &Address,
+        key: &[u8],
+        slot: Slot,
+    ) -> Result<Option<Vec<u8>>, ExecutionError> {
+        self.final_state
+            .read()
+            .get_data_entry_at(address, key, slot)
+            .map_err(|err| ExecutionError::HistoryError(err.to_string()))
+    }
+
+    /// Gets the final value of a ledger sub-entry along with its proof against the final ledger hash
+    pub fn get_final_ledger_proof(
+        &self,
+        address: &Address,
+        sub_entry: LedgerSubEntry,
+    ) -> LedgerEntryProof {
+        self.final_state
+            .read()
+            .get_ledger_proof(address, sub_entry)
+    }
+
     /// Get every final and active datastore key of the given address
     pub fn get_final_and_candidate_datastore_keys(
         &self,
@@ -1214,6 +1502,31 @@ impl ExecutionState {
         }
     }
 
+    /// Gets a page of final events matching the filter, starting right after `cursor`.
+    /// Events are read from the persistent event store if it is enabled,
+    /// otherwise from the final events kept in RAM.
+    pub fn get_final_sc_output_events_page(
+        &self,
+        filter: &EventFilter,
+        cursor: Option<EventCursor>,
+        limit: usize,
+    ) -> (Vec<SCOutputEvent>, Option<EventCursor>) {
+        if let Some(final_event_store) = self.final_event_store.as_ref() {
+            return final_event_store.get_filtered_events(filter, cursor, limit);
+        }
+        let mut events = self
+            .final_events
+            .get_filtered_sc_output_events(filter)
+            .into_iter()
+            .filter(|event| cursor.map_or(true, |cursor| event.cursor() > cursor));
+        let page: Vec<SCOutputEvent> = events.by_ref().take(limit).collect();
+        let next_cursor = match events.next() {
+            Some(_) => page.last().map(SCOutputEvent::cursor),
+            None => None,
+        };
+        (page, next_cursor)
+    }
+
     /// List which operations inside the provided list were not executed
     pub fn unexecuted_ops_among(
         &self,
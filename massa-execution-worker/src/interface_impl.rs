@@ -11,7 +11,8 @@ use massa_async_pool::AsyncMessage;
 use massa_execution_exports::ExecutionConfig;
 use massa_execution_exports::ExecutionStackElement;
 use massa_models::{
-    address::Address, amount::Amount, slot::Slot, timeslots::get_block_slot_timestamp,
+    address::Address, amount::Amount, execution::TraceAction, slot::Slot,
+    timeslots::get_block_slot_timestamp,
 };
 use massa_sc_runtime::{Interface, InterfaceClone};
 use parking_lot::Mutex;
@@ -115,6 +116,9 @@ impl Interface for InterfaceImpl {
             owned_addresses: vec![to_address],
             operation_datastore: None,
         });
+        if let Some(tracer) = context.tracer.as_mut() {
+            tracer.enter_call(to_address, coins);
+        }
 
         // return the target bytecode
         Ok(bytecode)
@@ -128,6 +132,9 @@ impl Interface for InterfaceImpl {
         if context.stack.pop().is_none() {
             bail!("call stack out of bounds")
         }
+        if let Some(tracer) = context.tracer.as_mut() {
+            tracer.exit_call();
+        }
 
         Ok(())
     }
@@ -184,8 +191,14 @@ impl Interface for InterfaceImpl {
     /// The datastore value matching the provided key, if found, otherwise an error.
     fn raw_get_data_for(&self, address: &str, key: &str) -> Result<Vec<u8>> {
         let addr = &massa_models::address::Address::from_str(address)?;
-        let context = context_guard!(self);
-        match context.get_data_entry(addr, key.as_bytes()) {
+        let mut context = context_guard!(self);
+        let entry = context.get_data_entry(addr, key.as_bytes());
+        context.trace(TraceAction::DatastoreRead {
+            address: *addr,
+            key: key.as_bytes().to_vec(),
+            value_size: entry.as_ref().map(Vec::len),
+        });
+        match entry {
             Some(value) => Ok(value),
             _ => bail!("data entry not found"),
         }
@@ -203,6 +216,12 @@ impl Interface for InterfaceImpl {
         let addr = massa_models::address::Address::from_str(address)?;
         let mut context = context_guard!(self);
         context.set_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
+        context.trace(TraceAction::DatastoreWrite {
+            address: addr,
+            key: key.as_bytes().to_vec(),
+            value_size: value.len(),
+            append: false,
+        });
         Ok(())
     }
 
@@ -215,7 +234,14 @@ impl Interface for InterfaceImpl {
     /// * value: value to append
     fn raw_append_data_for(&self, address: &str, key: &str, value: &[u8]) -> Result<()> {
         let addr = massa_models::address::Address::from_str(address)?;
-        context_guard!(self).append_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
+        let mut context = context_guard!(self);
+        context.append_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
+        context.trace(TraceAction::DatastoreWrite {
+            address: addr,
+            key: key.as_bytes().to_vec(),
+            value_size: value.len(),
+            append: true,
+        });
         Ok(())
     }
 
@@ -227,7 +253,12 @@ impl Interface for InterfaceImpl {
     /// * key: string key of the datastore entry to delete
     fn raw_delete_data_for(&self, address: &str, key: &str) -> Result<()> {
         let addr = &massa_models::address::Address::from_str(address)?;
-        context_guard!(self).delete_data_entry(addr, key.as_bytes())?;
+        let mut context = context_guard!(self);
+        context.delete_data_entry(addr, key.as_bytes())?;
+        context.trace(TraceAction::DatastoreDelete {
+            address: *addr,
+            key: key.as_bytes().to_vec(),
+        });
         Ok(())
     }
 
@@ -253,9 +284,15 @@ impl Interface for InterfaceImpl {
     /// # Returns
     /// The datastore value matching the provided key, if found, otherwise an error.
     fn raw_get_data(&self, key: &str) -> Result<Vec<u8>> {
-        let context = context_guard!(self);
+        let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
-        match context.get_data_entry(&addr, key.as_bytes()) {
+        let entry = context.get_data_entry(&addr, key.as_bytes());
+        context.trace(TraceAction::DatastoreRead {
+            address: addr,
+            key: key.as_bytes().to_vec(),
+            value_size: entry.as_ref().map(Vec::len),
+        });
+        match entry {
             Some(data) => Ok(data),
             _ => bail!("data entry not found"),
         }
@@ -273,6 +310,12 @@ impl Interface for InterfaceImpl {
         let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
         context.set_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
+        context.trace(TraceAction::DatastoreWrite {
+            address: addr,
+            key: key.as_bytes().to_vec(),
+            value_size: value.len(),
+            append: false,
+        });
         Ok(())
     }
 
@@ -287,6 +330,12 @@ impl Interface for InterfaceImpl {
         let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
         context.append_data_entry(&addr, key.as_bytes().to_vec(), value.to_vec())?;
+        context.trace(TraceAction::DatastoreWrite {
+            address: addr,
+            key: key.as_bytes().to_vec(),
+            value_size: value.len(),
+            append: true,
+        });
         Ok(())
     }
 
@@ -299,6 +348,10 @@ impl Interface for InterfaceImpl {
         let mut context = context_guard!(self);
         let addr = context.get_current_address()?;
         context.delete_data_entry(&addr, key.as_bytes())?;
+        context.trace(TraceAction::DatastoreDelete {
+            address: addr,
+            key: key.as_bytes().to_vec(),
+        });
         Ok(())
     }
 
@@ -434,6 +487,11 @@ impl Interface for InterfaceImpl {
         let mut context = context_guard!(self);
         let from_address = context.get_current_address()?;
         context.transfer_coins(Some(from_address), Some(to_address), amount, true)?;
+        context.trace(TraceAction::Transfer {
+            from: from_address,
+            to: to_address,
+            amount,
+        });
         Ok(())
     }
 
@@ -454,6 +512,11 @@ impl Interface for InterfaceImpl {
         let amount = massa_models::amount::Amount::from_raw(raw_amount);
         let mut context = context_guard!(self);
         context.transfer_coins(Some(from_address), Some(to_address), amount, true)?;
+        context.trace(TraceAction::Transfer {
+            from: from_address,
+            to: to_address,
+            amount,
+        });
         Ok(())
     }
 
@@ -501,6 +564,9 @@ impl Interface for InterfaceImpl {
     fn generate_event(&self, data: String) -> Result<()> {
         let mut context = context_guard!(self);
         let event = context.event_create(data);
+        context.trace(TraceAction::Event {
+            data: event.data.clone(),
+        });
         context.event_emit(event);
         Ok(())
     }
@@ -561,12 +627,19 @@ impl Interface for InterfaceImpl {
         let emission_index = execution_context.created_message_index;
         let sender = execution_context.get_current_address()?;
         let coins = Amount::from_raw(raw_coins);
+        let destination = Address::from_str(target_address)?;
         execution_context.transfer_coins(Some(sender), None, coins, true)?;
+        execution_context.trace(TraceAction::SendMessage {
+            destination,
+            handler: target_handler.to_string(),
+            max_gas,
+            coins,
+        });
         execution_context.push_new_message(AsyncMessage {
             emission_slot,
             emission_index,
             sender,
-            destination: Address::from_str(target_address)?,
+            destination,
             handler: target_handler.to_string(),
             validity_start: Slot::new(validity_start.0, validity_start.1),
             validity_end: Slot::new(validity_end.0, validity_end.1),
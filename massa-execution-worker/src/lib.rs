@@ -75,6 +75,9 @@
 //!
 //! ## `stats.rs`
 //! Defines a structure that gathers execution statistics.
+//!
+//! ## `trace.rs`
+//! Records the call tree of operation executions when execution tracing is enabled.
 
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
@@ -94,6 +97,7 @@ mod speculative_executed_ops;
 mod speculative_ledger;
 mod speculative_roll_state;
 mod stats;
+mod trace;
 mod worker;
 
 pub use worker::start_execution_worker;
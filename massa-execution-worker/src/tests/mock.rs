@@ -95,6 +95,7 @@ pub fn get_sample_state() -> Result<(Arc<RwLock<FinalState>>, NamedTempFile, Tem
         ledger_config,
         async_pool_config,
         final_history_length: 128,
+        reverse_history_length: 128,
         thread_count: THREAD_COUNT,
         initial_rolls_path: rolls_file.path().to_path_buf(),
         initial_seed_string: "".to_string(),
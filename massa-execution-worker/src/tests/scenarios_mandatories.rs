@@ -3,10 +3,11 @@
 use crate::start_execution_worker;
 use crate::tests::mock::{create_block, get_random_address_full, get_sample_state};
 use massa_execution_exports::{
-    ExecutionConfig, ExecutionController, ExecutionError, ReadOnlyExecutionRequest,
-    ReadOnlyExecutionTarget,
+    ExecutionChannels, ExecutionConfig, ExecutionController, ExecutionError, ExecutionStackElement,
+    ReadOnlyExecutionRequest, ReadOnlyExecutionTarget,
 };
 use massa_models::config::{LEDGER_ENTRY_BASE_SIZE, LEDGER_ENTRY_DATASTORE_BASE_SIZE};
+use massa_models::execution::{ReadOnlyResult, TraceAction};
 use massa_models::prehash::PreHashMap;
 use massa_models::{address::Address, amount::Amount, slot::Slot};
 use massa_models::{
@@ -32,6 +33,8 @@ fn test_execution_shutdown() {
         ExecutionConfig::default(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     manager.stop();
 }
@@ -44,6 +47,8 @@ fn test_sending_command() {
         ExecutionConfig::default(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     controller.update_blockclique_status(
         Default::default(),
@@ -61,6 +66,8 @@ fn test_readonly_execution() {
         ExecutionConfig::default(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     let mut res = controller
         .execute_readonly_request(ReadOnlyExecutionRequest {
@@ -72,7 +79,102 @@ fn test_readonly_execution() {
             ),
         })
         .expect("readonly execution failed");
-    assert_eq!(res.events.take().len(), 1, "wrong number of events");
+    assert_eq!(res.out.events.take().len(), 1, "wrong number of events");
+    assert!(res.gas_cost > 0, "no gas spent");
+
+    manager.stop();
+}
+
+/// Read-only call sending coins to an address without calling any function,
+/// as done to estimate the gas of a `CallSC` operation with an empty target function
+#[test]
+#[serial]
+fn test_readonly_call_with_coins() {
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+    let (mut manager, controller) = start_execution_worker(
+        ExecutionConfig::default(),
+        sample_state.clone(),
+        sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
+    );
+    let sender_keypair =
+        KeyPair::from_str("S1JJeHiZv1C1zZN5GLFcbz6EXYiccmUPLkYuDFA3kayjxP39kFQ").unwrap();
+    let sender_address = Address::from_public_key(&sender_keypair.get_public_key());
+    let sender_balance = sample_state
+        .read()
+        .ledger
+        .get_balance(&sender_address)
+        .unwrap();
+    let (target_address, _keypair) = get_random_address_full();
+    let coins = Amount::from_str("100").unwrap();
+    let res = controller
+        .execute_readonly_request(ReadOnlyExecutionRequest {
+            max_gas: 1_000_000,
+            simulated_gas_price: Amount::from_mantissa_scale(1_000_000, 0),
+            call_stack: vec![
+                ExecutionStackElement {
+                    address: sender_address,
+                    coins: Amount::zero(),
+                    owned_addresses: vec![sender_address],
+                    operation_datastore: None,
+                },
+                ExecutionStackElement {
+                    address: target_address,
+                    coins,
+                    owned_addresses: vec![target_address],
+                    operation_datastore: None,
+                },
+            ],
+            target: ReadOnlyExecutionTarget::FunctionCall {
+                target_addr: target_address,
+                target_func: String::new(),
+                parameter: String::new(),
+            },
+        })
+        .expect("readonly execution failed");
+
+    // no function was called but the coins were transferred
+    assert_eq!(res.gas_cost, 0, "gas spent without any function call");
+    let summary = res.out.state_changes.ledger_changes.get_summary();
+    let sender_change = summary
+        .iter()
+        .find(|change| change.address == sender_address)
+        .expect("sender balance unchanged");
+    assert_eq!(
+        sender_change.balance,
+        Some(sender_balance.checked_sub(coins).unwrap())
+    );
+    assert!(summary
+        .iter()
+        .any(|change| change.address == target_address && change.balance.is_some()));
+
+    // the call fails if the caller can't afford the coins
+    assert!(controller
+        .execute_readonly_request(ReadOnlyExecutionRequest {
+            max_gas: 1_000_000,
+            simulated_gas_price: Amount::from_mantissa_scale(1_000_000, 0),
+            call_stack: vec![
+                ExecutionStackElement {
+                    address: target_address,
+                    coins: Amount::zero(),
+                    owned_addresses: vec![target_address],
+                    operation_datastore: None,
+                },
+                ExecutionStackElement {
+                    address: sender_address,
+                    coins,
+                    owned_addresses: vec![sender_address],
+                    operation_datastore: None,
+                },
+            ],
+            target: ReadOnlyExecutionTarget::FunctionCall {
+                target_addr: sender_address,
+                target_func: String::new(),
+                parameter: String::new(),
+            },
+        })
+        .is_err());
 
     manager.stop();
 }
@@ -127,6 +229,8 @@ fn test_nested_call_gas_usage() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -265,6 +369,8 @@ fn send_and_receive_async_message() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -325,6 +431,8 @@ pub fn send_and_receive_transaction() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -384,6 +492,182 @@ pub fn send_and_receive_transaction() {
     manager.stop();
 }
 
+#[test]
+#[serial]
+pub fn query_balances_at_past_final_slot() {
+    // setup the period duration
+    let exec_cfg = ExecutionConfig {
+        t0: 100.into(),
+        cursor_delay: 0.into(),
+        ..ExecutionConfig::default()
+    };
+    // get a sample final state
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+
+    // init the storage
+    let mut storage = Storage::create_root();
+    // start the execution worker
+    let (mut manager, controller) = start_execution_worker(
+        exec_cfg.clone(),
+        sample_state.clone(),
+        sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
+    );
+    // initialize the execution system with genesis blocks
+    init_execution_worker(&exec_cfg, &storage, controller.clone());
+    // generate the sender_keypair and recipient_address
+    let sender_keypair =
+        KeyPair::from_str("S1JJeHiZv1C1zZN5GLFcbz6EXYiccmUPLkYuDFA3kayjxP39kFQ").unwrap();
+    let sender_address = Address::from_public_key(&sender_keypair.get_public_key());
+    let (recipient_address, _keypair) = get_random_address_full();
+    let sender_balance_before = sample_state
+        .read()
+        .ledger
+        .get_balance(&sender_address)
+        .unwrap();
+    // create the operation
+    let operation = Operation::new_wrapped(
+        Operation {
+            fee: Amount::zero(),
+            expire_period: 10,
+            op: OperationType::Transaction {
+                recipient_address,
+                amount: Amount::from_str("100").unwrap(),
+            },
+        },
+        OperationSerializer::new(),
+        &sender_keypair,
+    )
+    .unwrap();
+    // create the block containing the transaction operation
+    storage.store_operations(vec![operation.clone()]);
+    let block = create_block(KeyPair::generate(), vec![operation], Slot::new(1, 0)).unwrap();
+    // store the block in storage
+    storage.store_block(block.clone());
+    // set our block as a final block so the transaction is processed
+    let mut finalized_blocks: HashMap<Slot, BlockId> = Default::default();
+    finalized_blocks.insert(block.content.header.content.slot, block.id);
+    let mut block_storage: PreHashMap<BlockId, Storage> = Default::default();
+    block_storage.insert(block.id, storage.clone());
+    controller.update_blockclique_status(
+        finalized_blocks,
+        Default::default(),
+        block_storage.clone(),
+    );
+    std::thread::sleep(Duration::from_millis(10));
+
+    // before the transaction, the recipient did not exist
+    let last_genesis_slot = Slot::new(0, exec_cfg.thread_count - 1);
+    let infos = controller
+        .get_addresses_infos_at_slot(&[sender_address, recipient_address], last_genesis_slot)
+        .unwrap();
+    assert_eq!(infos[0].final_balance, sender_balance_before);
+    assert_eq!(infos[1].final_balance, Amount::zero());
+
+    // after the transaction, the balances match the current final ones
+    let infos = controller
+        .get_addresses_infos_at_slot(&[sender_address, recipient_address], Slot::new(1, 0))
+        .unwrap();
+    assert_eq!(
+        Some(infos[0].final_balance),
+        sample_state.read().ledger.get_balance(&sender_address)
+    );
+    assert_eq!(
+        Some(infos[1].final_balance),
+        sample_state.read().ledger.get_balance(&recipient_address)
+    );
+
+    // slots that are not final yet or older than the history cannot be queried
+    assert!(controller
+        .get_addresses_infos_at_slot(&[recipient_address], Slot::new(2, 0))
+        .is_err());
+    assert!(controller
+        .get_addresses_infos_at_slot(&[recipient_address], Slot::new(0, 0))
+        .is_err());
+
+    // stop the execution controller
+    manager.stop();
+}
+
+#[test]
+#[serial]
+pub fn simulate_transaction() {
+    // setup the period duration
+    let exec_cfg = ExecutionConfig {
+        t0: 100.into(),
+        cursor_delay: 0.into(),
+        ..ExecutionConfig::default()
+    };
+    // get a sample final state
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+
+    // init the storage
+    let storage = Storage::create_root();
+    // start the execution worker
+    let (mut manager, controller) = start_execution_worker(
+        exec_cfg.clone(),
+        sample_state.clone(),
+        sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
+    );
+    // initialize the execution system with genesis blocks
+    init_execution_worker(&exec_cfg, &storage, controller.clone());
+    // generate the sender_keypair and recipient_address
+    let sender_keypair =
+        KeyPair::from_str("S1JJeHiZv1C1zZN5GLFcbz6EXYiccmUPLkYuDFA3kayjxP39kFQ").unwrap();
+    let (recipient_address, _keypair) = get_random_address_full();
+    // create the operation
+    let operation = Operation::new_wrapped(
+        Operation {
+            fee: Amount::from_str("1").unwrap(),
+            expire_period: 10,
+            op: OperationType::Transaction {
+                recipient_address,
+                amount: Amount::from_str("100").unwrap(),
+            },
+        },
+        OperationSerializer::new(),
+        &sender_keypair,
+    )
+    .unwrap();
+    // simulate the operation twice: the second one cannot be included
+    let simulations = controller
+        .simulate_operations(vec![operation.clone(), operation])
+        .expect("operation simulation failed");
+    assert_eq!(simulations.len(), 2);
+    assert!(matches!(simulations[0].result, ReadOnlyResult::Ok));
+    assert_eq!(simulations[0].fee, Amount::from_str("1").unwrap());
+    let recipient_change = simulations[0]
+        .balance_changes
+        .iter()
+        .find(|change| change.address == recipient_address)
+        .expect("missing recipient balance change");
+    assert_eq!(recipient_change.balance_before, None);
+    assert_eq!(
+        recipient_change.balance_after,
+        Some(
+            Amount::from_str("100").unwrap().saturating_sub(
+                exec_cfg
+                    .storage_costs_constants
+                    .ledger_cost_per_byte
+                    .saturating_mul_u64(LEDGER_ENTRY_BASE_SIZE as u64)
+            )
+        )
+    );
+    assert!(matches!(simulations[1].result, ReadOnlyResult::Error(_)));
+    assert_eq!(simulations[1].fee, Amount::zero());
+    // check that nothing was applied
+    assert!(sample_state
+        .read()
+        .ledger
+        .get_balance(&recipient_address)
+        .is_none());
+    // stop the execution controller
+    manager.stop();
+}
+
 #[test]
 #[serial]
 pub fn roll_buy() {
@@ -403,6 +687,8 @@ pub fn roll_buy() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -468,6 +754,8 @@ pub fn roll_sell() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -536,6 +824,8 @@ fn sc_execution_error() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -595,6 +885,8 @@ fn sc_datastore() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -634,6 +926,93 @@ fn sc_datastore() {
     manager.stop();
 }
 
+#[test]
+#[serial]
+fn sc_execution_trace() {
+    // setup the period duration and enable execution tracing
+    let exec_cfg = ExecutionConfig {
+        t0: 100.into(),
+        max_async_gas: 100_000,
+        cursor_delay: 0.into(),
+        trace_executions: true,
+        ..ExecutionConfig::default()
+    };
+    // get a sample final state
+    let (sample_state, _keep_file, _keep_dir) = get_sample_state().unwrap();
+
+    // init the storage
+    let mut storage = Storage::create_root();
+    // start the execution worker
+    let (mut manager, controller) = start_execution_worker(
+        exec_cfg.clone(),
+        sample_state.clone(),
+        sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
+    );
+    // initialize the execution system with genesis blocks
+    init_execution_worker(&exec_cfg, &storage, controller.clone());
+    // keypair associated to thread 0
+    let keypair = KeyPair::from_str("S1JJeHiZv1C1zZN5GLFcbz6EXYiccmUPLkYuDFA3kayjxP39kFQ").unwrap();
+    let address = Address::from_public_key(&keypair.get_public_key());
+    // load bytecode
+    // you can check the source code of the following wasm file in massa-sc-examples
+    let bytecode = include_bytes!("./wasm/datastore.wasm");
+
+    let datastore = BTreeMap::from([(vec![65, 66], vec![255]), (vec![9], vec![10, 11])]);
+
+    // create the block contaning the smart contract execution operation
+    let operation =
+        create_execute_sc_operation_with_datastore(&keypair, bytecode, datastore).unwrap();
+    storage.store_operations(vec![operation.clone()]);
+    let block = create_block(
+        KeyPair::generate(),
+        vec![operation.clone()],
+        Slot::new(1, 0),
+    )
+    .unwrap();
+    // store the block in storage
+    storage.store_block(block.clone());
+    // set our block as a final block
+    let mut finalized_blocks: HashMap<Slot, BlockId> = Default::default();
+    finalized_blocks.insert(block.content.header.content.slot, block.id);
+    let mut block_storage: PreHashMap<BlockId, Storage> = Default::default();
+    block_storage.insert(block.id, storage.clone());
+    controller.update_blockclique_status(finalized_blocks, Some(Default::default()), block_storage);
+    std::thread::sleep(Duration::from_millis(10));
+
+    // retrieve the trace of the operation, and of an operation that was never executed
+    let unknown_operation = create_execute_sc_operation(&keypair, bytecode).unwrap();
+    let traces = controller.get_execution_traces(&[operation.id, unknown_operation.id]);
+    assert!(traces[1].is_none());
+    let trace = traces[0].as_ref().expect("missing execution trace");
+    assert_eq!(trace.executed_at, Slot::new(1, 0));
+    assert_eq!(trace.block_id, Some(block.id));
+    assert!(trace.error.is_none());
+    assert_eq!(trace.root.address, address);
+    assert!(trace.root.gas_cost.unwrap() > 0);
+    let events: Vec<&String> = trace
+        .root
+        .actions
+        .iter()
+        .filter_map(|action| match action {
+            TraceAction::Event { data } => Some(data),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            "keys: 9,65,66",
+            "has_key_1: true - has_key_2: false",
+            "data key 1: 255 - data key 3: 10,11"
+        ]
+    );
+
+    // stop the execution controller
+    manager.stop();
+}
+
 #[test]
 #[serial]
 fn set_bytecode_error() {
@@ -654,6 +1033,8 @@ fn set_bytecode_error() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -711,6 +1092,8 @@ fn datastore_manipulations() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -787,6 +1170,8 @@ fn events_from_switching_blockclique() {
         exec_cfg.clone(),
         sample_state.clone(),
         sample_state.read().pos_state.selector.clone(),
+        ExecutionChannels::default(),
+        None,
     );
     // initialize the execution system with genesis blocks
     init_execution_worker(&exec_cfg, &storage, controller.clone());
@@ -0,0 +1,146 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module records the call tree of an operation execution when execution tracing is enabled.
+//! A frame is opened for each element pushed on the call stack, and the actions
+//! happening during the call are recorded in the frame at the top of the stack.
+
+use massa_models::address::Address;
+use massa_models::amount::Amount;
+use massa_models::execution::{TraceAction, TraceFrame};
+
+/// Call tree recorder of an execution
+#[derive(Default)]
+pub(crate) struct ExecutionTracer {
+    /// frames of the calls that have not returned yet, the root call is at the front
+    open_frames: Vec<TraceFrame>,
+    /// root frame, once the root call has returned
+    root: Option<TraceFrame>,
+}
+
+impl ExecutionTracer {
+    /// Opens the frame of a new call
+    ///
+    /// # Arguments
+    /// * `address`: called address
+    /// * `coins`: coins transferred to the called address
+    pub fn enter_call(&mut self, address: Address, coins: Amount) {
+        self.open_frames.push(TraceFrame {
+            address,
+            coins,
+            gas_cost: None,
+            actions: Vec::new(),
+        });
+    }
+
+    /// Closes the frame of the current call and attaches it to the frame of its caller
+    pub fn exit_call(&mut self) {
+        if let Some(frame) = self.open_frames.pop() {
+            match self.open_frames.last_mut() {
+                Some(caller) => caller.actions.push(TraceAction::Call(frame)),
+                None => self.root = Some(frame),
+            }
+        }
+    }
+
+    /// Records an action in the frame of the current call.
+    /// Actions happening outside of any call are ignored.
+    pub fn record(&mut self, action: TraceAction) {
+        if let Some(frame) = self.open_frames.last_mut() {
+            frame.actions.push(action);
+        }
+    }
+
+    /// Sets the gas spent by the whole execution on the frames still open once it returned,
+    /// which are the root frame and the frame of the function run by the VM.
+    /// The frames of the nested calls made by the bytecode are closed by then
+    /// and keep a `None` gas cost, the runtime does not report the gas of nested calls.
+    pub fn set_gas_cost(&mut self, gas_cost: u64) {
+        for frame in self.open_frames.iter_mut() {
+            frame.gas_cost = Some(gas_cost);
+        }
+    }
+
+    /// Closes the frames of the calls interrupted by an error and returns the root frame,
+    /// or `None` if no call happened
+    pub fn finish(mut self) -> Option<TraceFrame> {
+        while !self.open_frames.is_empty() {
+            self.exit_call();
+        }
+        self.root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExecutionTracer;
+    use massa_models::address::Address;
+    use massa_models::amount::Amount;
+    use massa_models::execution::TraceAction;
+    use massa_signature::KeyPair;
+
+    #[test]
+    fn test_tracer_call_tree() {
+        let caller = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let callee = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let mut tracer = ExecutionTracer::default();
+
+        tracer.record(TraceAction::Event {
+            data: "ignored".into(),
+        });
+        tracer.enter_call(caller, Amount::zero());
+        tracer.enter_call(callee, Amount::from_raw(10));
+        tracer.record(TraceAction::Event {
+            data: "nested".into(),
+        });
+        tracer.exit_call();
+        tracer.set_gas_cost(42);
+        tracer.record(TraceAction::Event {
+            data: "root".into(),
+        });
+        // the root call is interrupted and never returns
+        tracer.enter_call(callee, Amount::zero());
+
+        let root = tracer.finish().expect("missing root frame");
+        assert_eq!(root.address, caller);
+        assert_eq!(root.gas_cost, Some(42));
+        assert_eq!(root.actions.len(), 3);
+        match &root.actions[0] {
+            TraceAction::Call(frame) => {
+                assert_eq!(frame.address, callee);
+                assert_eq!(frame.coins, Amount::from_raw(10));
+                assert_eq!(frame.gas_cost, None);
+                assert_eq!(frame.actions.len(), 1);
+            }
+            _ => panic!("expected a nested call"),
+        }
+        assert!(matches!(&root.actions[1], TraceAction::Event { data } if data == "root"));
+        assert!(matches!(&root.actions[2], TraceAction::Call(frame) if frame.actions.is_empty()));
+    }
+
+    #[test]
+    fn test_tracer_gas_cost() {
+        let sender = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let target = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let mut tracer = ExecutionTracer::default();
+
+        // frames of a `CallSC` operation: the VM runs a function of the target,
+        // which calls the sender
+        tracer.enter_call(sender, Amount::zero());
+        tracer.enter_call(target, Amount::zero());
+        tracer.enter_call(sender, Amount::zero());
+        tracer.exit_call();
+        tracer.set_gas_cost(42);
+
+        let root = tracer.finish().expect("missing root frame");
+        assert_eq!(root.gas_cost, Some(42));
+        let target_frame = match &root.actions[..] {
+            [TraceAction::Call(frame)] => frame,
+            _ => panic!("expected a single call"),
+        };
+        assert_eq!(target_frame.gas_cost, Some(42));
+        match &target_frame.actions[..] {
+            [TraceAction::Call(frame)] => assert_eq!(frame.gas_cost, None),
+            _ => panic!("expected a single nested call"),
+        }
+    }
+}
@@ -10,11 +10,14 @@ use crate::execution::ExecutionState;
 use crate::request_queue::RequestQueue;
 use crate::slot_sequencer::SlotSequencer;
 use massa_execution_exports::{
-    ExecutionConfig, ExecutionController, ExecutionError, ExecutionManager, ExecutionOutput,
-    ReadOnlyExecutionRequest,
+    ExecutionChannels, ExecutionConfig, ExecutionController, ExecutionError, ExecutionManager,
+    ReadOnlyExecutionOutput, ReadOnlyExecutionRequest,
 };
 use massa_final_state::FinalState;
+use massa_ledger_exports::EventStoreController;
 use massa_models::block::BlockId;
+use massa_models::execution::OperationSimulation;
+use massa_models::operation::WrappedOperation;
 use massa_models::slot::Slot;
 use massa_pos_exports::SelectorController;
 use massa_storage::Storage;
@@ -35,7 +38,9 @@ pub(crate) struct ExecutionThread {
     // Execution state (see execution.rs) to which execution requests are sent
     execution_state: Arc<RwLock<ExecutionState>>,
     /// queue for read-only requests and response MPSCs to send back their outputs
-    readonly_requests: RequestQueue<ReadOnlyExecutionRequest, ExecutionOutput>,
+    readonly_requests: RequestQueue<ReadOnlyExecutionRequest, ReadOnlyExecutionOutput>,
+    /// queue for operation simulation requests and response MPSCs to send back their outputs
+    simulation_requests: RequestQueue<Vec<WrappedOperation>, Vec<OperationSimulation>>,
     /// Selector controller
     selector: Box<dyn SelectorController>,
 }
@@ -61,6 +66,7 @@ impl ExecutionThread {
         ExecutionThread {
             input_data,
             readonly_requests: RequestQueue::new(config.readonly_queue_length),
+            simulation_requests: RequestQueue::new(config.readonly_queue_length),
             execution_state,
             slot_sequencer: SlotSequencer::new(config.clone(), final_cursor),
             selector,
@@ -72,13 +78,23 @@ impl ExecutionThread {
     /// Cancel those that are in excess if there are too many.
     fn update_readonly_requests(
         &mut self,
-        new_requests: RequestQueue<ReadOnlyExecutionRequest, ExecutionOutput>,
+        new_requests: RequestQueue<ReadOnlyExecutionRequest, ReadOnlyExecutionOutput>,
     ) {
         // Append incoming readonly requests to our readonly request queue
         // Excess requests are cancelled
         self.readonly_requests.extend(new_requests);
     }
 
+    /// Append incoming operation simulation requests to the relevant queue,
+    /// Cancel those that are in excess if there are too many.
+    fn update_simulation_requests(
+        &mut self,
+        new_requests: RequestQueue<Vec<WrappedOperation>, Vec<OperationSimulation>>,
+    ) {
+        // Excess requests are cancelled
+        self.simulation_requests.extend(new_requests);
+    }
+
     /// Executes a read-only request from the queue, if any.
     /// The result of the execution is sent asynchronously through the response channel provided with the request.
     ///
@@ -101,6 +117,27 @@ impl ExecutionThread {
         false
     }
 
+    /// Executes an operation simulation request from the queue, if any.
+    /// The result of the simulation is sent asynchronously through the response channel provided with the request.
+    ///
+    /// # Returns
+    /// true if a request was executed, false otherwise
+    fn execute_one_simulation_request(&mut self) -> bool {
+        if let Some(req_resp) = self.simulation_requests.pop() {
+            let (operations, resp_tx) = req_resp.into_request_sender_pair();
+
+            // Acquire read access to the execution state and simulate the operations
+            let outcome = self.execution_state.read().simulate_operations(operations);
+
+            // Send the simulation outcome through resp_tx, ignoring errors
+            // because they just mean that the request emitter does not need the response anymore.
+            let _ = resp_tx.send(Ok(outcome));
+
+            return true;
+        }
+        false
+    }
+
     /// Waits for an event to trigger a new iteration in the execution main loop.
     ///
     /// # Returns
@@ -124,6 +161,7 @@ impl ExecutionThread {
                 || !input_data.finalized_blocks.is_empty()
                 || !input_data.block_storage.is_empty()
                 || !input_data.readonly_requests.is_empty()
+                || !input_data.simulation_requests.is_empty()
             {
                 return (input_data, false);
             }
@@ -133,8 +171,8 @@ impl ExecutionThread {
                 return (input_data, false);
             }
 
-            // there are read-only requests ready
-            if !self.readonly_requests.is_empty() {
+            // there are read-only or simulation requests ready
+            if !self.readonly_requests.is_empty() || !self.simulation_requests.is_empty() {
                 return (input_data, false);
             }
 
@@ -165,13 +203,14 @@ impl ExecutionThread {
         // It also prioritizes executions in the following order:
         // 1 - final executions
         // 2 - speculative executions
-        // 3 - read-only executions
+        // 3 - read-only executions and operation simulations
         loop {
             let (input_data, stop) = self.wait_loop_event();
             debug!("Execution loop triggered, input_data = {}", input_data);
 
             // update the sequence of read-only requests
             self.update_readonly_requests(input_data.readonly_requests);
+            self.update_simulation_requests(input_data.simulation_requests);
 
             if stop {
                 // we need to stop
@@ -209,7 +248,12 @@ impl ExecutionThread {
             }
 
             // low priority: execute a read-only request (note that the queue is of finite length), if there is one ready.
-            self.execute_one_readonly_request();
+            if self.execute_one_readonly_request() {
+                continue;
+            }
+
+            // low priority: simulate operations, if there is a request ready.
+            self.execute_one_simulation_request();
         }
 
         // We are quitting the loop.
@@ -218,12 +262,14 @@ impl ExecutionThread {
         let cancel_err = ExecutionError::ChannelError(
             "readonly execution cancelled because the execution worker is closing".into(),
         );
-        self.input_data
-            .1
-            .lock()
-            .take()
-            .readonly_requests
-            .cancel(cancel_err);
+        let mut input_data = self.input_data.1.lock().take();
+        input_data.readonly_requests.cancel(cancel_err);
+
+        // Cancel pending operation simulation requests
+        let cancel_err = ExecutionError::ChannelError(
+            "operation simulation cancelled because the execution worker is closing".into(),
+        );
+        input_data.simulation_requests.cancel(cancel_err);
     }
 }
 
@@ -232,6 +278,9 @@ impl ExecutionThread {
 /// # parameters
 /// * `config`: execution configuration
 /// * `final_state`: a thread-safe shared access to the final state for reading and writing
+/// * `selector`: selector controller
+/// * `channels`: channels used to broadcast execution outputs
+/// * `final_event_store`: optional persistent store of final events
 ///
 /// # Returns
 /// A pair `(execution_manager, execution_controller)` where:
@@ -241,11 +290,15 @@ pub fn start_execution_worker(
     config: ExecutionConfig,
     final_state: Arc<RwLock<FinalState>>,
     selector: Box<dyn SelectorController>,
+    channels: ExecutionChannels,
+    final_event_store: Option<Box<dyn EventStoreController>>,
 ) -> (Box<dyn ExecutionManager>, Box<dyn ExecutionController>) {
     // create an execution state
     let execution_state = Arc::new(RwLock::new(ExecutionState::new(
         config.clone(),
         final_state,
+        channels,
+        final_event_store,
     )));
 
     // define the input data interface
@@ -27,4 +27,7 @@ pub struct FactoryConfig {
 
     /// maximal block gas
     pub max_block_gas: u64,
+
+    /// maximal number of endorsements per block
+    pub max_block_endorsement_count: u32,
 }
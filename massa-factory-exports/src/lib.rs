@@ -9,11 +9,13 @@
 mod config;
 mod controller_traits;
 mod error;
+mod strategy;
 mod types;
 
 pub use config::FactoryConfig;
 pub use controller_traits::FactoryManager;
 pub use error::*;
+pub use strategy::{BlockContents, BlockProductionStrategy, DefaultBlockProductionStrategy};
 pub use types::*;
 
 /// Tests utils
@@ -0,0 +1,109 @@
+//! Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This module defines the strategies choosing the contents of the blocks produced by the factory.
+
+use crate::{FactoryChannels, FactoryError, FactoryResult};
+use massa_models::{
+    block::BlockId, endorsement::WrappedEndorsement, operation::OperationId, prehash::PreHashSet,
+    slot::Slot,
+};
+use massa_storage::Storage;
+
+/// Contents of a block to produce, chosen by a `BlockProductionStrategy`
+pub struct BlockContents {
+    /// parents of the block, one per thread
+    pub parents: Vec<BlockId>,
+    /// endorsements of the block
+    pub endorsements: Vec<WrappedEndorsement>,
+    /// operations of the block, in order
+    pub operations: Vec<OperationId>,
+    /// storage holding references to the parents, endorsements and operations of the block
+    pub storage: Storage,
+}
+
+/// Strategy choosing the parents, endorsements and operations of the blocks produced by the block factory.
+/// The factory then checks the contents, builds and signs the block, and sends it to consensus.
+/// Contents that would make an invalid block are replaced by the ones of `DefaultBlockProductionStrategy`.
+pub trait BlockProductionStrategy: Send {
+    /// Chooses the contents of the block produced at `slot`.
+    ///
+    /// # Arguments
+    /// * `slot`: slot of the block
+    /// * `channels`: channels to the consensus, the pool and the storage the contents are chosen from
+    fn choose_block_contents(
+        &mut self,
+        slot: Slot,
+        channels: &FactoryChannels,
+    ) -> FactoryResult<BlockContents>;
+}
+
+/// Default block production strategy:
+/// the best parents according to consensus, and the endorsements and operations chosen by the pool.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultBlockProductionStrategy;
+
+impl BlockProductionStrategy for DefaultBlockProductionStrategy {
+    fn choose_block_contents(
+        &mut self,
+        slot: Slot,
+        channels: &FactoryChannels,
+    ) -> FactoryResult<BlockContents> {
+        // get best parents and their periods
+        let parents: Vec<(BlockId, u64)> =
+            channels.consensus.get_best_parents().map_err(|err| {
+                FactoryError::GenericError(format!("could not get best parents: {}", err))
+            })?;
+
+        // generate the local storage object
+        let mut storage = channels.storage.clone_without_refs();
+
+        // claim block parents in local storage
+        let claimed_parents = storage.claim_block_refs(
+            &parents
+                .iter()
+                .map(|(b_id, _)| *b_id)
+                .collect::<PreHashSet<BlockId>>(),
+        );
+        if claimed_parents.len() != parents.len() {
+            return Err(FactoryError::GenericError(
+                "could not claim parents".to_string(),
+            ));
+        }
+
+        // get the parent in the same thread, with its period
+        // will not panic because the thread is validated before the call
+        let (same_thread_parent_id, _) = parents[slot.thread as usize];
+
+        // gather endorsements
+        let (endorsements_ids, endo_storage) = channels
+            .pool
+            .get_block_endorsements(&same_thread_parent_id, &slot);
+
+        //TODO: Do we want ot populate only with endorsement id in the future ?
+        let endorsements: Vec<WrappedEndorsement> = {
+            let endo_read = endo_storage.read_endorsements();
+            endorsements_ids
+                .into_iter()
+                .flatten()
+                .map(|endo_id| {
+                    endo_read
+                        .get(&endo_id)
+                        .expect("could not retrieve endorsement")
+                        .clone()
+                })
+                .collect()
+        };
+        storage.extend(endo_storage);
+
+        // gather operations
+        let (operations, op_storage) = channels.pool.get_block_operations(&slot);
+        storage.extend(op_storage);
+
+        Ok(BlockContents {
+            parents: parents.into_iter().map(|(id, _period)| id).collect(),
+            endorsements,
+            operations,
+            storage,
+        })
+    }
+}
@@ -1,19 +1,23 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use massa_factory_exports::{FactoryChannels, FactoryConfig};
+use massa_factory_exports::{
+    BlockContents, BlockProductionStrategy, DefaultBlockProductionStrategy, FactoryChannels,
+    FactoryConfig, FactoryError, FactoryResult,
+};
 use massa_hash::Hash;
 use massa_models::{
-    block::{Block, BlockHeader, BlockHeaderSerializer, BlockId, BlockSerializer, WrappedHeader},
-    endorsement::WrappedEndorsement,
+    block::{
+        Block, BlockHeader, BlockHeaderSerializer, BlockSerializer, WrappedBlock, WrappedHeader,
+    },
     prehash::PreHashSet,
     slot::Slot,
     timeslots::{get_block_slot_timestamp, get_closest_slot_to_timestamp},
     wrapped::WrappedContent,
 };
 use massa_time::MassaTime;
-use massa_wallet::Wallet;
-use parking_lot::RwLock;
+use massa_wallet::{Signer, SigningContent};
 use std::{
+    collections::HashSet,
     sync::{mpsc, Arc},
     thread,
     time::Instant,
@@ -23,8 +27,9 @@ use tracing::{info, warn};
 /// Structure gathering all elements needed by the factory thread
 pub(crate) struct BlockFactoryWorker {
     cfg: FactoryConfig,
-    wallet: Arc<RwLock<Wallet>>,
+    signer: Arc<dyn Signer>,
     channels: FactoryChannels,
+    strategy: Box<dyn BlockProductionStrategy>,
     factory_receiver: mpsc::Receiver<()>,
 }
 
@@ -33,8 +38,9 @@ impl BlockFactoryWorker {
     /// needed by the factory worker thread.
     pub(crate) fn spawn(
         cfg: FactoryConfig,
-        wallet: Arc<RwLock<Wallet>>,
+        signer: Arc<dyn Signer>,
         channels: FactoryChannels,
+        strategy: Box<dyn BlockProductionStrategy>,
         factory_receiver: mpsc::Receiver<()>,
     ) -> thread::JoinHandle<()> {
         thread::Builder::new()
@@ -42,8 +48,9 @@ impl BlockFactoryWorker {
             .spawn(|| {
                 let mut this = Self {
                     cfg,
-                    wallet,
+                    signer,
                     channels,
+                    strategy,
                     factory_receiver,
                 };
                 this.run();
@@ -131,69 +138,34 @@ impl BlockFactoryWorker {
             }
         };
 
-        // check if the block producer address is handled by the wallet
-        let block_producer_keypair_ref = self.wallet.read();
-        let block_producer_keypair = if let Some(kp) =
-            block_producer_keypair_ref.find_associated_keypair(&block_producer_addr)
-        {
-            // the selected block producer is managed locally => continue to attempt block production
-            kp
-        } else {
-            // the selected block producer is not managed locally => quit
-            return;
-        };
-        // get best parents and their periods
-        let parents: Vec<(BlockId, u64)> = self
-            .channels
-            .consensus
-            .get_best_parents()
-            .expect("Couldn't get best parents"); // Vec<(parent_id, parent_period)>
-                                                  // generate the local storage object
-        let mut block_storage = self.channels.storage.clone_without_refs();
+        // check if the block producer address is handled by the signer
+        let block_producer_public_key =
+            if let Some(public_key) = self.signer.get_public_key(&block_producer_addr) {
+                // the selected block producer is managed by the signer => continue to attempt block production
+                public_key
+            } else {
+                // the selected block producer is not managed by the signer => quit
+                return;
+            };
 
-        // claim block parents in local storage
-        {
-            let claimed_parents = block_storage.claim_block_refs(
-                &parents
-                    .iter()
-                    .map(|(b_id, _)| *b_id)
-                    .collect::<PreHashSet<BlockId>>(),
-            );
-            if claimed_parents.len() != parents.len() {
-                warn!("block factory could claim parents for slot {}", slot);
+        // choose the contents of the block
+        let BlockContents {
+            parents,
+            endorsements,
+            operations: op_ids,
+            storage: mut block_storage,
+        } = match choose_block_contents(&self.cfg, self.strategy.as_mut(), slot, &self.channels) {
+            Ok(contents) => contents,
+            Err(err) => {
+                warn!(
+                    "block factory could not choose the contents of the block at slot {}: {}",
+                    slot, err
+                );
                 return;
             }
-        }
-
-        // get the parent in the same thread, with its period
-        // will not panic because the thread is validated before the call
-        let (same_thread_parent_id, _) = parents[slot.thread as usize];
-
-        // gather endorsements
-        let (endorsements_ids, endo_storage) = self
-            .channels
-            .pool
-            .get_block_endorsements(&same_thread_parent_id, &slot);
-
-        //TODO: Do we want ot populate only with endorsement id in the future ?
-        let endorsements: Vec<WrappedEndorsement> = {
-            let endo_read = endo_storage.read_endorsements();
-            endorsements_ids
-                .into_iter()
-                .flatten()
-                .map(|endo_id| {
-                    endo_read
-                        .get(&endo_id)
-                        .expect("could not retrieve endorsement")
-                        .clone()
-                })
-                .collect()
         };
-        block_storage.extend(endo_storage);
 
-        // gather operations and compute global operations hash
-        let (op_ids, op_storage) = self.channels.pool.get_block_operations(&slot);
-        block_storage.extend(op_storage);
+        // compute global operations hash
         let global_operations_hash = Hash::compute_from(
             &op_ids
                 .iter()
@@ -202,28 +174,49 @@ impl BlockFactoryWorker {
         );
 
         // create header
-        let header: WrappedHeader = BlockHeader::new_wrapped::<BlockHeaderSerializer, BlockId>(
+        let header: WrappedHeader = match BlockHeader::new_wrapped_with_signer(
             BlockHeader {
                 slot,
-                parents: parents.into_iter().map(|(id, _period)| id).collect(),
+                parents,
                 operation_merkle_root: global_operations_hash,
                 endorsements,
             },
             BlockHeaderSerializer::new(), // TODO reuse self.block_header_serializer
-            block_producer_keypair,
-        )
-        .expect("error while producing block header");
+            block_producer_public_key,
+            |_hash, content| {
+                self.signer.sign(
+                    &block_producer_addr,
+                    &SigningContent::BlockHeader(content.to_vec()),
+                )
+            },
+        ) {
+            Ok(header) => header,
+            Err(err) => {
+                warn!(
+                    "block factory could not sign the block header at slot {}: {}",
+                    slot, err
+                );
+                return;
+            }
+        };
 
-        // create block
-        let block = Block::new_wrapped(
+        // create block, signed by its header
+        let block: WrappedBlock = match Block::new_wrapped_with_header(
             Block {
                 header,
                 operations: op_ids.into_iter().collect(),
             },
             BlockSerializer::new(), // TODO reuse self.block_serializer
-            block_producer_keypair,
-        )
-        .expect("error while producing block");
+        ) {
+            Ok(block) => block,
+            Err(err) => {
+                warn!(
+                    "block factory could not create the block at slot {}: {}",
+                    slot, err
+                );
+                return;
+            }
+        };
         let block_id = block.id;
         // store block in storage
         block_storage.store_block(block);
@@ -265,3 +258,118 @@ impl BlockFactoryWorker {
         }
     }
 }
+
+/// Chooses the contents of the block produced at `slot` with `strategy`.
+/// If the strategy chooses contents that would make an invalid block,
+/// they are dropped and the contents are chosen by the default strategy instead.
+pub(crate) fn choose_block_contents(
+    cfg: &FactoryConfig,
+    strategy: &mut dyn BlockProductionStrategy,
+    slot: Slot,
+    channels: &FactoryChannels,
+) -> FactoryResult<BlockContents> {
+    let contents = strategy.choose_block_contents(slot, channels)?;
+    match check_block_contents(cfg, slot, &contents) {
+        Ok(()) => Ok(contents),
+        Err(err) => {
+            warn!(
+                "block production strategy chose invalid contents at slot {}, falling back to the default strategy: {}",
+                slot, err
+            );
+            DefaultBlockProductionStrategy.choose_block_contents(slot, channels)
+        }
+    }
+}
+
+/// Checks that `contents` make a valid block at `slot`:
+/// one parent per thread, endorsements of the parent in the slot thread at distinct indexes,
+/// and operations of the slot thread that fit in the block, with the parents and operations claimed in storage.
+pub(crate) fn check_block_contents(
+    cfg: &FactoryConfig,
+    slot: Slot,
+    contents: &BlockContents,
+) -> FactoryResult<()> {
+    // parents
+    if contents.parents.len() != cfg.thread_count as usize {
+        return Err(FactoryError::GenericError(format!(
+            "expected {} parents, got {}",
+            cfg.thread_count,
+            contents.parents.len()
+        )));
+    }
+    let block_refs = contents.storage.get_block_refs();
+    if let Some(parent) = contents.parents.iter().find(|id| !block_refs.contains(id)) {
+        return Err(FactoryError::GenericError(format!(
+            "parent {} is not claimed in storage",
+            parent
+        )));
+    }
+
+    // endorsements
+    if contents.endorsements.len() > cfg.max_block_endorsement_count as usize {
+        return Err(FactoryError::GenericError(format!(
+            "{} endorsements exceed the max of {}",
+            contents.endorsements.len(),
+            cfg.max_block_endorsement_count
+        )));
+    }
+    let same_thread_parent = contents.parents[slot.thread as usize];
+    let mut endorsement_indexes = HashSet::with_capacity(contents.endorsements.len());
+    for endorsement in &contents.endorsements {
+        if endorsement.content.slot != slot
+            || endorsement.content.endorsed_block != same_thread_parent
+            || endorsement.content.index >= cfg.max_block_endorsement_count
+            || !endorsement_indexes.insert(endorsement.content.index)
+        {
+            return Err(FactoryError::GenericError(format!(
+                "endorsement {} can't be included in the block",
+                endorsement.id
+            )));
+        }
+    }
+
+    // operations
+    let op_refs = contents.storage.get_op_refs();
+    let ops = contents.storage.read_operations();
+    let mut op_ids = PreHashSet::with_capacity(contents.operations.len());
+    let mut total_size: u64 = 0;
+    let mut total_gas: u64 = 0;
+    for op_id in &contents.operations {
+        let op = match ops.get(op_id) {
+            Some(op) if op_refs.contains(op_id) => op,
+            _ => {
+                return Err(FactoryError::GenericError(format!(
+                    "operation {} is not claimed in storage",
+                    op_id
+                )))
+            }
+        };
+        if !op_ids.insert(*op_id) {
+            return Err(FactoryError::GenericError(format!(
+                "operation {} is included twice",
+                op_id
+            )));
+        }
+        if op.creator_address.get_thread(cfg.thread_count) != slot.thread {
+            return Err(FactoryError::GenericError(format!(
+                "operation {} is not in the thread of the block",
+                op_id
+            )));
+        }
+        total_size = total_size.saturating_add(op.serialized_size() as u64);
+        total_gas = total_gas.saturating_add(op.get_gas_usage());
+    }
+    if total_size > cfg.max_block_size {
+        return Err(FactoryError::GenericError(format!(
+            "operations size {} exceeds the max block size {}",
+            total_size, cfg.max_block_size
+        )));
+    }
+    if total_gas > cfg.max_block_gas {
+        return Err(FactoryError::GenericError(format!(
+            "operations gas {} exceeds the max block gas {}",
+            total_gas, cfg.max_block_gas
+        )));
+    }
+    Ok(())
+}
@@ -2,16 +2,16 @@
 
 use massa_factory_exports::{FactoryChannels, FactoryConfig};
 use massa_models::{
+    address::Address,
     block::BlockId,
     endorsement::{Endorsement, EndorsementSerializer, WrappedEndorsement},
     slot::Slot,
     timeslots::{get_block_slot_timestamp, get_closest_slot_to_timestamp},
     wrapped::WrappedContent,
 };
-use massa_signature::KeyPair;
+use massa_signature::PublicKey;
 use massa_time::MassaTime;
-use massa_wallet::Wallet;
-use parking_lot::RwLock;
+use massa_wallet::{Signer, SigningContent};
 use std::{
     sync::{mpsc, Arc},
     thread,
@@ -22,7 +22,7 @@ use tracing::{debug, warn};
 /// Structure gathering all elements needed by the factory thread
 pub(crate) struct EndorsementFactoryWorker {
     cfg: FactoryConfig,
-    wallet: Arc<RwLock<Wallet>>,
+    signer: Arc<dyn Signer>,
     channels: FactoryChannels,
     factory_receiver: mpsc::Receiver<()>,
     half_t0: MassaTime,
@@ -34,7 +34,7 @@ impl EndorsementFactoryWorker {
     /// needed by the factory worker thread.
     pub(crate) fn spawn(
         cfg: FactoryConfig,
-        wallet: Arc<RwLock<Wallet>>,
+        signer: Arc<dyn Signer>,
         channels: FactoryChannels,
         factory_receiver: mpsc::Receiver<()>,
     ) -> thread::JoinHandle<()> {
@@ -47,7 +47,7 @@ impl EndorsementFactoryWorker {
                         .checked_div_u64(2)
                         .expect("could not compute half_t0"),
                     cfg,
-                    wallet,
+                    signer,
                     channels,
                     factory_receiver,
                     endorsement_serializer: EndorsementSerializer::new(),
@@ -138,22 +138,19 @@ impl EndorsementFactoryWorker {
             }
         };
 
-        // get creators if they are managed by our wallet
-        let mut producers_indices: Vec<(KeyPair, usize)> = Vec::new();
-        {
-            let wallet = self.wallet.read();
-            for (index, producer_addr) in producer_addrs.into_iter().enumerate() {
-                // check if the block producer address is handled by the wallet
-                let producer_keypair =
-                    if let Some(kp) = wallet.find_associated_keypair(&producer_addr) {
-                        // the selected block producer is managed locally => continue to attempt endorsement production
-                        kp.clone()
-                    } else {
-                        // the selected block producer is not managed locally => continue
-                        continue;
-                    };
-                producers_indices.push((producer_keypair, index));
-            }
+        // get creators if they are managed by our signer
+        let mut producers_indices: Vec<(Address, PublicKey, usize)> = Vec::new();
+        for (index, producer_addr) in producer_addrs.into_iter().enumerate() {
+            // check if the block producer address is handled by the signer
+            let producer_public_key =
+                if let Some(public_key) = self.signer.get_public_key(&producer_addr) {
+                    // the selected block producer is managed by the signer => continue to attempt endorsement production
+                    public_key
+                } else {
+                    // the selected block producer is not managed by the signer => continue
+                    continue;
+                };
+            producers_indices.push((producer_addr, producer_public_key, index));
         }
 
         // quit if there is nothing to produce
@@ -182,17 +179,32 @@ impl EndorsementFactoryWorker {
 
         // produce endorsements
         let mut endorsements: Vec<WrappedEndorsement> = Vec::with_capacity(producers_indices.len());
-        for (keypair, index) in producers_indices {
-            let endorsement = Endorsement::new_wrapped(
+        for (producer_addr, public_key, index) in producers_indices {
+            let index = index as u32;
+            let endorsement: WrappedEndorsement = match Endorsement::new_wrapped_with_signer(
                 Endorsement {
                     slot,
-                    index: index as u32,
+                    index,
                     endorsed_block,
                 },
                 self.endorsement_serializer.clone(),
-                &keypair,
-            )
-            .expect("could not create endorsement");
+                public_key,
+                |_hash, content| {
+                    self.signer.sign(
+                        &producer_addr,
+                        &SigningContent::Endorsement(content.to_vec()),
+                    )
+                },
+            ) {
+                Ok(endorsement) => endorsement,
+                Err(err) => {
+                    warn!(
+                        "endorsement factory could not sign the endorsement at slot {} index {}: {}",
+                        slot, index, err
+                    );
+                    continue;
+                }
+            };
 
             // log endorsement creation
             debug!(
@@ -1,28 +1,31 @@
 //! Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use parking_lot::RwLock;
 use std::sync::{mpsc, Arc};
 
 use crate::{
     block_factory::BlockFactoryWorker, endorsement_factory::EndorsementFactoryWorker,
     manager::FactoryManagerImpl,
 };
-use massa_factory_exports::{FactoryChannels, FactoryConfig, FactoryManager};
-use massa_wallet::Wallet;
+use massa_factory_exports::{
+    BlockProductionStrategy, FactoryChannels, FactoryConfig, FactoryManager,
+};
+use massa_wallet::Signer;
 
 /// Start factory
 ///
 /// # Arguments
 /// * `cfg`: factory configuration
-/// * `wallet`: atomic reference to the node wallet
+/// * `signer`: signer of the blocks and endorsements, on behalf of the staking addresses
 /// * `channels`: channels to communicate with other modules
+/// * `block_production_strategy`: strategy choosing the contents of the produced blocks
 ///
 /// # Return value
 /// Returns a factory manager allowing to stop the workers cleanly.
 pub fn start_factory(
     cfg: FactoryConfig,
-    wallet: Arc<RwLock<Wallet>>,
+    signer: Arc<dyn Signer>,
     channels: FactoryChannels,
+    block_production_strategy: Box<dyn BlockProductionStrategy>,
 ) -> Box<dyn FactoryManager> {
     // create block factory channel
     let (block_worker_tx, block_worker_rx) = mpsc::channel::<()>();
@@ -33,14 +36,15 @@ pub fn start_factory(
     // start block factory worker
     let block_worker_handle = BlockFactoryWorker::spawn(
         cfg.clone(),
-        wallet.clone(),
+        signer.clone(),
         channels.clone(),
+        block_production_strategy,
         block_worker_rx,
     );
 
     // start endorsement factory worker
     let endorsement_worker_handle =
-        EndorsementFactoryWorker::spawn(cfg, wallet, channels, endorsement_worker_rx);
+        EndorsementFactoryWorker::spawn(cfg, signer, channels, endorsement_worker_rx);
 
     // create factory manager
     let manager = FactoryManagerImpl {
@@ -1,4 +1,5 @@
 mod scenarios;
+mod strategy;
 mod tools;
 
 pub use tools::*;
@@ -0,0 +1,188 @@
+use crate::block_factory::{check_block_contents, choose_block_contents};
+use massa_consensus_exports::{commands::ConsensusCommand, test_exports::MockConsensusController};
+use massa_factory_exports::{
+    test_exports::create_empty_block, BlockContents, BlockProductionStrategy, FactoryChannels,
+    FactoryConfig, FactoryResult,
+};
+use massa_models::{
+    address::Address,
+    amount::Amount,
+    block::BlockId,
+    operation::{Operation, OperationSerializer, OperationType, WrappedOperation},
+    slot::Slot,
+    wrapped::WrappedContent,
+};
+use massa_pool_exports::test_exports::{
+    MockPoolController, MockPoolControllerMessage, PoolEventReceiver,
+};
+use massa_pos_exports::test_exports::MockSelectorController;
+use massa_protocol_exports::test_exports::MockProtocolController;
+use massa_signature::KeyPair;
+use massa_storage::Storage;
+use massa_time::MassaTime;
+use std::str::FromStr;
+
+/// Strategy choosing the same parents and operations for every block
+struct FixedContentsStrategy {
+    parents: Vec<BlockId>,
+    operations: Vec<WrappedOperation>,
+}
+
+impl BlockProductionStrategy for FixedContentsStrategy {
+    fn choose_block_contents(
+        &mut self,
+        _slot: Slot,
+        channels: &FactoryChannels,
+    ) -> FactoryResult<BlockContents> {
+        let mut storage = channels.storage.clone_without_refs();
+        storage.claim_block_refs(&self.parents.iter().copied().collect());
+        storage.store_operations(self.operations.clone());
+        Ok(BlockContents {
+            parents: self.parents.clone(),
+            endorsements: Vec::new(),
+            operations: self.operations.iter().map(|op| op.id).collect(),
+            storage,
+        })
+    }
+}
+
+/// Channels to mocks, with a storage holding the genesis blocks
+struct TestChannels {
+    channels: FactoryChannels,
+    consensus_controller: MockConsensusController,
+    pool_receiver: PoolEventReceiver,
+    genesis_blocks: Vec<BlockId>,
+    _storage: Storage,
+}
+
+fn get_test_channels(cfg: &FactoryConfig) -> TestChannels {
+    let (selector, _selector_receiver) = MockSelectorController::new_with_receiver();
+    let (consensus_controller, consensus, _consensus_event_receiver) =
+        MockConsensusController::new_with_receiver();
+    let (pool, pool_receiver) = MockPoolController::new_with_receiver();
+    let (_protocol_controller, protocol, _protocol_event_receiver) = MockProtocolController::new();
+    let mut storage = Storage::create_root();
+    let keypair = KeyPair::generate();
+    let genesis_blocks = (0..cfg.thread_count)
+        .map(|thread| {
+            let block = create_empty_block(&keypair, &Slot::new(0, thread));
+            let id = block.id;
+            storage.store_block(block);
+            id
+        })
+        .collect();
+    TestChannels {
+        channels: FactoryChannels {
+            selector,
+            consensus,
+            pool,
+            protocol,
+            storage: storage.clone_without_refs(),
+        },
+        consensus_controller,
+        pool_receiver,
+        genesis_blocks,
+        _storage: storage,
+    }
+}
+
+/// Creates a roll buy operation of `keypair`
+fn get_roll_buy(keypair: &KeyPair) -> WrappedOperation {
+    let content = Operation {
+        fee: Amount::from_str("0.01").unwrap(),
+        expire_period: 2,
+        op: OperationType::RollBuy { roll_count: 1 },
+    };
+    Operation::new_wrapped(content, OperationSerializer::new(), keypair).unwrap()
+}
+
+/// The valid contents chosen by a custom strategy are used for the block
+#[test]
+fn test_custom_strategy_contents_accepted() {
+    let cfg = FactoryConfig::default();
+    let mut test_channels = get_test_channels(&cfg);
+    let keypair = KeyPair::generate();
+    let operation = get_roll_buy(&keypair);
+    let slot = Slot::new(
+        1,
+        Address::from_public_key(&keypair.get_public_key()).get_thread(cfg.thread_count),
+    );
+    let mut strategy = FixedContentsStrategy {
+        parents: test_channels.genesis_blocks.clone(),
+        operations: vec![operation.clone()],
+    };
+
+    let contents =
+        choose_block_contents(&cfg, &mut strategy, slot, &test_channels.channels).unwrap();
+    assert_eq!(contents.parents, test_channels.genesis_blocks);
+    assert_eq!(contents.operations, vec![operation.id]);
+    // the default strategy was not asked for contents
+    assert!(test_channels
+        .consensus_controller
+        .consensus_command_rx
+        .try_recv()
+        .is_err());
+
+    // operations of another thread can't be included
+    let other_thread_slot = Slot::new(1, (slot.thread + 1) % cfg.thread_count);
+    assert!(check_block_contents(&cfg, other_thread_slot, &contents).is_err());
+}
+
+/// The invalid contents chosen by a custom strategy are replaced by the ones of the default strategy
+#[test]
+fn test_custom_strategy_contents_rejected() {
+    let cfg = FactoryConfig::default();
+    let TestChannels {
+        channels,
+        mut consensus_controller,
+        mut pool_receiver,
+        genesis_blocks,
+        _storage,
+    } = get_test_channels(&cfg);
+    let keypair = KeyPair::generate();
+    let operation = get_roll_buy(&keypair);
+    let slot = Slot::new(
+        1,
+        Address::from_public_key(&keypair.get_public_key()).get_thread(cfg.thread_count),
+    );
+    // a parent is missing
+    let mut strategy = FixedContentsStrategy {
+        parents: genesis_blocks[1..].to_vec(),
+        operations: vec![operation],
+    };
+
+    // answer the requests of the default strategy
+    let best_parents = genesis_blocks.iter().map(|id| (*id, 0)).collect();
+    let responder = std::thread::spawn(move || {
+        match consensus_controller.consensus_command_rx.blocking_recv() {
+            Some(ConsensusCommand::GetBestParents { response_tx }) => {
+                response_tx.send(best_parents).unwrap()
+            }
+            _ => panic!("unexpected message"),
+        }
+        for _ in 0..2 {
+            pool_receiver
+                .wait_command(MassaTime::from_millis(1000), |command| match command {
+                    MockPoolControllerMessage::GetBlockEndorsements { response_tx, .. } => {
+                        response_tx
+                            .send((Vec::new(), Storage::create_root()))
+                            .unwrap();
+                        Some(())
+                    }
+                    MockPoolControllerMessage::GetBlockOperations { response_tx, .. } => {
+                        response_tx
+                            .send((Vec::new(), Storage::create_root()))
+                            .unwrap();
+                        Some(())
+                    }
+                    _ => panic!("unexpected message"),
+                })
+                .unwrap();
+        }
+    });
+
+    let contents = choose_block_contents(&cfg, &mut strategy, slot, &channels).unwrap();
+    responder.join().unwrap();
+    assert_eq!(contents.parents, genesis_blocks);
+    assert!(contents.operations.is_empty());
+}
@@ -7,7 +7,8 @@ use std::{
 
 use massa_consensus_exports::{commands::ConsensusCommand, test_exports::MockConsensusController};
 use massa_factory_exports::{
-    test_exports::create_empty_block, FactoryChannels, FactoryConfig, FactoryManager,
+    test_exports::create_empty_block, DefaultBlockProductionStrategy, FactoryChannels,
+    FactoryConfig, FactoryManager,
 };
 use massa_models::{
     address::Address, block::BlockId, config::ENDORSEMENT_COUNT, endorsement::WrappedEndorsement,
@@ -27,7 +28,7 @@ use massa_storage::Storage;
 use massa_time::MassaTime;
 
 use crate::start_factory;
-use massa_wallet::test_exports::create_test_wallet;
+use massa_wallet::{test_exports::create_test_wallet, LocalSigner};
 
 /// This structure store all information and links to creates tests for the factory.
 /// The factory will ask that to the the pool, consensus and factory and then will send the block to the consensus.
@@ -79,7 +80,9 @@ impl TestFactory {
             .unwrap();
         let factory_manager = start_factory(
             factory_config.clone(),
-            Arc::new(RwLock::new(create_test_wallet(Some(accounts)))),
+            Arc::new(LocalSigner::new(Arc::new(RwLock::new(create_test_wallet(
+                Some(accounts),
+            ))))),
             FactoryChannels {
                 selector: selector_controller.clone(),
                 consensus: consensus_command_sender,
@@ -87,6 +90,7 @@ impl TestFactory {
                 protocol: protocol_command_sender,
                 storage: storage.clone_without_refs(),
             },
+            Box::new(DefaultBlockProductionStrategy),
         );
 
         TestFactory {
@@ -15,6 +15,8 @@ pub struct FinalStateConfig {
     pub async_pool_config: AsyncPoolConfig,
     /// final changes history length
     pub final_history_length: usize,
+    /// number of slots whose reverse changes are kept to answer queries about past final slots
+    pub reverse_history_length: usize,
     /// thread count
     pub thread_count: u8,
     /// periods per cycle
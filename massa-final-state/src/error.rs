@@ -13,4 +13,10 @@ pub enum FinalStateError {
     LedgerError(String),
     /// PoS error: {0}
     PosError(String),
+    /// history error: {0}
+    HistoryError(String),
+    /// snapshot error: {0}
+    SnapshotError(String),
+    /// state hash error: {0}
+    StateHashError(String),
 }
@@ -6,14 +6,18 @@
 //! and need to be bootstrapped by nodes joining the network.
 
 use crate::{
-    config::FinalStateConfig, error::FinalStateError, state_changes::StateChanges, ExecutedOps,
-    ExecutedOpsStreamingStep,
+    config::FinalStateConfig, error::FinalStateError, reverse_changes::ReverseStateChanges,
+    state_changes::StateChanges, ExecutedOps, ExecutedOpsStreamingStep,
 };
 use massa_async_pool::{AsyncMessageId, AsyncPool, AsyncPoolChanges, Change};
+use massa_hash::Hash;
 use massa_ledger_exports::{LedgerChanges, LedgerController};
-use massa_models::{address::Address, slot::Slot};
+use massa_models::{
+    address::Address, amount::Amount, ledger_models::LedgerSubEntry,
+    ledger_proof::LedgerEntryProof, slot::Slot,
+};
 use massa_pos_exports::{PoSCycleStreamingStep, PoSFinalState, SelectorController};
-use std::collections::VecDeque;
+use std::collections::{BTreeSet, VecDeque};
 use tracing::debug;
 
 /// Represents a final state `(ledger, async pool, executed_ops and the state of the PoS)`
@@ -33,6 +37,9 @@ pub struct FinalState {
     /// history of recent final state changes, useful for streaming bootstrap
     /// `front = oldest`, `back = newest`
     pub changes_history: VecDeque<(Slot, StateChanges)>,
+    /// values overwritten by the changes of recent final slots, useful for queries at a past final slot
+    /// `front = oldest`, `back = newest`
+    pub reverse_history: VecDeque<(Slot, ReverseStateChanges)>,
 }
 
 impl FinalState {
@@ -73,6 +80,7 @@ impl FinalState {
             config,
             executed_ops,
             changes_history: Default::default(), // no changes in history
+            reverse_history: Default::default(),
         })
     }
 
@@ -83,6 +91,18 @@ impl FinalState {
             .map_err(|err| FinalStateError::PosError(err.to_string()))
     }
 
+    /// Resets everything but the ledger to the initial empty state.
+    /// Used by the parallel bootstrap to drop the part of the final state received from a server whose final state hash did not match.
+    pub fn reset_state_without_ledger(&mut self) {
+        self.slot = Slot::new(0, self.config.thread_count.saturating_sub(1));
+        self.async_pool = AsyncPool::new(self.config.async_pool_config.clone());
+        self.pos_state.cycle_history.clear();
+        self.pos_state.deferred_credits = Default::default();
+        self.executed_ops = ExecutedOps::default();
+        self.changes_history.clear();
+        self.reverse_history.clear();
+    }
+
     /// Applies changes to the execution state at a given slot, and settles that slot forever.
     /// Once this is called, the state is attached at the output of the provided slot.
     ///
@@ -100,6 +120,16 @@ impl FinalState {
         // update current slot
         self.slot = slot;
 
+        // gather the values overwritten by the changes and limit reverse history size
+        if self.config.reverse_history_length > 0 {
+            while self.reverse_history.len() >= self.config.reverse_history_length {
+                self.reverse_history.pop_front();
+            }
+            let reverse_changes =
+                ReverseStateChanges::new(&changes, self.ledger.as_ref(), &self.pos_state);
+            self.reverse_history.push_back((slot, reverse_changes));
+        }
+
         // apply changes
         self.ledger
             .apply_changes(changes.ledger_changes.clone(), self.slot);
@@ -130,6 +160,219 @@ impl FinalState {
         );
     }
 
+    /// Returns the reverse changes to apply to the current state to get back to the state at the output of `slot`,
+    /// newest first.
+    ///
+    /// Error case: When `slot` is not final yet, or is too old for `self.reverse_history`
+    fn get_reverse_changes_since(
+        &self,
+        slot: Slot,
+    ) -> Result<impl Iterator<Item = &ReverseStateChanges>, FinalStateError> {
+        if slot > self.slot {
+            return Err(FinalStateError::HistoryError(format!(
+                "slot {} is not final yet",
+                slot
+            )));
+        }
+        if slot < self.slot {
+            let next_slot = slot.get_next_slot(self.config.thread_count).map_err(|_| {
+                FinalStateError::HistoryError("slot overflow in history".to_string())
+            })?;
+            match self.reverse_history.front() {
+                Some((first_slot, _)) if *first_slot <= next_slot => {}
+                _ => {
+                    return Err(FinalStateError::HistoryError(format!(
+                        "slot {} is too old for the final state history",
+                        slot
+                    )))
+                }
+            }
+        }
+        Ok(self
+            .reverse_history
+            .iter()
+            .rev()
+            .take_while(move |(change_slot, _)| *change_slot > slot)
+            .map(|(_, changes)| changes))
+    }
+
+    /// Gets the balance of an address at the output of a past final slot
+    pub fn get_balance_at(
+        &self,
+        addr: &Address,
+        slot: Slot,
+    ) -> Result<Option<Amount>, FinalStateError> {
+        let mut balance = self.ledger.get_balance(addr);
+        for changes in self.get_reverse_changes_since(slot)? {
+            if let Some(entry) = changes.ledger.get(addr) {
+                balance = entry.balance;
+            }
+        }
+        Ok(balance)
+    }
+
+    /// Gets the roll count of an address at the output of a past final slot
+    pub fn get_rolls_at(&self, addr: &Address, slot: Slot) -> Result<u64, FinalStateError> {
+        let mut rolls = self.pos_state.get_rolls_for(addr);
+        for changes in self.get_reverse_changes_since(slot)? {
+            if let Some(previous_rolls) = changes.rolls.get(addr) {
+                rolls = *previous_rolls;
+            }
+        }
+        Ok(rolls)
+    }
+
+    /// Gets a datastore entry of an address at the output of a past final slot
+    pub fn get_data_entry_at(
+        &self,
+        addr: &Address,
+        key: &[u8],
+        slot: Slot,
+    ) -> Result<Option<Vec<u8>>, FinalStateError> {
+        let mut value = self.ledger.get_data_entry(addr, key);
+        for changes in self.get_reverse_changes_since(slot)? {
+            if let Some(previous_value) = changes
+                .ledger
+                .get(addr)
+                .and_then(|entry| entry.datastore.get(key))
+            {
+                value = previous_value.clone();
+            }
+        }
+        Ok(value)
+    }
+
+    /// Gets the datastore keys of an address at the output of a past final slot
+    pub fn get_datastore_keys_at(
+        &self,
+        addr: &Address,
+        slot: Slot,
+    ) -> Result<BTreeSet<Vec<u8>>, FinalStateError> {
+        let mut keys = self.ledger.get_datastore_keys(addr);
+        for changes in self.get_reverse_changes_since(slot)? {
+            if let Some(entry) = changes.ledger.get(addr) {
+                for (key, previous_value) in entry.datastore.iter() {
+                    if previous_value.is_some() {
+                        keys.insert(key.clone());
+                    } else {
+                        keys.remove(key);
+                    }
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Gets the value of a ledger sub-entry along with its proof against the ledger hash of the current final slot
+    pub fn get_ledger_proof(&self, addr: &Address, sub_entry: LedgerSubEntry) -> LedgerEntryProof {
+        let (ledger_key, value, proof) = self.ledger.get_ledger_proof(addr, sub_entry.clone());
+        LedgerEntryProof {
+            address: *addr,
+            sub_entry,
+            ledger_key,
+            value,
+            slot: self.slot,
+            ledger_hash: self.ledger.get_ledger_hash(),
+            proof,
+        }
+    }
+
+    /// Computes a hash of the whole final state, allowing to check that two nodes hold the same state at the same slot.
+    /// It covers the slot, the ledger hash, the async pool, the PoS cycle history and deferred credits
+    /// and the executed operations hash.
+    ///
+    /// The streams of the bootstrap parts are hashed as a whole, so that the result does not depend on the part sizes.
+    pub fn get_state_hash(&self) -> Result<Hash, FinalStateError> {
+        let state_hash_error = |err: massa_models::error::ModelsError| {
+            FinalStateError::StateHashError(err.to_string())
+        };
+
+        let mut pool_stream = Vec::new();
+        let mut last_async_message_id = None;
+        loop {
+            let (part, new_last_async_message_id) = self
+                .async_pool
+                .get_pool_part(last_async_message_id)
+                .map_err(state_hash_error)?;
+            if part.is_empty() {
+                break;
+            }
+            pool_stream.extend(part);
+            last_async_message_id = new_last_async_message_id;
+        }
+
+        let mut cycle_stream = Vec::new();
+        let mut cycle_step = PoSCycleStreamingStep::Started;
+        loop {
+            let (part, new_cycle_step) = self
+                .pos_state
+                .get_cycle_history_part(cycle_step)
+                .map_err(state_hash_error)?;
+            if part.is_empty() {
+                break;
+            }
+            cycle_stream.extend(part);
+            cycle_step = new_cycle_step;
+        }
+
+        let mut credits_stream = Vec::new();
+        let mut last_credits_slot = None;
+        loop {
+            let (part, new_last_credits_slot) = self
+                .pos_state
+                .get_deferred_credits_part(last_credits_slot)
+                .map_err(state_hash_error)?;
+            if part.is_empty() {
+                break;
+            }
+            credits_stream.extend(part);
+            last_credits_slot = new_last_credits_slot;
+        }
+
+        Ok(Hash::compute_from(
+            &[
+                &self.slot.to_bytes_key()[..],
+                &self.ledger.get_ledger_hash().to_bytes()[..],
+                &Hash::compute_from(&pool_stream).to_bytes()[..],
+                &Hash::compute_from(&cycle_stream).to_bytes()[..],
+                &Hash::compute_from(&credits_stream).to_bytes()[..],
+                &self.executed_ops.hash.to_bytes()[..],
+            ]
+            .concat(),
+        ))
+    }
+
+    /// Used for bootstrap
+    /// Returns every final state change after `last_slot`, oldest first.
+    /// Nothing is returned if `last_slot` is not before the current slot.
+    ///
+    /// Error case: When the `last_slot` is too old for `self.changes_history`
+    pub fn get_state_changes_since(
+        &self,
+        last_slot: Slot,
+    ) -> Result<Vec<(Slot, StateChanges)>, FinalStateError> {
+        if last_slot >= self.slot {
+            return Ok(Vec::new());
+        }
+        let next_slot = last_slot
+            .get_next_slot(self.config.thread_count)
+            .map_err(|_| FinalStateError::HistoryError("slot overflow in history".to_string()))?;
+        match self.changes_history.front() {
+            Some((first_slot, _)) if *first_slot <= next_slot => {}
+            _ => {
+                return Err(FinalStateError::LedgerError(
+                    "Last slot is overflowing history.".to_string(),
+                ))
+            }
+        }
+        Ok(self
+            .changes_history
+            .iter()
+            .filter(|(slot, _)| *slot > last_slot)
+            .cloned()
+            .collect())
+    }
+
     /// Used for bootstrap
     /// Take a part of the final state changes (ledger and async pool) using a `Slot`, a `Address` and a `AsyncMessageId`.
     /// Every ledgers changes that are after `last_slot` and before or equal of `last_address` must be returned.
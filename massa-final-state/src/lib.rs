@@ -25,6 +25,10 @@
 //! Defines a structure to list and prune previously executed operations.
 //! Used to detect operation reuse.
 //!
+//! ## `snapshot.rs`
+//! Exports the final state to a hash-checked snapshot file and imports it back,
+//! reusing the serialization of the bootstrap parts.
+//!
 //! ## `bootstrap.rs`
 //! Provides serializable structures and tools for bootstrapping the final state.
 //!
@@ -43,12 +47,16 @@ mod config;
 mod error;
 mod executed_ops;
 mod final_state;
+mod reverse_changes;
+mod snapshot;
 mod state_changes;
 
 pub use config::FinalStateConfig;
 pub use error::FinalStateError;
 pub use executed_ops::*;
 pub use final_state::FinalState;
+pub use reverse_changes::{ReverseLedgerChanges, ReverseStateChanges};
+pub use snapshot::{FinalStateCursor, FinalStateSnapshot, SNAPSHOT_VERSION};
 pub use state_changes::{StateChanges, StateChangesDeserializer, StateChangesSerializer};
 
 #[cfg(test)]
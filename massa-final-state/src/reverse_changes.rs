@@ -0,0 +1,82 @@
+//! Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This file provides structures representing the values of the final state
+//! that are overwritten when applying `StateChanges`, allowing to answer queries about past final slots
+
+use crate::StateChanges;
+use massa_ledger_exports::{LedgerController, SetUpdateOrDelete};
+use massa_models::{address::Address, amount::Amount, prehash::PreHashMap};
+use massa_pos_exports::PoSFinalState;
+use std::collections::BTreeMap;
+
+/// Ledger values of an address as they were before some changes
+#[derive(Default, Debug, Clone)]
+pub struct ReverseLedgerChanges {
+    /// balance, `None` if the ledger entry did not exist
+    pub balance: Option<Amount>,
+    /// values of the changed datastore entries, `None` if the entry did not exist
+    pub datastore: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+/// Values of the final state overwritten by the changes of a slot
+#[derive(Default, Debug, Clone)]
+pub struct ReverseStateChanges {
+    /// previous ledger values of the addresses changed by the slot
+    pub ledger: PreHashMap<Address, ReverseLedgerChanges>,
+    /// previous roll counts of the addresses changed by the slot
+    pub rolls: PreHashMap<Address, u64>,
+}
+
+impl ReverseStateChanges {
+    /// Gathers the values that `changes` are about to overwrite.
+    /// Must be called before the changes are applied.
+    ///
+    /// # Arguments
+    /// * `changes`: changes about to be applied to the final state
+    /// * `ledger`: final ledger, before the changes
+    /// * `pos_state`: final proof of stake state, before the changes
+    pub fn new(
+        changes: &StateChanges,
+        ledger: &dyn LedgerController,
+        pos_state: &PoSFinalState,
+    ) -> Self {
+        let mut reverse = ReverseStateChanges::default();
+        for (addr, change) in changes.ledger_changes.0.iter() {
+            let mut entry = ReverseLedgerChanges {
+                balance: ledger.get_balance(addr),
+                datastore: BTreeMap::new(),
+            };
+            match change {
+                SetUpdateOrDelete::Set(new_entry) => {
+                    // the whole datastore is replaced: previous keys are restored, new keys are removed
+                    let previous_datastore = ledger.get_entire_datastore(addr);
+                    for key in new_entry.datastore.keys() {
+                        if !previous_datastore.contains_key(key) {
+                            entry.datastore.insert(key.clone(), None);
+                        }
+                    }
+                    for (key, value) in previous_datastore {
+                        entry.datastore.insert(key, Some(value));
+                    }
+                }
+                SetUpdateOrDelete::Update(update) => {
+                    for key in update.datastore.keys() {
+                        entry
+                            .datastore
+                            .insert(key.clone(), ledger.get_data_entry(addr, key));
+                    }
+                }
+                SetUpdateOrDelete::Delete => {
+                    for (key, value) in ledger.get_entire_datastore(addr) {
+                        entry.datastore.insert(key, Some(value));
+                    }
+                }
+            }
+            reverse.ledger.insert(*addr, entry);
+        }
+        for addr in changes.pos_changes.roll_changes.keys() {
+            reverse.rolls.insert(*addr, pos_state.get_rolls_for(addr));
+        }
+        reverse
+    }
+}
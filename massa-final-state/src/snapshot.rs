@@ -0,0 +1,369 @@
+//! Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This file provides the export of the final state to a snapshot file and its import,
+//! allowing to provision nodes without streaming the whole final state from a bootstrap server.
+//!
+//! A snapshot file is made of:
+//! * a header: `SNAPSHOT_MAGIC`, the format version, the thread count and the slot of the state
+//! * records, each one containing a ledger, async pool, PoS cycle history, deferred credits and executed ops part
+//!   as produced by the `get_*_part` functions used by the bootstrap
+//! * a trailer containing the ledger hash of the state and the snapshot hash
+//!
+//! The snapshot hash chains the hashes of the header, of every record and of the ledger hash.
+//! It is the value to be trusted when importing a snapshot.
+
+use crate::{ExecutedOpsStreamingStep, FinalState, FinalStateError};
+use massa_async_pool::AsyncMessageId;
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use massa_models::serialization::{VecU8Deserializer, VecU8Serializer};
+use massa_models::slot::{Slot, SLOT_KEY_SIZE};
+use massa_pos_exports::PoSCycleStreamingStep;
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::ops::Bound::Included;
+use std::path::Path;
+
+/// Identifies a final state snapshot file
+const SNAPSHOT_MAGIC: &[u8; 8] = b"MASSAFSS";
+/// Version of the snapshot format
+pub const SNAPSHOT_VERSION: u32 = 0;
+/// Size of the snapshot header
+const HEADER_SIZE: usize = SNAPSHOT_MAGIC.len() + 4 + 1 + SLOT_KEY_SIZE;
+/// Number of parts in a record
+const RECORD_PART_COUNT: usize = 5;
+/// Tag preceding a record
+const RECORD_TAG: u8 = 0;
+/// Tag preceding the trailer
+const END_TAG: u8 = 1;
+
+/// Position reached in the streaming of each element of the final state,
+/// in the format used by the bootstrap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinalStateCursor {
+    /// last streamed ledger key
+    pub last_key: Option<Vec<u8>>,
+    /// last streamed async message
+    pub last_async_message_id: Option<AsyncMessageId>,
+    /// PoS cycle history streaming step
+    pub last_cycle_step: PoSCycleStreamingStep,
+    /// slot of the last streamed deferred credits
+    pub last_credits_slot: Option<Slot>,
+    /// executed ops streaming step
+    pub last_exec_ops_step: ExecutedOpsStreamingStep,
+}
+
+impl Default for FinalStateCursor {
+    fn default() -> Self {
+        FinalStateCursor {
+            last_key: None,
+            last_async_message_id: None,
+            last_cycle_step: PoSCycleStreamingStep::Started,
+            last_credits_slot: None,
+            last_exec_ops_step: ExecutedOpsStreamingStep::Started,
+        }
+    }
+}
+
+/// Converts any error happening while handling a snapshot
+fn snapshot_error(err: impl std::fmt::Display) -> FinalStateError {
+    FinalStateError::SnapshotError(err.to_string())
+}
+
+/// Adds `data` to the snapshot hash chain
+fn chain_hash(hash: &Hash, data: &[u8]) -> Hash {
+    Hash::compute_from(&[&hash.to_bytes()[..], data].concat())
+}
+
+/// Reads a snapshot file, checking its format and its integrity
+///
+/// # Arguments
+/// * `path`: snapshot file
+/// * `thread_count`: expected thread count
+/// * `on_record`: called on the parts of each record, in order
+///
+/// # Returns
+/// The slot of the state, its ledger hash and the snapshot hash
+fn read_snapshot<F>(
+    path: &Path,
+    thread_count: u8,
+    mut on_record: F,
+) -> Result<(Slot, Hash, Hash), FinalStateError>
+where
+    F: FnMut(Vec<Vec<u8>>) -> Result<(), FinalStateError>,
+{
+    let mut reader = BufReader::new(File::open(path).map_err(snapshot_error)?);
+
+    // header
+    let mut header = [0u8; HEADER_SIZE];
+    reader.read_exact(&mut header).map_err(snapshot_error)?;
+    if &header[..SNAPSHOT_MAGIC.len()] != SNAPSHOT_MAGIC {
+        return Err(snapshot_error("not a final state snapshot"));
+    }
+    let version = u32::from_be_bytes(header[8..12].try_into().unwrap());
+    if version != SNAPSHOT_VERSION {
+        return Err(snapshot_error(format!(
+            "unsupported snapshot version {}, expected {}",
+            version, SNAPSHOT_VERSION
+        )));
+    }
+    if header[12] != thread_count {
+        return Err(snapshot_error(format!(
+            "snapshot made for {} threads instead of {}",
+            header[12], thread_count
+        )));
+    }
+    let slot = Slot::from_bytes_key(header[13..].try_into().unwrap());
+    let mut snapshot_hash = Hash::compute_from(&header);
+
+    let part_deserializer = VecU8Deserializer::new(Included(0), Included(u64::MAX));
+    loop {
+        let mut tag = [0u8; 1];
+        reader.read_exact(&mut tag).map_err(snapshot_error)?;
+        match tag[0] {
+            RECORD_TAG => {
+                let mut len_bytes = [0u8; 8];
+                reader.read_exact(&mut len_bytes).map_err(snapshot_error)?;
+                let len = u64::from_be_bytes(len_bytes);
+                let mut record = Vec::new();
+                reader
+                    .by_ref()
+                    .take(len)
+                    .read_to_end(&mut record)
+                    .map_err(snapshot_error)?;
+                if record.len() as u64 != len {
+                    return Err(snapshot_error("truncated snapshot record"));
+                }
+                snapshot_hash = chain_hash(&snapshot_hash, &record);
+
+                let mut parts = Vec::with_capacity(RECORD_PART_COUNT);
+                let mut rest = &record[..];
+                for _ in 0..RECORD_PART_COUNT {
+                    let (next, part) = part_deserializer
+                        .deserialize::<DeserializeError>(rest)
+                        .map_err(snapshot_error)?;
+                    parts.push(part);
+                    rest = next;
+                }
+                if !rest.is_empty() {
+                    return Err(snapshot_error("data is left after a snapshot record"));
+                }
+                on_record(parts)?;
+            }
+            END_TAG => {
+                let mut hashes = [0u8; 2 * HASH_SIZE_BYTES];
+                reader.read_exact(&mut hashes).map_err(snapshot_error)?;
+                let ledger_hash = Hash::from_bytes(hashes[..HASH_SIZE_BYTES].try_into().unwrap());
+                snapshot_hash = chain_hash(&snapshot_hash, ledger_hash.to_bytes());
+                if hashes[HASH_SIZE_BYTES..] != snapshot_hash.to_bytes()[..] {
+                    return Err(snapshot_error(
+                        "snapshot hash mismatch, the file is corrupted",
+                    ));
+                }
+                return Ok((slot, ledger_hash, snapshot_hash));
+            }
+            tag => return Err(snapshot_error(format!("unknown snapshot tag {}", tag))),
+        }
+    }
+}
+
+/// Content of a final state snapshot file, copied from the final state
+/// so that the file can be written without accessing the final state anymore
+pub struct FinalStateSnapshot {
+    /// snapshot header
+    header: Vec<u8>,
+    /// serialized records, in order
+    records: Vec<Vec<u8>>,
+    /// ledger hash of the state
+    ledger_hash: Hash,
+}
+
+impl FinalStateSnapshot {
+    /// Writes the snapshot to a file
+    ///
+    /// # Returns
+    /// The snapshot hash, to be trusted when importing the snapshot
+    pub fn write(&self, path: &Path) -> Result<Hash, FinalStateError> {
+        let mut writer = BufWriter::new(File::create(path).map_err(snapshot_error)?);
+        writer.write_all(&self.header).map_err(snapshot_error)?;
+        let mut snapshot_hash = Hash::compute_from(&self.header);
+        for record in self.records.iter() {
+            writer.write_all(&[RECORD_TAG]).map_err(snapshot_error)?;
+            writer
+                .write_all(&(record.len() as u64).to_be_bytes())
+                .map_err(snapshot_error)?;
+            writer.write_all(record).map_err(snapshot_error)?;
+            snapshot_hash = chain_hash(&snapshot_hash, record);
+        }
+
+        snapshot_hash = chain_hash(&snapshot_hash, self.ledger_hash.to_bytes());
+        writer.write_all(&[END_TAG]).map_err(snapshot_error)?;
+        writer
+            .write_all(self.ledger_hash.to_bytes())
+            .map_err(snapshot_error)?;
+        writer
+            .write_all(snapshot_hash.to_bytes())
+            .map_err(snapshot_error)?;
+        writer.flush().map_err(snapshot_error)?;
+        Ok(snapshot_hash)
+    }
+}
+
+impl FinalState {
+    /// Writes the whole final state to a snapshot file
+    ///
+    /// # Returns
+    /// The snapshot hash, to be trusted when importing the snapshot
+    pub fn export_snapshot(&self, path: &Path) -> Result<Hash, FinalStateError> {
+        self.take_snapshot()?.write(path)
+    }
+
+    /// Copies the whole final state in memory, in the format of a snapshot file
+    pub fn take_snapshot(&self) -> Result<FinalStateSnapshot, FinalStateError> {
+        let part_serializer = VecU8Serializer::new();
+
+        let header = [
+            &SNAPSHOT_MAGIC[..],
+            &SNAPSHOT_VERSION.to_be_bytes(),
+            &[self.config.thread_count],
+            &self.slot.to_bytes_key(),
+        ]
+        .concat();
+
+        let mut records = Vec::new();
+        let mut cursor = FinalStateCursor::default();
+        loop {
+            let (ledger_part, new_last_key) = self
+                .ledger
+                .get_ledger_part(&cursor.last_key)
+                .map_err(snapshot_error)?;
+            let (pool_part, new_last_async_message_id) = self
+                .async_pool
+                .get_pool_part(cursor.last_async_message_id)
+                .map_err(snapshot_error)?;
+            let (cycle_part, new_cycle_step) = self
+                .pos_state
+                .get_cycle_history_part(cursor.last_cycle_step)
+                .map_err(snapshot_error)?;
+            let (credits_part, new_last_credits_slot) = self
+                .pos_state
+                .get_deferred_credits_part(cursor.last_credits_slot)
+                .map_err(snapshot_error)?;
+            let (exec_ops_part, new_exec_ops_step) = self
+                .executed_ops
+                .get_executed_ops_part(cursor.last_exec_ops_step)
+                .map_err(snapshot_error)?;
+            let parts = [
+                ledger_part,
+                pool_part,
+                cycle_part,
+                credits_part,
+                exec_ops_part,
+            ];
+            if parts.iter().all(|part| part.is_empty()) {
+                break;
+            }
+
+            // advance the cursors the same way the bootstrap server does
+            if new_last_key.is_some() || !parts[0].is_empty() {
+                cursor.last_key = new_last_key;
+            }
+            if new_last_async_message_id.is_some() || !parts[1].is_empty() {
+                cursor.last_async_message_id = new_last_async_message_id;
+            }
+            if !parts[2].is_empty() {
+                cursor.last_cycle_step = new_cycle_step;
+            }
+            if new_last_credits_slot.is_some() || !parts[3].is_empty() {
+                cursor.last_credits_slot = new_last_credits_slot;
+            }
+            if !parts[4].is_empty() {
+                cursor.last_exec_ops_step = new_exec_ops_step;
+            }
+
+            let mut record = Vec::new();
+            for part in parts.iter() {
+                part_serializer
+                    .serialize(part, &mut record)
+                    .map_err(snapshot_error)?;
+            }
+            records.push(record);
+        }
+
+        Ok(FinalStateSnapshot {
+            header,
+            records,
+            ledger_hash: self.ledger.get_ledger_hash(),
+        })
+    }
+
+    /// Loads the final state from a snapshot file.
+    /// The state must be empty, as when created before a bootstrap.
+    ///
+    /// The integrity of the whole file is checked before modifying the state,
+    /// as well as the snapshot hash if a trusted one is provided,
+    /// and the ledger hash of the loaded state.
+    ///
+    /// # Returns
+    /// The cursor reached by the import, allowing to stream the rest of the state from a bootstrap server
+    pub fn import_snapshot(
+        &mut self,
+        path: &Path,
+        trusted_hash: Option<Hash>,
+    ) -> Result<FinalStateCursor, FinalStateError> {
+        let thread_count = self.config.thread_count;
+        let (slot, ledger_hash, snapshot_hash) = read_snapshot(path, thread_count, |_| Ok(()))?;
+        if let Some(trusted_hash) = trusted_hash {
+            if snapshot_hash != trusted_hash {
+                return Err(snapshot_error(format!(
+                    "snapshot hash {} does not match the trusted hash {}",
+                    snapshot_hash, trusted_hash
+                )));
+            }
+        }
+
+        let mut cursor = FinalStateCursor::default();
+        read_snapshot(path, thread_count, |parts| {
+            if !parts[0].is_empty() {
+                cursor.last_key = self
+                    .ledger
+                    .set_ledger_part(parts[0].clone())
+                    .map_err(snapshot_error)?;
+            }
+            if !parts[1].is_empty() {
+                cursor.last_async_message_id = self
+                    .async_pool
+                    .set_pool_part(&parts[1])
+                    .map_err(snapshot_error)?;
+            }
+            if !parts[2].is_empty() {
+                cursor.last_cycle_step = self
+                    .pos_state
+                    .set_cycle_history_part(&parts[2])
+                    .map_err(snapshot_error)?;
+            }
+            if !parts[3].is_empty() {
+                cursor.last_credits_slot = self
+                    .pos_state
+                    .set_deferred_credits_part(&parts[3])
+                    .map_err(snapshot_error)?;
+            }
+            if !parts[4].is_empty() {
+                cursor.last_exec_ops_step = self
+                    .executed_ops
+                    .set_executed_ops_part(&parts[4], thread_count)
+                    .map_err(snapshot_error)?;
+            }
+            Ok(())
+        })?;
+        self.slot = slot;
+        self.executed_ops.prune(slot);
+
+        if self.ledger.get_ledger_hash() != ledger_hash {
+            return Err(snapshot_error(
+                "the ledger hash of the imported state does not match the snapshot",
+            ));
+        }
+        Ok(cursor)
+    }
+}
@@ -36,6 +36,7 @@ impl FinalState {
             async_pool,
             config,
             changes_history: Default::default(), // no changes in history
+            reverse_history: Default::default(),
             pos_state,
             executed_ops,
         }
@@ -49,6 +50,7 @@ impl Default for FinalStateConfig {
             ledger_config: LedgerConfig::default(),
             async_pool_config: AsyncPoolConfig::default(),
             final_history_length: 10,
+            reverse_history_length: 10,
             thread_count: 2,
             periods_per_cycle: 100,
             initial_rolls_path: PathBuf::new(),
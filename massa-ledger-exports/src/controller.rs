@@ -1,5 +1,8 @@
 use massa_hash::Hash;
-use massa_models::{address::Address, amount::Amount, error::ModelsError, slot::Slot};
+use massa_models::{
+    address::Address, amount::Amount, error::ModelsError, ledger_models::LedgerSubEntry,
+    ledger_proof::LedgerProof, slot::Slot,
+};
 use std::collections::BTreeSet;
 use std::fmt::Debug;
 
@@ -59,6 +62,20 @@ pub trait LedgerController: Send + Sync + Debug {
     /// Get the current disk ledger hash
     fn get_ledger_hash(&self) -> Hash;
 
+    /// Get the value of a ledger sub-entry along with its inclusion or exclusion proof against the current ledger hash
+    ///
+    /// # Arguments
+    /// * `addr`: target address
+    /// * `sub_entry`: proven sub-entry
+    ///
+    /// # Returns
+    /// A tuple containing the ledger key of the sub-entry, its value if it exists, and the proof
+    fn get_ledger_proof(
+        &self,
+        addr: &Address,
+        sub_entry: LedgerSubEntry,
+    ) -> (Vec<u8>, Option<Vec<u8>>, LedgerProof);
+
     /// Get a part of the ledger
     /// Used for bootstrap
     /// Return: Tuple with data and last key
@@ -67,6 +84,20 @@ pub trait LedgerController: Send + Sync + Debug {
         last_key: &Option<Vec<u8>>,
     ) -> Result<(Vec<u8>, Option<Vec<u8>>), ModelsError>;
 
+    /// Get a part of the ledger restricted to the keys strictly after `last_key` and strictly before `end_key` if defined.
+    /// `last_key` does not need to be an existing key, which allows streaming a range of the ledger.
+    /// Used for parallel bootstrap
+    /// Return: Tuple with data and last key
+    fn get_ledger_range_part(
+        &self,
+        last_key: &[u8],
+        end_key: &Option<Vec<u8>>,
+    ) -> Result<(Vec<u8>, Option<Vec<u8>>), ModelsError>;
+
+    /// Delete the keys from `first_key` (included) to `end_key` (excluded) if defined, or until the end of the ledger otherwise.
+    /// Used for parallel bootstrap
+    fn delete_ledger_range(&mut self, first_key: &[u8], end_key: &Option<Vec<u8>>);
+
     /// Set a part of the ledger
     /// Used for bootstrap
     /// Return: Last key inserted
@@ -0,0 +1,33 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! This file defines the interface of the persistent store of final smart contract events
+
+use massa_models::api::EventFilter;
+use massa_models::output_event::{EventCursor, SCOutputEvent};
+use std::fmt::Debug;
+
+/// Interface of a persistent store of final smart contract events
+pub trait EventStoreController: Send + Sync + Debug {
+    /// Persists final events.
+    /// Events are identified by their slot and index in the slot,
+    /// so appending an already stored event overwrites it.
+    fn append_events(&mut self, events: Vec<SCOutputEvent>);
+
+    /// Gets a page of stored events matching the filter, in slot order.
+    ///
+    /// # Arguments
+    /// * `filter`: criteria the returned events must match
+    /// * `cursor`: position of the last event of the previous page, if any
+    /// * `limit`: maximum number of returned events
+    ///
+    /// # Returns
+    /// A tuple containing:
+    /// * The matching events
+    /// * The cursor to pass to get the next page, or `None` if there are no more matching events
+    fn get_filtered_events(
+        &self,
+        filter: &EventFilter,
+        cursor: Option<EventCursor>,
+        limit: usize,
+    ) -> (Vec<SCOutputEvent>, Option<EventCursor>);
+}
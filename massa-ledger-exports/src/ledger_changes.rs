@@ -10,6 +10,7 @@ use crate::types::{
 };
 use massa_models::address::{Address, AddressDeserializer};
 use massa_models::amount::{Amount, AmountDeserializer, AmountSerializer};
+use massa_models::execution::LedgerChangeSummary;
 use massa_models::prehash::PreHashMap;
 use massa_models::serialization::{VecU8Deserializer, VecU8Serializer};
 use massa_serialization::{
@@ -951,4 +952,53 @@ impl LedgerChanges {
             }
         }
     }
+
+    /// Summarizes the changes of every ledger entry, sorted by address
+    pub fn get_summary(&self) -> Vec<LedgerChangeSummary> {
+        let mut summary: Vec<LedgerChangeSummary> = self
+            .0
+            .iter()
+            .map(|(addr, change)| match change {
+                SetUpdateOrDelete::Set(entry) => LedgerChangeSummary {
+                    address: *addr,
+                    deleted: false,
+                    balance: Some(entry.balance),
+                    bytecode_changed: true,
+                    set_datastore_keys: entry.datastore.keys().cloned().collect(),
+                    deleted_datastore_keys: Vec::new(),
+                },
+                SetUpdateOrDelete::Update(update) => LedgerChangeSummary {
+                    address: *addr,
+                    deleted: false,
+                    balance: match update.balance {
+                        SetOrKeep::Set(v) => Some(v),
+                        SetOrKeep::Keep => None,
+                    },
+                    bytecode_changed: matches!(update.bytecode, SetOrKeep::Set(_)),
+                    set_datastore_keys: update
+                        .datastore
+                        .iter()
+                        .filter(|(_, v)| matches!(v, SetOrDelete::Set(_)))
+                        .map(|(k, _)| k.clone())
+                        .collect(),
+                    deleted_datastore_keys: update
+                        .datastore
+                        .iter()
+                        .filter(|(_, v)| matches!(v, SetOrDelete::Delete))
+                        .map(|(k, _)| k.clone())
+                        .collect(),
+                },
+                SetUpdateOrDelete::Delete => LedgerChangeSummary {
+                    address: *addr,
+                    deleted: true,
+                    balance: None,
+                    bytecode_changed: false,
+                    set_datastore_keys: Vec::new(),
+                    deleted_datastore_keys: Vec::new(),
+                },
+            })
+            .collect();
+        summary.sort_unstable_by_key(|change| change.address);
+        summary
+    }
 }
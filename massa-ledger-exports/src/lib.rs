@@ -7,6 +7,7 @@
 mod config;
 mod controller;
 mod error;
+mod event_store;
 mod key;
 mod ledger_changes;
 mod ledger_entry;
@@ -15,6 +16,7 @@ mod types;
 pub use config::LedgerConfig;
 pub use controller::LedgerController;
 pub use error::LedgerError;
+pub use event_store::EventStoreController;
 pub use key::{
     get_address_from_key, KeyDeserializer, KeySerializer, BALANCE_IDENT, BYTECODE_IDENT,
     DATASTORE_IDENT,
@@ -0,0 +1,270 @@
+//! Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Module to persist final smart contract events on disk
+
+use crate::ledger_db::end_prefix;
+use massa_ledger_exports::EventStoreController;
+use massa_models::{
+    api::EventFilter,
+    output_event::{EventCursor, SCOutputEvent},
+    slot::SLOT_KEY_SIZE,
+};
+use rocksdb::{
+    ColumnFamilyDescriptor, Direction, IteratorMode, Options, ReadOptions, WriteBatch, DB,
+};
+use std::fmt::Debug;
+use std::path::PathBuf;
+
+const EVENTS_CF: &str = "events";
+const EMITTER_INDEX_CF: &str = "emitter_index";
+const CALLER_INDEX_CF: &str = "caller_index";
+const OPERATION_INDEX_CF: &str = "operation_index";
+const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
+const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
+const CF_ERROR: &str = "critical: rocksdb column family operation failed";
+const EVENT_SER_ERROR: &str = "critical: event serialization failed";
+const EVENT_DESER_ERROR: &str = "critical: stored event is corrupted";
+const EVENT_KEY_SIZE: usize = SLOT_KEY_SIZE + 8;
+
+/// Sortable key of an event: slot key followed by the big-endian index in the slot
+fn event_key(cursor: &EventCursor) -> [u8; EVENT_KEY_SIZE] {
+    let mut key = [0u8; EVENT_KEY_SIZE];
+    key[..SLOT_KEY_SIZE].copy_from_slice(&cursor.slot.to_bytes_key());
+    key[SLOT_KEY_SIZE..].copy_from_slice(&cursor.index_in_slot.to_be_bytes());
+    key
+}
+
+/// Disk event store module
+///
+/// Contains a `RocksDB` DB instance holding the final events,
+/// keyed by slot and index in the slot, along with secondary indexes
+/// by emitter address, original caller address and origin operation.
+/// Index keys are the indexed value followed by the event key.
+pub struct FinalEventStore {
+    db: DB,
+}
+
+impl Debug for FinalEventStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#?}", self.db)
+    }
+}
+
+impl FinalEventStore {
+    /// Create and initialize a new `FinalEventStore`.
+    ///
+    /// # Arguments
+    /// * path: path to the desired disk event store db directory
+    pub fn new(path: PathBuf) -> Self {
+        let mut db_opts = Options::default();
+        db_opts.create_if_missing(true);
+        db_opts.create_missing_column_families(true);
+
+        let db = DB::open_cf_descriptors(
+            &db_opts,
+            path,
+            vec![
+                ColumnFamilyDescriptor::new(EVENTS_CF, Options::default()),
+                ColumnFamilyDescriptor::new(EMITTER_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(CALLER_INDEX_CF, Options::default()),
+                ColumnFamilyDescriptor::new(OPERATION_INDEX_CF, Options::default()),
+            ],
+        )
+        .expect(OPEN_ERROR);
+
+        FinalEventStore { db }
+    }
+}
+
+impl EventStoreController for FinalEventStore {
+    /// Allows persisting final events
+    fn append_events(&mut self, events: Vec<SCOutputEvent>) {
+        let events_handle = self.db.cf_handle(EVENTS_CF).expect(CF_ERROR);
+        let emitter_handle = self.db.cf_handle(EMITTER_INDEX_CF).expect(CF_ERROR);
+        let caller_handle = self.db.cf_handle(CALLER_INDEX_CF).expect(CF_ERROR);
+        let operation_handle = self.db.cf_handle(OPERATION_INDEX_CF).expect(CF_ERROR);
+
+        let mut batch = WriteBatch::default();
+        for event in events {
+            let key = event_key(&event.cursor());
+            if let Some(emitter) = event.context.call_stack.front() {
+                batch.put_cf(emitter_handle, [&emitter.to_bytes()[..], &key].concat(), b"");
+            }
+            if let Some(caller) = event.context.call_stack.back() {
+                batch.put_cf(caller_handle, [&caller.to_bytes()[..], &key].concat(), b"");
+            }
+            if let Some(op_id) = event.context.origin_operation_id {
+                batch.put_cf(operation_handle, [&op_id.to_bytes()[..], &key].concat(), b"");
+            }
+            batch.put_cf(
+                events_handle,
+                key,
+                serde_json::to_vec(&event).expect(EVENT_SER_ERROR),
+            );
+        }
+        self.db.write(batch).expect(CRUD_ERROR);
+    }
+
+    /// Get a page of stored events matching the filter.
+    /// The most selective available index is iterated, starting right after the cursor.
+    fn get_filtered_events(
+        &self,
+        filter: &EventFilter,
+        cursor: Option<EventCursor>,
+        limit: usize,
+    ) -> (Vec<SCOutputEvent>, Option<EventCursor>) {
+        if limit == 0 {
+            return (Vec::new(), cursor);
+        }
+        let events_handle = self.db.cf_handle(EVENTS_CF).expect(CF_ERROR);
+
+        // choose the column family to iterate and the prefix of its keys
+        let (cf, prefix) = if let Some(op_id) = filter.original_operation_id {
+            (OPERATION_INDEX_CF, op_id.to_bytes().to_vec())
+        } else if let Some(emitter) = filter.emitter_address {
+            (EMITTER_INDEX_CF, emitter.to_bytes().to_vec())
+        } else if let Some(caller) = filter.original_caller_address {
+            (CALLER_INDEX_CF, caller.to_bytes().to_vec())
+        } else {
+            (EVENTS_CF, Vec::new())
+        };
+        let handle = self.db.cf_handle(cf).expect(CF_ERROR);
+
+        // start right after the cursor, and not before the start slot of the filter
+        let from = filter.start.map(|slot| EventCursor {
+            slot,
+            index_in_slot: 0,
+        });
+        let after_cursor = cursor.map(|cursor| EventCursor {
+            slot: cursor.slot,
+            index_in_slot: cursor.index_in_slot.saturating_add(1),
+        });
+        let start_key = match from.max(after_cursor) {
+            Some(from) => [&prefix[..], &event_key(&from)].concat(),
+            None => prefix.clone(),
+        };
+
+        // stop before the end slot of the filter, or at the end of the prefix
+        let mut opt = ReadOptions::default();
+        if let Some(end) = filter.end {
+            let end = EventCursor {
+                slot: end,
+                index_in_slot: 0,
+            };
+            opt.set_iterate_upper_bound([&prefix[..], &event_key(&end)].concat());
+        } else if let Some(end) = end_prefix(&prefix) {
+            opt.set_iterate_upper_bound(end);
+        }
+
+        let mut events: Vec<SCOutputEvent> = Vec::new();
+        for (key, value) in self
+            .db
+            .iterator_cf_opt(
+                handle,
+                opt,
+                IteratorMode::From(&start_key, Direction::Forward),
+            )
+            .flatten()
+        {
+            let bytes = if prefix.is_empty() {
+                value.to_vec()
+            } else {
+                match self
+                    .db
+                    .get_cf(events_handle, &key[prefix.len()..])
+                    .expect(CRUD_ERROR)
+                {
+                    Some(bytes) => bytes,
+                    None => continue,
+                }
+            };
+            let event: SCOutputEvent = serde_json::from_slice(&bytes).expect(EVENT_DESER_ERROR);
+            if !filter.matches(&event) {
+                continue;
+            }
+            // a matching event remains after a full page
+            if events.len() == limit {
+                let next_cursor = events.last().map(SCOutputEvent::cursor);
+                return (events, next_cursor);
+            }
+            events.push(event);
+        }
+        (events, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FinalEventStore;
+    use massa_ledger_exports::EventStoreController;
+    use massa_models::{
+        address::Address,
+        api::EventFilter,
+        output_event::{EventExecutionContext, SCOutputEvent},
+        slot::Slot,
+    };
+    use massa_signature::KeyPair;
+    use std::collections::VecDeque;
+    use tempfile::TempDir;
+
+    fn event(slot: Slot, index_in_slot: u64, call_stack: Vec<Address>) -> SCOutputEvent {
+        SCOutputEvent {
+            context: EventExecutionContext {
+                slot,
+                block: None,
+                read_only: false,
+                index_in_slot,
+                call_stack: VecDeque::from(call_stack),
+                origin_operation_id: None,
+                is_final: true,
+            },
+            data: format!("{}:{}", slot, index_in_slot),
+        }
+    }
+
+    #[test]
+    fn test_event_store_pagination() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut store = FinalEventStore::new(temp_dir.path().to_path_buf());
+        let addr_a = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let addr_b = Address::from_public_key(&KeyPair::generate().get_public_key());
+
+        let mut events = Vec::new();
+        for period in 0..5 {
+            events.push(event(Slot::new(period, 0), 0, vec![addr_a]));
+            events.push(event(Slot::new(period, 0), 1, vec![addr_b, addr_a]));
+        }
+        store.append_events(events);
+
+        // page through every event
+        let filter = EventFilter::default();
+        let (page, cursor) = store.get_filtered_events(&filter, None, 4);
+        assert_eq!(page.len(), 4);
+        let (page, cursor) = store.get_filtered_events(&filter, cursor, 4);
+        assert_eq!(page.len(), 4);
+        assert_eq!(page[0].context.slot, Slot::new(2, 0));
+        let (page, cursor) = store.get_filtered_events(&filter, cursor, 4);
+        assert_eq!(page.len(), 2);
+        assert!(cursor.is_none());
+
+        // emitter index, restricted to a slot range
+        let filter = EventFilter {
+            start: Some(Slot::new(1, 0)),
+            end: Some(Slot::new(3, 0)),
+            emitter_address: Some(addr_b),
+            ..Default::default()
+        };
+        let (page, cursor) = store.get_filtered_events(&filter, None, 10);
+        assert_eq!(page.len(), 2);
+        assert!(page.iter().all(|e| e.context.index_in_slot == 1));
+        assert!(cursor.is_none());
+
+        // original caller index
+        let filter = EventFilter {
+            original_caller_address: Some(addr_a),
+            ..Default::default()
+        };
+        let (page, _) = store.get_filtered_events(&filter, None, 10);
+        assert_eq!(page.len(), 10);
+    }
+}
@@ -2,7 +2,7 @@
 
 //! This file defines the final ledger associating addresses to their balances, bytecode and data.
 
-use crate::ledger_db::{LedgerDB, LedgerSubEntry};
+use crate::ledger_db::LedgerDB;
 use massa_hash::Hash;
 use massa_ledger_exports::{
     LedgerChanges, LedgerConfig, LedgerController, LedgerEntry, LedgerError,
@@ -11,6 +11,8 @@ use massa_models::{
     address::Address,
     amount::{Amount, AmountDeserializer},
     error::ModelsError,
+    ledger_models::LedgerSubEntry,
+    ledger_proof::LedgerProof,
     slot::Slot,
 };
 use massa_serialization::{DeserializeError, Deserializer};
@@ -160,6 +162,18 @@ impl LedgerController for FinalLedger {
         self.sorted_ledger.get_ledger_hash()
     }
 
+    /// Get the value of a ledger sub-entry along with its inclusion or exclusion proof against the current ledger hash
+    ///
+    /// # Returns
+    /// A tuple containing the ledger key of the sub-entry, its value if it exists, and the proof
+    fn get_ledger_proof(
+        &self,
+        addr: &Address,
+        sub_entry: LedgerSubEntry,
+    ) -> (Vec<u8>, Option<Vec<u8>>, LedgerProof) {
+        self.sorted_ledger.get_ledger_proof(addr, sub_entry)
+    }
+
     /// Get a part of the disk ledger.
     ///
     /// Solely used by the bootstrap.
@@ -173,6 +187,27 @@ impl LedgerController for FinalLedger {
         self.sorted_ledger.get_ledger_part(last_key)
     }
 
+    /// Get a part of a range of the disk ledger.
+    ///
+    /// Solely used by the parallel bootstrap.
+    ///
+    /// # Returns
+    /// A tuple containing the data and the last returned key
+    fn get_ledger_range_part(
+        &self,
+        last_key: &[u8],
+        end_key: &Option<Vec<u8>>,
+    ) -> Result<(Vec<u8>, Option<Vec<u8>>), ModelsError> {
+        self.sorted_ledger.get_ledger_range_part(last_key, end_key)
+    }
+
+    /// Delete a range of the disk ledger.
+    ///
+    /// Solely used by the parallel bootstrap.
+    fn delete_ledger_range(&mut self, first_key: &[u8], end_key: &Option<Vec<u8>>) {
+        self.sorted_ledger.delete_ledger_range(first_key, end_key)
+    }
+
     /// Set a part of the disk ledger.
     ///
     /// Solely used by the bootstrap.
@@ -2,54 +2,45 @@
 
 //! Module to interact with the disk ledger
 
+use crate::merkle::{merkle_node_key, with_path_bit, MerkleNode};
 use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_ledger_exports::*;
 use massa_models::{
     address::{Address, ADDRESS_SIZE_BYTES},
     amount::AmountSerializer,
     error::ModelsError,
+    ledger_models::LedgerSubEntry,
+    ledger_proof::{
+        merkle_node_hash, merkle_path, merkle_path_bit, LedgerProof, MerkleLeaf,
+        MERKLE_EMPTY_HASH_BYTES,
+    },
     serialization::{VecU8Deserializer, VecU8Serializer},
     slot::{Slot, SlotSerializer},
 };
-use massa_serialization::{Deserializer, Serializer, U64VarIntSerializer};
+use massa_serialization::{Deserializer, Serializer};
 use nom::multi::many0;
 use nom::sequence::tuple;
 use rocksdb::{
     ColumnFamily, ColumnFamilyDescriptor, Direction, IteratorMode, Options, ReadOptions,
     WriteBatch, DB,
 };
+use std::collections::{BTreeSet, HashMap};
 use std::ops::Bound;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::{collections::BTreeMap, fmt::Debug};
-use std::{
-    collections::{BTreeSet, HashMap},
-    convert::TryInto,
-};
 
 #[cfg(feature = "testing")]
 use massa_models::amount::{Amount, AmountDeserializer};
 
 const LEDGER_CF: &str = "ledger";
 const METADATA_CF: &str = "metadata";
+const MERKLE_CF: &str = "merkle";
 const OPEN_ERROR: &str = "critical: rocksdb open operation failed";
 const CRUD_ERROR: &str = "critical: rocksdb crud operation failed";
 const CF_ERROR: &str = "critical: rocksdb column family operation failed";
-const LEDGER_HASH_ERROR: &str = "critical: saved ledger hash is corrupted";
-const KEY_LEN_SER_ERROR: &str = "critical: key length serialization failed";
 const SLOT_KEY: &[u8; 1] = b"s";
-const LEDGER_HASH_KEY: &[u8; 1] = b"h";
-const LEDGER_HASH_INITIAL_BYTES: &[u8; 32] = &[0; HASH_SIZE_BYTES];
-
-/// Ledger sub entry enum
-pub enum LedgerSubEntry {
-    /// Balance
-    Balance,
-    /// Bytecode
-    Bytecode,
-    /// Datastore entry
-    Datastore(Vec<u8>),
-}
+const LEDGER_HASH_INITIAL_BYTES: &[u8; HASH_SIZE_BYTES] = &MERKLE_EMPTY_HASH_BYTES;
 
 /// Disk ledger DB module
 ///
@@ -59,7 +50,6 @@ pub(crate) struct LedgerDB {
     thread_count: u8,
     amount_serializer: AmountSerializer,
     slot_serializer: SlotSerializer,
-    len_serializer: U64VarIntSerializer,
     max_datastore_key_length: u8,
     ledger_part_size_message_bytes: u64,
     #[cfg(feature = "testing")]
@@ -76,7 +66,7 @@ impl Debug for LedgerDB {
 /// This assumes the key bytes are ordered in lexicographical order.
 /// Since key length is not limited, for some case we return `None` because there is
 /// no bounded limit (every keys in the series `[]`, `[255]`, `[255, 255]` ...).
-fn end_prefix(prefix: &[u8]) -> Option<Vec<u8>> {
+pub(crate) fn end_prefix(prefix: &[u8]) -> Option<Vec<u8>> {
     let mut end_range = prefix.to_vec();
     while let Some(0xff) = end_range.last() {
         end_range.pop();
@@ -96,23 +86,12 @@ fn test_end_prefix() {
 }
 
 /// Batch containing write operations to perform on disk and cache for the ledger hash computing
+#[derive(Default)]
 pub struct LedgerBatch {
     // Rocksdb write batch
     write_batch: WriteBatch,
-    // Ledger hash state in the current batch
-    ledger_hash: Hash,
-    // Added entry hashes in the current batch
-    aeh_list: BTreeMap<Vec<u8>, Hash>,
-}
-
-impl LedgerBatch {
-    pub fn new(ledger_hash: Hash) -> Self {
-        Self {
-            write_batch: WriteBatch::default(),
-            ledger_hash,
-            aeh_list: BTreeMap::new(),
-        }
-    }
+    // Ledger tree nodes changed in the current batch, `None` for deleted nodes
+    merkle_nodes: BTreeMap<Vec<u8>, Option<MerkleNode>>,
 }
 
 impl LedgerDB {
@@ -136,6 +115,7 @@ impl LedgerDB {
             vec![
                 ColumnFamilyDescriptor::new(LEDGER_CF, Options::default()),
                 ColumnFamilyDescriptor::new(METADATA_CF, Options::default()),
+                ColumnFamilyDescriptor::new(MERKLE_CF, Options::default()),
             ],
         )
         .expect(OPEN_ERROR);
@@ -145,7 +125,6 @@ impl LedgerDB {
             thread_count,
             amount_serializer: AmountSerializer::new(),
             slot_serializer: SlotSerializer::new(),
-            len_serializer: U64VarIntSerializer::new(),
             max_datastore_key_length,
             ledger_part_size_message_bytes,
             #[cfg(feature = "testing")]
@@ -160,10 +139,7 @@ impl LedgerDB {
     ///
     /// # Arguments
     pub fn load_initial_ledger(&mut self, initial_ledger: HashMap<Address, LedgerEntry>) {
-        // initial ledger_hash value to avoid matching an option in every XOR operation
-        // because of a one time case being an empty ledger
-        let ledger_hash = Hash::from_bytes(LEDGER_HASH_INITIAL_BYTES);
-        let mut batch = LedgerBatch::new(ledger_hash);
+        let mut batch = LedgerBatch::default();
         for (address, entry) in initial_ledger {
             self.put_entry(&address, entry, &mut batch);
         }
@@ -181,7 +157,7 @@ impl LedgerDB {
     /// * slot: new slot associated to the final ledger
     pub fn apply_changes(&mut self, changes: LedgerChanges, slot: Slot) {
         // create the batch
-        let mut batch = LedgerBatch::new(self.get_ledger_hash());
+        let mut batch = LedgerBatch::default();
         // for all incoming changes
         for (addr, change) in changes.0 {
             match change {
@@ -211,10 +187,13 @@ impl LedgerDB {
 
     /// Apply the given operation batch to the disk ledger
     fn write_batch(&self, mut batch: LedgerBatch) {
-        let handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
-        batch
-            .write_batch
-            .put_cf(handle, LEDGER_HASH_KEY, batch.ledger_hash.to_bytes());
+        let handle = self.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+        for (node_key, node) in batch.merkle_nodes {
+            match node {
+                Some(node) => batch.write_batch.put_cf(handle, node_key, node.to_bytes()),
+                None => batch.write_batch.delete_cf(handle, node_key),
+            }
+        }
         self.db.write(batch.write_batch).expect(CRUD_ERROR);
     }
 
@@ -230,31 +209,153 @@ impl LedgerDB {
         self.slot_serializer
             .serialize(&slot, &mut slot_bytes)
             .unwrap();
-        batch
-            .write_batch
-            .put_cf(handle, SLOT_KEY, slot_bytes.clone());
-        // XOR previous slot and new one
-        if let Some(prev_bytes) = self.db.get_cf(handle, SLOT_KEY).expect(CRUD_ERROR) {
-            batch.ledger_hash ^= Hash::compute_from(&prev_bytes);
-        }
-        batch.ledger_hash ^= Hash::compute_from(&slot_bytes);
+        batch.write_batch.put_cf(handle, SLOT_KEY, slot_bytes);
     }
 
-    /// Get the current disk ledger hash
+    /// Get the current disk ledger hash, which is the root of the ledger tree
     pub fn get_ledger_hash(&self) -> Hash {
-        let handle = self.db.cf_handle(METADATA_CF).expect(CF_ERROR);
-        if let Some(ledger_hash_bytes) = self.db.get_cf(handle, LEDGER_HASH_KEY).expect(CRUD_ERROR)
-        {
-            Hash::from_bytes(&ledger_hash_bytes.try_into().expect(LEDGER_HASH_ERROR))
-        } else {
-            // initial ledger_hash value to avoid matching an option in every XOR operation
-            // because of a one time case being an empty ledger
-            // also note that the if you XOR a hash with itself result is LEDGER_HASH_INITIAL_BYTES
-            Hash::from_bytes(LEDGER_HASH_INITIAL_BYTES)
+        // the root is the node at depth 0, whatever the path
+        let root_key = merkle_node_key(0, &Hash::from_bytes(LEDGER_HASH_INITIAL_BYTES));
+        self.get_merkle_node(None, &root_key)
+            .map(|root| root.hash())
+            // the hash of an empty ledger
+            .unwrap_or_else(|| Hash::from_bytes(LEDGER_HASH_INITIAL_BYTES))
+    }
+
+    /// Get the value of a ledger key along with its inclusion or exclusion proof against the current ledger hash
+    ///
+    /// # Arguments
+    /// * `addr`: associated address
+    /// * `ty`: type of the proven sub-entry
+    ///
+    /// # Returns
+    /// A tuple containing the ledger key, its value if it exists, and the proof
+    pub fn get_ledger_proof(
+        &self,
+        addr: &Address,
+        ty: LedgerSubEntry,
+    ) -> (Vec<u8>, Option<Vec<u8>>, LedgerProof) {
+        let key = match ty {
+            LedgerSubEntry::Balance => balance_key!(addr),
+            LedgerSubEntry::Bytecode => bytecode_key!(addr),
+            LedgerSubEntry::Datastore(hash) => data_key!(addr, hash),
+        };
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        let value = self.db.get_cf(handle, &key).expect(CRUD_ERROR);
+
+        // walk down the path of the key until reaching a leaf or an empty subtree
+        let path = merkle_path(&key);
+        let mut proof = LedgerProof {
+            siblings: Vec::new(),
+            other_leaf: None,
+        };
+        for depth in 0.. {
+            match self.get_merkle_node(None, &merkle_node_key(depth, &path)) {
+                Some(MerkleNode::Internal(_)) => {
+                    let sibling_path = with_path_bit(&path, depth, !merkle_path_bit(&path, depth));
+                    proof.siblings.push(
+                        self.get_merkle_node(None, &merkle_node_key(depth + 1, &sibling_path))
+                            .map(|sibling| sibling.hash())
+                            .unwrap_or_else(|| Hash::from_bytes(&MERKLE_EMPTY_HASH_BYTES)),
+                    );
+                }
+                Some(MerkleNode::Leaf(leaf)) => {
+                    if leaf.path != path {
+                        proof.other_leaf = Some(leaf);
+                    }
+                    break;
+                }
+                None => break,
+            }
+        }
+        (key, value, proof)
+    }
+
+    /// Internal function to get a ledger tree node, looking first at the nodes changed by `batch`
+    fn get_merkle_node(&self, batch: Option<&LedgerBatch>, node_key: &[u8]) -> Option<MerkleNode> {
+        if let Some(node) = batch.and_then(|batch| batch.merkle_nodes.get(node_key)) {
+            return *node;
+        }
+        let handle = self.db.cf_handle(MERKLE_CF).expect(CF_ERROR);
+        self.db
+            .get_cf(handle, node_key)
+            .expect(CRUD_ERROR)
+            .map(|bytes| MerkleNode::from_bytes(&bytes))
+    }
+
+    /// Internal function to insert or replace the leaf of `path` in the ledger subtree at `depth`
+    fn merkle_insert(&self, batch: &mut LedgerBatch, depth: usize, leaf: MerkleLeaf) {
+        let node_key = merkle_node_key(depth, &leaf.path);
+        match self.get_merkle_node(Some(&*batch), &node_key) {
+            Some(MerkleNode::Leaf(other)) if other.path != leaf.path => {
+                // the subtree now contains two leaves: push the existing one down and insert next to it
+                batch.merkle_nodes.insert(
+                    merkle_node_key(depth + 1, &other.path),
+                    Some(MerkleNode::Leaf(other)),
+                );
+                self.merkle_insert(batch, depth + 1, leaf);
+                self.merkle_update_node(batch, depth, &leaf.path);
+            }
+            Some(MerkleNode::Internal(_)) => {
+                self.merkle_insert(batch, depth + 1, leaf);
+                self.merkle_update_node(batch, depth, &leaf.path);
+            }
+            // empty subtree or same key
+            _ => {
+                batch
+                    .merkle_nodes
+                    .insert(node_key, Some(MerkleNode::Leaf(leaf)));
+            }
+        }
+    }
+
+    /// Internal function to remove the leaf of `path` from the ledger subtree at `depth`, if it exists
+    fn merkle_remove(&self, batch: &mut LedgerBatch, depth: usize, path: &Hash) {
+        let node_key = merkle_node_key(depth, path);
+        match self.get_merkle_node(Some(&*batch), &node_key) {
+            Some(MerkleNode::Leaf(leaf)) if leaf.path == *path => {
+                batch.merkle_nodes.insert(node_key, None);
+            }
+            Some(MerkleNode::Internal(_)) => {
+                self.merkle_remove(batch, depth + 1, path);
+                self.merkle_update_node(batch, depth, path);
+            }
+            _ => {}
         }
     }
 
-    /// Internal function to put a key & value and perform the ledger hash XORs
+    /// Internal function to recompute the ledger tree node at `depth` on `path` from its children.
+    /// A node left with a single leaf below it is replaced by that leaf.
+    fn merkle_update_node(&self, batch: &mut LedgerBatch, depth: usize, path: &Hash) {
+        let left_key = merkle_node_key(depth + 1, &with_path_bit(path, depth, false));
+        let right_key = merkle_node_key(depth + 1, &with_path_bit(path, depth, true));
+        let node = match (
+            self.get_merkle_node(Some(&*batch), &left_key),
+            self.get_merkle_node(Some(&*batch), &right_key),
+        ) {
+            (None, None) => None,
+            (Some(leaf @ MerkleNode::Leaf(_)), None) => {
+                batch.merkle_nodes.insert(left_key, None);
+                Some(leaf)
+            }
+            (None, Some(leaf @ MerkleNode::Leaf(_))) => {
+                batch.merkle_nodes.insert(right_key, None);
+                Some(leaf)
+            }
+            (left, right) => {
+                let empty_hash = Hash::from_bytes(&MERKLE_EMPTY_HASH_BYTES);
+                Some(MerkleNode::Internal(merkle_node_hash(
+                    &left.map_or(empty_hash, |node| node.hash()),
+                    &right.map_or(empty_hash, |node| node.hash()),
+                )))
+            }
+        };
+        batch
+            .merkle_nodes
+            .insert(merkle_node_key(depth, path), node);
+    }
+
+    /// Internal function to put a key & value and update the ledger tree
     fn put_entry_value(
         &self,
         handle: &ColumnFamily,
@@ -262,13 +363,11 @@ impl LedgerDB {
         key: &[u8],
         value: &[u8],
     ) {
-        let mut len_bytes = Vec::new();
-        self.len_serializer
-            .serialize(&(key.len() as u64), &mut len_bytes)
-            .expect(KEY_LEN_SER_ERROR);
-        let hash = Hash::compute_from(&[&len_bytes, key, value].concat());
-        batch.ledger_hash ^= hash;
-        batch.aeh_list.insert(key.to_vec(), hash);
+        let leaf = MerkleLeaf {
+            path: merkle_path(key),
+            value_hash: Hash::compute_from(value),
+        };
+        self.merkle_insert(batch, 0, leaf);
         batch.write_batch.put_cf(handle, key, value);
     }
 
@@ -346,29 +445,6 @@ impl LedgerDB {
             .collect()
     }
 
-    /// Internal function to update a key & value and perform the ledger hash XORs
-    fn update_key_value(
-        &self,
-        handle: &ColumnFamily,
-        batch: &mut LedgerBatch,
-        key: &[u8],
-        value: &[u8],
-    ) {
-        let mut len_bytes = Vec::new();
-        self.len_serializer
-            .serialize(&(key.len() as u64), &mut len_bytes)
-            .expect(KEY_LEN_SER_ERROR);
-        if let Some(added_hash) = batch.aeh_list.get(key) {
-            batch.ledger_hash ^= *added_hash;
-        } else if let Some(prev_bytes) = self.db.get_cf(handle, key).expect(CRUD_ERROR) {
-            batch.ledger_hash ^= Hash::compute_from(&[&len_bytes, key, &prev_bytes].concat());
-        }
-        let hash = Hash::compute_from(&[&len_bytes, key, value].concat());
-        batch.ledger_hash ^= hash;
-        batch.aeh_list.insert(key.to_vec(), hash);
-        batch.write_batch.put_cf(handle, key, value);
-    }
-
     /// Update the ledger entry of a given address.
     ///
     /// # Arguments
@@ -389,36 +465,28 @@ impl LedgerDB {
             self.amount_serializer
                 .serialize(&balance, &mut bytes)
                 .unwrap();
-            self.update_key_value(handle, batch, &balance_key!(addr), &bytes);
+            self.put_entry_value(handle, batch, &balance_key!(addr), &bytes);
         }
 
         // bytecode
         if let SetOrKeep::Set(bytecode) = entry_update.bytecode {
-            self.update_key_value(handle, batch, &bytecode_key!(addr), &bytecode);
+            self.put_entry_value(handle, batch, &bytecode_key!(addr), &bytecode);
         }
 
         // datastore
         for (hash, update) in entry_update.datastore {
             match update {
                 SetOrDelete::Set(entry) => {
-                    self.update_key_value(handle, batch, &data_key!(addr, hash), &entry)
+                    self.put_entry_value(handle, batch, &data_key!(addr, hash), &entry)
                 }
                 SetOrDelete::Delete => self.delete_key(handle, batch, &data_key!(addr, hash)),
             }
         }
     }
 
-    /// Internal function to delete a key and perform the ledger hash XOR
+    /// Internal function to delete a key and update the ledger tree
     fn delete_key(&self, handle: &ColumnFamily, batch: &mut LedgerBatch, key: &[u8]) {
-        if let Some(added_hash) = batch.aeh_list.get(key) {
-            batch.ledger_hash ^= *added_hash;
-        } else if let Some(prev_bytes) = self.db.get_cf(handle, key).expect(CRUD_ERROR) {
-            let mut len_bytes = Vec::new();
-            self.len_serializer
-                .serialize(&(key.len() as u64), &mut len_bytes)
-                .expect(KEY_LEN_SER_ERROR);
-            batch.ledger_hash ^= Hash::compute_from(&[&len_bytes, key, &prev_bytes].concat());
-        }
+        self.merkle_remove(batch, 0, &merkle_path(key));
         batch.write_batch.delete_cf(handle, key);
     }
 
@@ -496,6 +564,77 @@ impl LedgerDB {
         Ok((part, last_key))
     }
 
+    /// Get a part of a range of the ledger.
+    /// Used for the parallel bootstrap, where each bootstrap server streams a different range of keys.
+    ///
+    /// # Arguments
+    /// * `last_key`: only the keys strictly after this one are returned, it does not need to be in the ledger
+    /// * `end_key`: if defined, only the keys strictly before this one are returned
+    ///
+    /// # Returns
+    /// A tuple containing the serialized part and the last returned key
+    pub fn get_ledger_range_part(
+        &self,
+        last_key: &[u8],
+        end_key: &Option<Vec<u8>>,
+    ) -> Result<(Vec<u8>, Option<Vec<u8>>), ModelsError> {
+        let ser = VecU8Serializer::new();
+        let key_serializer = KeySerializer::new();
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        let mut part = Vec::new();
+        let opt = ReadOptions::default();
+        let db_iterator = self.db.iterator_cf_opt(
+            handle,
+            opt,
+            IteratorMode::From(last_key, Direction::Forward),
+        );
+        let mut new_last_key = None;
+
+        for (key, entry) in db_iterator.flatten() {
+            if key.as_ref() == last_key {
+                continue;
+            }
+            if matches!(end_key, Some(end) if key.as_ref() >= end.as_slice()) {
+                break;
+            }
+            if (part.len() as u64) < (self.ledger_part_size_message_bytes) {
+                key_serializer.serialize(&key.to_vec(), &mut part)?;
+                ser.serialize(&entry.to_vec(), &mut part)?;
+                new_last_key = Some(key.to_vec());
+            } else {
+                break;
+            }
+        }
+        Ok((part, new_last_key))
+    }
+
+    /// Delete a range of the ledger.
+    /// Used for the parallel bootstrap, to drop a range received from a server whose final state hash did not match.
+    ///
+    /// # Arguments
+    /// * `first_key`: first deleted key, it does not need to be in the ledger
+    /// * `end_key`: if defined, only the keys strictly before this one are deleted
+    pub fn delete_ledger_range(&self, first_key: &[u8], end_key: &Option<Vec<u8>>) {
+        let handle = self.db.cf_handle(LEDGER_CF).expect(CF_ERROR);
+        let mut batch = LedgerBatch::default();
+        let mut opt = ReadOptions::default();
+        if let Some(end_key) = end_key {
+            opt.set_iterate_upper_bound(end_key.clone());
+        }
+        for (key, _) in self
+            .db
+            .iterator_cf_opt(
+                handle,
+                opt,
+                IteratorMode::From(first_key, Direction::Forward),
+            )
+            .flatten()
+        {
+            self.delete_key(handle, &mut batch, &key);
+        }
+        self.write_batch(batch);
+    }
+
     /// Set a part of the ledger in the database.
     /// We deserialize in this function because we insert in the ledger while deserializing.
     /// Used for bootstrap.
@@ -511,7 +650,7 @@ impl LedgerDB {
             VecU8Deserializer::new(Bound::Included(0), Bound::Excluded(u64::MAX));
         let key_deserializer = KeyDeserializer::new(self.max_datastore_key_length);
         let mut last_key = Rc::new(None);
-        let mut batch = LedgerBatch::new(self.get_ledger_hash());
+        let mut batch = LedgerBatch::default();
 
         // Since this data is coming from the network, deser to address and ser back to bytes for a security check.
         let (rest, _) = many0(|input: &'a [u8]| {
@@ -646,7 +785,7 @@ mod tests {
         // write data
         let temp_dir = TempDir::new().unwrap();
         let mut db = LedgerDB::new(temp_dir.path().to_path_buf(), 32, 255, 1_000_000);
-        let mut batch = LedgerBatch::new(Hash::from_bytes(LEDGER_HASH_INITIAL_BYTES));
+        let mut batch = LedgerBatch::default();
         db.put_entry(&addr, entry, &mut batch);
         db.update_entry(&addr, entry_update, &mut batch);
         db.write_batch(batch);
@@ -660,7 +799,6 @@ mod tests {
     fn test_ledger_db() {
         let addr = Address::from_public_key(&KeyPair::generate().get_public_key());
         let (db, data) = init_test_ledger(addr);
-        let ledger_hash = db.get_ledger_hash();
         let amount_deserializer =
             AmountDeserializer::new(Included(Amount::MIN), Included(Amount::MAX));
 
@@ -682,7 +820,7 @@ mod tests {
         );
 
         // delete entry
-        let mut batch = LedgerBatch::new(ledger_hash);
+        let mut batch = LedgerBatch::default();
         db.delete_entry(&addr, &mut batch);
         db.write_batch(batch);
 
@@ -703,4 +841,87 @@ mod tests {
         let res = db.get_ledger_part(&None).unwrap();
         db.set_ledger_part(&res.0[..]).unwrap();
     }
+
+    #[test]
+    fn test_ledger_range_parts() {
+        let addr = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let (db, _) = init_test_ledger(addr);
+        let (whole, _) = db.get_ledger_part(&None).unwrap();
+
+        // the keys of an address are strictly after its bytes
+        let (part, last_key) = db.get_ledger_range_part(addr.to_bytes(), &None).unwrap();
+        assert_eq!(part, whole);
+
+        // resuming from the last key returns nothing
+        let (rest, _) = db.get_ledger_range_part(&last_key.unwrap(), &None).unwrap();
+        assert!(rest.is_empty());
+
+        // the range ending at the address bytes is empty
+        let (before, last_key) = db
+            .get_ledger_range_part(&[], &Some(addr.to_bytes().to_vec()))
+            .unwrap();
+        assert!(before.is_empty());
+        assert!(last_key.is_none());
+    }
+
+    #[test]
+    fn test_delete_ledger_range() {
+        let addr = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let (db, _) = init_test_ledger(addr);
+        let (whole, _) = db.get_ledger_part(&None).unwrap();
+
+        // the range ending at the address bytes does not contain the entry
+        db.delete_ledger_range(&[], &Some(addr.to_bytes().to_vec()));
+        assert_eq!(db.get_ledger_part(&None).unwrap().0, whole);
+
+        // the range starting at the address bytes contains the whole entry
+        db.delete_ledger_range(addr.to_bytes(), &None);
+        assert!(db.get_ledger_part(&None).unwrap().0.is_empty());
+        assert!(db.get_sub_entry(&addr, LedgerSubEntry::Balance).is_none());
+        assert_eq!(
+            Hash::from_bytes(LEDGER_HASH_INITIAL_BYTES),
+            db.get_ledger_hash()
+        );
+    }
+
+    #[test]
+    fn test_ledger_proofs() {
+        let addr = Address::from_public_key(&KeyPair::generate().get_public_key());
+        let (db, data) = init_test_ledger(addr);
+        let ledger_hash = db.get_ledger_hash();
+
+        // inclusion proofs
+        for sub_entry in [
+            LedgerSubEntry::Balance,
+            LedgerSubEntry::Bytecode,
+            LedgerSubEntry::Datastore(b"2".to_vec()),
+        ] {
+            let (key, value, proof) = db.get_ledger_proof(&addr, sub_entry);
+            let value = value.expect("missing proven value");
+            assert!(proof.verify(&ledger_hash, &key, Some(&value)));
+            assert!(!proof.verify(&ledger_hash, &key, Some(b"forged")));
+            assert!(!proof.verify(&ledger_hash, &key, None));
+        }
+        let (_, value, _) = db.get_ledger_proof(&addr, LedgerSubEntry::Datastore(b"2".to_vec()));
+        assert_eq!(value.as_ref(), data.get(&b"2".to_vec()));
+
+        // exclusion proofs
+        let unknown_addr = Address::from_public_key(&KeyPair::generate().get_public_key());
+        for (address, sub_entry) in [
+            (addr, LedgerSubEntry::Datastore(b"4".to_vec())),
+            (unknown_addr, LedgerSubEntry::Balance),
+        ] {
+            let (key, value, proof) = db.get_ledger_proof(&address, sub_entry);
+            assert!(value.is_none());
+            assert!(proof.verify(&ledger_hash, &key, None));
+            assert!(!proof.verify(&ledger_hash, &key, Some(b"forged")));
+        }
+
+        // a ledger bootstrapped from the same content has the same hash
+        let temp_dir = TempDir::new().unwrap();
+        let copy = LedgerDB::new(temp_dir.path().to_path_buf(), 32, 255, 1_000_000);
+        let (part, _) = db.get_ledger_part(&None).unwrap();
+        copy.set_ledger_part(&part).unwrap();
+        assert_eq!(copy.get_ledger_hash(), ledger_hash);
+    }
 }
@@ -27,6 +27,14 @@
 //! Represents a list of changes to ledger entries that
 //! can be modified, combined or applied to the final ledger.
 //!
+//! ## `merkle.rs`
+//! Defines the stored nodes of the sparse Merkle tree whose root is the ledger hash,
+//! allowing to prove the value or the absence of any ledger key against it.
+//!
+//! ## `event_store_db.rs`
+//! Defines the `FinalEventStore`, an optional disk store of final smart contract events
+//! indexed by emitter address, original caller address, origin operation and slot.
+//!
 //! ## `bootstrap.rs`
 //! Provides serializable structures and tools for bootstrapping the final ledger.  
 //!
@@ -38,9 +46,12 @@
 #![warn(missing_docs)]
 #![warn(unused_crate_dependencies)]
 
+mod event_store_db;
 mod ledger;
 mod ledger_db;
+mod merkle;
 
+pub use event_store_db::FinalEventStore;
 pub use ledger::FinalLedger;
 
 #[cfg(test)]
@@ -0,0 +1,97 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Nodes of the sparse Merkle tree committing to the disk ledger (see `massa_models::ledger_proof`).
+//!
+//! A node is stored under the key made of its depth followed by the bits of its path up to that depth.
+//! Only non-empty subtrees have a node: a subtree containing a single leaf is stored as that leaf,
+//! and a subtree containing several leaves as an internal node holding its hash.
+
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use massa_models::ledger_proof::{merkle_leaf_hash, MerkleLeaf};
+use std::convert::TryInto;
+
+const INTERNAL_NODE_IDENT: u8 = 0u8;
+const LEAF_NODE_IDENT: u8 = 1u8;
+const MERKLE_NODE_ERROR: &str = "critical: saved merkle node is corrupted";
+
+/// Stored node of the ledger tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MerkleNode {
+    /// root of a subtree containing several leaves, with its hash
+    Internal(Hash),
+    /// only leaf of a subtree
+    Leaf(MerkleLeaf),
+}
+
+impl MerkleNode {
+    /// Hash of the subtree represented by the node
+    pub fn hash(&self) -> Hash {
+        match self {
+            MerkleNode::Internal(hash) => *hash,
+            MerkleNode::Leaf(leaf) => merkle_leaf_hash(&leaf.path, &leaf.value_hash),
+        }
+    }
+
+    /// Serializes the node for storage
+    pub fn to_bytes(self) -> Vec<u8> {
+        match self {
+            MerkleNode::Internal(hash) => [&[INTERNAL_NODE_IDENT], &hash.to_bytes()[..]].concat(),
+            MerkleNode::Leaf(leaf) => [
+                &[LEAF_NODE_IDENT],
+                &leaf.path.to_bytes()[..],
+                &leaf.value_hash.to_bytes()[..],
+            ]
+            .concat(),
+        }
+    }
+
+    /// Deserializes a stored node
+    ///
+    /// Panics if the stored bytes are corrupted
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let hash_at = |index: usize| {
+            Hash::from_bytes(
+                bytes[1 + index * HASH_SIZE_BYTES..1 + (index + 1) * HASH_SIZE_BYTES]
+                    .try_into()
+                    .expect(MERKLE_NODE_ERROR),
+            )
+        };
+        match (bytes.first(), bytes.len()) {
+            (Some(&INTERNAL_NODE_IDENT), len) if len == 1 + HASH_SIZE_BYTES => {
+                MerkleNode::Internal(hash_at(0))
+            }
+            (Some(&LEAF_NODE_IDENT), len) if len == 1 + 2 * HASH_SIZE_BYTES => {
+                MerkleNode::Leaf(MerkleLeaf {
+                    path: hash_at(0),
+                    value_hash: hash_at(1),
+                })
+            }
+            _ => panic!("{}", MERKLE_NODE_ERROR),
+        }
+    }
+}
+
+/// Returns `path` with its bit at `depth` set to `bit`
+pub(crate) fn with_path_bit(path: &Hash, depth: usize, bit: bool) -> Hash {
+    let mut bytes = path.into_bytes();
+    let mask = 1 << (7 - depth % 8);
+    if bit {
+        bytes[depth / 8] |= mask;
+    } else {
+        bytes[depth / 8] &= !mask;
+    }
+    Hash::from_bytes(&bytes)
+}
+
+/// Returns the storage key of the node at `depth` on `path`
+pub(crate) fn merkle_node_key(depth: usize, path: &Hash) -> Vec<u8> {
+    let mut prefix = path.into_bytes();
+    // clear the bits below the node
+    if depth % 8 != 0 {
+        prefix[depth / 8] &= 0xffu8 << (8 - depth % 8);
+    }
+    for byte in prefix.iter_mut().skip((depth + 7) / 8) {
+        *byte = 0;
+    }
+    [&[depth as u8], &prefix[..]].concat()
+}
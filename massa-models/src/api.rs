@@ -4,11 +4,12 @@ use crate::address::ExecutionAddressCycleInfo;
 use crate::endorsement::{EndorsementId, WrappedEndorsement};
 use crate::ledger_models::LedgerData;
 use crate::node::NodeId;
-use crate::operation::{OperationId, WrappedOperation};
+use crate::operation::{OperationId, OperationType, WrappedOperation};
+use crate::output_event::SCOutputEvent;
 use crate::stats::{ConsensusStats, ExecutionStats, NetworkStats};
 use crate::{
     address::Address, amount::Amount, block::Block, block::BlockId, config::CompactConfig,
-    slot::Slot, version::Version,
+    config::MAX_OPERATIONS_PER_BLOCK, slot::Slot, version::Version,
 };
 use massa_signature::{PublicKey, Signature};
 use massa_time::MassaTime;
@@ -42,6 +43,8 @@ pub struct NodeStatus {
     pub current_cycle: u64,
     /// connected nodes (node id, ip address, true if the connection is outgoing, false if incoming)
     pub connected_nodes: HashMap<NodeId, (IpAddr, bool)>,
+    /// reputation scores of the connected peers
+    pub peer_scores: HashMap<IpAddr, f64>,
     /// latest slot, none if now is before genesis timestamp
     pub last_slot: Option<Slot>,
     /// next slot
@@ -90,10 +93,11 @@ impl std::fmt::Display for NodeStatus {
         for (node_id, (ip_addr, is_outgoing)) in &self.connected_nodes {
             writeln!(
                 f,
-                "Node's ID: {} / IP address: {} / {} connection",
+                "Node's ID: {} / IP address: {} / {} connection / score: {:.2}",
                 node_id,
                 ip_addr,
-                if *is_outgoing { "Out" } else { "In" }
+                if *is_outgoing { "Out" } else { "In" },
+                self.peer_scores.get(ip_addr).copied().unwrap_or_default()
             )?
         }
         Ok(())
@@ -522,6 +526,31 @@ impl std::fmt::Display for DatastoreEntryOutput {
     }
 }
 
+/// Page requested from a list-returning API endpoint
+#[derive(Default, Debug, Deserialize, Clone, Serialize)]
+pub struct PageRequest {
+    /// number of items to skip, ignored when `cursor` is set
+    #[serde(default)]
+    pub offset: usize,
+    /// cursor returned as `next_cursor` by the previous page,
+    /// on endpoints that page with cursors
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// max number of items to return, capped by the server
+    pub limit: usize,
+}
+
+/// Page of items returned by a list-returning API endpoint
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct PagedResponse<T> {
+    /// items of the page
+    pub content: Vec<T>,
+    /// total number of items, when it is known
+    pub total_count: Option<usize>,
+    /// cursor to request the next page with, `None` on the last page or on endpoints paging with offsets
+    pub next_cursor: Option<String>,
+}
+
 /// filter used when retrieving SC output events
 #[derive(Default, Debug, Deserialize, Clone, Serialize)]
 pub struct EventFilter {
@@ -543,6 +572,152 @@ pub struct EventFilter {
     pub is_final: Option<bool>,
 }
 
+impl EventFilter {
+    /// Returns true if the given event matches all the criteria of the filter
+    pub fn matches(&self, event: &SCOutputEvent) -> bool {
+        if let Some(start) = self.start {
+            if event.context.slot < start {
+                return false;
+            }
+        }
+        if let Some(end) = self.end {
+            if event.context.slot >= end {
+                return false;
+            }
+        }
+        if let Some(is_final) = self.is_final {
+            if event.context.is_final != is_final {
+                return false;
+            }
+        }
+        match (self.emitter_address, event.context.call_stack.front()) {
+            (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
+            (Some(_), None) => return false,
+            _ => (),
+        }
+        match (
+            self.original_caller_address,
+            event.context.call_stack.back(),
+        ) {
+            (Some(addr1), Some(addr2)) if addr1 != *addr2 => return false,
+            (Some(_), None) => return false,
+            _ => (),
+        }
+        match (
+            self.original_operation_id,
+            event.context.origin_operation_id,
+        ) {
+            (Some(addr1), Some(addr2)) if addr1 != addr2 => return false,
+            (Some(_), None) => return false,
+            _ => (),
+        }
+        true
+    }
+}
+
+/// filter used when listing the operations of the pool
+#[derive(Default, Debug, Deserialize, Clone, Serialize)]
+pub struct PoolOperationFilter {
+    /// optional creator address
+    pub creator_address: Option<Address>,
+    /// optional thread
+    pub thread: Option<u8>,
+}
+
+/// Reason why an operation was rejected by the pool or evicted from it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PoolOperationRemovalReason {
+    /// its validity period ended before the last final period of its thread
+    Expired,
+    /// it conflicts with an operation of the pool with a higher or equal fee
+    ConflictsWithHigherFee(OperationId),
+    /// it was replaced by a conflicting operation with a higher fee
+    ReplacedByFee(OperationId),
+    /// its creator has too many better operations in the pool
+    SenderLimitReached,
+    /// its thread has too many better operations in the pool
+    PoolFull,
+    /// it was already executed
+    Executed,
+}
+
+impl std::fmt::Display for PoolOperationRemovalReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PoolOperationRemovalReason::Expired => write!(f, "expired"),
+            PoolOperationRemovalReason::ConflictsWithHigherFee(id) => {
+                write!(f, "conflicts with operation {} with a higher fee", id)
+            }
+            PoolOperationRemovalReason::ReplacedByFee(id) => {
+                write!(f, "replaced by operation {} with a higher fee", id)
+            }
+            PoolOperationRemovalReason::SenderLimitReached => {
+                write!(f, "its creator has too many better operations in the pool")
+            }
+            PoolOperationRemovalReason::PoolFull => {
+                write!(f, "its thread has too many better operations in the pool")
+            }
+            PoolOperationRemovalReason::Executed => write!(f, "already executed"),
+        }
+    }
+}
+
+/// Status of an operation in the pool
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PoolOperationStatus {
+    /// id
+    pub id: OperationId,
+    /// true if the operation is in the pool
+    pub in_pool: bool,
+    /// thread of the operation, if the pool knows the operation
+    pub thread: Option<u8>,
+    /// rank of the operation by fee quality among the operations of its thread in the pool, 0 being the best,
+    /// if fewer than `MAX_OPERATIONS_PER_BLOCK` operations of its thread are better
+    pub rank: Option<usize>,
+    /// periods during which the operation can be included in a block, if the pool knows the operation
+    pub validity_period_range: Option<(u64, u64)>,
+    /// last final period of the thread of the operation, if the pool knows the operation
+    pub last_final_period: Option<u64>,
+    /// why the operation was rejected by the pool or evicted from it, if it is not in the pool
+    /// and its removal is recent enough to be remembered
+    pub removal_reason: Option<PoolOperationRemovalReason>,
+}
+
+impl std::fmt::Display for PoolOperationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Operation {}{}",
+            self.id,
+            display_if_true(self.in_pool, " (in pool)")
+        )?;
+        if let Some(thread) = self.thread {
+            writeln!(f, "\tThread: {}", thread)?;
+        }
+        match (self.in_pool, self.rank) {
+            (_, Some(rank)) => writeln!(f, "\tRank in thread: {}", rank)?,
+            (true, None) => writeln!(f, "\tRank in thread: {} or more", MAX_OPERATIONS_PER_BLOCK)?,
+            (false, None) => {}
+        }
+        if let Some((start, end)) = self.validity_period_range {
+            writeln!(f, "\tValidity periods: {} to {}", start, end)?;
+        }
+        if let Some(last_final_period) = self.last_final_period {
+            writeln!(
+                f,
+                "\tLast final period of the thread: {}",
+                last_final_period
+            )?;
+        }
+        match (self.in_pool, &self.removal_reason) {
+            (true, _) => {}
+            (false, Some(reason)) => writeln!(f, "\tNot in pool: {}", reason)?,
+            (false, None) => writeln!(f, "\tNot in pool: unknown operation")?,
+        }
+        Ok(())
+    }
+}
+
 /// read only bytecode execution request
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct ReadOnlyBytecodeExecution {
@@ -558,6 +733,16 @@ pub struct ReadOnlyBytecodeExecution {
     pub operation_datastore: Option<Vec<u8>>,
 }
 
+/// gas estimation request
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct GasEstimationInput {
+    /// unsigned operation to estimate, either an `ExecuteSC` or a `CallSC`.
+    /// Its max gas is ignored: the estimation may use up to the max gas of a block
+    pub op: OperationType,
+    /// address of the operation sender, whose balance is used
+    pub sender_address: Address,
+}
+
 /// read SC call request
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct ReadOnlyCall {
@@ -109,11 +109,12 @@ pub struct Block {
 /// Wrapped Block
 pub type WrappedBlock = Wrapped<Block, BlockId>;
 
-impl WrappedContent for Block {
-    fn new_wrapped<SC: Serializer<Self>, U: Id>(
+impl Block {
+    /// Creates a wrapped version of the block,
+    /// with the signature and the id of its header as blocks are signed by their header
+    pub fn new_wrapped_with_header<SC: Serializer<Self>, U: Id>(
         content: Self,
         content_serializer: SC,
-        _keypair: &KeyPair,
     ) -> Result<Wrapped<Self, U>, ModelsError> {
         let mut content_serialized = Vec::new();
         content_serializer.serialize(&content, &mut content_serialized)?;
@@ -126,6 +127,31 @@ impl WrappedContent for Block {
             serialized_data: content_serialized,
         })
     }
+}
+
+impl WrappedContent for Block {
+    fn new_wrapped<SC: Serializer<Self>, U: Id>(
+        content: Self,
+        content_serializer: SC,
+        _keypair: &KeyPair,
+    ) -> Result<Wrapped<Self, U>, ModelsError> {
+        Block::new_wrapped_with_header(content, content_serializer)
+    }
+
+    fn new_wrapped_with_signer<SC, U, E, F>(
+        content: Self,
+        content_serializer: SC,
+        _public_key: PublicKey,
+        _sign: F,
+    ) -> Result<Wrapped<Self, U>, E>
+    where
+        SC: Serializer<Self>,
+        U: Id,
+        E: From<ModelsError>,
+        F: FnOnce(&Hash, &[u8]) -> Result<Signature, E>,
+    {
+        Ok(Block::new_wrapped_with_header(content, content_serializer)?)
+    }
 
     fn serialize(
         _signature: &Signature,
@@ -30,6 +30,14 @@ pub const MAX_ASK_BLOCKS_PER_MESSAGE: u32 = 128;
 pub const MAX_OPERATIONS_PER_MESSAGE: u32 = 1024;
 /// Length of the handshake random signature
 pub const HANDSHAKE_RANDOMNESS_SIZE_BYTES: usize = 32;
+/// Size of the ephemeral X25519 public keys exchanged during the handshake
+pub const X25519_PUBLIC_KEY_SIZE_BYTES: usize = 32;
+/// Minimal `(major, minor)` version of both peers for their connection to be encrypted after the handshake
+pub const ENCRYPTED_TRANSPORT_MIN_VERSION: (u32, u32) = (15, 2);
+/// Minimal `(major, minor)` version of a peer for us to ask it for blocks compactly, with operation prefixes
+pub const COMPACT_BLOCK_RELAY_MIN_VERSION: (u32, u32) = (15, 3);
+/// Minimal `(major, minor)` version of a peer for us to exchange signed peer records with it
+pub const PEER_RECORDS_MIN_VERSION: (u32, u32) = (15, 3);
 
 /// Consensus static parameters (defined by protocol used)
 /// Changing one of the following values is considered as a breaking change
@@ -64,7 +72,7 @@ lazy_static::lazy_static! {
         if cfg!(feature = "sandbox") {
             "SAND.0.0"
         } else {
-            "TEST.15.1"
+            "TEST.15.3"
         }
         .parse()
         .unwrap()
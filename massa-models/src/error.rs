@@ -36,6 +36,8 @@ pub enum ModelsError {
     CheckedOperationError(String),
     /// invalid version identifier: {0}
     InvalidVersionError(String),
+    /// invalid IP range: {0}
+    InvalidIpRangeError(String),
     /// invalid ledger change: {0}
     InvalidLedgerChange(String),
     /// Time overflow error
@@ -1,6 +1,9 @@
 use std::{collections::VecDeque, fmt::Display};
 
-use crate::{output_event::SCOutputEvent, slot::Slot};
+use crate::{
+    address::Address, amount::Amount, block::BlockId, operation::OperationId,
+    output_event::SCOutputEvent, slot::Slot,
+};
 use serde::{Deserialize, Serialize};
 
 /// The result of the read-only execution.
@@ -45,3 +48,338 @@ impl Display for ExecuteReadOnlyResponse {
         Ok(())
     }
 }
+
+/// Summary of the changes brought to a ledger entry
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LedgerChangeSummary {
+    /// address of the ledger entry
+    pub address: Address,
+    /// whether the ledger entry is deleted
+    pub deleted: bool,
+    /// new balance of the ledger entry, if it is set
+    pub balance: Option<Amount>,
+    /// whether the bytecode of the ledger entry is set
+    pub bytecode_changed: bool,
+    /// datastore keys that are set
+    pub set_datastore_keys: Vec<Vec<u8>>,
+    /// datastore keys that are deleted
+    pub deleted_datastore_keys: Vec<Vec<u8>>,
+}
+
+impl Display for LedgerChangeSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.deleted {
+            return writeln!(f, "{}: deleted", self.address);
+        }
+        writeln!(f, "{}:", self.address)?;
+        if let Some(balance) = self.balance {
+            writeln!(f, "\tBalance: {}", balance)?;
+        }
+        if self.bytecode_changed {
+            writeln!(f, "\tBytecode changed")?;
+        }
+        if !self.set_datastore_keys.is_empty() {
+            writeln!(f, "\tSet datastore keys: {:?}", self.set_datastore_keys)?;
+        }
+        if !self.deleted_datastore_keys.is_empty() {
+            writeln!(
+                f,
+                "\tDeleted datastore keys: {:?}",
+                self.deleted_datastore_keys
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The response to a gas estimation request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GasEstimation {
+    /// The slot at which the estimation was executed.
+    pub executed_at: Slot,
+    /// The result of the execution.
+    pub result: ReadOnlyResult,
+    /// The gas spent by the execution.
+    pub gas_cost: u64,
+    /// The output events that the operation would emit.
+    pub output_events: VecDeque<SCOutputEvent>,
+    /// The ledger changes that the operation would cause.
+    pub ledger_changes: Vec<LedgerChangeSummary>,
+}
+
+impl Display for GasEstimation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Executed at slot: {}", self.executed_at)?;
+        writeln!(
+            f,
+            "Result: {}",
+            match &self.result {
+                ReadOnlyResult::Error(e) =>
+                    format!("an error occurred during the execution: {}", e),
+                ReadOnlyResult::Ok => "ok".to_string(),
+            }
+        )?;
+        writeln!(f, "Gas cost: {}", self.gas_cost)?;
+        if !self.output_events.is_empty() {
+            writeln!(f, "Generated events:",)?;
+            for event in self.output_events.iter() {
+                writeln!(f, "{}", event)?; // id already displayed in event
+            }
+        }
+        if !self.ledger_changes.is_empty() {
+            writeln!(f, "Ledger changes:")?;
+            for change in self.ledger_changes.iter() {
+                write!(f, "{}", change)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Balance of an address before and after a simulated operation
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct BalanceChange {
+    /// address
+    pub address: Address,
+    /// balance before the operation, `None` if the address did not exist
+    pub balance_before: Option<Amount>,
+    /// balance after the operation, `None` if the address does not exist anymore
+    pub balance_after: Option<Amount>,
+}
+
+impl Display for BalanceChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}: {} -> {}",
+            self.address,
+            self.balance_before
+                .map_or_else(|| "none".to_string(), |balance| balance.to_string()),
+            self.balance_after
+                .map_or_else(|| "none".to_string(), |balance| balance.to_string())
+        )
+    }
+}
+
+/// The result of the simulation of an operation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct OperationSimulation {
+    /// The ID of the simulated operation.
+    pub operation_id: OperationId,
+    /// The slot at which the operation was simulated.
+    pub executed_at: Slot,
+    /// The result of the simulation.
+    pub result: ReadOnlyResult,
+    /// The fees that the operation would spend, zero if it cannot be included.
+    pub fee: Amount,
+    /// The balances changed by the operation.
+    pub balance_changes: Vec<BalanceChange>,
+    /// The output events that the operation would emit.
+    pub output_events: VecDeque<SCOutputEvent>,
+}
+
+impl Display for OperationSimulation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Operation {}", self.operation_id)?;
+        writeln!(f, "Executed at slot: {}", self.executed_at)?;
+        writeln!(
+            f,
+            "Result: {}",
+            match &self.result {
+                ReadOnlyResult::Error(e) =>
+                    format!("an error occurred during the execution: {}", e),
+                ReadOnlyResult::Ok => "ok".to_string(),
+            }
+        )?;
+        writeln!(f, "Fee: {}", self.fee)?;
+        if !self.balance_changes.is_empty() {
+            writeln!(f, "Balance changes:")?;
+            for change in self.balance_changes.iter() {
+                write!(f, "\t{}", change)?;
+            }
+        }
+        if !self.output_events.is_empty() {
+            writeln!(f, "Generated events:",)?;
+            for event in self.output_events.iter() {
+                writeln!(f, "{}", event)?; // id already displayed in event
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An action recorded in a frame of an execution trace.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum TraceAction {
+    /// A nested call, with its own frame.
+    Call(TraceFrame),
+    /// A datastore entry was read.
+    DatastoreRead {
+        /// The address owning the datastore.
+        address: Address,
+        /// The key of the entry.
+        key: Vec<u8>,
+        /// The size of the read value, `None` if the entry was absent.
+        value_size: Option<usize>,
+    },
+    /// A datastore entry was set, or appended to if `append` is true.
+    DatastoreWrite {
+        /// The address owning the datastore.
+        address: Address,
+        /// The key of the entry.
+        key: Vec<u8>,
+        /// The size of the written value.
+        value_size: usize,
+        /// Whether the value was appended to the existing one.
+        append: bool,
+    },
+    /// A datastore entry was deleted.
+    DatastoreDelete {
+        /// The address owning the datastore.
+        address: Address,
+        /// The key of the entry.
+        key: Vec<u8>,
+    },
+    /// Coins were transferred between two addresses.
+    Transfer {
+        /// The address sending the coins.
+        from: Address,
+        /// The address receiving the coins.
+        to: Address,
+        /// The amount of transferred coins.
+        amount: Amount,
+    },
+    /// An asynchronous message was sent.
+    SendMessage {
+        /// The address of the message target.
+        destination: Address,
+        /// The function handling the message.
+        handler: String,
+        /// The max gas of the message execution.
+        max_gas: u64,
+        /// The coins given by the sender.
+        coins: Amount,
+    },
+    /// An event was emitted.
+    Event {
+        /// The payload of the event.
+        data: String,
+    },
+}
+
+impl TraceAction {
+    fn fmt_indented(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        let indent = "\t".repeat(depth);
+        match self {
+            TraceAction::Call(frame) => frame.fmt_indented(f, depth),
+            TraceAction::DatastoreRead {
+                address,
+                key,
+                value_size,
+            } => writeln!(
+                f,
+                "{}read {}[{:?}]: {}",
+                indent,
+                address,
+                key,
+                value_size.map_or_else(|| "absent".to_string(), |size| format!("{} bytes", size))
+            ),
+            TraceAction::DatastoreWrite {
+                address,
+                key,
+                value_size,
+                append,
+            } => writeln!(
+                f,
+                "{}{} {}[{:?}]: {} bytes",
+                indent,
+                if *append { "append" } else { "write" },
+                address,
+                key,
+                value_size
+            ),
+            TraceAction::DatastoreDelete { address, key } => {
+                writeln!(f, "{}delete {}[{:?}]", indent, address, key)
+            }
+            TraceAction::Transfer { from, to, amount } => {
+                writeln!(f, "{}transfer {} from {} to {}", indent, amount, from, to)
+            }
+            TraceAction::SendMessage {
+                destination,
+                handler,
+                max_gas,
+                coins,
+            } => writeln!(
+                f,
+                "{}message to {}::{} with {} coins and {} max gas",
+                indent, destination, handler, coins, max_gas
+            ),
+            TraceAction::Event { data } => writeln!(f, "{}event: {}", indent, data),
+        }
+    }
+}
+
+/// A call frame of an execution trace.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TraceFrame {
+    /// The address that was called.
+    pub address: Address,
+    /// The coins transferred to the address by the call.
+    pub coins: Amount,
+    /// The gas spent by the call.
+    /// Only known for the root frame and the frame of the function run by the operation,
+    /// `None` for the nested calls made by the bytecode as the runtime does not report their gas.
+    pub gas_cost: Option<u64>,
+    /// The actions of the call, in execution order.
+    pub actions: Vec<TraceAction>,
+}
+
+impl TraceFrame {
+    fn fmt_indented(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        write!(
+            f,
+            "{}call {} with {} coins",
+            "\t".repeat(depth),
+            self.address,
+            self.coins
+        )?;
+        match self.gas_cost {
+            Some(gas_cost) => writeln!(f, ", {} gas", gas_cost)?,
+            None => writeln!(f)?,
+        }
+        for action in self.actions.iter() {
+            action.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// The call tree recorded during the execution of an operation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ExecutionTrace {
+    /// The ID of the traced operation.
+    pub operation_id: OperationId,
+    /// The slot at which the operation was executed.
+    pub executed_at: Slot,
+    /// The block in which the operation was executed.
+    pub block_id: Option<BlockId>,
+    /// The error that made the execution fail, if any.
+    /// The effects of the recorded actions were reverted in that case.
+    pub error: Option<String>,
+    /// The root frame of the call tree, called by the operation sender.
+    pub root: TraceFrame,
+}
+
+impl Display for ExecutionTrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Operation {}", self.operation_id)?;
+        writeln!(f, "Executed at slot: {}", self.executed_at)?;
+        if let Some(block_id) = self.block_id {
+            writeln!(f, "In block: {}", block_id)?;
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "Failed: {}", error)?;
+        }
+        self.root.fmt_indented(f, 0)
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use crate::error::ModelsError;
+use serde::de::Unexpected;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::{fmt, str::FromStr};
+
+/// Range of IP addresses in CIDR notation, like `192.168.0.0/16` or `2001:db8::/32`.
+/// A single IP address is a range with the full prefix length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpRange {
+    /// first address of the range, the bits after the prefix are zeroed
+    network: IpAddr,
+    /// number of leading bits shared by all the addresses of the range
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// Creates the range of the addresses sharing the first `prefix_len` bits of `ip`
+    pub fn new(ip: IpAddr, prefix_len: u8) -> Result<Self, ModelsError> {
+        let network = match ip {
+            IpAddr::V4(ip) if prefix_len <= 32 => {
+                IpAddr::V4(Ipv4Addr::from(u32::from(ip) & v4_mask(prefix_len)))
+            }
+            IpAddr::V6(ip) if prefix_len <= 128 => {
+                IpAddr::V6(Ipv6Addr::from(u128::from(ip) & v6_mask(prefix_len)))
+            }
+            _ => {
+                return Err(ModelsError::InvalidIpRangeError(format!(
+                    "prefix length {} is too long for {}",
+                    prefix_len, ip
+                )))
+            }
+        };
+        Ok(IpRange {
+            network,
+            prefix_len,
+        })
+    }
+
+    /// Whether `ip` belongs to the range.
+    /// IPv4 addresses and their IPv4-mapped IPv6 form are considered equal.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.network, *ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                u32::from(ip) & v4_mask(self.prefix_len) == u32::from(network)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                u128::from(ip) & v6_mask(self.prefix_len) == u128::from(network)
+            }
+            (IpAddr::V4(_), IpAddr::V6(ip)) => ip
+                .to_ipv4_mapped()
+                .map_or(false, |ip| self.contains(&IpAddr::V4(ip))),
+            (IpAddr::V6(_), IpAddr::V4(ip)) => self.contains(&IpAddr::V6(ip.to_ipv6_mapped())),
+        }
+    }
+}
+
+fn v4_mask(prefix_len: u8) -> u32 {
+    u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0)
+}
+
+fn v6_mask(prefix_len: u8) -> u128 {
+    u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0)
+}
+
+impl From<IpAddr> for IpRange {
+    fn from(ip: IpAddr) -> Self {
+        let prefix_len = match ip {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        IpRange {
+            network: ip,
+            prefix_len,
+        }
+    }
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = ModelsError;
+
+    /// ## Example
+    /// ```rust
+    /// # use massa_models::ip_range::IpRange;
+    /// # use std::str::FromStr;
+    /// let range = IpRange::from_str("192.168.1.0/24").unwrap();
+    /// assert!(range.contains(&"192.168.1.42".parse().unwrap()));
+    /// assert!(!range.contains(&"192.168.2.1".parse().unwrap()));
+    /// assert_eq!(IpRange::from_str("10.0.0.1").unwrap().to_string(), "10.0.0.1/32");
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((ip, prefix_len)) => {
+                let ip = IpAddr::from_str(ip)
+                    .map_err(|_| ModelsError::InvalidIpRangeError(s.to_string()))?;
+                let prefix_len = u8::from_str(prefix_len)
+                    .map_err(|_| ModelsError::InvalidIpRangeError(s.to_string()))?;
+                IpRange::new(ip, prefix_len)
+            }
+            None => IpAddr::from_str(s)
+                .map(IpRange::from)
+                .map_err(|_| ModelsError::InvalidIpRangeError(s.to_string())),
+        }
+    }
+}
+
+struct IpRangeVisitor;
+
+impl<'de> serde::de::Visitor<'de> for IpRangeVisitor {
+    type Value = IpRange;
+
+    fn visit_str<E>(self, value: &str) -> Result<IpRange, E>
+    where
+        E: serde::de::Error,
+    {
+        IpRange::from_str(value).map_err(|_| E::invalid_value(Unexpected::Str(value), &self))
+    }
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "an IP address or an IP range in CIDR notation, like 192.168.0.0/16"
+        )
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for IpRange {
+    fn deserialize<D>(deserializer: D) -> Result<IpRange, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        deserializer.deserialize_str(IpRangeVisitor)
+    }
+}
+
+impl serde::Serialize for IpRange {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Lists of IP ranges that are allowed or denied access to a service
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpAccessLists {
+    /// if not empty, only the IPs in one of these ranges are allowed
+    pub allow_list: Vec<IpRange>,
+    /// the IPs in one of these ranges are denied, even if they are in the allow list
+    pub deny_list: Vec<IpRange>,
+}
+
+impl IpAccessLists {
+    /// Whether `ip` is allowed access
+    pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+        (self.allow_list.is_empty() || self.allow_list.iter().any(|range| range.contains(ip)))
+            && !self.deny_list.iter().any(|range| range.contains(ip))
+    }
+
+    /// Adds ranges to the allow list, ignoring the ones already in it
+    pub fn allow(&mut self, ranges: Vec<IpRange>) {
+        add_ranges(&mut self.allow_list, ranges);
+    }
+
+    /// Removes ranges from the allow list
+    pub fn remove_from_allow_list(&mut self, ranges: &[IpRange]) {
+        self.allow_list.retain(|range| !ranges.contains(range));
+    }
+
+    /// Adds ranges to the deny list, ignoring the ones already in it
+    pub fn deny(&mut self, ranges: Vec<IpRange>) {
+        add_ranges(&mut self.deny_list, ranges);
+    }
+
+    /// Removes ranges from the deny list
+    pub fn remove_from_deny_list(&mut self, ranges: &[IpRange]) {
+        self.deny_list.retain(|range| !ranges.contains(range));
+    }
+}
+
+impl fmt::Display for IpAccessLists {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.allow_list.is_empty() {
+            writeln!(f, "Allow list: empty, all IPs are allowed")?;
+        } else {
+            writeln!(f, "Allow list:")?;
+            for range in &self.allow_list {
+                writeln!(f, "\t{}", range)?;
+            }
+        }
+        writeln!(f, "Deny list:")?;
+        for range in &self.deny_list {
+            writeln!(f, "\t{}", range)?;
+        }
+        Ok(())
+    }
+}
+
+fn add_ranges(list: &mut Vec<IpRange>, ranges: Vec<IpRange>) {
+    for range in ranges {
+        if !list.contains(&range) {
+            list.push(range);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_range() {
+        let range = IpRange::from_str("10.1.2.3/16").unwrap();
+        assert_eq!(range.to_string(), "10.1.0.0/16");
+        assert!(range.contains(&"10.1.255.1".parse().unwrap()));
+        assert!(range.contains(&"::ffff:10.1.0.1".parse().unwrap()));
+        assert!(!range.contains(&"10.2.0.1".parse().unwrap()));
+        assert!(!range.contains(&"2001:db8::1".parse().unwrap()));
+
+        let range = IpRange::from_str("2001:db8::/32").unwrap();
+        assert!(range.contains(&"2001:db8:1::1".parse().unwrap()));
+        assert!(!range.contains(&"2001:db9::1".parse().unwrap()));
+
+        let range = IpRange::from_str("::ffff:10.0.0.1").unwrap();
+        assert!(range.contains(&"10.0.0.1".parse().unwrap()));
+
+        let everything = IpRange::from_str("0.0.0.0/0").unwrap();
+        assert!(everything.contains(&"1.2.3.4".parse().unwrap()));
+
+        assert!(IpRange::from_str("10.0.0.0/33").is_err());
+        assert!(IpRange::from_str("10.0.0/8").is_err());
+        assert!(IpRange::from_str("2001:db8::/129").is_err());
+    }
+
+    #[test]
+    fn test_ip_access_lists() {
+        let mut lists = IpAccessLists::default();
+        let ip = "192.168.1.10".parse().unwrap();
+        assert!(lists.is_allowed(&ip));
+
+        lists.allow(vec![IpRange::from_str("192.168.0.0/16").unwrap()]);
+        assert!(lists.is_allowed(&ip));
+        assert!(!lists.is_allowed(&"10.0.0.1".parse().unwrap()));
+
+        // the deny list takes precedence
+        lists.deny(vec![IpRange::from_str("192.168.1.0/24").unwrap()]);
+        assert!(!lists.is_allowed(&ip));
+        assert!(lists.is_allowed(&"192.168.2.10".parse().unwrap()));
+
+        lists.remove_from_deny_list(&[IpRange::from_str("192.168.1.0/24").unwrap()]);
+        assert!(lists.is_allowed(&ip));
+        lists.remove_from_allow_list(&[IpRange::from_str("192.168.0.0/16").unwrap()]);
+        assert!(lists.allow_list.is_empty());
+        assert!(lists.is_allowed(&"10.0.0.1".parse().unwrap()));
+    }
+}
@@ -495,3 +495,14 @@ impl LedgerChanges {
         )
     }
 }
+
+/// Sub-entry of a ledger entry
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum LedgerSubEntry {
+    /// Balance
+    Balance,
+    /// Bytecode
+    Bytecode,
+    /// Datastore entry
+    Datastore(Vec<u8>),
+}
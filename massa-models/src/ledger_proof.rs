@@ -0,0 +1,154 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! The final ledger is committed to by the root of a sparse Merkle tree.
+//!
+//! Each ledger key is placed in the tree at the 256-bit path given by its hash.
+//! A subtree containing a single leaf is represented by that leaf, and an empty subtree by `MERKLE_EMPTY_HASH_BYTES`,
+//! so that the tree only contains `O(N)` nodes and proofs are `O(log(N))` long.
+//! This module provides the hashing rules of the tree and the verification of the proofs it produces.
+
+use crate::address::Address;
+use crate::ledger_models::LedgerSubEntry;
+use crate::slot::Slot;
+use massa_hash::{Hash, HASH_SIZE_BYTES};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Hash of an empty subtree, which is also the ledger hash of an empty ledger
+pub const MERKLE_EMPTY_HASH_BYTES: [u8; HASH_SIZE_BYTES] = [0; HASH_SIZE_BYTES];
+
+/// Prefix of the hashed data of a leaf
+const LEAF_PREFIX: u8 = 0;
+/// Prefix of the hashed data of an internal node
+const NODE_PREFIX: u8 = 1;
+
+/// Returns the path of a ledger key in the tree
+pub fn merkle_path(ledger_key: &[u8]) -> Hash {
+    Hash::compute_from(ledger_key)
+}
+
+/// Returns the bit of `path` at `depth`: `false` goes left and `true` goes right
+pub fn merkle_path_bit(path: &Hash, depth: usize) -> bool {
+    (path.to_bytes()[depth / 8] >> (7 - depth % 8)) & 1 == 1
+}
+
+/// Hash of a leaf of the tree
+///
+/// # Arguments
+/// * `path`: path of the leaf ledger key
+/// * `value_hash`: hash of the leaf ledger value
+pub fn merkle_leaf_hash(path: &Hash, value_hash: &Hash) -> Hash {
+    Hash::compute_from(
+        &[
+            &[LEAF_PREFIX],
+            &path.to_bytes()[..],
+            &value_hash.to_bytes()[..],
+        ]
+        .concat(),
+    )
+}
+
+/// Hash of an internal node of the tree from the hashes of its children
+pub fn merkle_node_hash(left: &Hash, right: &Hash) -> Hash {
+    Hash::compute_from(&[&[NODE_PREFIX], &left.to_bytes()[..], &right.to_bytes()[..]].concat())
+}
+
+/// Leaf of the tree, identified by the path of its ledger key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct MerkleLeaf {
+    /// path of the ledger key
+    pub path: Hash,
+    /// hash of the ledger value
+    pub value_hash: Hash,
+}
+
+/// Inclusion or exclusion proof of a ledger key against the ledger hash
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LedgerProof {
+    /// hashes of the siblings of the nodes on the path of the key, from the root down
+    pub siblings: Vec<Hash>,
+    /// for an exclusion proof, the leaf of another key found at the end of the path, if any
+    pub other_leaf: Option<MerkleLeaf>,
+}
+
+impl LedgerProof {
+    /// Checks the proof against a ledger hash
+    ///
+    /// # Arguments
+    /// * `root`: ledger hash
+    /// * `ledger_key`: proven ledger key
+    /// * `value`: value of the key, or `None` to check that the key is absent
+    pub fn verify(&self, root: &Hash, ledger_key: &[u8], value: Option<&[u8]>) -> bool {
+        let path = merkle_path(ledger_key);
+        let mut hash = match (value, &self.other_leaf) {
+            (Some(value), None) => merkle_leaf_hash(&path, &Hash::compute_from(value)),
+            (Some(_), Some(_)) => return false,
+            // the other leaf must be the only one in the subtree reached by the path of the key
+            (None, Some(leaf)) => {
+                if leaf.path == path
+                    || (0..self.siblings.len()).any(|depth| {
+                        merkle_path_bit(&leaf.path, depth) != merkle_path_bit(&path, depth)
+                    })
+                {
+                    return false;
+                }
+                merkle_leaf_hash(&leaf.path, &leaf.value_hash)
+            }
+            (None, None) => Hash::from_bytes(&MERKLE_EMPTY_HASH_BYTES),
+        };
+        for (depth, sibling) in self.siblings.iter().enumerate().rev() {
+            hash = if merkle_path_bit(&path, depth) {
+                merkle_node_hash(sibling, &hash)
+            } else {
+                merkle_node_hash(&hash, sibling)
+            };
+        }
+        hash == *root
+    }
+}
+
+/// Value of a final ledger sub-entry along with its proof against the final ledger hash
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LedgerEntryProof {
+    /// address of the ledger entry
+    pub address: Address,
+    /// proven sub-entry
+    pub sub_entry: LedgerSubEntry,
+    /// key of the sub-entry in the ledger
+    pub ledger_key: Vec<u8>,
+    /// value of the sub-entry, `None` if it does not exist
+    pub value: Option<Vec<u8>>,
+    /// final slot of the ledger
+    pub slot: Slot,
+    /// final ledger hash at `slot`
+    pub ledger_hash: Hash,
+    /// proof of `value` against `ledger_hash`
+    pub proof: LedgerProof,
+}
+
+impl LedgerEntryProof {
+    /// Checks the proof against the ledger hash it was produced for
+    pub fn verify(&self) -> bool {
+        self.proof
+            .verify(&self.ledger_hash, &self.ledger_key, self.value.as_deref())
+    }
+}
+
+impl Display for LedgerEntryProof {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Address: {}", self.address)?;
+        writeln!(f, "Sub-entry: {:?}", self.sub_entry)?;
+        match &self.value {
+            Some(value) => writeln!(f, "Value: {:?}", value)?,
+            None => writeln!(f, "Value: absent")?,
+        }
+        writeln!(f, "Final slot: {}", self.slot)?;
+        writeln!(f, "Ledger hash: {}", self.ledger_hash)?;
+        writeln!(
+            f,
+            "Proof: {} siblings, {}",
+            self.proof.siblings.len(),
+            if self.verify() { "valid" } else { "INVALID" }
+        )
+    }
+}
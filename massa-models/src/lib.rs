@@ -33,8 +33,12 @@ pub mod endorsement;
 pub mod error;
 /// execution related structures
 pub mod execution;
+/// IP ranges and access lists
+pub mod ip_range;
 /// ledger related structures
 pub mod ledger_models;
+/// ledger Merkle commitment and proofs
+pub mod ledger_proof;
 /// node related structure
 pub mod node;
 /// operations
@@ -1,6 +1,8 @@
-use crate::{address::Address, block::BlockId, operation::OperationId, slot::Slot};
+use crate::{
+    address::Address, block::BlockId, error::ModelsError, operation::OperationId, slot::Slot,
+};
 use serde::{Deserialize, Serialize};
-use std::{collections::VecDeque, fmt::Display};
+use std::{collections::VecDeque, fmt::Display, str::FromStr};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// By product of a byte code execution
@@ -11,6 +13,16 @@ pub struct SCOutputEvent {
     pub data: String,
 }
 
+impl SCOutputEvent {
+    /// Position of the event in the event history
+    pub fn cursor(&self) -> EventCursor {
+        EventCursor {
+            slot: self.context.slot,
+            index_in_slot: self.context.index_in_slot,
+        }
+    }
+}
+
 impl Display for SCOutputEvent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "Context: {}", self.context)?;
@@ -18,6 +30,47 @@ impl Display for SCOutputEvent {
     }
 }
 
+/// Position of an event in the event history, used to page through events.
+/// Events are ordered by slot, then by index in the slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct EventCursor {
+    /// slot at which the event was emitted
+    pub slot: Slot,
+    /// index of the event in the slot
+    pub index_in_slot: u64,
+}
+
+impl Display for EventCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}_{}_{}",
+            self.slot.period, self.slot.thread, self.index_in_slot
+        )
+    }
+}
+
+impl FromStr for EventCursor {
+    type Err = ModelsError;
+
+    /// Parses a cursor formatted as `period_thread_index`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ModelsError::DeserializeError(format!("invalid event cursor: {}", s));
+        let mut parts = s.split('_');
+        let mut next = || parts.next().ok_or_else(err);
+        let period = next()?.parse::<u64>().map_err(|_| err())?;
+        let thread = next()?.parse::<u8>().map_err(|_| err())?;
+        let index_in_slot = next()?.parse::<u64>().map_err(|_| err())?;
+        if parts.next().is_some() {
+            return Err(err());
+        }
+        Ok(EventCursor {
+            slot: Slot::new(period, thread),
+            index_in_slot,
+        })
+    }
+}
+
 /// Context of the event (not generated by the user)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventExecutionContext {
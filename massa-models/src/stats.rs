@@ -3,6 +3,7 @@
 use crate::slot::Slot;
 use massa_time::MassaTime;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt::Formatter;
 
 /// execution statistics
@@ -61,6 +62,23 @@ pub struct NetworkStats {
     pub banned_peer_count: u64,
     /// active node count
     pub active_node_count: u64,
+    /// traffic with the active nodes since startup, by message type
+    pub traffic: BTreeMap<String, MessageTrafficStats>,
+}
+
+/// traffic counters of a network message type
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MessageTrafficStats {
+    /// bytes received
+    pub bytes_in: u64,
+    /// bytes sent
+    pub bytes_out: u64,
+    /// messages received
+    pub messages_in: u64,
+    /// messages sent
+    pub messages_out: u64,
+    /// received messages dropped for exceeding rate limits
+    pub messages_dropped: u64,
 }
 
 impl std::fmt::Display for NetworkStats {
@@ -71,6 +89,19 @@ impl std::fmt::Display for NetworkStats {
         writeln!(f, "\tKnown peers: {}", self.known_peer_count)?;
         writeln!(f, "\tBanned peers: {}", self.banned_peer_count)?;
         writeln!(f, "\tActive nodes: {}", self.active_node_count)?;
+        writeln!(f, "\tTraffic:")?;
+        for (message_type, traffic) in &self.traffic {
+            writeln!(
+                f,
+                "\t\t{}: in {} messages / {} bytes ({} dropped), out {} messages / {} bytes",
+                message_type,
+                traffic.messages_in,
+                traffic.bytes_in,
+                traffic.messages_dropped,
+                traffic.messages_out,
+                traffic.bytes_out
+            )?;
+        }
         Ok(())
     }
 }
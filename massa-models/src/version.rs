@@ -194,6 +194,11 @@ impl Version {
             && self.minor >= 1
             && other.minor >= 1
     }
+
+    /// true if the version is at least `major.minor`, regardless of the instance
+    pub fn is_at_least(&self, major: u32, minor: u32) -> bool {
+        (self.major, self.minor) >= (major, minor)
+    }
 }
 
 impl fmt::Display for Version {
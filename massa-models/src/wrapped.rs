@@ -55,16 +55,40 @@ where
         content_serializer: SC,
         keypair: &KeyPair,
     ) -> Result<Wrapped<Self, U>, ModelsError> {
+        Self::new_wrapped_with_signer(
+            content,
+            content_serializer,
+            keypair.get_public_key(),
+            |hash, _content| Ok(keypair.sign(hash)?),
+        )
+    }
+
+    /// Creates a wrapped version of the object,
+    /// signed by `sign` on behalf of the owner of `public_key`, for keys that are not held locally.
+    /// `sign` gets the hash to sign and the serialized content it is computed from.
+    fn new_wrapped_with_signer<SC, U, E, F>(
+        content: Self,
+        content_serializer: SC,
+        public_key: PublicKey,
+        sign: F,
+    ) -> Result<Wrapped<Self, U>, E>
+    where
+        SC: Serializer<Self>,
+        U: Id,
+        E: From<ModelsError>,
+        F: FnOnce(&Hash, &[u8]) -> Result<Signature, E>,
+    {
         let mut content_serialized = Vec::new();
-        content_serializer.serialize(&content, &mut content_serialized)?;
+        content_serializer
+            .serialize(&content, &mut content_serialized)
+            .map_err(ModelsError::from)?;
         let mut hash_data = Vec::new();
-        let public_key = keypair.get_public_key();
         hash_data.extend(public_key.to_bytes());
         hash_data.extend(content_serialized.clone());
         let hash = Hash::compute_from(&hash_data);
         let creator_address = Address::from_public_key(&public_key);
         Ok(Wrapped {
-            signature: keypair.sign(&hash)?,
+            signature: sign(&hash, &content_serialized)?,
             creator_public_key: public_key,
             creator_address,
             content,
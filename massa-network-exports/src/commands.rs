@@ -69,13 +69,13 @@
 //! Look at `massa-protocol-worker/src/node-info.rs` to look further how we
 //! remember which node know what.
 
-use crate::{BootstrapPeers, ConnectionClosureReason, Peers};
+use crate::{BootstrapPeers, ConnectionClosureReason, PeerBehavior, Peers, SignedPeerRecord};
 use massa_models::{
     block::{BlockId, WrappedHeader},
     composite::PubkeySig,
     endorsement::WrappedEndorsement,
     node::NodeId,
-    operation::{OperationId, OperationPrefixIds, WrappedOperation},
+    operation::{OperationId, OperationPrefixId, OperationPrefixIds, WrappedOperation},
     stats::NetworkStats,
 };
 use serde::{Deserialize, Serialize};
@@ -88,6 +88,8 @@ use tokio::sync::oneshot;
 pub enum NodeCommand {
     /// Send given peer list to node.
     SendPeerList(Vec<IpAddr>),
+    /// Send given signed peer records to node.
+    SendPeerRecords(Vec<SignedPeerRecord>),
     /// Send the header of a block to a node.
     SendBlockHeader(WrappedHeader),
     /// Ask for info on a list of blocks.
@@ -116,6 +118,10 @@ pub enum NodeEventType {
     AskedPeerList,
     /// Node we are connected to sent peer list
     ReceivedPeerList(Vec<IpAddr>),
+    /// Node we are connected to asked for signed peer records
+    AskedPeerRecords,
+    /// Node we are connected to sent signed peer records, whose signatures are not checked yet
+    ReceivedPeerRecords(Vec<SignedPeerRecord>),
     /// Node we are connected to sent block header
     ReceivedBlockHeader(WrappedHeader),
     /// Node we are connected asked for info on a list of blocks.
@@ -130,6 +136,8 @@ pub enum NodeEventType {
     ReceivedAskForOperations(OperationPrefixIds),
     /// Receive a set of endorsement
     ReceivedEndorsements(Vec<WrappedEndorsement>),
+    /// Node we are connected to exceeded our rate limits, its messages are dropped
+    RateLimitExceeded,
 }
 
 /// Events node worker can emit.
@@ -147,6 +155,10 @@ pub enum AskForBlocksInfo {
     Info,
     /// The actual operations are required.
     Operations(Vec<OperationId>),
+    /// The ordered prefixes of the operations of the block are required.
+    CompactInfo,
+    /// The operations of the block whose id starts with one of the given prefixes are required.
+    OperationsByPrefix(Vec<OperationPrefixId>),
 }
 
 /// Commands that the worker can execute
@@ -184,6 +196,8 @@ pub enum NetworkCommand {
     NodeUnbanByIds(Vec<NodeId>),
     /// Unban a list of peer by their ip address
     NodeUnbanByIps(Vec<IpAddr>),
+    /// Update the reputation scores of peers according to the behaviors of their nodes
+    NodeBehaviors(Vec<(NodeId, PeerBehavior)>),
     /// Send endorsements to a node
     SendEndorsements {
         /// to node id
@@ -241,6 +255,8 @@ pub enum BlockInfoReply {
     Info(Vec<OperationId>),
     /// The actual operations required.
     Operations(Vec<WrappedOperation>),
+    /// The ordered prefixes of the operations of the block.
+    CompactInfo(Vec<OperationPrefixId>),
     /// Block not found
     NotFound,
 }
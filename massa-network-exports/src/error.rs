@@ -72,6 +72,12 @@ pub enum HandshakeErrorType {
     HandshakeKey,
     /// Invalid signature
     HandshakeInvalidSignature,
+    /// Ephemeral key that does not contribute to the shared secret
+    HandshakeInvalidEphemeralKey,
+    /// Handshake tampered with to disable encryption
+    HandshakeDowngrade,
+    /// Peer that does not encrypt its connection while we require it
+    EncryptionRequired,
     /// Incompatible version
     IncompatibleVersion,
     /// Outgoing connection returned a bootstrapable peer list: {0:?}
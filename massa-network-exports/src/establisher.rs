@@ -15,7 +15,7 @@ mod types {
 #[cfg(not(feature = "testing"))]
 mod types {
     use massa_time::MassaTime;
-    use std::{io, net::SocketAddr};
+    use std::{io, net::SocketAddr, task::Poll};
     use tokio::{
         net::{TcpListener, TcpStream},
         time::timeout,
@@ -30,15 +30,32 @@ mod types {
     /// Connection establisher
     pub type Establisher = DefaultEstablisher;
 
-    /// The listener we are using
+    /// The listener we are using, accepting connections on one or several addresses
     #[derive(Debug)]
-    pub struct DefaultListener(TcpListener);
+    pub struct DefaultListener {
+        listeners: Vec<TcpListener>,
+        /// index of the first listener polled on the next accept, so that no address starves the others
+        next: usize,
+    }
 
     impl DefaultListener {
         /// Accepts a new incoming connection from this listener.
         pub async fn accept(&mut self) -> io::Result<(ReadHalf, WriteHalf, SocketAddr)> {
-            // accept
-            let (sock, mut remote_addr) = self.0.accept().await?;
+            // accept on whichever address is ready first
+            let count = self.listeners.len();
+            let start = self.next;
+            let (index, res) = std::future::poll_fn(|cx| {
+                for offset in 0..count {
+                    let index = (start + offset) % count;
+                    if let Poll::Ready(res) = self.listeners[index].poll_accept(cx) {
+                        return Poll::Ready((index, res));
+                    }
+                }
+                Poll::Pending
+            })
+            .await;
+            self.next = (index + 1) % count;
+            let (sock, mut remote_addr) = res?;
             // normalize IP
             remote_addr.set_ip(remote_addr.ip().to_canonical());
             // split into read half and write half
@@ -81,9 +98,19 @@ mod types {
         /// Gets the associated listener
         ///
         /// # Argument
-        /// * `addr`: `SocketAddr` we want to bind to.
-        pub async fn get_listener(&mut self, addr: SocketAddr) -> io::Result<DefaultListener> {
-            Ok(DefaultListener(TcpListener::bind(addr).await?))
+        /// * `addrs`: the `SocketAddr`s we want to bind to, at least one.
+        pub async fn get_listener(&mut self, addrs: &[SocketAddr]) -> io::Result<DefaultListener> {
+            if addrs.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "no address to listen on",
+                ));
+            }
+            let mut listeners = Vec::with_capacity(addrs.len());
+            for addr in addrs {
+                listeners.push(TcpListener::bind(addr).await?);
+            }
+            Ok(DefaultListener { listeners, next: 0 })
         }
 
         /// Get the connector with associated timeout
@@ -15,9 +15,10 @@ pub use establisher::{Establisher, Listener, ReadHalf, WriteHalf};
 pub use network_controller::{NetworkCommandSender, NetworkEventReceiver, NetworkManager};
 pub use peers::{
     BootstrapPeers, BootstrapPeersDeserializer, BootstrapPeersSerializer, ConnectionCount, Peer,
-    PeerInfo, PeerType, Peers,
+    PeerBehavior, PeerInfo, PeerRecord, PeerRecordDeserializer, PeerRecordSerializer, PeerType,
+    Peers, SignedPeerRecord, SignedPeerRecordDeserializer, SignedPeerRecordSerializer,
 };
-pub use settings::NetworkConfig;
+pub use settings::{NetworkConfig, RateLimit};
 
 mod commands;
 mod common;
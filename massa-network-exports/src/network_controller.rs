@@ -3,7 +3,7 @@
 use crate::{
     commands::{AskForBlocksInfo, NetworkManagementCommand},
     error::NetworkError,
-    BlockInfoReply, BootstrapPeers, NetworkCommand, NetworkEvent, Peers,
+    BlockInfoReply, BootstrapPeers, NetworkCommand, NetworkEvent, PeerBehavior, Peers,
 };
 use massa_models::{
     block::{BlockId, WrappedHeader},
@@ -87,6 +87,26 @@ impl NetworkCommandSender {
         Ok(())
     }
 
+    /// Report behaviors of nodes affecting the reputation scores of their peers.
+    /// The report is dropped with a warning if the command channel is full.
+    pub fn report_node_behaviors(
+        &self,
+        behaviors: Vec<(NodeId, PeerBehavior)>,
+    ) -> Result<(), NetworkError> {
+        match self.0.try_send(NetworkCommand::NodeBehaviors(behaviors)) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                warn!("Failed to send NetworkCommand NodeBehaviors channel full");
+            }
+            Err(TrySendError::Closed(_)) => {
+                return Err(NetworkError::ChannelError(
+                    "could not send NodeBehaviors command".into(),
+                ));
+            }
+        };
+        Ok(())
+    }
+
     /// Send info about the contents of a block.
     pub async fn send_block_info(
         &self,
@@ -1,18 +1,27 @@
+use crate::error::NetworkError;
 use crate::settings::PeerTypeConnectionConfig;
 use displaydoc::Display;
 use enum_map::Enum;
+use massa_hash::Hash;
 use massa_models::node::NodeId;
 use massa_models::serialization::{IpAddrDeserializer, IpAddrSerializer};
+use massa_models::version::{Version, VersionDeserializer, VersionSerializer};
 use massa_serialization::{
-    Deserializer, SerializeError, Serializer, U32VarIntDeserializer, U32VarIntSerializer,
+    Deserializer, SerializeError, Serializer, U16VarIntDeserializer, U16VarIntSerializer,
+    U32VarIntDeserializer, U32VarIntSerializer,
 };
-use massa_time::MassaTime;
-use nom::error::{ContextError, ParseError};
+use massa_signature::{KeyPair, PublicKeyDeserializer, Signature, SignatureDeserializer};
+use massa_time::{MassaTime, MassaTimeDeserializer, MassaTimeSerializer};
+use nom::error::{context, ContextError, ParseError};
 use nom::multi::length_count;
+use nom::sequence::tuple;
 use nom::{IResult, Parser};
 use serde::{Deserialize, Serialize};
 use std::ops::Bound::Included;
-use std::{collections::HashMap, net::IpAddr};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+};
 /// Associate a peer info with nodes
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Peer {
@@ -141,6 +150,267 @@ impl Deserializer<BootstrapPeers> for BootstrapPeersDeserializer {
     }
 }
 
+/// Address of a node, as advertised by the node itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerRecord {
+    /// Id of the node, whose keypair signs the record
+    pub node_id: NodeId,
+    /// Address the node listens on
+    pub addr: SocketAddr,
+    /// Time in milliseconds at which the node signed the record
+    pub last_seen: MassaTime,
+    /// Version of the node
+    pub version: Version,
+}
+
+impl PeerRecord {
+    /// Hash of the serialized record, which is what its node signs
+    fn compute_hash(&self) -> Result<Hash, NetworkError> {
+        let mut buffer = Vec::new();
+        PeerRecordSerializer::new().serialize(self, &mut buffer)?;
+        Ok(Hash::compute_from(&buffer))
+    }
+
+    /// Whether the record was signed less than `max_age` away from `now`,
+    /// in either direction to tolerate clock differences between nodes
+    pub fn is_fresh(&self, max_age: MassaTime, now: MassaTime) -> bool {
+        now.saturating_sub(self.last_seen) <= max_age
+            && self.last_seen.saturating_sub(now) <= max_age
+    }
+}
+
+/// `PeerRecord` signed with the keypair of its node,
+/// which proves that the node advertised that address itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedPeerRecord {
+    /// the record
+    pub record: PeerRecord,
+    /// signature of the hash of the record with the keypair of `record.node_id`
+    pub signature: Signature,
+}
+
+impl SignedPeerRecord {
+    /// Signs `record` with `keypair`, which has to be the keypair of `record.node_id`
+    pub fn new_signed(record: PeerRecord, keypair: &KeyPair) -> Result<Self, NetworkError> {
+        let signature = keypair.sign(&record.compute_hash()?)?;
+        Ok(SignedPeerRecord { record, signature })
+    }
+
+    /// Checks that the record was signed by its node
+    pub fn verify_signature(&self) -> Result<(), NetworkError> {
+        self.record
+            .node_id
+            .0
+            .verify_signature(&self.record.compute_hash()?, &self.signature)?;
+        Ok(())
+    }
+}
+
+/// Serializer for `PeerRecord`
+pub struct PeerRecordSerializer {
+    ip_addr_serializer: IpAddrSerializer,
+    u16_serializer: U16VarIntSerializer,
+    time_serializer: MassaTimeSerializer,
+    version_serializer: VersionSerializer,
+}
+
+impl PeerRecordSerializer {
+    /// Creates a new `PeerRecordSerializer`
+    pub fn new() -> Self {
+        Self {
+            ip_addr_serializer: IpAddrSerializer::new(),
+            u16_serializer: U16VarIntSerializer::new(),
+            time_serializer: MassaTimeSerializer::new(),
+            version_serializer: VersionSerializer::new(),
+        }
+    }
+}
+
+impl Default for PeerRecordSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer<PeerRecord> for PeerRecordSerializer {
+    fn serialize(&self, value: &PeerRecord, buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        buffer.extend(value.node_id.0.to_bytes());
+        self.ip_addr_serializer
+            .serialize(&value.addr.ip(), buffer)?;
+        self.u16_serializer.serialize(&value.addr.port(), buffer)?;
+        self.time_serializer.serialize(&value.last_seen, buffer)?;
+        self.version_serializer.serialize(&value.version, buffer)?;
+        Ok(())
+    }
+}
+
+/// Deserializer for `PeerRecord`
+pub struct PeerRecordDeserializer {
+    public_key_deserializer: PublicKeyDeserializer,
+    ip_addr_deserializer: IpAddrDeserializer,
+    u16_deserializer: U16VarIntDeserializer,
+    time_deserializer: MassaTimeDeserializer,
+    version_deserializer: VersionDeserializer,
+}
+
+impl PeerRecordDeserializer {
+    /// Creates a new `PeerRecordDeserializer`
+    pub fn new() -> Self {
+        Self {
+            public_key_deserializer: PublicKeyDeserializer::new(),
+            ip_addr_deserializer: IpAddrDeserializer::new(),
+            u16_deserializer: U16VarIntDeserializer::new(Included(0), Included(u16::MAX)),
+            time_deserializer: MassaTimeDeserializer::new((
+                Included(MassaTime::from_millis(0)),
+                Included(MassaTime::from_millis(u64::MAX)),
+            )),
+            version_deserializer: VersionDeserializer::new(),
+        }
+    }
+}
+
+impl Default for PeerRecordDeserializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deserializer<PeerRecord> for PeerRecordDeserializer {
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], PeerRecord, E> {
+        context(
+            "Failed PeerRecord deserialization",
+            tuple((
+                context("Failed node_id deserialization", |input| {
+                    self.public_key_deserializer.deserialize(input)
+                }),
+                context("Failed ip deserialization", |input| {
+                    self.ip_addr_deserializer.deserialize(input)
+                }),
+                context("Failed port deserialization", |input| {
+                    self.u16_deserializer.deserialize(input)
+                }),
+                context("Failed last_seen deserialization", |input| {
+                    self.time_deserializer.deserialize(input)
+                }),
+                context("Failed version deserialization", |input| {
+                    self.version_deserializer.deserialize(input)
+                }),
+            )),
+        )
+        .map(|(public_key, ip, port, last_seen, version)| PeerRecord {
+            node_id: NodeId(public_key),
+            addr: SocketAddr::new(ip, port),
+            last_seen,
+            version,
+        })
+        .parse(buffer)
+    }
+}
+
+/// Serializer for `SignedPeerRecord`
+pub struct SignedPeerRecordSerializer {
+    record_serializer: PeerRecordSerializer,
+}
+
+impl SignedPeerRecordSerializer {
+    /// Creates a new `SignedPeerRecordSerializer`
+    pub fn new() -> Self {
+        Self {
+            record_serializer: PeerRecordSerializer::new(),
+        }
+    }
+}
+
+impl Default for SignedPeerRecordSerializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Serializer<SignedPeerRecord> for SignedPeerRecordSerializer {
+    fn serialize(
+        &self,
+        value: &SignedPeerRecord,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        self.record_serializer.serialize(&value.record, buffer)?;
+        buffer.extend(value.signature.to_bytes());
+        Ok(())
+    }
+}
+
+/// Deserializer for `SignedPeerRecord`.
+/// The signature is not checked, see `SignedPeerRecord::verify_signature`.
+pub struct SignedPeerRecordDeserializer {
+    record_deserializer: PeerRecordDeserializer,
+    signature_deserializer: SignatureDeserializer,
+}
+
+impl SignedPeerRecordDeserializer {
+    /// Creates a new `SignedPeerRecordDeserializer`
+    pub fn new() -> Self {
+        Self {
+            record_deserializer: PeerRecordDeserializer::new(),
+            signature_deserializer: SignatureDeserializer::new(),
+        }
+    }
+}
+
+impl Default for SignedPeerRecordDeserializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deserializer<SignedPeerRecord> for SignedPeerRecordDeserializer {
+    /// ```
+    /// use massa_models::{node::NodeId, version::Version};
+    /// use massa_network_exports::{
+    ///     PeerRecord, SignedPeerRecord, SignedPeerRecordDeserializer, SignedPeerRecordSerializer,
+    /// };
+    /// use massa_serialization::{DeserializeError, Deserializer, Serializer};
+    /// use massa_signature::KeyPair;
+    /// use massa_time::MassaTime;
+    /// use std::str::FromStr;
+    ///
+    /// let keypair = KeyPair::generate();
+    /// let record = PeerRecord {
+    ///     node_id: NodeId(keypair.get_public_key()),
+    ///     addr: "169.202.0.11:31244".parse().unwrap(),
+    ///     last_seen: MassaTime::from_millis(1000),
+    ///     version: Version::from_str("TEST.1.10").unwrap(),
+    /// };
+    /// let signed = SignedPeerRecord::new_signed(record, &keypair).unwrap();
+    /// let mut serialized = Vec::new();
+    /// SignedPeerRecordSerializer::new().serialize(&signed, &mut serialized).unwrap();
+    /// let (rest, deserialized) = SignedPeerRecordDeserializer::new()
+    ///     .deserialize::<DeserializeError>(&serialized)
+    ///     .unwrap();
+    /// assert!(rest.is_empty());
+    /// assert_eq!(deserialized, signed);
+    /// assert!(deserialized.verify_signature().is_ok());
+    /// ```
+    fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], SignedPeerRecord, E> {
+        context(
+            "Failed SignedPeerRecord deserialization",
+            tuple((
+                |input| self.record_deserializer.deserialize(input),
+                context("Failed signature deserialization", |input| {
+                    self.signature_deserializer.deserialize(input)
+                }),
+            )),
+        )
+        .map(|(record, signature)| SignedPeerRecord { record, signature })
+        .parse(buffer)
+    }
+}
+
 /// Peer categories.
 /// There is a defined number of slots for each category.
 /// Order matters: less prioritized peer type first
@@ -202,6 +472,20 @@ pub struct PeerInfo {
     /// Isn't dump into peer file.
     #[serde(default = "usize::default")]
     pub active_in_connections: usize,
+    /// Reputation score of the peer as of `score_updated_at`, see `PeerBehavior`.
+    /// It decays towards zero over time.
+    #[serde(default)]
+    pub score: f64,
+    /// Time in milliseconds of the last update of `score`
+    #[serde(default)]
+    pub score_updated_at: Option<MassaTime>,
+    /// Id of the node we last completed a handshake with at this ip
+    #[serde(default)]
+    pub node_id: Option<NodeId>,
+    /// Latest signed record advertising the address of the peer.
+    /// It is only relayed to other peers if its node id is `node_id`.
+    #[serde(default)]
+    pub record: Option<SignedPeerRecord>,
 }
 
 impl PeerInfo {
@@ -244,9 +528,48 @@ impl PeerInfo {
             active_in_connections: 0,
             peer_type: Default::default(),
             banned: false,
+            score: 0.0,
+            score_updated_at: None,
+            node_id: None,
+            record: None,
+        }
+    }
+
+    /// Address to connect to the peer: the one of its record if any,
+    /// else its ip with `default_port`
+    pub fn get_addr(&self, default_port: u16) -> SocketAddr {
+        match self.record {
+            Some(signed) => signed.record.addr,
+            None => SocketAddr::new(self.ip, default_port),
         }
     }
 
+    /// Whether the record of the peer was signed by the node we know at its ip,
+    /// in which case it can be relayed to other peers
+    pub fn has_verified_record(&self) -> bool {
+        match (self.record, self.node_id) {
+            (Some(signed), Some(node_id)) => signed.record.node_id == node_id,
+            _ => false,
+        }
+    }
+
+    /// Score of the peer at `now`, decayed towards zero with a half-life of `score_half_life`
+    pub fn get_score(&self, score_half_life: MassaTime, now: MassaTime) -> f64 {
+        match self.score_updated_at {
+            Some(updated_at) if score_half_life.to_millis() > 0 => {
+                let elapsed = now.saturating_sub(updated_at).to_millis() as f64;
+                self.score * 0.5f64.powf(elapsed / score_half_life.to_millis() as f64)
+            }
+            _ => self.score,
+        }
+    }
+
+    /// Applies the decay of the score up to `now`, then adds `change` to it
+    pub fn change_score(&mut self, change: f64, score_half_life: MassaTime, now: MassaTime) {
+        self.score = self.get_score(score_half_life, now) + change;
+        self.score_updated_at = Some(now);
+    }
+
     /// peer is ready to be retried, enough time has elapsed since last failure
     pub fn is_peer_ready(&self, wakeup_interval: MassaTime, now: MassaTime) -> bool {
         if let Some(last_failure) = self.last_failure {
@@ -264,6 +587,37 @@ impl PeerInfo {
     }
 }
 
+/// Behaviors of a peer that affect its reputation score
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum PeerBehavior {
+    /// The peer was the first to announce a block to us
+    FirstBlockAnnouncement,
+    /// The peer replied to a block request before it timed out
+    FastBlockReply,
+    /// The peer did not reply to a block request before it timed out
+    AskTimeout,
+    /// The peer sent us data we already had, or did not ask for
+    DuplicateData,
+    /// The peer sent us an invalid item
+    InvalidData,
+    /// The peer sent us more traffic than allowed by our rate limits
+    RateLimitExceeded,
+}
+
+impl PeerBehavior {
+    /// Change of the reputation score of a peer caused by the behavior
+    pub fn score_change(&self) -> f64 {
+        match self {
+            PeerBehavior::FirstBlockAnnouncement => 2.0,
+            PeerBehavior::FastBlockReply => 1.0,
+            PeerBehavior::AskTimeout => -2.0,
+            PeerBehavior::DuplicateData => -1.0,
+            PeerBehavior::InvalidData => -10.0,
+            PeerBehavior::RateLimitExceeded => -5.0,
+        }
+    }
+}
+
 /// Connection count for a category
 #[derive(Default, Debug)]
 pub struct ConnectionCount {
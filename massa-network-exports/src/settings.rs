@@ -3,6 +3,7 @@
 use enum_map::EnumMap;
 use massa_time::MassaTime;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
 
 use crate::peers::PeerType;
@@ -12,8 +13,13 @@ use crate::peers::PeerType;
 pub struct NetworkConfig {
     /// Where to listen for communications.
     pub bind: SocketAddr,
+    /// Other addresses to listen on, e.g. an IPv6 one next to an IPv4 `bind`
+    pub additional_binds: Vec<SocketAddr>,
     /// Our own IP if it is routable, else None.
     pub routable_ip: Option<IpAddr>,
+    /// Addresses at which other nodes can reach us, advertised in our signed peer records.
+    /// If empty, `routable_ip` is advertised with the port of `bind`.
+    pub advertised_addrs: Vec<SocketAddr>,
     /// Protocol port
     pub protocol_port: u16,
     /// Time interval spent waiting for a response from a peer.
@@ -42,6 +48,8 @@ pub struct NetworkConfig {
     pub message_timeout: MassaTime,
     /// Every `ask_peer_list_interval` in milliseconds we ask every one for its advertisable peers list.
     pub ask_peer_list_interval: MassaTime,
+    /// Signed peer records whose signing time is more than `peer_record_max_age` milliseconds away from ours are ignored
+    pub peer_record_max_age: MassaTime,
     /// Max wait time for sending a Network or Node event.
     pub max_send_wait: MassaTime,
     /// Time after which we forget a node
@@ -56,6 +64,18 @@ pub struct NetworkConfig {
     pub max_bytes_read: f64,
     /// Write limitation for a connection in bytes per seconds
     pub max_bytes_write: f64,
+    /// Refuse to connect to peers that do not encrypt their connection
+    pub require_encryption: bool,
+    /// Half-life of the peer reputation scores in milliseconds
+    pub peer_score_half_life: MassaTime,
+    /// Peers whose reputation score falls below `peer_ban_score` are banned
+    pub peer_ban_score: f64,
+    /// Limit on the bytes received from a peer
+    pub peer_bytes_rate_limit: RateLimit,
+    /// Limit on the messages received from a peer
+    pub peer_messages_rate_limit: RateLimit,
+    /// Limits on the messages of a given type received from a peer, by message type name (e.g. `AskForOperations`)
+    pub message_type_rate_limits: HashMap<String, RateLimit>,
     /// Max number ids in ask blocks message
     pub max_ask_blocks: u32,
     /// Max operations per block
@@ -92,6 +112,64 @@ pub struct NetworkConfig {
     pub node_event_channel_size: usize,
 }
 
+impl NetworkConfig {
+    /// Addresses to listen on: `bind` followed by `additional_binds`
+    pub fn get_listen_addrs(&self) -> Vec<SocketAddr> {
+        std::iter::once(self.bind)
+            .chain(self.additional_binds.iter().copied())
+            .collect()
+    }
+
+    /// Addresses at which we are reachable, with canonical IPs and without duplicates
+    pub fn get_advertised_addrs(&self) -> Vec<SocketAddr> {
+        let mut addrs: Vec<SocketAddr> = Vec::new();
+        let configured = if self.advertised_addrs.is_empty() {
+            self.routable_ip
+                .map(|ip| SocketAddr::new(ip, self.bind.port()))
+                .into_iter()
+                .collect()
+        } else {
+            self.advertised_addrs.clone()
+        };
+        for addr in configured {
+            let addr = SocketAddr::new(addr.ip().to_canonical(), addr.port());
+            if !addrs.contains(&addr) {
+                addrs.push(addr);
+            }
+        }
+        addrs
+    }
+
+    /// Whether `ip` is one of our own routable IPs
+    pub fn is_own_ip(&self, ip: &IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.routable_ip
+            .map_or(false, |our_ip| our_ip.to_canonical() == ip)
+            || self
+                .advertised_addrs
+                .iter()
+                .any(|addr| addr.ip().to_canonical() == ip)
+    }
+}
+
+/// Token bucket limit: the bucket holds at most `burst` tokens and refills at `rate` tokens per second.
+/// Each received message consumes one token from message limits, and its size in bytes from byte limits.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// tokens added per second
+    pub rate: f64,
+    /// max tokens in the bucket
+    pub burst: f64,
+}
+
+impl RateLimit {
+    /// A limit that never drops anything
+    pub const UNLIMITED: RateLimit = RateLimit {
+        rate: f64::INFINITY,
+        burst: f64::INFINITY,
+    };
+}
+
 /// Connection configuration for a peer type
 /// Limit the current connections for a given peer type as a whole
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -107,8 +185,8 @@ pub struct PeerTypeConnectionConfig {
 /// setting tests
 #[cfg(feature = "testing")]
 pub mod tests {
-    use crate::NetworkConfig;
     use crate::{test_exports::tools::get_temp_keypair_file, PeerType};
+    use crate::{NetworkConfig, RateLimit};
     use enum_map::enum_map;
     use massa_models::config::{
         ENDORSEMENT_COUNT, MAX_ADVERTISE_LENGTH, MAX_ASK_BLOCKS_PER_MESSAGE,
@@ -144,7 +222,9 @@ pub mod tests {
             };
             NetworkConfig {
                 bind: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080),
+                additional_binds: Vec::new(),
                 routable_ip: Some(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))),
+                advertised_addrs: Vec::new(),
                 protocol_port: 0,
                 connect_timeout: MassaTime::from_millis(180_000),
                 wakeup_interval: MassaTime::from_millis(10_000),
@@ -155,6 +235,7 @@ pub mod tests {
                 peers_file_dump_interval: MassaTime::from_millis(10_000),
                 message_timeout: MassaTime::from_millis(5000u64),
                 ask_peer_list_interval: MassaTime::from_millis(50000u64),
+                peer_record_max_age: MassaTime::from_millis(86_400_000),
                 keypair_file: std::path::PathBuf::new(),
                 max_send_wait: MassaTime::from_millis(100),
                 ban_timeout: MassaTime::from_millis(100_000_000),
@@ -165,6 +246,12 @@ pub mod tests {
                 max_operations_per_message: MAX_OPERATIONS_PER_MESSAGE,
                 max_bytes_read: std::f64::INFINITY,
                 max_bytes_write: std::f64::INFINITY,
+                require_encryption: false,
+                peer_score_half_life: MassaTime::from_millis(600_000),
+                peer_ban_score: -100.0,
+                peer_bytes_rate_limit: RateLimit::UNLIMITED,
+                peer_messages_rate_limit: RateLimit::UNLIMITED,
+                message_type_rate_limits: Default::default(),
                 max_ask_blocks: MAX_ASK_BLOCKS_PER_MESSAGE,
                 endorsement_count: ENDORSEMENT_COUNT,
                 max_endorsements_per_message: MAX_ENDORSEMENTS_PER_MESSAGE,
@@ -210,7 +297,9 @@ pub mod tests {
             let routable_ip = Some(IpAddr::V4(Ipv4Addr::new(200, 200, 200, 200)));
             Self {
                 bind,
+                additional_binds: Vec::new(),
                 routable_ip,
+                advertised_addrs: Vec::new(),
                 protocol_port: port,
                 connect_timeout: MassaTime::from_millis(3000),
                 peers_file: peers_file.to_path_buf(),
@@ -221,6 +310,7 @@ pub mod tests {
                 peers_file_dump_interval: MassaTime::from_millis(30000),
                 message_timeout: MassaTime::from_millis(5000u64),
                 ask_peer_list_interval: MassaTime::from_millis(50000u64),
+                peer_record_max_age: MassaTime::from_millis(86_400_000),
                 keypair_file: get_temp_keypair_file().path().to_path_buf(),
                 max_send_wait: MassaTime::from_millis(100),
                 ban_timeout: MassaTime::from_millis(100_000_000),
@@ -231,6 +321,12 @@ pub mod tests {
                 max_operations_per_message: MAX_OPERATIONS_PER_MESSAGE,
                 max_bytes_read: std::f64::INFINITY,
                 max_bytes_write: std::f64::INFINITY,
+                require_encryption: false,
+                peer_score_half_life: MassaTime::from_millis(600_000),
+                peer_ban_score: -100.0,
+                peer_bytes_rate_limit: RateLimit::UNLIMITED,
+                peer_messages_rate_limit: RateLimit::UNLIMITED,
+                message_type_rate_limits: Default::default(),
                 max_ask_blocks: 10,
                 endorsement_count: 8,
                 max_endorsements_per_message: MAX_ENDORSEMENTS_PER_MESSAGE,
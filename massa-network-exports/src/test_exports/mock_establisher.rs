@@ -129,7 +129,7 @@ impl MockEstablisher {
     }
 
     /// get connection listener
-    pub async fn get_listener(&mut self, _addr: SocketAddr) -> io::Result<MockListener> {
+    pub async fn get_listener(&mut self, _addrs: &[SocketAddr]) -> io::Result<MockListener> {
         Ok(MockListener {
             connection_listener_rx: self
                 .connection_listener_rx
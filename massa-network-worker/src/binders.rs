@@ -4,6 +4,10 @@
 use crate::messages::{MessageDeserializer, MessageSerializer};
 
 use super::messages::Message;
+use chacha20poly1305::{
+    aead::{AeadInPlace, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
 use massa_models::{
     error::ModelsError,
     serialization::{DeserializeMinBEInt, SerializeMinBEInt},
@@ -15,11 +19,59 @@ use std::convert::TryInto;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tracing::warn;
 
+/// Size in bytes of the authentication tag appended to encrypted frames
+const AEAD_TAG_SIZE: u32 = 16;
+
+/// Encrypts or decrypts the frames of one direction of a connection with ChaCha20-Poly1305.
+///
+/// The nonce is the count of frames processed so far, so frames that are dropped, replayed
+/// or reordered fail authentication. Each direction must therefore use its own key.
+pub struct FrameCipher {
+    cipher: ChaCha20Poly1305,
+    frame_index: u64,
+}
+
+impl FrameCipher {
+    /// Creates a new `FrameCipher` from a 32 bytes session key
+    pub fn new(key: &[u8; 32]) -> Self {
+        FrameCipher {
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+            frame_index: 0,
+        }
+    }
+
+    /// Returns the nonce of the next frame and increments the frame count
+    fn next_nonce(&mut self) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+        nonce[4..].copy_from_slice(&self.frame_index.to_be_bytes());
+        self.frame_index += 1;
+        nonce
+    }
+
+    /// Encrypts a frame in place and appends its authentication tag
+    fn encrypt(&mut self, buf: &mut Vec<u8>) -> Result<(), NetworkError> {
+        let nonce = self.next_nonce();
+        self.cipher
+            .encrypt_in_place(Nonce::from_slice(&nonce), b"", buf)
+            .map_err(|_| NetworkError::GeneralProtocolError("frame encryption failed".into()))
+    }
+
+    /// Authenticates and decrypts a frame in place, removing its authentication tag
+    fn decrypt(&mut self, buf: &mut Vec<u8>) -> Result<(), NetworkError> {
+        let nonce = self.next_nonce();
+        self.cipher
+            .decrypt_in_place(Nonce::from_slice(&nonce), b"", buf)
+            .map_err(|_| NetworkError::GeneralProtocolError("frame authentication failed".into()))
+    }
+}
+
 /// Used to serialize and send data.
 pub struct WriteBinder {
     write_half: WriteHalf,
     message_index: u64,
     max_message_size: u32,
+    cipher: Option<FrameCipher>,
+    last_frame_size: usize,
 }
 
 impl WriteBinder {
@@ -33,6 +85,28 @@ impl WriteBinder {
             write_half,
             message_index: 0,
             max_message_size,
+            cipher: None,
+            last_frame_size: 0,
+        }
+    }
+
+    /// Size in bytes of the last frame sent, including its size field
+    pub fn last_frame_size(&self) -> usize {
+        self.last_frame_size
+    }
+
+    /// Encrypts all the messages sent from now on with `cipher`.
+    /// The peer must decrypt them with a `FrameCipher` built from the same key.
+    pub fn enable_encryption(&mut self, cipher: FrameCipher) {
+        self.cipher = Some(cipher);
+    }
+
+    /// Maximal size of a frame, which includes the authentication tag once encryption is enabled
+    fn max_frame_size(&self) -> u32 {
+        if self.cipher.is_some() {
+            self.max_message_size + AEAD_TAG_SIZE
+        } else {
+            self.max_message_size
         }
     }
 
@@ -44,16 +118,19 @@ impl WriteBinder {
         //        massa_trace!("binder.send", { "msg": msg });
         let mut buf = Vec::new();
         MessageSerializer::new().serialize(msg, &mut buf)?;
+        if let Some(cipher) = self.cipher.as_mut() {
+            cipher.encrypt(&mut buf)?;
+        }
         let msg_size: u32 = buf
             .len()
             .try_into()
             .map_err(|_| NetworkError::GeneralProtocolError("message too long".into()))?;
-        self.write_half
-            .write_all(&msg_size.to_be_bytes_min(self.max_message_size)?[..])
-            .await?;
+        let size_field = msg_size.to_be_bytes_min(self.max_frame_size())?;
+        self.write_half.write_all(&size_field[..]).await?;
 
         // send message
         self.write_half.write_all(&buf).await?;
+        self.last_frame_size = size_field.len() + buf.len();
 
         let res_index = self.message_index;
         self.message_index += 1;
@@ -71,6 +148,8 @@ pub struct ReadBinder {
     msg_size: Option<u32>,
     max_message_size: u32,
     message_deserializer: MessageDeserializer,
+    cipher: Option<FrameCipher>,
+    last_frame_size: usize,
 }
 
 impl ReadBinder {
@@ -93,6 +172,28 @@ impl ReadBinder {
             msg_size: None,
             max_message_size,
             message_deserializer,
+            cipher: None,
+            last_frame_size: 0,
+        }
+    }
+
+    /// Size in bytes of the last frame received, including its size field
+    pub fn last_frame_size(&self) -> usize {
+        self.last_frame_size
+    }
+
+    /// Decrypts all the messages received from now on with `cipher`.
+    /// The peer must encrypt them with a `FrameCipher` built from the same key.
+    pub fn enable_encryption(&mut self, cipher: FrameCipher) {
+        self.cipher = Some(cipher);
+    }
+
+    /// Maximal size of a frame, which includes the authentication tag once encryption is enabled
+    fn max_frame_size(&self) -> u32 {
+        if self.cipher.is_some() {
+            self.max_message_size + AEAD_TAG_SIZE
+        } else {
+            self.max_message_size
         }
     }
 
@@ -111,7 +212,7 @@ impl ReadBinder {
         // check if we are in the process of reading the message length
         if self.msg_size.is_none() {
             // pre-allocate the buffer to fit the encoded message size if the buffer is not already allocated
-            let size_field_len = u32::be_bytes_min_length(self.max_message_size);
+            let size_field_len = u32::be_bytes_min_length(self.max_frame_size());
             if self.buf.len() != size_field_len {
                 self.buf = vec![0u8; size_field_len];
             }
@@ -139,7 +240,7 @@ impl ReadBinder {
             }
 
             // once we have all the message size bytes, deserialize it
-            let res_size = u32::from_be_bytes_min(&self.buf, self.max_message_size)?.0;
+            let res_size = u32::from_be_bytes_min(&self.buf, self.max_frame_size())?.0;
             // set self.msg_size to indicate that we are now in the process of reading the message contents (and not the size anymore).
             self.msg_size = Some(res_size);
             // allocate the buffer to match the message length
@@ -169,6 +270,10 @@ impl ReadBinder {
                 }
             }
         }
+        // authenticate and decrypt the frame if the connection is encrypted
+        if let Some(cipher) = self.cipher.as_mut() {
+            cipher.decrypt(&mut self.buf)?;
+        }
         let (_, res_msg) = self
             .message_deserializer
             .deserialize::<DeserializeError>(&self.buf)
@@ -178,6 +283,8 @@ impl ReadBinder {
             })?;
 
         // now the message readout is over, we reset the state to start reading the next message's size field again at the next run
+        self.last_frame_size = u32::be_bytes_min_length(self.max_frame_size())
+            + self.msg_size.unwrap_or_default() as usize;
         self.cursor = 0;
         self.msg_size = None;
 
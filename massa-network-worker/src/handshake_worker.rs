@@ -5,7 +5,7 @@
 use crate::messages::MessageDeserializer;
 
 use super::{
-    binders::{ReadBinder, WriteBinder},
+    binders::{FrameCipher, ReadBinder, WriteBinder},
     messages::Message,
 };
 use futures::future::try_join;
@@ -14,11 +14,13 @@ use massa_logging::massa_trace;
 use massa_models::{
     config::{
         constants::{MAX_DATASTORE_VALUE_LENGTH, MAX_FUNCTION_NAME_LENGTH, MAX_PARAMETERS_SIZE},
-        ENDORSEMENT_COUNT, MAX_ADVERTISE_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE, MAX_MESSAGE_SIZE,
+        ENCRYPTED_TRANSPORT_MIN_VERSION, ENDORSEMENT_COUNT, HANDSHAKE_RANDOMNESS_SIZE_BYTES,
+        MAX_ADVERTISE_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE, MAX_MESSAGE_SIZE,
         MAX_OPERATIONS_PER_BLOCK, MAX_OPERATION_DATASTORE_ENTRY_COUNT,
         MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH, THREAD_COUNT,
+        X25519_PUBLIC_KEY_SIZE_BYTES,
     },
-    version::Version,
+    version::{Version, VersionSerializer},
 };
 use massa_models::{
     config::{MAX_ASK_BLOCKS_PER_MESSAGE, MAX_OPERATIONS_PER_MESSAGE},
@@ -28,14 +30,28 @@ use massa_network_exports::{
     throw_handshake_error as throw, ConnectionId, HandshakeErrorType, NetworkError, ReadHalf,
     WriteHalf,
 };
+use massa_serialization::Serializer;
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
-use rand::{rngs::StdRng, RngCore, SeedableRng};
+use rand::{
+    rngs::{OsRng, StdRng},
+    RngCore, SeedableRng,
+};
 use tokio::{task::JoinHandle, time::timeout};
 use tracing::debug;
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+
+/// Domain separation prefix of the derivation of the session keys
+const SESSION_KEY_CONTEXT: &[u8] = b"massa_network_session_key";
+
+/// Ending of the random bytes of the peers that encrypt their connection.
+/// The random bytes being signed by the other peer, this announces encryption support
+/// in a way that can't be removed from the handshake without being detected.
+const ENCRYPTION_SENTINEL: &[u8; 8] = b"MASSAENC";
 
-/// Type alias for more readability
-pub type HandshakeReturnType = Result<(NodeId, ReadBinder, WriteBinder), NetworkError>;
+/// Type alias for more readability.
+/// On success, contains the id and the version of the other node.
+pub type HandshakeReturnType = Result<(NodeId, ReadBinder, WriteBinder, Version), NetworkError>;
 
 /// Manages handshakes.
 pub struct HandshakeWorker {
@@ -50,6 +66,8 @@ pub struct HandshakeWorker {
     /// After `timeout_duration` milliseconds, the handshake attempt is dropped.
     timeout_duration: MassaTime,
     version: Version,
+    /// Refuse the peers that do not encrypt their connection
+    require_encryption: bool,
 }
 
 impl HandshakeWorker {
@@ -69,6 +87,7 @@ impl HandshakeWorker {
     /// * `timeout_duration`: after `timeout_duration` milliseconds, the handshake attempt is dropped.
     /// * `connection_id`: Node we are trying to connect for debugging
     /// * `version`: Node version used in handshake initialization (check peers compatibility)
+    /// * `require_encryption`: refuse the peers that do not encrypt their connection
     #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         socket_reader: ReadHalf,
@@ -80,6 +99,7 @@ impl HandshakeWorker {
         connection_id: ConnectionId,
         max_bytes_read: f64,
         max_bytes_write: f64,
+        require_encryption: bool,
     ) -> JoinHandle<(ConnectionId, HandshakeReturnType)> {
         debug!("starting handshake with connection_id={}", connection_id);
         massa_trace!("network_worker.new_connection", {
@@ -116,6 +136,7 @@ impl HandshakeWorker {
                     keypair,
                     timeout_duration,
                     version,
+                    require_encryption,
                 }
                 .run()
                 .await,
@@ -131,6 +152,12 @@ impl HandshakeWorker {
         // generate random bytes
         let mut self_random_bytes = [0u8; 32];
         StdRng::from_entropy().fill_bytes(&mut self_random_bytes);
+        let (min_major, min_minor) = ENCRYPTED_TRANSPORT_MIN_VERSION;
+        let self_encrypts = self.version.is_at_least(min_major, min_minor);
+        if self_encrypts {
+            self_random_bytes[HANDSHAKE_RANDOMNESS_SIZE_BYTES - ENCRYPTION_SENTINEL.len()..]
+                .copy_from_slice(ENCRYPTION_SENTINEL);
+        }
         let self_random_hash = Hash::compute_from(&self_random_bytes);
         // send handshake init future
         let msg = Message::HandshakeInitiation {
@@ -174,6 +201,27 @@ impl HandshakeWorker {
             throw!(IncompatibleVersion)
         }
 
+        // if both peers support it, the connection is encrypted after the handshake
+        if self_encrypts {
+            if other_version.is_at_least(min_major, min_minor) {
+                self.run_key_exchange(
+                    other_node_id,
+                    &self_random_bytes,
+                    &other_random_bytes,
+                    other_version,
+                )
+                .await?;
+                return Ok((other_node_id, self.reader, self.writer, other_version));
+            }
+            // the version of a peer announcing encryption support was rewritten
+            if other_random_bytes.ends_with(ENCRYPTION_SENTINEL) {
+                throw!(HandshakeDowngrade)
+            }
+            if self.require_encryption {
+                throw!(EncryptionRequired)
+            }
+        }
+
         // sign their random bytes
         let other_random_hash = Hash::compute_from(&other_random_bytes);
         let self_signature = self.keypair.sign(&other_random_hash)?;
@@ -211,6 +259,137 @@ impl HandshakeWorker {
                 NetworkError::HandshakeError(HandshakeErrorType::HandshakeInvalidSignature)
             })?;
 
-        Ok((other_node_id, self.reader, self.writer))
+        Ok((other_node_id, self.reader, self.writer, other_version))
     }
+
+    /// Replaces the handshake reply when both peers encrypt their connection.
+    ///
+    /// Each peer sends an ephemeral X25519 public key signed along with the random bytes of the other peer,
+    /// which authenticates the key exchange with the node keypairs and makes it fresh.
+    /// The versions announced in the unsigned handshake initiations are signed as well,
+    /// so that the key exchange fails if they were tampered with.
+    /// The session keys are then derived from the shared secret and the random bytes of both peers,
+    /// with one key per direction, and all the following frames are encrypted.
+    async fn run_key_exchange(
+        &mut self,
+        other_node_id: NodeId,
+        self_random_bytes: &[u8; HANDSHAKE_RANDOMNESS_SIZE_BYTES],
+        other_random_bytes: &[u8; HANDSHAKE_RANDOMNESS_SIZE_BYTES],
+        other_version: Version,
+    ) -> Result<(), NetworkError> {
+        // generate our ephemeral key and sign it along with their random bytes and both versions
+        // the secret is generated for this connection only and zeroized when dropped
+        let mut self_ephemeral_secret_bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut self_ephemeral_secret_bytes);
+        let self_ephemeral_secret = StaticSecret::from(self_ephemeral_secret_bytes);
+        let self_ephemeral_public_key = X25519PublicKey::from(&self_ephemeral_secret).to_bytes();
+        let self_signature = self.keypair.sign(&key_exchange_hash(
+            other_random_bytes,
+            &self_ephemeral_public_key,
+            self.version,
+            other_version,
+        )?)?;
+
+        // send handshake key reply future
+        let msg = Message::HandshakeKeyReply {
+            ephemeral_public_key: self_ephemeral_public_key,
+            signature: self_signature,
+        };
+        let send_reply_fut = self.writer.send(&msg);
+
+        // receive handshake key reply future
+        let recv_reply_fut = self.reader.next();
+
+        // join send_reply_fut and recv_reply_fut with a timeout, and match result
+        let (other_ephemeral_public_key, other_signature) = match timeout(
+            self.timeout_duration.to_duration(),
+            try_join(send_reply_fut, recv_reply_fut),
+        )
+        .await
+        {
+            Err(_) => throw!(HandshakeTimeout),
+            Ok(Err(e)) => return Err(e),
+            Ok(Ok((_, None))) => throw!(HandshakeInterruption, "repl".into()),
+            Ok(Ok((_, Some((_, msg))))) => match msg {
+                Message::HandshakeKeyReply {
+                    ephemeral_public_key,
+                    signature,
+                } => (ephemeral_public_key, signature),
+                _ => throw!(HandshakeWrongMessage),
+            },
+        };
+
+        // check their signature of our random bytes, of their ephemeral key and of the versions we announced
+        other_node_id
+            .0
+            .verify_signature(
+                &key_exchange_hash(
+                    self_random_bytes,
+                    &other_ephemeral_public_key,
+                    other_version,
+                    self.version,
+                )?,
+                &other_signature,
+            )
+            .map_err(|_err| {
+                NetworkError::HandshakeError(HandshakeErrorType::HandshakeInvalidSignature)
+            })?;
+
+        // compute the shared secret, rejecting low order points
+        let shared_secret = self_ephemeral_secret
+            .diffie_hellman(&X25519PublicKey::from(other_ephemeral_public_key));
+        if shared_secret.as_bytes().iter().all(|byte| *byte == 0) {
+            throw!(HandshakeInvalidEphemeralKey)
+        }
+
+        // derive one key per direction, the sender random bytes coming first
+        let derive_key = |sender_random_bytes: &[u8], receiver_random_bytes: &[u8]| {
+            Hash::compute_from(
+                &[
+                    SESSION_KEY_CONTEXT,
+                    shared_secret.as_bytes(),
+                    sender_random_bytes,
+                    receiver_random_bytes,
+                ]
+                .concat(),
+            )
+            .into_bytes()
+        };
+        self.writer.enable_encryption(FrameCipher::new(&derive_key(
+            self_random_bytes,
+            other_random_bytes,
+        )));
+        self.reader.enable_encryption(FrameCipher::new(&derive_key(
+            other_random_bytes,
+            self_random_bytes,
+        )));
+        Ok(())
+    }
+}
+
+/// Hash signed by a peer during the key exchange.
+///
+/// It covers the random bytes of the receiver, the ephemeral key of the signer,
+/// the versions announced by both peers and the encryption flag of the connection.
+///
+/// # Arguments
+/// * `receiver_random_bytes`: random bytes sent by the receiver of the signature
+/// * `signer_ephemeral_public_key`: ephemeral key of the signer
+/// * `signer_version`: version announced by the signer
+/// * `receiver_version`: version announced by the receiver, as seen by the signer
+fn key_exchange_hash(
+    receiver_random_bytes: &[u8; HANDSHAKE_RANDOMNESS_SIZE_BYTES],
+    signer_ephemeral_public_key: &[u8; X25519_PUBLIC_KEY_SIZE_BYTES],
+    signer_version: Version,
+    receiver_version: Version,
+) -> Result<Hash, NetworkError> {
+    let mut data = Vec::new();
+    data.extend(receiver_random_bytes);
+    data.extend(signer_ephemeral_public_key);
+    let version_serializer = VersionSerializer::new();
+    version_serializer.serialize(&signer_version, &mut data)?;
+    version_serializer.serialize(&receiver_version, &mut data)?;
+    // encryption flag, the key exchange only happens on encrypted connections
+    data.push(1);
+    Ok(Hash::compute_from(&data))
 }
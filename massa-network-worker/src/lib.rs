@@ -30,6 +30,7 @@ mod network_event;
 mod network_worker;
 mod node_worker;
 mod peer_info_database;
+mod traffic;
 
 #[cfg(test)]
 pub mod tests;
@@ -56,12 +57,17 @@ pub async fn start_network_controller(
 > {
     debug!("starting network controller");
 
-    // check that local IP is routable
+    // check that local IPs are routable
     if let Some(self_ip) = network_settings.routable_ip {
         if !self_ip.is_global() {
             return Err(NetworkError::InvalidIpError(self_ip));
         }
     }
+    for addr in network_settings.advertised_addrs.iter() {
+        if !addr.ip().is_global() {
+            return Err(NetworkError::InvalidIpError(addr.ip()));
+        }
+    }
 
     // try to read node keypair from file, otherwise generate it & write to file. Then derive nodeId
     let keypair = if std::path::Path::is_file(&network_settings.keypair_file) {
@@ -91,7 +97,9 @@ pub async fn start_network_controller(
     massa_trace!("self_node_id", { "node_id": self_node_id });
 
     // create listener
-    let listener = establisher.get_listener(network_settings.bind).await?;
+    let listener = establisher
+        .get_listener(&network_settings.get_listen_addrs())
+        .await?;
 
     debug!("Loading peer database");
     // load peer info database
@@ -3,19 +3,23 @@
 use massa_hash::HashDeserializer;
 use massa_models::{
     block::{BlockHeader, BlockHeaderDeserializer, BlockId, WrappedHeader},
-    config::HANDSHAKE_RANDOMNESS_SIZE_BYTES,
+    config::{HANDSHAKE_RANDOMNESS_SIZE_BYTES, X25519_PUBLIC_KEY_SIZE_BYTES},
     endorsement::{Endorsement, EndorsementDeserializer, WrappedEndorsement},
     operation::{
-        OperationIdsDeserializer, OperationIdsSerializer, OperationPrefixIds,
-        OperationPrefixIdsDeserializer, OperationPrefixIdsSerializer, OperationsDeserializer,
-        OperationsSerializer, WrappedOperation,
+        OperationIdsDeserializer, OperationIdsSerializer, OperationPrefixId,
+        OperationPrefixIdDeserializer, OperationPrefixIds, OperationPrefixIdsDeserializer,
+        OperationPrefixIdsSerializer, OperationsDeserializer, OperationsSerializer,
+        WrappedOperation,
     },
     serialization::array_from_slice,
     serialization::{IpAddrDeserializer, IpAddrSerializer},
     version::{Version, VersionDeserializer, VersionSerializer},
     wrapped::{WrappedDeserializer, WrappedSerializer},
 };
-use massa_network_exports::{AskForBlocksInfo, BlockInfoReply};
+use massa_network_exports::{
+    AskForBlocksInfo, BlockInfoReply, SignedPeerRecord, SignedPeerRecordDeserializer,
+    SignedPeerRecordSerializer,
+};
 use massa_serialization::{
     Deserializer, SerializeError, Serializer, U32VarIntDeserializer, U32VarIntSerializer,
 };
@@ -51,6 +55,14 @@ pub enum Message {
         /// Signature of the received random bytes with our `keypair`.
         signature: Signature,
     },
+    /// Reply to a handshake initiation message when both peers encrypt their connection.
+    HandshakeKeyReply {
+        /// Our ephemeral X25519 public key, used to derive the session keys.
+        ephemeral_public_key: [u8; X25519_PUBLIC_KEY_SIZE_BYTES],
+        /// Signature with our `keypair` of the hash of the received random bytes,
+        /// `ephemeral_public_key` and the versions announced by both peers.
+        signature: Signature,
+    },
     /// Block header
     BlockHeader(WrappedHeader),
     /// Message asking the peer for info on a list of blocks.
@@ -64,6 +76,12 @@ pub enum Message {
     /// If the ip of the node that sent that message is routable,
     /// it is the first ip of the list.
     PeerList(Vec<IpAddr>),
+    /// Message asking the peer for signed records of its advertisable peers.
+    AskPeerRecords,
+    /// Reply to a `AskPeerRecords` message.
+    /// If the ip of the node that sent that message is routable,
+    /// its own record is the first of the list.
+    PeerRecords(Vec<SignedPeerRecord>),
     /// Batch of operation ids
     OperationsAnnouncement(OperationPrefixIds),
     /// Someone ask for operations.
@@ -74,7 +92,7 @@ pub enum Message {
     Endorsements(Vec<WrappedEndorsement>),
 }
 
-#[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
+#[derive(IntoPrimitive, Debug, Clone, Copy, Eq, PartialEq, Hash, TryFromPrimitive)]
 #[repr(u32)]
 pub(crate) enum MessageTypeId {
     HandshakeInitiation = 0u32,
@@ -88,6 +106,37 @@ pub(crate) enum MessageTypeId {
     AskForOperations,
     OperationsAnnouncement,
     ReplyForBlocks,
+    HandshakeKeyReply,
+    AskPeerRecords,
+    PeerRecords,
+}
+
+impl MessageTypeId {
+    /// All the message types, ordered by id
+    pub(crate) fn all() -> impl Iterator<Item = MessageTypeId> {
+        (0u32..).map_while(|id| MessageTypeId::try_from(id).ok())
+    }
+}
+
+impl From<&Message> for MessageTypeId {
+    fn from(message: &Message) -> Self {
+        match message {
+            Message::HandshakeInitiation { .. } => MessageTypeId::HandshakeInitiation,
+            Message::HandshakeReply { .. } => MessageTypeId::HandshakeReply,
+            Message::HandshakeKeyReply { .. } => MessageTypeId::HandshakeKeyReply,
+            Message::BlockHeader(_) => MessageTypeId::BlockHeader,
+            Message::AskForBlocks(_) => MessageTypeId::AskForBlocks,
+            Message::ReplyForBlocks(_) => MessageTypeId::ReplyForBlocks,
+            Message::AskPeerList => MessageTypeId::AskPeerList,
+            Message::PeerList(_) => MessageTypeId::PeerList,
+            Message::AskPeerRecords => MessageTypeId::AskPeerRecords,
+            Message::PeerRecords(_) => MessageTypeId::PeerRecords,
+            Message::OperationsAnnouncement(_) => MessageTypeId::OperationsAnnouncement,
+            Message::AskForOperations(_) => MessageTypeId::AskForOperations,
+            Message::Operations(_) => MessageTypeId::Operations,
+            Message::Endorsements(_) => MessageTypeId::Endorsements,
+        }
+    }
 }
 
 #[derive(IntoPrimitive, Debug, Eq, PartialEq, TryFromPrimitive)]
@@ -97,6 +146,8 @@ pub(crate) enum BlockInfoType {
     Info,
     Operations,
     NotFound,
+    CompactInfo,
+    OperationsByPrefix,
 }
 
 /// Basic serializer for `Message`.
@@ -108,6 +159,7 @@ pub struct MessageSerializer {
     operations_ids_serializer: OperationIdsSerializer,
     operations_serializer: OperationsSerializer,
     ip_addr_serializer: IpAddrSerializer,
+    signed_peer_record_serializer: SignedPeerRecordSerializer,
 }
 
 impl MessageSerializer {
@@ -121,7 +173,28 @@ impl MessageSerializer {
             operations_ids_serializer: OperationIdsSerializer::new(),
             operations_serializer: OperationsSerializer::new(),
             ip_addr_serializer: IpAddrSerializer::new(),
+            signed_peer_record_serializer: SignedPeerRecordSerializer::new(),
+        }
+    }
+}
+
+impl MessageSerializer {
+    /// Serializes an ordered list of operation prefixes
+    fn serialize_prefix_list(
+        &self,
+        prefixes: &[OperationPrefixId],
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        let list_len: u32 = prefixes.len().try_into().map_err(|_| {
+            SerializeError::NumberTooBig(
+                "could not encode operation prefix list length as u32".into(),
+            )
+        })?;
+        self.u32_serializer.serialize(&list_len, buffer)?;
+        for prefix in prefixes {
+            buffer.extend(Vec::<u8>::from(prefix));
         }
+        Ok(())
     }
 }
 
@@ -152,6 +225,15 @@ impl Serializer<Message> for MessageSerializer {
                     .serialize(&(MessageTypeId::HandshakeReply as u32), buffer)?;
                 buffer.extend(signature.to_bytes());
             }
+            Message::HandshakeKeyReply {
+                ephemeral_public_key,
+                signature,
+            } => {
+                self.u32_serializer
+                    .serialize(&(MessageTypeId::HandshakeKeyReply as u32), buffer)?;
+                buffer.extend(ephemeral_public_key);
+                buffer.extend(signature.to_bytes());
+            }
             Message::BlockHeader(header) => {
                 self.u32_serializer
                     .serialize(&(MessageTypeId::BlockHeader as u32), buffer)?;
@@ -168,12 +250,19 @@ impl Serializer<Message> for MessageSerializer {
                         AskForBlocksInfo::Header => BlockInfoType::Header,
                         AskForBlocksInfo::Info => BlockInfoType::Info,
                         AskForBlocksInfo::Operations(_) => BlockInfoType::Operations,
+                        AskForBlocksInfo::CompactInfo => BlockInfoType::CompactInfo,
+                        AskForBlocksInfo::OperationsByPrefix(_) => {
+                            BlockInfoType::OperationsByPrefix
+                        }
                     };
                     self.u32_serializer
                         .serialize(&u32::from(info_type), buffer)?;
                     if let AskForBlocksInfo::Operations(ids) = info {
                         self.operations_ids_serializer.serialize(ids, buffer)?;
                     }
+                    if let AskForBlocksInfo::OperationsByPrefix(prefixes) = info {
+                        self.serialize_prefix_list(prefixes, buffer)?;
+                    }
                 }
             }
             Message::ReplyForBlocks(list) => {
@@ -187,6 +276,7 @@ impl Serializer<Message> for MessageSerializer {
                         BlockInfoReply::Header(_) => BlockInfoType::Header,
                         BlockInfoReply::Info(_) => BlockInfoType::Info,
                         BlockInfoReply::Operations(_) => BlockInfoType::Operations,
+                        BlockInfoReply::CompactInfo(_) => BlockInfoType::CompactInfo,
                         BlockInfoReply::NotFound => BlockInfoType::NotFound,
                     };
                     self.u32_serializer
@@ -200,6 +290,9 @@ impl Serializer<Message> for MessageSerializer {
                     if let BlockInfoReply::Info(ids) = info {
                         self.operations_ids_serializer.serialize(ids, buffer)?;
                     }
+                    if let BlockInfoReply::CompactInfo(prefixes) = info {
+                        self.serialize_prefix_list(prefixes, buffer)?;
+                    }
                 }
             }
             Message::AskPeerList => {
@@ -215,6 +308,20 @@ impl Serializer<Message> for MessageSerializer {
                     self.ip_addr_serializer.serialize(peer, buffer)?;
                 }
             }
+            Message::AskPeerRecords => {
+                self.u32_serializer
+                    .serialize(&(MessageTypeId::AskPeerRecords as u32), buffer)?;
+            }
+            Message::PeerRecords(records) => {
+                self.u32_serializer
+                    .serialize(&(MessageTypeId::PeerRecords as u32), buffer)?;
+                self.u32_serializer
+                    .serialize(&(records.len() as u32), buffer)?;
+                for record in records {
+                    self.signed_peer_record_serializer
+                        .serialize(record, buffer)?;
+                }
+            }
             Message::OperationsAnnouncement(operation_prefix_ids) => {
                 self.u32_serializer
                     .serialize(&(MessageTypeId::OperationsAnnouncement as u32), buffer)?;
@@ -261,7 +368,10 @@ pub struct MessageDeserializer {
     endorsement_deserializer: WrappedDeserializer<Endorsement, EndorsementDeserializer>,
     operation_prefix_ids_deserializer: OperationPrefixIdsDeserializer,
     infos_deserializer: OperationIdsDeserializer,
+    prefix_list_length_deserializer: U32VarIntDeserializer,
+    prefix_deserializer: OperationPrefixIdDeserializer,
     ip_addr_deserializer: IpAddrDeserializer,
+    signed_peer_record_deserializer: SignedPeerRecordDeserializer,
 }
 
 impl MessageDeserializer {
@@ -321,11 +431,37 @@ impl MessageDeserializer {
                 max_operations_per_message,
             ),
             infos_deserializer: OperationIdsDeserializer::new(max_operations_per_block),
+            prefix_list_length_deserializer: U32VarIntDeserializer::new(
+                Included(0),
+                Included(max_operations_per_block),
+            ),
+            prefix_deserializer: OperationPrefixIdDeserializer::new(),
             ip_addr_deserializer: IpAddrDeserializer::new(),
+            signed_peer_record_deserializer: SignedPeerRecordDeserializer::new(),
         }
     }
 }
 
+impl MessageDeserializer {
+    /// Deserializes an ordered list of operation prefixes
+    fn deserialize_prefix_list<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
+        &self,
+        buffer: &'a [u8],
+    ) -> IResult<&'a [u8], Vec<OperationPrefixId>, E> {
+        context(
+            "Failed operation prefix list deserialization",
+            length_count(
+                context("Failed length deserialization", |input| {
+                    self.prefix_list_length_deserializer.deserialize(input)
+                }),
+                context("Failed OperationPrefixId deserialization", |input| {
+                    self.prefix_deserializer.deserialize(input)
+                }),
+            ),
+        )(buffer)
+    }
+}
+
 impl Deserializer<Message> for MessageDeserializer {
     fn deserialize<'a, E: ParseError<&'a [u8]> + ContextError<&'a [u8]>>(
         &self,
@@ -371,6 +507,26 @@ impl Deserializer<Message> for MessageDeserializer {
                     .map(|signature| Message::HandshakeReply { signature })
                     .parse(input)
                 }
+                MessageTypeId::HandshakeKeyReply => context(
+                    "Failed HandshakeKeyReply deserialization",
+                    tuple((
+                        context(
+                            "Failed ephemeral_public_key deserialization",
+                            take(X25519_PUBLIC_KEY_SIZE_BYTES),
+                        ),
+                        context("Failed signature deserialization", |input| {
+                            self.signature_deserializer.deserialize(input)
+                        }),
+                    ))
+                    .map(|(ephemeral_public_key, signature)| {
+                        // Unwrap safety: we checked above that we took enough bytes
+                        Message::HandshakeKeyReply {
+                            ephemeral_public_key: array_from_slice(ephemeral_public_key).unwrap(),
+                            signature,
+                        }
+                    }),
+                )
+                .parse(input),
                 MessageTypeId::BlockHeader => {
                     context("Failed BlockHeader deserialization", |input| {
                         self.block_header_deserializer.deserialize(input)
@@ -412,6 +568,17 @@ impl Deserializer<Message> for MessageDeserializer {
                                             .map(|(rest, operation_ids)| {
                                                 (rest, AskForBlocksInfo::Operations(operation_ids))
                                             }),
+                                        BlockInfoType::CompactInfo => {
+                                            Ok((rest, AskForBlocksInfo::CompactInfo))
+                                        }
+                                        BlockInfoType::OperationsByPrefix => self
+                                            .deserialize_prefix_list(rest)
+                                            .map(|(rest, prefixes)| {
+                                                (
+                                                    rest,
+                                                    AskForBlocksInfo::OperationsByPrefix(prefixes),
+                                                )
+                                            }),
                                         BlockInfoType::NotFound => {
                                             Err(nom::Err::Error(ParseError::from_error_kind(
                                                 buffer,
@@ -468,9 +635,20 @@ impl Deserializer<Message> for MessageDeserializer {
                                             .map(|(rest, operations)| {
                                                 (rest, BlockInfoReply::Operations(operations))
                                             }),
+                                        BlockInfoType::CompactInfo => self
+                                            .deserialize_prefix_list(rest)
+                                            .map(|(rest, prefixes)| {
+                                                (rest, BlockInfoReply::CompactInfo(prefixes))
+                                            }),
                                         BlockInfoType::NotFound => {
                                             Ok((rest, BlockInfoReply::NotFound))
                                         }
+                                        BlockInfoType::OperationsByPrefix => {
+                                            Err(nom::Err::Error(ParseError::from_error_kind(
+                                                buffer,
+                                                nom::error::ErrorKind::Digit,
+                                            )))
+                                        }
                                     }
                                 },
                             )),
@@ -493,6 +671,18 @@ impl Deserializer<Message> for MessageDeserializer {
                 )
                 .map(Message::PeerList)
                 .parse(input),
+                MessageTypeId::AskPeerRecords => Ok((input, Message::AskPeerRecords)),
+                MessageTypeId::PeerRecords => context(
+                    "Failed PeerRecords deserialization",
+                    length_count(
+                        context("Failed length deserialization", |input| {
+                            self.peer_list_length_deserializer.deserialize(input)
+                        }),
+                        |input| self.signed_peer_record_deserializer.deserialize(input),
+                    ),
+                )
+                .map(Message::PeerRecords)
+                .parse(input),
                 MessageTypeId::Operations => {
                     context("Failed Operations deserialization", |input| {
                         self.operations_deserializer.deserialize(input)
@@ -536,6 +726,7 @@ impl Deserializer<Message> for MessageDeserializer {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use massa_hash::Hash;
     use massa_models::config::{
         ENDORSEMENT_COUNT, MAX_ADVERTISE_LENGTH, MAX_ASK_BLOCKS_PER_MESSAGE,
         MAX_DATASTORE_VALUE_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE, MAX_FUNCTION_NAME_LENGTH,
@@ -543,8 +734,12 @@ mod tests {
         MAX_OPERATION_DATASTORE_KEY_LENGTH, MAX_OPERATION_DATASTORE_VALUE_LENGTH,
         MAX_PARAMETERS_SIZE, THREAD_COUNT,
     };
+    use massa_models::node::NodeId;
+    use massa_models::operation::OPERATION_ID_PREFIX_SIZE_BYTES;
+    use massa_network_exports::PeerRecord;
     use massa_serialization::DeserializeError;
     use massa_signature::KeyPair;
+    use massa_time::MassaTime;
     use rand::{prelude::StdRng, RngCore, SeedableRng};
     use serial_test::serial;
     use std::str::FromStr;
@@ -600,5 +795,97 @@ mod tests {
             }
             _ => panic!("unexpected message"),
         }
+
+        let signature = keypair.sign(&Hash::compute_from(&random_bytes)).unwrap();
+        let msg = Message::HandshakeKeyReply {
+            ephemeral_public_key: random_bytes,
+            signature,
+        };
+        let mut ser = Vec::new();
+        message_serializer.serialize(&msg, &mut ser).unwrap();
+        let (rest, deser) = message_deserializer
+            .deserialize::<DeserializeError>(&ser)
+            .unwrap();
+        assert!(rest.is_empty());
+        match deser {
+            Message::HandshakeKeyReply {
+                ephemeral_public_key,
+                signature: sig,
+            } => {
+                assert_eq!(ephemeral_public_key, random_bytes);
+                assert_eq!(sig, signature);
+            }
+            _ => panic!("unexpected message"),
+        }
+
+        let block_id = BlockId(Hash::compute_from(&random_bytes));
+        let prefixes = vec![
+            OperationPrefixId::from(&[1; OPERATION_ID_PREFIX_SIZE_BYTES]),
+            OperationPrefixId::from(&[2; OPERATION_ID_PREFIX_SIZE_BYTES]),
+        ];
+        let msg = Message::AskForBlocks(vec![
+            (block_id, AskForBlocksInfo::CompactInfo),
+            (
+                block_id,
+                AskForBlocksInfo::OperationsByPrefix(prefixes.clone()),
+            ),
+        ]);
+        let mut ser = Vec::new();
+        message_serializer.serialize(&msg, &mut ser).unwrap();
+        let (rest, deser) = message_deserializer
+            .deserialize::<DeserializeError>(&ser)
+            .unwrap();
+        assert!(rest.is_empty());
+        match deser {
+            Message::AskForBlocks(list) => match list.as_slice() {
+                [(_, AskForBlocksInfo::CompactInfo), (id, AskForBlocksInfo::OperationsByPrefix(asked))] =>
+                {
+                    assert_eq!(*id, block_id);
+                    assert_eq!(asked, &prefixes);
+                }
+                _ => panic!("unexpected ask"),
+            },
+            _ => panic!("unexpected message"),
+        }
+
+        let msg = Message::ReplyForBlocks(vec![(
+            block_id,
+            BlockInfoReply::CompactInfo(prefixes.clone()),
+        )]);
+        let mut ser = Vec::new();
+        message_serializer.serialize(&msg, &mut ser).unwrap();
+        let (rest, deser) = message_deserializer
+            .deserialize::<DeserializeError>(&ser)
+            .unwrap();
+        assert!(rest.is_empty());
+        match deser {
+            Message::ReplyForBlocks(list) => match list.as_slice() {
+                [(id, BlockInfoReply::CompactInfo(replied))] => {
+                    assert_eq!(*id, block_id);
+                    assert_eq!(replied, &prefixes);
+                }
+                _ => panic!("unexpected reply"),
+            },
+            _ => panic!("unexpected message"),
+        }
+
+        let record = PeerRecord {
+            node_id: NodeId(keypair.get_public_key()),
+            addr: "169.202.0.11:4242".parse().unwrap(),
+            last_seen: MassaTime::from_millis(1000),
+            version: Version::from_str("TEST.1.10").unwrap(),
+        };
+        let signed_record = SignedPeerRecord::new_signed(record, &keypair).unwrap();
+        let msg = Message::PeerRecords(vec![signed_record]);
+        let mut ser = Vec::new();
+        message_serializer.serialize(&msg, &mut ser).unwrap();
+        let (rest, deser) = message_deserializer
+            .deserialize::<DeserializeError>(&ser)
+            .unwrap();
+        assert!(rest.is_empty());
+        match deser {
+            Message::PeerRecords(records) => assert_eq!(records, vec![signed_record]),
+            _ => panic!("unexpected message"),
+        }
     }
 }
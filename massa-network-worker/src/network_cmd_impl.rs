@@ -33,8 +33,9 @@ use massa_models::{
 };
 use massa_network_exports::{
     AskForBlocksInfo, BlockInfoReply, BootstrapPeers, ConnectionClosureReason, ConnectionId,
-    NetworkError, NodeCommand, Peer, Peers,
+    NetworkError, NodeCommand, Peer, PeerBehavior, Peers,
 };
+use massa_time::MassaTime;
 use std::{
     collections::{HashMap, HashSet},
     net::IpAddr,
@@ -104,16 +105,27 @@ async fn node_ban_by_ids(worker: &mut NetworkWorker, ids: Vec<NodeId>) -> Result
 }
 
 /// For each peer get all node id associated to this peer ip.
+/// The reputation scores of the peers are decayed up to now.
 async fn get_peers(worker: &mut NetworkWorker, response_tx: oneshot::Sender<Peers>) {
+    let now = match MassaTime::now(worker.peer_info_db.clock_compensation) {
+        Ok(now) => now,
+        Err(err) => {
+            warn!("network: could not get current time for GetPeers: {}", err);
+            return;
+        }
+    };
+    let score_half_life = worker.peer_info_db.network_settings.peer_score_half_life;
     let peers: HashMap<IpAddr, Peer> = worker
         .peer_info_db
         .get_peers()
         .iter()
         .map(|(peer_ip_addr, peer)| {
+            let mut peer_info = *peer;
+            peer_info.change_score(0.0, score_half_life, now);
             (
                 *peer_ip_addr,
                 Peer {
-                    peer_info: *peer,
+                    peer_info,
                     active_nodes: worker
                         .active_connections
                         .iter()
@@ -172,6 +184,40 @@ pub async fn on_node_ban_by_ids_cmd(
     node_ban_by_ids(worker, ids).await
 }
 
+/// Updates the reputation scores of the peers of `behaviors` nodes.
+/// Peers whose score falls below `peer_ban_score` are banned.
+pub async fn on_node_behaviors_cmd(
+    worker: &mut NetworkWorker,
+    behaviors: Vec<(NodeId, PeerBehavior)>,
+) -> Result<(), NetworkError> {
+    massa_trace!(
+        "network_worker.manage_network_command receive NetworkCommand::NodeBehaviors",
+        { "behaviors": behaviors }
+    );
+    let mut ips_to_ban = Vec::new();
+    for (node_id, behavior) in behaviors {
+        let ip = match get_ip(worker, &node_id) {
+            Some(ip) => ip,
+            None => continue,
+        };
+        let score = worker
+            .peer_info_db
+            .change_peer_score(&ip, behavior.score_change())?;
+        if score < worker.peer_info_db.network_settings.peer_ban_score && !ips_to_ban.contains(&ip)
+        {
+            warn!(
+                "peer {} reputation score fell to {:.2}, banning it",
+                ip, score
+            );
+            ips_to_ban.push(ip);
+        }
+    }
+    if ips_to_ban.is_empty() {
+        return Ok(());
+    }
+    node_ban_by_ips(worker, ips_to_ban).await
+}
+
 pub async fn on_send_block_header_cmd(
     worker: &mut NetworkWorker,
     node: NodeId,
@@ -339,6 +385,7 @@ pub async fn on_get_stats_cmd(
             .filter(|(_, p)| p.banned)
             .fold(0, |acc, _| acc + 1),
         active_node_count: worker.active_nodes.len() as u64,
+        traffic: worker.traffic.get_stats(),
     };
     if response_tx.send(res).is_err() {
         warn!("network: could not send NodeSignMessage response upstream");
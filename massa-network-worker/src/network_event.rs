@@ -77,7 +77,7 @@ impl EventSender {
 }
 
 pub mod event_impl {
-    use crate::network_worker::NetworkWorker;
+    use crate::{network_cmd_impl::on_node_behaviors_cmd, network_worker::NetworkWorker};
     use massa_logging::massa_trace;
     use massa_models::{
         block::{BlockId, WrappedHeader},
@@ -87,7 +87,8 @@ pub mod event_impl {
         wrapped::Id,
     };
     use massa_network_exports::{AskForBlocksInfo, BlockInfoReply, NodeCommand};
-    use massa_network_exports::{NetworkError, NetworkEvent};
+    use massa_network_exports::{NetworkError, NetworkEvent, PeerBehavior, SignedPeerRecord};
+    use massa_time::MassaTime;
     use std::net::IpAddr;
     use tracing::{debug, info};
     macro_rules! evt_failed {
@@ -187,6 +188,77 @@ pub mod event_impl {
         Ok(())
     }
 
+    /// The node worker signals that it received signed peer records from a node.
+    ///
+    /// Records with an invalid signature get the node penalized.
+    /// Stale records and records of incompatible nodes are ignored,
+    /// the others are merged in the peer database.
+    pub async fn on_received_peer_records(
+        worker: &mut NetworkWorker,
+        from: NodeId,
+        records: Vec<SignedPeerRecord>,
+    ) -> Result<(), NetworkError> {
+        debug!(
+            "node_id={} sent us peer records ({} records)",
+            from,
+            records.len()
+        );
+        massa_trace!("peer_records_received", {
+            "node_id": from,
+            "records": records
+        });
+        let now = MassaTime::now(worker.peer_info_db.clock_compensation)?;
+        let max_age = worker.peer_info_db.network_settings.peer_record_max_age;
+        let mut invalid_signature = false;
+        let mut valid_records = Vec::with_capacity(records.len());
+        for signed in records {
+            if signed.verify_signature().is_err() {
+                invalid_signature = true;
+            } else if signed.record.is_fresh(max_age, now)
+                && worker.version.is_compatible(&signed.record.version)
+            {
+                valid_records.push(signed);
+            }
+        }
+        worker.peer_info_db.merge_peer_records(valid_records)?;
+        if invalid_signature {
+            debug!(
+                "node_id={} sent us peer records with invalid signatures",
+                from
+            );
+            on_node_behaviors_cmd(worker, vec![(from, PeerBehavior::InvalidData)]).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn on_asked_peer_records(
+        worker: &mut NetworkWorker,
+        from: NodeId,
+    ) -> Result<(), NetworkError> {
+        debug!("node_id={} asked us for peer records", from);
+        massa_trace!("node_asked_peer_records", { "node_id": from });
+        let own_records = worker.sign_own_peer_records()?;
+        let records = worker
+            .peer_info_db
+            .get_advertisable_peer_records(own_records);
+        if let Some((_, node_command_tx)) = worker.active_nodes.get(&from) {
+            let res = node_command_tx
+                .send(NodeCommand::SendPeerRecords(records))
+                .await;
+            if res.is_err() {
+                debug!(
+                    "{}",
+                    NetworkError::ChannelError("node command send send_peer_records failed".into(),)
+                );
+            }
+        } else {
+            massa_trace!("node asked us for peer records and disappeared", {
+                "node_id": from
+            })
+        }
+        Ok(())
+    }
+
     /// The node worker signal that he received some full `operations` from a
     /// node.
     ///
@@ -279,4 +351,17 @@ pub mod event_impl {
             evt_failed!(err)
         }
     }
+
+    /// The node worker signals that the node exceeded our rate limits:
+    /// penalize the node, which gets banned if its reputation falls too low.
+    pub async fn on_rate_limit_exceeded(
+        worker: &mut NetworkWorker,
+        from: NodeId,
+    ) -> Result<(), NetworkError> {
+        massa_trace!("network_worker.on_node_event receive NodeEventType::RateLimitExceeded", {
+            "node": from
+        });
+        debug!("node_id={} exceeded our rate limits", from);
+        on_node_behaviors_cmd(worker, vec![(from, PeerBehavior::RateLimitExceeded)]).await
+    }
 }
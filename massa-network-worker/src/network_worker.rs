@@ -9,6 +9,7 @@ use crate::{
     handshake_worker::HandshakeWorker,
     messages::{Message, MessageDeserializer},
     network_event::EventSender,
+    traffic::TrafficCounters,
 };
 use futures::{stream::FuturesUnordered, StreamExt};
 use massa_logging::massa_trace;
@@ -16,12 +17,15 @@ use massa_models::{node::NodeId, version::Version};
 use massa_network_exports::{
     ConnectionClosureReason, ConnectionId, Establisher, HandshakeErrorType, Listener,
     NetworkCommand, NetworkConfig, NetworkConnectionErrorType, NetworkError, NetworkEvent,
-    NetworkManagementCommand, NodeCommand, NodeEvent, NodeEventType, ReadHalf, WriteHalf,
+    NetworkManagementCommand, NodeCommand, NodeEvent, NodeEventType, PeerRecord, ReadHalf,
+    SignedPeerRecord, WriteHalf,
 };
 use massa_signature::KeyPair;
+use massa_time::MassaTime;
 use std::{
     collections::{hash_map, HashMap, HashSet},
     net::{IpAddr, SocketAddr},
+    sync::Arc,
 };
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
@@ -61,9 +65,11 @@ pub struct NetworkWorker {
     /// Map of connection to ip, `is_outgoing`.
     pub(crate) active_connections: HashMap<ConnectionId, (IpAddr, bool)>,
     /// Node version
-    version: Version,
+    pub(crate) version: Version,
     /// Event sender
     pub(crate) event: EventSender,
+    /// Traffic counters by message type, shared with the node workers
+    pub(crate) traffic: Arc<TrafficCounters>,
 }
 
 pub struct NetworkWorkerChannels {
@@ -120,6 +126,7 @@ impl NetworkWorker {
             node_worker_handles: FuturesUnordered::new(),
             active_connections: HashMap::new(),
             version,
+            traffic: Arc::new(TrafficCounters::new()),
         }
     }
 
@@ -145,7 +152,11 @@ impl NetworkWorker {
                         .establisher
                         .get_connector(self.cfg.connect_timeout)
                         .await?;
-                    let addr = SocketAddr::new(ip, self.cfg.protocol_port);
+                    // peers advertising their address in a signed record may use another port
+                    let addr = self.peer_info_db.peers.get(&ip).map_or_else(
+                        || SocketAddr::new(ip, self.cfg.protocol_port),
+                        |peer| peer.get_addr(self.cfg.protocol_port),
+                    );
                     out_connecting_futures.push(async move {
                         match connector.connect(addr).await {
                             Ok((reader, writer)) => (addr.ip(), Ok((reader, writer))),
@@ -311,6 +322,24 @@ impl NetworkWorker {
         Ok(())
     }
 
+    /// Signs a peer record for each of the addresses we advertise
+    pub(crate) fn sign_own_peer_records(&self) -> Result<Vec<SignedPeerRecord>, NetworkError> {
+        let last_seen = MassaTime::now(self.peer_info_db.clock_compensation)?;
+        self.cfg
+            .get_advertised_addrs()
+            .into_iter()
+            .map(|addr| {
+                let record = PeerRecord {
+                    node_id: self.self_node_id,
+                    addr,
+                    last_seen,
+                    version: self.version,
+                };
+                SignedPeerRecord::new_signed(record, &self.keypair)
+            })
+            .collect()
+    }
+
     /// Manages finished handshakes.
     /// Only used by the worker.
     ///
@@ -327,7 +356,7 @@ impl NetworkWorker {
         });
         match outcome {
             // a handshake finished, and succeeded
-            Ok((new_node_id, socket_reader, socket_writer)) => {
+            Ok((new_node_id, socket_reader, socket_writer, new_node_version)) => {
                 debug!(
                     "handshake with connection_id={} succeeded => node_id={}",
                     new_connection_id, new_node_id
@@ -380,6 +409,7 @@ impl NetworkWorker {
                                 .ok_or_else(|| {
                                     NetworkError::ActiveConnectionMissing(new_connection_id)
                                 })?;
+                        self.peer_info_db.peer_identified(ip, new_node_id)?;
                         self.peer_info_db.peer_alive(ip)?;
 
                         // spawn node_controller_fn
@@ -387,14 +417,17 @@ impl NetworkWorker {
                             mpsc::channel::<NodeCommand>(self.cfg.node_command_channel_size);
                         let node_event_tx_clone = self.event.clone_node_sender();
                         let cfg_copy = self.cfg.clone();
+                        let traffic = self.traffic.clone();
                         let node_fn_handle = tokio::spawn(async move {
                             let res = NodeWorker::new(
                                 cfg_copy,
                                 new_node_id,
+                                new_node_version,
                                 socket_reader,
                                 socket_writer,
                                 node_command_rx,
                                 node_event_tx_clone,
+                                traffic,
                             )
                             .run_loop()
                             .await;
@@ -539,6 +572,9 @@ impl NetworkWorker {
             }
             NetworkCommand::NodeUnbanByIds(ids) => on_node_unban_by_ids_cmd(self, ids).await?,
             NetworkCommand::NodeUnbanByIps(ips) => on_node_unban_by_ips_cmd(self, ips).await?,
+            NetworkCommand::NodeBehaviors(behaviors) => {
+                on_node_behaviors_cmd(self, behaviors).await?
+            }
             NetworkCommand::GetStats { response_tx } => on_get_stats_cmd(self, response_tx).await,
             NetworkCommand::Whitelist(ips) => on_whitelist_cmd(self, ips).await?,
             NetworkCommand::RemoveFromWhitelist(ips) => {
@@ -622,7 +658,11 @@ impl NetworkWorker {
         match res {
             Ok((reader, writer, remote_addr)) => {
                 match self.peer_info_db.try_new_in_connection(&remote_addr.ip()) {
-                    Ok(_) => {
+                    Ok(evicted_ip) => {
+                        if let Some(evicted_ip) = evicted_ip {
+                            self.evict_in_connections(evicted_ip, remote_addr.ip())
+                                .await;
+                        }
                         let connection_id = *cur_connection_id;
                         debug!(
                             "inbound connection from addr={} succeeded => connection_id={}",
@@ -654,6 +694,34 @@ impl NetworkWorker {
         Ok(())
     }
 
+    /// Closes the in connections with `evicted_ip`,
+    /// to make room for the better scored `new_ip`.
+    async fn evict_in_connections(&mut self, evicted_ip: IpAddr, new_ip: IpAddr) {
+        debug!(
+            "max in connection count reached: evicting in connections of ip={} in favor of ip={}",
+            evicted_ip, new_ip
+        );
+        let evicted_connection_ids: HashSet<ConnectionId> = self
+            .active_connections
+            .iter()
+            .filter(|(_, (ip, is_outgoing))| !is_outgoing && ip.to_canonical() == evicted_ip)
+            .map(|(connection_id, _)| *connection_id)
+            .collect();
+        for (connection_id, node_command_tx) in self.active_nodes.values() {
+            if evicted_connection_ids.contains(connection_id)
+                && node_command_tx
+                    .send(NodeCommand::Close(ConnectionClosureReason::Normal))
+                    .await
+                    .is_err()
+            {
+                massa_trace!("network_worker.evict_in_connections", {
+                    "err": NetworkError::ChannelError("close node command send failed".into())
+                        .to_string()
+                });
+            }
+        }
+    }
+
     /// Start to mock a handshake and try to send a message with a list of
     /// peers.
     /// The function is used while `manage_in_connections()` if the current
@@ -777,6 +845,7 @@ impl NetworkWorker {
             connection_id,
             self.cfg.max_bytes_read,
             self.cfg.max_bytes_write,
+            self.cfg.require_encryption,
         ));
         Ok(())
     }
@@ -805,6 +874,12 @@ impl NetworkWorker {
             NodeEvent(from_node_id, NodeEventType::AskedPeerList) => {
                 event_impl::on_asked_peer_list(self, from_node_id).await?
             }
+            NodeEvent(from_node_id, NodeEventType::ReceivedPeerRecords(records)) => {
+                event_impl::on_received_peer_records(self, from_node_id, records).await?
+            }
+            NodeEvent(from_node_id, NodeEventType::AskedPeerRecords) => {
+                event_impl::on_asked_peer_records(self, from_node_id).await?
+            }
             NodeEvent(node, NodeEventType::ReceivedOperations(operations)) => {
                 event_impl::on_received_operations(self, node, operations).await
             }
@@ -817,6 +892,9 @@ impl NetworkWorker {
             NodeEvent(node, NodeEventType::ReceivedAskForOperations(operation_ids)) => {
                 event_impl::on_received_ask_for_operations(self, node, operation_ids).await
             }
+            NodeEvent(node, NodeEventType::RateLimitExceeded) => {
+                event_impl::on_rate_limit_exceeded(self, node).await?
+            }
         }
         Ok(())
     }
@@ -2,18 +2,25 @@
 
 use super::{
     binders::{ReadBinder, WriteBinder},
-    messages::Message,
+    messages::{Message, MessageTypeId},
+    traffic::{RateLimiter, TrafficCounters},
 };
 use itertools::Itertools;
 use massa_logging::massa_trace;
 use massa_models::{
-    config::{MAX_ENDORSEMENTS_PER_MESSAGE, NODE_SEND_CHANNEL_SIZE},
+    config::{
+        COMPACT_BLOCK_RELAY_MIN_VERSION, MAX_ENDORSEMENTS_PER_MESSAGE, NODE_SEND_CHANNEL_SIZE,
+        PEER_RECORDS_MIN_VERSION,
+    },
     node::NodeId,
+    version::Version,
     wrapped::Id,
 };
 use massa_network_exports::{
-    ConnectionClosureReason, NetworkConfig, NetworkError, NodeCommand, NodeEvent, NodeEventType,
+    AskForBlocksInfo, ConnectionClosureReason, NetworkConfig, NetworkError, NodeCommand, NodeEvent,
+    NodeEventType,
 };
+use std::{sync::Arc, time::Instant};
 use tokio::{
     sync::mpsc,
     sync::mpsc::{
@@ -31,6 +38,8 @@ pub struct NodeWorker {
     cfg: NetworkConfig,
     /// Node id associated to that worker.
     node_id: NodeId,
+    /// Version of the node, received during the handshake.
+    node_version: Version,
     /// Reader for incoming data.
     socket_reader: ReadBinder,
     /// Optional writer to send data.
@@ -39,6 +48,10 @@ pub struct NodeWorker {
     node_command_rx: mpsc::Receiver<NodeCommand>,
     /// Channel to send node events.
     node_event_tx: mpsc::Sender<NodeEvent>,
+    /// Traffic counters by message type, shared by all node workers.
+    traffic: Arc<TrafficCounters>,
+    /// Rate limits on the messages received from the node.
+    rate_limiter: RateLimiter,
 }
 
 impl NodeWorker {
@@ -47,26 +60,32 @@ impl NodeWorker {
     /// # Arguments
     /// * `cfg`: Network configuration.
     /// * `node_id`: Node id associated to that worker.
+    /// * `node_version`: Version of the node, received during the handshake.
     /// * `socket_reader`: Reader for incoming data.
     /// * `socket_writer`: Writer for sending data.
     /// * `node_command_rx`: Channel to receive node commands.
     /// * `node_event_tx`: Channel to send node events.
-    /// * `storage`: Shared storage.
-    pub fn new(
+    /// * `traffic`: Traffic counters shared by all node workers.
+    pub(crate) fn new(
         cfg: NetworkConfig,
         node_id: NodeId,
+        node_version: Version,
         socket_reader: ReadBinder,
         socket_writer: WriteBinder,
         node_command_rx: mpsc::Receiver<NodeCommand>,
         node_event_tx: mpsc::Sender<NodeEvent>,
+        traffic: Arc<TrafficCounters>,
     ) -> NodeWorker {
         NodeWorker {
+            rate_limiter: RateLimiter::new(&cfg, Instant::now()),
             cfg,
             node_id,
+            node_version,
             socket_reader,
             socket_writer_opt: Some(socket_writer),
             node_command_rx,
             node_event_tx,
+            traffic,
         }
     }
 
@@ -126,10 +145,12 @@ impl NodeWorker {
         })?;
         let write_timeout = self.cfg.message_timeout;
         let node_id_copy = self.node_id;
+        let traffic = self.traffic.clone();
         let node_writer_handle = tokio::spawn(async move {
             loop {
                 match writer_command_rx.recv().await {
                     Some(to_send) => {
+                        let message_type = MessageTypeId::from(&to_send);
                         match timeout(write_timeout.to_duration(), socket_writer.send(&to_send))
                             .await
                         {
@@ -150,6 +171,7 @@ impl NodeWorker {
                                 return Err(err);
                             }
                             Ok(Ok(id)) => {
+                                traffic.note_out(message_type, socket_writer.last_frame_size());
                                 massa_trace!("node_worker.run_loop.loop.writer_command_rx.recv.send.ok", {
                                     "node": node_id_copy, "msg_id": id,
                                 })
@@ -170,6 +192,8 @@ impl NodeWorker {
         let mut ask_peer_list_interval =
             tokio::time::interval(self.cfg.ask_peer_list_interval.to_duration());
         let mut exit_reason = ConnectionClosureReason::Normal;
+        // whether the last message received from the node exceeded our rate limits
+        let mut rate_limited = false;
         'select_loop: loop {
             /*
                 select! without the "biased" modifier will randomly select the 1st branch to check,
@@ -211,6 +235,20 @@ impl NodeWorker {
                     Ok(Some((index, msg))) => {
                         massa_trace!(
                             "node_worker.run_loop. receive self.socket_reader.next()", {"index": index});
+                        let message_type = MessageTypeId::from(&msg);
+                        let size = self.socket_reader.last_frame_size();
+                        self.traffic.note_in(message_type, size);
+                        if !self.rate_limiter.try_accept(message_type, size, Instant::now()) {
+                            self.traffic.note_dropped(message_type);
+                            massa_trace!("node_worker.run_loop. rate limit exceeded", {"node": self.node_id, "msg_type": format!("{:?}", message_type)});
+                            // penalize the node once per series of dropped messages
+                            if !rate_limited {
+                                rate_limited = true;
+                                self.send_node_event(NodeEvent(self.node_id, NodeEventType::RateLimitExceeded)).await;
+                            }
+                            continue;
+                        }
+                        rate_limited = false;
                         match msg {
                             Message::BlockHeader(header) => {
                                 massa_trace!(
@@ -234,6 +272,13 @@ impl NodeWorker {
                             Message::AskPeerList => {
                                 self.send_node_event(NodeEvent(self.node_id, NodeEventType::AskedPeerList)).await;
                             }
+                            Message::PeerRecords(records) =>  {
+                                massa_trace!("node_worker.run_loop. receive Message::PeerRecords", {"records": records, "node": self.node_id});
+                                self.send_node_event(NodeEvent(self.node_id, NodeEventType::ReceivedPeerRecords(records))).await;
+                            }
+                            Message::AskPeerRecords => {
+                                self.send_node_event(NodeEvent(self.node_id, NodeEventType::AskedPeerRecords)).await;
+                            }
                             Message::Operations(operations) => {
                                 massa_trace!(
                                     "node_worker.run_loop. receive Message::Operations: ",
@@ -288,13 +333,28 @@ impl NodeWorker {
                                 break;
                             }
                         },
+                        Some(NodeCommand::SendPeerRecords(records)) => {
+                            massa_trace!("node_worker.run_loop. send Message::PeerRecords", {"records": records, "node": self.node_id});
+                            if self.try_send_to_node(&writer_command_tx, Message::PeerRecords(records)).is_err() {
+                                break;
+                            }
+                        },
                         Some(NodeCommand::SendBlockHeader(header)) => {
                             massa_trace!("node_worker.run_loop. send Message::BlockHeader", {"hash": header.id, "node": self.node_id});
                             if self.try_send_to_node(&writer_command_tx, Message::BlockHeader(header)).is_err() {
                                 break;
                             }
                         },
-                        Some(NodeCommand::AskForBlocks(list)) => {
+                        Some(NodeCommand::AskForBlocks(mut list)) => {
+                            // older nodes do not know compact block relay: ask them for the full info instead
+                            let (min_major, min_minor) = COMPACT_BLOCK_RELAY_MIN_VERSION;
+                            if !self.node_version.is_at_least(min_major, min_minor) {
+                                for (_, info) in list.iter_mut() {
+                                    if matches!(info, AskForBlocksInfo::CompactInfo | AskForBlocksInfo::OperationsByPrefix(_)) {
+                                        *info = AskForBlocksInfo::Info;
+                                    }
+                                }
+                            }
                             // cut hash list on sub list if exceed max_ask_blocks_per_message
                             massa_trace!("node_worker.run_loop. send Message::AskForBlocks", {"hashlist": list, "node": self.node_id});
                             for to_send_list in list.chunks(self.cfg.max_ask_blocks as usize) {
@@ -380,6 +440,20 @@ impl NodeWorker {
                         }
                     };
                     trace!("after sending Message::AskPeerList from writer_command_tx in node_worker run_loop");
+
+                    // older nodes do not know signed peer records
+                    let (min_major, min_minor) = PEER_RECORDS_MIN_VERSION;
+                    if self.node_version.is_at_least(min_major, min_minor) {
+                        match writer_command_tx.try_send(Message::AskPeerRecords) {
+                            Ok(()) => {},
+                            Err(TrySendError::Full(_)) => {
+                                warn!("node channel full: {}", self.node_id);
+                            },
+                            Err(TrySendError::Closed(_)) => {
+                                return Err(NetworkError::ChannelError("writer send ask peer records failed".into()));
+                            }
+                        };
+                    }
                 }
             }
         }
@@ -3,6 +3,7 @@
 use enum_map::EnumMap;
 use itertools::Itertools;
 use massa_logging::massa_trace;
+use massa_models::node::NodeId;
 use massa_network_exports::settings::PeerTypeConnectionConfig;
 use massa_network_exports::ConnectionCount;
 use massa_network_exports::NetworkConfig;
@@ -10,6 +11,7 @@ use massa_network_exports::NetworkConnectionErrorType;
 use massa_network_exports::NetworkError;
 use massa_network_exports::PeerInfo;
 use massa_network_exports::PeerType;
+use massa_network_exports::SignedPeerRecord;
 use massa_time::MassaTime;
 use serde_json::json;
 use std::cmp::Reverse;
@@ -58,6 +60,10 @@ async fn dump_peers(
                 "last_alive": peer.last_alive,
                 "last_failure": peer.last_failure,
                 "advertised": peer.advertised,
+                "score": peer.score,
+                "score_updated_at": peer.score_updated_at,
+                "node_id": peer.node_id,
+                "record": peer.record,
             })
         })
         .collect();
@@ -102,13 +108,8 @@ pub(crate) fn cleanup_peers(
                     // avoid non-global IPs
                     return false;
                 }
-                if let Some(our_ip) = cfg.routable_ip {
-                    // avoid our own IP
-                    if ip == our_ip.to_canonical() {
-                        return false;
-                    }
-                }
-                true
+                // avoid our own IPs
+                !cfg.is_own_ip(&ip)
             })
             .take(cfg.max_peer_advertise_length as usize)
             .map(|ip| PeerInfo::new(ip, true))
@@ -129,11 +130,9 @@ pub(crate) fn cleanup_peers(
             // avoid non-global IPs
             continue;
         }
-        if let Some(our_ip) = cfg.routable_ip {
-            // avoid our own IP
-            if ip == our_ip.to_canonical() {
-                continue;
-            }
+        if cfg.is_own_ip(&ip) {
+            // avoid our own IPs
+            continue;
         }
         if p.peer_type != Default::default() || p.is_active() {
             keep_peers.push(p);
@@ -333,6 +332,49 @@ impl PeerInfoDatabase {
         self.request_dump()
     }
 
+    /// Merges signed peer records, whose signatures were checked beforehand, with our peers.
+    /// Unknown peers are added as candidates, like with `merge_candidate_peers`.
+    /// A record replaces the one of a peer if it is more recent,
+    /// and if it was signed by the node we know at the ip of the peer, if any.
+    /// A dump is requested afterwards.
+    ///
+    /// # Argument
+    /// `records`: records we are trying to merge
+    pub fn merge_peer_records(
+        &mut self,
+        records: Vec<SignedPeerRecord>,
+    ) -> Result<(), NetworkError> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let ips: Vec<IpAddr> = records
+            .iter()
+            .map(|signed| signed.record.addr.ip())
+            .collect();
+        cleanup_peers(
+            &self.network_settings,
+            &mut self.peers,
+            Some(&ips),
+            self.clock_compensation,
+            self.network_settings.ban_timeout,
+        )?;
+        for signed in records {
+            // peers that were filtered out by the cleanup are skipped
+            if let Some(peer) = self.peers.get_mut(&signed.record.addr.ip().to_canonical()) {
+                let is_newer = peer.record.map_or(true, |current| {
+                    current.record.last_seen < signed.record.last_seen
+                });
+                let is_same_node = peer
+                    .node_id
+                    .map_or(true, |node_id| node_id == signed.record.node_id);
+                if is_newer && is_same_node {
+                    peer.record = Some(signed);
+                }
+            }
+        }
+        self.request_dump()
+    }
+
     ////////////////////////////////
     // high level peer management //
     ////////////////////////////////
@@ -345,6 +387,9 @@ impl PeerInfoDatabase {
             if let Some(peer) = self.peers.get_mut(&ip) {
                 update_happened = update_happened || peer.banned;
                 peer.banned = false;
+                // give the peer a fresh start, otherwise it would be banned again on its next misbehavior
+                peer.score = 0.0;
+                peer.score_updated_at = None;
             }
         }
         self.update()?;
@@ -478,6 +523,28 @@ impl PeerInfoDatabase {
         self.request_dump()
     }
 
+    /// Notes the id of the node we completed a handshake with at `ip`.
+    /// The record of the peer is forgotten if it was signed by another node.
+    /// Requests a dump.
+    ///
+    /// # Arguments
+    /// * ip : ip address of the considered peer.
+    /// * node_id : id of the node
+    pub fn peer_identified(&mut self, ip: &IpAddr, node_id: NodeId) -> Result<(), NetworkError> {
+        let ip = ip.to_canonical();
+        let peer = self.peers.get_mut(&ip).ok_or_else(|| {
+            NetworkError::PeerConnectionError(NetworkConnectionErrorType::PeerInfoNotFoundError(ip))
+        })?;
+        peer.node_id = Some(node_id);
+        if peer
+            .record
+            .map_or(false, |signed| signed.record.node_id != node_id)
+        {
+            peer.record = None;
+        }
+        self.request_dump()
+    }
+
     /// Sets the peer status as failed.
     /// Requests a dump.
     ///
@@ -496,6 +563,26 @@ impl PeerInfoDatabase {
         self.request_dump()
     }
 
+    /// Changes the reputation score of the peer by `change`, after decaying it.
+    /// Requests a dump.
+    ///
+    /// # Arguments
+    /// * ip : ip address of the considered peer.
+    /// * change : score change
+    ///
+    /// Returns the new score of the peer.
+    pub fn change_peer_score(&mut self, ip: &IpAddr, change: f64) -> Result<f64, NetworkError> {
+        let ip = ip.to_canonical();
+        let now = MassaTime::now(self.clock_compensation)?;
+        let peer = self.peers.get_mut(&ip).ok_or_else(|| {
+            NetworkError::PeerConnectionError(NetworkConnectionErrorType::PeerInfoNotFoundError(ip))
+        })?;
+        peer.change_score(change, self.network_settings.peer_score_half_life, now);
+        let score = peer.score;
+        self.request_dump()?;
+        Ok(score)
+    }
+
     /// Sets that the peer is banned now.
     /// If the peer is not active, the database is cleaned up.
     /// A dump is requested.
@@ -697,14 +784,19 @@ impl PeerInfoDatabase {
     }
 
     /// An ip has successfully connected to us.
-    /// returns true if some in slots for connections are left.
     /// If the corresponding peer exists, it is updated,
     /// otherwise it is created (not advertised).
     /// A dump is requested.
     ///
+    /// If there is no in slot left for the peer type, the in connections
+    /// of the worst scored peer are evicted to make room for the new one,
+    /// provided that its score is strictly lower than the score of the new peer.
+    ///
     /// # Argument
     /// * ip : ip address of the considered peer.
-    pub fn try_new_in_connection(&mut self, ip: &IpAddr) -> Result<(), NetworkError> {
+    ///
+    /// Returns the ip address of the peer whose in connections must be closed, if any.
+    pub fn try_new_in_connection(&mut self, ip: &IpAddr) -> Result<Option<IpAddr>, NetworkError> {
         let ip = ip.to_canonical();
         // try to create a new input connection, return false if no slots
         if !ip.is_global() || self.network_settings.max_in_connections_per_ip == 0 {
@@ -712,14 +804,12 @@ impl PeerInfoDatabase {
                 NetworkConnectionErrorType::MaxPeersConnectionReached(ip),
             ));
         }
-        if let Some(our_ip) = self.network_settings.routable_ip {
-            // avoid our own IP
-            if ip == our_ip.to_canonical() {
-                warn!("incoming connection from our own IP");
-                return Err(NetworkError::PeerConnectionError(
-                    NetworkConnectionErrorType::SelfConnection,
-                ));
-            }
+        if self.network_settings.is_own_ip(&ip) {
+            // avoid our own IPs
+            warn!("incoming connection from our own IP");
+            return Err(NetworkError::PeerConnectionError(
+                NetworkConnectionErrorType::SelfConnection,
+            ));
         }
 
         let peer_type = self
@@ -728,12 +818,20 @@ impl PeerInfoDatabase {
             .or_insert_with(|| PeerInfo::new(ip, false))
             .peer_type;
 
-        // we need to first check if there is a global slot available
-        if self.is_max_in_connection_count_reached(peer_type) {
-            return Err(NetworkError::PeerConnectionError(
-                NetworkConnectionErrorType::MaxPeersConnectionReached(ip),
-            ));
-        }
+        // we need to first check if there is a global slot available,
+        // or a worse peer to evict
+        let evicted_ip = if self.is_max_in_connection_count_reached(peer_type) {
+            Some(
+                self.get_in_connection_eviction_candidate(&ip, peer_type)?
+                    .ok_or_else(|| {
+                        NetworkError::PeerConnectionError(
+                            NetworkConnectionErrorType::MaxPeersConnectionReached(ip),
+                        )
+                    })?,
+            )
+        } else {
+            None
+        };
 
         let peer_type = {
             let peer = self.peers.get_mut(&ip).ok_or_else(|| {
@@ -764,14 +862,46 @@ impl PeerInfoDatabase {
 
         self.increase_global_active_in_connection_count(peer_type)?;
         self.request_dump()?;
-        Ok(())
+        Ok(evicted_ip)
+    }
+
+    /// Finds the peer whose in connections can be evicted to make room for `ip`:
+    /// the peer of type `peer_type` with in connections and the lowest score,
+    /// if that score is strictly lower than the score of `ip`.
+    ///
+    /// No peer is evicted while the max in connection count is already exceeded,
+    /// that is while a previous eviction is still pending.
+    fn get_in_connection_eviction_candidate(
+        &self,
+        ip: &IpAddr,
+        peer_type: PeerType,
+    ) -> Result<Option<IpAddr>, NetworkError> {
+        if self.peer_types_connection_count[peer_type].active_in_connections
+            > self.network_settings.peer_types_config[peer_type].max_in_connections
+        {
+            return Ok(None);
+        }
+        let now = MassaTime::now(self.clock_compensation)?;
+        let half_life = self.network_settings.peer_score_half_life;
+        let score = self
+            .peers
+            .get(ip)
+            .map_or(0.0, |p| p.get_score(half_life, now));
+        Ok(self
+            .peers
+            .values()
+            .filter(|p| p.peer_type == peer_type && p.active_in_connections > 0 && &p.ip != ip)
+            .map(|p| (p.ip, p.get_score(half_life, now)))
+            .filter(|(_, peer_score)| *peer_score < score)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(peer_ip, _)| peer_ip))
     }
 
     ////////////////////
     // public getters //
     ////////////////////
 
-    /// Sorts peers by `( last_failure, rev(last_success) )`
+    /// Sorts peers by `( rev(score), last_failure, rev(last_success) )`, alternating between IPv4 and IPv6 peers,
     /// and returns as many peers as there are available slots to attempt outgoing connections to.
     pub fn get_out_connection_candidate_ips(&self) -> Result<Vec<IpAddr>, NetworkError> {
         let mut connections = vec![];
@@ -805,16 +935,36 @@ impl PeerInfoDatabase {
             .copied()
             .collect();
         sorted_peers.sort_unstable_by_key(|&p| (std::cmp::Reverse(p.last_alive), p.last_failure));
-        let mut sorted_ips: Vec<IpAddr> = sorted_peers
+        // our own IPs first
+        self.network_settings
+            .get_advertised_addrs()
             .into_iter()
+            .map(|addr| addr.ip())
+            .unique()
+            .chain(sorted_peers.into_iter().map(|p| p.ip))
             .take(self.network_settings.max_peer_advertise_length as usize)
-            .map(|p| p.ip)
+            .collect()
+    }
+
+    /// Returns the signed records of advertisable peers, sorted like `get_advertisable_peer_ips`.
+    /// Only the records signed by the node we know at the ip of their peer are returned,
+    /// preceded by `own_records`.
+    pub fn get_advertisable_peer_records(
+        &self,
+        own_records: Vec<SignedPeerRecord>,
+    ) -> Vec<SignedPeerRecord> {
+        let mut sorted_peers: Vec<PeerInfo> = self
+            .peers
+            .values()
+            .filter(|&p| p.advertised && !p.banned && p.has_verified_record())
+            .copied()
             .collect();
-        if let Some(our_ip) = self.network_settings.routable_ip {
-            sorted_ips.insert(0, our_ip.to_canonical());
-            sorted_ips.truncate(self.network_settings.max_peer_advertise_length as usize);
-        }
-        sorted_ips
+        sorted_peers.sort_unstable_by_key(|&p| (std::cmp::Reverse(p.last_alive), p.last_failure));
+        own_records
+            .into_iter()
+            .chain(sorted_peers.into_iter().filter_map(|p| p.record))
+            .take(self.network_settings.max_peer_advertise_length as usize)
+            .collect()
     }
 
     //////////////////////////////
@@ -859,14 +1009,37 @@ impl PeerInfoDatabase {
             }
             p.is_peer_ready(self.wakeup_interval, now)
         };
+        let half_life = self.network_settings.peer_score_half_life;
         let mut res: Vec<_> = self
             .peers
             .values()
             .filter(f)
-            .take(available_slots)
+            .map(|p| (p.get_score(half_life, now), p))
             .collect();
-        res.sort_unstable_by_key(|&p| (p.last_failure, std::cmp::Reverse(p.last_alive)));
-        Ok(res.into_iter().map(|p| p.ip).collect())
+        // best scored peers first
+        res.sort_unstable_by(|(score_a, a), (score_b, b)| {
+            score_b.total_cmp(score_a).then_with(|| {
+                (a.last_failure, std::cmp::Reverse(a.last_alive))
+                    .cmp(&(b.last_failure, std::cmp::Reverse(b.last_alive)))
+            })
+        });
+        // alternate between IPv4 and IPv6 peers, starting with the family of the best one,
+        // so that the connections of dual-stack nodes are balanced between families
+        let starts_with_ipv6 = res.first().map_or(false, |(_, p)| p.ip.is_ipv6());
+        let (ipv6, ipv4): (Vec<IpAddr>, Vec<IpAddr>) = res
+            .into_iter()
+            .map(|(_, p)| p.ip)
+            .partition(IpAddr::is_ipv6);
+        let (first, second) = if starts_with_ipv6 {
+            (ipv6, ipv4)
+        } else {
+            (ipv4, ipv6)
+        };
+        Ok(first
+            .into_iter()
+            .interleave(second)
+            .take(available_slots)
+            .collect())
     }
 
     fn get_peer_type(&self, ip: &IpAddr) -> Option<PeerType> {
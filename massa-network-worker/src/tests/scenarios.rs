@@ -2,9 +2,11 @@
 
 // To start alone RUST_BACKTRACE=1 cargo test -- --nocapture --test-threads=1
 use super::tools;
-use crate::messages::{Message, MessageDeserializer};
+use crate::handshake_worker::HandshakeWorker;
+use crate::messages::{Message, MessageDeserializer, MessageSerializer};
 use crate::node_worker::NodeWorker;
 use crate::tests::tools::{get_dummy_block_id, get_transaction};
+use crate::traffic::TrafficCounters;
 use crate::NetworkError;
 use crate::NetworkEvent;
 use crate::{
@@ -19,28 +21,35 @@ use massa_models::config::{
     MAX_DATASTORE_VALUE_LENGTH, MAX_ENDORSEMENTS_PER_MESSAGE, MAX_FUNCTION_NAME_LENGTH,
     MAX_MESSAGE_SIZE, MAX_OPERATIONS_PER_BLOCK, MAX_OPERATIONS_PER_MESSAGE,
     MAX_OPERATION_DATASTORE_ENTRY_COUNT, MAX_OPERATION_DATASTORE_KEY_LENGTH,
-    MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_PARAMETERS_SIZE, THREAD_COUNT,
+    MAX_OPERATION_DATASTORE_VALUE_LENGTH, MAX_PARAMETERS_SIZE, THREAD_COUNT, VERSION,
 };
 use massa_models::{
     block::BlockId,
     endorsement::{Endorsement, EndorsementSerializer},
     node::NodeId,
     slot::Slot,
+    version::Version,
     wrapped::WrappedContent,
 };
-use massa_network_exports::{settings::PeerTypeConnectionConfig, NodeCommand, NodeEvent};
+use massa_network_exports::{
+    settings::PeerTypeConnectionConfig, NodeCommand, NodeEvent, NodeEventType, RateLimit,
+};
 use massa_network_exports::{
     AskForBlocksInfo, BlockInfoReply, ConnectionClosureReason, ConnectionId, HandshakeErrorType,
-    PeerInfo, PeerType,
+    PeerInfo, PeerRecord, PeerType, SignedPeerRecord,
 };
+use massa_serialization::{DeserializeError, Deserializer, Serializer};
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use serial_test::serial;
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
     time::{Duration, Instant},
 };
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::mpsc;
 use tokio::time::sleep;
 use tracing::trace;
@@ -108,10 +117,12 @@ async fn test_node_worker_shutdown() {
         NodeWorker::new(
             network_conf,
             mock_node_id,
+            Version::from_str("TEST.1.10").unwrap(),
             reader,
             writer,
             node_command_rx,
             node_event_tx,
+            Arc::new(TrafficCounters::new()),
         )
         .run_loop()
         .await
@@ -183,10 +194,12 @@ async fn test_node_worker_operations_message() {
         NodeWorker::new(
             network_conf,
             mock_node_id,
+            Version::from_str("TEST.1.10").unwrap(),
             reader,
             writer,
             node_command_rx,
             node_event_tx,
+            Arc::new(TrafficCounters::new()),
         )
         .run_loop()
         .await
@@ -220,6 +233,113 @@ async fn test_node_worker_operations_message() {
 // test connecting two different peers simultaneously to the controller
 // then attempt to connect to controller from an already connected peer to test max_in_connections_per_ip
 // then try to connect a third peer to test max_in_connection
+/// Test that a node worker drops the messages exceeding the rate limits,
+/// counts them, and reports the node once per series of dropped messages.
+#[tokio::test]
+#[serial]
+async fn test_node_worker_rate_limit() {
+    let bind_port: u16 = 50_000;
+    let temp_peers_file = super::tools::generate_peers_file(&[]);
+    let mut network_conf = NetworkConfig::scenarios_default(bind_port, temp_peers_file.path());
+    network_conf.message_type_rate_limits.insert(
+        "AskPeerList".into(),
+        RateLimit {
+            rate: 0.0,
+            burst: 1.0,
+        },
+    );
+    let (duplex_controller, duplex_mock) = tokio::io::duplex(1024);
+    let (duplex_controller_read, duplex_controller_write) = tokio::io::split(duplex_controller);
+    let (_duplex_mock_read, duplex_mock_write) = tokio::io::split(duplex_mock);
+    let reader = ReadBinder::new(
+        duplex_controller_read,
+        f64::INFINITY,
+        MAX_MESSAGE_SIZE,
+        MessageDeserializer::new(
+            THREAD_COUNT,
+            ENDORSEMENT_COUNT,
+            MAX_ADVERTISE_LENGTH,
+            MAX_ASK_BLOCKS_PER_MESSAGE,
+            MAX_OPERATIONS_PER_BLOCK,
+            MAX_OPERATIONS_PER_MESSAGE,
+            MAX_ENDORSEMENTS_PER_MESSAGE,
+            MAX_DATASTORE_VALUE_LENGTH,
+            MAX_FUNCTION_NAME_LENGTH,
+            MAX_PARAMETERS_SIZE,
+            MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+            MAX_OPERATION_DATASTORE_KEY_LENGTH,
+            MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        ),
+    );
+    let writer = WriteBinder::new(duplex_controller_write, f64::INFINITY, MAX_MESSAGE_SIZE);
+    let mut mock_writer = WriteBinder::new(duplex_mock_write, f64::INFINITY, MAX_MESSAGE_SIZE);
+
+    let (node_command_tx, node_command_rx) = mpsc::channel::<NodeCommand>(1);
+    let (node_event_tx, mut node_event_rx) = mpsc::channel::<NodeEvent>(10);
+
+    let keypair = KeyPair::generate();
+    let mock_node_id = NodeId(keypair.get_public_key());
+    let traffic = Arc::new(TrafficCounters::new());
+
+    let traffic_clone = traffic.clone();
+    let node_fn_handle = tokio::spawn(async move {
+        NodeWorker::new(
+            network_conf,
+            mock_node_id,
+            Version::from_str("TEST.1.10").unwrap(),
+            reader,
+            writer,
+            node_command_rx,
+            node_event_tx,
+            traffic_clone,
+        )
+        .run_loop()
+        .await
+    });
+
+    // the first peer list request is accepted, the next ones are dropped
+    for _ in 0..3 {
+        mock_writer.send(&Message::AskPeerList).await.unwrap();
+    }
+    mock_writer
+        .send(&Message::PeerList(vec![IpAddr::V4(Ipv4Addr::new(
+            169, 202, 0, 11,
+        ))]))
+        .await
+        .unwrap();
+
+    let mut events = Vec::new();
+    while events.len() < 3 {
+        let NodeEvent(node_id, event) =
+            tokio::time::timeout(Duration::from_millis(1000), node_event_rx.recv())
+                .await
+                .expect("node event not received in time")
+                .unwrap();
+        assert_eq!(node_id, mock_node_id);
+        events.push(event);
+    }
+    assert!(matches!(
+        events.as_slice(),
+        [
+            NodeEventType::AskedPeerList,
+            NodeEventType::RateLimitExceeded,
+            NodeEventType::ReceivedPeerList(_)
+        ]
+    ));
+
+    let stats = traffic.get_stats();
+    assert_eq!(stats["AskPeerList"].messages_in, 3);
+    assert_eq!(stats["AskPeerList"].messages_dropped, 2);
+    assert_eq!(stats["PeerList"].messages_in, 1);
+    assert_eq!(stats["PeerList"].messages_dropped, 0);
+
+    node_command_tx
+        .send(NodeCommand::Close(ConnectionClosureReason::Normal))
+        .await
+        .unwrap();
+    node_fn_handle.await.unwrap().unwrap();
+}
+
 #[tokio::test]
 #[serial]
 async fn test_multiple_connections_to_controller() {
@@ -628,6 +748,10 @@ async fn test_advertised_and_wakeup_interval() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        score: 0.0,
+        score_updated_at: None,
+        node_id: None,
+        record: None,
     }]);
     let network_conf = NetworkConfig {
         wakeup_interval: MassaTime::from_millis(500),
@@ -738,6 +862,100 @@ async fn test_advertised_and_wakeup_interval() {
     .await;
 }
 
+/// Test that the controller connects to the peers advertised in signed records,
+/// on the port of their record, and ignores the records with an invalid signature.
+#[tokio::test]
+#[serial]
+async fn test_peer_records_discovery() {
+    // test config
+    let bind_port: u16 = 50_000;
+    let mock_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 12)), bind_port);
+    let advertised_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 21)), 4242);
+    let forged_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(169, 202, 0, 22)), 4242);
+    let temp_peers_file = super::tools::generate_peers_file(&[]);
+    let network_conf = NetworkConfig {
+        wakeup_interval: MassaTime::from_millis(500),
+        ..NetworkConfig::scenarios_default(bind_port, temp_peers_file.path())
+    };
+
+    tools::network_test(
+        network_conf.clone(),
+        temp_peers_file,
+        async move |_network_command_sender,
+                    mut network_event_receiver,
+                    network_manager,
+                    mut mock_interface| {
+            let (_conn_id, conn_r, mut conn_w) = tools::full_connection_to_controller(
+                &mut network_event_receiver,
+                &mut mock_interface,
+                mock_addr,
+                1_000u64,
+                1_000u64,
+                1_000u64,
+                ConnectionId(0),
+            )
+            .await;
+            let conn_drain = tools::incoming_message_drain_start(conn_r).await;
+
+            // send a valid record, and one that is not signed by its node
+            let now = MassaTime::now(0).unwrap();
+            let version = Version::from_str("TEST.1.10").unwrap();
+            let keypair = KeyPair::generate();
+            let advertised_record = SignedPeerRecord::new_signed(
+                PeerRecord {
+                    node_id: NodeId(keypair.get_public_key()),
+                    addr: advertised_addr,
+                    last_seen: now,
+                    version,
+                },
+                &keypair,
+            )
+            .unwrap();
+            let forged_record = SignedPeerRecord::new_signed(
+                PeerRecord {
+                    node_id: NodeId(keypair.get_public_key()),
+                    addr: forged_addr,
+                    last_seen: now,
+                    version,
+                },
+                &KeyPair::generate(),
+            )
+            .unwrap();
+            conn_w
+                .send(&Message::PeerRecords(vec![
+                    advertised_record,
+                    forged_record,
+                ]))
+                .await
+                .expect("could not send peer records");
+
+            // the controller only tries to connect to the advertised address
+            for _ in 0..2 {
+                let (_, _, addr, accept_tx) = tokio::time::timeout(
+                    Duration::from_millis(1500),
+                    mock_interface.wait_connection_attempt_from_controller(),
+                )
+                .await
+                .expect("wait_connection_attempt_from_controller timed out")
+                .expect("wait_connection_attempt_from_controller failed");
+                assert_eq!(
+                    addr, advertised_addr,
+                    "unexpected connection attempt address"
+                );
+                accept_tx.send(false).expect("accept_tx failed");
+            }
+
+            (
+                network_event_receiver,
+                network_manager,
+                mock_interface,
+                vec![conn_drain],
+            )
+        },
+    )
+    .await;
+}
+
 #[tokio::test]
 #[serial]
 async fn test_block_not_found() {
@@ -763,6 +981,10 @@ async fn test_block_not_found() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        score: 0.0,
+        score_updated_at: None,
+        node_id: None,
+        record: None,
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -945,6 +1167,10 @@ async fn test_retry_connection_closed() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        score: 0.0,
+        score_updated_at: None,
+        node_id: None,
+        record: None,
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -1044,6 +1270,10 @@ async fn test_operation_messages() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        score: 0.0,
+        score_updated_at: None,
+        node_id: None,
+        record: None,
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -1159,6 +1389,10 @@ async fn test_endorsements_messages() {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        score: 0.0,
+        score_updated_at: None,
+        node_id: None,
+        record: None,
     }]);
     let network_conf = NetworkConfig {
         peer_types_config: default_testing_peer_type_enum_map(),
@@ -1269,3 +1503,536 @@ async fn test_endorsements_messages() {
     )
     .await;
 }
+
+/// Relays the frames read from `reader` to `writer` until the connection closes.
+/// Each frame is passed to `tamper` along with its index, which returns the frames to forward instead.
+/// Returns the frames as they were read.
+fn relay_frames<F>(
+    mut reader: tokio::io::ReadHalf<tokio::io::DuplexStream>,
+    mut writer: tokio::io::WriteHalf<tokio::io::DuplexStream>,
+    mut tamper: F,
+) -> tokio::task::JoinHandle<Vec<Vec<u8>>>
+where
+    F: FnMut(usize, Vec<u8>) -> Vec<Vec<u8>> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut frames = Vec::new();
+        loop {
+            // with the default max message size, frame sizes are encoded on 4 bytes
+            let mut size = [0u8; 4];
+            if reader.read_exact(&mut size).await.is_err() {
+                break;
+            }
+            let mut frame = vec![0u8; u32::from_be_bytes(size) as usize];
+            if reader.read_exact(&mut frame).await.is_err() {
+                break;
+            }
+            for forwarded in tamper(frames.len(), frame.clone()) {
+                let size = (forwarded.len() as u32).to_be_bytes();
+                if writer.write_all(&size).await.is_err()
+                    || writer.write_all(&forwarded).await.is_err()
+                {
+                    return frames;
+                }
+            }
+            frames.push(frame);
+        }
+        frames
+    })
+}
+
+/// Serializes a message as it is sent on a cleartext connection
+fn serialize_message(msg: &Message) -> Vec<u8> {
+    let mut buf = Vec::new();
+    MessageSerializer::new().serialize(msg, &mut buf).unwrap();
+    buf
+}
+
+/// Rewrites the version announced in a handshake initiation frame,
+/// and its random bytes with `random_bytes` if any
+fn rewrite_handshake_initiation(
+    frame: Vec<u8>,
+    new_version: &str,
+    random_bytes: Option<[u8; 32]>,
+) -> Vec<u8> {
+    let deserializer = MessageDeserializer::new(
+        THREAD_COUNT,
+        ENDORSEMENT_COUNT,
+        MAX_ADVERTISE_LENGTH,
+        MAX_ASK_BLOCKS_PER_MESSAGE,
+        MAX_OPERATIONS_PER_BLOCK,
+        MAX_OPERATIONS_PER_MESSAGE,
+        MAX_ENDORSEMENTS_PER_MESSAGE,
+        MAX_DATASTORE_VALUE_LENGTH,
+        MAX_FUNCTION_NAME_LENGTH,
+        MAX_PARAMETERS_SIZE,
+        MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+        MAX_OPERATION_DATASTORE_KEY_LENGTH,
+        MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+    );
+    match deserializer
+        .deserialize::<DeserializeError>(&frame)
+        .unwrap()
+        .1
+    {
+        Message::HandshakeInitiation {
+            public_key,
+            random_bytes: original_random_bytes,
+            ..
+        } => serialize_message(&Message::HandshakeInitiation {
+            public_key,
+            random_bytes: random_bytes.unwrap_or(original_random_bytes),
+            version: Version::from_str(new_version).unwrap(),
+        }),
+        _ => panic!("unexpected message"),
+    }
+}
+
+/// Peer of a handshake through a relay
+struct RelayedPeer {
+    node_id: NodeId,
+    handshake:
+        tokio::task::JoinHandle<(ConnectionId, crate::handshake_worker::HandshakeReturnType)>,
+    /// frames sent by the peer, as read by the relay
+    sent_frames: tokio::task::JoinHandle<Vec<Vec<u8>>>,
+}
+
+/// Starts a handshake between two peers with the given versions and encryption requirements,
+/// through a relay that passes the frames sent by each peer to `tamper_a` and `tamper_b`.
+fn relayed_handshake<F, G>(
+    (version_a, require_encryption_a): (&str, bool),
+    (version_b, require_encryption_b): (&str, bool),
+    tamper_a: F,
+    tamper_b: G,
+) -> (RelayedPeer, RelayedPeer)
+where
+    F: FnMut(usize, Vec<u8>) -> Vec<Vec<u8>> + Send + 'static,
+    G: FnMut(usize, Vec<u8>) -> Vec<Vec<u8>> + Send + 'static,
+{
+    let (duplex_a, relay_duplex_a) = tokio::io::duplex(1 << 16);
+    let (duplex_b, relay_duplex_b) = tokio::io::duplex(1 << 16);
+    let (relay_read_a, relay_write_a) = tokio::io::split(relay_duplex_a);
+    let (relay_read_b, relay_write_b) = tokio::io::split(relay_duplex_b);
+    let spawn_peer = |duplex, version: &str, require_encryption, connection_id| {
+        let (read, write) = tokio::io::split(duplex);
+        let keypair = KeyPair::generate();
+        let node_id = NodeId(keypair.get_public_key());
+        let handshake = HandshakeWorker::spawn(
+            read,
+            write,
+            node_id,
+            keypair,
+            1000.into(),
+            Version::from_str(version).unwrap(),
+            ConnectionId(connection_id),
+            f64::INFINITY,
+            f64::INFINITY,
+            require_encryption,
+        );
+        (node_id, handshake)
+    };
+    let (node_id_a, handshake_a) = spawn_peer(duplex_a, version_a, require_encryption_a, 0);
+    let (node_id_b, handshake_b) = spawn_peer(duplex_b, version_b, require_encryption_b, 1);
+    (
+        RelayedPeer {
+            node_id: node_id_a,
+            handshake: handshake_a,
+            sent_frames: relay_frames(relay_read_a, relay_write_b, tamper_a),
+        },
+        RelayedPeer {
+            node_id: node_id_b,
+            handshake: handshake_b,
+            sent_frames: relay_frames(relay_read_b, relay_write_a, tamper_b),
+        },
+    )
+}
+
+/// Forwards frames as they are
+fn forward(_index: usize, frame: Vec<u8>) -> Vec<Vec<u8>> {
+    vec![frame]
+}
+
+/// Performs a handshake between two handshake workers with the given versions,
+/// checks that messages go through in both directions afterwards,
+/// and returns the frames sent by each peer.
+async fn handshake_and_exchange(version_a: &str, version_b: &str) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+    let (peer_a, peer_b) =
+        relayed_handshake((version_a, false), (version_b, false), forward, forward);
+    let (other_id_a, mut reader_a, mut writer_a, _) = peer_a.handshake.await.unwrap().1.unwrap();
+    let (other_id_b, mut reader_b, mut writer_b, _) = peer_b.handshake.await.unwrap().1.unwrap();
+    assert_eq!(other_id_a, peer_b.node_id);
+    assert_eq!(other_id_b, peer_a.node_id);
+
+    // several messages in each direction to check that the frame counters stay in sync
+    for _ in 0..3 {
+        writer_a.send(&Message::AskPeerList).await.unwrap();
+        writer_b
+            .send(&Message::PeerList(vec![IpAddr::V4(Ipv4Addr::new(
+                169, 202, 0, 11,
+            ))]))
+            .await
+            .unwrap();
+        match reader_b.next().await.unwrap().unwrap().1 {
+            Message::AskPeerList => {}
+            _ => panic!("unexpected message"),
+        }
+        match reader_a.next().await.unwrap().unwrap().1 {
+            Message::PeerList(list) => {
+                assert_eq!(list, vec![IpAddr::V4(Ipv4Addr::new(169, 202, 0, 11))])
+            }
+            _ => panic!("unexpected message"),
+        }
+    }
+
+    // close the connection to get the frames from the relay
+    drop((reader_a, writer_a, reader_b, writer_b));
+    (
+        peer_a.sent_frames.await.unwrap(),
+        peer_b.sent_frames.await.unwrap(),
+    )
+}
+
+/// Test that peers supporting it encrypt their connection,
+/// and that they still connect in cleartext to older peers.
+#[tokio::test]
+#[serial]
+async fn test_encrypted_transport_handshake() {
+    let ask_peer_list = serialize_message(&Message::AskPeerList);
+    let peer_list = serialize_message(&Message::PeerList(vec![IpAddr::V4(Ipv4Addr::new(
+        169, 202, 0, 11,
+    ))]));
+
+    // both peers encrypt: after the handshake initiation and the key reply,
+    // the frames on the wire are not the cleartext messages and carry an authentication tag
+    let (frames_a, frames_b) = handshake_and_exchange("TEST.15.2", &VERSION.to_string()).await;
+    assert_eq!(frames_a.len(), 5);
+    assert_eq!(frames_b.len(), 5);
+    for frame in &frames_a[2..] {
+        assert_ne!(frame, &ask_peer_list);
+        assert_eq!(frame.len(), ask_peer_list.len() + 16);
+    }
+    for frame in &frames_b[2..] {
+        assert_ne!(frame, &peer_list);
+        assert_eq!(frame.len(), peer_list.len() + 16);
+        assert!(!frame.windows(4).any(|window| window == [169, 202, 0, 11]));
+    }
+    // frames encrypting the same message differ as their nonces differ
+    assert_ne!(frames_a[2], frames_a[3]);
+
+    // one peer does not support encryption: the messages are sent in cleartext
+    let (frames_a, frames_b) = handshake_and_exchange("TEST.15.2", "TEST.15.1").await;
+    assert!(frames_a[2..].iter().all(|frame| frame == &ask_peer_list));
+    assert!(frames_b[2..].iter().all(|frame| frame == &peer_list));
+    handshake_and_exchange("TEST.15.1", "TEST.15.1").await;
+}
+
+/// Test that frames that are tampered with or replayed on an encrypted connection are rejected
+#[tokio::test]
+#[serial]
+async fn test_encrypted_transport_rejects_tampered_and_replayed_frames() {
+    // flip a bit of the first frame after the handshake
+    let tamper = |index: usize, mut frame: Vec<u8>| {
+        if index == 2 {
+            frame[0] ^= 1;
+        }
+        vec![frame]
+    };
+    // replay the first frame after the handshake
+    let replay = |index: usize, frame: Vec<u8>| {
+        if index == 2 {
+            vec![frame.clone(), frame]
+        } else {
+            vec![frame]
+        }
+    };
+
+    let (peer_a, peer_b) =
+        relayed_handshake(("TEST.15.2", false), ("TEST.15.2", false), tamper, replay);
+    let (_, mut reader_a, mut writer_a, _) = peer_a.handshake.await.unwrap().1.unwrap();
+    let (_, mut reader_b, mut writer_b, _) = peer_b.handshake.await.unwrap().1.unwrap();
+
+    writer_a.send(&Message::AskPeerList).await.unwrap();
+    assert!(reader_b.next().await.is_err(), "tampered frame accepted");
+
+    writer_b.send(&Message::AskPeerList).await.unwrap();
+    match reader_a.next().await.unwrap().unwrap().1 {
+        Message::AskPeerList => {}
+        _ => panic!("unexpected message"),
+    }
+    assert!(reader_a.next().await.is_err(), "replayed frame accepted");
+}
+
+/// Test that a handshake rewritten to disable encryption is refused,
+/// and that peers requiring encryption refuse peers that don't support it.
+#[tokio::test]
+#[serial]
+async fn test_encrypted_transport_downgrade() {
+    // the versions announced by both peers are rewritten to a version without encryption
+    let downgrade = |index: usize, frame: Vec<u8>| {
+        if index == 0 {
+            vec![rewrite_handshake_initiation(frame, "TEST.15.1", None)]
+        } else {
+            vec![frame]
+        }
+    };
+    let (peer_a, peer_b) = relayed_handshake(
+        ("TEST.15.2", false),
+        ("TEST.15.2", false),
+        downgrade,
+        downgrade,
+    );
+    for peer in [peer_a, peer_b] {
+        assert!(matches!(
+            peer.handshake.await.unwrap().1,
+            Err(NetworkError::HandshakeError(
+                HandshakeErrorType::HandshakeDowngrade
+            ))
+        ));
+    }
+
+    // the random bytes announcing encryption support are rewritten as well
+    let downgrade = |index: usize, frame: Vec<u8>| {
+        if index == 0 {
+            vec![rewrite_handshake_initiation(
+                frame,
+                "TEST.15.1",
+                Some([0u8; 32]),
+            )]
+        } else {
+            vec![frame]
+        }
+    };
+    let (peer_a, peer_b) = relayed_handshake(
+        ("TEST.15.2", false),
+        ("TEST.15.2", false),
+        downgrade,
+        downgrade,
+    );
+    for peer in [peer_a, peer_b] {
+        assert!(matches!(
+            peer.handshake.await.unwrap().1,
+            Err(NetworkError::HandshakeError(
+                HandshakeErrorType::HandshakeInvalidSignature
+            ))
+        ));
+    }
+
+    // only one of the versions is rewritten to another version supporting encryption
+    let rewrite_minor = |index: usize, frame: Vec<u8>| {
+        if index == 0 {
+            vec![rewrite_handshake_initiation(
+                frame,
+                &VERSION.to_string(),
+                None,
+            )]
+        } else {
+            vec![frame]
+        }
+    };
+    let (peer_a, peer_b) = relayed_handshake(
+        ("TEST.15.2", false),
+        ("TEST.15.2", false),
+        rewrite_minor,
+        forward,
+    );
+    for peer in [peer_a, peer_b] {
+        assert!(matches!(
+            peer.handshake.await.unwrap().1,
+            Err(NetworkError::HandshakeError(
+                HandshakeErrorType::HandshakeInvalidSignature
+            ))
+        ));
+    }
+
+    // a peer requiring encryption refuses a peer that does not support it
+    let (peer_a, peer_b) =
+        relayed_handshake(("TEST.15.2", true), ("TEST.15.1", false), forward, forward);
+    assert!(matches!(
+        peer_a.handshake.await.unwrap().1,
+        Err(NetworkError::HandshakeError(
+            HandshakeErrorType::EncryptionRequired
+        ))
+    ));
+    assert!(peer_b.handshake.await.unwrap().1.is_err());
+}
+
+/// Starts a node worker connected to a mocked peer announcing `peer_version`,
+/// and returns the sender of its commands, the reader of the messages it sends to the peer and its handle
+fn start_node_worker_with_peer(
+    peer_version: &str,
+) -> (
+    mpsc::Sender<NodeCommand>,
+    ReadBinder,
+    tokio::task::JoinHandle<Result<ConnectionClosureReason, NetworkError>>,
+) {
+    let bind_port: u16 = 50_000;
+    let temp_peers_file = super::tools::generate_peers_file(&[]);
+    let network_conf = NetworkConfig::scenarios_default(bind_port, temp_peers_file.path());
+    let (duplex_controller, duplex_mock) = tokio::io::duplex(1_000_000);
+    let (duplex_controller_read, duplex_controller_write) = tokio::io::split(duplex_controller);
+    let (duplex_mock_read, _duplex_mock_write) = tokio::io::split(duplex_mock);
+    let message_deserializer = || {
+        MessageDeserializer::new(
+            THREAD_COUNT,
+            ENDORSEMENT_COUNT,
+            MAX_ADVERTISE_LENGTH,
+            MAX_ASK_BLOCKS_PER_MESSAGE,
+            MAX_OPERATIONS_PER_BLOCK,
+            MAX_OPERATIONS_PER_MESSAGE,
+            MAX_ENDORSEMENTS_PER_MESSAGE,
+            MAX_DATASTORE_VALUE_LENGTH,
+            MAX_FUNCTION_NAME_LENGTH,
+            MAX_PARAMETERS_SIZE,
+            MAX_OPERATION_DATASTORE_ENTRY_COUNT,
+            MAX_OPERATION_DATASTORE_KEY_LENGTH,
+            MAX_OPERATION_DATASTORE_VALUE_LENGTH,
+        )
+    };
+    let reader = ReadBinder::new(
+        duplex_controller_read,
+        f64::INFINITY,
+        MAX_MESSAGE_SIZE,
+        message_deserializer(),
+    );
+    let writer = WriteBinder::new(duplex_controller_write, f64::INFINITY, MAX_MESSAGE_SIZE);
+    let mut mock_reader = ReadBinder::new(
+        duplex_mock_read,
+        f64::INFINITY,
+        MAX_MESSAGE_SIZE,
+        message_deserializer(),
+    );
+
+    let (node_command_tx, node_command_rx) = mpsc::channel::<NodeCommand>(10);
+    let (node_event_tx, _node_event_rx) = mpsc::channel::<NodeEvent>(10);
+    let mock_node_id = NodeId(KeyPair::generate().get_public_key());
+    let peer_version = Version::from_str(peer_version).unwrap();
+    let node_fn_handle = tokio::spawn(async move {
+        NodeWorker::new(
+            network_conf,
+            mock_node_id,
+            peer_version,
+            reader,
+            writer,
+            node_command_rx,
+            node_event_tx,
+            Arc::new(TrafficCounters::new()),
+        )
+        .run_loop()
+        .await
+    });
+    (node_command_tx, mock_reader, node_fn_handle)
+}
+
+/// Reads the next message sent by a node worker to its mocked peer
+async fn next_message_to_peer(mock_reader: &mut ReadBinder) -> Message {
+    tokio::time::timeout(Duration::from_millis(1000), mock_reader.next())
+        .await
+        .expect("timeout while waiting for a message of the node worker")
+        .expect("could not read the message sent by the node worker")
+        .expect("connection closed by the node worker")
+        .1
+}
+
+/// Makes a node worker connected to a peer announcing `peer_version` ask for a block with `info`,
+/// and returns the info actually asked to the peer
+async fn ask_block_to_peer(peer_version: &str, info: AskForBlocksInfo) -> AskForBlocksInfo {
+    let (node_command_tx, mut mock_reader, node_fn_handle) =
+        start_node_worker_with_peer(peer_version);
+    let block_id = get_dummy_block_id("block");
+    node_command_tx
+        .send(NodeCommand::AskForBlocks(vec![(block_id, info)]))
+        .await
+        .unwrap();
+    let asked_info = loop {
+        match next_message_to_peer(&mut mock_reader).await {
+            Message::AskForBlocks(mut list) => {
+                assert_eq!(list.len(), 1);
+                let (asked_block_id, asked_info) = list.pop().unwrap();
+                assert_eq!(asked_block_id, block_id);
+                break asked_info;
+            }
+            _ => continue,
+        }
+    };
+
+    node_command_tx
+        .send(NodeCommand::Close(ConnectionClosureReason::Normal))
+        .await
+        .unwrap();
+    node_fn_handle.await.unwrap().unwrap();
+    asked_info
+}
+
+/// Test that blocks are asked compactly only to peers supporting compact block relay,
+/// and that older peers are asked for the full block info instead.
+#[tokio::test]
+#[serial]
+async fn test_compact_block_relay_peer_version() {
+    let prefix = get_transaction(50, 10).id.prefix();
+
+    // peers of our version support compact block relay and are asked compactly
+    assert!(matches!(
+        ask_block_to_peer(&VERSION.to_string(), AskForBlocksInfo::CompactInfo).await,
+        AskForBlocksInfo::CompactInfo
+    ));
+    assert!(matches!(
+        ask_block_to_peer(&VERSION.to_string(), AskForBlocksInfo::OperationsByPrefix(vec![prefix])).await,
+        AskForBlocksInfo::OperationsByPrefix(prefixes) if prefixes == vec![prefix]
+    ));
+
+    // legacy peers could not decode the compact variants: they are asked for the block info
+    for info in [
+        AskForBlocksInfo::CompactInfo,
+        AskForBlocksInfo::OperationsByPrefix(vec![prefix]),
+    ] {
+        assert!(matches!(
+            ask_block_to_peer("TEST.15.2", info).await,
+            AskForBlocksInfo::Info
+        ));
+    }
+
+    // other requests are sent as they are
+    assert!(matches!(
+        ask_block_to_peer("TEST.15.2", AskForBlocksInfo::Header).await,
+        AskForBlocksInfo::Header
+    ));
+}
+
+/// Test that peers of our version are asked for their signed peer records along with their peer list,
+/// and that older peers are only asked for their peer list.
+#[tokio::test]
+#[serial]
+async fn test_peer_records_peer_version() {
+    for (peer_version, expect_ask_records) in
+        [(VERSION.to_string(), true), ("TEST.15.2".into(), false)]
+    {
+        let (node_command_tx, mut mock_reader, node_fn_handle) =
+            start_node_worker_with_peer(&peer_version);
+
+        // the peer list is asked when the node worker starts
+        while !matches!(
+            next_message_to_peer(&mut mock_reader).await,
+            Message::AskPeerList
+        ) {}
+        // the peer records are asked right after the peer list, before the block asked afterwards
+        node_command_tx
+            .send(NodeCommand::AskForBlocks(vec![(
+                get_dummy_block_id("block"),
+                AskForBlocksInfo::Header,
+            )]))
+            .await
+            .unwrap();
+        let asked_records = match next_message_to_peer(&mut mock_reader).await {
+            Message::AskPeerRecords => true,
+            Message::AskForBlocks(_) => false,
+            other => panic!("unexpected message {:?}", other),
+        };
+        assert_eq!(asked_records, expect_ask_records);
+
+        node_command_tx
+            .send(NodeCommand::Close(ConnectionClosureReason::Normal))
+            .await
+            .unwrap();
+        node_fn_handle.await.unwrap().unwrap();
+    }
+}
@@ -3,12 +3,19 @@ use crate::{
     NetworkConfig, NetworkError,
 };
 use enum_map::enum_map;
+use massa_models::{node::NodeId, version::Version};
 use massa_network_exports::{
-    settings::PeerTypeConnectionConfig, NetworkConnectionErrorType, PeerInfo, PeerType,
+    settings::PeerTypeConnectionConfig, NetworkConnectionErrorType, PeerInfo, PeerRecord, PeerType,
+    SignedPeerRecord,
 };
+use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use serial_test::serial;
-use std::{collections::HashMap, net::IpAddr};
+use std::str::FromStr;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+};
 use tokio::sync::watch;
 
 #[tokio::test]
@@ -806,6 +813,279 @@ async fn test_cleanup_peers() {
     assert!(peers.contains_key(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 43))));
 }
 
+#[tokio::test]
+#[serial]
+async fn test_peer_scores() {
+    let peer_types_config = enum_map! {
+        PeerType::Standard => PeerTypeConnectionConfig {
+            target_out_connections: 2,
+            max_in_connections: 2,
+            max_out_attempts: 2,
+        },
+        PeerType::Bootstrap => Default::default(),
+        PeerType::WhiteListed => Default::default()
+    };
+    let network_settings = NetworkConfig {
+        peer_types_config,
+        ..Default::default()
+    };
+    let half_life = network_settings.peer_score_half_life;
+    let mut peers: HashMap<IpAddr, PeerInfo> = HashMap::new();
+
+    // advertised peers with increasing scores
+    for (i, score) in [-5.0, 0.0, 5.0].into_iter().enumerate() {
+        let mut peer = default_peer_info_not_connected(IpAddr::V4(std::net::Ipv4Addr::new(
+            169,
+            202,
+            0,
+            11 + i as u8,
+        )));
+        peer.score = score;
+        peers.insert(peer.ip, peer);
+    }
+
+    let wakeup_interval = network_settings.wakeup_interval;
+    // keep the saver receiver alive: score changes request dumps
+    let (saver_watch_tx, mut saver_watch_rx) = watch::channel(peers.clone());
+    let saver_join_handle =
+        tokio::spawn(async move { while let Ok(()) = saver_watch_rx.changed().await {} });
+
+    let mut db = PeerInfoDatabase {
+        network_settings,
+        peers,
+        saver_join_handle,
+        saver_watch_tx,
+        peer_types_connection_count: Default::default(),
+        wakeup_interval,
+        clock_compensation: 0,
+    };
+
+    // best scored peers are chosen first
+    let ip_list = db.get_out_connection_candidate_ips().unwrap();
+    assert_eq!(
+        ip_list,
+        vec![
+            IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 13)),
+            IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 12)),
+        ]
+    );
+
+    // score changes and decay
+    let ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11));
+    assert_eq!(db.change_peer_score(&ip, 15.0).unwrap(), 10.0);
+    let peer = db.peers.get(&ip).unwrap();
+    let updated_at = peer.score_updated_at.unwrap();
+    let decayed = peer.get_score(half_life, updated_at.saturating_add(half_life));
+    assert!((decayed - 5.0).abs() < 1e-9);
+    db.change_peer_score(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 99)), 1.0)
+        .expect_err("unknown peer not detected.");
+
+    // fill the in slots with the two worst scored peers
+    db.change_peer_score(&ip, -20.0).unwrap();
+    assert_eq!(db.try_new_in_connection(&ip).unwrap(), None);
+    assert_eq!(
+        db.try_new_in_connection(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 12)))
+            .unwrap(),
+        None
+    );
+
+    // a new peer scored worse than the connected ones is refused
+    let new_ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 20));
+    let mut new_peer = default_peer_info_not_connected(new_ip);
+    new_peer.score = -20.0;
+    db.peers.insert(new_ip, new_peer);
+    match db.try_new_in_connection(&new_ip) {
+        Err(NetworkError::PeerConnectionError(
+            NetworkConnectionErrorType::MaxPeersConnectionReached(ip_err),
+        )) => assert_eq!(new_ip, ip_err),
+        res => panic!("MaxPeersConnectionReached error not return: {:?}", res),
+    }
+
+    // a better scored peer evicts the worst scored one
+    assert_eq!(
+        db.try_new_in_connection(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 13)))
+            .unwrap(),
+        Some(ip)
+    );
+    assert_eq!(db.get_in_connection_count(), 3);
+
+    // no other eviction while the previous one is pending
+    db.change_peer_score(&new_ip, 70.0).unwrap();
+    db.try_new_in_connection(&new_ip)
+        .expect_err("eviction while another one is pending not detected.");
+    db.in_connection_closed(&ip).unwrap();
+    assert_eq!(db.get_in_connection_count(), 2);
+
+    // unban resets the score
+    db.peer_banned(&ip).unwrap();
+    db.unban(vec![ip]).unwrap();
+    assert_eq!(db.peers.get(&ip).unwrap().score, 0.0);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_peer_records() {
+    let network_settings = NetworkConfig {
+        max_idle_peers: 10,
+        ..Default::default()
+    };
+    let peers: HashMap<IpAddr, PeerInfo> = HashMap::new();
+    let wakeup_interval = network_settings.wakeup_interval;
+    let (saver_watch_tx, mut saver_watch_rx) = watch::channel(peers.clone());
+    let saver_join_handle =
+        tokio::spawn(async move { while let Ok(()) = saver_watch_rx.changed().await {} });
+
+    let mut db = PeerInfoDatabase {
+        network_settings,
+        peers,
+        saver_join_handle,
+        saver_watch_tx,
+        peer_types_connection_count: Default::default(),
+        wakeup_interval,
+        clock_compensation: 0,
+    };
+
+    let ip = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11));
+    let keypair_a = KeyPair::generate();
+    let keypair_b = KeyPair::generate();
+    let node_a = NodeId(keypair_a.get_public_key());
+    let node_b = NodeId(keypair_b.get_public_key());
+    let sign_record = |keypair: &KeyPair, port: u16, last_seen: u64| {
+        let record = PeerRecord {
+            node_id: NodeId(keypair.get_public_key()),
+            addr: SocketAddr::new(ip, port),
+            last_seen: MassaTime::from_millis(last_seen),
+            version: Version::from_str("TEST.1.10").unwrap(),
+        };
+        SignedPeerRecord::new_signed(record, keypair).unwrap()
+    };
+
+    // the record of an unknown peer adds it, reachable on the port of the record
+    let record_a = sign_record(&keypair_a, 4242, 1000);
+    db.merge_peer_records(vec![record_a]).unwrap();
+    let peer = db.peers.get(&ip).unwrap();
+    assert!(peer.advertised);
+    assert_eq!(peer.get_addr(31244), SocketAddr::new(ip, 4242));
+
+    // older records are ignored
+    db.merge_peer_records(vec![sign_record(&keypair_a, 4243, 500)])
+        .unwrap();
+    assert_eq!(db.peers.get(&ip).unwrap().record, Some(record_a));
+
+    // the record is only relayed once we know its node at that ip
+    assert!(db.get_advertisable_peer_records(vec![]).is_empty());
+    db.peer_identified(&ip, node_a).unwrap();
+    assert_eq!(db.get_advertisable_peer_records(vec![]), vec![record_a]);
+
+    // records of other nodes cannot replace it
+    db.merge_peer_records(vec![sign_record(&keypair_b, 4244, 2000)])
+        .unwrap();
+    assert_eq!(db.peers.get(&ip).unwrap().record, Some(record_a));
+
+    // our own record comes first
+    let own_record = sign_record(&keypair_b, 4245, 3000);
+    assert_eq!(
+        db.get_advertisable_peer_records(vec![own_record]),
+        vec![own_record, record_a]
+    );
+
+    // another node at that ip makes us forget the record
+    db.peer_identified(&ip, node_b).unwrap();
+    let peer = db.peers.get(&ip).unwrap();
+    assert_eq!(peer.record, None);
+    assert_eq!(peer.get_addr(31244), SocketAddr::new(ip, 31244));
+}
+
+#[tokio::test]
+#[serial]
+async fn test_dual_stack_peers() {
+    let own_ipv4 = IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 1));
+    let own_ipv6 = IpAddr::V6(std::net::Ipv6Addr::new(0x2a01, 0x4f8, 0, 0, 0, 0, 0, 1));
+    let mut network_settings = NetworkConfig {
+        routable_ip: Some(own_ipv4),
+        advertised_addrs: vec![
+            SocketAddr::new(own_ipv4, 4242),
+            SocketAddr::new(own_ipv6, 4242),
+            SocketAddr::new(own_ipv4, 4242),
+        ],
+        ..Default::default()
+    };
+    network_settings.peer_types_config[PeerType::Standard].max_out_attempts = 3;
+
+    // our addresses are advertised once each and our ips are never peers
+    assert_eq!(
+        network_settings.get_advertised_addrs(),
+        vec![
+            SocketAddr::new(own_ipv4, 4242),
+            SocketAddr::new(own_ipv6, 4242)
+        ]
+    );
+    assert!(network_settings.is_own_ip(&own_ipv6));
+    assert!(!network_settings.is_own_ip(&IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 2))));
+
+    // IPv4 peers were alive more recently than IPv6 ones
+    let mut peers: HashMap<IpAddr, PeerInfo> = HashMap::new();
+    let peer_ips = [
+        (IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 11)), 1000),
+        (IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 12)), 2000),
+        (IpAddr::V4(std::net::Ipv4Addr::new(169, 202, 0, 13)), 3000),
+        (
+            IpAddr::V6(std::net::Ipv6Addr::new(0x2001, 0x4860, 0, 0, 0, 0, 0, 11)),
+            4000,
+        ),
+        (
+            IpAddr::V6(std::net::Ipv6Addr::new(0x2001, 0x4860, 0, 0, 0, 0, 0, 12)),
+            5000,
+        ),
+    ];
+    for (ip, alive_ago) in peer_ips {
+        let mut peer = default_peer_info_not_connected(ip);
+        peer.last_alive = Some(
+            MassaTime::now(0)
+                .unwrap()
+                .checked_sub(MassaTime::from_millis(alive_ago))
+                .unwrap(),
+        );
+        peers.insert(ip, peer);
+    }
+    let mut own_peers: HashMap<IpAddr, PeerInfo> = HashMap::new();
+    own_peers.insert(own_ipv6, default_peer_info_not_connected(own_ipv6));
+    cleanup_peers(
+        &network_settings,
+        &mut own_peers,
+        Some(&vec![own_ipv4]),
+        0,
+        network_settings.ban_timeout,
+    )
+    .unwrap();
+    assert!(own_peers.is_empty());
+
+    let wakeup_interval = network_settings.wakeup_interval;
+    let (saver_watch_tx, _) = watch::channel(peers.clone());
+    let saver_join_handle = tokio::spawn(async move {});
+
+    let db = PeerInfoDatabase {
+        network_settings,
+        peers,
+        saver_join_handle,
+        saver_watch_tx,
+        peer_types_connection_count: Default::default(),
+        wakeup_interval,
+        clock_compensation: 0,
+    };
+
+    // our ips are advertised first
+    let ip_list = db.get_advertisable_peer_ips();
+    assert_eq!(ip_list[..2], [own_ipv4, own_ipv6]);
+    assert_eq!(ip_list.len(), 7);
+
+    // candidates alternate between families, starting with the best peer
+    assert_eq!(
+        db.get_out_connection_candidate_ips().unwrap(),
+        vec![peer_ips[0].0, peer_ips[3].0, peer_ips[1].0]
+    );
+}
+
 #[tokio::test]
 #[serial]
 async fn test() {
@@ -825,6 +1105,10 @@ fn default_peer_info_connected(ip: IpAddr) -> PeerInfo {
         active_out_connections: 1,
         active_in_connections: 0,
         banned: false,
+        score: 0.0,
+        score_updated_at: None,
+        node_id: None,
+        record: None,
     }
 }
 
@@ -839,6 +1123,10 @@ fn default_peer_info_not_connected(ip: IpAddr) -> PeerInfo {
         active_out_connections: 0,
         active_in_connections: 0,
         banned: false,
+        score: 0.0,
+        score_updated_at: None,
+        node_id: None,
+        record: None,
     }
 }
 
@@ -879,6 +1167,10 @@ impl From<u32> for PeerInfoDatabase {
                 active_out_connections: 0,
                 active_in_connections: 0,
                 banned: ip[1] % 5 == 0,
+                score: 0.0,
+                score_updated_at: None,
+                node_id: None,
+                record: None,
             };
             peers.insert(peer.ip, peer);
         }
@@ -94,6 +94,7 @@ pub async fn full_connection_to_controller(
         connection_id,
         f64::INFINITY,
         f64::INFINITY,
+        false,
     )
     .await
     .expect("handshake creation failed")
@@ -153,6 +154,7 @@ pub async fn rejected_connection_to_controller(
         connection_id,
         f64::INFINITY,
         f64::INFINITY,
+        false,
     )
     .await
     .expect("handshake creation failed")
@@ -238,6 +240,7 @@ pub async fn full_connection_from_controller(
         connection_id,
         f64::INFINITY,
         f64::INFINITY,
+        false,
     )
     .await
     .expect("handshake creation failed")
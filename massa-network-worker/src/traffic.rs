@@ -0,0 +1,247 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Traffic accounting and rate limiting of the messages exchanged with nodes
+
+use crate::messages::MessageTypeId;
+use massa_models::stats::MessageTrafficStats;
+use massa_network_exports::{NetworkConfig, RateLimit};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Token bucket enforcing a `RateLimit`
+#[derive(Debug)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket
+    fn new(limit: RateLimit, now: Instant) -> Self {
+        TokenBucket {
+            limit,
+            tokens: limit.burst,
+            last_refill: now,
+        }
+    }
+
+    /// Adds the tokens accumulated since the last refill
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.last_refill = now;
+        if elapsed > 0.0 {
+            self.tokens = (self.tokens + elapsed * self.limit.rate).min(self.limit.burst);
+        }
+    }
+
+    /// Whether the bucket holds at least `amount` tokens
+    fn has(&self, amount: f64) -> bool {
+        self.tokens >= amount
+    }
+
+    fn consume(&mut self, amount: f64) {
+        self.tokens -= amount;
+    }
+}
+
+/// Rate limits on the messages received from a node
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    /// bytes received from the node
+    bytes: TokenBucket,
+    /// messages received from the node
+    messages: TokenBucket,
+    /// messages of a given type received from the node
+    message_types: HashMap<MessageTypeId, TokenBucket>,
+}
+
+impl RateLimiter {
+    /// Creates the rate limiter of a node from the network configuration.
+    /// Message type limits whose name is not a message type are ignored.
+    pub(crate) fn new(cfg: &NetworkConfig, now: Instant) -> Self {
+        let message_types = MessageTypeId::all()
+            .filter_map(|id| {
+                cfg.message_type_rate_limits
+                    .get(&format!("{:?}", id))
+                    .map(|limit| (id, TokenBucket::new(*limit, now)))
+            })
+            .collect();
+        RateLimiter {
+            bytes: TokenBucket::new(cfg.peer_bytes_rate_limit, now),
+            messages: TokenBucket::new(cfg.peer_messages_rate_limit, now),
+            message_types,
+        }
+    }
+
+    /// Accounts for a received message.
+    ///
+    /// # Returns
+    /// false if the message exceeds one of the limits, in which case it should be dropped
+    /// and none of the limits is charged for it
+    pub(crate) fn try_accept(
+        &mut self,
+        message_type: MessageTypeId,
+        size: usize,
+        now: Instant,
+    ) -> bool {
+        let size = size as f64;
+        self.bytes.refill(now);
+        self.messages.refill(now);
+        let mut type_bucket = self.message_types.get_mut(&message_type);
+        if let Some(bucket) = type_bucket.as_mut() {
+            bucket.refill(now);
+        }
+
+        if !self.bytes.has(size)
+            || !self.messages.has(1.0)
+            || !type_bucket.as_ref().map_or(true, |bucket| bucket.has(1.0))
+        {
+            return false;
+        }
+        self.bytes.consume(size);
+        self.messages.consume(1.0);
+        if let Some(bucket) = type_bucket {
+            bucket.consume(1.0);
+        }
+        true
+    }
+}
+
+/// Traffic counters of a message type
+#[derive(Debug, Default)]
+struct MessageTrafficCounters {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    messages_in: AtomicU64,
+    messages_out: AtomicU64,
+    messages_dropped: AtomicU64,
+}
+
+/// Traffic counters by message type, shared by all the node workers
+#[derive(Debug)]
+pub(crate) struct TrafficCounters {
+    /// counters indexed by message type id
+    counters: Vec<MessageTrafficCounters>,
+}
+
+impl TrafficCounters {
+    pub(crate) fn new() -> Self {
+        TrafficCounters {
+            counters: MessageTypeId::all()
+                .map(|_| MessageTrafficCounters::default())
+                .collect(),
+        }
+    }
+
+    fn get(&self, message_type: MessageTypeId) -> &MessageTrafficCounters {
+        // will not panic: there is a counter for each message type
+        &self.counters[u32::from(message_type) as usize]
+    }
+
+    /// Notes a message received from a node
+    pub(crate) fn note_in(&self, message_type: MessageTypeId, size: usize) {
+        let counters = self.get(message_type);
+        counters.messages_in.fetch_add(1, Ordering::Relaxed);
+        counters.bytes_in.fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    /// Notes a message received from a node and dropped for exceeding rate limits
+    pub(crate) fn note_dropped(&self, message_type: MessageTypeId) {
+        self.get(message_type)
+            .messages_dropped
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Notes a message sent to a node
+    pub(crate) fn note_out(&self, message_type: MessageTypeId, size: usize) {
+        let counters = self.get(message_type);
+        counters.messages_out.fetch_add(1, Ordering::Relaxed);
+        counters.bytes_out.fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    /// Gets the counters of all message types, by message type name
+    pub(crate) fn get_stats(&self) -> BTreeMap<String, MessageTrafficStats> {
+        MessageTypeId::all()
+            .map(|id| {
+                let counters = self.get(id);
+                (
+                    format!("{:?}", id),
+                    MessageTrafficStats {
+                        bytes_in: counters.bytes_in.load(Ordering::Relaxed),
+                        bytes_out: counters.bytes_out.load(Ordering::Relaxed),
+                        messages_in: counters.messages_in.load(Ordering::Relaxed),
+                        messages_out: counters.messages_out.load(Ordering::Relaxed),
+                        messages_dropped: counters.messages_dropped.load(Ordering::Relaxed),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut cfg = NetworkConfig::default();
+        cfg.peer_bytes_rate_limit = RateLimit {
+            rate: 100.0,
+            burst: 1000.0,
+        };
+        cfg.message_type_rate_limits.insert(
+            "AskPeerList".into(),
+            RateLimit {
+                rate: 1.0,
+                burst: 2.0,
+            },
+        );
+        let now = Instant::now();
+        let mut limiter = RateLimiter::new(&cfg, now);
+
+        // the message type burst is used up, other types are not affected
+        assert!(limiter.try_accept(MessageTypeId::AskPeerList, 10, now));
+        assert!(limiter.try_accept(MessageTypeId::AskPeerList, 10, now));
+        assert!(!limiter.try_accept(MessageTypeId::AskPeerList, 10, now));
+        assert!(limiter.try_accept(MessageTypeId::PeerList, 10, now));
+
+        // the message type bucket refills over time
+        let now = now + std::time::Duration::from_secs(1);
+        assert!(limiter.try_accept(MessageTypeId::AskPeerList, 10, now));
+        assert!(!limiter.try_accept(MessageTypeId::AskPeerList, 10, now));
+
+        // byte limit: dropped messages are not charged
+        assert!(!limiter.try_accept(MessageTypeId::Operations, 2000, now));
+        assert!(limiter.try_accept(MessageTypeId::Operations, 900, now));
+        assert!(!limiter.try_accept(MessageTypeId::Operations, 500, now));
+    }
+
+    #[test]
+    fn test_traffic_counters() {
+        let counters = TrafficCounters::new();
+        counters.note_in(MessageTypeId::Operations, 100);
+        counters.note_in(MessageTypeId::Operations, 50);
+        counters.note_dropped(MessageTypeId::Operations);
+        counters.note_out(MessageTypeId::AskPeerList, 3);
+
+        let stats = counters.get_stats();
+        assert_eq!(stats.len(), MessageTypeId::all().count());
+        assert_eq!(
+            stats["Operations"],
+            MessageTrafficStats {
+                bytes_in: 150,
+                bytes_out: 0,
+                messages_in: 2,
+                messages_out: 0,
+                messages_dropped: 1,
+            }
+        );
+        assert_eq!(stats["AskPeerList"].messages_out, 1);
+        assert_eq!(stats["AskPeerList"].bytes_out, 3);
+    }
+}
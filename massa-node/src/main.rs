@@ -7,21 +7,26 @@ extern crate massa_logging;
 use crate::settings::SETTINGS;
 
 use dialoguer::Password;
-use massa_api::{APIConfig, Private, Public, RpcServer, StopHandle, API};
+use massa_api::{APIConfig, Private, PubSub, Public, RpcServer, StopHandle, API};
 use massa_async_pool::AsyncPoolConfig;
 use massa_bootstrap::{get_state, start_bootstrap_server, BootstrapConfig, BootstrapManager};
-use massa_consensus_exports::ConsensusManager;
 use massa_consensus_exports::{
     events::ConsensusEvent, settings::ConsensusChannels, ConsensusConfig, ConsensusEventReceiver,
 };
-use massa_consensus_worker::start_consensus_controller;
-use massa_execution_exports::{ExecutionConfig, ExecutionManager, StorageCostsConstants};
+use massa_consensus_exports::{BlockStoreController, ConsensusManager};
+use massa_consensus_worker::{start_consensus_controller, FinalBlockStore};
+use massa_execution_exports::{
+    ExecutionChannels, ExecutionConfig, ExecutionManager, StorageCostsConstants,
+};
 use massa_execution_worker::start_execution_worker;
-use massa_factory_exports::{FactoryChannels, FactoryConfig, FactoryManager};
+use massa_factory_exports::{
+    DefaultBlockProductionStrategy, FactoryChannels, FactoryConfig, FactoryManager,
+};
 use massa_factory_worker::start_factory;
 use massa_final_state::{FinalState, FinalStateConfig};
-use massa_ledger_exports::LedgerConfig;
-use massa_ledger_worker::FinalLedger;
+use massa_hash::Hash;
+use massa_ledger_exports::{EventStoreController, LedgerConfig};
+use massa_ledger_worker::{FinalEventStore, FinalLedger};
 use massa_logging::massa_trace;
 use massa_models::address::Address;
 use massa_models::config::constants::{
@@ -45,22 +50,23 @@ use massa_models::config::constants::{
 use massa_models::config::POOL_CONTROLLER_CHANNEL_SIZE;
 use massa_network_exports::{Establisher, NetworkConfig, NetworkManager};
 use massa_network_worker::start_network_controller;
-use massa_pool_exports::{PoolConfig, PoolManager};
+use massa_pool_exports::{PoolChannels, PoolConfig, PoolManager};
 use massa_pool_worker::start_pool_controller;
 use massa_pos_exports::{SelectorConfig, SelectorManager};
 use massa_pos_worker::start_selector_worker;
 use massa_protocol_exports::{ProtocolConfig, ProtocolManager};
 use massa_protocol_worker::start_protocol_controller;
+use massa_signature::KeyPair;
 use massa_storage::Storage;
 use massa_time::MassaTime;
-use massa_wallet::Wallet;
+use massa_wallet::{LocalSigner, RemoteSigner, Signer, Wallet};
 use parking_lot::RwLock;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{path::Path, process, sync::Arc};
 use structopt::StructOpt;
 use tokio::signal;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tracing::{error, info, warn};
 use tracing_subscriber::filter::{filter_fn, LevelFilter};
 
@@ -68,7 +74,10 @@ mod settings;
 
 async fn launch(
     node_wallet: Arc<RwLock<Wallet>>,
+    snapshot_import: Option<(PathBuf, Option<Hash>)>,
+    final_block_store: Option<Box<dyn BlockStoreController>>,
 ) -> (
+    Arc<RwLock<FinalState>>,
     ConsensusEventReceiver,
     Option<BootstrapManager>,
     ConsensusManager,
@@ -81,6 +90,7 @@ async fn launch(
     mpsc::Receiver<()>,
     StopHandle,
     StopHandle,
+    Option<StopHandle>,
 ) {
     info!("Node version : {}", *VERSION);
     if let Some(end) = *END_TIMESTAMP {
@@ -108,6 +118,7 @@ async fn launch(
     };
     let final_state_config = FinalStateConfig {
         final_history_length: SETTINGS.ledger.final_history_length,
+        reverse_history_length: SETTINGS.ledger.reverse_history_length,
         thread_count: THREAD_COUNT,
         ledger_config: ledger_config.clone(),
         periods_per_cycle: PERIODS_PER_CYCLE,
@@ -147,6 +158,26 @@ async fn launch(
         .expect("could not init final state"),
     ));
 
+    // import the final state snapshot if any
+    let snapshot_cursor = snapshot_import.map(|(path, trusted_hash)| {
+        if trusted_hash.is_none() {
+            warn!(
+                "importing final state snapshot {} without a trusted hash",
+                path.display()
+            );
+        }
+        let cursor = final_state
+            .write()
+            .import_snapshot(&path, trusted_hash)
+            .unwrap_or_else(|err| panic!("could not import final state snapshot: {}", err));
+        info!(
+            "final state snapshot {} imported at slot {}",
+            path.display(),
+            final_state.read().slot
+        );
+        cursor
+    });
+
     // interrupt signal listener
     let stop_signal = signal::ctrl_c();
     tokio::pin!(stop_signal);
@@ -189,6 +220,19 @@ async fn launch(
         max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
         max_op_datastore_value_length: MAX_OPERATION_DATASTORE_VALUE_LENGTH,
         max_changes_slot_count: SETTINGS.ledger.final_history_length as u32,
+        checkpoint_path: SETTINGS
+            .bootstrap
+            .checkpoint
+            .then(|| SETTINGS.bootstrap.checkpoint_path.clone()),
+        checkpoint_interval: SETTINGS.bootstrap.checkpoint_interval,
+        parallel_bootstrap_servers: SETTINGS.bootstrap.parallel_bootstrap_servers,
+        ip_allow_list: SETTINGS.bootstrap.ip_allow_list.clone(),
+        ip_deny_list: SETTINGS.bootstrap.ip_deny_list.clone(),
+        authorized_client_keys: SETTINGS.bootstrap.authorized_client_keys.clone(),
+        client_keypair: SETTINGS.bootstrap.client_authentication.then(|| {
+            load_bootstrap_client_keypair(&SETTINGS.bootstrap.client_keypair_path)
+                .expect("could not load bootstrap client keypair")
+        }),
     };
 
     // bootstrap
@@ -204,6 +248,7 @@ async fn launch(
             *VERSION,
             *GENESIS_TIMESTAMP,
             *END_TIMESTAMP,
+            snapshot_cursor,
         ) => match res {
             Ok(vals) => vals,
             Err(err) => panic!("critical error detected in the bootstrap process: {}", err)
@@ -212,7 +257,9 @@ async fn launch(
 
     let network_config: NetworkConfig = NetworkConfig {
         bind: SETTINGS.network.bind,
+        additional_binds: SETTINGS.network.additional_binds.clone(),
         routable_ip: SETTINGS.network.routable_ip,
+        advertised_addrs: SETTINGS.network.advertised_addrs.clone(),
         protocol_port: SETTINGS.network.protocol_port,
         connect_timeout: SETTINGS.network.connect_timeout,
         wakeup_interval: SETTINGS.network.wakeup_interval,
@@ -226,6 +273,7 @@ async fn launch(
         peers_file_dump_interval: SETTINGS.network.peers_file_dump_interval,
         message_timeout: SETTINGS.network.message_timeout,
         ask_peer_list_interval: SETTINGS.network.ask_peer_list_interval,
+        peer_record_max_age: SETTINGS.network.peer_record_max_age,
         max_send_wait: SETTINGS.network.max_send_wait,
         ban_timeout: SETTINGS.network.ban_timeout,
         peer_list_send_timeout: SETTINGS.network.peer_list_send_timeout,
@@ -233,6 +281,12 @@ async fn launch(
         max_operations_per_message: SETTINGS.network.max_operations_per_message,
         max_bytes_read: SETTINGS.network.max_bytes_read,
         max_bytes_write: SETTINGS.network.max_bytes_write,
+        require_encryption: SETTINGS.network.require_encryption,
+        peer_score_half_life: SETTINGS.network.peer_score_half_life,
+        peer_ban_score: SETTINGS.network.peer_ban_score,
+        peer_bytes_rate_limit: SETTINGS.network.peer_bytes_rate_limit,
+        peer_messages_rate_limit: SETTINGS.network.peer_messages_rate_limit,
+        message_type_rate_limits: SETTINGS.network.message_type_rate_limits.clone(),
         max_ask_blocks: MAX_ASK_BLOCKS_PER_MESSAGE,
         max_operations_per_block: MAX_OPERATIONS_PER_BLOCK,
         thread_count: THREAD_COUNT,
@@ -280,6 +334,12 @@ async fn launch(
             .checked_mul_u64(LEDGER_ENTRY_DATASTORE_BASE_SIZE as u64)
             .expect("Overflow when creating constant ledger_entry_datastore_base_size"),
     };
+    // channels broadcasting node activity to the subscription API
+    let (block_sender, _) = broadcast::channel(SETTINGS.api.broadcast_capacity);
+    let (finalized_block_sender, _) = broadcast::channel(SETTINGS.api.broadcast_capacity);
+    let (operation_sender, _) = broadcast::channel(SETTINGS.api.broadcast_capacity);
+    let (sc_output_event_sender, _) = broadcast::channel(SETTINGS.api.broadcast_capacity);
+
     // launch execution module
     let execution_config = ExecutionConfig {
         max_final_events: SETTINGS.execution.max_final_events,
@@ -302,11 +362,27 @@ async fn launch(
         max_bytecode_size: MAX_BYTECODE_LENGTH,
         max_datastore_value_size: MAX_DATASTORE_VALUE_LENGTH,
         storage_costs_constants,
+        broadcast_enabled: SETTINGS.api.enable_ws,
+        trace_executions: SETTINGS.execution.trace_executions,
+        max_execution_traces: SETTINGS.execution.max_execution_traces,
     };
+    let execution_channels = ExecutionChannels {
+        sc_output_event_sender: sc_output_event_sender.clone(),
+    };
+    let final_event_store: Option<Box<dyn EventStoreController>> =
+        if SETTINGS.ledger.persistent_event_store {
+            Some(Box::new(FinalEventStore::new(
+                SETTINGS.ledger.disk_event_store_path.clone(),
+            )))
+        } else {
+            None
+        };
     let (execution_manager, execution_controller) = start_execution_worker(
         execution_config,
         final_state.clone(),
         selector_controller.clone(),
+        execution_channels,
+        final_event_store,
     );
 
     // launch pool controller
@@ -318,11 +394,21 @@ async fn launch(
         max_block_endorsement_count: ENDORSEMENT_COUNT,
         operation_validity_periods: OPERATION_VALIDITY_PERIODS,
         max_operation_pool_size_per_thread: SETTINGS.pool.max_pool_size_per_thread,
+        max_operations_per_sender: SETTINGS.pool.max_operations_per_sender,
+        removed_operations_history_size: SETTINGS.pool.removed_operations_history_size,
         max_endorsements_pool_size_per_thread: SETTINGS.pool.max_pool_size_per_thread,
         channels_size: POOL_CONTROLLER_CHANNEL_SIZE,
+        broadcast_enabled: SETTINGS.api.enable_ws,
     };
-    let (pool_manager, pool_controller) =
-        start_pool_controller(pool_config, &shared_storage, execution_controller.clone());
+    let pool_channels = PoolChannels {
+        operation_sender: operation_sender.clone(),
+    };
+    let (pool_manager, pool_controller) = start_pool_controller(
+        pool_config,
+        &shared_storage,
+        execution_controller.clone(),
+        pool_channels,
+    );
 
     // launch protocol controller
     let protocol_config = ProtocolConfig {
@@ -354,6 +440,10 @@ async fn launch(
         t0: T0,
         max_operations_propagation_time: SETTINGS.protocol.max_operations_propagation_time,
         max_endorsements_propagation_time: SETTINGS.protocol.max_endorsements_propagation_time,
+        compact_block_relay: SETTINGS.protocol.compact_block_relay,
+        sig_verification_batch_window: SETTINGS.protocol.sig_verification_batch_window,
+        max_sig_verification_batch_size: SETTINGS.protocol.max_sig_verification_batch_size,
+        sig_verification_thread_count: SETTINGS.protocol.sig_verification_thread_count,
     };
     let (protocol_command_sender, protocol_event_receiver, protocol_manager) =
         start_protocol_controller(
@@ -388,6 +478,9 @@ async fn launch(
         max_item_return_count: SETTINGS.consensus.max_item_return_count,
         max_gas_per_block: MAX_GAS_PER_BLOCK,
         channel_size: CHANNEL_SIZE,
+        broadcast_enabled: SETTINGS.api.enable_ws,
+        block_store_archive: SETTINGS.consensus.block_store_archive,
+        block_store_retention_periods: SETTINGS.consensus.block_store_retention_periods,
     };
     // launch consensus controller
     let (consensus_command_sender, consensus_event_receiver, consensus_manager) =
@@ -399,6 +492,9 @@ async fn launch(
                 protocol_event_receiver,
                 pool_command_sender: pool_controller.clone(),
                 selector_controller: selector_controller.clone(),
+                block_sender: block_sender.clone(),
+                finalized_block_sender: finalized_block_sender.clone(),
+                final_block_store: final_block_store.clone(),
             },
             bootstrap_state.graph,
             shared_storage.clone(),
@@ -416,6 +512,7 @@ async fn launch(
         initial_delay: SETTINGS.factory.initial_delay,
         max_block_size: MAX_BLOCK_SIZE as u64,
         max_block_gas: MAX_GAS_PER_BLOCK,
+        max_block_endorsement_count: ENDORSEMENT_COUNT,
     };
     let factory_channels = FactoryChannels {
         selector: selector_controller.clone(),
@@ -424,7 +521,33 @@ async fn launch(
         protocol: protocol_command_sender.clone(),
         storage: shared_storage.clone(),
     };
-    let factory_manager = start_factory(factory_config, node_wallet.clone(), factory_channels);
+    let factory_signer: Arc<dyn Signer> = match &SETTINGS.factory.remote_signer {
+        Some(endpoint) => {
+            let remote_signer = RemoteSigner::connect(
+                endpoint.clone(),
+                SETTINGS
+                    .factory
+                    .remote_signer_secret
+                    .clone()
+                    .expect("remote_signer_secret is needed to use a remote signer"),
+                SETTINGS.factory.remote_signer_timeout.to_duration(),
+            )
+            .expect("could not connect to the remote signer");
+            info!(
+                "signing blocks and endorsements with the remote signer at {} for addresses {:?}",
+                endpoint,
+                remote_signer.get_addresses()
+            );
+            Arc::new(remote_signer)
+        }
+        None => Arc::new(LocalSigner::new(node_wallet.clone())),
+    };
+    let factory_manager = start_factory(
+        factory_config,
+        factory_signer,
+        factory_channels,
+        Box::new(DefaultBlockProductionStrategy),
+    );
 
     // launch bootstrap server
     let bootstrap_manager = start_bootstrap_server(
@@ -443,14 +566,20 @@ async fn launch(
     let api_config: APIConfig = APIConfig {
         bind_private: SETTINGS.api.bind_private,
         bind_public: SETTINGS.api.bind_public,
+        enable_ws: SETTINGS.api.enable_ws,
+        bind_ws: SETTINGS.api.bind_ws,
+        max_subscriptions_per_session: SETTINGS.api.max_subscriptions_per_session,
         draw_lookahead_period_count: SETTINGS.api.draw_lookahead_period_count,
         max_arguments: SETTINGS.api.max_arguments,
+        max_page_size: SETTINGS.api.max_page_size,
+        max_events_page_size: SETTINGS.api.max_events_page_size,
         max_datastore_value_length: MAX_DATASTORE_VALUE_LENGTH,
         max_op_datastore_entry_count: MAX_OPERATION_DATASTORE_ENTRY_COUNT,
         max_op_datastore_key_length: MAX_OPERATION_DATASTORE_KEY_LENGTH,
         max_op_datastore_value_length: MAX_OPERATION_DATASTORE_VALUE_LENGTH,
         max_function_name_length: MAX_FUNCTION_NAME_LENGTH,
         max_parameter_size: MAX_PARAMETERS_SIZE,
+        max_gas_per_block: MAX_GAS_PER_BLOCK,
     };
     // spawn private API
     let (api_private, api_private_stop_rx) = API::<Private>::new(
@@ -460,6 +589,9 @@ async fn launch(
         api_config,
         consensus_config.clone(),
         node_wallet,
+        bootstrap_manager
+            .as_ref()
+            .map(BootstrapManager::get_access_lists),
     );
     let api_private_handle = api_private.serve(&SETTINGS.api.bind_private);
 
@@ -478,9 +610,24 @@ async fn launch(
         bootstrap_state.compensation_millis,
         node_id,
         shared_storage.clone(),
+        final_block_store,
     );
     let api_public_handle = api_public.serve(&SETTINGS.api.bind_public);
 
+    // spawn subscription API
+    let api_pubsub_handle = if SETTINGS.api.enable_ws {
+        let api_pubsub = API::<PubSub>::new(
+            block_sender,
+            finalized_block_sender,
+            operation_sender,
+            sc_output_event_sender,
+            api_config,
+        );
+        Some(api_pubsub.serve(&SETTINGS.api.bind_ws))
+    } else {
+        None
+    };
+
     #[cfg(feature = "deadlock_detection")]
     {
         // only for #[cfg]
@@ -511,6 +658,7 @@ async fn launch(
             .expect("failed to spawn thread : deadlock-detection");
     }
     (
+        final_state,
         consensus_event_receiver,
         bootstrap_manager,
         consensus_manager,
@@ -523,6 +671,7 @@ async fn launch(
         api_private_stop_rx,
         api_private_handle,
         api_public_handle,
+        api_pubsub_handle,
     )
 }
 
@@ -551,6 +700,7 @@ async fn stop(
     }: Managers,
     api_private_handle: StopHandle,
     api_public_handle: StopHandle,
+    api_pubsub_handle: Option<StopHandle>,
 ) {
     // stop bootstrap
     if let Some(bootstrap_manager) = bootstrap_manager {
@@ -560,6 +710,11 @@ async fn stop(
             .expect("bootstrap server shutdown failed")
     }
 
+    // stop subscription API
+    if let Some(api_pubsub_handle) = api_pubsub_handle {
+        api_pubsub_handle.stop();
+    }
+
     // stop public API
     api_public_handle.stop();
 
@@ -607,6 +762,15 @@ struct Args {
     /// Wallet password
     #[structopt(short = "p", long = "pwd")]
     password: Option<String>,
+    /// Final state snapshot to start from
+    #[structopt(long = "import-snapshot", parse(from_os_str))]
+    import_snapshot: Option<PathBuf>,
+    /// Trusted hash of the imported final state snapshot
+    #[structopt(long = "snapshot-hash")]
+    snapshot_hash: Option<Hash>,
+    /// Export the final state to this snapshot file when the node stops
+    #[structopt(long = "export-snapshot", parse(from_os_str))]
+    export_snapshot: Option<PathBuf>,
 }
 
 /// Load wallet, asking for passwords if necessary
@@ -633,6 +797,22 @@ fn load_wallet(password: Option<String>, path: &Path) -> anyhow::Result<Arc<RwLo
     )?)))
 }
 
+/// Load the keypair proving our identity to bootstrap servers, generating it if the file does not exist
+fn load_bootstrap_client_keypair(path: &Path) -> anyhow::Result<KeyPair> {
+    let keypair = if path.is_file() {
+        serde_json::from_str::<KeyPair>(&std::fs::read_to_string(path)?)?
+    } else {
+        let keypair = KeyPair::generate();
+        std::fs::write(path, serde_json::to_string(&keypair)?)?;
+        keypair
+    };
+    info!(
+        "The bootstrap client public key of this node is: {}",
+        keypair.get_public_key()
+    );
+    Ok(keypair)
+}
+
 #[paw::main]
 fn main(args: Args) -> anyhow::Result<()> {
     let tokio_rt = tokio::runtime::Builder::new_multi_thread()
@@ -681,8 +861,24 @@ async fn run(args: Args) -> anyhow::Result<()> {
     // load or create wallet, asking for password if necessary
     let node_wallet = load_wallet(args.password, &SETTINGS.factory.staking_wallet_path)?;
 
+    // the disk block store outlives restarts
+    let final_block_store: Option<Box<dyn BlockStoreController>> =
+        if SETTINGS.consensus.persistent_block_store {
+            Some(Box::new(FinalBlockStore::new(
+                SETTINGS.consensus.disk_block_store_path.clone(),
+                THREAD_COUNT,
+                ENDORSEMENT_COUNT,
+            )))
+        } else {
+            None
+        };
+
+    // the snapshot is only imported at the first launch
+    let mut snapshot_import = args.import_snapshot.map(|path| (path, args.snapshot_hash));
+
     loop {
         let (
+            final_state,
             mut consensus_event_receiver,
             bootstrap_manager,
             consensus_manager,
@@ -695,7 +891,13 @@ async fn run(args: Args) -> anyhow::Result<()> {
             mut api_private_stop_rx,
             api_private_handle,
             api_public_handle,
-        ) = launch(node_wallet.clone()).await;
+            api_pubsub_handle,
+        ) = launch(
+            node_wallet.clone(),
+            snapshot_import.take(),
+            final_block_store.clone(),
+        )
+        .await;
 
         // interrupt signal listener
         let stop_signal = signal::ctrl_c();
@@ -744,10 +946,21 @@ async fn run(args: Args) -> anyhow::Result<()> {
             },
             api_private_handle,
             api_public_handle,
+            api_pubsub_handle,
         )
         .await;
 
         if !restart {
+            if let Some(path) = &args.export_snapshot {
+                match final_state.read().export_snapshot(path) {
+                    Ok(hash) => info!(
+                        "final state snapshot exported to {} with hash {}",
+                        path.display(),
+                        hash
+                    ),
+                    Err(err) => error!("could not export final state snapshot: {}", err),
+                }
+            }
             break;
         }
     }
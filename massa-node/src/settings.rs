@@ -5,12 +5,15 @@ use std::path::PathBuf;
 
 use enum_map::EnumMap;
 use massa_models::config::build_massa_settings;
+use massa_models::ip_range::IpRange;
 use massa_signature::PublicKey;
 use massa_time::MassaTime;
+use massa_wallet::SignerEndpoint;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
 
-use massa_network_exports::{settings::PeerTypeConnectionConfig, PeerType};
+use massa_network_exports::{settings::PeerTypeConnectionConfig, PeerType, RateLimit};
 
 lazy_static::lazy_static! {
     pub static ref SETTINGS: Settings = build_massa_settings("massa-node", "MASSA_NODE");
@@ -27,6 +30,8 @@ pub struct ExecutionSettings {
     pub readonly_queue_length: usize,
     pub cursor_delay: MassaTime,
     pub stats_time_window_duration: MassaTime,
+    pub trace_executions: bool,
+    pub max_execution_traces: usize,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -40,12 +45,17 @@ pub struct LedgerSettings {
     pub initial_ledger_path: PathBuf,
     pub disk_ledger_path: PathBuf,
     pub final_history_length: usize,
+    pub reverse_history_length: usize,
+    pub persistent_event_store: bool,
+    pub disk_event_store_path: PathBuf,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct NetworkSettings {
     pub bind: SocketAddr,
+    pub additional_binds: Vec<SocketAddr>,
     pub routable_ip: Option<IpAddr>,
+    pub advertised_addrs: Vec<SocketAddr>,
     pub protocol_port: u16,
     pub connect_timeout: MassaTime,
     pub wakeup_interval: MassaTime,
@@ -59,6 +69,7 @@ pub struct NetworkSettings {
     pub peers_file_dump_interval: MassaTime,
     pub message_timeout: MassaTime,
     pub ask_peer_list_interval: MassaTime,
+    pub peer_record_max_age: MassaTime,
     pub max_send_wait: MassaTime,
     pub ban_timeout: MassaTime,
     pub peer_list_send_timeout: MassaTime,
@@ -66,6 +77,12 @@ pub struct NetworkSettings {
     pub max_operations_per_message: u32,
     pub max_bytes_read: f64,
     pub max_bytes_write: f64,
+    pub require_encryption: bool,
+    pub peer_score_half_life: MassaTime,
+    pub peer_ban_score: f64,
+    pub peer_bytes_rate_limit: RateLimit,
+    pub peer_messages_rate_limit: RateLimit,
+    pub message_type_rate_limits: HashMap<String, RateLimit>,
 }
 
 /// Bootstrap configuration.
@@ -86,6 +103,15 @@ pub struct BootstrapSettings {
     pub per_ip_min_interval: MassaTime,
     pub ip_list_max_size: usize,
     pub max_bytes_read_write: f64,
+    pub checkpoint: bool,
+    pub checkpoint_path: PathBuf,
+    pub checkpoint_interval: MassaTime,
+    pub parallel_bootstrap_servers: usize,
+    pub ip_allow_list: Vec<IpRange>,
+    pub ip_deny_list: Vec<IpRange>,
+    pub authorized_client_keys: Vec<PublicKey>,
+    pub client_authentication: bool,
+    pub client_keypair_path: PathBuf,
 }
 
 /// Factory settings
@@ -95,12 +121,20 @@ pub struct FactorySettings {
     pub initial_delay: MassaTime,
     /// Staking wallet file
     pub staking_wallet_path: PathBuf,
+    /// Endpoint of a remote signer holding the staking keys, used instead of the staking wallet
+    pub remote_signer: Option<SignerEndpoint>,
+    /// Secret shared with the remote signer, needed to use it
+    pub remote_signer_secret: Option<String>,
+    /// Timeout of the connection to the remote signer and of each request
+    pub remote_signer_timeout: MassaTime,
 }
 
 /// Pool configuration, read from a file configuration
 #[derive(Debug, Deserialize, Clone)]
 pub struct PoolSettings {
     pub max_pool_size_per_thread: usize,
+    pub max_operations_per_sender: usize,
+    pub removed_operations_history_size: usize,
     pub max_operation_future_validity_start_periods: u64,
     pub max_endorsement_count: u64,
     pub max_item_return_count: usize,
@@ -113,6 +147,12 @@ pub struct APISettings {
     pub bind_private: SocketAddr,
     pub bind_public: SocketAddr,
     pub max_arguments: u64,
+    pub max_page_size: usize,
+    pub max_events_page_size: usize,
+    pub enable_ws: bool,
+    pub bind_ws: SocketAddr,
+    pub max_subscriptions_per_session: usize,
+    pub broadcast_capacity: usize,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -152,6 +192,14 @@ pub struct ConsensusSettings {
     pub block_db_prune_interval: MassaTime,
     /// max number of items returned while querying
     pub max_item_return_count: usize,
+    /// whether final blocks, operations and endorsements are persisted on disk
+    pub persistent_block_store: bool,
+    /// path to the disk block store db directory
+    pub disk_block_store_path: PathBuf,
+    /// whether the disk block store keeps every final block instead of pruning the old ones
+    pub block_store_archive: bool,
+    /// number of final periods kept in the disk block store when it is not an archive
+    pub block_store_retention_periods: u64,
 }
 
 /// Protocol Configuration, read from toml user configuration file
@@ -195,6 +243,14 @@ pub struct ProtocolSettings {
     pub max_operations_propagation_time: MassaTime,
     /// Time threshold after which operation are not propagated
     pub max_endorsements_propagation_time: MassaTime,
+    /// Fetch blocks with their operation prefixes and only ask for the operations we don't have
+    pub compact_block_relay: bool,
+    /// Received signatures are collected during this many milliseconds, then verified together
+    pub sig_verification_batch_window: MassaTime,
+    /// Collected signatures are verified right away once there are this many of them
+    pub max_sig_verification_batch_size: usize,
+    /// Number of threads verifying the collected signatures
+    pub sig_verification_thread_count: usize,
 }
 
 #[cfg(test)]
@@ -0,0 +1,11 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+use massa_models::operation::WrappedOperation;
+use tokio::sync::broadcast;
+
+/// Channels used by the pool to broadcast its activity to external subscribers
+#[derive(Clone)]
+pub struct PoolChannels {
+    /// Broadcast channel for operations newly added to the pool
+    pub operation_sender: broadcast::Sender<WrappedOperation>,
+}
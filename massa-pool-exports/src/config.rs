@@ -18,10 +18,16 @@ pub struct PoolConfig {
     pub operation_validity_periods: u64,
     /// max operation pool size per thread (in number of operations)
     pub max_operation_pool_size_per_thread: usize,
+    /// max number of operations of a single sender in the pool
+    pub max_operations_per_sender: usize,
+    /// number of operations rejected or evicted by the pool whose removal reason is kept
+    pub removed_operations_history_size: usize,
     /// max endorsement pool size per thread (in number of endorsements)
     pub max_endorsements_pool_size_per_thread: usize,
     /// max number of endorsements per block
     pub max_block_endorsement_count: u32,
     /// operations and endorsements communication channels size
     pub channels_size: usize,
+    /// whether operations added to the pool are broadcast to external subscribers
+    pub broadcast_enabled: bool,
 }
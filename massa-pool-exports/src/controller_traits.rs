@@ -1,7 +1,11 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_models::{
-    block::BlockId, endorsement::EndorsementId, operation::OperationId, slot::Slot,
+    api::{PagedResponse, PoolOperationFilter, PoolOperationStatus},
+    block::BlockId,
+    endorsement::EndorsementId,
+    operation::OperationId,
+    slot::Slot,
 };
 use massa_storage::Storage;
 
@@ -38,6 +42,19 @@ pub trait PoolController: Send + Sync {
     /// Check if the pool contains a list of operations. Returns one boolean per item.
     fn contains_operations(&self, operations: &[OperationId]) -> Vec<bool>;
 
+    /// Get the status of an operation in the pool, or the reason why it is not in the pool
+    fn get_operation_status(&self, id: &OperationId) -> PoolOperationStatus;
+
+    /// List the operations of the pool matching `filter`, by thread and by decreasing fee quality.
+    /// Returns at most `limit` operations, starting after the operation `cursor` if any,
+    /// or `None` if the operation `cursor` is not in the pool anymore.
+    fn list_operations(
+        &self,
+        filter: &PoolOperationFilter,
+        cursor: Option<OperationId>,
+        limit: usize,
+    ) -> Option<PagedResponse<OperationId>>;
+
     /// Returns a boxed clone of self.
     /// Useful to allow cloning `Box<dyn PoolController>`.
     fn clone_box(&self) -> Box<dyn PoolController>;
@@ -1,11 +1,12 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
 use massa_models::config::{
-    ENDORSEMENT_COUNT, MAX_BLOCK_SIZE, MAX_GAS_PER_BLOCK, OPERATION_VALIDITY_PERIODS, ROLL_PRICE,
-    THREAD_COUNT,
+    CHANNEL_SIZE, ENDORSEMENT_COUNT, MAX_BLOCK_SIZE, MAX_GAS_PER_BLOCK,
+    OPERATION_VALIDITY_PERIODS, ROLL_PRICE, THREAD_COUNT,
 };
+use tokio::sync::broadcast;
 
-use crate::PoolConfig;
+use crate::{PoolChannels, PoolConfig};
 
 impl Default for PoolConfig {
     fn default() -> Self {
@@ -16,9 +17,20 @@ impl Default for PoolConfig {
             roll_price: ROLL_PRICE,
             max_block_size: MAX_BLOCK_SIZE,
             max_operation_pool_size_per_thread: 1000,
+            max_operations_per_sender: 1000,
+            removed_operations_history_size: 1000,
             max_endorsements_pool_size_per_thread: 1000,
             max_block_endorsement_count: ENDORSEMENT_COUNT,
             channels_size: 1024,
+            broadcast_enabled: false,
+        }
+    }
+}
+
+impl Default for PoolChannels {
+    fn default() -> Self {
+        Self {
+            operation_sender: broadcast::channel(CHANNEL_SIZE).0,
         }
     }
 }
@@ -6,7 +6,11 @@ use std::sync::{
 };
 
 use massa_models::{
-    block::BlockId, endorsement::EndorsementId, operation::OperationId, slot::Slot,
+    api::{PagedResponse, PoolOperationFilter, PoolOperationStatus},
+    block::BlockId,
+    endorsement::EndorsementId,
+    operation::OperationId,
+    slot::Slot,
 };
 use massa_storage::Storage;
 use massa_time::MassaTime;
@@ -71,6 +75,24 @@ pub enum MockPoolControllerMessage {
         /// Response channel
         response_tx: mpsc::Sender<Vec<bool>>,
     },
+    /// Get the status of an operation in the pool
+    GetOperationStatus {
+        /// id of the operation
+        id: OperationId,
+        /// Response channel
+        response_tx: mpsc::Sender<PoolOperationStatus>,
+    },
+    /// List the operations of the pool
+    ListOperations {
+        /// filter on the operations
+        filter: PoolOperationFilter,
+        /// operation after which the operations are listed
+        cursor: Option<OperationId>,
+        /// max number of operations to return
+        limit: usize,
+        /// Response channel
+        response_tx: mpsc::Sender<Option<PagedResponse<OperationId>>>,
+    },
     /// Get stats of the pool
     GetStats {
         /// Response channel
@@ -216,6 +238,39 @@ impl PoolController for MockPoolController {
         response_rx.recv().unwrap()
     }
 
+    fn get_operation_status(&self, id: &OperationId) -> PoolOperationStatus {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockPoolControllerMessage::GetOperationStatus {
+                id: *id,
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
+    fn list_operations(
+        &self,
+        filter: &PoolOperationFilter,
+        cursor: Option<OperationId>,
+        limit: usize,
+    ) -> Option<PagedResponse<OperationId>> {
+        let (response_tx, response_rx) = mpsc::channel();
+        self.0
+            .lock()
+            .unwrap()
+            .send(MockPoolControllerMessage::ListOperations {
+                filter: filter.clone(),
+                cursor,
+                limit,
+                response_tx,
+            })
+            .unwrap();
+        response_rx.recv().unwrap()
+    }
+
     fn notify_final_cs_periods(&mut self, final_cs_periods: &[u64]) {
         self.0
             .lock()
@@ -3,7 +3,11 @@
 //! Pool controller implementation
 
 use massa_models::{
-    block::BlockId, endorsement::EndorsementId, operation::OperationId, slot::Slot,
+    api::{PagedResponse, PoolOperationFilter, PoolOperationStatus},
+    block::BlockId,
+    endorsement::EndorsementId,
+    operation::OperationId,
+    slot::Slot,
 };
 use massa_pool_exports::{PoolConfig, PoolController, PoolManager};
 use massa_storage::Storage;
@@ -150,6 +154,23 @@ impl PoolController for PoolControllerImpl {
         let lck = self.operation_pool.read();
         operations.iter().map(|id| lck.contains(id)).collect()
     }
+
+    /// Get the status of an operation in the pool, or the reason why it is not in the pool
+    fn get_operation_status(&self, id: &OperationId) -> PoolOperationStatus {
+        self.operation_pool.read().get_operation_status(id)
+    }
+
+    /// List the operations of the pool matching `filter`, by thread and by decreasing fee quality
+    fn list_operations(
+        &self,
+        filter: &PoolOperationFilter,
+        cursor: Option<OperationId>,
+        limit: usize,
+    ) -> Option<PagedResponse<OperationId>> {
+        self.operation_pool
+            .read()
+            .list_operations(filter, cursor, limit)
+    }
 }
 
 /// Implementation of the pool manager.
@@ -4,15 +4,18 @@ use massa_execution_exports::ExecutionController;
 use massa_models::{
     address::Address,
     amount::Amount,
+    api::{PagedResponse, PoolOperationFilter, PoolOperationRemovalReason, PoolOperationStatus},
+    config::MAX_OPERATIONS_PER_BLOCK,
     operation::OperationId,
     prehash::{CapacityAllocator, PreHashMap, PreHashSet},
     slot::Slot,
 };
-use massa_pool_exports::PoolConfig;
+use massa_pool_exports::{PoolChannels, PoolConfig};
 use massa_storage::Storage;
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, VecDeque};
+use std::ops::Bound::{Excluded, Unbounded};
 
-use crate::types::{OperationInfo, PoolOperationCursor};
+use crate::types::{OperationInfo, PoolOperationCursor, RemovedOperationInfo};
 
 pub struct OperationPool {
     /// configuration
@@ -27,6 +30,15 @@ pub struct OperationPool {
     /// operations sorted by increasing expiration slot
     ops_per_expiration: BTreeSet<(Slot, OperationId)>,
 
+    /// operations indexed by creator address
+    ops_per_sender: PreHashMap<Address, PreHashSet<OperationId>>,
+
+    /// operations recently rejected or evicted, with the reason of their removal
+    removed_ops: PreHashMap<OperationId, RemovedOperationInfo>,
+
+    /// IDs of `removed_ops`, from the oldest removal to the newest
+    removed_ops_order: VecDeque<OperationId>,
+
     /// storage instance
     pub(crate) storage: Storage,
 
@@ -35,6 +47,9 @@ pub struct OperationPool {
 
     /// last consensus final periods, per thread
     last_cs_final_periods: Vec<u64>,
+
+    /// channels used to broadcast pool activity
+    channels: PoolChannels,
 }
 
 impl OperationPool {
@@ -42,15 +57,20 @@ impl OperationPool {
         config: PoolConfig,
         storage: &Storage,
         execution_controller: Box<dyn ExecutionController>,
+        channels: PoolChannels,
     ) -> Self {
         OperationPool {
             operations: Default::default(),
             sorted_ops_per_thread: vec![Default::default(); config.thread_count as usize],
             ops_per_expiration: Default::default(),
+            ops_per_sender: Default::default(),
+            removed_ops: Default::default(),
+            removed_ops_order: Default::default(),
             last_cs_final_periods: vec![0u64; config.thread_count as usize],
             config,
             storage: storage.clone_without_refs(),
             execution_controller,
+            channels,
         }
     }
 
@@ -66,6 +86,15 @@ impl OperationPool {
 
     /// notify of new final slot
     pub(crate) fn notify_final_cs_periods(&mut self, final_cs_periods: &[u64]) {
+        // operations are only executed in final slots: look for executed ops in the threads that got new ones
+        let advanced_threads: Vec<u8> = final_cs_periods
+            .iter()
+            .zip(self.last_cs_final_periods.iter())
+            .enumerate()
+            .filter(|(_, (new_period, old_period))| new_period > old_period)
+            .map(|(thread, _)| thread as u8)
+            .collect();
+
         // update internal final slot counter
         self.last_cs_final_periods = final_cs_periods.to_vec();
 
@@ -75,14 +104,13 @@ impl OperationPool {
             if expire_slot.period > self.last_cs_final_periods[expire_slot.thread as usize] {
                 break;
             }
-            self.ops_per_expiration.pop_first();
-            let op_info = self
-                .operations
-                .remove(&op_id)
-                .expect("expected op presence in operations list");
-            if !self.sorted_ops_per_thread[expire_slot.thread as usize].remove(&op_info.cursor) {
-                panic!("expected op presence in sorted list")
-            }
+            self.remove_operation(&op_id, PoolOperationRemovalReason::Expired);
+            removed_ops.insert(op_id);
+        }
+
+        // prune ops that were already executed
+        for op_id in self.get_executed_operations(&advanced_threads) {
+            self.remove_operation(&op_id, PoolOperationRemovalReason::Executed);
             removed_ops.insert(op_id);
         }
 
@@ -90,6 +118,28 @@ impl OperationPool {
         self.storage.drop_operation_refs(&removed_ops);
     }
 
+    /// Gets the operations of the pool that were already executed, among the best ones of the given threads.
+    /// Only the operations that a block could include are checked, to bound the work done at each new final period:
+    /// the other ones are checked once they get among the best ones of their thread, if they don't expire before.
+    fn get_executed_operations(&self, threads: &[u8]) -> PreHashSet<OperationId> {
+        let mut executed_ops = PreHashSet::default();
+        for thread in threads {
+            let ops: PreHashSet<OperationId> = self.sorted_ops_per_thread[*thread as usize]
+                .iter()
+                .take(MAX_OPERATIONS_PER_BLOCK as usize)
+                .map(|cursor| cursor.get_id())
+                .collect();
+            if ops.is_empty() {
+                continue;
+            }
+            let unexecuted_ops = self
+                .execution_controller
+                .unexecuted_ops_among(&ops, *thread);
+            executed_ops.extend(ops.difference(&unexecuted_ops));
+        }
+        executed_ops
+    }
+
     /// Checks if an operation is relevant according to its thread and period validity range
     pub(crate) fn is_operation_relevant(&self, op_info: &OperationInfo) -> bool {
         // too old
@@ -97,7 +147,141 @@ impl OperationPool {
         // todo check if validity not started yet
     }
 
-    /// Add a list of operations to the pool
+    /// Inserts an operation in the pool indexes, it must not be in the pool already
+    fn insert_operation(&mut self, op_info: OperationInfo) {
+        if !self.sorted_ops_per_thread[op_info.thread as usize].insert(op_info.cursor) {
+            panic!("sorted ops should not contain the op at this point");
+        }
+        if !self.ops_per_expiration.insert((
+            Slot::new(*op_info.validity_period_range.end(), op_info.thread),
+            op_info.id,
+        )) {
+            panic!("expiration indexed ops should not contain the op at this point");
+        }
+        self.ops_per_sender
+            .entry(op_info.creator_address)
+            .or_default()
+            .insert(op_info.id);
+        if self.operations.insert(op_info.id, op_info).is_some() {
+            panic!("operations should not contain the op at this point");
+        }
+    }
+
+    /// Removes an operation from the pool indexes, it must be in the pool
+    fn remove_operation(&mut self, op_id: &OperationId, reason: PoolOperationRemovalReason) {
+        let op_info = self
+            .operations
+            .remove(op_id)
+            .expect("expected op presence in operations list");
+        if !self.sorted_ops_per_thread[op_info.thread as usize].remove(&op_info.cursor) {
+            panic!("expected op presence in sorted list");
+        }
+        let end_slot = Slot::new(*op_info.validity_period_range.end(), op_info.thread);
+        if !self.ops_per_expiration.remove(&(end_slot, op_info.id)) {
+            panic!("expected op presence in expiration indexed list");
+        }
+        if let Some(sender_ops) = self.ops_per_sender.get_mut(&op_info.creator_address) {
+            sender_ops.remove(&op_info.id);
+            if sender_ops.is_empty() {
+                self.ops_per_sender.remove(&op_info.creator_address);
+            }
+        }
+        self.note_removal(&op_info, reason);
+    }
+
+    /// Remembers why an operation was rejected or evicted,
+    /// forgetting the oldest removals beyond `removed_operations_history_size`
+    fn note_removal(&mut self, op_info: &OperationInfo, reason: PoolOperationRemovalReason) {
+        let removed_op = RemovedOperationInfo {
+            thread: op_info.thread,
+            validity_period_range: op_info.validity_period_range.clone(),
+            reason,
+        };
+        if self.removed_ops.insert(op_info.id, removed_op).is_none() {
+            self.removed_ops_order.push_back(op_info.id);
+        }
+        while self.removed_ops_order.len() > self.config.removed_operations_history_size {
+            // the unwrap below won't panic because the loop condition tests for non-emptiness
+            let op_id = self.removed_ops_order.pop_front().unwrap();
+            self.removed_ops.remove(&op_id);
+        }
+    }
+
+    /// Gets the candidate balances of the creators of `op_ids` whose operations might conflict:
+    /// the ones that already have operations in the pool, or that created several of `op_ids`.
+    /// The creators whose balance is unknown are left out.
+    fn get_sender_balances(
+        &self,
+        ops_storage: &Storage,
+        op_ids: &[OperationId],
+    ) -> PreHashMap<Address, Amount> {
+        let mut new_ops_per_sender: PreHashMap<Address, usize> = Default::default();
+        {
+            let ops = ops_storage.read_operations();
+            for op_id in op_ids {
+                let op = ops
+                    .get(op_id)
+                    .expect("attempting to add operation to pool, but it is absent from storage");
+                *new_ops_per_sender.entry(op.creator_address).or_default() += 1;
+            }
+        }
+        let senders: Vec<Address> = new_ops_per_sender
+            .into_iter()
+            .filter(|(address, count)| *count > 1 || self.ops_per_sender.contains_key(address))
+            .map(|(address, _)| address)
+            .collect();
+        if senders.is_empty() {
+            return Default::default();
+        }
+        senders
+            .iter()
+            .zip(
+                self.execution_controller
+                    .get_final_and_candidate_balance(&senders),
+            )
+            .filter_map(|(address, (final_balance, candidate_balance))| {
+                candidate_balance
+                    .or(final_balance)
+                    .map(|balance| (*address, balance))
+            })
+            .collect()
+    }
+
+    /// Gets the IDs of the operations of the pool conflicting with `op_info`:
+    /// the ones of the same creator that can't be executed along with it,
+    /// as their max spendings together exceed the balance of the creator.
+    /// Operations whose creator balance is unknown do not conflict.
+    fn get_conflicting_operations(
+        &self,
+        op_info: &OperationInfo,
+        sender_balances: &PreHashMap<Address, Amount>,
+    ) -> Vec<OperationId> {
+        let (sender_ops, balance) = match (
+            self.ops_per_sender.get(&op_info.creator_address),
+            sender_balances.get(&op_info.creator_address),
+        ) {
+            (Some(sender_ops), Some(balance)) => (sender_ops, balance),
+            _ => return Vec::new(),
+        };
+        sender_ops
+            .iter()
+            .filter(|id| {
+                self.operations[*id]
+                    .max_spending
+                    .saturating_add(op_info.max_spending)
+                    > *balance
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Add a list of operations to the pool.
+    ///
+    /// An operation replaces the operations it conflicts with (see `get_conflicting_operations`)
+    /// if its fee is higher than all of theirs, otherwise it is ignored.
+    /// Operations that can all be executed never replace each other, even with the same content.
+    /// When its creator already has `max_operations_per_sender` operations in the pool,
+    /// it replaces the worst of them if it is better, otherwise it is ignored.
     pub(crate) fn add_operations(&mut self, mut ops_storage: Storage) {
         let items = ops_storage
             .get_op_refs()
@@ -108,52 +292,114 @@ impl OperationPool {
         let mut added = PreHashSet::with_capacity(items.len());
         let mut removed = PreHashSet::with_capacity(items.len());
 
+        // the balances of the senders tell which of their operations conflict
+        let sender_balances = self.get_sender_balances(&ops_storage, &items);
+
         // add items to pool
         {
             let ops = ops_storage.read_operations();
             for op_id in items {
+                let op = ops
+                    .get(&op_id)
+                    .expect("attempting to add operation to pool, but it is absent from storage");
                 let op_info = OperationInfo::from_op(
-                    ops.get(&op_id).expect(
-                        "attempting to add operation to pool, but it is absent from storage",
-                    ),
+                    op,
                     self.config.operation_validity_periods,
                     self.config.roll_price,
                     self.config.thread_count,
+                    self.config.max_block_size,
+                    self.config.max_block_gas,
                 );
+                if self.contains(&op_info.id) {
+                    continue;
+                }
                 if !self.is_operation_relevant(&op_info) {
+                    self.note_removal(&op_info, PoolOperationRemovalReason::Expired);
+                    continue;
+                }
+
+                // replace by fee
+                let conflicting_ops = self.get_conflicting_operations(&op_info, &sender_balances);
+                let fee = op.get_total_fee();
+                if let Some(&conflicting_op) = conflicting_ops.iter().find(|id| {
+                    ops.get(id)
+                        .expect("the operations of the pool should be in storage")
+                        .get_total_fee()
+                        >= fee
+                }) {
+                    self.note_removal(
+                        &op_info,
+                        PoolOperationRemovalReason::ConflictsWithHigherFee(conflicting_op),
+                    );
                     continue;
                 }
-                if let Ok(op_info) = self.operations.try_insert(op_info.id, op_info) {
-                    if !self.sorted_ops_per_thread[op_info.thread as usize].insert(op_info.cursor) {
-                        panic!("sorted ops should not contain the op at this point");
+
+                // limit the number of operations per sender
+                let sender_op_count = self
+                    .ops_per_sender
+                    .get(&op_info.creator_address)
+                    .map_or(0, |ops| ops.len());
+                let mut evicted_op = None;
+                if sender_op_count - conflicting_ops.len() >= self.config.max_operations_per_sender
+                {
+                    // the new op can only take the place of the worst remaining one of the sender
+                    let worst_cursor = self
+                        .ops_per_sender
+                        .get(&op_info.creator_address)
+                        .into_iter()
+                        .flatten()
+                        .filter(|id| !conflicting_ops.contains(*id))
+                        .map(|id| self.operations[id].cursor)
+                        .max();
+                    match worst_cursor {
+                        Some(cursor) if cursor > op_info.cursor => {
+                            evicted_op = Some(cursor.get_id())
+                        }
+                        _ => {
+                            self.note_removal(
+                                &op_info,
+                                PoolOperationRemovalReason::SenderLimitReached,
+                            );
+                            continue;
+                        }
                     }
-                    if !self.ops_per_expiration.insert((
-                        Slot::new(*op_info.validity_period_range.end(), op_info.thread),
-                        op_info.id,
-                    )) {
-                        panic!("expiration indexed ops should not contain the op at this point");
+                }
+
+                let replaced_ops = conflicting_ops
+                    .into_iter()
+                    .map(|id| (id, PoolOperationRemovalReason::ReplacedByFee(op_info.id)))
+                    .chain(
+                        evicted_op.map(|id| (id, PoolOperationRemovalReason::SenderLimitReached)),
+                    );
+                for (id, reason) in replaced_ops {
+                    self.remove_operation(&id, reason);
+                    // ops added by this call are not in self.storage yet
+                    if !added.remove(&id) {
+                        removed.insert(id);
                     }
-                    added.insert(op_info.id);
+                }
+                added.insert(op_info.id);
+                self.insert_operation(op_info);
+                if self.config.broadcast_enabled {
+                    // an error only means that there is currently no subscriber
+                    let _ = self.channels.operation_sender.send(op.clone());
                 }
             }
         }
 
         // prune excess operations
-        self.sorted_ops_per_thread.iter_mut().for_each(|ops| {
-            while ops.len() > self.config.max_operation_pool_size_per_thread {
-                // the unwrap below won't panic because the loop condition tests for non-emptines of self.operations
-                let cursor = ops.pop_last().unwrap();
-                let op_info = self
-                    .operations
-                    .remove(&cursor.get_id())
-                    .expect("the operation should be in self.operations at this point");
-                let end_slot = Slot::new(*op_info.validity_period_range.end(), op_info.thread);
-                if !self.ops_per_expiration.remove(&(end_slot, op_info.id)) {
-                    panic!("the operation should be in self.ops_per_expiration at this point");
+        for thread in 0..self.config.thread_count as usize {
+            while self.sorted_ops_per_thread[thread].len()
+                > self.config.max_operation_pool_size_per_thread
+            {
+                // the unwrap below won't panic because the loop condition tests for non-emptines of the sorted ops
+                let op_id = self.sorted_ops_per_thread[thread].last().unwrap().get_id();
+                self.remove_operation(&op_id, PoolOperationRemovalReason::PoolFull);
+                if !added.remove(&op_id) {
+                    removed.insert(op_id);
                 }
-                removed.insert(op_info.id);
             }
-        });
+        }
 
         // This will add the new ops to the storage without taking locks.
         // It just take the local references from `ops_storage` if they are not in `self.storage` yet.
@@ -171,7 +417,147 @@ impl OperationPool {
         self.storage.drop_operation_refs(&removed);
     }
 
+    /// Get the status of an operation in the pool, or the reason why it is not in the pool
+    pub fn get_operation_status(&self, id: &OperationId) -> PoolOperationStatus {
+        let mut status = PoolOperationStatus {
+            id: *id,
+            in_pool: false,
+            thread: None,
+            rank: None,
+            validity_period_range: None,
+            last_final_period: None,
+            removal_reason: None,
+        };
+        let (thread, validity_period_range) = if let Some(op_info) = self.operations.get(id) {
+            status.in_pool = true;
+            // counting the better ops is linear in the rank: stop beyond the ops that a block can contain
+            let rank = self.sorted_ops_per_thread[op_info.thread as usize]
+                .range(..op_info.cursor)
+                .take(MAX_OPERATIONS_PER_BLOCK as usize)
+                .count();
+            if rank < MAX_OPERATIONS_PER_BLOCK as usize {
+                status.rank = Some(rank);
+            }
+            (op_info.thread, &op_info.validity_period_range)
+        } else if let Some(removed_op) = self.removed_ops.get(id) {
+            status.removal_reason = Some(removed_op.reason);
+            (removed_op.thread, &removed_op.validity_period_range)
+        } else {
+            return status;
+        };
+        status.thread = Some(thread);
+        status.validity_period_range =
+            Some((*validity_period_range.start(), *validity_period_range.end()));
+        status.last_final_period = Some(self.last_cs_final_periods[thread as usize]);
+        status
+    }
+
+    /// List the operations of the pool matching `filter`, by thread and by decreasing fee quality.
+    /// Returns at most `limit` operations, starting after the operation `cursor` if any,
+    /// or `None` if the operation `cursor` is not in the pool anymore.
+    /// The next cursor of the page is its last operation, if more operations match.
+    pub fn list_operations(
+        &self,
+        filter: &PoolOperationFilter,
+        cursor: Option<OperationId>,
+        limit: usize,
+    ) -> Option<PagedResponse<OperationId>> {
+        // position of the cursor in the listing order
+        let start = match cursor {
+            Some(id) => {
+                let op_info = self.operations.get(&id)?;
+                Some((op_info.thread, op_info.cursor))
+            }
+            None => None,
+        };
+
+        // take an extra op to know whether there is a next page
+        let (mut op_ids, total_count) = match filter.creator_address {
+            Some(address) => {
+                self.list_sender_operations(&address, filter.thread, start, limit.saturating_add(1))
+            }
+            None => self.list_thread_operations(filter.thread, start, limit.saturating_add(1)),
+        };
+        let mut next_cursor = None;
+        if op_ids.len() > limit {
+            op_ids.truncate(limit);
+            next_cursor = op_ids.last().map(|id| id.to_string());
+        }
+        Some(PagedResponse {
+            content: op_ids,
+            total_count: Some(total_count),
+            next_cursor,
+        })
+    }
+
+    /// List at most `limit` operations of `address` in `thread` if any, by thread and by decreasing fee quality,
+    /// starting after the position `start` if any.
+    /// Returns them with the total number of operations of `address` in `thread`.
+    fn list_sender_operations(
+        &self,
+        address: &Address,
+        thread: Option<u8>,
+        start: Option<(u8, PoolOperationCursor)>,
+        limit: usize,
+    ) -> (Vec<OperationId>, usize) {
+        // the ops of a sender are few: sort them directly
+        let mut sender_ops: Vec<(u8, PoolOperationCursor)> = self
+            .ops_per_sender
+            .get(address)
+            .into_iter()
+            .flatten()
+            .map(|id| &self.operations[id])
+            .filter(|op_info| thread.map_or(true, |thread| thread == op_info.thread))
+            .map(|op_info| (op_info.thread, op_info.cursor))
+            .collect();
+        sender_ops.sort_unstable();
+        let op_ids = sender_ops
+            .iter()
+            .filter(|position| start.map_or(true, |start| **position > start))
+            .map(|(_, cursor)| cursor.get_id())
+            .take(limit)
+            .collect();
+        (op_ids, sender_ops.len())
+    }
+
+    /// List at most `limit` operations in `thread` if any, by thread and by decreasing fee quality,
+    /// starting after the position `start` if any.
+    /// Returns them with the total number of operations in `thread`.
+    fn list_thread_operations(
+        &self,
+        thread: Option<u8>,
+        start: Option<(u8, PoolOperationCursor)>,
+        limit: usize,
+    ) -> (Vec<OperationId>, usize) {
+        let threads: Vec<u8> = (0..self.config.thread_count)
+            .filter(|t| thread.map_or(true, |thread| thread == *t))
+            .collect();
+        let total_count = threads
+            .iter()
+            .map(|t| self.sorted_ops_per_thread[*t as usize].len())
+            .sum();
+        let op_ids = threads
+            .into_iter()
+            .filter(|t| start.map_or(true, |(start_thread, _)| *t >= start_thread))
+            .flat_map(|t| {
+                let lower_bound = match start {
+                    Some((start_thread, cursor)) if start_thread == t => Excluded(cursor),
+                    _ => Unbounded,
+                };
+                self.sorted_ops_per_thread[t as usize]
+                    .range((lower_bound, Unbounded))
+                    .map(|cursor| cursor.get_id())
+            })
+            .take(limit)
+            .collect();
+        (op_ids, total_count)
+    }
+
     /// get operations for block creation
+    ///
+    /// Operations are packed greedily by decreasing fee per weighted resource (see `build_operation_cursor`),
+    /// skipping the ones that do not fit in the remaining block space or gas,
+    /// or whose max spending exceeds what remains of the balance of their sender after the operations already chosen.
     pub fn get_block_operations(&self, slot: &Slot) -> (Vec<OperationId>, Storage) {
         // init list of selected operation IDs
         let mut op_ids = Vec::new();
@@ -235,7 +621,8 @@ impl OperationPool {
                     continue;
                 };
 
-            if *creator_balance < op_info.fee {
+            // exclude ops that might spend more than what remains of the sender's balance
+            if *creator_balance < op_info.max_spending {
                 continue;
             }
 
@@ -17,21 +17,29 @@
 //! latest period given his own thread. All operation which doesn't fit these
 //! requirements are "irrelevant"
 //!
-use super::tools::{create_some_operations, operation_pool_test};
+use super::tools::{
+    answer_execution_requests, create_some_operations, operation_pool_test,
+    operation_pool_test_with_balance,
+};
 use crate::operation_pool::OperationPool;
-use massa_execution_exports::test_exports::MockExecutionController;
+use massa_execution_exports::test_exports::{
+    MockExecutionController, MockExecutionControllerMessage,
+};
 use massa_models::{
     address::Address,
     amount::Amount,
+    api::{PoolOperationFilter, PoolOperationRemovalReason},
     operation::{Operation, OperationSerializer, OperationType, WrappedOperation},
     prehash::PreHashMap,
     slot::Slot,
     wrapped::WrappedContent,
 };
-use massa_pool_exports::PoolConfig;
+use massa_pool_exports::{PoolChannels, PoolConfig};
 use massa_signature::KeyPair;
 use massa_storage::Storage;
+use parking_lot::Mutex;
 use std::str::FromStr;
+use std::sync::Arc;
 
 #[test]
 fn test_add_operation() {
@@ -57,20 +65,28 @@ fn test_add_irrelevant_operation() {
 }
 
 fn get_transaction(expire_period: u64, fee: u64) -> WrappedOperation {
-    let sender_keypair = KeyPair::generate();
-
     let recv_keypair = KeyPair::generate();
 
     let op = OperationType::Transaction {
         recipient_address: Address::from_public_key(&recv_keypair.get_public_key()),
         amount: Amount::default(),
     };
+    get_operation(&KeyPair::generate(), op, expire_period, fee)
+}
+
+/// Creates an operation of type `op` sent by `sender_keypair`
+fn get_operation(
+    sender_keypair: &KeyPair,
+    op: OperationType,
+    expire_period: u64,
+    fee: u64,
+) -> WrappedOperation {
     let content = Operation {
         fee: Amount::from_str(&fee.to_string()).unwrap(),
         op,
         expire_period,
     };
-    Operation::new_wrapped(content, OperationSerializer::new(), &sender_keypair).unwrap()
+    Operation::new_wrapped(content, OperationSerializer::new(), sender_keypair).unwrap()
 }
 
 /// TODO refactor old tests
@@ -80,7 +96,12 @@ fn test_pool() {
     let (execution_controller, _execution_receiver) = MockExecutionController::new_with_receiver();
     let pool_config = PoolConfig::default();
     let storage_base = Storage::create_root();
-    let mut pool = OperationPool::init(pool_config, &storage_base, execution_controller);
+    let mut pool = OperationPool::init(
+        pool_config,
+        &storage_base,
+        execution_controller,
+        PoolChannels::default(),
+    );
     // generate (id, transactions, range of validity) by threads
     let mut thread_tx_lists = vec![Vec::new(); pool_config.thread_count as usize];
     for i in 0..18 {
@@ -190,3 +211,357 @@ fn test_pool() {
         assert!(ids.is_empty());
     }
 }
+
+/// Executes bytecode with up to `max_gas`
+fn execute_sc(max_gas: u64) -> OperationType {
+    OperationType::ExecuteSC {
+        data: vec![0; 10],
+        max_gas,
+        gas_price: Amount::default(),
+        datastore: Default::default(),
+    }
+}
+
+/// Operations are chosen by fee per weighted size and gas:
+/// a single op using all the block gas loses to two cheaper ops that fit together.
+#[test]
+fn test_block_operations_gas_packing() {
+    let pool_config = PoolConfig {
+        max_block_gas: 1000,
+        ..PoolConfig::default()
+    };
+    let (execution_controller, execution_receiver) = MockExecutionController::new_with_receiver();
+    answer_execution_requests(execution_receiver, Some(Amount::from_str("1000").unwrap()));
+    let storage_base = Storage::create_root();
+    let mut pool = OperationPool::init(
+        pool_config,
+        &storage_base,
+        execution_controller,
+        PoolChannels::default(),
+    );
+
+    let keypair = KeyPair::generate();
+    let gas_heavy_op = get_operation(&keypair, execute_sc(1000), 10, 10);
+    let light_ops = vec![
+        get_operation(&keypair, execute_sc(400), 10, 8),
+        get_operation(&keypair, execute_sc(400), 10, 8),
+    ];
+    let mut storage = storage_base.clone_without_refs();
+    storage.store_operations(vec![gas_heavy_op.clone()]);
+    storage.store_operations(light_ops.clone());
+    pool.add_operations(storage);
+
+    let thread = gas_heavy_op
+        .creator_address
+        .get_thread(pool_config.thread_count);
+    let (mut ids, _) = pool.get_block_operations(&Slot::new(10, thread));
+    let mut expected: Vec<_> = light_ops.iter().map(|op| op.id).collect();
+    expected.sort();
+    ids.sort();
+    assert_eq!(ids, expected);
+}
+
+/// The max spendings of the ops chosen for a sender do not exceed its balance
+#[test]
+fn test_block_operations_sender_balance() {
+    let pool_config = PoolConfig::default();
+    let (execution_controller, execution_receiver) = MockExecutionController::new_with_receiver();
+    answer_execution_requests(execution_receiver, Some(Amount::from_str("10").unwrap()));
+    let storage_base = Storage::create_root();
+    let mut pool = OperationPool::init(
+        pool_config,
+        &storage_base,
+        execution_controller,
+        PoolChannels::default(),
+    );
+
+    // each transaction might spend 4 coins: 3 of amount and 1 of fee
+    let keypair = KeyPair::generate();
+    let ops: Vec<WrappedOperation> = (0..3)
+        .map(|_| {
+            let op = OperationType::Transaction {
+                recipient_address: Address::from_public_key(&KeyPair::generate().get_public_key()),
+                amount: Amount::from_str("3").unwrap(),
+            };
+            get_operation(&keypair, op, 10, 1)
+        })
+        .collect();
+    let mut storage = storage_base.clone_without_refs();
+    storage.store_operations(ops.clone());
+    pool.add_operations(storage);
+
+    let thread = ops[0].creator_address.get_thread(pool_config.thread_count);
+    let (ids, _) = pool.get_block_operations(&Slot::new(10, thread));
+    assert_eq!(ids.len(), 2);
+}
+
+/// Transfers one coin to `recipient_address`
+fn transfer(recipient_address: Address) -> OperationType {
+    OperationType::Transaction {
+        recipient_address,
+        amount: Amount::from_str("1").unwrap(),
+    }
+}
+
+/// An operation that can't be executed along with another one of its sender,
+/// as their sender can't afford both, replaces it only if its fee is higher
+#[test]
+fn test_replace_by_fee() {
+    // the sender can afford a payment of 1 coin with a fee of 2 and another one without fee, but not two with fees
+    let balance = Some(Amount::from_str("4").unwrap());
+    operation_pool_test_with_balance(
+        PoolConfig::default(),
+        balance,
+        |mut operation_pool, storage| {
+            let keypair = KeyPair::generate();
+            let recipient_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+            let stuck_op = get_operation(&keypair, transfer(recipient_address), 10, 1);
+            let replacing_op = get_operation(&keypair, transfer(recipient_address), 11, 2);
+            let cheaper_op = get_operation(&keypair, transfer(recipient_address), 12, 1);
+
+            for op in [&stuck_op, &replacing_op, &cheaper_op] {
+                let mut op_storage = storage.clone_without_refs();
+                op_storage.store_operations(vec![op.clone()]);
+                operation_pool.add_operations(op_storage);
+            }
+            assert_eq!(operation_pool.len(), 1);
+            assert!(operation_pool.contains(&replacing_op.id));
+            assert_eq!(operation_pool.storage.get_op_refs().len(), 1);
+
+            // the same payment again does not conflict when the sender can afford both
+            let repeated_op = get_operation(&keypair, transfer(recipient_address), 13, 0);
+            let mut op_storage = storage.clone_without_refs();
+            op_storage.store_operations(vec![repeated_op.clone()]);
+            operation_pool.add_operations(op_storage);
+            assert_eq!(operation_pool.len(), 2);
+            assert!(operation_pool.contains(&repeated_op.id));
+        },
+    );
+
+    // operations whose sender balance is unknown never conflict
+    operation_pool_test(PoolConfig::default(), |mut operation_pool, mut storage| {
+        let keypair = KeyPair::generate();
+        let recipient_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+        storage.store_operations(vec![
+            get_operation(&keypair, transfer(recipient_address), 10, 1),
+            get_operation(&keypair, transfer(recipient_address), 11, 2),
+        ]);
+        operation_pool.add_operations(storage);
+        assert_eq!(operation_pool.len(), 2);
+    });
+}
+
+/// A sender with too many operations in the pool only gets its best ones kept
+#[test]
+fn test_max_operations_per_sender() {
+    let pool_config = PoolConfig {
+        max_operations_per_sender: 2,
+        ..PoolConfig::default()
+    };
+    operation_pool_test(pool_config, |mut operation_pool, mut storage| {
+        let keypair = KeyPair::generate();
+        let ops: Vec<WrappedOperation> = [2, 1, 3]
+            .into_iter()
+            .map(|fee| {
+                let recipient_address =
+                    Address::from_public_key(&KeyPair::generate().get_public_key());
+                get_operation(&keypair, transfer(recipient_address), 10, fee)
+            })
+            .collect();
+        storage.store_operations(ops.clone());
+        operation_pool.add_operations(storage);
+        assert_eq!(operation_pool.len(), 2);
+        assert!(operation_pool.contains(&ops[0].id));
+        assert!(!operation_pool.contains(&ops[1].id));
+        assert!(operation_pool.contains(&ops[2].id));
+        assert_eq!(operation_pool.storage.get_op_refs().len(), 2);
+    });
+}
+
+/// The pool reports the rank of its operations and why it rejected or evicted the others
+#[test]
+fn test_operation_status() {
+    // the sender can't afford both payments, but can afford one of them and a roll sell
+    let balance = Some(Amount::from_str("5").unwrap());
+    operation_pool_test_with_balance(
+        PoolConfig::default(),
+        balance,
+        |mut operation_pool, storage| {
+            let keypair = KeyPair::generate();
+            let recipient_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+            let stuck_op = get_operation(&keypair, transfer(recipient_address), 10, 1);
+            let replacing_op = get_operation(&keypair, transfer(recipient_address), 10, 3);
+            let other_op =
+                get_operation(&keypair, OperationType::RollSell { roll_count: 1 }, 10, 1);
+            for op in [&stuck_op, &replacing_op, &other_op] {
+                let mut op_storage = storage.clone_without_refs();
+                op_storage.store_operations(vec![op.clone()]);
+                operation_pool.add_operations(op_storage);
+            }
+
+            let status = operation_pool.get_operation_status(&replacing_op.id);
+            assert!(status.in_pool);
+            assert_eq!(status.rank, Some(0));
+            assert_eq!(
+                status.validity_period_range,
+                Some((
+                    10u64.saturating_sub(PoolConfig::default().operation_validity_periods),
+                    10
+                ))
+            );
+            assert_eq!(status.last_final_period, Some(0));
+            assert_eq!(
+                operation_pool.get_operation_status(&other_op.id).rank,
+                Some(1)
+            );
+
+            let status = operation_pool.get_operation_status(&stuck_op.id);
+            assert!(!status.in_pool);
+            assert_eq!(
+                status.removal_reason,
+                Some(PoolOperationRemovalReason::ReplacedByFee(replacing_op.id))
+            );
+
+            let filter = PoolOperationFilter {
+                creator_address: Some(stuck_op.creator_address),
+                thread: None,
+            };
+            let page = operation_pool.list_operations(&filter, None, 1).unwrap();
+            assert_eq!(page.content, vec![replacing_op.id]);
+            assert_eq!(page.total_count, Some(2));
+            let cursor = page.next_cursor.unwrap().parse().unwrap();
+            let page = operation_pool
+                .list_operations(&filter, Some(cursor), 1)
+                .unwrap();
+            assert_eq!(page.content, vec![other_op.id]);
+            assert_eq!(page.next_cursor, None);
+
+            // the listing can't continue from an operation that left the pool
+            assert!(operation_pool
+                .list_operations(&filter, Some(stuck_op.id), 1)
+                .is_none());
+        },
+    );
+}
+
+/// Paging through the operations of the pool with cursors lists them all once,
+/// in the order of a single listing
+#[test]
+fn test_list_operations_pages() {
+    let thread_count = PoolConfig::default().thread_count;
+    operation_pool_test(PoolConfig::default(), |mut operation_pool, mut storage| {
+        let ops: Vec<WrappedOperation> = (0..20).map(|fee| get_transaction(10, fee)).collect();
+        storage.store_operations(ops.clone());
+        operation_pool.add_operations(storage);
+
+        let filter = PoolOperationFilter::default();
+        let full_page = operation_pool
+            .list_operations(&filter, None, ops.len())
+            .unwrap();
+        assert_eq!(full_page.content.len(), ops.len());
+        assert_eq!(full_page.next_cursor, None);
+
+        let mut listed = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = operation_pool.list_operations(&filter, cursor, 3).unwrap();
+            assert!(page.content.len() <= 3);
+            assert_eq!(page.total_count, Some(ops.len()));
+            listed.extend(page.content);
+            match page.next_cursor {
+                Some(next_cursor) => cursor = Some(next_cursor.parse().unwrap()),
+                None => break,
+            }
+        }
+        assert_eq!(listed, full_page.content);
+
+        // only the operations of the filtered thread are listed
+        let thread = ops[0].creator_address.get_thread(thread_count);
+        let thread_filter = PoolOperationFilter {
+            creator_address: None,
+            thread: Some(thread),
+        };
+        let page = operation_pool
+            .list_operations(&thread_filter, None, ops.len())
+            .unwrap();
+        assert!(page.content.contains(&ops[0].id));
+        assert!(ops
+            .iter()
+            .filter(|op| page.content.contains(&op.id))
+            .all(|op| op.creator_address.get_thread(thread_count) == thread));
+    });
+}
+
+/// Operations that were already executed are removed from the pool once new slots of their thread are final,
+/// while the ones that their creator can't afford are kept until they expire
+#[test]
+fn test_prune_executed_operations() {
+    let pool_config = PoolConfig::default();
+    let keypair = KeyPair::generate();
+    let recipient_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+    // each transfer might spend 2 coins
+    let executed_op = get_operation(&keypair, transfer(recipient_address), 10, 1);
+    let affordable_op = get_operation(&keypair, transfer(recipient_address), 11, 1);
+    // this one might spend 4 coins
+    let expensive_op = get_operation(&keypair, transfer(recipient_address), 12, 3);
+
+    // the balance of the creator is unknown until the operations are added,
+    // so that they don't replace each other
+    let balance = Arc::new(Mutex::new(None));
+    let (execution_controller, execution_receiver) = MockExecutionController::new_with_receiver();
+    let executed_op_id = executed_op.id;
+    let sender_balance = balance.clone();
+    std::thread::spawn(move || {
+        while let Ok(msg) = execution_receiver.recv() {
+            match msg {
+                MockExecutionControllerMessage::UnexecutedOpsAmong {
+                    mut ops,
+                    response_tx,
+                    ..
+                } => {
+                    ops.remove(&executed_op_id);
+                    response_tx.send(ops).unwrap()
+                }
+                MockExecutionControllerMessage::GetFinalAndCandidateBalance {
+                    addresses,
+                    response_tx,
+                } => {
+                    let balance = *sender_balance.lock();
+                    response_tx
+                        .send(vec![(balance, balance); addresses.len()])
+                        .unwrap()
+                }
+                _ => {}
+            }
+        }
+    });
+    let storage = Storage::create_root();
+    let mut pool = OperationPool::init(
+        pool_config,
+        &storage,
+        execution_controller,
+        PoolChannels::default(),
+    );
+    let mut ops_storage = storage.clone_without_refs();
+    ops_storage.store_operations(vec![
+        executed_op.clone(),
+        affordable_op.clone(),
+        expensive_op.clone(),
+    ]);
+    pool.add_operations(ops_storage);
+    assert_eq!(pool.len(), 3);
+
+    *balance.lock() = Some(Amount::from_str("3").unwrap());
+    // no new final period: the pool is not checked
+    pool.notify_final_cs_periods(&vec![0; pool_config.thread_count as usize]);
+    assert_eq!(pool.len(), 3);
+
+    pool.notify_final_cs_periods(&vec![1; pool_config.thread_count as usize]);
+    assert_eq!(pool.len(), 2);
+    assert!(pool.contains(&affordable_op.id));
+    assert!(pool.contains(&expensive_op.id));
+    assert_eq!(
+        pool.get_operation_status(&executed_op.id).removal_reason,
+        Some(PoolOperationRemovalReason::Executed)
+    );
+}
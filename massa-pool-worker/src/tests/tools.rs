@@ -14,7 +14,7 @@ use massa_models::{
     slot::Slot,
     wrapped::WrappedContent,
 };
-use massa_pool_exports::{PoolConfig, PoolController, PoolManager};
+use massa_pool_exports::{PoolChannels, PoolConfig, PoolController, PoolManager};
 use massa_signature::{KeyPair, PublicKey};
 use massa_storage::Storage;
 use std::collections::BTreeMap;
@@ -65,19 +65,57 @@ where
 
     let (execution_controller, execution_receiver) = MockExecutionController::new_with_receiver();
     let (pool_manager, pool_controller) =
-        start_pool_controller(cfg, &storage, execution_controller);
+        start_pool_controller(cfg, &storage, execution_controller, PoolChannels::default());
 
     test(pool_manager, pool_controller, execution_receiver, storage)
 }
 
+/// Answers the requests of the pool to the mock execution controller:
+/// all the operations are unexecuted and every address has `balance` coins, if any.
+pub fn answer_execution_requests(
+    execution_receiver: Receiver<MockExecutionControllerMessage>,
+    balance: Option<Amount>,
+) {
+    std::thread::spawn(move || {
+        while let Ok(msg) = execution_receiver.recv() {
+            match msg {
+                MockExecutionControllerMessage::UnexecutedOpsAmong {
+                    ops, response_tx, ..
+                } => response_tx.send(ops).unwrap(),
+                MockExecutionControllerMessage::GetFinalAndCandidateBalance {
+                    addresses,
+                    response_tx,
+                } => response_tx
+                    .send(vec![(balance, balance); addresses.len()])
+                    .unwrap(),
+                _ => {}
+            }
+        }
+    });
+}
+
 pub fn operation_pool_test<F>(cfg: PoolConfig, test: F)
 where
     F: FnOnce(OperationPool, Storage),
 {
-    let (execution_controller, _) = MockExecutionController::new_with_receiver();
+    operation_pool_test_with_balance(cfg, None, test)
+}
+
+/// Same as `operation_pool_test`, but every address has `balance` coins, if any
+pub fn operation_pool_test_with_balance<F>(cfg: PoolConfig, balance: Option<Amount>, test: F)
+where
+    F: FnOnce(OperationPool, Storage),
+{
+    let (execution_controller, execution_receiver) = MockExecutionController::new_with_receiver();
+    answer_execution_requests(execution_receiver, balance);
     let storage = Storage::create_root();
     test(
-        OperationPool::init(cfg, &storage.clone_without_refs(), execution_controller),
+        OperationPool::init(
+            cfg,
+            &storage.clone_without_refs(),
+            execution_controller,
+            PoolChannels::default(),
+        ),
         storage,
     )
 }
@@ -1,13 +1,14 @@
 use massa_models::{
     address::Address,
     amount::Amount,
+    api::PoolOperationRemovalReason,
     operation::{OperationId, WrappedOperation},
 };
 use num::rational::Ratio;
 use std::cmp::Reverse;
 use std::ops::RangeInclusive;
 
-pub type OperationCursorInner = (Reverse<Ratio<u64>>, OperationId);
+pub type OperationCursorInner = (Reverse<Ratio<u128>>, OperationId);
 /// A cursor for pool operations, sorted by increasing quality
 #[derive(PartialEq, Eq, PartialOrd, Ord, Copy, Clone, Debug)]
 pub struct PoolOperationCursor(OperationCursorInner);
@@ -32,7 +33,6 @@ pub struct OperationInfo {
     pub max_gas: u64,
     pub creator_address: Address,
     pub thread: u8,
-    pub fee: Amount,
     /// max amount that the op might spend from the sender's balance
     pub max_spending: Amount,
     pub validity_period_range: RangeInclusive<u64>,
@@ -44,14 +44,15 @@ impl OperationInfo {
         operation_validity_periods: u64,
         roll_price: Amount,
         thread_count: u8,
+        max_block_size: u32,
+        max_block_gas: u64,
     ) -> Self {
         OperationInfo {
             id: op.id,
-            cursor: build_operation_cursor(op),
+            cursor: build_operation_cursor(op, max_block_size, max_block_gas),
             size: op.serialized_size(),
             max_gas: op.get_gas_usage(),
             creator_address: op.creator_address,
-            fee: op.get_total_fee(),
             thread: op.creator_address.get_thread(thread_count),
             validity_period_range: op.get_validity_range(operation_validity_periods),
             max_spending: op.get_max_spending(roll_price),
@@ -59,10 +60,29 @@ impl OperationInfo {
     }
 }
 
+/// What the pool remembers of an operation it rejected or evicted
+#[derive(Debug, Clone)]
+pub struct RemovedOperationInfo {
+    pub thread: u8,
+    pub validity_period_range: RangeInclusive<u64>,
+    pub reason: PoolOperationRemovalReason,
+}
+
 /// build a cursor from an operation
-fn build_operation_cursor(op: &WrappedOperation) -> PoolOperationCursor {
-    let quality = Ratio::new(op.get_total_fee().to_raw(), op.serialized_size() as u64);
+///
+/// The quality of an operation is its fee per unit of weighted resource usage,
+/// each resource being weighted by the inverse of its capacity in a block:
+/// `fee / (size / max_block_size + max_gas / max_block_gas)`.
+/// Both sides of the ratio are multiplied by `max_block_size * max_block_gas` to stay in integers.
+fn build_operation_cursor(
+    op: &WrappedOperation,
+    max_block_size: u32,
+    max_block_gas: u64,
+) -> PoolOperationCursor {
+    let weight = (op.serialized_size() as u128)
+        .saturating_mul(max_block_gas as u128)
+        .saturating_add((op.get_gas_usage() as u128).saturating_mul(max_block_size as u128));
+    let quality = Ratio::new(op.get_total_fee().to_raw() as u128, weight.max(1));
     let inner = (Reverse(quality), op.id);
-    // TODO take into account max_gas as well in the future (multi-dimensional packing)
     PoolOperationCursor::new(inner)
 }
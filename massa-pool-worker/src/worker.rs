@@ -6,7 +6,7 @@ use crate::controller_impl::{Command, PoolManagerImpl};
 use crate::operation_pool::OperationPool;
 use crate::{controller_impl::PoolControllerImpl, endorsement_pool::EndorsementPool};
 use massa_execution_exports::ExecutionController;
-use massa_pool_exports::PoolConfig;
+use massa_pool_exports::{PoolChannels, PoolConfig};
 use massa_pool_exports::{PoolController, PoolManager};
 use massa_storage::Storage;
 use parking_lot::RwLock;
@@ -112,6 +112,7 @@ pub fn start_pool_controller(
     config: PoolConfig,
     storage: &Storage,
     execution_controller: Box<dyn ExecutionController>,
+    channels: PoolChannels,
 ) -> (Box<dyn PoolManager>, Box<dyn PoolController>) {
     let (operations_input_sender, operations_input_receiver) = sync_channel(config.channels_size);
     let (endorsements_input_sender, endorsements_input_receiver) =
@@ -120,6 +121,7 @@ pub fn start_pool_controller(
         config,
         storage,
         execution_controller,
+        channels,
     )));
     let endorsement_pool = Arc::new(RwLock::new(EndorsementPool::init(config, storage)));
     let controller = PoolControllerImpl {
@@ -55,4 +55,13 @@ pub struct ProtocolConfig {
     pub max_operations_propagation_time: MassaTime,
     /// max time we propagate endorsements
     pub max_endorsements_propagation_time: MassaTime,
+    /// Ask peers for the operation prefixes of a block and fetch only the operations missing from storage
+    pub compact_block_relay: bool,
+    /// Signatures of the headers, endorsements and operations received from nodes
+    /// are collected for `sig_verification_batch_window` milliseconds, then verified together
+    pub sig_verification_batch_window: MassaTime,
+    /// Collected signatures are verified right away once there are `max_sig_verification_batch_size` of them
+    pub max_sig_verification_batch_size: usize,
+    /// Number of threads verifying the collected signatures
+    pub sig_verification_thread_count: usize,
 }
@@ -16,7 +16,7 @@ use massa_models::{
     operation::{Operation, OperationType, WrappedOperation},
     slot::Slot,
 };
-use massa_network_exports::{AskForBlocksInfo, BlockInfoReply, NetworkCommand};
+use massa_network_exports::{AskForBlocksInfo, BlockInfoReply, NetworkCommand, PeerBehavior};
 use massa_signature::KeyPair;
 use massa_time::MassaTime;
 use std::collections::HashMap;
@@ -285,6 +285,10 @@ pub fn create_protocol_config() -> ProtocolConfig {
         t0: MassaTime::from_millis(16000),
         max_operations_propagation_time: MassaTime::from_millis(30000),
         max_endorsements_propagation_time: MassaTime::from_millis(60000),
+        compact_block_relay: false,
+        sig_verification_batch_window: 10.into(),
+        max_sig_verification_batch_size: 1024,
+        sig_verification_thread_count: 2,
     }
 }
 
@@ -367,3 +371,30 @@ pub async fn assert_banned_nodes(
         }
     }
 }
+
+/// assert a behavior of a list of node(s) has been reported
+pub async fn assert_reported_behaviors(
+    mut nodes: Vec<NodeId>,
+    behavior: PeerBehavior,
+    network_controller: &mut MockNetworkController,
+) {
+    let timer = sleep(MassaTime::from_millis(5000).into());
+    tokio::pin!(timer);
+    loop {
+        tokio::select! {
+            msg = network_controller
+                   .wait_command(2000.into(), |cmd| match cmd {
+                       NetworkCommand::NodeBehaviors(behaviors) => Some(behaviors),
+                       _ => None,
+                   })
+             =>  {
+                 let reported = msg.expect("Behaviors not reported before timeout.");
+                 nodes.drain_filter(|id| reported.contains(&(*id, behavior)));
+                 if nodes.is_empty() {
+                     break;
+                 }
+            },
+            _ = &mut timer => panic!("Behaviors not reported before timeout.")
+        }
+    }
+}
@@ -6,17 +6,19 @@ use std::collections::hash_map::Entry;
 
 use crate::node_info::NodeInfo;
 use crate::protocol_worker::ProtocolWorker;
+use crate::sig_verifier::{Signatures, SignedData, VerifiedData};
 use massa_hash::{Hash, HASH_SIZE_BYTES};
 use massa_logging::massa_trace;
 use massa_models::{
     block::Block,
     block::{BlockId, BlockSerializer, WrappedHeader},
+    endorsement::WrappedEndorsement,
     node::NodeId,
-    operation::{OperationId, WrappedOperation},
+    operation::{OperationId, OperationPrefixId, OperationPrefixIds, WrappedOperation},
     prehash::{CapacityAllocator, PreHashSet},
     wrapped::{Id, Wrapped},
 };
-use massa_network_exports::{AskForBlocksInfo, BlockInfoReply, NetworkEvent};
+use massa_network_exports::{AskForBlocksInfo, BlockInfoReply, NetworkEvent, PeerBehavior};
 use massa_protocol_exports::{ProtocolError, ProtocolEvent};
 use massa_serialization::Serializer;
 use massa_storage::Storage;
@@ -94,10 +96,182 @@ impl ProtocolWorker {
                 header,
             } => {
                 massa_trace!(BLOCK_HEADER, { "node": source_node_id, "header": header});
+                self.on_signed_data_received(
+                    source_node_id,
+                    SignedData::Header(header),
+                    block_ask_timer,
+                    op_timer,
+                )
+                .await?;
+            }
+            NetworkEvent::ReceivedOperations { node, operations } => {
+                massa_trace!(OPS, { "node": node, "operations": operations});
+                self.on_signed_data_received(
+                    node,
+                    SignedData::Operations(operations),
+                    block_ask_timer,
+                    op_timer,
+                )
+                .await?;
+            }
+            NetworkEvent::ReceivedEndorsements { node, endorsements } => {
+                massa_trace!(ENDORSEMENTS, { "node": node, "endorsements": endorsements});
+                self.on_signed_data_received(
+                    node,
+                    SignedData::Endorsements(endorsements),
+                    block_ask_timer,
+                    op_timer,
+                )
+                .await?;
+            }
+            NetworkEvent::ReceivedOperationAnnouncements {
+                node,
+                operation_prefix_ids,
+            } => {
+                massa_trace!(OPS_BATCH, { "node": node, "operation_ids": operation_prefix_ids});
+                self.on_operations_announcements_received(operation_prefix_ids, node)
+                    .await?;
+            }
+            NetworkEvent::ReceiveAskForOperations {
+                node,
+                operation_prefix_ids,
+            } => {
+                massa_trace!(ASKED_OPS, { "node": node, "operation_ids": operation_prefix_ids});
+                self.on_asked_operations_received(node, operation_prefix_ids)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// On headers, endorsements or operations broadcast by a node.
+    ///
+    /// Their signatures that were not verified yet are collected by the `SigVerifier`,
+    /// to be verified in a batch with the ones received from other nodes.
+    /// The data is processed by `on_verified_data_received` once they are verified.
+    async fn on_signed_data_received(
+        &mut self,
+        node_id: NodeId,
+        data: SignedData,
+        block_ask_timer: &mut Pin<&mut Sleep>,
+        op_timer: &mut Pin<&mut Sleep>,
+    ) -> Result<(), ProtocolError> {
+        let signatures = self.get_unchecked_signatures(&data);
+        if signatures.is_empty() {
+            // nothing to verify
+            return self
+                .on_checked_data_received(node_id, data, block_ask_timer, op_timer)
+                .await;
+        }
+        self.sig_verifier.submit(node_id, data, signatures);
+        Ok(())
+    }
+
+    /// Gets the signatures of some data that are not known to be valid yet
+    fn get_unchecked_signatures(&self, data: &SignedData) -> Signatures {
+        let endorsement_signature = |endorsement: &WrappedEndorsement| {
+            (
+                *endorsement.id.get_hash(),
+                endorsement.signature,
+                endorsement.creator_public_key,
+            )
+        };
+        match data {
+            SignedData::Header(header) => {
+                // genesis and known headers are handled without verification
+                if header.content.slot.period == 0
+                    || header.content.parents.is_empty()
+                    || self.checked_headers.get(&header.id).is_some()
+                {
+                    return Vec::new();
+                }
+                std::iter::once((
+                    *header.id.get_hash(),
+                    header.signature,
+                    header.creator_public_key,
+                ))
+                .chain(
+                    header
+                        .content
+                        .endorsements
+                        .iter()
+                        .filter(|endorsement| !self.checked_endorsements.contains(&endorsement.id))
+                        .map(endorsement_signature),
+                )
+                .collect()
+            }
+            SignedData::Endorsements(endorsements) => endorsements
+                .iter()
+                .filter(|endorsement| !self.checked_endorsements.contains(&endorsement.id))
+                .map(endorsement_signature)
+                .collect(),
+            SignedData::Operations(operations) => operations
+                .iter()
+                .filter(|operation| !self.checked_operations.contains_id(&operation.id))
+                .map(|operation| {
+                    (
+                        *operation.id.get_hash(),
+                        operation.signature,
+                        operation.creator_public_key,
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// On a batch of data whose signatures were verified by the `SigVerifier`.
+    ///
+    /// Valid data is processed. Nodes that sent data with invalid signatures are reported
+    /// with `PeerBehavior::InvalidData`, lowering the reputation of their peer until the network bans it.
+    pub(crate) async fn on_verified_data_received(
+        &mut self,
+        verified: Vec<VerifiedData>,
+        block_ask_timer: &mut Pin<&mut Sleep>,
+        op_timer: &mut Pin<&mut Sleep>,
+    ) -> Result<(), ProtocolError> {
+        self.sig_verifier.note_batch_received();
+        for VerifiedData {
+            node_id,
+            data,
+            is_valid,
+        } in verified
+        {
+            if is_valid {
+                self.on_checked_data_received(node_id, data, block_ask_timer, op_timer)
+                    .await?;
+            } else {
+                warn!(
+                    "node {} sent us data with invalid signatures, \
+                    which may be an attack attempt by the remote node",
+                    node_id
+                );
+                self.report_node_behavior(&node_id, PeerBehavior::InvalidData);
+            }
+        }
+        // the batch that waited for a thread can be verified now
+        self.sig_verifier.flush_if_due(Instant::now());
+        Ok(())
+    }
+
+    /// On headers, endorsements or operations broadcast by a node, whose signatures are valid
+    async fn on_checked_data_received(
+        &mut self,
+        node_id: NodeId,
+        data: SignedData,
+        block_ask_timer: &mut Pin<&mut Sleep>,
+        op_timer: &mut Pin<&mut Sleep>,
+    ) -> Result<(), ProtocolError> {
+        match data {
+            SignedData::Header(header) => {
+                if !self.active_nodes.contains_key(&node_id) {
+                    // the node disconnected while the header was being verified
+                    return Ok(());
+                }
                 if let Some((block_id, is_new)) =
-                    self.note_header_from_node(&header, &source_node_id).await?
+                    self.note_header_from_node(&header, &node_id, true).await?
                 {
                     if is_new {
+                        self.report_node_behavior(&node_id, PeerBehavior::FirstBlockAnnouncement);
                         self.send_protocol_event(ProtocolEvent::ReceivedBlockHeader {
                             block_id,
                             header,
@@ -110,47 +284,29 @@ impl ProtocolWorker {
                         "node {} sent us critically incorrect header, \
                         which may be an attack attempt by the remote node \
                         or a loss of sync between us and the remote node",
-                        source_node_id,
+                        node_id,
                     );
-                    let _ = self.ban_node(&source_node_id).await;
+                    self.report_node_behavior(&node_id, PeerBehavior::InvalidData);
                 }
             }
-            NetworkEvent::ReceivedOperations { node, operations } => {
-                massa_trace!(OPS, { "node": node, "operations": operations});
-                self.on_operations_received(node, operations, op_timer)
+            SignedData::Operations(operations) => {
+                self.on_operations_received(node_id, operations, op_timer)
                     .await;
             }
-            NetworkEvent::ReceivedEndorsements { node, endorsements } => {
-                massa_trace!(ENDORSEMENTS, { "node": node, "endorsements": endorsements});
+            SignedData::Endorsements(endorsements) => {
                 if let Err(err) = self
-                    .note_endorsements_from_node(endorsements, &node, true)
+                    .note_endorsements_from_node(endorsements, &node_id, true, true)
                     .await
                 {
                     warn!(
                         "node {} sent us critically incorrect endorsements, \
                         which may be an attack attempt by the remote node or a \
                         loss of sync between us and the remote node. Err = {}",
-                        node, err
+                        node_id, err
                     );
-                    let _ = self.ban_node(&node).await;
+                    self.report_node_behavior(&node_id, PeerBehavior::InvalidData);
                 }
             }
-            NetworkEvent::ReceivedOperationAnnouncements {
-                node,
-                operation_prefix_ids,
-            } => {
-                massa_trace!(OPS_BATCH, { "node": node, "operation_ids": operation_prefix_ids});
-                self.on_operations_announcements_received(operation_prefix_ids, node)
-                    .await?;
-            }
-            NetworkEvent::ReceiveAskForOperations {
-                node,
-                operation_prefix_ids,
-            } => {
-                massa_trace!(ASKED_OPS, { "node": node, "operation_ids": operation_prefix_ids});
-                self.on_asked_operations_received(node, operation_prefix_ids)
-                    .await?;
-            }
         }
         Ok(())
     }
@@ -159,7 +315,9 @@ impl ProtocolWorker {
     ///
     /// React on another node asking for blocks information. We can forward the operation ids if
     /// the foreign node asked for `AskForBlocksInfo::Info` or the full operations if he asked for
-    /// the missing operations in his storage with `AskForBlocksInfo::Operations`
+    /// the missing operations in his storage with `AskForBlocksInfo::Operations`.
+    /// Compact relay asks for the operation prefixes with `AskForBlocksInfo::CompactInfo`,
+    /// then for the operations it could not find locally with `AskForBlocksInfo::OperationsByPrefix`.
     ///
     /// Forward the reply to the network.
     async fn on_asked_for_blocks_received(
@@ -208,6 +366,31 @@ impl ProtocolWorker {
                     };
                     BlockInfoReply::Operations(needed_ops)
                 }
+                AskForBlocksInfo::CompactInfo => BlockInfoReply::CompactInfo(
+                    operations_ids.iter().map(|id| id.prefix()).collect(),
+                ),
+                AskForBlocksInfo::OperationsByPrefix(prefixes) => {
+                    // Mark the node as having the block.
+                    node_info.insert_known_blocks(
+                        &[*hash],
+                        true,
+                        Instant::now(),
+                        self.config.max_node_known_blocks_size,
+                    );
+
+                    // Send only the operations of the block matching the asked prefixes.
+                    let prefixes: OperationPrefixIds = prefixes.iter().copied().collect();
+                    let needed_ops = {
+                        let operations = self.storage.read_operations();
+                        operations_ids
+                            .into_iter()
+                            .filter(|id| prefixes.contains(&id.prefix()))
+                            .filter_map(|id| operations.get(&id))
+                            .cloned()
+                            .collect()
+                    };
+                    BlockInfoReply::Operations(needed_ops)
+                }
             };
             all_blocks_info.push((*hash, block_info));
         }
@@ -249,6 +432,7 @@ impl ProtocolWorker {
                     "Node {} sent us header for block id {} but we already received it.",
                     from_node_id, block_id
                 );
+                self.report_node_behavior(&from_node_id, PeerBehavior::DuplicateData);
                 if let Some(node) = self.active_nodes.get_mut(&from_node_id) && node.asked_blocks.contains_key(&block_id) {
                     node.asked_blocks.remove(&block_id);
                     node.insert_known_blocks(&[block_id], false, Instant::now(), self.config.max_node_known_blocks_size);
@@ -257,14 +441,17 @@ impl ProtocolWorker {
                 return Ok(());
             }
         }
-        if let Err(err) = self.note_header_from_node(&header, &from_node_id).await {
+        if let Err(err) = self
+            .note_header_from_node(&header, &from_node_id, false)
+            .await
+        {
             warn!(
                 "node {} sent us critically incorrect header through protocol, \
                 which may be an attack attempt by the remote node \
                 or a loss of sync between us and the remote node. Err = {}",
                 from_node_id, err
             );
-            let _ = self.ban_node(&from_node_id).await;
+            self.report_node_behavior(&from_node_id, PeerBehavior::InvalidData);
             return Ok(());
         };
         if let Some(info) = self.block_wishlist.get_mut(&block_id) {
@@ -316,6 +503,7 @@ impl ProtocolWorker {
                 "Node {} sent us an operation list but we don't have block id {} in our wishlist.",
                 from_node_id, block_id
             );
+            self.report_node_behavior(&from_node_id, PeerBehavior::DuplicateData);
             if let Some(node) = self.active_nodes.get_mut(&from_node_id) && node.asked_blocks.contains_key(&block_id) {
                 node.asked_blocks.remove(&block_id);
                 node.insert_known_blocks(&[block_id], false, Instant::now(), self.config.max_node_known_blocks_size);
@@ -339,6 +527,7 @@ impl ProtocolWorker {
                 "Node {} sent us an operation list for block id {} but we already received it.",
                 from_node_id, block_id
             );
+            self.report_node_behavior(&from_node_id, PeerBehavior::DuplicateData);
             if let Some(node) = self.active_nodes.get_mut(&from_node_id) && node.asked_blocks.contains_key(&block_id) {
                 node.asked_blocks.remove(&block_id);
                 node.insert_known_blocks(&[block_id], false, Instant::now(), self.config.max_node_known_blocks_size);
@@ -369,7 +558,7 @@ impl ProtocolWorker {
 
             if info.operations_size > self.config.max_serialized_operations_size_per_block {
                 warn!("Node id {} sent us a operation list for block id {} but the operations we already have in our records exceed max size.", from_node_id, block_id);
-                let _ = self.ban_node(&from_node_id).await;
+                self.report_node_behavior(&from_node_id, PeerBehavior::InvalidData);
                 return Ok(());
             }
 
@@ -391,7 +580,7 @@ impl ProtocolWorker {
             }
         } else {
             warn!("Node id {} sent us a operation list for block id {} but the hash in header doesn't match.", from_node_id, block_id);
-            let _ = self.ban_node(&from_node_id).await;
+            self.report_node_behavior(&from_node_id, PeerBehavior::InvalidData);
         }
         Ok(())
     }
@@ -417,14 +606,14 @@ impl ProtocolWorker {
         op_timer: &mut Pin<&mut Sleep>,
     ) -> Result<(), ProtocolError> {
         if let Err(err) = self
-            .note_operations_from_node(operations.clone(), &from_node_id, op_timer)
+            .note_operations_from_node(operations.clone(), &from_node_id, op_timer, false)
             .await
         {
             warn!(
                 "Node id {} sent us operations for block id {} but they failed at verifications. Err = {}",
                 from_node_id, block_id, err
             );
-            let _ = self.ban_node(&from_node_id).await;
+            self.report_node_behavior(&from_node_id, PeerBehavior::InvalidData);
             return Ok(());
         }
 
@@ -469,7 +658,7 @@ impl ProtocolWorker {
                 };
                 if full_op_size > self.config.max_serialized_operations_size_per_block {
                     warn!("Node id {} sent us full operations for block id {} but they exceed max size.", from_node_id, block_id);
-                    let _ = self.ban_node(&from_node_id).await;
+                    self.report_node_behavior(&from_node_id, PeerBehavior::InvalidData);
                     self.block_wishlist.remove(&block_id);
                     ProtocolEvent::InvalidBlock { block_id, header }
                 } else {
@@ -478,6 +667,7 @@ impl ProtocolWorker {
                             "Node id {} didn't sent us all the full operations for block id {}.",
                             from_node_id, block_id
                         );
+                        self.report_node_behavior(&from_node_id, PeerBehavior::InvalidData);
                         if let Some(node) = self.active_nodes.get_mut(&from_node_id) && node.asked_blocks.contains_key(&block_id) {
                             node.asked_blocks.remove(&block_id);
                             node.insert_known_blocks(&[block_id], false, Instant::now(), self.config.max_node_known_blocks_size);
@@ -540,6 +730,223 @@ impl ProtocolWorker {
         self.remove_asked_blocks_of_node(&remove_hashes)
     }
 
+    /// On the ordered operation prefixes of a block received from a node (compact block relay).
+    /// The prefixes are resolved against the operations we already have,
+    /// and only the missing ones are asked with `AskForBlocksInfo::OperationsByPrefix`.
+    async fn on_block_compact_info_received(
+        &mut self,
+        from_node_id: NodeId,
+        block_id: BlockId,
+        prefixes: Vec<OperationPrefixId>,
+        op_timer: &mut Pin<&mut Sleep>,
+    ) -> Result<(), ProtocolError> {
+        // add to known ops
+        if let Some(node_info) = self.active_nodes.get_mut(&from_node_id) {
+            node_info.insert_known_ops(prefixes.iter().copied());
+        }
+
+        let info = if let Some(info) = self.block_wishlist.get_mut(&block_id) {
+            info
+        } else {
+            warn!(
+                "Node {} sent us operation prefixes but we don't have block id {} in our wishlist.",
+                from_node_id, block_id
+            );
+            self.report_node_behavior(&from_node_id, PeerBehavior::DuplicateData);
+            if let Some(node) = self.active_nodes.get_mut(&from_node_id) && node.asked_blocks.contains_key(&block_id) {
+                node.asked_blocks.remove(&block_id);
+                node.insert_known_blocks(&[block_id], false, Instant::now(), self.config.max_node_known_blocks_size);
+            }
+            return Ok(());
+        };
+
+        if info.header.is_none() {
+            warn!("Node {} sent us operation prefixes but we don't have receive the header of block id {} yet.", from_node_id, block_id);
+            if let Some(node) = self.active_nodes.get_mut(&from_node_id) && node.asked_blocks.contains_key(&block_id) {
+                node.asked_blocks.remove(&block_id);
+                node.insert_known_blocks(&[block_id], false, Instant::now(), self.config.max_node_known_blocks_size);
+            }
+            return Ok(());
+        }
+
+        if info.operation_ids.is_some() || info.operation_prefix_ids.is_some() {
+            warn!(
+                "Node {} sent us operation prefixes for block id {} but we already received them.",
+                from_node_id, block_id
+            );
+            self.report_node_behavior(&from_node_id, PeerBehavior::DuplicateData);
+            if let Some(node) = self.active_nodes.get_mut(&from_node_id) && node.asked_blocks.contains_key(&block_id) {
+                node.asked_blocks.remove(&block_id);
+                node.insert_known_blocks(&[block_id], false, Instant::now(), self.config.max_node_known_blocks_size);
+            }
+            return Ok(());
+        }
+        info.operation_prefix_ids = Some(prefixes);
+
+        // Update ask block
+        let mut set = PreHashSet::<BlockId>::with_capacity(1);
+        set.insert(block_id);
+        self.remove_asked_blocks_of_node(&set)?;
+
+        self.resolve_compact_block(from_node_id, block_id, op_timer)
+            .await?;
+        Ok(())
+    }
+
+    /// On the operations asked by prefix for a compact block received from a node.
+    /// The operations are stored in the block storage before resolving the block prefixes again.
+    async fn on_block_compact_operations_received(
+        &mut self,
+        from_node_id: NodeId,
+        block_id: BlockId,
+        mut operations: Vec<WrappedOperation>,
+        op_timer: &mut Pin<&mut Sleep>,
+    ) -> Result<(), ProtocolError> {
+        if let Err(err) = self
+            .note_operations_from_node(operations.clone(), &from_node_id, op_timer, false)
+            .await
+        {
+            warn!(
+                "Node id {} sent us operations for block id {} but they failed at verifications. Err = {}",
+                from_node_id, block_id, err
+            );
+            self.report_node_behavior(&from_node_id, PeerBehavior::InvalidData);
+            return Ok(());
+        }
+
+        let info = match self.block_wishlist.get_mut(&block_id) {
+            Some(info) => info,
+            None => return Ok(()),
+        };
+        let prefixes: OperationPrefixIds = match &info.operation_prefix_ids {
+            Some(prefixes) => prefixes.iter().copied().collect(),
+            None => return Ok(()),
+        };
+        operations.retain(|op| prefixes.contains(&op.id.prefix()));
+        // add operations to local storage and claim ref
+        info.storage.store_operations(operations);
+
+        if !self
+            .resolve_compact_block(from_node_id, block_id, op_timer)
+            .await?
+        {
+            warn!(
+                "Node id {} didn't sent us all the operations asked by prefix for block id {}.",
+                from_node_id, block_id
+            );
+            self.report_node_behavior(&from_node_id, PeerBehavior::InvalidData);
+            if let Some(node) = self.active_nodes.get_mut(&from_node_id) && node.asked_blocks.contains_key(&block_id) {
+                node.asked_blocks.remove(&block_id);
+                node.insert_known_blocks(&[block_id], false, Instant::now(), self.config.max_node_known_blocks_size);
+            }
+        }
+        Ok(())
+    }
+
+    /// Tries to resolve the operation prefixes of a compact block into operation ids,
+    /// using the operations in storage.
+    ///
+    /// Once all prefixes are resolved and match the operation merkle root of the header,
+    /// the block goes through the usual full operations checks.
+    /// If a prefix is ambiguous or the resolved operations don't match the header,
+    /// compact relay is abandoned for that block and its full operation list is asked instead.
+    ///
+    /// # Result
+    /// `false` if some operations are still missing from storage, `true` otherwise.
+    async fn resolve_compact_block(
+        &mut self,
+        from_node_id: NodeId,
+        block_id: BlockId,
+        op_timer: &mut Pin<&mut Sleep>,
+    ) -> Result<bool, ProtocolError> {
+        let info = match self.block_wishlist.get_mut(&block_id) {
+            Some(info) => info,
+            None => return Ok(true),
+        };
+        let (prefixes, header) = match (&info.operation_prefix_ids, &info.header) {
+            (Some(prefixes), Some(header)) => (prefixes, header),
+            _ => return Ok(true),
+        };
+
+        let mut operation_ids = Vec::with_capacity(prefixes.len());
+        let mut missing = false;
+        let mut ambiguous = false;
+        {
+            let block_operations = info.storage.get_op_refs();
+            let operations = self.storage.read_operations();
+            for prefix in prefixes {
+                let candidates = match operations.get_operations_by_prefix(prefix) {
+                    Some(candidates) if !candidates.is_empty() => candidates,
+                    _ => {
+                        missing = true;
+                        continue;
+                    }
+                };
+                if candidates.len() == 1 {
+                    operation_ids.extend(candidates.iter().copied());
+                    continue;
+                }
+                // prefix collision: prefer the operation received for this block
+                let mut in_block = candidates.iter().filter(|id| block_operations.contains(id));
+                match (in_block.next(), in_block.next()) {
+                    (Some(id), None) => operation_ids.push(*id),
+                    _ => {
+                        ambiguous = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !ambiguous {
+            let resolved: PreHashSet<OperationId> = operation_ids.iter().copied().collect();
+            // keep the resolved operations until the missing ones are received
+            info.storage.claim_operation_refs(&resolved);
+            if missing {
+                return Ok(false);
+            }
+
+            let mut total_hash: Vec<u8> =
+                Vec::with_capacity(operation_ids.len().saturating_mul(HASH_SIZE_BYTES));
+            operation_ids.iter().for_each(|op_id| {
+                total_hash.extend(op_id.get_hash().into_bytes());
+            });
+            if resolved.len() == operation_ids.len()
+                && header.content.operation_merkle_root == Hash::compute_from(&total_hash)
+            {
+                // drop the operations received for this block that were not resolved
+                let unused: PreHashSet<OperationId> = info
+                    .storage
+                    .get_op_refs()
+                    .difference(&resolved)
+                    .copied()
+                    .collect();
+                info.storage.drop_operation_refs(&unused);
+                info.operation_prefix_ids = None;
+                info.operation_ids = Some(operation_ids);
+                self.checked_operations.extend(resolved.iter().copied());
+                self.on_block_full_operations_received(
+                    from_node_id,
+                    block_id,
+                    Default::default(),
+                    op_timer,
+                )
+                .await?;
+                return Ok(true);
+            }
+        }
+
+        warn!(
+            "Could not resolve the operation prefixes of block id {} sent by node {}, asking for its full operation list.",
+            block_id, from_node_id
+        );
+        let refs = info.storage.get_op_refs().clone();
+        info.storage.drop_operation_refs(&refs);
+        info.operation_prefix_ids = None;
+        info.compact_relay_failed = true;
+        Ok(true)
+    }
+
     async fn on_block_info_received(
         &mut self,
         from_node_id: NodeId,
@@ -547,6 +954,17 @@ impl ProtocolWorker {
         info: BlockInfoReply,
         op_timer: &mut Pin<&mut Sleep>,
     ) -> Result<(), ProtocolError> {
+        // reward nodes replying to our block requests before they time out
+        let replied_in_time = self
+            .active_nodes
+            .get(&from_node_id)
+            .and_then(|node_info| node_info.asked_blocks.get(&block_id))
+            .map_or(false, |ask_time| {
+                ask_time.elapsed() < self.config.ask_block_timeout.to_duration()
+            });
+        if replied_in_time && !matches!(info, BlockInfoReply::NotFound) {
+            self.report_node_behavior(&from_node_id, PeerBehavior::FastBlockReply);
+        }
         match info {
             BlockInfoReply::Header(header) => {
                 // Verify and Send it consensus
@@ -566,7 +984,26 @@ impl ProtocolWorker {
                 )
                 .await
             }
+            BlockInfoReply::CompactInfo(prefixes) => {
+                self.on_block_compact_info_received(from_node_id, block_id, prefixes, op_timer)
+                    .await
+            }
             BlockInfoReply::Operations(operations) => {
+                // Operations asked by prefix complete a compact block.
+                let compact = self
+                    .block_wishlist
+                    .get(&block_id)
+                    .map_or(false, |info| info.operation_prefix_ids.is_some());
+                if compact {
+                    return self
+                        .on_block_compact_operations_received(
+                            from_node_id,
+                            block_id,
+                            operations,
+                            op_timer,
+                        )
+                        .await;
+                }
                 // Send operations to pool,
                 // before performing the below checks,
                 // and wait for them to have been procesed(i.e. added to storage).
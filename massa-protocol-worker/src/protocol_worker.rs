@@ -2,7 +2,7 @@
 
 use crate::cache::{LinearHashCacheMap, LinearHashCacheSet};
 use crate::checked_operations::CheckedOperations;
-use crate::sig_verifier::verify_sigs_batch;
+use crate::sig_verifier::{verify_sigs_batch, SigVerifier};
 use crate::{node_info::NodeInfo, worker_operations_impl::OperationBatchBuffer};
 
 use massa_logging::massa_trace;
@@ -14,10 +14,12 @@ use massa_models::{
     endorsement::{EndorsementId, WrappedEndorsement},
     node::NodeId,
     operation::OperationPrefixId,
-    operation::{OperationId, WrappedOperation},
+    operation::{OperationId, OperationPrefixId, OperationPrefixIds, WrappedOperation},
     prehash::{CapacityAllocator, PreHashMap, PreHashSet},
 };
-use massa_network_exports::{AskForBlocksInfo, NetworkCommandSender, NetworkEventReceiver};
+use massa_network_exports::{
+    AskForBlocksInfo, NetworkCommandSender, NetworkEventReceiver, PeerBehavior,
+};
 use massa_pool_exports::PoolController;
 use massa_protocol_exports::{
     ProtocolCommand, ProtocolCommandSender, ProtocolConfig, ProtocolError, ProtocolEvent,
@@ -69,21 +71,20 @@ pub async fn start_protocol_controller(
         mpsc::channel::<ProtocolCommand>(config.controller_channel_size);
     let (manager_tx, controller_manager_rx) = mpsc::channel::<ProtocolManagementCommand>(1);
     let pool_controller = pool_controller.clone();
+    let worker = ProtocolWorker::new(
+        config,
+        ProtocolWorkerChannels {
+            network_command_sender,
+            network_event_receiver,
+            controller_event_tx,
+            controller_command_rx,
+            controller_manager_rx,
+        },
+        pool_controller,
+        storage,
+    )?;
     let join_handle = tokio::spawn(async move {
-        let res = ProtocolWorker::new(
-            config,
-            ProtocolWorkerChannels {
-                network_command_sender,
-                network_event_receiver,
-                controller_event_tx,
-                controller_command_rx,
-                controller_manager_rx,
-            },
-            pool_controller,
-            storage,
-        )
-        .run_loop()
-        .await;
+        let res = worker.run_loop().await;
         match res {
             Err(err) => {
                 error!("protocol worker crashed: {}", err);
@@ -115,6 +116,11 @@ pub(crate) struct BlockInfo {
     pub(crate) storage: Storage,
     /// Full operations size in bytes
     pub(crate) operations_size: usize,
+    /// Ordered operation prefixes received through compact block relay,
+    /// until they are all resolved to operations ids.
+    pub(crate) operation_prefix_ids: Option<Vec<OperationPrefixId>>,
+    /// Compact block relay failed for this block, the full operation list is asked instead
+    pub(crate) compact_relay_failed: bool,
 }
 
 impl BlockInfo {
@@ -124,6 +130,8 @@ impl BlockInfo {
             operation_ids: None,
             storage,
             operations_size: 0,
+            operation_prefix_ids: None,
+            compact_relay_failed: false,
         }
     }
 }
@@ -150,7 +158,7 @@ pub struct ProtocolWorker {
     /// with the info representing their state with in the `as_block` workflow.
     pub(crate) block_wishlist: PreHashMap<BlockId, BlockInfo>,
     /// List of processed endorsements
-    checked_endorsements: LinearHashCacheSet<EndorsementId>,
+    pub(crate) checked_endorsements: LinearHashCacheSet<EndorsementId>,
     /// Cache of processed operations
     pub(crate) checked_operations: CheckedOperations,
     /// List of processed headers
@@ -163,6 +171,10 @@ pub struct ProtocolWorker {
     pub(crate) storage: Storage,
     /// Operations to announce at the next interval.
     operations_to_announce: Vec<OperationId>,
+    /// Verifies the signatures of the data received from nodes in batches
+    pub(crate) sig_verifier: SigVerifier,
+    /// Behaviors of nodes to report to the network at the end of the current loop iteration.
+    node_behaviors: Vec<(NodeId, PeerBehavior)>,
 }
 
 /// channels used by the protocol worker
@@ -199,8 +211,8 @@ impl ProtocolWorker {
         }: ProtocolWorkerChannels,
         pool_controller: Box<dyn PoolController>,
         storage: Storage,
-    ) -> ProtocolWorker {
-        ProtocolWorker {
+    ) -> Result<ProtocolWorker, ProtocolError> {
+        Ok(ProtocolWorker {
             config,
             network_command_sender,
             network_event_receiver,
@@ -221,7 +233,9 @@ impl ProtocolWorker {
             operations_to_announce: Vec::with_capacity(
                 config.operation_announcement_buffer_capacity,
             ),
-        }
+            sig_verifier: SigVerifier::new(&config)?,
+            node_behaviors: Vec::new(),
+        })
     }
 
     pub(crate) async fn send_protocol_event(&self, event: ProtocolEvent) {
@@ -274,8 +288,10 @@ impl ProtocolWorker {
                     * manager commands: low freq, avoid having to wait to stop
                     * incoming commands (high frequency): process commands in priority (this is a high-level crate so we prioritize this side to avoid slowing down consensus)
                     * network events (high frequency): process incoming events
+                    * verified data (high frequency): process the data received from nodes once its signatures are verified
                     * ask for blocks (timing not important)
             */
+            let sig_verification_deadline = self.sig_verifier.get_deadline();
             tokio::select! {
                 // listen to management commands
                 cmd = self.controller_manager_rx.recv() => {
@@ -293,12 +309,24 @@ impl ProtocolWorker {
                         &mut operation_announcement_interval).await?;
                 }
 
-                // listen to network controller events
-                evt = self.network_event_receiver.wait_event() => {
+                // listen to network controller events, unless too many signatures are waiting for verification
+                evt = self.network_event_receiver.wait_event(), if !self.sig_verifier.is_full() => {
                     massa_trace!("protocol.protocol_worker.run_loop.network_event_rx", {});
                     self.on_network_event(evt?, &mut block_ask_timer, &mut operation_announcement_interval).await?;
                 }
 
+                // data whose signatures were verified
+                Some(verified) = self.sig_verifier.results_rx.recv() => {
+                    massa_trace!("protocol.protocol_worker.run_loop.sig_verifier_results_rx", {});
+                    self.on_verified_data_received(verified, &mut block_ask_timer, &mut operation_announcement_interval).await?;
+                }
+
+                // signature verification batch window
+                _ = sleep_until(sig_verification_deadline), if self.sig_verifier.can_flush() => {
+                    massa_trace!("protocol.protocol_worker.run_loop.sig_verifier_deadline", {});
+                    self.sig_verifier.flush();
+                }
+
                 // block ask timer
                 _ = &mut block_ask_timer => {
                     massa_trace!("protocol.protocol_worker.run_loop.block_ask_timer", { });
@@ -324,6 +352,7 @@ impl ProtocolWorker {
                     self.prune_asked_operations(&mut operation_prune_timer)?;
                 }
             }
+            self.flush_node_behaviors();
             massa_trace!("protocol.protocol_worker.run_loop.end", {});
         }
 
@@ -583,12 +612,34 @@ impl ProtocolWorker {
         let mut ask_block_list: HashMap<NodeId, Vec<(BlockId, AskForBlocksInfo)>> =
             Default::default();
 
+        // nodes that did not reply to our block requests in time
+        let mut timed_out_nodes: Vec<NodeId> = Vec::new();
+
         // list blocks to re-ask and from whom
         for (hash, block_info) in self.block_wishlist.iter() {
             let required_info = if block_info.header.is_none() {
                 AskForBlocksInfo::Header
             } else if block_info.operation_ids.is_none() {
-                AskForBlocksInfo::Info
+                if let Some(prefixes) = &block_info.operation_prefix_ids {
+                    // ask only for the operations we could not find in storage
+                    let stored_prefixes: OperationPrefixIds = block_info
+                        .storage
+                        .get_op_refs()
+                        .iter()
+                        .map(|id| id.prefix())
+                        .collect();
+                    AskForBlocksInfo::OperationsByPrefix(
+                        prefixes
+                            .iter()
+                            .filter(|prefix| !stored_prefixes.contains(prefix))
+                            .copied()
+                            .collect(),
+                    )
+                } else if self.config.compact_block_relay && !block_info.compact_relay_failed {
+                    AskForBlocksInfo::CompactInfo
+                } else {
+                    AskForBlocksInfo::Info
+                }
             } else {
                 let already_stored_operations = block_info.storage.get_op_refs();
                 // Unwrap safety: Check if `operation_ids` is none just above
@@ -652,6 +703,7 @@ impl ProtocolWorker {
                                 timeout_at,
                                 self.config.max_node_known_blocks_size,
                             );
+                            timed_out_nodes.push(*node_id);
                             (2u8, ask_time_opt)
                         } else {
                             // told us it has it after a timeout: good candidate again
@@ -668,6 +720,7 @@ impl ProtocolWorker {
                                 timeout_at,
                                 self.config.max_node_known_blocks_size,
                             );
+                            timed_out_nodes.push(*node_id);
                         }
                         (2u8, ask_time_opt)
                     }
@@ -679,6 +732,7 @@ impl ProtocolWorker {
                             timeout_at,
                             self.config.max_node_known_blocks_size,
                         );
+                        timed_out_nodes.push(*node_id);
                         (2u8, ask_time_opt)
                     }
                 };
@@ -697,6 +751,11 @@ impl ProtocolWorker {
             }
         }
 
+        // each timeout is noted only once, as the node is then marked as not having the block
+        for node_id in timed_out_nodes.iter() {
+            self.report_node_behavior(node_id, PeerBehavior::AskTimeout);
+        }
+
         // count active block requests per node
         let mut active_block_req_count: HashMap<NodeId, usize> = self
             .active_nodes
@@ -786,6 +845,28 @@ impl ProtocolWorker {
         Ok(())
     }
 
+    /// Report a behavior of a node, affecting the reputation score of its peer.
+    ///
+    /// Unlike `ban_node`, the node is only banned by the network once the score of its peer is too low.
+    /// The behaviors are sent to the network together at the end of the current loop iteration.
+    pub(crate) fn report_node_behavior(&mut self, node_id: &NodeId, behavior: PeerBehavior) {
+        massa_trace!("protocol.protocol_worker.report_node_behavior", { "node": node_id, "behavior": behavior });
+        self.node_behaviors.push((*node_id, behavior));
+    }
+
+    /// Send the behaviors of nodes reported since the last call to the network, in a single command.
+    fn flush_node_behaviors(&mut self) {
+        if self.node_behaviors.is_empty() {
+            return;
+        }
+        if let Err(err) = self
+            .network_command_sender
+            .report_node_behaviors(std::mem::take(&mut self.node_behaviors))
+        {
+            warn!("could not report behaviors of nodes: {}", err);
+        }
+    }
+
     /// Perform checks on a header,
     /// and if valid update the node's view of the world.
     ///
@@ -796,17 +877,21 @@ impl ProtocolWorker {
     /// Checks performed on Header:
     /// - Not genesis.
     /// - Can compute a `BlockId`.
-    /// - Valid signature.
+    /// - Valid signature, unless `signatures_checked`.
     /// - Absence of duplicate endorsements.
     ///
     /// Checks performed on endorsements:
     /// - Unique indices.
     /// - Slot matches that of the block.
     /// - Block matches that of the block.
+    ///
+    /// `signatures_checked` tells whether the signatures of the header and of its endorsements
+    /// were already verified by the `SigVerifier`.
     pub(crate) async fn note_header_from_node(
         &mut self,
         header: &WrappedHeader,
         source_node_id: &NodeId,
+        signatures_checked: bool,
     ) -> Result<Option<(BlockId, bool)>, ProtocolError> {
         massa_trace!("protocol.protocol_worker.note_header_from_node", { "node": source_node_id, "header": header });
 
@@ -851,7 +936,12 @@ impl ProtocolWorker {
         }
 
         if let Err(err) = self
-            .note_endorsements_from_node(header.content.endorsements.clone(), source_node_id, false)
+            .note_endorsements_from_node(
+                header.content.endorsements.clone(),
+                source_node_id,
+                false,
+                signatures_checked,
+            )
             .await
         {
             warn!(
@@ -862,7 +952,7 @@ impl ProtocolWorker {
         };
 
         // check header signature
-        if let Err(err) = header.verify_signature() {
+        if !signatures_checked && let Err(err) = header.verify_signature() {
             massa_trace!("protocol.protocol_worker.check_header.err_signature", { "header": header, "err": format!("{}", err)});
             return Ok(None);
         };
@@ -917,12 +1007,13 @@ impl ProtocolWorker {
     /// Does not ban if the operation is invalid.
     ///
     /// Checks performed:
-    /// - Valid signature
+    /// - Valid signature, unless `signatures_checked` tells that it was already verified by the `SigVerifier`
     pub(crate) async fn note_operations_from_node(
         &mut self,
         operations: Vec<WrappedOperation>,
         source_node_id: &NodeId,
         op_timer: &mut Pin<&mut Sleep>,
+        signatures_checked: bool,
     ) -> Result<(), ProtocolError> {
         massa_trace!("protocol.protocol_worker.note_operations_from_node", { "node": source_node_id, "operations": operations });
         let length = operations.len();
@@ -940,12 +1031,14 @@ impl ProtocolWorker {
         }
 
         // optimized signature verification
-        verify_sigs_batch(
-            &new_operations
-                .iter()
-                .map(|(op_id, op)| (*op_id.get_hash(), op.signature, op.creator_public_key))
-                .collect::<Vec<_>>(),
-        )?;
+        if !signatures_checked {
+            verify_sigs_batch(
+                &new_operations
+                    .iter()
+                    .map(|(op_id, op)| (*op_id.get_hash(), op.signature, op.creator_public_key))
+                    .collect::<Vec<_>>(),
+            )?;
+        }
 
         // add to checked operations
         self.checked_operations
@@ -1011,12 +1104,13 @@ impl ProtocolWorker {
     /// Does not ban if the endorsement is invalid
     ///
     /// Checks performed:
-    /// - Valid signature.
+    /// - Valid signature, unless `signatures_checked` tells that it was already verified by the `SigVerifier`.
     pub(crate) async fn note_endorsements_from_node(
         &mut self,
         endorsements: Vec<WrappedEndorsement>,
         source_node_id: &NodeId,
         propagate: bool,
+        signatures_checked: bool,
     ) -> Result<(), ProtocolError> {
         massa_trace!("protocol.protocol_worker.note_endorsements_from_node", { "node": source_node_id, "endorsements": endorsements});
         let length = endorsements.len();
@@ -1034,18 +1128,20 @@ impl ProtocolWorker {
 
         // Batch signature verification
         // optimized signature verification
-        verify_sigs_batch(
-            &new_endorsements
-                .iter()
-                .map(|(endorsement_id, endorsement)| {
-                    (
-                        *endorsement_id.get_hash(),
-                        endorsement.signature,
-                        endorsement.creator_public_key,
-                    )
-                })
-                .collect::<Vec<_>>(),
-        )?;
+        if !signatures_checked {
+            verify_sigs_batch(
+                &new_endorsements
+                    .iter()
+                    .map(|(endorsement_id, endorsement)| {
+                        (
+                            *endorsement_id.get_hash(),
+                            endorsement.signature,
+                            endorsement.creator_public_key,
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )?;
+        }
 
         // add to verified signature cache
         self.checked_endorsements
@@ -3,9 +3,14 @@
 //! Optimized batch signature verifier
 
 use massa_hash::Hash;
-use massa_protocol_exports::ProtocolError;
+use massa_models::{
+    block::WrappedHeader, endorsement::WrappedEndorsement, node::NodeId,
+    operation::WrappedOperation,
+};
+use massa_protocol_exports::{ProtocolConfig, ProtocolError};
 use massa_signature::{verify_signature_batch, PublicKey, Signature};
-use rayon::{prelude::ParallelIterator, slice::ParallelSlice};
+use rayon::{prelude::ParallelIterator, slice::ParallelSlice, ThreadPool, ThreadPoolBuilder};
+use tokio::{sync::mpsc, time::Instant};
 
 /// Limit for small batch optimization
 const SMALL_BATCH_LIMIT: usize = 2;
@@ -27,3 +32,217 @@ pub fn verify_sigs_batch(ops: &[(Hash, Signature, PublicKey)]) -> Result<(), Pro
         .try_for_each(verify_signature_batch)
         .map_err(|_err| ProtocolError::WrongSignature)
 }
+
+/// Data received from a node, whose signatures are verified by the `SigVerifier`
+#[derive(Debug)]
+pub(crate) enum SignedData {
+    /// a block header, along with the endorsements it contains
+    Header(WrappedHeader),
+    /// endorsements
+    Endorsements(Vec<WrappedEndorsement>),
+    /// operations
+    Operations(Vec<WrappedOperation>),
+}
+
+/// Data received from a node, once its signatures are verified
+#[derive(Debug)]
+pub(crate) struct VerifiedData {
+    /// node that sent the data
+    pub(crate) node_id: NodeId,
+    /// the data
+    pub(crate) data: SignedData,
+    /// whether all the verified signatures of the data are valid
+    pub(crate) is_valid: bool,
+}
+
+/// Signatures of some data, as verified by `verify_sigs_batch`
+pub(crate) type Signatures = Vec<(Hash, Signature, PublicKey)>;
+
+/// Collects the data received from all nodes during a short time window,
+/// then verifies their signatures together on a dedicated thread pool, off the protocol worker loop.
+/// The verified data is sent back to the protocol worker through `results_rx`.
+pub(crate) struct SigVerifier {
+    /// threads verifying the batches
+    thread_pool: ThreadPool,
+    /// data waiting to be verified with the next batch, with the signatures to verify
+    pending: Vec<(NodeId, SignedData, Signatures)>,
+    /// number of signatures in `pending`
+    pending_sig_count: usize,
+    /// instant at which the pending batch is due, if any data is pending
+    deadline: Option<Instant>,
+    /// number of batches being verified
+    running_batch_count: usize,
+    /// time window during which data is collected before being verified
+    batch_window: std::time::Duration,
+    /// a batch is verified right away once it holds this many signatures
+    max_batch_size: usize,
+    /// max number of batches being verified at once
+    max_running_batch_count: usize,
+    /// sends the verified batches back to the worker
+    results_tx: mpsc::Sender<Vec<VerifiedData>>,
+    /// receives the verified batches
+    pub(crate) results_rx: mpsc::Receiver<Vec<VerifiedData>>,
+}
+
+impl SigVerifier {
+    /// Creates the verifier and its thread pool
+    pub(crate) fn new(config: &ProtocolConfig) -> Result<Self, ProtocolError> {
+        let thread_count = std::cmp::max(1, config.sig_verification_thread_count);
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(thread_count)
+            .thread_name(|index| format!("protocol-sig-verifier-{}", index))
+            .build()
+            .map_err(|err| {
+                ProtocolError::GeneralProtocolError(format!(
+                    "could not start the signature verification threads: {}",
+                    err
+                ))
+            })?;
+        // one batch per thread: results never wait for room in the channel
+        let (results_tx, results_rx) = mpsc::channel(thread_count);
+        Ok(SigVerifier {
+            thread_pool,
+            pending: Vec::new(),
+            pending_sig_count: 0,
+            deadline: None,
+            running_batch_count: 0,
+            batch_window: config.sig_verification_batch_window.to_duration(),
+            max_batch_size: std::cmp::max(1, config.max_sig_verification_batch_size),
+            max_running_batch_count: thread_count,
+            results_tx,
+            results_rx,
+        })
+    }
+
+    /// Adds data to the pending batch, which is verified right away if it is full.
+    ///
+    /// # Arguments
+    /// * `node_id`: node that sent the data
+    /// * `data`: the data
+    /// * `signatures`: the signatures of the data that need to be verified
+    pub(crate) fn submit(&mut self, node_id: NodeId, data: SignedData, signatures: Signatures) {
+        if self.deadline.is_none() {
+            self.deadline = Some(Instant::now() + self.batch_window);
+        }
+        self.pending_sig_count = self.pending_sig_count.saturating_add(signatures.len());
+        self.pending.push((node_id, data, signatures));
+        self.flush_if_due(Instant::now());
+    }
+
+    /// Whether the pending batch is full. No data should be submitted until it is verified.
+    pub(crate) fn is_full(&self) -> bool {
+        self.pending_sig_count >= self.max_batch_size
+    }
+
+    /// Instant at which the pending batch should be verified.
+    /// Far in the future if no data is pending.
+    pub(crate) fn get_deadline(&self) -> Instant {
+        self.deadline
+            .unwrap_or_else(|| Instant::now() + std::time::Duration::from_secs(3600))
+    }
+
+    /// Whether the pending batch can start being verified
+    pub(crate) fn can_flush(&self) -> bool {
+        !self.pending.is_empty() && self.running_batch_count < self.max_running_batch_count
+    }
+
+    /// Notes that a batch sent through `results_rx` was received
+    pub(crate) fn note_batch_received(&mut self) {
+        self.running_batch_count = self.running_batch_count.saturating_sub(1);
+    }
+
+    /// Starts verifying the pending batch if it is full or its window is over,
+    /// and a thread is available.
+    pub(crate) fn flush_if_due(&mut self, now: Instant) {
+        let is_due = self.is_full() || self.deadline.map_or(false, |deadline| deadline <= now);
+        if is_due && self.can_flush() {
+            self.flush();
+        }
+    }
+
+    /// Starts verifying the pending batch on the thread pool
+    pub(crate) fn flush(&mut self) {
+        let batch = std::mem::take(&mut self.pending);
+        self.pending_sig_count = 0;
+        self.deadline = None;
+        self.running_batch_count += 1;
+        let results_tx = self.results_tx.clone();
+        self.thread_pool.spawn(move || {
+            // the worker stopping is the only reason for this to fail
+            let _ = results_tx.blocking_send(verify_batch(batch));
+        });
+    }
+}
+
+/// Verifies all the signatures of a batch together.
+/// If one of them is invalid, the signatures of each data are verified separately
+/// to find which data is invalid.
+fn verify_batch(batch: Vec<(NodeId, SignedData, Signatures)>) -> Vec<VerifiedData> {
+    let all_signatures: Signatures = batch
+        .iter()
+        .flat_map(|(_, _, signatures)| signatures.iter().copied())
+        .collect();
+    let all_valid = verify_sigs_batch(&all_signatures).is_ok();
+    batch
+        .into_iter()
+        .map(|(node_id, data, signatures)| VerifiedData {
+            node_id,
+            data,
+            is_valid: all_valid || verify_sigs_batch(&signatures).is_ok(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_protocol_exports::tests::tools::{
+        create_operation_with_expire_period, create_protocol_config,
+    };
+    use massa_signature::KeyPair;
+
+    fn get_signatures(operation: &WrappedOperation) -> Signatures {
+        vec![(
+            *operation.id.get_hash(),
+            operation.signature,
+            operation.creator_public_key,
+        )]
+    }
+
+    #[tokio::test]
+    async fn test_sig_verifier_batches() {
+        let mut config = create_protocol_config();
+        config.sig_verification_batch_window = 60_000.into();
+        config.max_sig_verification_batch_size = 2;
+        let mut verifier = SigVerifier::new(&config).unwrap();
+        let node_a = NodeId(KeyPair::generate().get_public_key());
+        let node_b = NodeId(KeyPair::generate().get_public_key());
+
+        // a valid operation waits for the end of the batch window
+        let valid_op = create_operation_with_expire_period(&KeyPair::generate(), 1);
+        let signatures = get_signatures(&valid_op);
+        verifier.submit(
+            node_a,
+            SignedData::Operations(vec![valid_op.clone()]),
+            signatures,
+        );
+        assert!(!verifier.is_full());
+        assert!(verifier.results_rx.try_recv().is_err());
+
+        // an operation with the signature of another one fills the batch, which is verified right away
+        let mut invalid_op = create_operation_with_expire_period(&KeyPair::generate(), 2);
+        invalid_op.signature = valid_op.signature;
+        let signatures = get_signatures(&invalid_op);
+        verifier.submit(node_b, SignedData::Operations(vec![invalid_op]), signatures);
+        assert!(!verifier.can_flush());
+        let results = verifier.results_rx.recv().await.unwrap();
+        verifier.note_batch_received();
+
+        // only the invalid operation is rejected
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].node_id, node_a);
+        assert!(results[0].is_valid);
+        assert_eq!(results[1].node_id, node_b);
+        assert!(!results[1].is_valid);
+    }
+}
@@ -1,6 +1,6 @@
 // Copyright (c) 2022 MASSA LABS <info@massa.net>
 
-use super::tools::protocol_test;
+use super::tools::{protocol_test, protocol_test_with_storage};
 use massa_models::prehash::PreHashSet;
 use massa_models::{block::BlockId, slot::Slot};
 use massa_network_exports::{AskForBlocksInfo, BlockInfoReply, NetworkCommand};
@@ -134,6 +134,239 @@ async fn test_full_ask_block_workflow() {
     .await;
 }
 
+#[tokio::test]
+#[serial]
+async fn test_compact_ask_block_workflow() {
+    // start
+    let mut protocol_config = *tools::PROTOCOL_CONFIG;
+    protocol_config.compact_block_relay = true;
+
+    protocol_test_with_storage(
+        &protocol_config,
+        async move |mut network_controller,
+                    mut protocol_event_receiver,
+                    mut protocol_command_sender,
+                    protocol_manager,
+                    protocol_pool_event_receiver,
+                    mut storage| {
+            let node_a = tools::create_and_connect_nodes(1, &mut network_controller)
+                .await
+                .pop()
+                .unwrap();
+            let node_b = tools::create_and_connect_nodes(1, &mut network_controller)
+                .await
+                .pop()
+                .unwrap();
+            let _node_c = tools::create_and_connect_nodes(1, &mut network_controller)
+                .await
+                .pop()
+                .unwrap();
+
+            // Create a block with two operations, the first one being already in storage.
+            let op_1 = tools::create_operation_with_expire_period(&node_a.keypair, 5);
+            let op_2 = tools::create_operation_with_expire_period(&node_a.keypair, 5);
+            let op_thread = op_1
+                .creator_address
+                .get_thread(protocol_config.thread_count);
+            let block = tools::create_block_with_operations(
+                &node_a.keypair,
+                Slot::new(1, op_thread),
+                vec![op_1.clone(), op_2.clone()],
+            );
+            storage.store_operations(vec![op_1.clone()]);
+            // end set up
+
+            // Send header via node_a
+            network_controller
+                .send_header(node_a.id, block.content.header.clone())
+                .await;
+
+            // Send wishlist
+            protocol_command_sender
+                .send_wishlist_delta(
+                    vec![(block.id, Some(block.content.header.clone()))]
+                        .into_iter()
+                        .collect(),
+                    PreHashSet::<BlockId>::default(),
+                )
+                .await
+                .unwrap();
+
+            // assert it was asked to node A, then B
+            assert_hash_asked_to_node(block.id, node_a.id, &mut network_controller).await;
+            let mut ask_list = asked_list(&mut network_controller).await;
+            let (hash, asked) = ask_list.get_mut(&node_b.id).unwrap().pop().unwrap();
+            assert_eq!(block.id, hash);
+            assert!(matches!(asked, AskForBlocksInfo::CompactInfo));
+
+            // Node B replied with the operation prefixes.
+            network_controller
+                .send_block_info(
+                    node_b.id,
+                    vec![(
+                        block.id,
+                        BlockInfoReply::CompactInfo(vec![op_1.id.prefix(), op_2.id.prefix()]),
+                    )],
+                )
+                .await;
+
+            // Make sure protocol only asked for the operation missing from storage.
+            let mut ask_list = asked_list(&mut network_controller).await;
+            let (hash, asked) = ask_list.get_mut(&node_b.id).unwrap().pop().unwrap();
+            assert_eq!(block.id, hash);
+            if let AskForBlocksInfo::OperationsByPrefix(prefixes) = asked {
+                assert_eq!(prefixes, vec![op_2.id.prefix()]);
+            } else {
+                panic!("Unexpected ask for blocks.");
+            }
+
+            // Node B replied with the missing operation.
+            network_controller
+                .send_block_info(
+                    node_b.id,
+                    vec![(block.id, BlockInfoReply::Operations(vec![op_2]))],
+                )
+                .await;
+
+            // Protocol sends expected block to consensus.
+            loop {
+                match protocol_event_receiver.wait_event().await.unwrap() {
+                    ProtocolEvent::ReceivedBlock {
+                        slot,
+                        block_id,
+                        storage,
+                    } => {
+                        assert_eq!(slot, block.content.header.content.slot);
+                        assert_eq!(block_id, block.id);
+                        let received_block = storage.read_blocks().get(&block_id).cloned().unwrap();
+                        assert_eq!(received_block.content.operations, block.content.operations);
+                        break;
+                    }
+                    _evt => continue,
+                };
+            }
+            (
+                network_controller,
+                protocol_event_receiver,
+                protocol_command_sender,
+                protocol_manager,
+                protocol_pool_event_receiver,
+            )
+        },
+    )
+    .await;
+}
+
+/// Compact block relay is enabled, but the node asked for the block is a legacy node:
+/// the network asks it for the block info instead, and the block is retrieved from it.
+#[tokio::test]
+#[serial]
+async fn test_compact_ask_block_legacy_node() {
+    // start
+    let mut protocol_config = *tools::PROTOCOL_CONFIG;
+    protocol_config.compact_block_relay = true;
+
+    protocol_test_with_storage(
+        &protocol_config,
+        async move |mut network_controller,
+                    mut protocol_event_receiver,
+                    mut protocol_command_sender,
+                    protocol_manager,
+                    protocol_pool_event_receiver,
+                    mut storage| {
+            let node_a = tools::create_and_connect_nodes(1, &mut network_controller)
+                .await
+                .pop()
+                .unwrap();
+
+            // Create a block with two operations, the first one being already in storage.
+            let op_1 = tools::create_operation_with_expire_period(&node_a.keypair, 5);
+            let op_2 = tools::create_operation_with_expire_period(&node_a.keypair, 5);
+            let op_thread = op_1
+                .creator_address
+                .get_thread(protocol_config.thread_count);
+            let block = tools::create_block_with_operations(
+                &node_a.keypair,
+                Slot::new(1, op_thread),
+                vec![op_1.clone(), op_2.clone()],
+            );
+            storage.store_operations(vec![op_1.clone()]);
+            // end set up
+
+            // Send header via node_a
+            network_controller
+                .send_header(node_a.id, block.content.header.clone())
+                .await;
+
+            // Send wishlist
+            protocol_command_sender
+                .send_wishlist_delta(
+                    vec![(block.id, Some(block.content.header.clone()))]
+                        .into_iter()
+                        .collect(),
+                    PreHashSet::<BlockId>::default(),
+                )
+                .await
+                .unwrap();
+
+            // the block is asked compactly to node A
+            let mut ask_list = asked_list(&mut network_controller).await;
+            let (hash, asked) = ask_list.get_mut(&node_a.id).unwrap().pop().unwrap();
+            assert_eq!(block.id, hash);
+            assert!(matches!(asked, AskForBlocksInfo::CompactInfo));
+
+            // Node A does not know compact block relay: it was asked for the info and replies with it.
+            network_controller
+                .send_block_info(
+                    node_a.id,
+                    vec![(block.id, BlockInfoReply::Info(vec![op_1.id, op_2.id]))],
+                )
+                .await;
+
+            // Make sure protocol only asked for the operation missing from storage.
+            let mut ask_list = asked_list(&mut network_controller).await;
+            let (hash, asked) = ask_list.get_mut(&node_a.id).unwrap().pop().unwrap();
+            assert_eq!(block.id, hash);
+            if let AskForBlocksInfo::Operations(ops) = asked {
+                assert_eq!(ops, vec![op_2.id]);
+            } else {
+                panic!("Unexpected ask for blocks.");
+            }
+
+            // Node A replied with the missing operation.
+            network_controller
+                .send_block_info(
+                    node_a.id,
+                    vec![(block.id, BlockInfoReply::Operations(vec![op_2]))],
+                )
+                .await;
+
+            // Protocol sends expected block to consensus.
+            loop {
+                match protocol_event_receiver.wait_event().await.unwrap() {
+                    ProtocolEvent::ReceivedBlock {
+                        block_id, storage, ..
+                    } => {
+                        assert_eq!(block_id, block.id);
+                        let received_block = storage.read_blocks().get(&block_id).cloned().unwrap();
+                        assert_eq!(received_block.content.operations, block.content.operations);
+                        break;
+                    }
+                    _evt => continue,
+                };
+            }
+            (
+                network_controller,
+                protocol_event_receiver,
+                protocol_command_sender,
+                protocol_manager,
+                protocol_pool_event_receiver,
+            )
+        },
+    )
+    .await;
+}
+
 #[tokio::test]
 #[serial]
 async fn test_empty_block() {
@@ -6,7 +6,7 @@ use massa_models::operation::OperationId;
 use massa_models::prehash::PreHashSet;
 use massa_models::wrapped::Id;
 use massa_models::{block::BlockId, slot::Slot};
-use massa_network_exports::{BlockInfoReply, NetworkCommand};
+use massa_network_exports::{BlockInfoReply, NetworkCommand, PeerBehavior};
 use massa_pool_exports::test_exports::MockPoolControllerMessage;
 use massa_protocol_exports::tests::tools;
 use massa_protocol_exports::ProtocolEvent;
@@ -17,7 +17,7 @@ use std::time::Duration;
 
 #[tokio::test]
 #[serial]
-async fn test_protocol_bans_node_sending_block_header_with_invalid_signature() {
+async fn test_protocol_reports_node_sending_block_header_with_invalid_signature() {
     let protocol_config = &tools::PROTOCOL_CONFIG;
     protocol_test(
         protocol_config,
@@ -42,8 +42,13 @@ async fn test_protocol_bans_node_sending_block_header_with_invalid_signature() {
                 .send_header(creator_node.id, block.content.header.clone())
                 .await;
 
-            // The node is banned.
-            tools::assert_banned_nodes(vec![creator_node.id], &mut network_controller).await;
+            // The node is reported.
+            tools::assert_reported_behaviors(
+                vec![creator_node.id],
+                PeerBehavior::InvalidData,
+                &mut network_controller,
+            )
+            .await;
 
             // Check protocol does not send block to consensus.
             match tools::wait_protocol_event(&mut protocol_event_receiver, 1000.into(), |evt| {
@@ -72,7 +77,7 @@ async fn test_protocol_bans_node_sending_block_header_with_invalid_signature() {
 
 #[tokio::test]
 #[serial]
-async fn test_protocol_bans_node_sending_operation_with_invalid_signature() {
+async fn test_protocol_reports_node_sending_operation_with_invalid_signature() {
     let protocol_config = &tools::PROTOCOL_CONFIG;
     protocol_test(
         protocol_config,
@@ -98,8 +103,13 @@ async fn test_protocol_bans_node_sending_operation_with_invalid_signature() {
                 .send_operations(creator_node.id, vec![operation])
                 .await;
 
-            // The node is banned.
-            tools::assert_banned_nodes(vec![creator_node.id], &mut network_controller).await;
+            // The node is reported.
+            tools::assert_reported_behaviors(
+                vec![creator_node.id],
+                PeerBehavior::InvalidData,
+                &mut network_controller,
+            )
+            .await;
 
             // Check protocol does not send operation to pool.
             pool_event_receiver.wait_command(1000.into(), |evt| match evt {
@@ -120,7 +130,7 @@ async fn test_protocol_bans_node_sending_operation_with_invalid_signature() {
 
 #[tokio::test]
 #[serial]
-async fn test_protocol_bans_node_sending_header_with_invalid_signature() {
+async fn test_protocol_reports_node_sending_header_with_invalid_signature() {
     let protocol_config = &tools::PROTOCOL_CONFIG;
     protocol_test(
         protocol_config,
@@ -180,8 +190,14 @@ async fn test_protocol_bans_node_sending_header_with_invalid_signature() {
                 )
                 .await;
 
-            // The node is banned.
-            tools::assert_banned_nodes(vec![to_ban_node.id], &mut network_controller).await;
+            // The node is reported, and banned by the network.
+            tools::assert_reported_behaviors(
+                vec![to_ban_node.id],
+                PeerBehavior::InvalidData,
+                &mut network_controller,
+            )
+            .await;
+            network_controller.close_connection(to_ban_node.id).await;
 
             // Create another node.
             let not_banned = tools::create_and_connect_nodes(1, &mut network_controller)
@@ -269,7 +285,7 @@ async fn test_protocol_does_not_asks_for_block_from_banned_node_who_propagated_h
 
             assert_eq!(expected_hash, received_hash);
 
-            // 4. Get the node banned.
+            // 4. Get the node reported, and banned by the network.
             // New keypair to avoid getting same block id
             let keypair = KeyPair::generate();
             let mut block = tools::create_block(&keypair);
@@ -277,7 +293,13 @@ async fn test_protocol_does_not_asks_for_block_from_banned_node_who_propagated_h
             network_controller
                 .send_header(creator_node.id, block.content.header.clone())
                 .await;
-            tools::assert_banned_nodes(vec![creator_node.id], &mut network_controller).await;
+            tools::assert_reported_behaviors(
+                vec![creator_node.id],
+                PeerBehavior::InvalidData,
+                &mut network_controller,
+            )
+            .await;
+            network_controller.close_connection(creator_node.id).await;
 
             // 5. Ask for block.
             protocol_command_sender
@@ -340,13 +362,19 @@ async fn test_protocol_does_not_send_blocks_when_asked_for_by_banned_node() {
 
             let expected_hash = block.id;
 
-            // 3. Get one node banned.
+            // 3. Get one node reported, and banned by the network.
             let mut bad_block = tools::create_block(&nodes[1].keypair);
             bad_block.content.header.id = BlockId::new(Hash::compute_from("invalid".as_bytes()));
             network_controller
                 .send_header(nodes[1].id, bad_block.content.header.clone())
                 .await;
-            tools::assert_banned_nodes(vec![nodes[1].id], &mut network_controller).await;
+            tools::assert_reported_behaviors(
+                vec![nodes[1].id],
+                PeerBehavior::InvalidData,
+                &mut network_controller,
+            )
+            .await;
+            network_controller.close_connection(nodes[1].id).await;
 
             // 4. Simulate two nodes asking for a block.
             for node in nodes.iter().take(2) {
@@ -521,13 +549,18 @@ async fn test_protocol_removes_banned_node_on_disconnection() {
 
             let creator_node = nodes.pop().expect("Failed to get node info.");
 
-            // Get the node banned.
+            // Get the node reported.
             let mut block = tools::create_block(&creator_node.keypair);
             block.content.header.id = BlockId::new(Hash::compute_from("invalid".as_bytes()));
             network_controller
                 .send_header(creator_node.id, block.content.header)
                 .await;
-            tools::assert_banned_nodes(vec![creator_node.id], &mut network_controller).await;
+            tools::assert_reported_behaviors(
+                vec![creator_node.id],
+                PeerBehavior::InvalidData,
+                &mut network_controller,
+            )
+            .await;
 
             // Close the connection.
             network_controller.close_connection(creator_node.id).await;
@@ -17,6 +17,7 @@ use massa_models::{
     operation::{OperationPrefixIds, WrappedOperation},
     prehash::CapacityAllocator,
 };
+use massa_network_exports::PeerBehavior;
 use massa_protocol_exports::ProtocolError;
 use massa_time::TimeError;
 use std::pin::Pin;
@@ -134,7 +135,7 @@ impl ProtocolWorker {
         }
     }
 
-    /// On full operations are received from the network,
+    /// On full operations are received from the network, once their signatures are verified,
     /// - Update the cache `received_operations` ids and each
     ///   `node_info.known_operations`
     /// - Notify the operations to he local node, to be propagated
@@ -145,11 +146,11 @@ impl ProtocolWorker {
         op_timer: &mut Pin<&mut Sleep>,
     ) {
         if let Err(err) = self
-            .note_operations_from_node(operations, &node_id, op_timer)
+            .note_operations_from_node(operations, &node_id, op_timer, true)
             .await
         {
             warn!("node {} sent us critically incorrect operation, which may be an attack attempt by the remote node or a loss of sync between us and the remote node. Err = {}", node_id, err);
-            let _ = self.ban_node(&node_id).await;
+            self.report_node_behavior(&node_id, PeerBehavior::InvalidData);
         }
     }
 
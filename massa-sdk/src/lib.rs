@@ -5,20 +5,29 @@
 #![warn(unused_crate_dependencies)]
 
 use jsonrpc_core_client::transports::http;
-use jsonrpc_core_client::{RpcChannel, RpcError, RpcResult, TypedClient};
+use jsonrpc_core_client::{RpcChannel, RpcError, TypedClient};
+
+pub use jsonrpc_core_client::RpcResult;
 use massa_models::api::{
     AddressInfo, BlockInfo, BlockSummary, DatastoreEntryInput, DatastoreEntryOutput,
-    EndorsementInfo, EventFilter, NodeStatus, OperationInfo, OperationInput,
+    EndorsementInfo, EventFilter, GasEstimationInput, NodeStatus, OperationInfo, OperationInput,
+    PageRequest, PagedResponse, PoolOperationFilter, PoolOperationStatus,
     ReadOnlyBytecodeExecution, ReadOnlyCall, TimeInterval,
 };
 use massa_models::clique::Clique;
 use massa_models::composite::PubkeySig;
-use massa_models::execution::ExecuteReadOnlyResponse;
+use massa_models::execution::{
+    ExecuteReadOnlyResponse, ExecutionTrace, GasEstimation, OperationSimulation,
+};
+use massa_models::ip_range::{IpAccessLists, IpRange};
+use massa_models::ledger_models::LedgerSubEntry;
+use massa_models::ledger_proof::LedgerEntryProof;
 use massa_models::node::NodeId;
 use massa_models::output_event::SCOutputEvent;
 use massa_models::prehash::{PreHashMap, PreHashSet};
 use massa_models::{
     address::Address, block::BlockId, endorsement::EndorsementId, operation::OperationId,
+    slot::Slot,
 };
 use massa_signature::KeyPair;
 use serde::de::DeserializeOwned;
@@ -155,6 +164,55 @@ impl RpcClient {
             .await
     }
 
+    /// get the IP ranges allowed and denied to bootstrap from the node
+    pub async fn get_bootstrap_access_lists(&self) -> RpcResult<IpAccessLists> {
+        self.call_method("get_bootstrap_access_lists", "IpAccessLists", ())
+            .await
+    }
+
+    /// allow IP ranges to bootstrap from the node
+    pub async fn node_add_to_bootstrap_allow_list(&self, ranges: Vec<IpRange>) -> RpcResult<()> {
+        self.call_method("node_add_to_bootstrap_allow_list", "()", vec![ranges])
+            .await
+    }
+
+    /// remove IP ranges from the bootstrap allow list
+    pub async fn node_remove_from_bootstrap_allow_list(
+        &self,
+        ranges: Vec<IpRange>,
+    ) -> RpcResult<()> {
+        self.call_method("node_remove_from_bootstrap_allow_list", "()", vec![ranges])
+            .await
+    }
+
+    /// deny IP ranges to bootstrap from the node
+    pub async fn node_add_to_bootstrap_deny_list(&self, ranges: Vec<IpRange>) -> RpcResult<()> {
+        self.call_method("node_add_to_bootstrap_deny_list", "()", vec![ranges])
+            .await
+    }
+
+    /// remove IP ranges from the bootstrap deny list
+    pub async fn node_remove_from_bootstrap_deny_list(
+        &self,
+        ranges: Vec<IpRange>,
+    ) -> RpcResult<()> {
+        self.call_method("node_remove_from_bootstrap_deny_list", "()", vec![ranges])
+            .await
+    }
+
+    /// get the call tree recorded during the latest execution of each operation
+    pub async fn get_execution_traces(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> RpcResult<Vec<Option<ExecutionTrace>>> {
+        self.call_method(
+            "get_execution_traces",
+            "Vec<Option<ExecutionTrace>>",
+            vec![operation_ids],
+        )
+        .await
+    }
+
     ////////////////
     // public-api //
     ////////////////
@@ -179,23 +237,31 @@ impl RpcClient {
     }
 
     /// Returns operations information associated to a given list of operations' IDs.
+    /// Without a page request, the first page of maximum size is returned.
     pub async fn get_operations(
         &self,
         operation_ids: Vec<OperationId>,
-    ) -> RpcResult<Vec<OperationInfo>> {
-        self.call_method("get_operations", "Vec<OperationInfo>", vec![operation_ids])
-            .await
+        page: Option<PageRequest>,
+    ) -> RpcResult<PagedResponse<OperationInfo>> {
+        self.call_method(
+            "get_operations",
+            "PagedResponse<OperationInfo>",
+            (operation_ids, page),
+        )
+        .await
     }
 
     /// get info on endorsements by ids
+    /// Without a page request, the first page of maximum size is returned.
     pub async fn get_endorsements(
         &self,
         endorsement_ids: Vec<EndorsementId>,
-    ) -> RpcResult<Vec<EndorsementInfo>> {
+        page: Option<PageRequest>,
+    ) -> RpcResult<PagedResponse<EndorsementInfo>> {
         self.call_method(
             "get_endorsements",
-            "Vec<EndorsementInfo>",
-            vec![endorsement_ids],
+            "PagedResponse<EndorsementInfo>",
+            (endorsement_ids, page),
         )
         .await
     }
@@ -207,14 +273,44 @@ impl RpcClient {
     }
 
     /// Get events emitted by smart contracts with various filters
+    /// Pages are requested with the cursor returned by the previous page.
     pub async fn get_filtered_sc_output_event(
         &self,
         filter: EventFilter,
-    ) -> RpcResult<Vec<SCOutputEvent>> {
+        page: Option<PageRequest>,
+    ) -> RpcResult<PagedResponse<SCOutputEvent>> {
         self.call_method(
             "get_filtered_sc_output_event",
-            "Vec<SCOutputEvent>",
-            vec![filter],
+            "PagedResponse<SCOutputEvent>",
+            (filter, page),
+        )
+        .await
+    }
+
+    /// Get the status of operations in the pool, or why they are not in it
+    pub async fn get_pool_operation_status(
+        &self,
+        operation_ids: Vec<OperationId>,
+    ) -> RpcResult<Vec<PoolOperationStatus>> {
+        self.call_method(
+            "get_pool_operation_status",
+            "Vec<PoolOperationStatus>",
+            vec![operation_ids],
+        )
+        .await
+    }
+
+    /// List the operations of the pool, optionally filtered by creator address and thread
+    /// Without a page request, the first page of maximum size is returned.
+    pub async fn list_pool_operations(
+        &self,
+        filter: PoolOperationFilter,
+        page: Option<PageRequest>,
+    ) -> RpcResult<PagedResponse<OperationId>> {
+        self.call_method(
+            "list_pool_operations",
+            "PagedResponse<OperationId>",
+            (filter, page),
         )
         .await
     }
@@ -230,24 +326,49 @@ impl RpcClient {
     }
 
     /// Get info by addresses
-    pub async fn get_addresses(&self, addresses: Vec<Address>) -> RpcResult<Vec<AddressInfo>> {
-        self.call_method("get_addresses", "Vec<AddressInfo>", vec![addresses])
-            .await
+    /// Without a page request, the first page of maximum size is returned.
+    /// With a past final slot, balances, roll counts and datastore keys are the ones at the output of that slot.
+    pub async fn get_addresses(
+        &self,
+        addresses: Vec<Address>,
+        page: Option<PageRequest>,
+        at_slot: Option<Slot>,
+    ) -> RpcResult<PagedResponse<AddressInfo>> {
+        self.call_method(
+            "get_addresses",
+            "PagedResponse<AddressInfo>",
+            (addresses, page, at_slot),
+        )
+        .await
     }
 
     /// Get datastore entries
+    /// Without a page request, the first page of maximum size is returned.
+    /// With a past final slot, values are the ones at the output of that slot.
     pub async fn get_datastore_entries(
         &self,
         input: Vec<DatastoreEntryInput>,
-    ) -> RpcResult<Vec<DatastoreEntryOutput>> {
+        page: Option<PageRequest>,
+        at_slot: Option<Slot>,
+    ) -> RpcResult<PagedResponse<DatastoreEntryOutput>> {
         self.call_method(
             "get_datastore_entries",
-            "Vec<DatastoreEntryOutput>",
-            vec![input],
+            "PagedResponse<DatastoreEntryOutput>",
+            (input, page, at_slot),
         )
         .await
     }
 
+    /// Get the final value of a ledger sub-entry with its proof against the final ledger hash
+    pub async fn get_ledger_proof(
+        &self,
+        address: Address,
+        sub_entry: LedgerSubEntry,
+    ) -> RpcResult<LedgerEntryProof> {
+        self.call_method("get_ledger_proof", "LedgerEntryProof", (address, sub_entry))
+            .await
+    }
+
     // User (interaction with the node)
 
     /// Adds operations to pool. Returns operations that were ok and sent to pool.
@@ -259,6 +380,19 @@ impl RpcClient {
             .await
     }
 
+    /// simulate operations without sending them
+    pub async fn simulate_operations(
+        &self,
+        operations: Vec<OperationInput>,
+    ) -> RpcResult<Vec<OperationSimulation>> {
+        self.call_method(
+            "simulate_operations",
+            "Vec<OperationSimulation>",
+            vec![operations],
+        )
+        .await
+    }
+
     /// execute read only bytecode
     pub async fn execute_read_only_bytecode(
         &self,
@@ -290,4 +424,16 @@ impl RpcClient {
         .pop()
         .ok_or_else(|| RpcError::Client("missing return value on execute_read_only_call".into()))
     }
+
+    /// estimate the gas spent by an unsigned `ExecuteSC` or `CallSC` operation
+    pub async fn estimate_gas(&self, input: GasEstimationInput) -> RpcResult<GasEstimation> {
+        self.call_method::<Vec<Vec<GasEstimationInput>>, Vec<GasEstimation>>(
+            "estimate_gas",
+            "Vec<GasEstimation>",
+            vec![vec![input]],
+        )
+        .await?
+        .pop()
+        .ok_or_else(|| RpcError::Client("missing return value on estimate_gas".into()))
+    }
 }
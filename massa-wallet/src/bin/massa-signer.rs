@@ -0,0 +1,44 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Signer process holding the staking keys of a node configured with a `remote_signer`.
+//!
+//! Usage: `massa-signer <endpoint> <wallet path> <signing record path>`,
+//! with the password of the wallet in the `MASSA_SIGNER_PASSWORD` environment variable
+//! and the secret shared with the node in the `MASSA_SIGNER_SECRET` environment variable.
+//! The endpoint is either a socket address or the path of a Unix socket prefixed with `unix:`.
+//! The messages exchanged with the node are not encrypted: prefer a Unix socket or a loopback address.
+
+use massa_models::config::{GENESIS_TIMESTAMP, T0};
+use massa_wallet::{SignerEndpoint, SignerService, Wallet, WalletError};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+fn main() -> Result<(), WalletError> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 4 {
+        eprintln!("usage: massa-signer <endpoint> <wallet path> <signing record path>");
+        std::process::exit(1);
+    }
+    let endpoint = SignerEndpoint::from_str(&args[1])?;
+    let password = std::env::var("MASSA_SIGNER_PASSWORD").map_err(|_| {
+        WalletError::RemoteSignerError("MASSA_SIGNER_PASSWORD is not set".to_string())
+    })?;
+    let secret = std::env::var("MASSA_SIGNER_SECRET").map_err(|_| {
+        WalletError::RemoteSignerError("MASSA_SIGNER_SECRET is not set".to_string())
+    })?;
+    let wallet = Wallet::new(PathBuf::from(&args[2]), password)?;
+    let service = Arc::new(SignerService::new(
+        wallet.keys,
+        secret,
+        PathBuf::from(&args[3]),
+        *GENESIS_TIMESTAMP,
+        T0,
+    )?);
+    println!(
+        "signing on {} for addresses {:?}",
+        endpoint,
+        service.get_addresses()
+    );
+    service.run(&endpoint)
+}
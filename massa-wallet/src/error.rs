@@ -22,4 +22,8 @@ pub enum WalletError {
     MissingKeyError(Address),
     /// `MassaCipher` error: {0}
     MassaCipherError(#[from] massa_cipher::CipherError),
+    /// `MassaSignature` error: {0}
+    MassaSignatureError(#[from] massa_signature::MassaSignatureError),
+    /// Remote signer error: {0}
+    RemoteSignerError(String),
 }
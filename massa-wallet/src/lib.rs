@@ -6,9 +6,12 @@
 #![feature(map_try_insert)]
 
 pub use error::WalletError;
+pub use remote_signer::{
+    RemoteSigner, SignerEndpoint, SignerRequest, SignerResponse, SignerService,
+};
+pub use signer::{LocalSigner, Signer, SigningContent};
 
 use massa_cipher::{decrypt, encrypt};
-use massa_hash::Hash;
 use massa_models::address::Address;
 use massa_models::composite::PubkeySig;
 use massa_models::operation::{Operation, OperationSerializer, WrappedOperation};
@@ -19,6 +22,8 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 mod error;
+mod remote_signer;
+mod signer;
 
 /// Contains the keypairs created in the wallet.
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -59,18 +64,12 @@ impl Wallet {
     /// returns none if the address isn't in the wallet or if an error occurred during the signature
     /// else returns the public key that signed the message and the signature
     pub fn sign_message(&self, address: &Address, msg: Vec<u8>) -> Option<PubkeySig> {
-        if let Some(key) = self.keys.get(address) {
-            if let Ok(signature) = key.sign(&Hash::compute_from(&msg)) {
-                Some(PubkeySig {
-                    public_key: key.get_public_key(),
-                    signature,
-                })
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+        let public_key = self.get_public_key(address)?;
+        let signature = self.sign(address, &SigningContent::Message(msg)).ok()?;
+        Some(PubkeySig {
+            public_key,
+            signature,
+        })
     }
 
     /// Adds a list of keypairs to the wallet, returns their addresses.
@@ -0,0 +1,787 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Remote signer: the staking keys stay in a separate signer process,
+//! reached over TCP or a Unix socket.
+//!
+//! The node and the signer process exchange JSON messages, one per line:
+//! the node sends a `SignerRequest` and the signer process answers with a `SignerResponse`.
+//! The signer process is run by the `massa-signer` binary of this crate.
+//!
+//! Both share a secret: on each connection, the signer process sends a random challenge
+//! and only answers the node once it proved that it knows the secret.
+//! The secret itself never goes through the connection, but the messages are not encrypted:
+//! prefer a Unix socket or a loopback address, and otherwise only a private network.
+
+use crate::signer::{Signer, SigningContent};
+use crate::WalletError;
+use massa_hash::Hash;
+use massa_models::{
+    address::Address,
+    block::BlockHeaderDeserializer,
+    config::{ENDORSEMENT_COUNT, THREAD_COUNT},
+    endorsement::EndorsementDeserializer,
+    prehash::PreHashMap,
+    slot::Slot,
+    timeslots::get_latest_block_slot_at_timestamp,
+};
+use massa_serialization::{DeserializeError, Deserializer};
+use massa_signature::{KeyPair, PublicKey, Signature};
+use massa_time::MassaTime;
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fmt, str::FromStr};
+
+/// Endpoint of a signer process: a socket address like `127.0.0.1:33040`,
+/// or a Unix socket path prefixed with `unix:`, like `unix:/run/massa/signer.sock`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum SignerEndpoint {
+    /// TCP socket address
+    Tcp(SocketAddr),
+    /// Unix socket path
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl fmt::Display for SignerEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerEndpoint::Tcp(addr) => write!(f, "{}", addr),
+            #[cfg(unix)]
+            SignerEndpoint::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for SignerEndpoint {
+    type Err = WalletError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        #[cfg(unix)]
+        if let Some(path) = s.strip_prefix("unix:") {
+            return Ok(SignerEndpoint::Unix(PathBuf::from(path)));
+        }
+        SocketAddr::from_str(s)
+            .map(SignerEndpoint::Tcp)
+            .map_err(|_| WalletError::RemoteSignerError(format!("invalid signer endpoint: {}", s)))
+    }
+}
+
+impl TryFrom<String> for SignerEndpoint {
+    type Error = WalletError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        SignerEndpoint::from_str(&s)
+    }
+}
+
+impl From<SignerEndpoint> for String {
+    fn from(endpoint: SignerEndpoint) -> Self {
+        endpoint.to_string()
+    }
+}
+
+/// Connection to a signer process
+trait SignerStream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> SignerStream for T {}
+
+impl SignerEndpoint {
+    fn connect(&self, timeout: Duration) -> std::io::Result<Box<dyn SignerStream>> {
+        match self {
+            SignerEndpoint::Tcp(addr) => {
+                let stream = TcpStream::connect_timeout(addr, timeout)?;
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                Ok(Box::new(stream))
+            }
+            #[cfg(unix)]
+            SignerEndpoint::Unix(path) => {
+                let stream = UnixStream::connect(path)?;
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
+
+/// Number of periods after the current one at which the signer process still accepts to sign,
+/// tolerating a clock drift between the node and the signer process
+const MAX_SIGNING_PERIODS_AHEAD: u64 = 2;
+
+/// Request sent by the node to the signer process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SignerRequest {
+    /// answer to the challenge of the signer process, first request of each connection
+    Authenticate {
+        /// hash of the shared secret followed by the challenge
+        proof: Hash,
+    },
+    /// get the public keys the signer process signs for
+    GetPublicKeys,
+    /// sign a content
+    Sign {
+        /// address signing
+        address: Address,
+        /// content to sign
+        content: SigningContent,
+    },
+}
+
+/// Response of the signer process to a `SignerRequest`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SignerResponse {
+    /// random challenge sent by the signer process when the node connects
+    Challenge([u8; 32]),
+    /// the node proved that it knows the shared secret
+    Authenticated,
+    /// public keys the signer process signs for
+    PublicKeys(Vec<PublicKey>),
+    /// signature of the requested content
+    Signature(Signature),
+    /// the signer process refused to sign, with the reason
+    Refused(String),
+}
+
+fn write_message<T: Serialize>(writer: &mut impl Write, message: &T) -> Result<(), WalletError> {
+    serde_json::to_writer(&mut *writer, message)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a message, returns `None` if the connection was closed
+fn read_message<T: DeserializeOwned>(reader: &mut impl BufRead) -> Result<Option<T>, WalletError> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+/// Proof that the node knows `secret`, answering `challenge`
+fn compute_proof(secret: &str, challenge: &[u8; 32]) -> Hash {
+    Hash::compute_from(&[secret.as_bytes(), challenge].concat())
+}
+
+/// Signer signing through a signer process
+pub struct RemoteSigner {
+    /// endpoint of the signer process
+    endpoint: SignerEndpoint,
+    /// secret shared with the signer process
+    secret: String,
+    /// timeout of the connection and of each read and write
+    timeout: Duration,
+    /// public keys the signer process signs for, by address
+    public_keys: PreHashMap<Address, PublicKey>,
+    /// connection to the signer process, opened again on the next request if it failed
+    connection: Mutex<Option<BufReader<Box<dyn SignerStream>>>>,
+}
+
+impl RemoteSigner {
+    /// Connects to the signer process at `endpoint` sharing `secret`, and gets the public keys it signs for
+    pub fn connect(
+        endpoint: SignerEndpoint,
+        secret: String,
+        timeout: Duration,
+    ) -> Result<Self, WalletError> {
+        let signer = RemoteSigner {
+            endpoint,
+            secret,
+            timeout,
+            public_keys: PreHashMap::default(),
+            connection: Mutex::new(None),
+        };
+        let public_keys = match signer.request(&SignerRequest::GetPublicKeys)? {
+            SignerResponse::PublicKeys(public_keys) => public_keys,
+            response => return Err(unexpected_response(response)),
+        };
+        Ok(RemoteSigner {
+            public_keys: public_keys
+                .into_iter()
+                .map(|public_key| (Address::from_public_key(&public_key), public_key))
+                .collect(),
+            ..signer
+        })
+    }
+
+    /// Addresses the signer process signs for
+    pub fn get_addresses(&self) -> Vec<Address> {
+        self.public_keys.keys().copied().collect()
+    }
+
+    /// Sends a request to the signer process and reads its response.
+    /// If an already open connection fails, the request is sent again on a new connection.
+    fn request(&self, request: &SignerRequest) -> Result<SignerResponse, WalletError> {
+        let mut connection = self.connection.lock();
+        if connection.is_some() {
+            match self.try_request(&mut connection, request) {
+                Ok(response) => return Ok(response),
+                Err(_) => *connection = None,
+            }
+        }
+        let result = self.try_request(&mut connection, request);
+        if result.is_err() {
+            *connection = None;
+        }
+        result
+    }
+
+    fn try_request(
+        &self,
+        connection: &mut Option<BufReader<Box<dyn SignerStream>>>,
+        request: &SignerRequest,
+    ) -> Result<SignerResponse, WalletError> {
+        let stream = match connection {
+            Some(stream) => stream,
+            None => {
+                let mut stream = BufReader::new(self.endpoint.connect(self.timeout)?);
+                self.authenticate(&mut stream)?;
+                connection.insert(stream)
+            }
+        };
+        write_message(stream.get_mut(), request)?;
+        read_response(stream)
+    }
+
+    /// Answers the challenge sent by the signer process on a new connection
+    fn authenticate(
+        &self,
+        stream: &mut BufReader<Box<dyn SignerStream>>,
+    ) -> Result<(), WalletError> {
+        let challenge = match read_response(stream)? {
+            SignerResponse::Challenge(challenge) => challenge,
+            response => return Err(unexpected_response(response)),
+        };
+        let request = SignerRequest::Authenticate {
+            proof: compute_proof(&self.secret, &challenge),
+        };
+        write_message(stream.get_mut(), &request)?;
+        match read_response(stream)? {
+            SignerResponse::Authenticated => Ok(()),
+            response => Err(unexpected_response(response)),
+        }
+    }
+}
+
+/// Reads a response of the signer process, failing if the connection was closed
+fn read_response(stream: &mut impl BufRead) -> Result<SignerResponse, WalletError> {
+    read_message(stream)?.ok_or_else(|| {
+        WalletError::RemoteSignerError("connection closed by the signer process".to_string())
+    })
+}
+
+fn unexpected_response(response: SignerResponse) -> WalletError {
+    WalletError::RemoteSignerError(format!(
+        "unexpected response from the signer process: {:?}",
+        response
+    ))
+}
+
+impl Signer for RemoteSigner {
+    fn get_public_key(&self, address: &Address) -> Option<PublicKey> {
+        self.public_keys.get(address).copied()
+    }
+
+    fn sign(&self, address: &Address, content: &SigningContent) -> Result<Signature, WalletError> {
+        let public_key = self
+            .get_public_key(address)
+            .ok_or(WalletError::MissingKeyError(*address))?;
+        let request = SignerRequest::Sign {
+            address: *address,
+            content: content.clone(),
+        };
+        match self.request(&request)? {
+            SignerResponse::Signature(signature) => {
+                public_key.verify_signature(&content.compute_hash(&public_key), &signature)?;
+                Ok(signature)
+            }
+            SignerResponse::Refused(reason) => Err(WalletError::RemoteSignerError(format!(
+                "signature refused: {}",
+                reason
+            ))),
+            response => Err(unexpected_response(response)),
+        }
+    }
+}
+
+/// Last slashable signatures of an address in a thread.
+/// Only the last signed period is kept: older periods are pruned and can't be signed anymore.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct ThreadSigningRecord {
+    /// period and hash of the last signed block header
+    header: Option<(u64, Hash)>,
+    /// period of the last signed endorsements, with their hashes by index
+    endorsements: Option<(u64, BTreeMap<u32, Hash>)>,
+}
+
+/// Last slashable signatures of the signer process, by address and thread
+type SigningRecord = PreHashMap<Address, BTreeMap<u8, ThreadSigningRecord>>;
+
+/// Signer process side of the protocol, signing with its own keys.
+///
+/// It computes the signed hashes itself from the contents sent by the node,
+/// and never signs two different block headers at the same slot with the same address,
+/// nor two different endorsements at the same slot and index.
+/// Messages are never signed, as all the keys of the signer process are staking keys.
+///
+/// The last slashable signatures are saved to a record file before each new signature is returned,
+/// so that they are remembered when the signer process restarts.
+/// Contents at periods too far after the current one are refused,
+/// as signing them would prevent signing at the current periods.
+pub struct SignerService {
+    /// keys of the signer process, by address
+    keys: PreHashMap<Address, KeyPair>,
+    /// secret shared with the node
+    secret: String,
+    /// path of the record file
+    record_path: PathBuf,
+    /// last slashable signatures
+    record: Mutex<SigningRecord>,
+    /// genesis timestamp of the network
+    genesis_timestamp: MassaTime,
+    /// time between the periods of a thread
+    t0: MassaTime,
+}
+
+impl SignerService {
+    /// Creates a service signing with `keys` for the nodes knowing `secret`,
+    /// recording its slashable signatures in the file at `record_path`.
+    /// The signatures already in the record file, if it exists, are loaded.
+    /// `genesis_timestamp` and `t0` are the ones of the network, giving the current period.
+    pub fn new(
+        keys: PreHashMap<Address, KeyPair>,
+        secret: String,
+        record_path: PathBuf,
+        genesis_timestamp: MassaTime,
+        t0: MassaTime,
+    ) -> Result<Self, WalletError> {
+        let record = if record_path.is_file() {
+            serde_json::from_slice(&std::fs::read(&record_path)?)?
+        } else {
+            SigningRecord::default()
+        };
+        Ok(SignerService {
+            keys,
+            secret,
+            record_path,
+            record: Mutex::new(record),
+            genesis_timestamp,
+            t0,
+        })
+    }
+
+    /// Addresses the service signs for
+    pub fn get_addresses(&self) -> Vec<Address> {
+        self.keys.keys().copied().collect()
+    }
+
+    /// Answers a request of an authenticated node
+    pub fn handle_request(&self, request: SignerRequest) -> SignerResponse {
+        match request {
+            SignerRequest::Authenticate { .. } => {
+                SignerResponse::Refused("already authenticated".to_string())
+            }
+            SignerRequest::GetPublicKeys => SignerResponse::PublicKeys(
+                self.keys.values().map(KeyPair::get_public_key).collect(),
+            ),
+            SignerRequest::Sign { address, content } => {
+                let keypair = match self.keys.get(&address) {
+                    Some(keypair) => keypair,
+                    None => return SignerResponse::Refused(format!("unknown address {}", address)),
+                };
+                let hash = content.compute_hash(&keypair.get_public_key());
+                if let Err(reason) = self.check_and_record(&address, &content, &hash) {
+                    return SignerResponse::Refused(reason);
+                }
+                match keypair.sign(&hash) {
+                    Ok(signature) => SignerResponse::Signature(signature),
+                    Err(err) => SignerResponse::Refused(err.to_string()),
+                }
+            }
+        }
+    }
+
+    /// Checks that signing `content`, whose hash is `hash`, on behalf of `address` can't get it slashed,
+    /// and records the signature if it is slashable. Returns the reason of the refusal otherwise.
+    fn check_and_record(
+        &self,
+        address: &Address,
+        content: &SigningContent,
+        hash: &Hash,
+    ) -> Result<(), String> {
+        match content {
+            SigningContent::BlockHeader(data) => {
+                let header = deserialize_content(
+                    &BlockHeaderDeserializer::new(THREAD_COUNT, ENDORSEMENT_COUNT),
+                    data,
+                )
+                .ok_or_else(|| "invalid block header".to_string())?;
+                self.check_period(header.slot)?;
+                let mut record = self.record.lock();
+                let thread_record = get_thread_record(&mut record, address, header.slot);
+                let signed_header = thread_record.header;
+                match signed_header {
+                    Some((period, signed_hash))
+                        if period > header.slot.period
+                            || (period == header.slot.period && signed_hash != *hash) =>
+                    {
+                        Err(format!(
+                            "a block header was already signed at period {} of thread {}",
+                            period, header.slot.thread
+                        ))
+                    }
+                    Some((period, _)) if period == header.slot.period => Ok(()),
+                    _ => {
+                        thread_record.header = Some((header.slot.period, *hash));
+                        self.save_record(&record)
+                    }
+                }
+            }
+            SigningContent::Endorsement(data) => {
+                let endorsement = deserialize_content(
+                    &EndorsementDeserializer::new(THREAD_COUNT, ENDORSEMENT_COUNT),
+                    data,
+                )
+                .ok_or_else(|| "invalid endorsement".to_string())?;
+                let slot = endorsement.slot;
+                self.check_period(slot)?;
+                let mut record = self.record.lock();
+                let thread_record = get_thread_record(&mut record, address, slot);
+                match &mut thread_record.endorsements {
+                    Some((period, _)) if *period > slot.period => Err(format!(
+                        "endorsements were already signed at period {} of thread {}",
+                        period, slot.thread
+                    )),
+                    Some((period, signed)) if *period == slot.period => {
+                        match signed.get(&endorsement.index) {
+                            Some(signed_hash) if signed_hash != hash => Err(format!(
+                                "an endorsement was already signed at slot {} index {}",
+                                slot, endorsement.index
+                            )),
+                            Some(_) => Ok(()),
+                            None => {
+                                signed.insert(endorsement.index, *hash);
+                                self.save_record(&record)
+                            }
+                        }
+                    }
+                    _ => {
+                        thread_record.endorsements =
+                            Some((slot.period, BTreeMap::from([(endorsement.index, *hash)])));
+                        self.save_record(&record)
+                    }
+                }
+            }
+            SigningContent::Message(_) => {
+                Err("messages are not signed with staking keys".to_string())
+            }
+        }
+    }
+
+    /// Checks that `slot` is not too far after the current slot
+    fn check_period(&self, slot: Slot) -> Result<(), String> {
+        let now = MassaTime::now(0).map_err(|err| err.to_string())?;
+        let current_period =
+            get_latest_block_slot_at_timestamp(THREAD_COUNT, self.t0, self.genesis_timestamp, now)
+                .map_err(|err| err.to_string())?
+                .map_or(0, |current_slot| current_slot.period);
+        if slot.period > current_period.saturating_add(MAX_SIGNING_PERIODS_AHEAD) {
+            return Err(format!(
+                "period {} is too far after the current period {}",
+                slot.period, current_period
+            ));
+        }
+        Ok(())
+    }
+
+    /// Writes `record` to the record file, replacing it atomically
+    fn save_record(&self, record: &SigningRecord) -> Result<(), String> {
+        let tmp_path = self.record_path.with_extension("tmp");
+        let write = |path: &Path| -> Result<(), WalletError> {
+            std::fs::write(path, serde_json::to_vec(record)?)?;
+            std::fs::rename(path, &self.record_path)?;
+            Ok(())
+        };
+        write(&tmp_path).map_err(|err| format!("could not save the signing record: {}", err))
+    }
+
+    /// Authenticates the node connected on `stream`, then answers its requests until it closes the connection
+    pub fn serve<S: Read + Write>(&self, stream: S) -> Result<(), WalletError> {
+        let mut stream = BufReader::new(stream);
+        let challenge: [u8; 32] = rand::random();
+        write_message(stream.get_mut(), &SignerResponse::Challenge(challenge))?;
+        match read_message(&mut stream)? {
+            Some(SignerRequest::Authenticate { proof })
+                if proof == compute_proof(&self.secret, &challenge) =>
+            {
+                write_message(stream.get_mut(), &SignerResponse::Authenticated)?
+            }
+            Some(_) => {
+                write_message(
+                    stream.get_mut(),
+                    &SignerResponse::Refused("authentication failed".to_string()),
+                )?;
+                return Err(WalletError::RemoteSignerError(
+                    "a client failed to authenticate".to_string(),
+                ));
+            }
+            None => return Ok(()),
+        }
+        while let Some(request) = read_message(&mut stream)? {
+            write_message(stream.get_mut(), &self.handle_request(request))?;
+        }
+        Ok(())
+    }
+
+    /// Listens on `endpoint` and answers the requests of each connection in its own thread
+    pub fn run(self: Arc<Self>, endpoint: &SignerEndpoint) -> Result<(), WalletError> {
+        match endpoint {
+            SignerEndpoint::Tcp(addr) => {
+                for stream in TcpListener::bind(addr)?.incoming() {
+                    let service = self.clone();
+                    let stream = stream?;
+                    std::thread::spawn(move || service.serve(stream));
+                }
+            }
+            #[cfg(unix)]
+            SignerEndpoint::Unix(path) => {
+                for stream in UnixListener::bind(path)?.incoming() {
+                    let service = self.clone();
+                    let stream = stream?;
+                    std::thread::spawn(move || service.serve(stream));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Gets the record of the signatures of `address` in the thread of `slot`
+fn get_thread_record<'a>(
+    record: &'a mut SigningRecord,
+    address: &Address,
+    slot: Slot,
+) -> &'a mut ThreadSigningRecord {
+    record
+        .entry(*address)
+        .or_default()
+        .entry(slot.thread)
+        .or_default()
+}
+
+/// Deserializes `data` entirely with `deserializer`
+fn deserialize_content<T, D: Deserializer<T>>(deserializer: &D, data: &[u8]) -> Option<T> {
+    match deserializer.deserialize::<DeserializeError>(data) {
+        Ok((rest, content)) if rest.is_empty() => Some(content),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use massa_models::{
+        block::{BlockHeader, BlockHeaderSerializer, BlockId},
+        endorsement::{Endorsement, EndorsementSerializer},
+        wrapped::Id,
+    };
+    use massa_serialization::Serializer;
+
+    /// Secret shared by the node and the signer process in the tests
+    const SECRET: &str = "secret";
+    /// Time between the periods of a thread in the tests
+    const T0: MassaTime = MassaTime::from_millis(16000);
+
+    /// Starts a signer process stand-in listening on a local TCP port,
+    /// on a network whose current period is 2
+    fn start_signer_service(keypair: &KeyPair, record_path: &Path) -> SocketAddr {
+        let mut keys = PreHashMap::default();
+        keys.insert(
+            Address::from_public_key(&keypair.get_public_key()),
+            keypair.clone(),
+        );
+        let genesis_timestamp = MassaTime::now(0)
+            .unwrap()
+            .saturating_sub(T0.saturating_mul(2));
+        let service = Arc::new(
+            SignerService::new(
+                keys,
+                SECRET.to_string(),
+                record_path.to_path_buf(),
+                genesis_timestamp,
+                T0,
+            )
+            .unwrap(),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let service = service.clone();
+                std::thread::spawn(move || service.serve(stream));
+            }
+        });
+        addr
+    }
+
+    fn connect(addr: SocketAddr, secret: &str) -> Result<RemoteSigner, WalletError> {
+        RemoteSigner::connect(
+            SignerEndpoint::from_str(&addr.to_string()).unwrap(),
+            secret.to_string(),
+            Duration::from_secs(5),
+        )
+    }
+
+    /// Serialized header of a block at `slot` with the operations hashed from `operations`
+    fn get_header(slot: Slot, operations: &[u8]) -> SigningContent {
+        let header = BlockHeader {
+            slot,
+            parents: (0..THREAD_COUNT)
+                .map(|i| BlockId::new(Hash::compute_from(&[i])))
+                .collect(),
+            operation_merkle_root: Hash::compute_from(operations),
+            endorsements: Vec::new(),
+        };
+        let mut content = Vec::new();
+        BlockHeaderSerializer::new()
+            .serialize(&header, &mut content)
+            .unwrap();
+        SigningContent::BlockHeader(content)
+    }
+
+    /// Serialized endorsement of `endorsed_block` at `slot` and `index`
+    fn get_endorsement(slot: Slot, index: u32, endorsed_block: &[u8]) -> SigningContent {
+        let endorsement = Endorsement {
+            slot,
+            index,
+            endorsed_block: BlockId::new(Hash::compute_from(endorsed_block)),
+        };
+        let mut content = Vec::new();
+        EndorsementSerializer::new()
+            .serialize(&endorsement, &mut content)
+            .unwrap();
+        SigningContent::Endorsement(content)
+    }
+
+    #[test]
+    fn test_remote_signer() {
+        let keypair = KeyPair::generate();
+        let public_key = keypair.get_public_key();
+        let address = Address::from_public_key(&public_key);
+        let record_dir = tempfile::tempdir().unwrap();
+        let record_path = record_dir.path().join("signing_record.json");
+        let addr = start_signer_service(&keypair, &record_path);
+        // nodes that don't know the secret are not answered
+        assert!(connect(addr, "wrong secret").is_err());
+        let signer = connect(addr, SECRET).unwrap();
+        assert_eq!(signer.get_public_key(&address), Some(public_key));
+        assert_eq!(signer.get_addresses(), vec![address]);
+        let other_address = Address::from_public_key(&KeyPair::generate().get_public_key());
+        assert_eq!(signer.get_public_key(&other_address), None);
+
+        // the signature is the one of the wrapped header
+        let slot = Slot::new(1, 0);
+        let header = get_header(slot, b"operations");
+        let signature = signer.sign(&address, &header).unwrap();
+        public_key
+            .verify_signature(&header.compute_hash(&public_key), &signature)
+            .unwrap();
+
+        // signing the same header again is fine
+        signer.sign(&address, &header).unwrap();
+        // signing another header at the same slot is refused
+        assert!(signer
+            .sign(&address, &get_header(slot, b"other operations"))
+            .is_err());
+        // but not at a later slot, after which the previous slots are refused
+        signer
+            .sign(&address, &get_header(Slot::new(2, 0), b"operations"))
+            .unwrap();
+        assert!(signer.sign(&address, &header).is_err());
+        // nor in another thread
+        signer
+            .sign(&address, &get_header(Slot::new(1, 1), b"operations"))
+            .unwrap();
+
+        // endorsements at the same slot are refused only for the same index
+        signer
+            .sign(&address, &get_endorsement(slot, 0, b"block"))
+            .unwrap();
+        signer
+            .sign(&address, &get_endorsement(slot, 0, b"block"))
+            .unwrap();
+        signer
+            .sign(&address, &get_endorsement(slot, 1, b"other block"))
+            .unwrap();
+        assert!(signer
+            .sign(&address, &get_endorsement(slot, 0, b"other block"))
+            .is_err());
+
+        // messages are refused, so that a header can't be signed as a message
+        let header_data = match get_header(slot, b"other operations") {
+            SigningContent::BlockHeader(data) => data,
+            _ => unreachable!(),
+        };
+        assert!(signer
+            .sign(&address, &SigningContent::Message(header_data))
+            .is_err());
+
+        // contents that can't be checked are refused
+        assert!(signer
+            .sign(&address, &SigningContent::BlockHeader(vec![1, 2, 3]))
+            .is_err());
+
+        // unknown addresses can't sign
+        assert!(signer.sign(&other_address, &header).is_err());
+
+        // contents too far after the current period are refused, and don't prevent signing later
+        assert!(signer
+            .sign(&address, &get_header(Slot::new(u64::MAX, 0), b"operations"))
+            .is_err());
+        assert!(signer
+            .sign(&address, &get_endorsement(Slot::new(5, 0), 0, b"block"))
+            .is_err());
+        signer
+            .sign(&address, &get_endorsement(Slot::new(4, 0), 0, b"block"))
+            .unwrap();
+
+        // the signatures are remembered by a new signer process
+        let signer = connect(start_signer_service(&keypair, &record_path), SECRET).unwrap();
+        assert!(signer
+            .sign(&address, &get_header(Slot::new(2, 0), b"other operations"))
+            .is_err());
+        assert!(signer
+            .sign(&address, &get_endorsement(slot, 1, b"block"))
+            .is_err());
+        signer
+            .sign(&address, &get_header(Slot::new(3, 0), b"operations"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_signer_endpoint() {
+        let endpoint = SignerEndpoint::from_str("127.0.0.1:33040").unwrap();
+        assert_eq!(
+            endpoint,
+            SignerEndpoint::Tcp("127.0.0.1:33040".parse().unwrap())
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            SignerEndpoint::from_str("unix:/run/massa/signer.sock")
+                .unwrap()
+                .to_string(),
+            "unix:/run/massa/signer.sock"
+        );
+        assert!(SignerEndpoint::from_str("localhost").is_err());
+    }
+}
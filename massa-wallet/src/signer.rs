@@ -0,0 +1,89 @@
+// Copyright (c) 2022 MASSA LABS <info@massa.net>
+
+//! Signers sign block headers, endorsements and messages on behalf of the staking addresses of the node,
+//! either with the keys of the node wallet or through a remote signer process.
+
+use crate::{Wallet, WalletError};
+use massa_hash::Hash;
+use massa_models::address::Address;
+use massa_signature::{PublicKey, Signature};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Content to sign.
+/// Signers get the content itself rather than its hash,
+/// so that they can check what they sign to refuse signatures that would get their owner slashed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SigningContent {
+    /// serialized header of a block
+    BlockHeader(Vec<u8>),
+    /// serialized endorsement
+    Endorsement(Vec<u8>),
+    /// arbitrary message
+    Message(Vec<u8>),
+}
+
+impl SigningContent {
+    /// Computes the hash signed on behalf of `public_key`:
+    /// for block headers and endorsements, the hash of the public key and of the serialized content,
+    /// like `WrappedContent::new_wrapped`, and for messages the hash of the message.
+    pub fn compute_hash(&self, public_key: &PublicKey) -> Hash {
+        match self {
+            SigningContent::BlockHeader(content) | SigningContent::Endorsement(content) => {
+                let mut hash_data = Vec::new();
+                hash_data.extend(public_key.to_bytes());
+                hash_data.extend(content);
+                Hash::compute_from(&hash_data)
+            }
+            SigningContent::Message(message) => Hash::compute_from(message),
+        }
+    }
+}
+
+/// Signs contents on behalf of addresses
+pub trait Signer: Send + Sync {
+    /// Gets the public key of `address`, if the signer can sign on its behalf
+    fn get_public_key(&self, address: &Address) -> Option<PublicKey>;
+
+    /// Signs the hash of `content` on behalf of `address` (see `SigningContent::compute_hash`)
+    ///
+    /// # Arguments
+    /// * `address`: address signing
+    /// * `content`: content to sign
+    fn sign(&self, address: &Address, content: &SigningContent) -> Result<Signature, WalletError>;
+}
+
+impl Signer for Wallet {
+    fn get_public_key(&self, address: &Address) -> Option<PublicKey> {
+        self.find_associated_public_key(address)
+    }
+
+    fn sign(&self, address: &Address, content: &SigningContent) -> Result<Signature, WalletError> {
+        let keypair = self
+            .find_associated_keypair(address)
+            .ok_or(WalletError::MissingKeyError(*address))?;
+        Ok(keypair.sign(&content.compute_hash(&keypair.get_public_key()))?)
+    }
+}
+
+/// Signer using the keys of a wallet shared with the rest of the node
+#[derive(Clone)]
+pub struct LocalSigner(Arc<RwLock<Wallet>>);
+
+impl LocalSigner {
+    /// Creates a signer using the keys of `wallet`
+    pub fn new(wallet: Arc<RwLock<Wallet>>) -> Self {
+        LocalSigner(wallet)
+    }
+}
+
+impl Signer for LocalSigner {
+    fn get_public_key(&self, address: &Address) -> Option<PublicKey> {
+        self.0.read().get_public_key(address)
+    }
+
+    fn sign(&self, address: &Address, content: &SigningContent) -> Result<Signature, WalletError> {
+        self.0.read().sign(address, content)
+    }
+}